@@ -0,0 +1,110 @@
+//! Compares loading a shell of many surfaces files with `load_shell_dir`, then taking the entries
+//! and blocks of each file, with `load_shell_dir_in` a `ParseArena`.
+//!
+//! Allocations are counted by the global allocator, and peaks are of bytes allocated at once.
+//!
+//! Run with `cargo bench --bench arena`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use shell_parser_common_rs::{
+    arena::ParseArena,
+    options::ParseOptions,
+    project::{load_shell_dir, load_shell_dir_in},
+};
+
+mod common;
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// `descript.txt` and 20 surfaces files of 500 surfaces each, about 4 MB.
+fn fixture(dir: &Path) {
+    std::fs::write(
+        dir.join("descript.txt"),
+        "charset,UTF-8\r\nname,master\r\nsakura.name,Emily\r\nkero.name,Teddy\r\n",
+    )
+    .unwrap();
+    for n in 0..20 {
+        let mut text = String::from("charset,UTF-8\r\n\r\n");
+        for id in n * 500..(n + 1) * 500 {
+            text.push_str(&format!(
+                "surface{id}\r\n{{\r\n// surface {id}\r\nelement0,base,body{}.png,0,0\r\nelement1,overlay,face{}.png,0,0\r\ncollision0,10,10,50,50,Head\r\nanimation0.interval,sometimes\r\nanimation0.pattern0,overlay,100,50,0,0\r\nanimation0.pattern1,overlay,-1,50,0,0\r\n}}\r\n\r\n",
+                id % 10,
+                id % 100
+            ));
+        }
+        let name = match n {
+            0 => String::from("surfaces.txt"),
+            n => format!("surfaces{}.txt", n + 1),
+        };
+        std::fs::write(dir.join(name), text).unwrap();
+    }
+}
+
+/// Prints allocations and the peak of one run of `f`, then measures its time as `common::measure` does.
+fn measure(name: &str, iterations: u32, f: impl Fn() -> usize) {
+    f();
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    println!(
+        "{name}: {} allocations, peak {} bytes",
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        PEAK.load(Ordering::Relaxed) - before
+    );
+
+    common::measure(name, iterations, f);
+}
+
+fn main() {
+    let dir = tempfile::tempdir().unwrap();
+    fixture(dir.path());
+
+    measure("load_shell_dir", 10, || {
+        let project = load_shell_dir(black_box(dir.path()), &ParseOptions::STRICT).unwrap();
+        let lines: usize = project
+            .surfaces()
+            .iter()
+            .map(|f| f.entries().len() + f.blocks().iter().map(|b| b.body.len()).sum::<usize>())
+            .sum();
+        lines + project.descript().entries().len()
+    });
+    measure("load_shell_dir_in", 10, || {
+        let arena = ParseArena::new();
+        let project =
+            load_shell_dir_in(black_box(dir.path()), &ParseOptions::STRICT, &arena).unwrap();
+        let lines: usize = project
+            .surfaces()
+            .iter()
+            .map(|f| f.entries().len() + f.blocks().iter().map(|b| b.body.len()).sum::<usize>())
+            .sum();
+        lines + project.descript().entries().len()
+    });
+}
@@ -0,0 +1,56 @@
+//! Compares the block tokenizers with collecting lines of each block, as it was before spans.
+//!
+//! Run with `cargo bench --bench blocks`.
+
+use std::{hint::black_box, time::Duration};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use shell_parser_common_rs::block::{parse_block_spans, parse_blocks};
+
+#[path = "../tests/common/reference.rs"]
+mod reference;
+
+/// About 4 MB of `surfaces.txt`.
+fn fixture() -> String {
+    let mut text = String::from("charset,UTF-8\r\n\r\n");
+    let mut id = 0;
+    while text.len() < 4 << 20 {
+        text.push_str(&format!(
+            "surface{id}\r\n{{\r\n// body\r\nelement0,base,surface{id}.png,0,0\r\ncollision0,10,10,50,50,Head\r\nanimation0.interval,sometimes\r\nanimation0.pattern0,overlay,100,50,0,0\r\n\r\n}}\r\n\r\n"
+        ));
+        id += 1;
+    }
+    text
+}
+
+fn blocks(c: &mut Criterion) {
+    let text = fixture();
+    let mut group = c.benchmark_group("blocks");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_with_input(BenchmarkId::new("collecting", "4MB"), &text, |b, text| {
+        b.iter(|| reference::collecting(black_box(text)).unwrap().len())
+    });
+    group.bench_with_input(BenchmarkId::new("parse_blocks", "4MB"), &text, |b, text| {
+        b.iter(|| parse_blocks(black_box(text)).unwrap().len())
+    });
+    group.bench_with_input(
+        BenchmarkId::new("parse_block_spans", "4MB"),
+        &text,
+        |b, text| b.iter(|| parse_block_spans(black_box(text)).unwrap().len()),
+    );
+    group.finish();
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = tokenizers;
+    config = config();
+    targets = blocks
+}
+criterion_main!(tokenizers);
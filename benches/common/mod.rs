@@ -0,0 +1,13 @@
+//! Helpers shared by the benches printing their own measurements.
+
+use std::{hint::black_box, time::Instant};
+
+/// Runs `f` once to warm up, then `iterations` times, printing the time of one iteration.
+pub fn measure(name: &str, iterations: u32, f: impl Fn() -> usize) {
+    f();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        black_box(f());
+    }
+    println!("{name}: {:?} per iteration", start.elapsed() / iterations);
+}
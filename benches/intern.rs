@@ -0,0 +1,74 @@
+//! Compares bytes of strings owned by each entry and element with those kept by an `Interner`.
+//!
+//! Run with `cargo bench --bench intern`.
+
+use std::hint::black_box;
+
+use shell_parser_common_rs::{
+    document::Document,
+    intern::Interner,
+    options::ParseOptions,
+    surfaces::{build_surfaces, intern_elements, parse_blocks_with},
+};
+
+mod common;
+
+use common::measure;
+
+/// 5000 surfaces drawing shared parts, about 1 MB of `surfaces.txt`.
+fn fixture() -> String {
+    let mut text = String::from("charset,UTF-8\r\n\r\n");
+    for id in 0..5000 {
+        text.push_str(&format!(
+            "surface{id}\r\n{{\r\nelement0,base,body{}.png,0,0\r\nelement1,overlay,face{}.png,0,0\r\nanimation0.interval,sometimes\r\nanimation0.pattern0,overlay,100,50,0,0\r\nanimation0.pattern1,overlay,-1,50,0,0\r\n}}\r\n\r\n",
+            id % 10,
+            id % 100
+        ));
+    }
+    text
+}
+
+fn main() {
+    let text = fixture();
+    println!("fixture: {} bytes", text.len());
+
+    let document = Document::parse(&text);
+    let owned: usize = document
+        .nodes()
+        .iter()
+        .filter_map(|n| n.key_value())
+        .map(|kv| kv.key.len() + kv.value.split(',').map(str::len).sum::<usize>())
+        .sum();
+    let mut interner = Interner::new();
+    document.intern_entries(&mut interner);
+    println!(
+        "entries: {owned} bytes owned, {} bytes in {} interned strings",
+        interner.stored_bytes(),
+        interner.len()
+    );
+
+    let blocks = parse_blocks_with(&text, &ParseOptions::COMPATIBLE).unwrap();
+    let (surfaces, _) = build_surfaces(&blocks.blocks);
+    let owned: usize = surfaces
+        .values()
+        .flat_map(|s| s.elements.iter())
+        .map(|e| e.filename.len())
+        .sum();
+    let mut interner = Interner::new();
+    intern_elements(&surfaces, &mut interner);
+    println!(
+        "filenames: {owned} bytes owned, {} bytes in {} interned strings",
+        interner.stored_bytes(),
+        interner.len()
+    );
+
+    measure("Document::parse", 10, || {
+        Document::parse(black_box(&text)).nodes().len()
+    });
+    measure("Document::intern_entries", 10, || {
+        document.intern_entries(&mut Interner::new()).len()
+    });
+    measure("intern_elements", 10, || {
+        intern_elements(black_box(&surfaces), &mut Interner::new()).len()
+    });
+}
@@ -0,0 +1,81 @@
+//! Compares the line iterator with per-character scanning, as it was before `memchr`.
+//!
+//! Run with `cargo bench --bench lines`.
+
+use std::{hint::black_box, time::Duration};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use shell_parser_common_rs::{charset::detect_charset_within, line::lines};
+
+/// About 4 MB of `surfaces.txt` with long lines.
+fn fixture() -> String {
+    let mut text = String::new();
+    let mut id = 0;
+    while text.len() < 4 << 20 {
+        text.push_str(&format!(
+            "surface{id}\r\n{{\r\nelement0,base,surface{id}.png,0,0\r\nanimation0.pattern0,overlay,100,50,0,0,{}\r\n}}\r\n",
+            "x".repeat(200)
+        ));
+        id += 1;
+    }
+    text
+}
+
+/// Line count by per-character scanning.
+fn per_char(input: &str) -> usize {
+    let mut count = 0;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let i = rest.find(['\r', '\n']).unwrap_or(rest.len());
+        let len = if rest[i..].starts_with("\r\n") { 2 } else { 1 };
+        rest = rest.get(i + len..).unwrap_or_default();
+        count += 1;
+    }
+    count
+}
+
+fn per_char_directive(input: &[u8]) -> bool {
+    input
+        .split(|b| *b == b'\n')
+        .any(|l| l.starts_with(b"charset,"))
+}
+
+fn line(c: &mut Criterion) {
+    let text = fixture();
+    let mut group = c.benchmark_group("lines");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_with_input(BenchmarkId::new("per_char", "4MB"), &text, |b, text| {
+        b.iter(|| per_char(black_box(text)))
+    });
+    group.bench_with_input(BenchmarkId::new("memchr", "4MB"), &text, |b, text| {
+        b.iter(|| lines(black_box(text)).count())
+    });
+    group.finish();
+}
+
+fn directive(c: &mut Criterion) {
+    let text = fixture();
+    let mut group = c.benchmark_group("directive");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_with_input(BenchmarkId::new("per_char", "4MB"), &text, |b, text| {
+        b.iter(|| per_char_directive(black_box(text.as_bytes())))
+    });
+    group.bench_with_input(BenchmarkId::new("memchr", "4MB"), &text, |b, text| {
+        b.iter(|| detect_charset_within(black_box(text.as_bytes()), usize::MAX).is_some())
+    });
+    group.finish();
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = scanners;
+    config = config();
+    targets = line, directive
+}
+criterion_main!(scanners);
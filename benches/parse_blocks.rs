@@ -0,0 +1,44 @@
+//! Compares parsing bodies of surface blocks sequentially and in parallel.
+//!
+//! Run with `cargo bench --bench parse_blocks --features rayon`.
+
+use std::hint::black_box;
+
+use shell_parser_common_rs::{
+    options::ParseOptions,
+    surfaces::{parse_blocks_parallel, parse_blocks_with},
+};
+
+mod common;
+
+use common::measure;
+
+/// `surfaces.txt` of 5000 surfaces.
+fn fixture() -> String {
+    let mut text = String::from("charset,UTF-8\r\n\r\n");
+    for id in 0..5000 {
+        text.push_str(&format!(
+            "surface{id}\r\n{{\r\n// 表情{id}\r\nelement0,base,surface{id}.png,0,0\r\nelement1,overlay,eye{id}.png,10,20\r\ncollision0,10,10,50,50,Head\r\ncollisionex1,Bust,polygon,10,10,50,10,50,50,10,50\r\nanimation0.interval,sometimes\r\nanimation0.pattern0,overlay,100,50,0,0\r\nanimation0.pattern1,overlay,101,50,0,0\r\nanimation0.pattern2,overlay,-1,50,0,0\r\npoint.centerx,60\r\nsakura.balloon.offsetx,20\r\ntooltip.Head,head\r\n}}\r\n\r\n"
+        ));
+    }
+    text
+}
+
+fn main() {
+    let text = fixture();
+    let options = ParseOptions::COMPATIBLE;
+    println!("fixture: {} bytes", text.len());
+
+    measure("sequential", 20, || {
+        parse_blocks_with(black_box(&text), &options)
+            .unwrap()
+            .blocks
+            .len()
+    });
+    measure("parallel", 20, || {
+        parse_blocks_parallel(black_box(&text), &options)
+            .unwrap()
+            .blocks
+            .len()
+    });
+}
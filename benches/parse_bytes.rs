@@ -0,0 +1,60 @@
+//! Compares `parse_bytes` with decoding through a lossy UTF-8 pre-pass.
+//!
+//! Run with `cargo bench --bench parse_bytes`.
+
+use std::hint::black_box;
+
+use shell_parser_common_rs::{
+    charset::{parse_charset, Charset},
+    document::parse_bytes,
+    kv::parse_key_value,
+};
+
+mod common;
+
+use common::measure;
+
+/// About 1 MB of `surfaces.txt` in Shift_JIS.
+fn fixture() -> Vec<u8> {
+    let mut text = String::from("charset,Shift_JIS\r\n\r\n");
+    let mut id = 0;
+    while text.len() < 1 << 20 {
+        text.push_str(&format!(
+            "surface{id}\r\n{{\r\n// 表情{id}\r\nelement0,base,surface{id}.png,0,0\r\nelement1,overlay,目{id}.png,10,20\r\ncollision0,10,10,50,50,Head\r\nanimation0.interval,sometimes\r\nanimation0.pattern0,overlay,100,50,0,0\r\n}}\r\n\r\n"
+        ));
+        id += 1;
+    }
+    encoding_rs::SHIFT_JIS.encode(&text).0.into_owned()
+}
+
+/// Lossy pre-pass to find the charset, decoding, then copying entries out.
+fn naive(bytes: &[u8]) -> usize {
+    let lossy = String::from_utf8_lossy(bytes).into_owned();
+    let charset = lossy
+        .lines()
+        .find_map(|l| l.strip_prefix("charset,"))
+        .and_then(|v| parse_charset(v.trim()).ok().map(|(_, c)| c))
+        .unwrap_or(Charset::ShiftJIS);
+    let text = charset.decode(bytes).unwrap().into_owned();
+    let entries: Vec<(String, String)> = text
+        .lines()
+        .filter_map(|l| parse_key_value(l).ok())
+        .map(|(_, kv)| (kv.key.to_string(), kv.value.to_string()))
+        .collect();
+    entries.len()
+}
+
+fn pipeline(bytes: &[u8]) -> usize {
+    parse_bytes(bytes, Charset::ShiftJIS)
+        .unwrap()
+        .entries()
+        .count()
+}
+
+fn main() {
+    let bytes = fixture();
+    println!("fixture: {} bytes", bytes.len());
+
+    measure("naive three copies", 20, || naive(black_box(&bytes)));
+    measure("parse_bytes", 20, || pipeline(black_box(&bytes)));
+}
@@ -0,0 +1,160 @@
+//! Throughput of each stage of parsing on files of `fixture::FixtureGenerator` of 1k, 10k and 100k lines:
+//! charset detection and decoding, line iteration, entries, blocks and tokens, and the whole `ShellFile::parse`.
+//!
+//! Run with `cargo bench --features test-util --bench throughput`, and a stage alone by its group,
+//! such as `cargo bench --features test-util --bench throughput -- kv`.
+//! Save a baseline with `-- --save-baseline main` and compare with `-- --baseline main`:
+//! changes within `NOISE_THRESHOLD` are noise, and those beyond it at `SIGNIFICANCE_LEVEL` are reported as regressions.
+
+use std::{hint::black_box, time::Duration};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use nom::combinator::all_consuming;
+use shell_parser_common_rs::{
+    block::parse_block_spans,
+    charset::{detect_charset, Charset, DEFAULT_SCAN_LIMIT},
+    document::{tokenize, ShellFile},
+    fixture::{Fixture, FixtureGenerator, Variant},
+    kv::parse_key_value,
+    line::{is_comment, lines},
+};
+
+const SIZES: &[usize] = &[1_000, 10_000, 100_000];
+
+/// Relative change of the time ignored as noise.
+const NOISE_THRESHOLD: f64 = 0.03;
+
+/// Significance level of a change beyond the noise.
+const SIGNIFICANCE_LEVEL: f64 = 0.01;
+
+/// `surfaces.txt` of each size, from the same seed on every run.
+fn fixtures() -> Vec<(usize, Fixture)> {
+    SIZES
+        .iter()
+        .map(|&lines| {
+            let fixture = FixtureGenerator::new(0x5eed).surfaces(lines, Variant::Clean);
+            (lines, fixture)
+        })
+        .collect()
+}
+
+/// Same as `fixtures`, in Shift_JIS.
+fn encoded(fixtures: &[(usize, Fixture)]) -> Vec<(usize, Vec<u8>)> {
+    fixtures
+        .iter()
+        .map(|(lines, f)| {
+            let text = f.text.replacen("charset,UTF-8", "charset,Shift_JIS", 1);
+            (*lines, encoding_rs::SHIFT_JIS.encode(&text).0.into_owned())
+        })
+        .collect()
+}
+
+fn charset(c: &mut Criterion) {
+    let fixtures = encoded(&fixtures());
+    let mut group = c.benchmark_group("charset");
+    for (lines, bytes) in fixtures.iter() {
+        // Only a prefix is scanned for the `charset` line.
+        let scanned = bytes.len().min(DEFAULT_SCAN_LIMIT);
+        group.throughput(Throughput::Bytes(scanned as u64));
+        group.bench_with_input(BenchmarkId::new("detect", lines), bytes, |b, bytes| {
+            b.iter(|| detect_charset(black_box(bytes)))
+        });
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("decode", lines), bytes, |b, bytes| {
+            b.iter(|| Charset::ShiftJIS.decode(black_box(bytes)).unwrap().len())
+        });
+    }
+    group.finish();
+}
+
+fn line(c: &mut Criterion) {
+    let fixtures = fixtures();
+    let mut group = c.benchmark_group("lines");
+    for (lines_count, fixture) in fixtures.iter() {
+        group.throughput(Throughput::Bytes(fixture.text.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("lines", lines_count),
+            &fixture.text,
+            |b, text| b.iter(|| lines(black_box(text)).count()),
+        );
+    }
+    group.finish();
+}
+
+fn kv(c: &mut Criterion) {
+    let fixtures = fixtures();
+    let mut group = c.benchmark_group("kv");
+    for (lines_count, fixture) in fixtures.iter() {
+        // Entries only, as a caller splitting lines itself gives them.
+        let entries: Vec<&str> = lines(&fixture.text)
+            .map(|l| l.text)
+            .filter(|l| l.contains(',') && !is_comment(l))
+            .collect();
+        group.throughput(Throughput::Elements(entries.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("parse_key_value", lines_count),
+            &entries,
+            |b, entries| {
+                b.iter(|| {
+                    entries
+                        .iter()
+                        .filter(|l| all_consuming(parse_key_value)(black_box(l)).is_ok())
+                        .count()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn blocks(c: &mut Criterion) {
+    let fixtures = fixtures();
+    let mut group = c.benchmark_group("blocks");
+    for (lines_count, fixture) in fixtures.iter() {
+        group.throughput(Throughput::Bytes(fixture.text.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("parse_block_spans", lines_count),
+            &fixture.text,
+            |b, text| b.iter(|| parse_block_spans(black_box(text)).unwrap().len()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("tokenize", lines_count),
+            &fixture.text,
+            |b, text| b.iter(|| tokenize(black_box(text)).len()),
+        );
+    }
+    group.finish();
+}
+
+fn shell_file(c: &mut Criterion) {
+    let fixtures = encoded(&fixtures());
+    let mut group = c.benchmark_group("shell_file");
+    for (lines, bytes) in fixtures.iter() {
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("parse", lines), bytes, |b, bytes| {
+            b.iter(|| {
+                ShellFile::parse(black_box(bytes), Charset::ShiftJIS)
+                    .unwrap()
+                    .text()
+                    .len()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn config() -> Criterion {
+    Criterion::default()
+        .noise_threshold(NOISE_THRESHOLD)
+        .significance_level(SIGNIFICANCE_LEVEL)
+        .sample_size(20)
+        .warm_up_time(Duration::from_millis(500))
+        .measurement_time(Duration::from_secs(2))
+}
+
+criterion_group! {
+    name = stages;
+    config = config();
+    targets = charset, line, kv, blocks, shell_file
+}
+criterion_main!(stages);
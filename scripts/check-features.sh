@@ -0,0 +1,38 @@
+#!/bin/sh
+# Builds the crate with its tests, benches and examples for each feature set of the matrix in README.md,
+# failing on warnings and lints.
+set -eu
+
+cd "$(dirname "$0")/.."
+
+for features in \
+    charset \
+    charset,std \
+    kv \
+    kv,document \
+    block \
+    seriko \
+    descript \
+    balloon \
+    surfaces \
+    document,surfaces \
+    std,kv,document \
+    std,surfaces \
+    io \
+    diagnostics \
+    rayon \
+    charset,serde \
+    kv,serde \
+    seriko,serde \
+    test-util \
+    std,full,diagnostics \
+    full,serde,diagnostics,unicode
+do
+    echo "checking --no-default-features --features $features"
+    cargo clippy --quiet --all-targets --no-default-features --features "$features" -- -D warnings
+done
+
+echo "checking default features"
+cargo clippy --quiet --all-targets -- -D warnings
+echo "checking all features"
+cargo clippy --quiet --all-targets --all-features -- -D warnings
@@ -0,0 +1,218 @@
+//! # `arena`
+//!
+//! Opt-in bump allocation of parse results, such as the texts, entries and blocks of
+//! [`load_shell_dir_in`], freed all at once with the [`ParseArena`].
+//! Parsers without a [`ParseArena`] are not affected.
+//!
+//! [`load_shell_dir_in`]: crate::project::load_shell_dir_in
+//! [`ParseArena`]: crate::arena::ParseArena
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::arena::ParseArena;
+//! use shell_parser_common_rs::kv::KeyValue;
+//!
+//! let arena = ParseArena::new();
+//! let text = arena.alloc_str("name,master");
+//! let entries = arena.alloc_slice_clone(&[KeyValue::new("name", &text[5..])]);
+//!
+//! assert_eq!(entries[0].value, "master");
+//! assert!(arena.allocated_bytes() >= text.len());
+//! ```
+
+use alloc::{
+    alloc::{alloc, dealloc, handle_alloc_error, Layout},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt, ptr::NonNull};
+
+/// Bytes of the first chunk of [`ParseArena::new`].
+///
+/// [`ParseArena::new`]: crate::arena::ParseArena::new
+const FIRST_CHUNK: usize = 4096;
+
+/// Alignment of every chunk, enough for the parse results of this crate.
+const CHUNK_ALIGN: usize = 16;
+
+struct Chunk {
+    start: NonNull<u8>,
+    layout: Layout,
+    used: usize,
+}
+
+/// Bump allocator of values living as long as it, allocated in chunks and freed together when it is dropped.
+///
+/// Values are never dropped, so those owning memory elsewhere, such as `Vec`, leak it.
+/// Parse results borrowing their input, such as [`KeyValue`] and [`Line`], own nothing.
+///
+/// [`KeyValue`]: crate::kv::KeyValue
+/// [`Line`]: crate::line::Line
+#[derive(Default)]
+pub struct ParseArena {
+    chunks: RefCell<Vec<Chunk>>,
+}
+
+impl ParseArena {
+    pub fn new() -> ParseArena {
+        ParseArena::default()
+    }
+
+    /// Makes an arena with a first chunk of `bytes`, such as the size of the files to load.
+    pub fn with_capacity(bytes: usize) -> ParseArena {
+        let arena = ParseArena::new();
+        if bytes > 0 {
+            arena
+                .chunks
+                .borrow_mut()
+                .push(Chunk::new(bytes, CHUNK_ALIGN));
+        }
+        arena
+    }
+
+    /// Returns a copy of `text` in the arena.
+    pub fn alloc_str(&self, text: &str) -> &str {
+        let bytes = self.alloc_slice_clone(text.as_bytes());
+        // SAFETY: the bytes are copied from a `str`.
+        unsafe { core::str::from_utf8_unchecked(bytes) }
+    }
+
+    /// Returns clones of `items` in the arena, in order.
+    pub fn alloc_slice_clone<T: Clone>(&self, items: &[T]) -> &[T] {
+        if items.is_empty() {
+            return &[];
+        }
+        let layout = Layout::array::<T>(items.len()).unwrap_or_else(|_| capacity_overflow());
+        let start = self.alloc_layout(layout).cast::<T>();
+        for (i, item) in items.iter().enumerate() {
+            // SAFETY: `start` is aligned for `T` with room for `items.len()` values.
+            unsafe { start.as_ptr().add(i).write(item.clone()) };
+        }
+        // SAFETY: all `items.len()` values are written above, and the memory is not given out again
+        // until the arena, which the slice borrows, is dropped.
+        unsafe { core::slice::from_raw_parts(start.as_ptr(), items.len()) }
+    }
+
+    /// Returns bytes of the chunks allocated, used or not.
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks.borrow().iter().map(|c| c.layout.size()).sum()
+    }
+
+    /// Returns bytes given out, with padding for alignment.
+    pub fn used_bytes(&self) -> usize {
+        self.chunks.borrow().iter().map(|c| c.used).sum()
+    }
+
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        let mut chunks = self.chunks.borrow_mut();
+        if layout.size() == 0 {
+            // SAFETY: an alignment is never zero.
+            return unsafe {
+                NonNull::new_unchecked(core::ptr::without_provenance_mut(layout.align()))
+            };
+        }
+        if let Some(start) = chunks.last_mut().and_then(|c| c.bump(layout)) {
+            return start;
+        }
+
+        let last = chunks.last().map_or(FIRST_CHUNK / 2, |c| c.layout.size());
+        let size = last.saturating_mul(2).max(layout.size());
+        let mut chunk = Chunk::new(size, layout.align().max(CHUNK_ALIGN));
+        let start = chunk.bump(layout).unwrap_or_else(|| capacity_overflow());
+        chunks.push(chunk);
+        start
+    }
+}
+
+impl Chunk {
+    fn new(size: usize, align: usize) -> Chunk {
+        let layout = Layout::from_size_align(size, align).unwrap_or_else(|_| capacity_overflow());
+        // SAFETY: `size` is not zero, as callers give at least the size of a value which is not zero-sized.
+        let start = unsafe { alloc(layout) };
+        let Some(start) = NonNull::new(start) else {
+            handle_alloc_error(layout);
+        };
+        Chunk {
+            start,
+            layout,
+            used: 0,
+        }
+    }
+
+    /// Returns the start of `layout` after the used bytes, or `None` when the chunk has no room.
+    fn bump(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+        let address = self.start.as_ptr() as usize + self.used;
+        let padding = address.next_multiple_of(layout.align()) - address;
+        let offset = self.used.checked_add(padding)?;
+        let end = offset.checked_add(layout.size())?;
+        if end > self.layout.size() {
+            return None;
+        }
+        self.used = end;
+        // SAFETY: `offset` is within the chunk.
+        Some(unsafe { self.start.add(offset) })
+    }
+}
+
+impl Drop for ParseArena {
+    fn drop(&mut self) {
+        for chunk in self.chunks.get_mut().drain(..) {
+            // SAFETY: the chunk is allocated by `Chunk::new` with this layout, and nothing borrows the arena.
+            unsafe { dealloc(chunk.start.as_ptr(), chunk.layout) };
+        }
+    }
+}
+
+impl fmt::Debug for ParseArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseArena")
+            .field("chunks", &self.chunks.borrow().len())
+            .field("allocated_bytes", &self.allocated_bytes())
+            .field("used_bytes", &self.used_bytes())
+            .finish()
+    }
+}
+
+fn capacity_overflow() -> ! {
+    panic!("arena allocation too large")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{error::Located, kv::KeyValue};
+
+    mod alloc_slice_clone {
+        use super::*;
+
+        #[test]
+        fn success_when_values_span_chunks() {
+            let arena = ParseArena::with_capacity(64);
+            let text = arena.alloc_str("charset,UTF-8");
+            let mut slices = Vec::new();
+            for i in 0..200 {
+                let entry = Located::new(i, KeyValue::new(&text[..7], &text[8..]));
+                slices.push(arena.alloc_slice_clone(&[entry.clone(), entry]));
+            }
+
+            for (i, slice) in slices.iter().enumerate() {
+                assert_eq!(slice.len(), 2);
+                assert_eq!(slice[1], Located::new(i, KeyValue::new("charset", "UTF-8")));
+                let address = slice.as_ptr() as usize;
+                assert_eq!(address % core::mem::align_of::<Located<KeyValue>>(), 0);
+            }
+            assert!(arena.chunks.borrow().len() > 1);
+            assert!(arena.used_bytes() <= arena.allocated_bytes());
+        }
+
+        #[test]
+        fn success_when_empty_or_zero_sized() {
+            let arena = ParseArena::new();
+            assert!(arena.alloc_slice_clone::<u32>(&[]).is_empty());
+            assert_eq!(arena.alloc_slice_clone(&[(), ()]).len(), 2);
+            assert_eq!(arena.alloc_str(""), "");
+            assert_eq!(arena.allocated_bytes(), 0);
+        }
+    }
+}
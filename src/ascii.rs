@@ -0,0 +1,105 @@
+//! # `ascii`
+//!
+//! Comparison of keys and labels ignoring ASCII case only, without allocation.
+//! Other letters, such as `İ` and full-width `Ａ`, are compared as they are,
+//! so that Japanese text is never folded by the locale.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::ascii::{eq_ignore_case, starts_with_ignore_case};
+//!
+//! assert!(eq_ignore_case("Sakura.Balloon.OffsetX", "sakura.balloon.offsetx"));
+//! assert!(!eq_ignore_case("Ａ", "ａ"));
+//! assert!(starts_with_ignore_case("Element0", "element"));
+//! ```
+
+use core::{cmp::Ordering, hash::Hasher};
+
+/// Checks whether `a` is `b`, ignoring ASCII case.
+pub fn eq_ignore_case(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Checks whether `haystack` starts with `prefix`, ignoring ASCII case.
+pub fn starts_with_ignore_case(haystack: &str, prefix: &str) -> bool {
+    haystack
+        .as_bytes()
+        .get(..prefix.len())
+        .is_some_and(|head| head.eq_ignore_ascii_case(prefix.as_bytes()))
+}
+
+/// Compares `a` and `b` as if ASCII letters were lowercased, in the order consistent with [`eq_ignore_case`].
+///
+/// [`eq_ignore_case`]: crate::ascii::eq_ignore_case
+pub fn cmp_ignore_case(a: &str, b: &str) -> Ordering {
+    let a = a.bytes().map(|b| b.to_ascii_lowercase());
+    a.cmp(b.bytes().map(|b| b.to_ascii_lowercase()))
+}
+
+/// Feeds `text` with ASCII letters lowercased into `state`, consistent with [`eq_ignore_case`].
+///
+/// Bytes are lowercased through a buffer on the stack, as `str` is hashed, terminated by `0xff`.
+///
+/// [`eq_ignore_case`]: crate::ascii::eq_ignore_case
+pub fn hash_ignore_case<H: Hasher>(text: &str, state: &mut H) {
+    let mut buffer = [0u8; 64];
+    for chunk in text.as_bytes().chunks(buffer.len()) {
+        let lowered = &mut buffer[..chunk.len()];
+        lowered.copy_from_slice(chunk);
+        lowered.make_ascii_lowercase();
+        state.write(lowered);
+    }
+    state.write_u8(0xff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod starts_with_ignore_case {
+        use super::*;
+
+        #[test]
+        fn success_when_cases_differ() {
+            assert!(starts_with_ignore_case("CollisionEx0", "collisionex"));
+            assert!(starts_with_ignore_case("name", ""));
+            assert!(!starts_with_ignore_case("coll", "collision"));
+            // The prefix ends inside a multibyte character.
+            assert!(!starts_with_ignore_case("aあ", "ab"));
+        }
+    }
+
+    mod cmp_ignore_case {
+        use super::*;
+
+        #[test]
+        fn success_when_cases_differ() {
+            assert_eq!(cmp_ignore_case("Name", "name"), Ordering::Equal);
+            assert_eq!(cmp_ignore_case("Name", "name2"), Ordering::Less);
+            // `_` is between `Z` and `a`, so ASCII case decides the order as if lowercased.
+            assert_eq!(cmp_ignore_case("A", "_"), Ordering::Greater);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod hash_ignore_case {
+        use super::*;
+
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash(text: &str) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            hash_ignore_case(text, &mut hasher);
+            hasher.finish()
+        }
+
+        #[test]
+        fn success_when_cases_differ() {
+            let long = "Sakura.".repeat(20);
+            assert_eq!(hash(&long), hash(&long.to_ascii_lowercase()));
+            assert_eq!(hash("CHARSET"), hash("charset"));
+            assert_ne!(hash("Ａ"), hash("ａ"));
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! # `balloon`
+//!
+//! Settings in `descript.txt` of balloons.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::balloon::BalloonGeometry;
+//! use shell_parser_common_rs::value::{Coordinate, Rect};
+//!
+//! let mut geometry = BalloonGeometry::default();
+//! geometry.apply(&["origin", "x"], "20").unwrap();
+//! geometry.apply(&["origin", "y"], "10").unwrap();
+//! geometry.apply(&["validrect"], "10,10,200,120").unwrap();
+//!
+//! assert_eq!(geometry.origin(), Some(Coordinate::new(20, 10)));
+//! assert_eq!(geometry.validrect(), Some(Rect::new(10, 10, 200, 120)));
+//! assert!(geometry.validate().is_empty());
+//! ```
+
+mod font;
+mod geometry;
+mod marker;
+
+pub use font::{BalloonFonts, FontSettings};
+pub use geometry::{BalloonGeometry, GeometryIssue};
+pub use marker::{ArrowSettings, MarkerSettings, Placement};
@@ -0,0 +1,134 @@
+//! Fonts of the balloon, `font.*` and `number.font.*`.
+
+use alloc::vec::Vec;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    value::{parse_bool, parse_u32, ColorAccumulator},
+    ShellParseError,
+};
+
+/// Font settings, keys following `font.`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FontSettings {
+    /// `height`
+    pub height: Option<u32>,
+    /// `color.r`, `color.g` and `color.b`
+    pub color: ColorAccumulator,
+    /// `shadowcolor.r`, `shadowcolor.g` and `shadowcolor.b`
+    pub shadow_color: ColorAccumulator,
+    /// `bold`
+    pub bold: Option<bool>,
+}
+
+impl FontSettings {
+    /// Applies one entry, `key_segments` without the `font` prefix
+    /// (e.g. `["color", "r"]`).
+    ///
+    /// Returns `Ok(false)` when the key is not handled here.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match key_segments {
+            ["height"] => self.height = Some(all_consuming(parse_u32)(value)?.1),
+            ["bold"] => self.bold = Some(all_consuming(parse_bool)(value)?.1),
+            ["color", rest @ ..] => return self.color.apply(rest, value),
+            ["shadowcolor", rest @ ..] => return self.shadow_color.apply(rest, value),
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Returns names of colors given only partially, such as `"shadowcolor"`.
+    pub fn partial_colors(&self) -> Vec<&'static str> {
+        [("color", &self.color), ("shadowcolor", &self.shadow_color)]
+            .into_iter()
+            .filter(|(_, color)| color.is_partial())
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// Fonts of the balloon text (`font.*`) and numbers (`number.font.*`).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BalloonFonts {
+    /// `font.*`
+    pub text: FontSettings,
+    /// `number.font.*`
+    pub number: FontSettings,
+}
+
+impl BalloonFonts {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a font key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match key_segments {
+            ["font", rest @ ..] => self.text.apply(rest, value),
+            ["number", "font", rest @ ..] => self.number.apply(rest, value),
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::value::Rgb;
+
+    mod balloon_fonts {
+        use super::*;
+
+        #[test]
+        fn success_when_full_spec() {
+            let mut fonts = BalloonFonts::default();
+            for (key, value) in [
+                (&["font", "height"][..], "12"),
+                (&["font", "color", "r"], "10"),
+                (&["font", "color", "g"], "20"),
+                (&["font", "color", "b"], "30"),
+                (&["font", "shadowcolor"], "200,200,200"),
+                (&["number", "font", "height"], "10"),
+            ] {
+                assert!(fonts.apply(key, value).unwrap());
+            }
+
+            assert_eq!(fonts.text.height, Some(12));
+            assert_eq!(fonts.text.color.color(), Some(Rgb::new(10, 20, 30)));
+            assert_eq!(
+                fonts.text.shadow_color.color(),
+                Some(Rgb::new(200, 200, 200))
+            );
+            assert_eq!(fonts.number.height, Some(10));
+            assert!(fonts.text.partial_colors().is_empty());
+        }
+
+        #[test]
+        fn detected_when_partial_shadow_color() {
+            let mut fonts = BalloonFonts::default();
+            fonts.apply(&["font", "shadowcolor", "r"], "0").unwrap();
+            assert_eq!(fonts.text.shadow_color.color(), None);
+            assert_eq!(fonts.text.partial_colors(), vec!["shadowcolor"]);
+        }
+
+        #[test]
+        fn success_when_bold_flag() {
+            let mut fonts = BalloonFonts::default();
+            for (value, expected) in [("1", true), ("0", false), ("true", true), ("false", false)] {
+                fonts.apply(&["font", "bold"], value).unwrap();
+                assert_eq!(fonts.text.bold, Some(expected));
+            }
+            assert!(fonts.apply(&["font", "bold"], "yes").is_err());
+            assert!(!fonts.apply(&["font", "italic"], "1").unwrap());
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Geometry of the balloon, `origin.*`, `validrect.*` and `wordwrappoint.*`.
+
+use alloc::vec::Vec;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    value::{parse_i32, parse_rect, Coordinate, Rect},
+    ShellParseError,
+};
+
+/// Geometry of the balloon.
+///
+/// Each field is `None` when the corresponding key is absent.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BalloonGeometry {
+    /// `origin.x`
+    pub origin_x: Option<i32>,
+    /// `origin.y`
+    pub origin_y: Option<i32>,
+    /// `validrect.left`
+    pub validrect_left: Option<i32>,
+    /// `validrect.top`
+    pub validrect_top: Option<i32>,
+    /// `validrect.right`
+    pub validrect_right: Option<i32>,
+    /// `validrect.bottom`
+    pub validrect_bottom: Option<i32>,
+    /// `wordwrappoint.x`
+    pub wordwrappoint_x: Option<i32>,
+}
+
+/// Problem found by [`BalloonGeometry::validate`].
+///
+/// [`BalloonGeometry::validate`]: crate::balloon::BalloonGeometry::validate
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GeometryIssue {
+    /// Only some of `validrect.*` are given.
+    IncompleteValidRect,
+    /// `left` is greater than `right`, or `top` is greater than `bottom`.
+    ValidRectNotNormalized,
+    /// `origin` is outside of `validrect`.
+    OriginOutsideValidRect,
+}
+
+impl BalloonGeometry {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// `validrect` accepts both the comma form `validrect,left,top,right,bottom`
+    /// and the per-component form `validrect.left`.
+    ///
+    /// Returns `Ok(false)` when the key is not a geometry key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let field = match key_segments {
+            ["validrect"] => {
+                let (_, rect) = all_consuming(parse_rect)(value)?;
+                self.validrect_left = Some(rect.left);
+                self.validrect_top = Some(rect.top);
+                self.validrect_right = Some(rect.right);
+                self.validrect_bottom = Some(rect.bottom);
+                return Ok(true);
+            }
+            ["origin", "x"] => &mut self.origin_x,
+            ["origin", "y"] => &mut self.origin_y,
+            ["validrect", "left"] => &mut self.validrect_left,
+            ["validrect", "top"] => &mut self.validrect_top,
+            ["validrect", "right"] => &mut self.validrect_right,
+            ["validrect", "bottom"] => &mut self.validrect_bottom,
+            ["wordwrappoint", "x"] => &mut self.wordwrappoint_x,
+            _ => return Ok(false),
+        };
+
+        let (_, v) = all_consuming(parse_i32)(value)?;
+        *field = Some(v);
+
+        Ok(true)
+    }
+
+    /// Returns `origin` when both components are given.
+    pub fn origin(&self) -> Option<Coordinate> {
+        Some(Coordinate::new(self.origin_x?, self.origin_y?))
+    }
+
+    /// Returns `validrect` when all components are given.
+    pub fn validrect(&self) -> Option<Rect> {
+        Some(Rect::new(
+            self.validrect_left?,
+            self.validrect_top?,
+            self.validrect_right?,
+            self.validrect_bottom?,
+        ))
+    }
+
+    /// Checks the consistency of `origin` and `validrect`.
+    pub fn validate(&self) -> Vec<GeometryIssue> {
+        let mut issues = Vec::new();
+
+        let components = [
+            self.validrect_left,
+            self.validrect_top,
+            self.validrect_right,
+            self.validrect_bottom,
+        ];
+        let Some(rect) = self.validrect() else {
+            if components.iter().any(|c| c.is_some()) {
+                issues.push(GeometryIssue::IncompleteValidRect);
+            }
+            return issues;
+        };
+
+        if rect.left > rect.right || rect.top > rect.bottom {
+            issues.push(GeometryIssue::ValidRectNotNormalized);
+            return issues;
+        }
+
+        if let Some(origin) = self.origin() {
+            let inside = (rect.left..=rect.right).contains(&origin.x)
+                && (rect.top..=rect.bottom).contains(&origin.y);
+            if !inside {
+                issues.push(GeometryIssue::OriginOutsideValidRect);
+            }
+        }
+
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod balloon_geometry {
+        use super::*;
+
+        #[test]
+        fn success_when_dotted_form() {
+            let mut geometry = BalloonGeometry::default();
+            for (key, value) in [
+                (["validrect", "left"], "10"),
+                (["validrect", "top"], "20"),
+                (["validrect", "right"], "200"),
+                (["validrect", "bottom"], "150"),
+                (["origin", "x"], "10"),
+                (["origin", "y"], "20"),
+                (["wordwrappoint", "x"], "-30"),
+            ] {
+                assert!(geometry.apply(&key, value).unwrap());
+            }
+
+            assert_eq!(geometry.validrect(), Some(Rect::new(10, 20, 200, 150)));
+            assert_eq!(geometry.origin(), Some(Coordinate::new(10, 20)));
+            assert_eq!(geometry.wordwrappoint_x, Some(-30));
+            assert!(geometry.validate().is_empty());
+        }
+
+        #[test]
+        fn success_when_comma_form() {
+            let mut geometry = BalloonGeometry::default();
+            assert!(geometry.apply(&["validrect"], "0,0,100,80").unwrap());
+            assert_eq!(geometry.validrect(), Some(Rect::new(0, 0, 100, 80)));
+            assert!(!geometry.apply(&["validrect", "width"], "10").unwrap());
+            assert!(geometry.apply(&["validrect"], "0,0,100").is_err());
+        }
+
+        #[test]
+        fn flagged_when_origin_outside() {
+            let mut geometry = BalloonGeometry::default();
+            geometry.apply(&["validrect"], "10,10,100,100").unwrap();
+            geometry.apply(&["origin", "x"], "5").unwrap();
+            geometry.apply(&["origin", "y"], "50").unwrap();
+            assert_eq!(
+                geometry.validate(),
+                vec![GeometryIssue::OriginOutsideValidRect]
+            );
+        }
+
+        #[test]
+        fn flagged_when_not_normalized_or_incomplete() {
+            let mut geometry = BalloonGeometry::default();
+            geometry.apply(&["validrect"], "100,10,10,100").unwrap();
+            assert_eq!(
+                geometry.validate(),
+                vec![GeometryIssue::ValidRectNotNormalized]
+            );
+
+            let mut geometry = BalloonGeometry::default();
+            geometry.apply(&["validrect", "left"], "0").unwrap();
+            assert_eq!(
+                geometry.validate(),
+                vec![GeometryIssue::IncompleteValidRect]
+            );
+        }
+    }
+}
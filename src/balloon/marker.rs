@@ -0,0 +1,225 @@
+//! Scroll arrows and markers of the balloon, `arrowN.*` and `*marker.*`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    value::{parse_filename, parse_i32, Coordinate},
+    ShellParseError,
+};
+
+/// Image placed on the balloon, with `filename`, `x` and `y` keys.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Placement {
+    pub filename: Option<String>,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+}
+
+impl Placement {
+    /// Applies `field` (`filename`, `x` or `y`).
+    ///
+    /// Returns `Ok(false)` when the field is not handled here.
+    fn apply<'a>(
+        &mut self,
+        field: &str,
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match field {
+            "filename" => {
+                self.filename = Some(all_consuming(parse_filename)(value)?.1.to_string());
+            }
+            "x" => self.x = Some(all_consuming(parse_i32)(value)?.1),
+            "y" => self.y = Some(all_consuming(parse_i32)(value)?.1),
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the position when both `x` and `y` are given.
+    pub fn position(&self) -> Option<Coordinate> {
+        Some(Coordinate::new(self.x?, self.y?))
+    }
+
+    /// Checks whether the image is given without its position.
+    pub fn lacks_position(&self) -> bool {
+        self.filename.is_some() && self.position().is_none()
+    }
+}
+
+/// Scroll arrows, `arrow0.*` (up) and `arrow1.*` (down).
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ArrowSettings {
+    /// `arrow0.*`
+    pub up: Placement,
+    /// `arrow1.*`
+    pub down: Placement,
+    /// Other `arrow*` entries, in order.
+    pub unknown: Vec<(String, String)>,
+}
+
+impl ArrowSettings {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not an `arrow*` key.
+    /// Unknown `arrow*` keys are kept in `unknown`.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let Some(first) = key_segments.first() else {
+            return Ok(false);
+        };
+        if !first.starts_with("arrow") {
+            return Ok(false);
+        }
+
+        let applied = match key_segments {
+            ["arrow0", field] => self.up.apply(field, value)?,
+            ["arrow1", field] => self.down.apply(field, value)?,
+            _ => false,
+        };
+        if !applied {
+            self.unknown
+                .push((key_segments.join("."), value.to_string()));
+        }
+
+        Ok(true)
+    }
+
+    /// Returns indices of arrows with the filename but without the position.
+    pub fn missing_positions(&self) -> Vec<u32> {
+        [(0, &self.up), (1, &self.down)]
+            .into_iter()
+            .filter(|(_, arrow)| arrow.lacks_position())
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// Markers, `sstpmarker.*` and `onlinemarker.*`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MarkerSettings {
+    /// `sstpmarker.*`
+    pub sstp: Placement,
+    /// `onlinemarker.*`
+    pub online: Placement,
+    /// Other `*marker*` entries, in order.
+    pub unknown: Vec<(String, String)>,
+}
+
+impl MarkerSettings {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a `*marker*` key.
+    /// Unknown `*marker*` keys are kept in `unknown`.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let Some(first) = key_segments.first() else {
+            return Ok(false);
+        };
+        if !first.contains("marker") {
+            return Ok(false);
+        }
+
+        let applied = match key_segments {
+            ["sstpmarker", field] => self.sstp.apply(field, value)?,
+            ["onlinemarker", field] => self.online.apply(field, value)?,
+            _ => false,
+        };
+        if !applied {
+            self.unknown
+                .push((key_segments.join("."), value.to_string()));
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod arrow_settings {
+        use super::*;
+
+        #[test]
+        fn success_when_both_arrows() {
+            let mut arrows = ArrowSettings::default();
+            for (key, value) in [
+                (["arrow0", "filename"], "arrow0.png"),
+                (["arrow0", "x"], "100"),
+                (["arrow0", "y"], "5"),
+                (["arrow1", "filename"], "arrow1.png"),
+                (["arrow1", "x"], "100"),
+                (["arrow1", "y"], "-20"),
+            ] {
+                assert!(arrows.apply(&key, value).unwrap());
+            }
+
+            assert_eq!(arrows.up.filename.as_deref(), Some("arrow0.png"));
+            assert_eq!(arrows.down.position(), Some(Coordinate::new(100, -20)));
+            assert!(arrows.missing_positions().is_empty());
+            assert!(arrows.unknown.is_empty());
+        }
+
+        #[test]
+        fn preserved_when_unknown_arrow_index() {
+            let mut arrows = ArrowSettings::default();
+            assert!(arrows.apply(&["arrow2", "x"], "10").unwrap());
+            assert!(arrows.apply(&["arrow0", "width"], "10").unwrap());
+            assert!(!arrows.apply(&["origin", "x"], "10").unwrap());
+            assert_eq!(
+                arrows.unknown,
+                vec![
+                    ("arrow2.x".to_string(), "10".to_string()),
+                    ("arrow0.width".to_string(), "10".to_string()),
+                ]
+            );
+        }
+
+        #[test]
+        fn detected_when_filename_without_position() {
+            let mut arrows = ArrowSettings::default();
+            arrows.apply(&["arrow1", "filename"], "down.png").unwrap();
+            arrows.apply(&["arrow1", "x"], "10").unwrap();
+            assert_eq!(arrows.missing_positions(), vec![1]);
+        }
+
+        #[test]
+        fn failed_when_invalid_filename() {
+            let mut arrows = ArrowSettings::default();
+            assert!(arrows.apply(&["arrow0", "filename"], "up?.png").is_err());
+        }
+    }
+
+    mod marker_settings {
+        use super::*;
+
+        #[test]
+        fn success_when_marker_without_coordinates() {
+            let mut markers = MarkerSettings::default();
+            assert!(markers
+                .apply(&["sstpmarker", "filename"], "sstp.png")
+                .unwrap());
+            assert!(markers
+                .apply(&["onlinemarker", "filename"], "online.png")
+                .unwrap());
+            assert!(markers.apply(&["newmarker", "x"], "3").unwrap());
+
+            assert_eq!(markers.sstp.filename.as_deref(), Some("sstp.png"));
+            assert_eq!(markers.sstp.position(), None);
+            assert!(markers.online.lacks_position());
+            assert_eq!(markers.unknown.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,588 @@
+//! # `binding`
+//!
+//! Dress-up settings: bind groups in `descript.txt`
+//! and their pairing with bind animations in `surfaces.txt`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::binding::{BindGroups, Dressup};
+//! use shell_parser_common_rs::scope::Scope;
+//! use shell_parser_common_rs::seriko::{Animation, Interval, Intervals};
+//!
+//! let mut groups = BindGroups::default();
+//! groups.apply(&["sakura", "bindgroup10", "name"], "Hat,Ribbon").unwrap();
+//!
+//! let mut animation = Animation::new(10);
+//! animation.intervals = Some(Intervals(vec![Interval::Bind]));
+//! let animations = vec![animation];
+//!
+//! let dressup = Dressup::build(&[(0, animations.as_slice())], &groups, Scope::Sakura);
+//!
+//! assert_eq!(dressup.layers_of(0).count(), 1);
+//! assert!(dressup.orphan_animations.is_empty());
+//! ```
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+
+use nom::{
+    bytes::complete::{is_not, tag},
+    combinator::{all_consuming, opt},
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{
+    error::ParseFailure,
+    kv::segment_index,
+    scope::{split_scope, Scope},
+    seriko::{animation_options, Animation, AnimationOption, Interval, Intervals},
+    value::{parse_bool, parse_filename, parse_u32, separator, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// Bind group defined by `<scope>.bindgroupN.*`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BindGroup {
+    /// Category, the first field of `bindgroupN.name`.
+    pub category: String,
+    /// Part name, the second field of `bindgroupN.name`.
+    pub part: String,
+    /// Thumbnail filename, the optional third field of `bindgroupN.name`.
+    pub thumbnail: Option<String>,
+    /// `bindgroupN.default`
+    pub default: bool,
+}
+
+/// Bind groups keyed by scope and bind group ID.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct BindGroups {
+    groups: BTreeMap<(Scope, u32), BindGroup>,
+}
+
+impl BindGroups {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a bind group key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let Some((scope, rest)) = split_scope(key_segments) else {
+            return Ok(false);
+        };
+        let [group, field] = rest else {
+            return Ok(false);
+        };
+        let Ok((_, id)) = all_consuming(bindgroup_id)(group) else {
+            return Ok(false);
+        };
+
+        match *field {
+            "name" => {
+                let (_, (category, part, thumbnail)) = all_consuming(bindgroup_name)(value)?;
+                let entry = self.groups.entry((scope, id)).or_default();
+                entry.category = category.to_string();
+                entry.part = part.to_string();
+                entry.thumbnail = thumbnail.map(|s| s.to_string());
+            }
+            "default" => {
+                let (_, default) = all_consuming(parse_bool)(value)?;
+                self.groups.entry((scope, id)).or_default().default = default;
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the bind group of `scope` and `id`.
+    pub fn get(&self, scope: Scope, id: u32) -> Option<&BindGroup> {
+        self.groups.get(&(scope, id))
+    }
+
+    /// Returns scopes with bind groups, in order.
+    pub fn scopes(&self) -> BTreeSet<Scope> {
+        self.groups.keys().map(|(scope, _)| *scope).collect()
+    }
+
+    /// Iterates bind groups of `scope` ordered by ID.
+    pub fn iter_scope(&self, scope: Scope) -> impl Iterator<Item = (u32, &BindGroup)> {
+        self.groups
+            .iter()
+            .filter(move |((s, _), _)| *s == scope)
+            .map(|((_, id), group)| (*id, group))
+    }
+}
+
+fn bindgroup_id(input: &str) -> IResult<&str, u32, ShellParseError<'_>> {
+    preceded(tag("bindgroup"), parse_u32)(input)
+}
+
+fn bindgroup_name(input: &str) -> IResult<&str, (&str, &str, Option<&str>), ShellParseError<'_>> {
+    let (input, category) = is_not(",\r\n")(input)?;
+    let (input, part) = preceded(separator, is_not(",\r\n"))(input)?;
+    let (input, thumbnail) = opt(preceded(separator, parse_filename))(input)?;
+
+    Ok((input, (category, part, thumbnail)))
+}
+
+/// One dress-up layer, a bind animation paired with its bind group.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DressupLayer {
+    pub surface: u32,
+    /// Animation ID, which is also the bind group ID.
+    pub animation: u32,
+    pub group: BindGroup,
+}
+
+/// Dress-up layers of a scope with pairing problems.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Dressup {
+    /// Layers ordered by surface, then animation ID.
+    pub layers: Vec<DressupLayer>,
+    /// Bind animations without the bind group, as `(surface, animation)`.
+    pub orphan_animations: Vec<(u32, u32)>,
+    /// Bind group IDs not used by any bind animation.
+    pub orphan_groups: Vec<u32>,
+}
+
+impl Dressup {
+    /// Pairs bind animations of `surfaces` (`(surface ID, animations)`) with bind groups of `scope`.
+    pub fn build(surfaces: &[(u32, &[Animation])], groups: &BindGroups, scope: Scope) -> Dressup {
+        let mut dressup = Dressup::default();
+        let mut used = BTreeSet::new();
+
+        let mut sorted: Vec<_> = surfaces.iter().collect();
+        sorted.sort_by_key(|(id, _)| *id);
+
+        for (surface, animations) in sorted {
+            let mut binds: Vec<_> = animations.iter().filter(|a| a.is_bind()).collect();
+            binds.sort_by_key(|a| a.id);
+
+            for animation in binds {
+                match groups.get(scope, animation.id) {
+                    Some(group) => {
+                        used.insert(animation.id);
+                        dressup.layers.push(DressupLayer {
+                            surface: *surface,
+                            animation: animation.id,
+                            group: group.clone(),
+                        });
+                    }
+                    None => dressup.orphan_animations.push((*surface, animation.id)),
+                }
+            }
+        }
+
+        dressup.orphan_groups = groups
+            .iter_scope(scope)
+            .map(|(id, _)| id)
+            .filter(|id| !used.contains(id))
+            .collect();
+
+        dressup
+    }
+
+    /// Iterates layers of `surface`.
+    pub fn layers_of(&self, surface: u32) -> impl Iterator<Item = &DressupLayer> {
+        self.layers.iter().filter(move |l| l.surface == surface)
+    }
+}
+
+/// Alternate spelling of a bind animation, found by [`normalize_bind_entry`].
+///
+/// [`normalize_bind_entry`]: crate::binding::normalize_bind_entry
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BindSpelling {
+    /// `animationN.option,bind`
+    OptionBind,
+    /// `bindN,1`
+    LegacyKey,
+}
+
+/// Entry of [`BIND_ALIASES`].
+///
+/// [`BIND_ALIASES`]: crate::binding::BIND_ALIASES
+#[derive(Debug, Clone, Copy)]
+pub struct BindAlias {
+    pub spelling: BindSpelling,
+    /// Key and value pattern with `N` for the animation ID.
+    pub pattern: &'static str,
+    /// Canonical form to write instead.
+    pub replacement: &'static str,
+    apply: ApplyAlias,
+}
+
+/// Applies an entry of the spelling, returning the ID of the animation bound.
+type ApplyAlias = fn(
+    &mut BTreeMap<u32, Animation>,
+    &[&str],
+    &str,
+    &NumericReader,
+) -> Result<Option<u32>, ParseFailure>;
+
+/// Alternate spellings of bind animations normalized by [`normalize_bind_entry`],
+/// tried in order.
+///
+/// - `animationN.option,bind`: `bind` among the options, the other options kept as options.
+/// - `bindN,1`: `bindN` with a boolean, `0` leaving animation `N` as is.
+///
+/// Both give animation `N` the interval `bind`, as `animationN.interval,bind` does.
+///
+/// [`normalize_bind_entry`]: crate::binding::normalize_bind_entry
+pub const BIND_ALIASES: &[BindAlias] = &[
+    BindAlias {
+        spelling: BindSpelling::OptionBind,
+        pattern: "animationN.option,bind",
+        replacement: "animationN.interval,bind",
+        apply: apply_option_bind,
+    },
+    BindAlias {
+        spelling: BindSpelling::LegacyKey,
+        pattern: "bindN",
+        replacement: "animationN.interval,bind",
+        apply: apply_legacy_bind,
+    },
+];
+
+impl BindSpelling {
+    /// Returns the entry of [`BIND_ALIASES`] for the spelling.
+    ///
+    /// [`BIND_ALIASES`]: crate::binding::BIND_ALIASES
+    pub fn alias(&self) -> &'static BindAlias {
+        BIND_ALIASES
+            .iter()
+            .find(|alias| alias.spelling == *self)
+            .expect("every spelling has its alias")
+    }
+}
+
+/// Note that an animation is bound by an alternate spelling, for linters to suggest [`BindAlias::replacement`].
+///
+/// [`BindAlias::replacement`]: crate::binding::BindAlias::replacement
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BindNote {
+    pub animation: u32,
+    pub spelling: BindSpelling,
+}
+
+impl fmt::Display for BindNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alias = self.spelling.alias();
+        let id = self.animation.to_string();
+        write!(
+            f,
+            "{} binds animation{}, use {}",
+            alias.pattern.replace('N', &id),
+            self.animation,
+            alias.replacement.replace('N', &id)
+        )
+    }
+}
+
+/// Applies an entry of a surface block written in one of [`BIND_ALIASES`] into `animations`,
+/// giving the animation the interval `bind` as the canonical form does.
+///
+/// Returns `Ok(None)` when the entry is not an alternate spelling.
+///
+/// [`BIND_ALIASES`]: crate::binding::BIND_ALIASES
+pub fn normalize_bind_entry(
+    animations: &mut BTreeMap<u32, Animation>,
+    key_segments: &[&str],
+    value: &str,
+) -> Result<Option<BindNote>, ParseFailure> {
+    normalize_bind_entry_with(
+        animations,
+        key_segments,
+        value,
+        &NumericReader::new(NumericPolicy::DEFAULT),
+    )
+}
+
+/// Same as [`normalize_bind_entry`], reading numbers by `numbers`.
+///
+/// [`normalize_bind_entry`]: crate::binding::normalize_bind_entry
+pub(crate) fn normalize_bind_entry_with(
+    animations: &mut BTreeMap<u32, Animation>,
+    key_segments: &[&str],
+    value: &str,
+    numbers: &NumericReader,
+) -> Result<Option<BindNote>, ParseFailure> {
+    for alias in BIND_ALIASES.iter() {
+        if let Some(animation) = (alias.apply)(animations, key_segments, value, numbers)? {
+            return Ok(Some(BindNote {
+                animation,
+                spelling: alias.spelling,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Adds the interval `bind` to `animation`, keeping the other intervals.
+pub(crate) fn mark_bind(animation: &mut Animation) {
+    match animation.intervals.as_mut() {
+        Some(intervals) if !intervals.is_bind() => intervals.0.push(Interval::Bind),
+        Some(_) => {}
+        None => animation.intervals = Some(Intervals(vec![Interval::Bind])),
+    }
+}
+
+fn apply_option_bind(
+    animations: &mut BTreeMap<u32, Animation>,
+    key_segments: &[&str],
+    value: &str,
+    numbers: &NumericReader,
+) -> Result<Option<u32>, ParseFailure> {
+    let [animation, "option"] = key_segments else {
+        return Ok(None);
+    };
+    let Some(id) = segment_index(animation, "animation") else {
+        return Ok(None);
+    };
+    let (_, mut options) = all_consuming(|i| animation_options(i, numbers))(value)?;
+    if !options.contains(&AnimationOption::Bind) {
+        return Ok(None);
+    }
+    options.retain(|o| *o != AnimationOption::Bind);
+
+    let animation = animations.entry(id).or_insert_with(|| Animation::new(id));
+    animation.options = options;
+    mark_bind(animation);
+    Ok(Some(id))
+}
+
+fn apply_legacy_bind(
+    animations: &mut BTreeMap<u32, Animation>,
+    key_segments: &[&str],
+    value: &str,
+    _numbers: &NumericReader,
+) -> Result<Option<u32>, ParseFailure> {
+    let [key] = key_segments else {
+        return Ok(None);
+    };
+    let Some(id) = segment_index(key, "bind") else {
+        return Ok(None);
+    };
+    let (_, bind) = all_consuming(parse_bool)(value)?;
+    if bind {
+        mark_bind(animations.entry(id).or_insert_with(|| Animation::new(id)));
+    }
+    Ok(Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::seriko::{Interval, Intervals};
+
+    fn bind_animation(id: u32) -> Animation {
+        let mut animation = Animation::new(id);
+        animation.intervals = Some(Intervals(vec![Interval::Bind]));
+        animation
+    }
+
+    fn groups() -> BindGroups {
+        let mut groups = BindGroups::default();
+        groups
+            .apply(&["sakura", "bindgroup1", "name"], "Hat,Ribbon,ribbon.png")
+            .unwrap();
+        groups
+            .apply(&["sakura", "bindgroup1", "default"], "1")
+            .unwrap();
+        groups
+            .apply(&["sakura", "bindgroup2", "name"], "Arm,Bag")
+            .unwrap();
+        groups
+    }
+
+    mod bind_groups {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_entries() {
+            let groups = groups();
+            let group = groups.get(Scope::Sakura, 1).unwrap();
+            assert_eq!(group.category, "Hat");
+            assert_eq!(group.part, "Ribbon");
+            assert_eq!(group.thumbnail.as_deref(), Some("ribbon.png"));
+            assert!(group.default);
+            assert!(!groups.get(Scope::Sakura, 2).unwrap().default);
+            assert!(groups.get(Scope::Kero, 1).is_none());
+        }
+
+        #[test]
+        fn ignored_when_unknown_key() {
+            let mut groups = BindGroups::default();
+            assert!(!groups
+                .apply(&["sakura", "bindgroup1", "color"], "x")
+                .unwrap());
+            assert!(!groups.apply(&["sakura", "menu", "name"], "x").unwrap());
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let mut groups = BindGroups::default();
+            assert!(groups
+                .apply(&["sakura", "bindgroup1", "name"], "Hat")
+                .is_err());
+            assert!(groups
+                .apply(&["sakura", "bindgroup1", "default"], "yes")
+                .is_err());
+        }
+    }
+
+    mod dressup {
+        use super::*;
+
+        #[test]
+        fn matched_pair() {
+            let animations = vec![bind_animation(1), bind_animation(2)];
+            let result = Dressup::build(&[(0, animations.as_slice())], &groups(), Scope::Sakura);
+            assert_eq!(result.layers.len(), 2);
+            assert_eq!(result.layers[0].group.part, "Ribbon");
+            assert!(result.orphan_animations.is_empty());
+            assert!(result.orphan_groups.is_empty());
+        }
+
+        #[test]
+        fn orphan_animation() {
+            let animations = vec![bind_animation(1), bind_animation(2), bind_animation(3)];
+            let result = Dressup::build(&[(0, animations.as_slice())], &groups(), Scope::Sakura);
+            assert_eq!(result.orphan_animations, vec![(0, 3)]);
+        }
+
+        #[test]
+        fn orphan_group() {
+            let mut plain = Animation::new(2);
+            plain.intervals = Some(Intervals(vec![Interval::Sometimes]));
+            let animations = vec![bind_animation(1), plain];
+            let result = Dressup::build(&[(0, animations.as_slice())], &groups(), Scope::Sakura);
+            assert_eq!(result.layers.len(), 1);
+            assert_eq!(result.orphan_groups, vec![2]);
+        }
+
+        #[test]
+        fn multiple_layers_on_one_surface() {
+            let surface0 = vec![bind_animation(2), bind_animation(1)];
+            let surface10 = vec![bind_animation(1)];
+            let result = Dressup::build(
+                &[(10, surface10.as_slice()), (0, surface0.as_slice())],
+                &groups(),
+                Scope::Sakura,
+            );
+            let layers: Vec<_> = result.layers_of(0).map(|l| l.animation).collect();
+            assert_eq!(layers, vec![1, 2]);
+            assert_eq!(result.layers_of(10).count(), 1);
+            assert_eq!(result.layers[0].surface, 0);
+        }
+    }
+
+    #[cfg(feature = "surfaces")]
+    mod normalize_bind_entry {
+        use super::*;
+
+        use crate::surfaces::SurfaceBody;
+
+        fn body(lines: &[&str]) -> SurfaceBody {
+            let mut body = SurfaceBody::default();
+            for line in lines {
+                body.apply_line(line).unwrap();
+            }
+            body
+        }
+
+        fn canonical() -> SurfaceBody {
+            body(&[
+                "animation1.interval,bind",
+                "animation1.pattern0,overlay,100,0,0,0",
+            ])
+        }
+
+        #[test]
+        fn success_when_canonical_form() {
+            let body = canonical();
+            assert!(body.animations[&1].is_bind());
+            assert!(body.bind_notes.is_empty());
+        }
+
+        #[test]
+        fn success_when_option_bind() {
+            let bound = body(&[
+                "animation1.option,bind",
+                "animation1.pattern0,overlay,100,0,0,0",
+            ]);
+            assert_eq!(bound.animations, canonical().animations);
+            assert_eq!(
+                bound.bind_notes,
+                vec![BindNote {
+                    animation: 1,
+                    spelling: BindSpelling::OptionBind,
+                }]
+            );
+            assert_eq!(
+                bound.bind_notes[0].to_string(),
+                "animation1.option,bind binds animation1, use animation1.interval,bind"
+            );
+
+            let later = body(&[
+                "animation2.option,bind,exclusive",
+                "animation2.interval,sometimes",
+            ]);
+            let animation = &later.animations[&2];
+            assert_eq!(
+                animation.intervals,
+                Some(Intervals(vec![Interval::Sometimes, Interval::Bind]))
+            );
+            assert_eq!(animation.options, vec![AnimationOption::Exclusive(None)]);
+        }
+
+        #[test]
+        fn success_when_legacy_key() {
+            let bound = body(&["bind1,1", "animation1.pattern0,overlay,100,0,0,0"]);
+            assert_eq!(bound.animations, canonical().animations);
+            assert_eq!(bound.bind_notes[0].spelling, BindSpelling::LegacyKey);
+            assert_eq!(
+                bound.bind_notes[0].to_string(),
+                "bind1 binds animation1, use animation1.interval,bind"
+            );
+
+            let unbound = body(&["bind3,0"]);
+            assert!(unbound.animations.is_empty());
+            assert!(SurfaceBody::default().apply_line("bind3,yes").is_err());
+        }
+
+        #[test]
+        fn success_when_all_spellings_equivalent() {
+            let spellings = [
+                canonical(),
+                body(&[
+                    "animation1.option,bind",
+                    "animation1.pattern0,overlay,100,0,0,0",
+                ]),
+                body(&["bind1,true", "animation1.pattern0,overlay,100,0,0,0"]),
+            ];
+            let expected = Dressup::build(
+                &[(0, &[canonical().animations[&1].clone()][..])],
+                &groups(),
+                Scope::Sakura,
+            );
+            for body in spellings.iter() {
+                let animations: Vec<_> = body.animations.values().cloned().collect();
+                let dressup =
+                    Dressup::build(&[(0, animations.as_slice())], &groups(), Scope::Sakura);
+                assert_eq!(dressup, expected);
+            }
+            assert_eq!(expected.layers[0].group.part, "Ribbon");
+        }
+    }
+}
@@ -0,0 +1,399 @@
+//! # `block`
+//!
+//! Tokenizer for blocks, `header` followed by lines enclosed in `{` and `}`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::block::parse_blocks;
+//!
+//! let case = "charset,UTF-8\r\n\r\nsurface0\r\n{\r\nelement0,base,surface0.png,0,0\r\n}\r\n";
+//! let blocks = parse_blocks(case).unwrap();
+//!
+//! assert_eq!(blocks.len(), 1);
+//! assert_eq!(blocks[0].header, "surface0");
+//! assert_eq!(blocks[0].body[0].text, "element0,base,surface0.png,0,0");
+//! ```
+
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+
+use crate::{
+    error::Located,
+    line::{is_comment, lines, lines_in, Line, Lines},
+    options::{Limit, LimitExceeded, ParseOptions},
+    span::span_of,
+};
+
+#[cfg(feature = "document")]
+mod cursor;
+
+#[cfg(feature = "document")]
+pub(crate) use cursor::BlockCursor;
+
+/// Block in the input.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Block<'a> {
+    /// Header text, trimmed.
+    pub header: &'a str,
+    /// Line number of the header.
+    pub header_line: usize,
+    /// Lines between the braces, including blank and comment lines.
+    pub body: Vec<Line<'a>>,
+    /// Line number of the closing brace.
+    pub close_line: usize,
+}
+
+/// `BlockError` type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+pub enum BlockError {
+    /// `{` without the header line before it.
+    MissingHeader,
+    /// `{` inside a block.
+    NestedOpen,
+    /// `}` outside blocks.
+    UnexpectedClose,
+    /// The block is not closed until the end of the input.
+    Unclosed,
+    /// The input is over one of [`ParseOptions::limits`], checked by [`parse_blocks_with`].
+    ///
+    /// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+    /// [`parse_blocks_with`]: crate::block::parse_blocks_with
+    LimitExceeded(LimitExceeded),
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            BlockError::MissingHeader => "block without header",
+            BlockError::NestedOpen => "nested block",
+            BlockError::UnexpectedClose => "unexpected closing brace",
+            BlockError::Unclosed => "unclosed block",
+            BlockError::LimitExceeded(e) => return write!(f, "{}", e),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl core::error::Error for BlockError {}
+
+/// Block in the input, as byte ranges of it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BlockSpan {
+    /// Range of the header text, trimmed.
+    pub header: Range<usize>,
+    /// Line number of the header.
+    pub header_line: usize,
+    /// Range of the lines between the braces, with their line endings.
+    pub body: Range<usize>,
+    /// Line number of the first line after `{`.
+    pub body_line: usize,
+    /// Line number of the closing brace.
+    pub close_line: usize,
+}
+
+impl BlockSpan {
+    /// Returns the header text in `input`, the text the span was made from.
+    pub fn header<'a>(&self, input: &'a str) -> &'a str {
+        &input[self.header.clone()]
+    }
+
+    /// Returns an iterator over the lines between the braces in `input`, the text the span was made from.
+    pub fn body_lines<'a>(&self, input: &'a str) -> Lines<'a> {
+        lines_in(input, self.body.clone(), self.body_line)
+    }
+
+    /// Makes the [`Block`] in `input`, the text the span was made from.
+    ///
+    /// [`Block`]: crate::block::Block
+    pub fn block<'a>(&self, input: &'a str) -> Block<'a> {
+        Block {
+            header: self.header(input),
+            header_line: self.header_line,
+            body: self.body_lines(input).collect(),
+            close_line: self.close_line,
+        }
+    }
+}
+
+/// Splits `input` into blocks.
+///
+/// Lines of each body are collected into [`Block::body`] from the spans of [`parse_block_spans`].
+/// Use [`parse_block_spans`] to read them lazily with [`BlockSpan::body_lines`].
+/// Lines outside blocks are skipped. The header is the last non-blank,
+/// non-comment line before `{`, or the text before `{` on the same line.
+/// An error is located at the offending line, or at the header for [`BlockError::Unclosed`].
+///
+/// [`Block::body`]: crate::block::Block::body
+/// [`parse_block_spans`]: crate::block::parse_block_spans
+/// [`BlockSpan::body_lines`]: crate::block::BlockSpan::body_lines
+/// [`BlockError::Unclosed`]: crate::block::BlockError::Unclosed
+pub fn parse_blocks(input: &str) -> Result<Vec<Block<'_>>, Located<BlockError>> {
+    Ok(parse_block_spans(input)?
+        .iter()
+        .map(|span| span.block(input))
+        .collect())
+}
+
+/// Same as [`parse_blocks`], checking `input` against [`ParseOptions::limits`] before collecting lines of blocks.
+///
+/// Lines are checked as [`Lines::limited`] does, and bodies of blocks against [`Limits::max_block_lines`].
+/// Limits exceeded are [`BlockError::LimitExceeded`], located at the line or at the header of the block.
+///
+/// [`parse_blocks`]: crate::block::parse_blocks
+/// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+/// [`Lines::limited`]: crate::line::Lines::limited
+/// [`Limits::max_block_lines`]: crate::options::Limits::max_block_lines
+/// [`BlockError::LimitExceeded`]: crate::block::BlockError::LimitExceeded
+pub fn parse_blocks_with<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<Block<'a>>, Located<BlockError>> {
+    if let Some(Err(e)) = lines(input).limited(options).find(|l| l.is_err()) {
+        return Err(e.map(BlockError::LimitExceeded));
+    }
+
+    let spans = parse_block_spans(input)?;
+    let max = options.limits.max_block_lines;
+    if let Some(span) = spans.iter().find(|s| s.close_line - s.body_line > max) {
+        return Err(Located::new(
+            span.header_line,
+            BlockError::LimitExceeded(LimitExceeded {
+                limit: Limit::BlockLines,
+                max,
+            }),
+        ));
+    }
+
+    Ok(spans.iter().map(|span| span.block(input)).collect())
+}
+
+/// Same as [`parse_blocks`], giving blocks as ranges of `input` in one pass without collecting their lines.
+///
+/// [`parse_blocks`]: crate::block::parse_blocks
+pub fn parse_block_spans(input: &str) -> Result<Vec<BlockSpan>, Located<BlockError>> {
+    let mut spans = Vec::new();
+    let mut header: Option<Line<'_>> = None;
+    let mut current: Option<BlockSpan> = None;
+
+    for line in lines(input) {
+        let text = line.text.trim();
+
+        if let Some(mut span) = current.take() {
+            if text == "}" {
+                span.body.end = line.offset;
+                span.close_line = line.number;
+                spans.push(span);
+            } else if text.starts_with('{') {
+                return Err(Located::new(line.number, BlockError::NestedOpen));
+            } else {
+                current = Some(span);
+            }
+            continue;
+        }
+
+        if text.is_empty() || is_comment(text) {
+            continue;
+        }
+
+        if text == "}" {
+            return Err(Located::new(line.number, BlockError::UnexpectedClose));
+        }
+
+        if let Some(head) = text.strip_suffix('{') {
+            let head = head.trim_end();
+            let (header_text, header_line) = if head.is_empty() {
+                match header.take() {
+                    Some(h) => (h.text.trim(), h.number),
+                    None => return Err(Located::new(line.number, BlockError::MissingHeader)),
+                }
+            } else {
+                (head, line.number)
+            };
+
+            let body = line.offset + line.text.len() + line.ending.as_str().len();
+            current = Some(BlockSpan {
+                header: span_of(input, header_text),
+                header_line,
+                body: body..body,
+                body_line: line.number + 1,
+                close_line: 0,
+            });
+            header = None;
+        } else {
+            header = Some(line);
+        }
+    }
+
+    if let Some(span) = current {
+        return Err(Located::new(span.header_line, BlockError::Unclosed));
+    }
+
+    Ok(spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_blocks {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "descript\r\n{\r\nversion,1\r\n}\r\n\r\n// comment\r\nsurface0,3-5 {\r\n\r\nelement0,base,surface0.png,0,0\r\n}\r\n";
+            let result = parse_blocks(case).unwrap();
+            assert_eq!(result.len(), 2);
+
+            assert_eq!(result[0].header, "descript");
+            assert_eq!(result[0].header_line, 1);
+            assert_eq!(result[0].close_line, 4);
+            let body: Vec<_> = result[0].body.iter().map(|l| l.text).collect();
+            assert_eq!(body, vec!["version,1"]);
+
+            assert_eq!(result[1].header, "surface0,3-5");
+            assert_eq!(result[1].header_line, 7);
+            let body: Vec<_> = result[1].body.iter().map(|l| l.text).collect();
+            assert_eq!(body, vec!["", "element0,base,surface0.png,0,0"]);
+        }
+
+        #[test]
+        fn failed_when_unclosed() {
+            let case = "surface0\r\n{\r\nelement0,base,surface0.png,0,0\r\n";
+            assert_eq!(
+                parse_blocks(case).unwrap_err(),
+                Located::new(1, BlockError::Unclosed)
+            );
+        }
+
+        #[test]
+        fn failed_when_stray_brace() {
+            let case = "surface0\r\n{\r\n}\r\n}\r\n";
+            assert_eq!(
+                parse_blocks(case).unwrap_err(),
+                Located::new(4, BlockError::UnexpectedClose)
+            );
+
+            let case = "{\r\n}\r\n";
+            assert_eq!(
+                parse_blocks(case).unwrap_err(),
+                Located::new(1, BlockError::MissingHeader)
+            );
+
+            let case = "surface0\r\n{\r\n{\r\n}\r\n";
+            assert_eq!(
+                parse_blocks(case).unwrap_err(),
+                Located::new(3, BlockError::NestedOpen)
+            );
+        }
+    }
+
+    mod parse_blocks_with {
+        use super::*;
+
+        use alloc::{format, string::String};
+
+        use crate::options::Limits;
+
+        #[test]
+        fn failed_when_block_too_long() {
+            let case = "surface0\r\n{\r\na,1\r\n}\r\nsurface1\r\n{\r\n\r\na,1\r\nb,2\r\n}\r\n";
+            let mut limits = Limits::DEFAULT;
+            limits.max_block_lines = 2;
+            let options = ParseOptions::STRICT.limits(limits);
+
+            assert_eq!(
+                parse_blocks_with(case, &options),
+                Err(Located::new(
+                    5,
+                    BlockError::LimitExceeded(LimitExceeded {
+                        limit: Limit::BlockLines,
+                        max: 2
+                    })
+                ))
+            );
+        }
+
+        #[test]
+        fn failed_when_line_too_long() {
+            let case = String::from("surface0\r\n{\r\n") + &"x".repeat(100) + "\r\n}\r\n";
+            let mut limits = Limits::DEFAULT;
+            limits.max_line_length = 99;
+            let options = ParseOptions::STRICT.limits(limits);
+
+            let e = parse_blocks_with(&case, &options).unwrap_err();
+            assert_eq!(e.line, 3);
+            assert!(matches!(
+                e.value,
+                BlockError::LimitExceeded(LimitExceeded {
+                    limit: Limit::LineLength,
+                    ..
+                })
+            ));
+        }
+
+        #[test]
+        fn success_when_large_fixture() {
+            let mut case = String::from("charset,UTF-8\r\n");
+            for id in 0..5000 {
+                case.push_str(&format!("surface{id}\r\n{{\r\n"));
+                for element in 0..20 {
+                    case.push_str(&format!(
+                        "element{element},overlay,surface{id}_{element}.png,0,0\r\n"
+                    ));
+                }
+                case.push_str("}\r\n");
+            }
+
+            assert_eq!(
+                parse_blocks_with(&case, &ParseOptions::COMPATIBLE),
+                parse_blocks(&case)
+            );
+        }
+    }
+
+    mod parse_block_spans {
+        use super::*;
+
+        use alloc::vec;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case =
+                "descript\r\n{\r\nversion,1\r\n}\r\nsurface0 {\n\nelement0,base,a.png,0,0\n}";
+            let spans = parse_block_spans(case).unwrap();
+
+            assert_eq!(
+                spans,
+                vec![
+                    BlockSpan {
+                        header: 0..8,
+                        header_line: 1,
+                        body: 13..24,
+                        body_line: 3,
+                        close_line: 4,
+                    },
+                    BlockSpan {
+                        header: 27..35,
+                        header_line: 5,
+                        body: 38..63,
+                        body_line: 6,
+                        close_line: 8,
+                    },
+                ]
+            );
+            assert_eq!(spans[1].header(case), "surface0");
+            let body: Vec<_> = spans[1]
+                .body_lines(case)
+                .map(|l| (l.number, l.text))
+                .collect();
+            assert_eq!(body, vec![(6, ""), (7, "element0,base,a.png,0,0")]);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! `BlockCursor`, finding whether lines are in blocks in one pass.
+
+use core::{iter::Peekable, ops::RangeInclusive, slice};
+
+use super::{Block, BlockSpan};
+
+/// Lines of a block, from the header to the closing brace.
+pub(crate) trait BlockLines {
+    fn block_lines(&self) -> RangeInclusive<usize>;
+}
+
+impl BlockLines for Block<'_> {
+    fn block_lines(&self) -> RangeInclusive<usize> {
+        self.header_line..=self.close_line
+    }
+}
+
+impl BlockLines for BlockSpan {
+    fn block_lines(&self) -> RangeInclusive<usize> {
+        self.header_line..=self.close_line
+    }
+}
+
+/// Finds whether lines are in blocks, in one pass over blocks in order of their lines.
+///
+/// Line numbers must be given in ascending order, as [`lines`] gives them.
+///
+/// [`lines`]: crate::line::lines
+pub(crate) struct BlockCursor<'b, T> {
+    blocks: Peekable<slice::Iter<'b, T>>,
+}
+
+impl<'b, T: BlockLines> BlockCursor<'b, T> {
+    pub(crate) fn new(blocks: &'b [T]) -> BlockCursor<'b, T> {
+        BlockCursor {
+            blocks: blocks.iter().peekable(),
+        }
+    }
+
+    /// Checks whether line `number` is in a block, skipping blocks before it.
+    pub(crate) fn contains(&mut self, number: usize) -> bool {
+        while self
+            .blocks
+            .next_if(|b| *b.block_lines().end() < number)
+            .is_some()
+        {}
+        self.blocks
+            .peek()
+            .is_some_and(|b| b.block_lines().contains(&number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use crate::{block::parse_block_spans, line::lines};
+
+    mod contains {
+        use super::*;
+
+        #[test]
+        fn success_when_lines_in_order() {
+            let case = "a,1\r\nsurface0\r\n{\r\n}\r\nb,2\r\nsurface1\r\n{\r\nc,3\r\n}\r\nd,4\r\n";
+            let spans = parse_block_spans(case).unwrap();
+            let mut cursor = BlockCursor::new(&spans);
+
+            let inside: Vec<usize> = lines(case)
+                .filter(|line| cursor.contains(line.number))
+                .map(|line| line.number)
+                .collect();
+            assert_eq!(inside, [2, 3, 4, 6, 7, 8, 9]);
+            assert!(!BlockCursor::<BlockSpan>::new(&[]).contains(1));
+        }
+    }
+}
@@ -0,0 +1,481 @@
+//! # `builder`
+//!
+//! [`SurfaceBuilder`], building definitions of a surface in code, and [`SurfaceBody::to_block_text`],
+//! writing them back into a block of `surfaces.txt`.
+//!
+//! [`SurfaceBuilder`]: crate::builder::SurfaceBuilder
+//! [`SurfaceBody::to_block_text`]: crate::surfaces::SurfaceBody::to_block_text
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::builder::SurfaceBuilder;
+//! use shell_parser_common_rs::format::FormatStyle;
+//! use shell_parser_common_rs::seriko::{DrawMethod, Interval, Pattern, Wait};
+//! use shell_parser_common_rs::value::{Coordinate, IdList, Rect};
+//!
+//! let body = SurfaceBuilder::new()
+//!     .element(DrawMethod::Base, "body.png", 0, 0)
+//!     .collision(Rect::new(10, 10, 50, 50), "Head")
+//!     .animation(1)
+//!     .interval(Interval::Sometimes)
+//!     .pattern(Pattern::Draw {
+//!         method: DrawMethod::Overlay,
+//!         surface: 100,
+//!         wait: Wait::Fixed(50),
+//!         offset: Coordinate::default(),
+//!         alpha: None,
+//!     })
+//!     .build()
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     body.to_block_text(&IdList(vec![0, 3]), &FormatStyle::DEFAULT.indent("\t")),
+//!     "surface0,surface3\r\n{\r\n\telement0,base,body.png,0,0\r\n\tcollision0,10,10,50,50,Head\r\n\
+//!      \tanimation1.interval,sometimes\r\n\tanimation1.pattern0,overlay,100,50,0,0\r\n}\r\n"
+//! );
+//! ```
+
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    format::FormatStyle,
+    line::LineEnding,
+    seriko::{Animation, DrawMethod, Interval, Intervals, Pattern},
+    surfaces::{Collision, Element, Surface, SurfaceBody},
+    value::{parse_filename, Coordinate, IdList, Rect},
+};
+
+/// `BuildError` type.
+///
+/// Definitions which baseware cannot read, or which would be read back differently, are rejected.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum BuildError {
+    /// The animation of the ID has patterns without `animationN.interval`.
+    PatternWithoutInterval(u32),
+    /// Elements share the index.
+    DuplicateElement(u32),
+    /// The filename of an element is not read by [`parse_filename`].
+    ///
+    /// [`parse_filename`]: crate::value::parse_filename
+    InvalidFilename(String),
+    /// The name of a collision is empty or contains a line ending.
+    InvalidName(String),
+    /// The pattern has alpha its method does not take, or an empty group of IDs.
+    UnwritablePattern { animation: u32, pattern: u32 },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::PatternWithoutInterval(id) => {
+                write!(f, "patterns without interval in animation{}", id)
+            }
+            BuildError::DuplicateElement(id) => write!(f, "duplicate element{}", id),
+            BuildError::InvalidFilename(filename) => write!(f, "invalid filename {:?}", filename),
+            BuildError::InvalidName(name) => write!(f, "invalid collision name {:?}", name),
+            BuildError::UnwritablePattern { animation, pattern } => {
+                write!(f, "unwritable animation{}.pattern{}", animation, pattern)
+            }
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+/// Builder of [`SurfaceBody`], checked by [`SurfaceBuilder::build`].
+///
+/// [`SurfaceBody`]: crate::surfaces::SurfaceBody
+/// [`SurfaceBuilder::build`]: crate::builder::SurfaceBuilder::build
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SurfaceBuilder {
+    body: SurfaceBody,
+}
+
+impl SurfaceBuilder {
+    /// Creates a builder without definitions.
+    pub fn new() -> SurfaceBuilder {
+        SurfaceBuilder::default()
+    }
+
+    /// Adds an element of the index following the last element, or `0` for the first.
+    pub fn element(self, method: DrawMethod, filename: &str, x: i32, y: i32) -> SurfaceBuilder {
+        let id = self
+            .body
+            .elements
+            .last()
+            .map_or(0, |e| e.id.saturating_add(1));
+        self.element_at(id, method, filename, x, y)
+    }
+
+    /// Adds an element of the index `id`.
+    pub fn element_at(
+        mut self,
+        id: u32,
+        method: DrawMethod,
+        filename: &str,
+        x: i32,
+        y: i32,
+    ) -> SurfaceBuilder {
+        self.body.elements.push(Element {
+            id,
+            method,
+            filename: filename.to_string(),
+            offset: Coordinate { x, y },
+            alpha: None,
+        });
+        self
+    }
+
+    /// Adds a collision of the index following the last collision, or `0` for the first.
+    pub fn collision(mut self, rect: Rect, name: &str) -> SurfaceBuilder {
+        let id = self
+            .body
+            .collisions
+            .last()
+            .map_or(0, |c| c.id.saturating_add(1));
+        self.body.collisions.push(Collision {
+            id,
+            rect,
+            name: name.to_string(),
+        });
+        self
+    }
+
+    /// Starts the animation of `id`, continuing the one given before with the same ID.
+    pub fn animation(mut self, id: u32) -> AnimationBuilder {
+        let animation = self
+            .body
+            .animations
+            .remove(&id)
+            .unwrap_or_else(|| Animation::new(id));
+        AnimationBuilder {
+            surface: self,
+            animation,
+        }
+    }
+
+    /// Checks the definitions and returns them.
+    pub fn build(self) -> Result<SurfaceBody, BuildError> {
+        let mut ids = BTreeSet::new();
+        for element in self.body.elements.iter() {
+            if !ids.insert(element.id) {
+                return Err(BuildError::DuplicateElement(element.id));
+            }
+            if all_consuming(parse_filename)(&element.filename).is_err() {
+                return Err(BuildError::InvalidFilename(element.filename.clone()));
+            }
+        }
+
+        for collision in self.body.collisions.iter() {
+            if collision.name.is_empty() || collision.name.contains(['\r', '\n']) {
+                return Err(BuildError::InvalidName(collision.name.clone()));
+            }
+        }
+
+        for animation in self.body.animations.values() {
+            if animation.intervals.is_none() && !animation.patterns.is_empty() {
+                return Err(BuildError::PatternWithoutInterval(animation.id));
+            }
+            for (index, pattern) in animation.patterns.iter() {
+                let writable = match pattern {
+                    Pattern::Draw { method, alpha, .. } => {
+                        alpha.is_none() || method.supports_alpha()
+                    }
+                    _ => !pattern.targets().is_empty(),
+                };
+                if !writable {
+                    return Err(BuildError::UnwritablePattern {
+                        animation: animation.id,
+                        pattern: *index,
+                    });
+                }
+            }
+        }
+
+        Ok(self.body)
+    }
+
+    /// Same as [`SurfaceBuilder::build`], giving the surface of `id`.
+    ///
+    /// [`SurfaceBuilder::build`]: crate::builder::SurfaceBuilder::build
+    pub fn build_surface(self, id: u32) -> Result<Surface, BuildError> {
+        self.build().map(|body| Surface::new(id, body))
+    }
+}
+
+/// Builder of an animation, given by [`SurfaceBuilder::animation`].
+///
+/// [`SurfaceBuilder::animation`]: crate::builder::SurfaceBuilder::animation
+#[derive(Debug, PartialEq, Clone)]
+pub struct AnimationBuilder {
+    surface: SurfaceBuilder,
+    animation: Animation,
+}
+
+impl AnimationBuilder {
+    /// Adds `interval`, combined by `+` with those given before.
+    pub fn interval(mut self, interval: Interval) -> AnimationBuilder {
+        self.animation
+            .intervals
+            .get_or_insert_with(|| Intervals(Vec::new()))
+            .0
+            .push(interval);
+        self
+    }
+
+    /// Adds `pattern` of the index following the last pattern, or `0` for the first.
+    pub fn pattern(mut self, pattern: Pattern) -> AnimationBuilder {
+        let index = self
+            .animation
+            .patterns
+            .keys()
+            .next_back()
+            .map_or(0, |i| i.saturating_add(1));
+        self.animation.patterns.insert(index, pattern);
+        self
+    }
+
+    /// Ends the animation and returns to the surface.
+    pub fn end(mut self) -> SurfaceBuilder {
+        self.surface
+            .body
+            .animations
+            .insert(self.animation.id, self.animation);
+        self.surface
+    }
+
+    /// Same as [`SurfaceBuilder::build`] after [`AnimationBuilder::end`].
+    ///
+    /// [`SurfaceBuilder::build`]: crate::builder::SurfaceBuilder::build
+    /// [`AnimationBuilder::end`]: crate::builder::AnimationBuilder::end
+    pub fn build(self) -> Result<SurfaceBody, BuildError> {
+        self.end().build()
+    }
+}
+
+impl SurfaceBody {
+    /// Writes the body as a block of `surfaceN` headers of `id_spec`, in the line ending and indentation of `style`.
+    ///
+    /// Elements, collisions, and intervals and patterns of animations are written in order.
+    /// Other definitions are not. Bodies given by [`SurfaceBuilder::build`] are parsed back
+    /// into the same definitions, with indentation only under [`ParseOptions::trim_whitespace`].
+    /// An empty `id_spec` gives an empty header, which is not read.
+    ///
+    /// [`SurfaceBuilder::build`]: crate::builder::SurfaceBuilder::build
+    /// [`ParseOptions::trim_whitespace`]: crate::options::ParseOptions::trim_whitespace
+    pub fn to_block_text(&self, id_spec: &IdList, style: &FormatStyle) -> String {
+        let ending = match style.line_ending {
+            LineEnding::None => LineEnding::CrLf,
+            ending => ending,
+        }
+        .as_str();
+
+        let mut out = String::new();
+        for (i, id) in id_spec.0.iter().enumerate() {
+            let separator = if i > 0 { "," } else { "" };
+            let _ = write!(out, "{}surface{}", separator, id);
+        }
+        out.push_str(ending);
+        out.push('{');
+        out.push_str(ending);
+
+        let mut line = |args: fmt::Arguments| {
+            let _ = write!(out, "{}{}{}", style.indent, args, ending);
+        };
+        for e in self.elements.iter() {
+            match e.alpha {
+                Some(alpha) => line(format_args!(
+                    "element{},{},{},{},{}",
+                    e.id, e.method, e.filename, e.offset, alpha
+                )),
+                None => line(format_args!(
+                    "element{},{},{},{}",
+                    e.id, e.method, e.filename, e.offset
+                )),
+            }
+        }
+        for c in self.collisions.iter() {
+            line(format_args!("collision{},{},{}", c.id, c.rect, c.name));
+        }
+        for animation in self.animations.values() {
+            if let Some(intervals) = &animation.intervals {
+                line(format_args!(
+                    "animation{}.interval,{}",
+                    animation.id, intervals
+                ));
+            }
+            for (index, pattern) in animation.patterns.iter() {
+                line(format_args!(
+                    "animation{}.pattern{},{}",
+                    animation.id, index, pattern
+                ));
+            }
+        }
+
+        out.push('}');
+        out.push_str(ending);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::{
+        block::parse_blocks,
+        options::ParseOptions,
+        seriko::{IdGroup, IdGroupDelimiter, IdGroupSeparator, Wait},
+        surfaces::{parse_block_body, parse_block_body_lenient_with},
+    };
+
+    fn overlay(surface: i32, alpha: Option<u8>) -> Pattern {
+        Pattern::Draw {
+            method: DrawMethod::Overlay,
+            surface,
+            wait: Wait::Range(50, 100),
+            offset: Coordinate { x: -5, y: 10 },
+            alpha,
+        }
+    }
+
+    mod to_block_text {
+        use super::*;
+
+        #[test]
+        fn success_when_re_parsed() {
+            let body = SurfaceBuilder::new()
+                .element(DrawMethod::Base, "body.png", 0, 0)
+                .element_at(3, DrawMethod::Overlay, "parts/arm.png", -10, 20)
+                .collision(Rect::new(10, 10, 50, 50), "Head")
+                .collision(Rect::new(0, 60, 40, 90), "Bust, left")
+                .animation(1)
+                .interval(Interval::Bind)
+                .interval(Interval::Random(3))
+                .pattern(overlay(100, Some(128)))
+                .pattern(Pattern::AlternativeStart(IdGroup {
+                    ids: vec![2, 4],
+                    delimiter: IdGroupDelimiter::Bracket,
+                    separator: IdGroupSeparator::Dot,
+                }))
+                .end()
+                .animation(2)
+                .interval(Interval::Never)
+                .pattern(Pattern::Start(1))
+                .build()
+                .unwrap();
+
+            let text = body.to_block_text(&IdList(vec![0, 5]), &FormatStyle::DEFAULT);
+            let blocks = parse_blocks(&text).unwrap();
+            assert_eq!(blocks.len(), 1);
+            assert_eq!(blocks[0].header, "surface0,surface5");
+            assert_eq!(parse_block_body(&blocks[0].body).unwrap(), body);
+
+            let style = FormatStyle::DEFAULT
+                .line_ending(LineEnding::Lf)
+                .indent("  ");
+            let text = body.to_block_text(&IdList(vec![0]), &style);
+            let blocks = parse_blocks(&text).unwrap();
+            let (parsed, warnings) =
+                parse_block_body_lenient_with(&blocks[0].body, &ParseOptions::COMPATIBLE);
+            assert!(warnings.is_empty());
+            assert_eq!(parsed, body);
+        }
+
+        #[test]
+        fn success_when_continuing_animation() {
+            let body = SurfaceBuilder::new()
+                .animation(0)
+                .interval(Interval::Always)
+                .pattern(overlay(10, None))
+                .end()
+                .element(DrawMethod::Base, "a.png", 0, 0)
+                .animation(0)
+                .pattern(overlay(11, None))
+                .build()
+                .unwrap();
+
+            let patterns = &body.animations[&0].patterns;
+            assert_eq!(patterns.keys().copied().collect::<Vec<_>>(), vec![0, 1]);
+            assert_eq!(
+                body.to_block_text(&IdList(vec![0]), &FormatStyle::DEFAULT),
+                "surface0\r\n{\r\nelement0,base,a.png,0,0\r\nanimation0.interval,always\r\n\
+                 animation0.pattern0,overlay,10,50-100,-5,10\r\nanimation0.pattern1,overlay,11,50-100,-5,10\r\n}\r\n"
+            );
+        }
+    }
+
+    mod build {
+        use super::*;
+
+        #[test]
+        fn failed_when_impossible() {
+            let cases = [
+                (
+                    SurfaceBuilder::new()
+                        .animation(4)
+                        .pattern(Pattern::Stop(1))
+                        .end(),
+                    BuildError::PatternWithoutInterval(4),
+                ),
+                (
+                    SurfaceBuilder::new()
+                        .element(DrawMethod::Base, "a.png", 0, 0)
+                        .element_at(0, DrawMethod::Overlay, "b.png", 0, 0),
+                    BuildError::DuplicateElement(0),
+                ),
+                (
+                    SurfaceBuilder::new().element(DrawMethod::Base, "a,b.png", 0, 0),
+                    BuildError::InvalidFilename("a,b.png".to_string()),
+                ),
+                (
+                    SurfaceBuilder::new().collision(Rect::new(0, 0, 1, 1), "Head\r\n"),
+                    BuildError::InvalidName("Head\r\n".to_string()),
+                ),
+                (
+                    SurfaceBuilder::new()
+                        .animation(1)
+                        .interval(Interval::Sometimes)
+                        .pattern(overlay(1, None))
+                        .pattern(Pattern::Draw {
+                            method: DrawMethod::Base,
+                            surface: 1,
+                            wait: Wait::Fixed(0),
+                            offset: Coordinate::default(),
+                            alpha: Some(10),
+                        })
+                        .end(),
+                    BuildError::UnwritablePattern {
+                        animation: 1,
+                        pattern: 1,
+                    },
+                ),
+            ];
+            for (builder, expected) in cases {
+                assert_eq!(builder.build().unwrap_err(), expected);
+            }
+        }
+
+        #[test]
+        fn success_when_interval_without_pattern() {
+            let surface = SurfaceBuilder::new()
+                .animation(0)
+                .interval(Interval::Bind)
+                .end()
+                .build_surface(7)
+                .unwrap();
+            assert_eq!(surface.id, 7);
+            assert!(surface.animations.get(0).unwrap().is_bind());
+        }
+    }
+}
@@ -6,6 +6,8 @@
 //! ## Example
 //!
 //! ```
+//! # #[cfg(feature = "encodings")]
+//! # fn main() {
 //! use encoding_rs::SHIFT_JIS;
 //!
 //! use shell_parser_common_rs::charset::parse_charset;
@@ -31,16 +33,49 @@
 //! };
 //!
 //! assert_eq!(result, case_raw);
+//! # }
+//! # #[cfg(not(feature = "encodings"))]
+//! # fn main() {}
 //! ```
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
 
-use nom::{branch::alt, bytes::complete::tag, combinator::map, IResult};
+use nom::{
+    bytes::complete::{is_not, tag},
+    character::complete::{char, space0},
+    combinator::{all_consuming, map, map_opt, opt},
+    error::ContextError,
+    sequence::{preceded, tuple},
+    IResult,
+};
 
-use crate::ShellParseError;
+use crate::{
+    error::Located,
+    kv::parse_key_value_with,
+    options::{is_control_char, ControlCharPolicy, ParseOptions},
+    value::tag_table,
+    ShellParseError,
+};
+
+#[cfg(feature = "encodings")]
+mod cp932;
+#[cfg(feature = "encodings")]
+mod custom;
+mod decoded_lines;
+#[cfg(feature = "encodings")]
+mod transcode;
+
+#[cfg(feature = "encodings")]
+pub use cp932::{scan_cp932_extensions, ExtensionKind, ExtensionUse};
+#[cfg(feature = "encodings")]
+pub use custom::{parse_charset_extended, AnyCharset, CodecError, CustomCharset, Decodable};
+pub use decoded_lines::DecodedLines;
+#[cfg(feature = "encodings")]
+pub use transcode::{transcode, transcode_with, TranscodeError};
 
 /// `Charset` type.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Charset {
     ASCII,
     ShiftJIS,
@@ -50,67 +85,957 @@ pub enum Charset {
     Default,
 }
 
+/// Byte order mark, which some editors write at the start of UTF-8 files.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// Byte order mark at the start of bytes, naming the encoding they are in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bom {
+    /// `EF BB BF`
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-8"))]
+    Utf8,
+    /// `FF FE`
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-16LE"))]
+    Utf16Le,
+    /// `FE FF`
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-16BE"))]
+    Utf16Be,
+}
+
+impl Bom {
+    /// Returns the byte order mark at the start of `input`.
+    pub fn sniff(input: &[u8]) -> Option<Bom> {
+        match input {
+            [0xEF, 0xBB, 0xBF, ..] => Some(Bom::Utf8),
+            [0xFF, 0xFE, ..] => Some(Bom::Utf16Le),
+            [0xFE, 0xFF, ..] => Some(Bom::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bytes of the mark.
+    pub fn byte_len(&self) -> usize {
+        match self {
+            Bom::Utf8 => 3,
+            Bom::Utf16Le | Bom::Utf16Be => 2,
+        }
+    }
+
+    /// Returns the encoding the mark names.
+    #[cfg(feature = "encodings")]
+    pub(crate) fn encoding(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Bom::Utf8 => encoding_rs::UTF_8,
+            Bom::Utf16Le => encoding_rs::UTF_16LE,
+            Bom::Utf16Be => encoding_rs::UTF_16BE,
+        }
+    }
+}
+
+impl fmt::Display for Bom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Bom::Utf8 => "UTF-8",
+            Bom::Utf16Le => "UTF-16LE",
+            Bom::Utf16Be => "UTF-16BE",
+        })
+    }
+}
+
+/// Names of charsets read by [`parse_charset`].
+///
+/// [`parse_charset`]: crate::charset::parse_charset
+const CHARSETS: &[(&str, Charset)] = &[
+    ("ASCII", Charset::ASCII),
+    ("Shift_JIS", Charset::ShiftJIS),
+    ("ISO-2022-JP", Charset::ISO2022JP),
+    ("EUC-JP", Charset::EUCJP),
+    ("UTF-8", Charset::UTF8),
+];
+
 /// parser for [`Charset`].
 ///
+/// Fails with the context `charset:empty` when the name is empty or only whitespace,
+/// such as `charset,` at the end of a line, and with `charset:unknown` otherwise,
+/// where the input of the error is the name up to `,`, whitespace or the line ending.
+///
 /// [`Charset`]: crate::charset::Charset
-pub fn parse_charset<'a>(input: &'a str) -> IResult<&'a str, Charset, ShellParseError> {
-    alt((
-        charset_ascii,
-        charset_shift_jis,
-        charset_iso_2022_jp,
-        charset_euc_jp,
-        charset_utf_8,
-    ))(input)
+pub fn parse_charset(input: &str) -> IResult<&str, Charset, ShellParseError<'_>> {
+    tag_table(CHARSETS)(input).map_err(|e| e.map(|e| charset_error(input, e)))
 }
 
-fn charset_ascii<'a>(input: &'a str) -> IResult<&'a str, Charset, ShellParseError> {
-    map(tag("ASCII"), |_| Charset::ASCII)(input)
+/// Adds `charset:empty` or `charset:unknown` to `error` of the name at the start of `input`.
+fn charset_error<'a>(input: &'a str, mut error: ShellParseError<'a>) -> ShellParseError<'a> {
+    let rest = input.trim_start_matches([' ', '\t']);
+    let name = &rest[..rest
+        .find([',', ' ', '\t', '\r', '\n'])
+        .unwrap_or(rest.len())];
+    if name.is_empty() {
+        return ShellParseError::add_context(input, "charset:empty", error);
+    }
+
+    for (i, _) in error.errors.iter_mut() {
+        if *i == input {
+            *i = name;
+        }
+    }
+    ShellParseError::add_context(name, "charset:unknown", error)
 }
 
-fn charset_shift_jis<'a>(input: &'a str) -> IResult<&'a str, Charset, ShellParseError> {
-    map(tag("Shift_JIS"), |_| Charset::ShiftJIS)(input)
+/// `charset,XXX` line, with what preceded the key.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CharsetDirective {
+    pub charset: Charset,
+    /// Whether the line starts with U+FEFF, a byte order mark.
+    pub bom: bool,
+    /// Whether spaces or tabs precede the key.
+    pub indented: bool,
 }
 
-fn charset_iso_2022_jp<'a>(input: &'a str) -> IResult<&'a str, Charset, ShellParseError> {
-    map(tag("ISO-2022-JP"), |_| Charset::ISO2022JP)(input)
+impl CharsetDirective {
+    /// Checks whether a byte order mark precedes a charset other than [`Charset::UTF8`],
+    /// which are inconsistent.
+    ///
+    /// [`Charset::UTF8`]: crate::charset::Charset::UTF8
+    pub fn bom_mismatch(&self) -> bool {
+        self.bom && self.charset != Charset::UTF8
+    }
 }
 
-fn charset_euc_jp<'a>(input: &'a str) -> IResult<&'a str, Charset, ShellParseError> {
-    map(tag("EUC-JP"), |_| Charset::EUCJP)(input)
+/// parser for [`CharsetDirective`], the `charset,XXX` line.
+///
+/// An optional U+FEFF and spaces or tabs before the key are skipped, which [`parse_charset`] does not.
+///
+/// [`CharsetDirective`]: crate::charset::CharsetDirective
+/// [`parse_charset`]: crate::charset::parse_charset
+pub fn parse_charset_directive(
+    input: &str,
+) -> IResult<&str, CharsetDirective, ShellParseError<'_>> {
+    map(
+        tuple((
+            opt(char(BOM)),
+            space0,
+            preceded(tag("charset,"), parse_charset),
+        )),
+        |(bom, indent, charset)| CharsetDirective {
+            charset,
+            bom: bom.is_some(),
+            indented: !indent.is_empty(),
+        },
+    )(input)
 }
 
-fn charset_utf_8<'a>(input: &'a str) -> IResult<&'a str, Charset, ShellParseError> {
-    map(tag("UTF-8"), |_| Charset::UTF8)(input)
+/// Same as [`parse_charset`], with the lenient behaviors of `options`.
+///
+/// With [`ParseOptions::charset_aliases`], the name is read to whitespace or the line ending.
+/// Errors have the contexts of [`parse_charset`] either way.
+///
+/// [`parse_charset`]: crate::charset::parse_charset
+/// [`ParseOptions::charset_aliases`]: crate::options::ParseOptions::charset_aliases
+pub fn parse_charset_with<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, Charset, ShellParseError<'a>> {
+    if options.charset_aliases {
+        map_opt(is_not(" \t\r\n"), charset_of_alias)(input)
+            .map_err(|e| e.map(|e| charset_error(input, e)))
+    } else {
+        parse_charset(input)
+    }
+}
+
+/// Aliases of charsets in lowercase, without `-` and `_`.
+const CHARSET_ALIASES: &[(&str, Charset)] = &[
+    ("ascii", Charset::ASCII),
+    ("usascii", Charset::ASCII),
+    ("shiftjis", Charset::ShiftJIS),
+    ("sjis", Charset::ShiftJIS),
+    ("xsjis", Charset::ShiftJIS),
+    ("mskanji", Charset::ShiftJIS),
+    ("windows31j", Charset::ShiftJIS),
+    ("cp932", Charset::ShiftJIS),
+    ("iso2022jp", Charset::ISO2022JP),
+    ("csiso2022jp", Charset::ISO2022JP),
+    ("eucjp", Charset::EUCJP),
+    ("xeucjp", Charset::EUCJP),
+    ("utf8", Charset::UTF8),
+];
+
+/// Returns the charset of `name`, ignoring ASCII case, `-` and `_`, without allocation.
+fn charset_of_alias(name: &str) -> Option<Charset> {
+    let normalized = || {
+        name.bytes()
+            .filter(|b| *b != b'-' && *b != b'_')
+            .map(|b| b.to_ascii_lowercase())
+    };
+
+    CHARSET_ALIASES
+        .iter()
+        .find(|(alias, _)| normalized().eq(alias.bytes()))
+        .map(|(_, charset)| charset.clone())
 }
 
 impl Charset {
     /// Decodes bytes of the type it represents into `Cow<'a, str>`.
-    pub fn decode<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, ()> {
-        let decoder = match self {
-            Charset::ASCII => encoding_rs::UTF_8,
-            Charset::ShiftJIS => encoding_rs::SHIFT_JIS,
-            Charset::ISO2022JP => encoding_rs::ISO_2022_JP,
-            Charset::EUCJP => encoding_rs::EUC_JP,
-            Charset::UTF8 => encoding_rs::UTF_8,
-            Charset::Default => {
-                let os_str = unsafe { std::ffi::OsStr::from_encoded_bytes_unchecked(input) };
-                return Ok(os_str.to_string_lossy());
+    ///
+    /// The byte order mark at the start, if any, is checked against the charset:
+    ///
+    /// | bytes | result |
+    /// |---|---|
+    /// | without a BOM | decoded as they are |
+    /// | with the UTF-8 BOM, for [`Charset::UTF8`] and [`Charset::ASCII`] | decoded without the BOM |
+    /// | with another BOM, such as of UTF-16 | [`DecodeError::BomMismatch`] |
+    ///
+    /// A BOM never switches the encoding. [`Charset::Default`] is decoded as the platform does, without checking the BOM.
+    ///
+    /// Without the `encodings` feature, only [`Charset::ASCII`], [`Charset::UTF8`] and [`Charset::Default`] are decoded,
+    /// except empty bytes.
+    ///
+    /// [`Charset::ASCII`]: crate::charset::Charset::ASCII
+    /// [`Charset::UTF8`]: crate::charset::Charset::UTF8
+    /// [`Charset::Default`]: crate::charset::Charset::Default
+    /// [`DecodeError::BomMismatch`]: crate::charset::DecodeError::BomMismatch
+    pub fn decode<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, DecodeError> {
+        if input.is_empty() {
+            return Ok(Cow::Borrowed(""));
+        }
+        if *self == Charset::Default {
+            return Ok(decode_lossy(input));
+        }
+
+        #[cfg(feature = "encodings")]
+        {
+            let encoding = self.encoding().unwrap_or(encoding_rs::UTF_8);
+            decode_strict(encoding, input).map_err(|e| match e {
+                StrictError::Bom(bom) => DecodeError::BomMismatch {
+                    declared: self.clone(),
+                    bom,
+                },
+                StrictError::Malformed(offset) => DecodeError::malformed(self, input, offset),
+            })
+        }
+
+        #[cfg(not(feature = "encodings"))]
+        {
+            if !self.is_utf_8() {
+                return Err(DecodeError::EncodingSupportDisabled(self.clone()));
             }
+            let skipped = match Bom::sniff(input) {
+                None => 0,
+                Some(Bom::Utf8) => Bom::Utf8.byte_len(),
+                Some(bom) => {
+                    return Err(DecodeError::BomMismatch {
+                        declared: self.clone(),
+                        bom,
+                    })
+                }
+            };
+            core::str::from_utf8(&input[skipped..])
+                .map(Cow::Borrowed)
+                .map_err(|e| DecodeError::malformed(self, input, skipped + e.valid_up_to()))
+        }
+    }
+
+    /// Same as [`Charset::decode`], with control characters handled by [`ParseOptions::control_chars`].
+    ///
+    /// [`Charset::decode`]: crate::charset::Charset::decode
+    /// [`ParseOptions::control_chars`]: crate::options::ParseOptions::control_chars
+    pub fn decode_with<'a>(
+        &self,
+        input: &'a [u8],
+        options: &ParseOptions,
+    ) -> Result<Cow<'a, str>, DecodeError> {
+        let text = self.decode(input)?;
+        let Some(offset) = text.find(is_control_char) else {
+            return Ok(text);
         };
 
-        let (cow, encoding_used, had_errors) = decoder.decode(input);
-        if had_errors || encoding_used != decoder {
-            Err(())
+        match options.control_chars {
+            ControlCharPolicy::Keep => Ok(text),
+            ControlCharPolicy::Strip => Ok(Cow::Owned(text.replace(is_control_char, ""))),
+            ControlCharPolicy::Reject => Err(DecodeError::ControlCharacter {
+                character: text[offset..].chars().next().unwrap_or_default(),
+                byte_offset: offset,
+                line: memchr::memchr_iter(b'\n', &text.as_bytes()[..offset]).count() + 1,
+            }),
+        }
+    }
+
+    /// Returns the charset for a WHATWG label, such as `csshiftjis`, from HTTP headers or HTML.
+    ///
+    /// Returns `None` for labels of encodings other than the charsets, such as `ISO-8859-5`.
+    /// `ascii` is a label of `windows-1252`, so it is `None` too.
+    #[cfg(feature = "encodings")]
+    pub fn from_label(label: &[u8]) -> Option<Charset> {
+        let encoding = encoding_rs::Encoding::for_label(label)?;
+        if encoding == encoding_rs::SHIFT_JIS {
+            Some(Charset::ShiftJIS)
+        } else if encoding == encoding_rs::ISO_2022_JP {
+            Some(Charset::ISO2022JP)
+        } else if encoding == encoding_rs::EUC_JP {
+            Some(Charset::EUCJP)
+        } else if encoding == encoding_rs::UTF_8 {
+            Some(Charset::UTF8)
         } else {
-            Ok(cow)
+            None
+        }
+    }
+
+    /// Returns the WHATWG name of the encoding, or `None` for [`Charset::ASCII`] and [`Charset::Default`].
+    ///
+    /// [`Charset::ASCII`]: crate::charset::Charset::ASCII
+    /// [`Charset::Default`]: crate::charset::Charset::Default
+    pub fn whatwg_name(&self) -> Option<&'static str> {
+        match self {
+            Charset::ShiftJIS => Some("Shift_JIS"),
+            Charset::ISO2022JP => Some("ISO-2022-JP"),
+            Charset::EUCJP => Some("EUC-JP"),
+            Charset::UTF8 => Some("UTF-8"),
+            Charset::ASCII | Charset::Default => None,
+        }
+    }
+
+    /// Returns the rank of the charset among candidates decoding the same bytes, higher first.
+    ///
+    /// | charset | priority |
+    /// |---|---|
+    /// | [`Charset::UTF8`] | 5 |
+    /// | [`Charset::ShiftJIS`] | 4 |
+    /// | [`Charset::EUCJP`] | 3 |
+    /// | [`Charset::ISO2022JP`] | 2 |
+    /// | [`Charset::ASCII`] | 1 |
+    /// | [`Charset::Default`] | 0 |
+    ///
+    /// The values are part of the API, used by [`decode_with_fallbacks`] and the order of `Charset`, so detectors outside the crate rank alike.
+    ///
+    /// [`Charset::UTF8`]: crate::charset::Charset::UTF8
+    /// [`Charset::ShiftJIS`]: crate::charset::Charset::ShiftJIS
+    /// [`Charset::EUCJP`]: crate::charset::Charset::EUCJP
+    /// [`Charset::ISO2022JP`]: crate::charset::Charset::ISO2022JP
+    /// [`Charset::ASCII`]: crate::charset::Charset::ASCII
+    /// [`Charset::Default`]: crate::charset::Charset::Default
+    /// [`decode_with_fallbacks`]: crate::charset::decode_with_fallbacks
+    pub fn detection_priority(&self) -> u8 {
+        match self {
+            Charset::UTF8 => 5,
+            Charset::ShiftJIS => 4,
+            Charset::EUCJP => 3,
+            Charset::ISO2022JP => 2,
+            Charset::ASCII => 1,
+            Charset::Default => 0,
+        }
+    }
+
+    /// Returns the encoding to decode with, or `None` for [`Charset::Default`].
+    ///
+    /// [`Charset::Default`]: crate::charset::Charset::Default
+    #[cfg(feature = "encodings")]
+    pub(crate) fn encoding(&self) -> Option<&'static encoding_rs::Encoding> {
+        match self {
+            Charset::ASCII => Some(encoding_rs::UTF_8),
+            Charset::ShiftJIS => Some(encoding_rs::SHIFT_JIS),
+            Charset::ISO2022JP => Some(encoding_rs::ISO_2022_JP),
+            Charset::EUCJP => Some(encoding_rs::EUC_JP),
+            Charset::UTF8 => Some(encoding_rs::UTF_8),
+            Charset::Default => None,
+        }
+    }
+
+    /// Returns whether it is decoded as UTF-8 without the `encodings` feature.
+    #[cfg(not(feature = "encodings"))]
+    pub(crate) fn is_utf_8(&self) -> bool {
+        matches!(self, Charset::ASCII | Charset::UTF8)
+    }
+}
+
+/// Ordered by [`Charset::detection_priority`], so the preferred charset is the greatest.
+///
+/// [`Charset::detection_priority`]: crate::charset::Charset::detection_priority
+impl PartialOrd for Charset {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Charset {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.detection_priority().cmp(&other.detection_priority())
+    }
+}
+
+#[cfg(not(feature = "encodings"))]
+pub(crate) const UTF_8_BOM: &[u8] = b"\xef\xbb\xbf";
+
+/// Returns a decoder for `charset`, removing the BOM.
+///
+/// [`Charset::Default`] is decoded as UTF-8.
+///
+/// [`Charset::Default`]: crate::charset::Charset::Default
+#[cfg(feature = "encodings")]
+pub(crate) fn new_decoder(charset: &Charset) -> encoding_rs::Decoder {
+    charset
+        .encoding()
+        .unwrap_or(encoding_rs::UTF_8)
+        .new_decoder_with_bom_removal()
+}
+
+/// Decodes `src` into `dst` and returns the number of bytes read,
+/// or the offset in `src` of the malformed sequence.
+///
+/// Invalid sequences are replaced only for [`Charset::Default`].
+/// `dst` is reserved for all of `src`, so `src` is read to the end unless it is malformed.
+/// A sequence started in the previous `src` is located at the start of `src`.
+///
+/// [`Charset::Default`]: crate::charset::Charset::Default
+#[cfg(feature = "encodings")]
+pub(crate) fn decode_to_string(
+    decoder: &mut encoding_rs::Decoder,
+    charset: &Charset,
+    src: &[u8],
+    dst: &mut String,
+    last: bool,
+) -> Result<usize, usize> {
+    let len = src.len();
+    if *charset == Charset::Default {
+        dst.reserve(decoder.max_utf8_buffer_length(len).unwrap_or(len * 3));
+        let (_, read, _) = decoder.decode_to_string(src, dst, last);
+        Ok(read)
+    } else {
+        dst.reserve(
+            decoder
+                .max_utf8_buffer_length_without_replacement(len)
+                .unwrap_or(len * 3),
+        );
+        match decoder.decode_to_string_without_replacement(src, dst, last) {
+            (encoding_rs::DecoderResult::Malformed(bad, extra), read) => {
+                Err(read.saturating_sub(bad as usize + extra as usize))
+            }
+            (_, read) => Ok(read),
         }
     }
 }
 
+/// Error of [`decode_strict`].
+#[cfg(feature = "encodings")]
+pub(crate) enum StrictError {
+    /// The input starts with the BOM of another encoding.
+    Bom(Bom),
+    /// Offset of the first malformed sequence.
+    Malformed(usize),
+}
+
+/// Decodes `input` in `encoding` without switching the encoding by the BOM,
+/// removing the BOM of `encoding`, or rejecting the BOM of another.
+#[cfg(feature = "encodings")]
+pub(crate) fn decode_strict<'a>(
+    encoding: &'static encoding_rs::Encoding,
+    input: &'a [u8],
+) -> Result<Cow<'a, str>, StrictError> {
+    let bom = Bom::sniff(input);
+    let (cow, had_errors) = match bom {
+        None => encoding.decode_without_bom_handling(input),
+        Some(bom) if bom.encoding() == encoding => encoding.decode_with_bom_removal(input),
+        Some(bom) => return Err(StrictError::Bom(bom)),
+    };
+    if had_errors {
+        Err(StrictError::Malformed(malformed_offset(
+            encoding,
+            input,
+            bom.is_some(),
+        )))
+    } else {
+        Ok(cow)
+    }
+}
+
+/// Encodes `text` into `encoding`, or into ASCII for `None`,
+/// or returns the offset in `text` and the char that cannot be encoded.
+#[cfg(feature = "encodings")]
+pub(crate) fn encode_strict<'a>(
+    encoding: Option<&'static encoding_rs::Encoding>,
+    text: &'a str,
+) -> Result<Cow<'a, [u8]>, (usize, char)> {
+    let Some(encoding) = encoding else {
+        return match text.char_indices().find(|(_, c)| !c.is_ascii()) {
+            Some(unmappable) => Err(unmappable),
+            None => Ok(Cow::Borrowed(text.as_bytes())),
+        };
+    };
+    if encoding == encoding_rs::UTF_8 {
+        return Ok(Cow::Borrowed(text.as_bytes()));
+    }
+
+    let mut encoder = encoding.new_encoder();
+    let mut result = alloc::vec::Vec::with_capacity(
+        encoder
+            .max_buffer_length_from_utf8_without_replacement(text.len())
+            .unwrap_or(text.len()),
+    );
+    let mut read = 0;
+    loop {
+        let (status, n) =
+            encoder.encode_from_utf8_to_vec_without_replacement(&text[read..], &mut result, true);
+        read += n;
+        match status {
+            encoding_rs::EncoderResult::InputEmpty => return Ok(Cow::Owned(result)),
+            encoding_rs::EncoderResult::OutputFull => result.reserve(result.capacity().max(16)),
+            encoding_rs::EncoderResult::Unmappable(c) => return Err((read - c.len_utf8(), c)),
+        }
+    }
+}
+
+/// Finds the offset of the first malformed sequence of `input` by decoding it again.
+///
+/// Called only after decoding failed, so that decoding succeeding is not slowed down.
+/// The BOM of `encoding` is skipped when `bom`. Returns 0 when none is found.
+#[cfg(feature = "encodings")]
+fn malformed_offset(encoding: &'static encoding_rs::Encoding, input: &[u8], bom: bool) -> usize {
+    let mut decoder = if bom {
+        encoding.new_decoder_with_bom_removal()
+    } else {
+        encoding.new_decoder_without_bom_handling()
+    };
+    let mut buffer = [0; 1024];
+    let mut offset = 0;
+
+    loop {
+        let (result, read, _) =
+            decoder.decode_to_utf8_without_replacement(&input[offset..], &mut buffer, true);
+        offset += read;
+        match result {
+            encoding_rs::DecoderResult::InputEmpty => return 0,
+            encoding_rs::DecoderResult::OutputFull => {}
+            encoding_rs::DecoderResult::Malformed(bad, extra) => {
+                return offset.saturating_sub(bad as usize + extra as usize);
+            }
+        }
+    }
+}
+
+/// Decodes bytes as the platform does, replacing invalid sequences.
+fn decode_lossy(input: &[u8]) -> Cow<'_, str> {
+    #[cfg(feature = "std")]
+    {
+        let os_str = unsafe { std::ffi::OsStr::from_encoded_bytes_unchecked(input) };
+        os_str.to_string_lossy()
+    }
+    #[cfg(not(feature = "std"))]
+    String::from_utf8_lossy(input)
+}
+
+/// `DecodeError` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodeError {
+    /// The bytes are not valid in the charset.
+    Malformed {
+        charset: Charset,
+        /// Offset of the first malformed sequence.
+        byte_offset: usize,
+        /// 1-based line number of the sequence, counting `\n` bytes before it.
+        line: usize,
+    },
+    /// The charset needs the `encodings` feature.
+    EncodingSupportDisabled(Charset),
+    /// The bytes start with the byte order mark of another encoding than the charset,
+    /// such as UTF-16 in a file of [`Charset::UTF8`].
+    ///
+    /// [`Charset::UTF8`]: crate::charset::Charset::UTF8
+    BomMismatch { declared: Charset, bom: Bom },
+    /// The text has a control character rejected by [`ControlCharPolicy::Reject`].
+    ///
+    /// [`ControlCharPolicy::Reject`]: crate::options::ControlCharPolicy::Reject
+    ControlCharacter {
+        character: char,
+        /// Offset of the character in the decoded text.
+        byte_offset: usize,
+        /// 1-based line number of the character.
+        line: usize,
+    },
+}
+
+impl DecodeError {
+    /// Creates [`DecodeError::Malformed`] at `byte_offset` of `input`.
+    ///
+    /// [`DecodeError::Malformed`]: crate::charset::DecodeError::Malformed
+    pub(crate) fn malformed(charset: &Charset, input: &[u8], byte_offset: usize) -> DecodeError {
+        DecodeError::Malformed {
+            charset: charset.clone(),
+            byte_offset,
+            line: memchr::memchr_iter(b'\n', &input[..byte_offset]).count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Malformed {
+                charset,
+                byte_offset,
+                line,
+            } => write!(
+                f,
+                "malformed bytes for {:?} at line {} (byte {})",
+                charset, line, byte_offset
+            ),
+            DecodeError::EncodingSupportDisabled(charset) => {
+                write!(f, "decoding {:?} needs the `encodings` feature", charset)
+            }
+            DecodeError::BomMismatch { declared, bom } => {
+                write!(f, "byte order mark of {} in a file of {:?}", bom, declared)
+            }
+            DecodeError::ControlCharacter {
+                character,
+                byte_offset,
+                line,
+            } => write!(
+                f,
+                "control character U+{:04X} at line {} (byte {})",
+                *character as u32, line, byte_offset
+            ),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// `EncodeError` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EncodeError {
+    /// The char cannot be represented in the charset.
+    Unmappable(Charset, char),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::Unmappable(charset, c) => {
+                write!(f, "{:?} cannot be encoded in {:?}", c, charset)
+            }
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {}
+
+/// Number of leading bytes examined by [`detect_charset`].
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+pub const DEFAULT_SCAN_LIMIT: usize = 4096;
+
+/// Finds the `charset,XXX` line in raw bytes and returns the charset it names.
+///
+/// Only ASCII bytes are examined, so it works before decoding.
+/// Returns `None` when no line names a known charset.
+/// When lines name different charsets, the first line is taken as baseware does, not ranked by [`Charset::detection_priority`].
+/// Only lines starting within [`DEFAULT_SCAN_LIMIT`] bytes are examined, as [`detect_charset_within`] does.
+///
+/// [`Charset::detection_priority`]: crate::charset::Charset::detection_priority
+/// [`DEFAULT_SCAN_LIMIT`]: crate::charset::DEFAULT_SCAN_LIMIT
+/// [`detect_charset_within`]: crate::charset::detect_charset_within
+pub fn detect_charset(input: &[u8]) -> Option<Charset> {
+    detect_charset_within(input, DEFAULT_SCAN_LIMIT)
+}
+
+/// Same as [`detect_charset`], but examines only lines starting within the first `limit` bytes.
+///
+/// The line straddling the limit is examined to its end.
+/// A `charset` line starting after the limit is treated as absent.
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+pub fn detect_charset_within(input: &[u8], limit: usize) -> Option<Charset> {
+    detect(input, limit, &ParseOptions::STRICT)
+}
+
+/// Same as [`detect_charset`], with the lenient behaviors of `options`,
+/// such as `Charset , sjis` in [`ParseOptions::COMPATIBLE`].
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`ParseOptions::COMPATIBLE`]: crate::options::ParseOptions::COMPATIBLE
+pub fn detect_charset_with(input: &[u8], options: &ParseOptions) -> Option<Charset> {
+    detect(input, DEFAULT_SCAN_LIMIT, options)
+}
+
+fn detect(input: &[u8], limit: usize, options: &ParseOptions) -> Option<Charset> {
+    detect_located(input, limit, options).map(|c| c.value)
+}
+
+/// Same as [`detect_charset_within`], with the line number of the `charset` line.
+///
+/// [`detect_charset_within`]: crate::charset::detect_charset_within
+pub(crate) fn detect_located(
+    input: &[u8],
+    limit: usize,
+    options: &ParseOptions,
+) -> Option<Located<Charset>> {
+    let end = match limit.checked_sub(1).and_then(|last| input.get(last..)) {
+        Some(rest) => memchr::memchr(b'\n', rest).map_or(input.len(), |p| limit + p),
+        None if limit == 0 => 0,
+        None => input.len(),
+    };
+    charset_lines(&input[..end], options).next()
+}
+
+/// Same as [`detect_charset`], returning all lines naming known charsets with their line numbers, in order.
+///
+/// All lines are examined, not only those within [`DEFAULT_SCAN_LIMIT`] bytes,
+/// so that lines conflicting with the first are found wherever they are.
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`DEFAULT_SCAN_LIMIT`]: crate::charset::DEFAULT_SCAN_LIMIT
+pub fn detect_charsets(input: &[u8]) -> Vec<Located<Charset>> {
+    detect_charsets_with(input, &ParseOptions::STRICT)
+}
+
+/// Same as [`detect_charsets`], with the lenient behaviors of `options` as [`detect_charset_with`].
+///
+/// [`detect_charsets`]: crate::charset::detect_charsets
+/// [`detect_charset_with`]: crate::charset::detect_charset_with
+pub fn detect_charsets_with(input: &[u8], options: &ParseOptions) -> Vec<Located<Charset>> {
+    charset_lines(input, options).collect()
+}
+
+fn charset_lines<'a>(
+    input: &'a [u8],
+    options: &'a ParseOptions,
+) -> impl Iterator<Item = Located<Charset>> + 'a {
+    let mut start = 0;
+    let ends = memchr::memchr_iter(b'\n', input).chain(core::iter::once(input.len()));
+    ends.enumerate().filter_map(move |(i, end)| {
+        let line = &input[start..end];
+        start = end + 1;
+        charset_of_line(line, options).map(|charset| Located::new(i + 1, charset))
+    })
+}
+
+/// Warning of [`decode_shell_text`] about `charset` lines after the first, which is used,
+/// or about those past the bytes examined when none is used.
+///
+/// [`decode_shell_text`]: crate::charset::decode_shell_text
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DirectiveWarning {
+    /// Lines naming charsets other than the first, ignored.
+    Conflicting {
+        used: Located<Charset>,
+        others: Vec<Located<Charset>>,
+    },
+    /// Lines naming the charset of the first again, with their line numbers.
+    Duplicate {
+        used: Located<Charset>,
+        lines: Vec<usize>,
+    },
+    /// Lines starting after [`DEFAULT_SCAN_LIMIT`] bytes, ignored as [`detect_charset`] does,
+    /// when none starts before.
+    ///
+    /// [`DEFAULT_SCAN_LIMIT`]: crate::charset::DEFAULT_SCAN_LIMIT
+    /// [`detect_charset`]: crate::charset::detect_charset
+    Late { directives: Vec<Located<Charset>> },
+}
+
+impl fmt::Display for DirectiveWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirectiveWarning::Conflicting { used, others } => {
+                write!(
+                    f,
+                    "charset {:?} of line {} used, ignoring",
+                    used.value, used.line
+                )?;
+                for (i, other) in others.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{:?} of line {}", separator, other.value, other.line)?;
+                }
+                Ok(())
+            }
+            DirectiveWarning::Duplicate { used, lines } => {
+                write!(
+                    f,
+                    "charset {:?} of line {} given again in line",
+                    used.value, used.line
+                )?;
+                for (i, line) in lines.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", separator, line)?;
+                }
+                Ok(())
+            }
+            DirectiveWarning::Late { directives } => {
+                write!(
+                    f,
+                    "charset lines after the first {} bytes ignored:",
+                    DEFAULT_SCAN_LIMIT
+                )?;
+                for (i, directive) in directives.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(
+                        f,
+                        "{}{:?} of line {}",
+                        separator, directive.value, directive.line
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Text decoded by [`decode_shell_text`], with the `charset` lines found.
+///
+/// [`decode_shell_text`]: crate::charset::decode_shell_text
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ShellText {
+    /// Charset used to decode.
+    pub charset: Charset,
+    pub text: String,
+    /// Lines naming known charsets, in order, as [`detect_charsets`] gives.
+    ///
+    /// [`detect_charsets`]: crate::charset::detect_charsets
+    pub directives: Vec<Located<Charset>>,
+    /// Conflicting lines first, then duplicate ones, each kind given when there are such lines,
+    /// or the late lines alone.
+    pub warnings: Vec<DirectiveWarning>,
+}
+
+/// Decodes `input` in the charset detected by [`detect_charset`], or in `fallback` when there is none,
+/// with warnings of the other `charset` lines, found in all of `input`.
+///
+/// Lines naming other charsets are [`DirectiveWarning::Conflicting`],
+/// and those naming the same charset again [`DirectiveWarning::Duplicate`].
+/// When all lines start after [`DEFAULT_SCAN_LIMIT`] bytes, they are [`DirectiveWarning::Late`].
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`DirectiveWarning::Conflicting`]: crate::charset::DirectiveWarning::Conflicting
+/// [`DirectiveWarning::Duplicate`]: crate::charset::DirectiveWarning::Duplicate
+/// [`DEFAULT_SCAN_LIMIT`]: crate::charset::DEFAULT_SCAN_LIMIT
+/// [`DirectiveWarning::Late`]: crate::charset::DirectiveWarning::Late
+pub fn decode_shell_text(input: &[u8], fallback: Charset) -> Result<ShellText, DecodeError> {
+    let used = detect_located(input, DEFAULT_SCAN_LIMIT, &ParseOptions::STRICT);
+    let directives = detect_charsets(input);
+    let mut warnings = Vec::new();
+    if let Some(used) = &used {
+        // The line used is the first of all, as it starts within the limit.
+        let rest = &directives[1..];
+        let (same, others): (Vec<_>, Vec<_>) =
+            rest.iter().cloned().partition(|d| d.value == used.value);
+        if !others.is_empty() {
+            warnings.push(DirectiveWarning::Conflicting {
+                used: used.clone(),
+                others,
+            });
+        }
+        if !same.is_empty() {
+            warnings.push(DirectiveWarning::Duplicate {
+                used: used.clone(),
+                lines: same.iter().map(|d| d.line).collect(),
+            });
+        }
+    } else if !directives.is_empty() {
+        warnings.push(DirectiveWarning::Late {
+            directives: directives.clone(),
+        });
+    }
+
+    let charset = used.map_or(fallback, |d| d.value);
+    let text = charset.decode(input)?.into_owned();
+    Ok(ShellText {
+        charset,
+        text,
+        directives,
+        warnings,
+    })
+}
+
+/// Decodes `input` in the charset detected by [`detect_charset`], or else in the first of `fallbacks` decoding it,
+/// and returns the charset with the text.
+///
+/// Fallbacks are tried in the order of [`Charset::detection_priority`], so of fallbacks all decoding `input`,
+/// the one of the highest priority is taken whatever order they are given in.
+/// The detected charset is not replaced by fallbacks; when it fails, the error is returned.
+/// When all fallbacks fail, the error of the first tried is returned.
+/// With no fallbacks, `input` is decoded as [`Charset::Default`].
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`Charset::detection_priority`]: crate::charset::Charset::detection_priority
+/// [`Charset::Default`]: crate::charset::Charset::Default
+pub fn decode_with_fallbacks<'a>(
+    input: &'a [u8],
+    fallbacks: &[Charset],
+) -> Result<(Charset, Cow<'a, str>), DecodeError> {
+    if let Some(charset) = detect_charset(input) {
+        let text = charset.decode(input)?;
+        return Ok((charset, text));
+    }
+
+    let mut fallbacks = fallbacks.to_vec();
+    if fallbacks.is_empty() {
+        fallbacks.push(Charset::Default);
+    }
+    fallbacks.sort_by(|a, b| b.cmp(a));
+    let mut first_error = None;
+    for charset in fallbacks {
+        match charset.decode(input) {
+            Ok(text) => return Ok((charset, text)),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(first_error.expect("fallbacks are not empty"))
+}
+
+/// Reads the file at `path` and decodes it as [`Charset::decode`] does,
+/// with the charset detected by [`detect_charset`], or `fallback`.
+///
+/// A decode error is returned as [`std::io::ErrorKind::InvalidData`] carrying the [`DecodeError`].
+///
+/// [`Charset::decode`]: crate::charset::Charset::decode
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`DecodeError`]: crate::charset::DecodeError
+#[cfg(feature = "std")]
+pub fn decode_path(
+    path: &std::path::Path,
+    fallback: Charset,
+) -> std::io::Result<(String, Charset)> {
+    let bytes = std::fs::read(path)?;
+    let charset = detect_charset(&bytes).unwrap_or(fallback);
+    let text = charset
+        .decode(&bytes)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        .into_owned();
+
+    Ok((text, charset))
+}
+
+fn charset_of_line(line: &[u8], options: &ParseOptions) -> Option<Charset> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let line = line.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(line);
+    let start = line.iter().position(|b| !b.is_ascii_whitespace())?;
+    let line = &line[start..];
+    let key = line.get(.."charset".len())?;
+    if !(key == b"charset" || options.case_insensitive_keys && key.eq_ignore_ascii_case(b"charset"))
+    {
+        return None;
+    }
+
+    let line = core::str::from_utf8(line).ok()?;
+    let (_, kv) = all_consuming(|input| parse_key_value_with(input, options))(line).ok()?;
+    if !options.key_eq(kv.key, "charset") {
+        return None;
+    }
+
+    all_consuming(|input| parse_charset_with(input, options))(kv.value.trim())
+        .ok()
+        .map(|(_, c)| c)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::error::ParseFailure;
+
     mod parse_charset {
         use super::*;
 
@@ -155,10 +1080,595 @@ mod tests {
         }
 
         #[test]
+        #[cfg(feature = "encodings")]
         fn failed_when_invalid_str() {
             let case = "x76";
             assert!(parse_charset(case).is_err());
         }
+
+        #[test]
+        fn failed_when_empty() {
+            for case in ["", " \t\r\n", ",x"] {
+                let failure = ParseFailure::from(parse_charset(case).unwrap_err());
+                assert_eq!(failure.contexts(), ["charset:empty"], "{:?}", case);
+                assert!(failure.expected().contains(&"UTF-8"));
+            }
+        }
+
+        #[test]
+        fn failed_when_unknown_label() {
+            let failure = ParseFailure::from(parse_charset("UTF-9,x\r\n").unwrap_err());
+            assert_eq!(failure.contexts(), ["charset:unknown"]);
+            assert_eq!(
+                failure,
+                ParseFailure::Syntax {
+                    contexts: vec!["charset:unknown"],
+                    fragment: "UTF-9".to_string(),
+                    expected: failure.expected().to_vec(),
+                }
+            );
+
+            let options = ParseOptions {
+                charset_aliases: true,
+                ..ParseOptions::STRICT
+            };
+            let failure =
+                ParseFailure::from(parse_charset_with("utf9 \r\n", &options).unwrap_err());
+            assert_eq!(failure.contexts(), ["charset:unknown"]);
+            let failure = ParseFailure::from(parse_charset_with("\r\n", &options).unwrap_err());
+            assert_eq!(failure.contexts(), ["charset:empty"]);
+        }
+
+        #[test]
+        fn success_when_label_starts_another() {
+            for (label, charset) in CHARSETS.iter() {
+                for (shorter, _) in CHARSETS.iter().filter(|(s, _)| s != label) {
+                    if label.starts_with(shorter) {
+                        assert_eq!(
+                            parse_charset(label),
+                            Ok(("", charset.clone())),
+                            "{} cut by {}",
+                            label,
+                            shorter
+                        );
+                    }
+                }
+            }
+            for (i, (alias, _)) in CHARSET_ALIASES.iter().enumerate() {
+                assert!(
+                    CHARSET_ALIASES[..i].iter().all(|(other, _)| other != alias),
+                    "{} given twice",
+                    alias
+                );
+            }
+        }
+
+        #[test]
+        fn success_when_label_followed_by_continuation() {
+            for (label, charset) in CHARSETS.iter() {
+                for rest in ["-LE", "-BE", "_JIS", "8", "16", "-2", ",x", " \r\n"] {
+                    let case = format!("{}{}", label, rest);
+                    assert_eq!(
+                        parse_charset(&case),
+                        Ok((rest, charset.clone())),
+                        "{:?}",
+                        case
+                    );
+                }
+            }
+        }
+    }
+
+    mod parse_charset_directive {
+        use super::*;
+
+        #[test]
+        fn success_when_bom() {
+            let (remain, result) = parse_charset_directive("\u{feff}charset,UTF-8\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                CharsetDirective {
+                    charset: Charset::UTF8,
+                    bom: true,
+                    indented: false,
+                }
+            );
+            assert!(!result.bom_mismatch());
+            assert!(parse_charset("\u{feff}UTF-8").is_err());
+        }
+
+        #[test]
+        fn success_when_indented() {
+            let (_, result) = parse_charset_directive(" \tcharset,Shift_JIS").unwrap();
+            assert_eq!(result.charset, Charset::ShiftJIS);
+            assert!(!result.bom);
+            assert!(result.indented);
+        }
+
+        #[test]
+        fn warned_when_bom_with_shift_jis() {
+            let (_, result) = parse_charset_directive("\u{feff}charset,Shift_JIS").unwrap();
+            assert!(result.bom_mismatch());
+        }
+
+        #[test]
+        fn failed_when_empty_or_unknown() {
+            let contexts = |case| {
+                ParseFailure::from(parse_charset_directive(case).unwrap_err())
+                    .contexts()
+                    .to_vec()
+            };
+            assert_eq!(contexts("charset,\r\n"), ["charset:empty"]);
+            assert_eq!(contexts("charset,UTF-9\r\n"), ["charset:unknown"]);
+        }
+    }
+
+    mod detect_charset {
+        use super::*;
+
+        #[test]
+        fn success_when_charset_line() {
+            let case = b"// comment\r\n  charset,Shift_JIS \r\nname,x\r\n";
+            assert_eq!(detect_charset(case), Some(Charset::ShiftJIS));
+            assert_eq!(detect_charset(b"charset,UTF-8"), Some(Charset::UTF8));
+        }
+
+        #[test]
+        fn success_when_bom() {
+            assert_eq!(
+                detect_charset(b"\xEF\xBB\xBFcharset,UTF-8\r\n"),
+                Some(Charset::UTF8)
+            );
+        }
+
+        #[test]
+        fn success_when_lines_of_different_charsets() {
+            let case = b"charset,Shift_JIS\r\ncharset,UTF-8\r\n";
+            assert_eq!(detect_charset(case), Some(Charset::ShiftJIS));
+            assert_eq!(
+                detect_charsets(case),
+                alloc::vec![
+                    Located::new(1, Charset::ShiftJIS),
+                    Located::new(2, Charset::UTF8)
+                ]
+            );
+        }
+
+        #[test]
+        fn none_when_absent_or_unknown() {
+            assert_eq!(detect_charset(b"name,x\r\n"), None);
+            assert_eq!(detect_charset(b"charset,latin1\r\n"), None);
+        }
+    }
+
+    mod decode_shell_text {
+        use super::*;
+
+        use alloc::vec;
+
+        #[test]
+        fn warned_when_conflicting_directives() {
+            let case = b"charset,UTF-8\r\nname,x\r\ncharset,ASCII\r\ncharset,UTF-8\r\n";
+            let result = decode_shell_text(case, Charset::ShiftJIS).unwrap();
+            assert_eq!(result.charset, Charset::UTF8);
+            assert_eq!(result.directives.len(), 3);
+            let used = Located::new(1, Charset::UTF8);
+            assert_eq!(
+                result.warnings,
+                vec![
+                    DirectiveWarning::Conflicting {
+                        used: used.clone(),
+                        others: vec![Located::new(3, Charset::ASCII)],
+                    },
+                    DirectiveWarning::Duplicate {
+                        used,
+                        lines: vec![4],
+                    },
+                ]
+            );
+            assert_eq!(
+                result.warnings[0].to_string(),
+                "charset UTF8 of line 1 used, ignoring ASCII of line 3"
+            );
+        }
+
+        #[test]
+        fn warned_when_agreeing_directives() {
+            let case = b"charset,UTF-8\r\ncharset,UTF-8\r\n";
+            let result = decode_shell_text(case, Charset::ShiftJIS).unwrap();
+            assert_eq!(
+                result.warnings,
+                vec![DirectiveWarning::Duplicate {
+                    used: Located::new(1, Charset::UTF8),
+                    lines: vec![2],
+                }]
+            );
+        }
+
+        #[test]
+        fn success_when_single_directive() {
+            let result =
+                decode_shell_text(b"charset,UTF-8\r\nname,x\r\n", Charset::ShiftJIS).unwrap();
+            assert_eq!(result.text, "charset,UTF-8\r\nname,x\r\n");
+            assert_eq!(result.directives, vec![Located::new(1, Charset::UTF8)]);
+            assert!(result.warnings.is_empty());
+
+            let result = decode_shell_text(b"name,x\r\n", Charset::UTF8).unwrap();
+            assert_eq!(result.charset, Charset::UTF8);
+            assert!(result.directives.is_empty());
+        }
+
+        #[test]
+        fn warned_when_directive_past_limit() {
+            let mut case = "/".repeat(DEFAULT_SCAN_LIMIT).into_bytes();
+            case.extend_from_slice(b"\r\ncharset,UTF-8\r\n");
+            let result = decode_shell_text(&case, Charset::ASCII).unwrap();
+            assert_eq!(result.charset, Charset::ASCII);
+            assert_eq!(detect_charset(&case), None);
+            assert_eq!(
+                result.warnings,
+                vec![DirectiveWarning::Late {
+                    directives: vec![Located::new(2, Charset::UTF8)]
+                }]
+            );
+            assert_eq!(
+                result.warnings[0].to_string(),
+                "charset lines after the first 4096 bytes ignored: UTF8 of line 2"
+            );
+
+            let mut case = b"charset,UTF-8\r\n".to_vec();
+            case.extend_from_slice(&"/".repeat(DEFAULT_SCAN_LIMIT).into_bytes());
+            case.extend_from_slice(b"\r\ncharset,ASCII\r\n");
+            let result = decode_shell_text(&case, Charset::ShiftJIS).unwrap();
+            assert_eq!(result.charset, Charset::UTF8);
+            assert!(matches!(
+                result.warnings[..],
+                [DirectiveWarning::Conflicting { .. }]
+            ));
+        }
+    }
+
+    mod detection_priority {
+        use super::*;
+
+        #[test]
+        fn success_when_pinned_values() {
+            let cases = [
+                (Charset::UTF8, 5),
+                (Charset::ShiftJIS, 4),
+                (Charset::EUCJP, 3),
+                (Charset::ISO2022JP, 2),
+                (Charset::ASCII, 1),
+                (Charset::Default, 0),
+            ];
+            for (charset, priority) in cases.iter() {
+                assert_eq!(charset.detection_priority(), *priority);
+            }
+
+            let mut charsets: alloc::vec::Vec<_> = cases.into_iter().map(|(c, _)| c).collect();
+            charsets.reverse();
+            assert!(charsets.windows(2).all(|w| w[0] < w[1]));
+            assert!(Charset::UTF8 > Charset::ShiftJIS && Charset::ShiftJIS > Charset::EUCJP);
+        }
+    }
+
+    mod decode_with_fallbacks {
+        use super::*;
+
+        #[test]
+        fn success_when_tie_broken_by_priority() {
+            let case = b"name,x\r\n";
+            let (charset, text) =
+                decode_with_fallbacks(case, &[Charset::ASCII, Charset::UTF8]).unwrap();
+
+            assert_eq!(charset, Charset::UTF8);
+            assert_eq!(text, "name,x\r\n");
+            assert_eq!(
+                decode_with_fallbacks(case, &[]).unwrap().0,
+                Charset::Default
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_tie_broken_by_priority_among_japanese() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター\r\n");
+            let fallbacks = [Charset::EUCJP, Charset::UTF8, Charset::ShiftJIS];
+            let (charset, text) = decode_with_fallbacks(&case, &fallbacks).unwrap();
+
+            assert_eq!(charset, Charset::ShiftJIS);
+            assert_eq!(text, "name,マスター\r\n");
+        }
+
+        #[test]
+        fn success_when_detected() {
+            let case = b"charset,ASCII\r\nname,x\r\n";
+            let (charset, _) = decode_with_fallbacks(case, &[Charset::UTF8]).unwrap();
+
+            assert_eq!(charset, Charset::ASCII);
+        }
+
+        #[test]
+        fn failed_when_no_fallback_decodes() {
+            let case = b"name,\xff\r\n";
+
+            assert_eq!(
+                decode_with_fallbacks(case, &[Charset::ASCII, Charset::UTF8]),
+                Err(DecodeError::Malformed {
+                    charset: Charset::UTF8,
+                    byte_offset: 5,
+                    line: 1,
+                })
+            );
+        }
+    }
+
+    mod detect_charset_within {
+        use super::*;
+
+        fn padded(padding: usize, rest: &str) -> Vec<u8> {
+            let mut case = "/".repeat(padding).into_bytes();
+            case.extend_from_slice(b"\r\n");
+            case.extend_from_slice(rest.as_bytes());
+            case
+        }
+
+        #[test]
+        fn success_when_just_inside_limit() {
+            // `charset` starts at byte 9.
+            let case = padded(7, "charset,UTF-8\r\n");
+            assert_eq!(detect_charset_within(&case, 10), Some(Charset::UTF8));
+        }
+
+        #[test]
+        fn none_when_just_outside_limit() {
+            let case = padded(7, "charset,UTF-8\r\n");
+            assert_eq!(detect_charset_within(&case, 9), None);
+            assert_eq!(detect_charset_within(&case, 0), None);
+
+            let case = padded(DEFAULT_SCAN_LIMIT, "charset,UTF-8\r\n");
+            assert_eq!(detect_charset(&case), None);
+            assert_eq!(
+                detect_charset_within(&case, usize::MAX),
+                Some(Charset::UTF8)
+            );
+        }
+
+        #[test]
+        fn success_when_line_straddles_limit() {
+            let case = b"name,x\r\ncharset,Shift_JIS\r\n";
+            assert_eq!(detect_charset_within(case, 9), Some(Charset::ShiftJIS));
+            assert_eq!(detect_charset_within(case, 15), Some(Charset::ShiftJIS));
+            assert_eq!(detect_charset_within(case, 8), None);
+        }
+    }
+
+    mod parse_charset_with {
+        use super::*;
+
+        #[test]
+        fn success_when_charset_aliases() {
+            let options = ParseOptions::STRICT.charset_aliases(true);
+            let cases = [
+                ("sjis", Charset::ShiftJIS),
+                ("Shift-JIS\r\n", Charset::ShiftJIS),
+                ("x-sjis", Charset::ShiftJIS),
+                ("utf8", Charset::UTF8),
+                ("EUC_JP", Charset::EUCJP),
+                ("iso-2022-jp", Charset::ISO2022JP),
+                ("US-ASCII", Charset::ASCII),
+            ];
+            for (case, charset) in cases {
+                assert_eq!(parse_charset_with(case, &options).unwrap().1, charset);
+            }
+            assert!(parse_charset_with("latin1", &options).is_err());
+            assert!(parse_charset_with("sjis", &ParseOptions::STRICT).is_err());
+        }
+    }
+
+    mod detect_charset_with {
+        use super::*;
+
+        #[test]
+        fn success_when_lenient_line() {
+            let case = b"name,a\r\nCharset \tsjis // comment\r\n";
+            assert_eq!(detect_charset_with(case, &ParseOptions::STRICT), None);
+            assert_eq!(
+                detect_charset_with(case, &ParseOptions::COMPATIBLE),
+                Some(Charset::ShiftJIS)
+            );
+            for options in [
+                ParseOptions::COMPATIBLE.case_insensitive_keys(false),
+                ParseOptions::COMPATIBLE.charset_aliases(false),
+                ParseOptions::COMPATIBLE.trim_whitespace(false),
+                ParseOptions::COMPATIBLE.tab_separator(false),
+                ParseOptions::COMPATIBLE.trailing_comments(false),
+            ] {
+                assert_eq!(detect_charset_with(case, &options), None);
+            }
+            assert_eq!(
+                detect_charset_with(b" charset,UTF-8 \r\n", &ParseOptions::STRICT),
+                Some(Charset::UTF8)
+            );
+        }
+    }
+
+    #[cfg(feature = "std")]
+    mod decode_path {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_shift_jis_file() {
+            let mut file = tempfile::NamedTempFile::new().unwrap();
+            let text = "charset,Shift_JIS\r\nname,マスター\r\n";
+            std::io::Write::write_all(&mut file, &encoding_rs::SHIFT_JIS.encode(text).0).unwrap();
+
+            let result = decode_path(file.path(), Charset::UTF8).unwrap();
+            assert_eq!(result, (text.to_string(), Charset::ShiftJIS));
+        }
+
+        #[test]
+        fn success_when_empty_file() {
+            let file = tempfile::NamedTempFile::new().unwrap();
+            let result = decode_path(file.path(), Charset::UTF8).unwrap();
+            assert_eq!(result, (String::new(), Charset::UTF8));
+        }
+
+        #[test]
+        fn failed_when_not_found_or_broken() {
+            let dir = tempfile::tempdir().unwrap();
+            let e = decode_path(&dir.path().join("descript.txt"), Charset::UTF8).unwrap_err();
+            assert_eq!(e.kind(), std::io::ErrorKind::NotFound);
+
+            let path = dir.path().join("broken.txt");
+            std::fs::write(&path, b"name,\xff\r\n").unwrap();
+            let e = decode_path(&path, Charset::UTF8).unwrap_err();
+            assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        }
+    }
+
+    #[cfg(feature = "encodings")]
+    mod from_label {
+        use super::*;
+
+        #[test]
+        fn success_when_known_label() {
+            let cases: [(&[u8], Charset); 13] = [
+                (b"Shift_JIS", Charset::ShiftJIS),
+                (b"csshiftjis", Charset::ShiftJIS),
+                (b"ms_kanji", Charset::ShiftJIS),
+                (b"windows-31j", Charset::ShiftJIS),
+                (b"x-sjis", Charset::ShiftJIS),
+                (b"euc-jp", Charset::EUCJP),
+                (b"cseucpkdfmtjapanese", Charset::EUCJP),
+                (b"x-euc-jp", Charset::EUCJP),
+                (b"csiso2022jp", Charset::ISO2022JP),
+                (b"ISO-2022-JP", Charset::ISO2022JP),
+                (b"utf8", Charset::UTF8),
+                (b"unicode-1-1-utf-8", Charset::UTF8),
+                (b" UTF-8\r\n", Charset::UTF8),
+            ];
+            for (label, charset) in cases {
+                assert_eq!(Charset::from_label(label), Some(charset));
+            }
+        }
+
+        #[test]
+        fn failed_when_other_encoding() {
+            assert_eq!(Charset::from_label(b"ISO-8859-5"), None);
+            assert_eq!(Charset::from_label(b"ascii"), None);
+            assert_eq!(Charset::from_label(b"\xff"), None);
+        }
+    }
+
+    mod whatwg_name {
+        use super::*;
+
+        #[test]
+        fn success_when_round_trip() {
+            for charset in [
+                Charset::ShiftJIS,
+                Charset::ISO2022JP,
+                Charset::EUCJP,
+                Charset::UTF8,
+            ] {
+                let name = charset.whatwg_name().unwrap();
+                assert_eq!(parse_charset(name), Ok(("", charset.clone())));
+                #[cfg(feature = "encodings")]
+                assert_eq!(Charset::from_label(name.as_bytes()), Some(charset));
+            }
+            assert_eq!(Charset::ASCII.whatwg_name(), None);
+            assert_eq!(Charset::Default.whatwg_name(), None);
+        }
+    }
+
+    mod decode_with {
+        use super::*;
+
+        #[cfg(feature = "document")]
+        use crate::document::{Document, NodeKind};
+
+        const NUL_IN_KEY: &[u8] = b"charset,UTF-8\r\nna\0me,master\r\n";
+
+        #[test]
+        fn failed_when_control_char_rejected() {
+            let options = ParseOptions::STRICT.control_chars(ControlCharPolicy::Reject);
+            let error = Charset::UTF8.decode_with(NUL_IN_KEY, &options).unwrap_err();
+
+            assert_eq!(
+                error,
+                DecodeError::ControlCharacter {
+                    character: '\0',
+                    byte_offset: 17,
+                    line: 2,
+                }
+            );
+            assert_eq!(
+                error.to_string(),
+                "control character U+0000 at line 2 (byte 17)"
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "document")]
+        fn success_when_control_char_stripped() {
+            let options = ParseOptions::STRICT.control_chars(ControlCharPolicy::Strip);
+            let text = Charset::UTF8.decode_with(NUL_IN_KEY, &options).unwrap();
+
+            assert_eq!(text, "charset,UTF-8\r\nname,master\r\n");
+            assert_eq!(Document::parse(&text).get("name"), Some("master"));
+
+            let case = b"homeurl,http://a\x0b.example/\r\n";
+            let text = Charset::UTF8.decode_with(case, &options).unwrap();
+            assert_eq!(text, "homeurl,http://a.example/\r\n");
+        }
+
+        #[test]
+        #[cfg(feature = "document")]
+        fn success_when_control_char_kept() {
+            let options = ParseOptions::STRICT;
+            let text = Charset::UTF8.decode_with(NUL_IN_KEY, &options).unwrap();
+            assert_eq!(text.as_bytes(), NUL_IN_KEY);
+
+            // A key with NUL is never an entry, so that it can not be taken for `name`.
+            let document = Document::parse(&text);
+            assert_eq!(document.nodes()[1].kind, NodeKind::Other);
+            assert_eq!(document.get("name"), None);
+
+            let case = b"homeurl,a\x0bb\r\n";
+            let text = Charset::UTF8.decode_with(case, &options).unwrap();
+            assert_eq!(Document::parse(&text).get("homeurl"), Some("a\x0bb"));
+        }
+
+        #[test]
+        fn ignored_when_tab_cr_lf() {
+            let case = b"sakura.name,a\tb\rkero.name\t,c\n\r\n";
+            for policy in [
+                ControlCharPolicy::Reject,
+                ControlCharPolicy::Strip,
+                ControlCharPolicy::Keep,
+            ] {
+                let options = ParseOptions::STRICT.control_chars(policy);
+                let text = Charset::UTF8.decode_with(case, &options).unwrap();
+                assert!(matches!(text, Cow::Borrowed(_)), "{:?}", policy);
+                assert_eq!(text.as_bytes(), case);
+            }
+        }
+    }
+
+    mod bom {
+        use super::*;
+
+        #[test]
+        fn success_when_sniffed() {
+            assert_eq!(Bom::sniff(b"\xEF\xBB\xBFa"), Some(Bom::Utf8));
+            assert_eq!(Bom::sniff(b"\xFF\xFE"), Some(Bom::Utf16Le));
+            assert_eq!(Bom::sniff(b"\xFE\xFFa"), Some(Bom::Utf16Be));
+            assert_eq!(Bom::sniff(b"\xEF\xBB"), None);
+            assert_eq!(Bom::sniff(b"charset,UTF-8"), None);
+        }
     }
 
     mod charset {
@@ -171,6 +1681,7 @@ mod tests {
         }
 
         #[test]
+        #[cfg(feature = "encodings")]
         fn success_when_valid_str() {
             let case = "abcdefg".as_bytes();
             let result = Charset::ASCII.decode(case).unwrap();
@@ -204,10 +1715,134 @@ mod tests {
         }
 
         #[test]
+        #[cfg(feature = "encodings")]
         fn failed_when_invalid_str() {
             let case_raw = "あいうえお";
             let (case, _, _) = encoding_rs::SHIFT_JIS.encode(case_raw);
             assert!(Charset::UTF8.decode(&case).is_err());
         }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn failed_when_malformed_located() {
+            let malformed = |byte_offset, line| {
+                Err(DecodeError::Malformed {
+                    charset: Charset::ShiftJIS,
+                    byte_offset,
+                    line,
+                })
+            };
+
+            assert_eq!(Charset::ShiftJIS.decode(b"name,\xa0\r\n"), malformed(5, 1));
+
+            let text = "name,マスター\r\n".repeat(499);
+            let (mut case, _, _) = encoding_rs::SHIFT_JIS.encode(&text);
+            let offset = case.len() + 5;
+            case.to_mut()
+                .extend_from_slice(b"name,\x82\x20\r\nafter,x\r\n");
+            assert_eq!(Charset::ShiftJIS.decode(&case), malformed(offset, 500));
+
+            let (mut case, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター\r\nname,");
+            case.to_mut().push(0x82);
+            assert_eq!(
+                Charset::ShiftJIS.decode(&case),
+                malformed(case.len() - 1, 2)
+            );
+        }
+
+        #[test]
+        fn success_when_no_bom() {
+            assert_eq!(Charset::UTF8.decode(b"name,a").unwrap(), "name,a");
+            assert!(matches!(
+                Charset::UTF8.decode(b"name,a").unwrap(),
+                Cow::Borrowed(_)
+            ));
+            assert_eq!(
+                Charset::UTF8.decode(b"name,\xff"),
+                Err(DecodeError::Malformed {
+                    charset: Charset::UTF8,
+                    byte_offset: 5,
+                    line: 1,
+                })
+            );
+        }
+
+        #[test]
+        fn success_when_matching_bom() {
+            for charset in [Charset::UTF8, Charset::ASCII] {
+                assert_eq!(charset.decode(b"\xEF\xBB\xBFname,a").unwrap(), "name,a");
+            }
+            assert_eq!(
+                Charset::UTF8.decode(b"\xEF\xBB\xBFname,\xff"),
+                Err(DecodeError::Malformed {
+                    charset: Charset::UTF8,
+                    byte_offset: 8,
+                    line: 1,
+                })
+            );
+        }
+
+        #[test]
+        fn failed_when_contradicting_bom() {
+            assert_eq!(
+                Charset::UTF8.decode(b"\xFF\xFEn\0a\0"),
+                Err(DecodeError::BomMismatch {
+                    declared: Charset::UTF8,
+                    bom: Bom::Utf16Le,
+                })
+            );
+            assert_eq!(
+                Charset::UTF8
+                    .decode(b"\xFE\xFF\0n")
+                    .unwrap_err()
+                    .to_string(),
+                "byte order mark of UTF-16BE in a file of UTF8"
+            );
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn failed_when_utf_8_bom_in_shift_jis() {
+            assert_eq!(
+                Charset::ShiftJIS.decode(b"\xEF\xBB\xBFcharset,Shift_JIS\r\n"),
+                Err(DecodeError::BomMismatch {
+                    declared: Charset::ShiftJIS,
+                    bom: Bom::Utf8,
+                })
+            );
+        }
+
+        #[test]
+        #[cfg(not(feature = "encodings"))]
+        fn success_when_utf_8_without_encodings() {
+            let result = Charset::ASCII.decode(b"abcdefg").unwrap();
+            assert_eq!(result, "abcdefg");
+
+            let case = "\u{feff}あいうえお".as_bytes();
+            let result = Charset::UTF8.decode(case).unwrap();
+            assert_eq!(result, "あいうえお");
+
+            let result = Charset::Default.decode(b"a\xffb").unwrap();
+            assert_eq!(result, "a\u{fffd}b");
+        }
+
+        #[test]
+        #[cfg(not(feature = "encodings"))]
+        fn failed_when_encoding_support_disabled() {
+            for charset in [Charset::ShiftJIS, Charset::ISO2022JP, Charset::EUCJP] {
+                assert_eq!(
+                    charset.decode(b"abc"),
+                    Err(DecodeError::EncodingSupportDisabled(charset.clone()))
+                );
+            }
+            assert_eq!(
+                Charset::UTF8.decode(b"a\x82\xa0"),
+                Err(DecodeError::Malformed {
+                    charset: Charset::UTF8,
+                    byte_offset: 1,
+                    line: 1,
+                })
+            );
+        }
     }
 }
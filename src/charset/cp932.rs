@@ -0,0 +1,140 @@
+//! Detection of CP932 extensions in Shift_JIS bytes, which are outside JIS X 0208.
+
+use alloc::vec::Vec;
+
+/// Range of CP932 extensions a char belongs to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExtensionKind {
+    /// NEC special characters of row 13, such as `①` and `㈱`, led by `0x87`.
+    NecSpecial,
+    /// NEC-selected IBM extensions, led by `0xED` and `0xEE`.
+    NecSelectedIbm,
+    /// IBM extensions, led by `0xFA` to `0xFC`.
+    Ibm,
+}
+
+/// CP932 extension found by [`scan_cp932_extensions`].
+///
+/// [`scan_cp932_extensions`]: crate::charset::scan_cp932_extensions
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ExtensionUse {
+    /// Byte offset of the lead byte.
+    pub offset: usize,
+    pub c: char,
+    pub kind: ExtensionKind,
+}
+
+/// Finds chars of CP932 extensions in Shift_JIS `bytes`, which [`Charset::ShiftJIS`] decodes
+/// but environments reading strict Shift_JIS may not.
+///
+/// Only pairs of a lead byte in the extension ranges are decoded, not the whole bytes.
+/// Invalid pairs and a lead byte truncated at the end are skipped.
+///
+/// [`Charset::ShiftJIS`]: crate::charset::Charset::ShiftJIS
+///
+/// ```
+/// use shell_parser_common_rs::charset::{scan_cp932_extensions, ExtensionKind};
+///
+/// let (case, _, _) = encoding_rs::SHIFT_JIS.encode("name,①マスター");
+/// let found = scan_cp932_extensions(&case);
+///
+/// assert_eq!(found.len(), 1);
+/// assert_eq!(found[0].offset, 5);
+/// assert_eq!(found[0].c, '①');
+/// assert_eq!(found[0].kind, ExtensionKind::NecSpecial);
+/// ```
+pub fn scan_cp932_extensions(bytes: &[u8]) -> Vec<ExtensionUse> {
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let lead = bytes[i];
+        if !is_lead(lead) {
+            i += 1;
+            continue;
+        }
+        let Some(&trail) = bytes.get(i + 1) else {
+            break;
+        };
+        if !is_trail(trail) {
+            i += 1;
+            continue;
+        }
+
+        if let Some(kind) = extension_kind(lead) {
+            let decoded = encoding_rs::SHIFT_JIS
+                .decode_without_bom_handling_and_without_replacement(&bytes[i..i + 2]);
+            if let Some(c) = decoded.and_then(|s| s.chars().next()) {
+                found.push(ExtensionUse { offset: i, c, kind });
+            }
+        }
+        i += 2;
+    }
+
+    found
+}
+
+fn is_lead(b: u8) -> bool {
+    matches!(b, 0x81..=0x9f | 0xe0..=0xfc)
+}
+
+fn is_trail(b: u8) -> bool {
+    matches!(b, 0x40..=0x7e | 0x80..=0xfc)
+}
+
+fn extension_kind(lead: u8) -> Option<ExtensionKind> {
+    match lead {
+        0x87 => Some(ExtensionKind::NecSpecial),
+        0xed | 0xee => Some(ExtensionKind::NecSelectedIbm),
+        0xfa..=0xfc => Some(ExtensionKind::Ibm),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod scan_cp932_extensions {
+        use super::*;
+
+        #[test]
+        fn success_when_extensions() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode("①a髙\r\n纊");
+            assert_eq!(
+                scan_cp932_extensions(&case),
+                vec![
+                    ExtensionUse {
+                        offset: 0,
+                        c: '①',
+                        kind: ExtensionKind::NecSpecial,
+                    },
+                    ExtensionUse {
+                        offset: 3,
+                        c: '髙',
+                        kind: ExtensionKind::Ibm,
+                    },
+                    ExtensionUse {
+                        offset: 7,
+                        c: '纊',
+                        kind: ExtensionKind::Ibm,
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn ignored_when_jis_x_0208() {
+            let (case, _, _) =
+                encoding_rs::SHIFT_JIS.encode("charset,Shift_JIS\r\nname,マスター表ｱ\r\n");
+            assert!(scan_cp932_extensions(&case).is_empty());
+        }
+
+        #[test]
+        fn ignored_when_truncated() {
+            assert!(scan_cp932_extensions(b"name,\x87").is_empty());
+            assert!(scan_cp932_extensions(b"\x87\x0a\x87").is_empty());
+            assert!(scan_cp932_extensions(b"\x81\x87\x40").is_empty());
+        }
+    }
+}
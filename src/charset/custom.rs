@@ -0,0 +1,287 @@
+//! Charsets not built in, registered by callers.
+
+use alloc::borrow::Cow;
+use core::fmt;
+
+use nom::IResult;
+
+use crate::{value::longest_tag, ShellParseError};
+
+use super::{
+    decode_strict, encode_strict, parse_charset, Bom, Charset, DecodeError, StrictError, CHARSETS,
+};
+
+/// Charset not built in, such as `GBK` of Chinese-localized shells, with the encoding it is decoded in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CustomCharset {
+    pub encoding: &'static encoding_rs::Encoding,
+}
+
+impl CustomCharset {
+    pub const fn new(encoding: &'static encoding_rs::Encoding) -> CustomCharset {
+        CustomCharset { encoding }
+    }
+}
+
+/// Charset read by [`parse_charset_extended`], built in or custom.
+///
+/// [`parse_charset_extended`]: crate::charset::parse_charset_extended
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AnyCharset {
+    Builtin(Charset),
+    Custom(CustomCharset),
+}
+
+/// `CodecError` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CodecError {
+    /// The bytes are not valid in the charset.
+    Malformed {
+        /// Offset of the first malformed sequence.
+        byte_offset: usize,
+        /// 1-based line number of the sequence, counting `\n` bytes before it.
+        line: usize,
+    },
+    /// The char cannot be represented in the charset.
+    Unmappable(char),
+    /// The bytes start with the byte order mark of another encoding than the charset.
+    BomMismatch(Bom),
+}
+
+impl CodecError {
+    fn malformed(input: &[u8], byte_offset: usize) -> CodecError {
+        CodecError::Malformed {
+            byte_offset,
+            line: memchr::memchr_iter(b'\n', &input[..byte_offset]).count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Malformed { byte_offset, line } => {
+                write!(f, "malformed bytes at line {} (byte {})", line, byte_offset)
+            }
+            CodecError::Unmappable(c) => write!(f, "{:?} cannot be encoded", c),
+            CodecError::BomMismatch(bom) => write!(f, "byte order mark of {}", bom),
+        }
+    }
+}
+
+impl core::error::Error for CodecError {}
+
+/// Charset that bytes are decoded from and text is encoded into, for code taking both [`Charset`] and [`CustomCharset`].
+///
+/// [`Charset`]: crate::charset::Charset
+/// [`CustomCharset`]: crate::charset::CustomCharset
+pub trait Decodable {
+    /// Decodes `input` strictly, removing the BOM.
+    fn decode_bytes<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, CodecError>;
+
+    /// Encodes `text` strictly.
+    fn encode_text<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>, CodecError>;
+}
+
+/// Decoded as [`Charset::decode`] does. [`Charset::Default`] is encoded as UTF-8.
+///
+/// [`Charset::decode`]: crate::charset::Charset::decode
+/// [`Charset::Default`]: crate::charset::Charset::Default
+impl Decodable for Charset {
+    fn decode_bytes<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, CodecError> {
+        self.decode(input).map_err(|e| match e {
+            DecodeError::Malformed {
+                byte_offset, line, ..
+            } => CodecError::Malformed { byte_offset, line },
+            DecodeError::BomMismatch { bom, .. } => CodecError::BomMismatch(bom),
+            DecodeError::EncodingSupportDisabled(_) => unreachable!("encodings are enabled"),
+            DecodeError::ControlCharacter { .. } => unreachable!("control characters are kept"),
+        })
+    }
+
+    fn encode_text<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>, CodecError> {
+        let encoding = match self {
+            Charset::ASCII => None,
+            _ => Some(self.encoding().unwrap_or(encoding_rs::UTF_8)),
+        };
+        encode_strict(encoding, text).map_err(|(_, c)| CodecError::Unmappable(c))
+    }
+}
+
+impl Decodable for CustomCharset {
+    fn decode_bytes<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, CodecError> {
+        if input.is_empty() {
+            return Ok(Cow::Borrowed(""));
+        }
+        decode_strict(self.encoding, input).map_err(|e| match e {
+            StrictError::Bom(bom) => CodecError::BomMismatch(bom),
+            StrictError::Malformed(offset) => CodecError::malformed(input, offset),
+        })
+    }
+
+    fn encode_text<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>, CodecError> {
+        encode_strict(Some(self.encoding), text).map_err(|(_, c)| CodecError::Unmappable(c))
+    }
+}
+
+impl Decodable for AnyCharset {
+    fn decode_bytes<'a>(&self, input: &'a [u8]) -> Result<Cow<'a, str>, CodecError> {
+        match self {
+            AnyCharset::Builtin(charset) => charset.decode_bytes(input),
+            AnyCharset::Custom(charset) => charset.decode_bytes(input),
+        }
+    }
+
+    fn encode_text<'a>(&self, text: &'a str) -> Result<Cow<'a, [u8]>, CodecError> {
+        match self {
+            AnyCharset::Builtin(charset) => charset.encode_text(text),
+            AnyCharset::Custom(charset) => charset.encode_text(text),
+        }
+    }
+}
+
+/// Same as [`parse_charset`], also reading the names in `extra`.
+///
+/// The longest matching name of the built-in names and `extra` is read,
+/// so a name in `extra` such as `UTF-8-MAC` is not cut short by `UTF-8`.
+/// Built-in names win over names in `extra` of the same length, and so over the same names.
+/// Errors are those of [`parse_charset`].
+///
+/// [`parse_charset`]: crate::charset::parse_charset
+pub fn parse_charset_extended<'a>(
+    input: &'a str,
+    extra: &[(&str, CustomCharset)],
+) -> IResult<&'a str, AnyCharset, ShellParseError<'a>> {
+    let builtin = CHARSETS
+        .iter()
+        .map(|(name, charset)| (*name, AnyCharset::Builtin(charset.clone())));
+    let custom = extra
+        .iter()
+        .map(|(name, charset)| (*name, AnyCharset::Custom(*charset)));
+    match longest_tag(input, builtin.chain(custom)) {
+        Some((name, charset)) => Ok((&input[name.len()..], charset)),
+        None => {
+            parse_charset(input).map(|(remain, charset)| (remain, AnyCharset::Builtin(charset)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GBK: CustomCharset = CustomCharset::new(encoding_rs::GBK);
+
+    mod parse_charset_extended {
+        use super::*;
+
+        #[test]
+        fn success_when_custom_name() {
+            let extra = [
+                ("GB", CustomCharset::new(encoding_rs::GB18030)),
+                ("GBK", GBK),
+            ];
+            let (remain, charset) = parse_charset_extended("GBK\r\n", &extra).unwrap();
+
+            assert_eq!(remain, "\r\n");
+            assert_eq!(charset, AnyCharset::Custom(GBK));
+        }
+
+        #[test]
+        fn success_when_builtin_name_in_extra() {
+            let extra = [("UTF-8", GBK)];
+            let (_, charset) = parse_charset_extended("UTF-8", &extra).unwrap();
+
+            assert_eq!(charset, AnyCharset::Builtin(Charset::UTF8));
+        }
+
+        #[test]
+        fn success_when_names_start_one_another() {
+            let big5 = CustomCharset::new(encoding_rs::BIG5);
+            let extra = [("Shift", GBK), ("UTF-8-MAC", big5)];
+            assert_eq!(
+                parse_charset_extended("Shift_JIS\r\n", &extra),
+                Ok(("\r\n", AnyCharset::Builtin(Charset::ShiftJIS)))
+            );
+            assert_eq!(
+                parse_charset_extended("Shift-X", &extra),
+                Ok(("-X", AnyCharset::Custom(GBK)))
+            );
+            assert_eq!(
+                parse_charset_extended("UTF-8-MAC\r\n", &extra),
+                Ok(("\r\n", AnyCharset::Custom(big5)))
+            );
+            assert_eq!(
+                parse_charset_extended("UTF-8-LE", &extra),
+                Ok(("-LE", AnyCharset::Builtin(Charset::UTF8)))
+            );
+        }
+
+        #[test]
+        fn failed_when_unknown_name() {
+            assert!(parse_charset_extended("Big5", &[("GBK", GBK)]).is_err());
+        }
+    }
+
+    mod decodable {
+        use super::*;
+
+        #[test]
+        fn success_when_gbk() {
+            let (bytes, _, _) = encoding_rs::GBK.encode("name,中文外壳\r\n");
+            let (_, charset) = parse_charset_extended("GBK", &[("GBK", GBK)]).unwrap();
+
+            assert_eq!(charset.decode_bytes(&bytes).unwrap(), "name,中文外壳\r\n");
+            assert_eq!(charset.encode_text("name,中文外壳\r\n").unwrap(), bytes);
+        }
+
+        #[test]
+        fn failed_when_gbk_malformed() {
+            assert_eq!(
+                GBK.decode_bytes(b"a\nb\x81"),
+                Err(CodecError::Malformed {
+                    byte_offset: 3,
+                    line: 2
+                })
+            );
+            assert_eq!(GBK.encode_text("ア🍄"), Err(CodecError::Unmappable('🍄')));
+        }
+
+        #[test]
+        fn success_when_bom_of_encoding() {
+            let utf_16 = CustomCharset::new(encoding_rs::UTF_16LE);
+            assert_eq!(utf_16.decode_bytes(b"\xFF\xFEa\0").unwrap(), "a");
+            assert_eq!(utf_16.decode_bytes(b"a\0").unwrap(), "a");
+        }
+
+        #[test]
+        fn failed_when_bom_of_another_encoding() {
+            assert_eq!(
+                GBK.decode_bytes(b"\xEF\xBB\xBFname,a"),
+                Err(CodecError::BomMismatch(Bom::Utf8))
+            );
+            assert_eq!(
+                CustomCharset::new(encoding_rs::UTF_16LE).decode_bytes(b"\xFE\xFF\0a"),
+                Err(CodecError::BomMismatch(Bom::Utf16Be))
+            );
+        }
+
+        #[test]
+        fn success_when_same_as_builtin() {
+            let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター");
+
+            assert_eq!(
+                Charset::ShiftJIS.decode_bytes(&bytes).unwrap(),
+                Charset::ShiftJIS.decode(&bytes).unwrap()
+            );
+            assert_eq!(
+                Charset::ShiftJIS.encode_text("name,マスター").unwrap(),
+                bytes
+            );
+            assert_eq!(
+                Charset::ASCII.encode_text("マ"),
+                Err(CodecError::Unmappable('マ'))
+            );
+        }
+    }
+}
@@ -0,0 +1,325 @@
+//! Lazy line iterator over raw bytes, decoding one chunk at a time.
+
+use alloc::string::String;
+
+#[cfg(feature = "encodings")]
+use encoding_rs::Decoder;
+
+use super::{Charset, DecodeError};
+
+/// Number of bytes decoded at a time.
+const CHUNK_SIZE: usize = 256;
+
+/// Iterator over decoded lines of raw bytes.
+///
+/// Bytes are decoded only as far as the lines taken, so stopping early skips the rest.
+/// Line endings are recognized as [`lines`] does and are not included.
+/// After a decode error, the iterator yields nothing.
+///
+/// [`Charset::Default`] is decoded as UTF-8, replacing invalid sequences.
+/// Without the `encodings` feature, the charsets [`Charset::decode`] rejects yield only the error.
+///
+/// [`lines`]: crate::line::lines
+/// [`Charset::Default`]: crate::charset::Charset::Default
+/// [`Charset::decode`]: crate::charset::Charset::decode
+///
+/// ```
+/// # #[cfg(feature = "encodings")]
+/// # fn main() {
+/// use shell_parser_common_rs::charset::{Charset, DecodedLines};
+///
+/// let (case, _, _) = encoding_rs::SHIFT_JIS.encode("charset,Shift_JIS\r\nname,マスター\r\n");
+/// let mut lines = DecodedLines::new(&case, Charset::ShiftJIS);
+///
+/// assert_eq!(lines.next(), Some(Ok("charset,Shift_JIS".to_string())));
+/// assert_eq!(lines.next(), Some(Ok("name,マスター".to_string())));
+/// assert_eq!(lines.next(), None);
+/// # }
+/// # #[cfg(not(feature = "encodings"))]
+/// # fn main() {}
+/// ```
+pub struct DecodedLines<'a> {
+    /// All of the bytes, for locating a decode error.
+    source: &'a [u8],
+    /// Bytes not yet decoded.
+    input: &'a [u8],
+    charset: Charset,
+    #[cfg(feature = "encodings")]
+    decoder: Decoder,
+    /// Decoded text not yet yielded.
+    pending: String,
+    /// Whether all of `input` has been decoded.
+    finished: bool,
+    /// Error to yield after the lines before it.
+    error: Option<DecodeError>,
+    /// Whether the iterator is exhausted.
+    done: bool,
+    /// Length of the decoded text yielded, with line endings.
+    offset: usize,
+}
+
+impl<'a> DecodedLines<'a> {
+    pub fn new(input: &'a [u8], charset: Charset) -> DecodedLines<'a> {
+        #[cfg(feature = "encodings")]
+        let decoder = super::new_decoder(&charset);
+        let source = input;
+        #[cfg(not(feature = "encodings"))]
+        let input = input.strip_prefix(super::UTF_8_BOM).unwrap_or(input);
+
+        DecodedLines {
+            source,
+            input,
+            charset,
+            #[cfg(feature = "encodings")]
+            decoder,
+            pending: String::new(),
+            finished: false,
+            error: None,
+            done: false,
+            offset: 0,
+        }
+    }
+
+    /// Returns the byte offset of the next line in the decoded text.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Decodes the next chunk into `pending`.
+    #[cfg(feature = "encodings")]
+    fn decode_chunk(&mut self) {
+        let len = self.input.len().min(CHUNK_SIZE);
+        let (chunk, rest) = self.input.split_at(len);
+        let last = rest.is_empty();
+
+        match super::decode_to_string(
+            &mut self.decoder,
+            &self.charset,
+            chunk,
+            &mut self.pending,
+            last,
+        ) {
+            Ok(read) => self.input = &self.input[read..],
+            Err(offset) => {
+                let offset = self.source.len() - self.input.len() + offset;
+                self.error = Some(DecodeError::malformed(&self.charset, self.source, offset));
+                self.finished = true;
+                return;
+            }
+        }
+
+        self.finished = self.input.is_empty() && last;
+    }
+
+    /// Decodes the next chunk into `pending`, as UTF-8.
+    #[cfg(not(feature = "encodings"))]
+    fn decode_chunk(&mut self) {
+        if self.charset != Charset::Default && !self.charset.is_utf_8() {
+            self.error = Some(DecodeError::EncodingSupportDisabled(self.charset.clone()));
+            self.finished = true;
+            return;
+        }
+
+        // Extends the chunk so that it ends on a char boundary.
+        let mut len = self.input.len().min(CHUNK_SIZE);
+        while self.input.get(len).is_some_and(|b| b & 0xc0 == 0x80) {
+            len += 1;
+        }
+        let position = self.source.len() - self.input.len();
+        let (chunk, rest) = self.input.split_at(len);
+        self.input = rest;
+        self.finished = rest.is_empty();
+
+        match core::str::from_utf8(chunk) {
+            Ok(text) => self.pending.push_str(text),
+            Err(_) if self.charset == Charset::Default => {
+                self.pending.push_str(&String::from_utf8_lossy(chunk))
+            }
+            Err(e) => {
+                let (valid, _) = chunk.split_at(e.valid_up_to());
+                self.pending
+                    .push_str(core::str::from_utf8(valid).unwrap_or_default());
+                self.error = Some(DecodeError::malformed(
+                    &self.charset,
+                    self.source,
+                    position + e.valid_up_to(),
+                ));
+                self.finished = true;
+            }
+        }
+    }
+
+    /// Takes the first complete line from `pending`.
+    fn take_line(&mut self) -> Option<String> {
+        let bytes = self.pending.as_bytes();
+        let i = memchr::memchr2(b'\r', b'\n', bytes)?;
+        let ending = match (bytes[i], bytes.get(i + 1)) {
+            (b'\r', Some(b'\n')) => 2,
+            // `\n` may follow in the next chunk.
+            (b'\r', None) if !self.finished => return None,
+            _ => 1,
+        };
+
+        self.offset += i + ending;
+        let rest = self.pending.split_off(i + ending);
+        let mut line = core::mem::replace(&mut self.pending, rest);
+        line.truncate(i);
+        Some(line)
+    }
+}
+
+impl Iterator for DecodedLines<'_> {
+    type Item = Result<String, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            if let Some(line) = self.take_line() {
+                return Some(Ok(line));
+            }
+            if self.finished {
+                break;
+            }
+            self.decode_chunk();
+        }
+
+        self.done = true;
+        if let Some(e) = self.error.take() {
+            return Some(Err(e));
+        }
+        if self.pending.is_empty() {
+            None
+        } else {
+            self.offset += self.pending.len();
+            Some(Ok(core::mem::take(&mut self.pending)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod decoded_lines {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn stopped_when_two_lines_taken() {
+            let mut text = String::from("charset,Shift_JIS\r\nname,マスター\r\n");
+            while text.len() < 1 << 20 {
+                text.push_str("element0,base,表面.png,0,0\r\n");
+            }
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode(&text);
+
+            let mut lines = DecodedLines::new(&case, Charset::ShiftJIS);
+            assert_eq!(lines.next(), Some(Ok("charset,Shift_JIS".to_string())));
+            assert_eq!(lines.next(), Some(Ok("name,マスター".to_string())));
+            assert_eq!(lines.input.len(), case.len() - CHUNK_SIZE);
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_char_across_chunks() {
+            for padding in CHUNK_SIZE - 3..=CHUNK_SIZE {
+                let text = format!("{}あい\r\nう\n", "a".repeat(padding));
+                let (case, _, _) = encoding_rs::SHIFT_JIS.encode(&text);
+                let result: Vec<_> = DecodedLines::new(&case, Charset::ShiftJIS).collect();
+                assert_eq!(
+                    result,
+                    vec![
+                        Ok(format!("{}あい", "a".repeat(padding))),
+                        Ok("う".to_string())
+                    ]
+                );
+            }
+        }
+
+        #[test]
+        fn success_when_mixed_endings() {
+            // `\r\n` split across chunks is one line ending.
+            let text = format!("{}\r\nb\nc\rd", "a".repeat(CHUNK_SIZE - 1));
+            let result: Vec<_> = DecodedLines::new(text.as_bytes(), Charset::UTF8)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(
+                result,
+                vec![
+                    "a".repeat(CHUNK_SIZE - 1),
+                    "b".into(),
+                    "c".into(),
+                    "d".into()
+                ]
+            );
+
+            let mut lines = DecodedLines::new(b"x\r\r\n\n", Charset::UTF8);
+            assert_eq!(lines.next(), Some(Ok("x".to_string())));
+            assert_eq!(lines.offset(), 2);
+            let result: Vec<_> = lines.by_ref().map(Result::unwrap).collect();
+            assert_eq!(result, vec!["", ""]);
+            assert_eq!(lines.offset(), 5);
+            assert_eq!(DecodedLines::new(b"", Charset::UTF8).next(), None);
+        }
+
+        #[test]
+        fn failed_when_invalid_sequence_mid_file() {
+            let mut case = Vec::new();
+            for i in 0..40 {
+                case.extend_from_slice(format!("line{},ok\r\n", i).as_bytes());
+            }
+            case.extend_from_slice(b"broken,\xff\xfe\r\nafter,x\r\n");
+
+            let result: Vec<_> = DecodedLines::new(&case, Charset::UTF8).collect();
+            assert_eq!(result.len(), 41);
+            assert!(result[..40].iter().all(|r| r.is_ok()));
+            assert_eq!(
+                result[40],
+                Err(DecodeError::Malformed {
+                    charset: Charset::UTF8,
+                    byte_offset: case.len() - 13,
+                    line: 41,
+                })
+            );
+        }
+
+        #[test]
+        #[cfg(not(feature = "encodings"))]
+        fn success_when_char_across_chunks_without_encodings() {
+            for padding in CHUNK_SIZE - 3..=CHUNK_SIZE {
+                let text = format!("{}あい\r\nう\n", "a".repeat(padding));
+                let result: Vec<_> = DecodedLines::new(text.as_bytes(), Charset::UTF8).collect();
+                assert_eq!(
+                    result,
+                    vec![
+                        Ok(format!("{}あい", "a".repeat(padding))),
+                        Ok("う".to_string())
+                    ]
+                );
+
+                let mut case = text.into_bytes();
+                case.insert(padding + 1, 0xff);
+                let result: Vec<_> = DecodedLines::new(&case, Charset::Default)
+                    .map(Result::unwrap)
+                    .collect();
+                let expected: Vec<_> = String::from_utf8_lossy(&case)
+                    .lines()
+                    .map(String::from)
+                    .collect();
+                assert_eq!(result, expected);
+            }
+        }
+
+        #[test]
+        #[cfg(not(feature = "encodings"))]
+        fn failed_when_encoding_support_disabled() {
+            let result: Vec<_> = DecodedLines::new(b"name,a\r\n", Charset::ShiftJIS).collect();
+            assert_eq!(
+                result,
+                vec![Err(DecodeError::EncodingSupportDisabled(Charset::ShiftJIS))]
+            );
+        }
+    }
+}
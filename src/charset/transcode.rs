@@ -0,0 +1,197 @@
+//! Transcoding whole files between charsets, rewriting the `charset` line.
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt;
+
+use super::{detect_charset, encode_strict, Charset, DecodeError, EncodeError, BOM, CHARSETS};
+
+/// `TranscodeError` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TranscodeError {
+    /// No source charset is given, and no `charset` line names one.
+    Undetected,
+    /// The bytes are not valid in the source charset.
+    Decode(DecodeError),
+    /// A char cannot be represented in the target charset.
+    Encode {
+        /// 1-based line number of the char.
+        line: usize,
+        error: EncodeError,
+    },
+}
+
+impl fmt::Display for TranscodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscodeError::Undetected => write!(f, "source charset not detected"),
+            TranscodeError::Decode(e) => write!(f, "{}", e),
+            TranscodeError::Encode { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl core::error::Error for TranscodeError {}
+
+/// Transcodes `bytes` from `from`, or the charset detected by [`detect_charset`], into `to`.
+///
+/// The bytes are decoded as [`Charset::decode`] does, so malformed bytes are an error.
+/// Lines whose key is exactly `charset` are rewritten to name `to`, keeping the rest of the line;
+/// keys such as `charset2` are left untouched.
+/// [`Charset::Default`] is encoded as UTF-8 without rewriting the lines, since it has no name.
+///
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`Charset::decode`]: crate::charset::Charset::decode
+/// [`Charset::Default`]: crate::charset::Charset::Default
+///
+/// ```
+/// use shell_parser_common_rs::charset::{transcode, Charset};
+///
+/// let (case, _, _) = encoding_rs::SHIFT_JIS.encode("charset,Shift_JIS\r\nname,マスター\r\n");
+/// let result = transcode(&case, None, Charset::UTF8).unwrap();
+///
+/// assert_eq!(result, "charset,UTF-8\r\nname,マスター\r\n".as_bytes());
+/// ```
+pub fn transcode(
+    bytes: &[u8],
+    from: Option<Charset>,
+    to: Charset,
+) -> Result<Vec<u8>, TranscodeError> {
+    transcode_with(bytes, from, to, false)
+}
+
+/// Same as [`transcode`], but adds a `charset` line at the start when `add_directive` is `true` and none is given.
+///
+/// [`transcode`]: crate::charset::transcode
+pub fn transcode_with(
+    bytes: &[u8],
+    from: Option<Charset>,
+    to: Charset,
+    add_directive: bool,
+) -> Result<Vec<u8>, TranscodeError> {
+    let from = match from {
+        Some(charset) => charset,
+        None => detect_charset(bytes).ok_or(TranscodeError::Undetected)?,
+    };
+    let text = from.decode(bytes).map_err(TranscodeError::Decode)?;
+    let text = match label(&to) {
+        Some(label) => rewrite_directive(&text, label, add_directive),
+        None => text.into_owned(),
+    };
+
+    encode(&text, &to)
+}
+
+fn label(charset: &Charset) -> Option<&'static str> {
+    CHARSETS
+        .iter()
+        .find(|(_, c)| c == charset)
+        .map(|(name, _)| *name)
+}
+
+/// Rewrites the values of `charset` lines of `text` to `label`, keeping line endings.
+fn rewrite_directive(text: &str, label: &str, add_directive: bool) -> String {
+    let mut result = String::with_capacity(text.len() + label.len());
+    let mut found = false;
+
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        match body.split_once(',') {
+            Some((key, _)) if is_charset_key(key) => {
+                found = true;
+                result.push_str(key);
+                result.push(',');
+                result.push_str(label);
+                result.push_str(&line[body.len()..]);
+            }
+            _ => result.push_str(line),
+        }
+    }
+
+    if !found && add_directive {
+        let ending = if text.contains("\r\n") || !text.contains('\n') {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        result.insert_str(0, ending);
+        result.insert_str(0, label);
+        result.insert_str(0, "charset,");
+    }
+
+    result
+}
+
+/// Checks whether `key` is `charset`, allowing a leading BOM and surrounding spaces as [`parse_charset_directive`] does.
+///
+/// [`parse_charset_directive`]: crate::charset::parse_charset_directive
+fn is_charset_key(key: &str) -> bool {
+    key.trim_start_matches(BOM).trim() == "charset"
+}
+
+/// Encodes `text` into `charset` strictly, locating an unmappable char by its line.
+fn encode(text: &str, charset: &Charset) -> Result<Vec<u8>, TranscodeError> {
+    let encoding = match charset {
+        Charset::ASCII => None,
+        _ => Some(charset.encoding().unwrap_or(encoding_rs::UTF_8)),
+    };
+
+    encode_strict(encoding, text)
+        .map(Cow::into_owned)
+        .map_err(|(read, c)| TranscodeError::Encode {
+            line: text[..read].matches('\n').count() + 1,
+            error: EncodeError::Unmappable(charset.clone(), c),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod transcode {
+        use super::*;
+
+        #[test]
+        fn success_when_shift_jis_to_utf_8() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS
+                .encode("charset,Shift_JIS\r\ncharset2,Shift_JIS\r\nname,マスター\r\nmycharset,x");
+            let result = transcode(&case, None, Charset::UTF8).unwrap();
+            assert_eq!(
+                result,
+                "charset,UTF-8\r\ncharset2,Shift_JIS\r\nname,マスター\r\nmycharset,x".as_bytes()
+            );
+        }
+
+        #[test]
+        fn failed_when_unmappable() {
+            let case = "charset,UTF-8\r\nname,マスター\r\ncraftman,🍄\r\n";
+            assert_eq!(
+                transcode(case.as_bytes(), None, Charset::ShiftJIS),
+                Err(TranscodeError::Encode {
+                    line: 3,
+                    error: EncodeError::Unmappable(Charset::ShiftJIS, '🍄'),
+                })
+            );
+            assert_eq!(
+                transcode("name,マスター".as_bytes(), None, Charset::UTF8),
+                Err(TranscodeError::Undetected)
+            );
+        }
+
+        #[test]
+        fn success_when_directive_added() {
+            let case = "name,マスター\r\n".as_bytes();
+            let (expected, _, _) =
+                encoding_rs::SHIFT_JIS.encode("charset,Shift_JIS\r\nname,マスター\r\n");
+            assert_eq!(
+                transcode_with(case, Some(Charset::UTF8), Charset::ShiftJIS, true).unwrap(),
+                *expected
+            );
+
+            let (expected, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター\r\n");
+            assert_eq!(
+                transcode(case, Some(Charset::UTF8), Charset::ShiftJIS).unwrap(),
+                *expected
+            );
+        }
+    }
+}
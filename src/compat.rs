@@ -0,0 +1,315 @@
+//! # `compat`
+//!
+//! Baseware compatibility levels which the features used by a shell require.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use shell_parser_common_rs::compat::required_level;
+//! use shell_parser_common_rs::options::ParseOptions;
+//! use shell_parser_common_rs::project::load_shell_dir;
+//!
+//! let project = load_shell_dir(Path::new("shell/master"), &ParseOptions::STRICT).unwrap();
+//! let report = required_level(&project);
+//! for (path, feature) in report.features.iter() {
+//!     println!(
+//!         "{}:{}: {:?} requires {:?}",
+//!         path.display(),
+//!         feature.line,
+//!         feature.value.feature,
+//!         feature.value.level
+//!     );
+//! }
+//! println!("requires {:?}", report.level);
+//! ```
+
+use alloc::vec::Vec;
+use std::path::Path;
+
+use crate::{
+    descript::SerikoSettings,
+    error::Located,
+    project::{ProjectFile, ShellProject},
+    scope::{split_scope, Scope},
+    seriko::AnimationCollision,
+    surfaces::{is_surface_header, SurfaceBody},
+};
+
+/// Compatibility level of baseware, from the oldest.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub enum CompatLevel {
+    /// Surfaces with elements and collisions only, read by any baseware.
+    #[default]
+    Basic,
+    /// SERIKO/2.0, with `animationN.*` definitions and their options.
+    Seriko2,
+    /// Extensions of SSP.
+    Ssp,
+}
+
+/// Feature detected by [`required_level`].
+///
+/// [`required_level`]: crate::compat::required_level
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum Feature {
+    /// `collisionexN` and `animationN.collisionexM`
+    CollisionEx,
+    /// `seriko.use_self_alpha,1`
+    SelfAlpha,
+    /// Interval of more than one trigger, such as `sometimes+talk,3`.
+    CompoundInterval,
+    /// `animationN.option`
+    AnimationOption,
+    /// Keys of `charN` scopes, where `N` is `2` or more.
+    CharScope,
+}
+
+/// Entry of [`FEATURE_LEVELS`].
+///
+/// [`FEATURE_LEVELS`]: crate::compat::FEATURE_LEVELS
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FeatureLevel {
+    pub feature: Feature,
+    /// Minimum level reading the feature.
+    pub level: CompatLevel,
+}
+
+/// Levels of features used by [`required_level`].
+///
+/// Pass another table to [`required_level_with`] to change them.
+/// Features not in the table are not reported.
+///
+/// [`required_level`]: crate::compat::required_level
+/// [`required_level_with`]: crate::compat::required_level_with
+pub const FEATURE_LEVELS: &[FeatureLevel] = &[
+    FeatureLevel {
+        feature: Feature::AnimationOption,
+        level: CompatLevel::Seriko2,
+    },
+    FeatureLevel {
+        feature: Feature::CollisionEx,
+        level: CompatLevel::Ssp,
+    },
+    FeatureLevel {
+        feature: Feature::SelfAlpha,
+        level: CompatLevel::Ssp,
+    },
+    FeatureLevel {
+        feature: Feature::CompoundInterval,
+        level: CompatLevel::Ssp,
+    },
+    FeatureLevel {
+        feature: Feature::CharScope,
+        level: CompatLevel::Ssp,
+    },
+];
+
+/// Result of [`required_level`].
+///
+/// [`required_level`]: crate::compat::required_level
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CompatReport<'a> {
+    /// Features in order of their first appearance, each with the file and the line of it.
+    pub features: Vec<(&'a Path, Located<FeatureLevel>)>,
+    /// Maximum level of `features`, or [`CompatLevel::Basic`] without them.
+    ///
+    /// [`CompatLevel::Basic`]: crate::compat::CompatLevel::Basic
+    pub level: CompatLevel,
+}
+
+/// Returns the features used by `project` with the levels of [`FEATURE_LEVELS`].
+///
+/// [`FEATURE_LEVELS`]: crate::compat::FEATURE_LEVELS
+pub fn required_level(project: &ShellProject) -> CompatReport<'_> {
+    required_level_with(project, FEATURE_LEVELS)
+}
+
+/// Same as [`required_level`], with the levels of `table`.
+///
+/// Features are detected from entries of `descript.txt` and lines of surface blocks,
+/// each parsed as [`SurfaceBody::apply_line_with`] does. Malformed lines are skipped,
+/// as they are reported by [`ProjectFile::outcome`].
+///
+/// [`required_level`]: crate::compat::required_level
+/// [`SurfaceBody::apply_line_with`]: crate::surfaces::SurfaceBody::apply_line_with
+/// [`ProjectFile::outcome`]: crate::project::ProjectFile::outcome
+pub fn required_level_with<'a>(
+    project: &'a ShellProject,
+    table: &[FeatureLevel],
+) -> CompatReport<'a> {
+    let mut report = CompatReport::default();
+    let mut found = |file: &'a ProjectFile, line: usize, feature: Feature| {
+        if report
+            .features
+            .iter()
+            .any(|(_, f)| f.value.feature == feature)
+        {
+            return;
+        }
+        if let Some(entry) = table.iter().find(|e| e.feature == feature) {
+            report.level = report.level.max(entry.level);
+            report
+                .features
+                .push((file.path(), Located::new(line, *entry)));
+        }
+    };
+
+    let descript = project.descript();
+    for entry in descript.entries() {
+        let segments = entry.value.key_segments();
+        let mut seriko = SerikoSettings::default();
+        if let Ok(true) = seriko.apply(&segments, entry.value.value) {
+            if seriko.use_self_alpha {
+                found(descript, entry.line, Feature::SelfAlpha);
+            }
+        }
+        if let Some((Scope::Char(n), _)) = split_scope(&segments) {
+            if n >= 2 {
+                found(descript, entry.line, Feature::CharScope);
+            }
+        }
+    }
+
+    for file in project.surfaces() {
+        for block in file.blocks() {
+            if !is_surface_header(block.header) {
+                continue;
+            }
+            for line in block.body.iter() {
+                let mut body = SurfaceBody::default();
+                if body.apply_line_with(line.text, file.options()).is_err() {
+                    continue;
+                }
+                for feature in features_of(&body) {
+                    found(file, line.number, feature);
+                }
+            }
+        }
+    }
+
+    report
+}
+
+/// Returns features used by `body`, the definitions of one line.
+fn features_of(body: &SurfaceBody) -> Vec<Feature> {
+    let mut features = Vec::new();
+    let animations = body.animations.values();
+    if !body.collisionexes.is_empty()
+        || animations
+            .clone()
+            .flat_map(|a| a.collisions.iter())
+            .any(|c| matches!(c, AnimationCollision::Ex(_)))
+    {
+        features.push(Feature::CollisionEx);
+    }
+    if animations
+        .clone()
+        .any(|a| a.intervals.as_ref().is_some_and(|i| i.0.len() > 1))
+    {
+        features.push(Feature::CompoundInterval);
+    }
+    if animations.clone().any(|a| !a.options.is_empty()) {
+        features.push(Feature::AnimationOption);
+    }
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::{options::ParseOptions, project::load_shell_dir};
+
+    const DESCRIPT: &str = "charset,UTF-8\r\nsakura.name,Sakura\r\nkero.name,Unyu\r\n";
+    const SURFACES: &str = "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,body.png,0,0\r\ncollision0,10,10,50,50,Head\r\n}\r\n";
+
+    fn load(descript: &str, surfaces: &str) -> (tempfile::TempDir, ShellProject) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("descript.txt"), descript).unwrap();
+        std::fs::write(dir.path().join("surfaces.txt"), surfaces).unwrap();
+        let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+        (dir, project)
+    }
+
+    fn located<'a>(report: &CompatReport<'a>) -> Vec<(&'a str, usize, Feature, CompatLevel)> {
+        report
+            .features
+            .iter()
+            .map(|(path, f)| {
+                (
+                    path.file_name().unwrap().to_str().unwrap(),
+                    f.line,
+                    f.value.feature,
+                    f.value.level,
+                )
+            })
+            .collect()
+    }
+
+    mod required_level {
+        use super::*;
+
+        #[test]
+        fn success_when_basic_shell() {
+            let (_dir, project) = load(DESCRIPT, SURFACES);
+            let report = required_level(&project);
+            assert!(report.features.is_empty());
+            assert_eq!(report.level, CompatLevel::Basic);
+        }
+
+        #[test]
+        fn detected_when_collisionex_and_compound_interval() {
+            let surfaces = [
+                SURFACES,
+                "surface1\r\n{\r\nelement0,base,body.png,0,0\r\nanimation0.interval,sometimes+talk,3\r\nanimation0.pattern0,overlay,100,0,0,0\r\ncollisionex0,Bust,rect,10,60,50,90\r\ncollisionex1,Head,ellipse,0,0,40,40\r\n}\r\n",
+            ]
+            .concat();
+            let descript = [DESCRIPT, "seriko.use_self_alpha,0\r\nchar2.name,Third\r\n"].concat();
+            let (_dir, project) = load(&descript, &surfaces);
+            let report = required_level(&project);
+
+            assert_eq!(
+                located(&report),
+                vec![
+                    ("descript.txt", 5, Feature::CharScope, CompatLevel::Ssp),
+                    (
+                        "surfaces.txt",
+                        10,
+                        Feature::CompoundInterval,
+                        CompatLevel::Ssp
+                    ),
+                    ("surfaces.txt", 12, Feature::CollisionEx, CompatLevel::Ssp),
+                ]
+            );
+            assert_eq!(report.level, CompatLevel::Ssp);
+        }
+
+        #[test]
+        fn success_when_custom_table() {
+            let surfaces = [
+                SURFACES,
+                "surface1\r\n{\r\nanimation0.interval,always\r\nanimation0.option,exclusive\r\n}\r\n",
+            ]
+            .concat();
+            let (_dir, project) = load(DESCRIPT, &surfaces);
+            assert_eq!(
+                located(&required_level(&project)),
+                vec![(
+                    "surfaces.txt",
+                    10,
+                    Feature::AnimationOption,
+                    CompatLevel::Seriko2
+                )]
+            );
+
+            let report = required_level_with(&project, &FEATURE_LEVELS[1..]);
+            assert!(report.features.is_empty());
+            assert_eq!(report.level, CompatLevel::Basic);
+        }
+    }
+}
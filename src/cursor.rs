@@ -0,0 +1,316 @@
+//! # `cursor`
+//!
+//! Mouse cursors bound to collisions.
+//! `<scope>.cursor.mouseupN,collision,filename` in `descript.txt`,
+//! or unscoped `cursor.mouseupN,collision,filename` inside a surface block.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::cursor::{merge_cursors, BlockCursors, Cursors};
+//! use shell_parser_common_rs::scope::Scope;
+//!
+//! let mut descript = Cursors::default();
+//! descript.apply(&["sakura", "cursor", "mouseup0"], "Head,head.cur").unwrap();
+//!
+//! let mut block = BlockCursors::default();
+//! block.apply(&["cursor", "mouseup0"], "Head,head_smile.cur").unwrap();
+//!
+//! let merged = merge_cursors(descript.get(Scope::Sakura), &block, &["Head"]);
+//! assert_eq!(merged.cursors[0].filename, "head_smile.cur");
+//! assert!(merged.unmatched.is_empty());
+//! ```
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag},
+    combinator::{all_consuming, value},
+    sequence::{pair, separated_pair},
+    IResult,
+};
+
+use crate::{
+    scope::{split_scope, Scope},
+    value::{parse_filename, parse_u32, separator},
+    ShellParseError,
+};
+
+/// Kind of the mouse state the cursor is shown in.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum CursorKind {
+    /// `mouseupN`
+    MouseUp,
+    /// `mousedownN`
+    MouseDown,
+}
+
+/// Cursor bound to a collision.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CursorBinding {
+    pub kind: CursorKind,
+    /// `N` of `mouseupN`.
+    pub index: u32,
+    pub collision: String,
+    pub filename: String,
+}
+
+impl CursorBinding {
+    /// Parses the key segment after `cursor` (e.g. `mouseup0`) and the value.
+    pub(crate) fn parse<'a>(
+        segment: &str,
+        value: &'a str,
+    ) -> Option<Result<CursorBinding, nom::Err<ShellParseError<'a>>>> {
+        let (_, (kind, index)) = all_consuming(cursor_kind)(segment).ok()?;
+
+        Some(
+            all_consuming(cursor_value)(value).map(|(_, (collision, filename))| CursorBinding {
+                kind,
+                index,
+                collision: collision.to_string(),
+                filename: filename.to_string(),
+            }),
+        )
+    }
+}
+
+/// Checks whether `segment` is the key segment after `cursor`, such as `mouseup0`.
+#[cfg(feature = "surfaces")]
+pub(crate) fn is_cursor_segment(segment: &str) -> bool {
+    all_consuming(cursor_kind)(segment).is_ok()
+}
+
+fn cursor_kind(input: &str) -> IResult<&str, (CursorKind, u32), ShellParseError<'_>> {
+    pair(
+        alt((
+            value(CursorKind::MouseUp, tag("mouseup")),
+            value(CursorKind::MouseDown, tag("mousedown")),
+        )),
+        parse_u32,
+    )(input)
+}
+
+fn cursor_value(input: &str) -> IResult<&str, (&str, &str), ShellParseError<'_>> {
+    separated_pair(is_not(",\r\n"), separator, parse_filename)(input)
+}
+
+/// Cursors in `descript.txt`, per scope.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Cursors {
+    cursors: BTreeMap<Scope, Vec<CursorBinding>>,
+}
+
+impl Cursors {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a scoped cursor key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let Some((scope, ["cursor", segment])) = split_scope(key_segments) else {
+            return Ok(false);
+        };
+        let Some(binding) = CursorBinding::parse(segment, value) else {
+            return Ok(false);
+        };
+
+        self.cursors.entry(scope).or_default().push(binding?);
+        Ok(true)
+    }
+
+    /// Returns cursors of `scope` in definition order.
+    pub fn get(&self, scope: Scope) -> &[CursorBinding] {
+        self.cursors
+            .get(&scope)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Iterates cursors ordered by scope, each in definition order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Scope, &[CursorBinding])> {
+        self.cursors.iter().map(|(scope, v)| (scope, v.as_slice()))
+    }
+}
+
+/// Cursors inside a surface block.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BlockCursors {
+    /// `cursor.*`, in order.
+    pub cursors: Vec<CursorBinding>,
+}
+
+impl BlockCursors {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not an unscoped cursor key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let ["cursor", segment] = key_segments else {
+            return Ok(false);
+        };
+        let Some(binding) = CursorBinding::parse(segment, value) else {
+            return Ok(false);
+        };
+
+        self.cursors.push(binding?);
+        Ok(true)
+    }
+}
+
+/// Result of [`merge_cursors`].
+///
+/// [`merge_cursors`]: crate::cursor::merge_cursors
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MergedCursors {
+    /// Cursors of `descript.txt` in order, each replaced in place by the block cursor of its kind and collision,
+    /// followed by the other block cursors in order.
+    pub cursors: Vec<CursorBinding>,
+    /// Collision names of block cursors not found in the block, in order.
+    pub unmatched: Vec<String>,
+}
+
+/// Layers `block` cursors over `descript` cursors.
+///
+/// A block cursor replaces the descript cursor of the same kind and collision name.
+/// Block cursors naming collisions not in `collisions` are reported, but still merged.
+pub fn merge_cursors(
+    descript: &[CursorBinding],
+    block: &BlockCursors,
+    collisions: &[&str],
+) -> MergedCursors {
+    let mut merged = MergedCursors {
+        cursors: descript.to_vec(),
+        unmatched: Vec::new(),
+    };
+
+    for binding in block.cursors.iter() {
+        if !collisions.contains(&binding.collision.as_str()) {
+            merged.unmatched.push(binding.collision.clone());
+        }
+
+        match merged
+            .cursors
+            .iter_mut()
+            .find(|c| c.kind == binding.kind && c.collision == binding.collision)
+        {
+            Some(c) => *c = binding.clone(),
+            None => merged.cursors.push(binding.clone()),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod cursors {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_entries() {
+            let mut cursors = Cursors::default();
+            assert!(cursors
+                .apply(&["sakura", "cursor", "mouseup0"], "Head,head.cur")
+                .unwrap());
+            assert!(cursors
+                .apply(&["kero", "cursor", "mousedown1"], "Face,face.cur")
+                .unwrap());
+
+            assert_eq!(
+                cursors.get(Scope::Sakura),
+                &[CursorBinding {
+                    kind: CursorKind::MouseUp,
+                    index: 0,
+                    collision: "Head".to_string(),
+                    filename: "head.cur".to_string(),
+                }]
+            );
+            assert_eq!(cursors.get(Scope::Kero)[0].kind, CursorKind::MouseDown);
+            assert!(cursors.get(Scope::Char(2)).is_empty());
+        }
+
+        #[test]
+        fn ignored_when_unscoped_or_unknown() {
+            let mut cursors = Cursors::default();
+            assert!(!cursors
+                .apply(&["cursor", "mouseup0"], "Head,head.cur")
+                .unwrap());
+            assert!(!cursors
+                .apply(&["sakura", "cursor", "mousewheel0"], "Head,head.cur")
+                .unwrap());
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let mut cursors = Cursors::default();
+            assert!(cursors
+                .apply(&["sakura", "cursor", "mouseup0"], "Head")
+                .is_err());
+        }
+    }
+
+    mod merge_cursors {
+        use super::*;
+
+        fn descript() -> Cursors {
+            let mut cursors = Cursors::default();
+            cursors
+                .apply(&["sakura", "cursor", "mouseup0"], "Head,head.cur")
+                .unwrap();
+            cursors
+        }
+
+        #[test]
+        fn block_only_cursor() {
+            let mut block = BlockCursors::default();
+            assert!(block
+                .apply(&["cursor", "mousedown0"], "Bust,bust.cur")
+                .unwrap());
+
+            let result = merge_cursors(descript().get(Scope::Sakura), &block, &["Head", "Bust"]);
+            assert_eq!(result.cursors.len(), 2);
+            assert_eq!(result.cursors[1].collision, "Bust");
+            assert!(result.unmatched.is_empty());
+        }
+
+        #[test]
+        fn block_overrides_descript() {
+            let mut block = BlockCursors::default();
+            block
+                .apply(&["cursor", "mouseup3"], "Head,other.cur")
+                .unwrap();
+
+            let result = merge_cursors(descript().get(Scope::Sakura), &block, &["Head"]);
+            assert_eq!(result.cursors.len(), 1);
+            assert_eq!(result.cursors[0].filename, "other.cur");
+        }
+
+        #[test]
+        fn unmatched_collision() {
+            let mut block = BlockCursors::default();
+            block
+                .apply(&["cursor", "mouseup0"], "Tail,tail.cur")
+                .unwrap();
+
+            let result = merge_cursors(descript().get(Scope::Sakura), &block, &["Head"]);
+            assert_eq!(result.unmatched, vec!["Tail".to_string()]);
+        }
+    }
+}
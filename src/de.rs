@@ -0,0 +1,638 @@
+//! # `de`
+//!
+//! Deserialization of entries into user structs with serde. Needs the `serde` feature.
+//!
+//! Dotted keys are nested fields, and indexed keys such as `element0` fill
+//! a `Vec` (in order of the index) or a `HashMap<u32, _>` field named `element`.
+//! Numbers and booleans are parsed by the parsers in [`value`].
+//!
+//! [`value`]: crate::value
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Deserialize;
+//! use shell_parser_common_rs::de::from_entries;
+//! use shell_parser_common_rs::error::Located;
+//! use shell_parser_common_rs::kv::KeyValue;
+//!
+//! #[derive(Deserialize)]
+//! struct Descript {
+//!     name: String,
+//!     sakura: Scoped,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Scoped {
+//!     balloon: Balloon,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Balloon {
+//!     offsetx: i32,
+//!     offsety: Option<i32>,
+//! }
+//!
+//! let entries = vec![
+//!     Located::new(1, KeyValue::new("name", "master")),
+//!     Located::new(2, KeyValue::new("sakura.balloon.offsetx", "-10")),
+//! ];
+//! let descript: Descript = from_entries(&entries).unwrap();
+//!
+//! assert_eq!(descript.name, "master");
+//! assert_eq!(descript.sakura.balloon.offsetx, -10);
+//! assert_eq!(descript.sakura.balloon.offsety, None);
+//! ```
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
+
+use nom::{combinator::all_consuming, IResult};
+use serde::de::{
+    self, value::StrDeserializer, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess,
+    SeqAccess, Visitor,
+};
+
+use crate::{
+    error::Located,
+    kv::{segment_index, KeyValue},
+    value::{parse_bool, parse_i32, parse_u32},
+    ShellParseError,
+};
+
+/// `DeError` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DeError {
+    /// Key where deserialization failed, or the prefix of a nested struct.
+    pub key: Option<String>,
+    /// Line number of the entry where deserialization failed.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl DeError {
+    /// Sets the location unless it is already set. The empty key is of the root.
+    fn at(mut self, key: &str, line: Option<usize>) -> DeError {
+        if self.key.is_none() && !key.is_empty() {
+            self.key = Some(key.to_string());
+            self.line = line;
+        }
+        self
+    }
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        match &self.key {
+            Some(key) => write!(f, "{:?}: {}", key, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl core::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError {
+            key: None,
+            line: None,
+            message: msg.to_string(),
+        }
+    }
+}
+
+/// Deserializes `entries` into `T`. A later entry with the same key wins.
+pub fn from_entries<T: DeserializeOwned>(entries: &[Located<KeyValue<'_>>]) -> Result<T, DeError> {
+    let mut root = Node::default();
+    for entry in entries.iter() {
+        let mut node = &mut root;
+        for segment in entry.value.key.split('.') {
+            let key = join(&node.key, segment);
+            node = node.children.entry(segment).or_insert_with(|| Node {
+                key,
+                ..Node::default()
+            });
+        }
+        node.entry = Some(Located::new(entry.line, entry.value.value));
+    }
+
+    T::deserialize(Source::Node(&root))
+}
+
+fn join(prefix: &str, segment: &str) -> String {
+    if prefix.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{}.{}", prefix, segment)
+    }
+}
+
+/// Entries as a tree of key segments.
+#[derive(Debug, Default)]
+struct Node<'a> {
+    /// Full key of the node.
+    key: String,
+    entry: Option<Located<&'a str>>,
+    children: BTreeMap<&'a str, Node<'a>>,
+}
+
+enum Source<'s> {
+    Node(&'s Node<'s>),
+    /// Children with indexed keys, in order of the index.
+    Indexed(String, Vec<(u32, &'s Node<'s>)>),
+    /// One field of a comma-separated value.
+    Text(Text<'s>),
+}
+
+#[derive(Clone, Copy)]
+struct Text<'s> {
+    key: &'s str,
+    line: usize,
+    text: &'s str,
+}
+
+impl<'s> Text<'s> {
+    fn error(&self, message: String) -> DeError {
+        DeError {
+            key: Some(self.key.to_string()),
+            line: Some(self.line),
+            message,
+        }
+    }
+
+    fn parse<T>(
+        &self,
+        name: &str,
+        parser: impl FnMut(&'s str) -> IResult<&'s str, T, ShellParseError<'s>>,
+    ) -> Result<T, DeError> {
+        all_consuming(parser)(self.text)
+            .map(|(_, v)| v)
+            .map_err(|_| self.error(format!("invalid {} {:?}", name, self.text)))
+    }
+
+    fn parse_str<T: FromStr>(&self, name: &str) -> Result<T, DeError> {
+        self.text
+            .parse()
+            .map_err(|_| self.error(format!("invalid {} {:?}", name, self.text)))
+    }
+
+    fn fields(&self) -> Vec<Source<'s>> {
+        self.text
+            .split(',')
+            .map(|text| Source::Text(Text { text, ..*self }))
+            .collect()
+    }
+}
+
+impl<'s> Source<'s> {
+    fn location(&self) -> (&str, Option<usize>) {
+        match self {
+            Source::Node(node) => (&node.key, node.entry.as_ref().map(|e| e.line)),
+            Source::Indexed(key, _) => (key, None),
+            Source::Text(text) => (text.key, Some(text.line)),
+        }
+    }
+
+    fn text(&self) -> Result<Text<'s>, DeError> {
+        match self {
+            Source::Node(node) => match &node.entry {
+                Some(entry) => Ok(Text {
+                    key: &node.key,
+                    line: entry.line,
+                    text: entry.value,
+                }),
+                None => Err(de::Error::custom("expected a value, found nested keys")),
+            },
+            Source::Indexed(..) => Err(de::Error::custom("expected a value, found indexed keys")),
+            Source::Text(text) => Ok(*text),
+        }
+    }
+
+    fn children(&self) -> Result<&'s BTreeMap<&'s str, Node<'s>>, DeError> {
+        match self {
+            Source::Node(node) => Ok(&node.children),
+            _ => Err(de::Error::custom("expected nested keys")),
+        }
+    }
+}
+
+macro_rules! deserialize_from_str {
+    ($($method:ident => $visit:ident, $t:ty;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                visitor.$visit(self.text()?.parse_str::<$t>(stringify!($t))?)
+            }
+        )*
+    };
+}
+
+impl<'de, 's> de::Deserializer<'de> for Source<'s> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self {
+            Source::Node(node) if node.children.is_empty() => match &node.entry {
+                Some(entry) => visitor.visit_str(entry.value),
+                None => visitor.visit_unit(),
+            },
+            Source::Node(_) => self.deserialize_map(visitor),
+            Source::Indexed(..) => self.deserialize_seq(visitor),
+            Source::Text(text) => visitor.visit_str(text.text),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_bool(self.text()?.parse("bool", parse_bool)?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_i32(self.text()?.parse("i32", parse_i32)?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_u32(self.text()?.parse("u32", parse_u32)?)
+    }
+
+    deserialize_from_str! {
+        deserialize_i8 => visit_i8, i8;
+        deserialize_i16 => visit_i16, i16;
+        deserialize_i64 => visit_i64, i64;
+        deserialize_u8 => visit_u8, u8;
+        deserialize_u16 => visit_u16, u16;
+        deserialize_u64 => visit_u64, u64;
+        deserialize_f32 => visit_f32, f32;
+        deserialize_f64 => visit_f64, f64;
+        deserialize_char => visit_char, char;
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_str(self.text()?.text)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_bytes(self.text()?.text.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Empty values are `None`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match &self {
+            Source::Node(node) if node.children.is_empty() => match &node.entry {
+                Some(entry) if !entry.value.is_empty() => visitor.visit_some(self),
+                _ => visitor.visit_none(),
+            },
+            Source::Text(text) if text.text.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// Indexed keys in order, or fields of a comma-separated value.
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let items = match self {
+            Source::Indexed(_, items) => items.into_iter().map(|(_, n)| Source::Node(n)).collect(),
+            _ => self.text()?.fields(),
+        };
+        visitor.visit_seq(Items(items.into_iter()))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_seq(visitor)
+    }
+
+    /// Indexed keys by index, or nested keys by segment.
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self {
+            Source::Indexed(_, items) => {
+                let items: Vec<_> = items
+                    .into_iter()
+                    .map(|(i, n)| (i, Source::Node(n)))
+                    .collect();
+                visitor.visit_map(Entries::new(items))
+            }
+            _ => {
+                let items: Vec<_> = self
+                    .children()?
+                    .iter()
+                    .map(|(segment, node)| (*segment, Source::Node(node)))
+                    .collect();
+                visitor.visit_map(Entries::new(items))
+            }
+        }
+    }
+
+    /// Fields are nested keys, or indexed keys prefixed by the field name.
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let Source::Node(node) = self else {
+            let (key, line) = self.location();
+            return Err(<DeError as de::Error>::custom("expected nested keys").at(key, line));
+        };
+
+        let mut items = Vec::new();
+        for field in fields.iter() {
+            if let Some(child) = node.children.get(field) {
+                items.push((*field, Source::Node(child)));
+                continue;
+            }
+
+            let mut indexed: Vec<_> = node
+                .children
+                .iter()
+                .filter_map(|(segment, child)| segment_index(segment, field).map(|i| (i, child)))
+                .collect();
+            if !indexed.is_empty() {
+                indexed.sort_by_key(|(i, _)| *i);
+                items.push((*field, Source::Indexed(join(&node.key, field), indexed)));
+            }
+        }
+
+        visitor
+            .visit_map(Entries::new(items))
+            .map_err(|e| e.at(&node.key, None))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let text = self.text()?;
+        let variant: StrDeserializer<'_, DeError> = text.text.into_deserializer();
+        visitor
+            .visit_enum(variant)
+            .map_err(|e| e.at(text.key, Some(text.line)))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+}
+
+struct Items<'s>(alloc::vec::IntoIter<Source<'s>>);
+
+impl<'de, 's> SeqAccess<'de> for Items<'s> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        match self.0.next() {
+            Some(source) => {
+                let (key, line) = source.location();
+                let (key, line) = (key.to_string(), line);
+                seed.deserialize(source)
+                    .map(Some)
+                    .map_err(|e| e.at(&key, line))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct Entries<'s, K> {
+    items: alloc::vec::IntoIter<(K, Source<'s>)>,
+    value: Option<Source<'s>>,
+}
+
+impl<'s, K> Entries<'s, K> {
+    fn new(items: Vec<(K, Source<'s>)>) -> Entries<'s, K> {
+        Entries {
+            items: items.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de, 's, K: IntoDeserializer<'de, DeError>> MapAccess<'de> for Entries<'s, K> {
+    type Error = DeError;
+
+    fn next_key_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        match self.items.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<T::Value, DeError> {
+        let source = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value without key"))?;
+        let (key, line) = source.location();
+        let (key, line) = (key.to_string(), line);
+        seed.deserialize(source).map_err(|e| e.at(&key, line))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.items.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use serde::Deserialize;
+
+    fn located<'a>(case: &[(&'a str, &'a str)]) -> Vec<Located<KeyValue<'a>>> {
+        case.iter()
+            .enumerate()
+            .map(|(i, (k, v))| Located::new(i + 1, KeyValue::new(k, v)))
+            .collect()
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Descript {
+        charset: String,
+        name: String,
+        sakura: Scoped,
+        kero: Option<Scoped>,
+        seriko: Option<Seriko>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Scoped {
+        balloon: Balloon,
+        bindgroup: Option<HashMap<u32, BindGroup>>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Balloon {
+        offsetx: i32,
+        offsety: Option<i32>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct BindGroup {
+        name: (String, String),
+        default: Option<bool>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Seriko {
+        use_self_alpha: bool,
+    }
+
+    mod from_entries {
+        use super::*;
+
+        #[test]
+        fn success_when_nested_scoped_fields() {
+            let entries = located(&[
+                ("charset", "UTF-8"),
+                ("name", "master"),
+                ("sakura.balloon.offsetx", "10"),
+                ("sakura.balloon.offsety", "-20"),
+                ("sakura.bindgroup3.name", "Hat,Ribbon"),
+                ("sakura.bindgroup0.name", "Arm,Bag"),
+                ("sakura.bindgroup0.default", "true"),
+                ("kero.balloon.offsetx", "5"),
+                ("seriko.use_self_alpha", "1"),
+                ("homeurl", "ignored"),
+            ]);
+            let descript: Descript = from_entries(&entries).unwrap();
+
+            assert_eq!(descript.charset, "UTF-8");
+            assert_eq!(descript.sakura.balloon.offsety, Some(-20));
+            assert_eq!(descript.kero.unwrap().balloon.offsetx, 5);
+            assert_eq!(
+                descript.seriko,
+                Some(Seriko {
+                    use_self_alpha: true
+                })
+            );
+
+            let groups = descript.sakura.bindgroup.unwrap();
+            assert_eq!(groups.len(), 2);
+            assert_eq!(groups[&3].name, ("Hat".to_string(), "Ribbon".to_string()));
+            assert_eq!(groups[&0].default, Some(true));
+        }
+
+        #[test]
+        fn success_when_indexed_vec() {
+            #[derive(Debug, Deserialize)]
+            struct Body {
+                element: Vec<(String, String, i32, i32)>,
+                collision: Vec<Vec<String>>,
+            }
+
+            let entries = located(&[
+                ("element2", "overlay,c.png,0,0"),
+                ("element0", "base,a.png,0,0"),
+                ("element10", "overlay,d.png,5,-5"),
+                ("collision0", "0,0,10,10,Head"),
+            ]);
+            let body: Body = from_entries(&entries).unwrap();
+
+            let filenames: Vec<_> = body.element.iter().map(|e| e.1.as_str()).collect();
+            assert_eq!(filenames, vec!["a.png", "c.png", "d.png"]);
+            assert_eq!(body.element[2].3, -5);
+            assert_eq!(body.collision[0][4], "Head");
+        }
+
+        #[test]
+        fn failed_when_type_mismatch() {
+            let entries = located(&[
+                ("charset", "UTF-8"),
+                ("name", "master"),
+                ("sakura.balloon.offsetx", "ten"),
+            ]);
+            let err = from_entries::<Descript>(&entries).unwrap_err();
+
+            assert_eq!(err.key.as_deref(), Some("sakura.balloon.offsetx"));
+            assert_eq!(err.line, Some(3));
+            assert_eq!(
+                err.to_string(),
+                "line 3: \"sakura.balloon.offsetx\": invalid i32 \"ten\""
+            );
+        }
+
+        #[test]
+        fn failed_when_missing_field() {
+            let entries = located(&[("charset", "UTF-8"), ("sakura.balloon.offsetx", "1")]);
+            let err = from_entries::<Descript>(&entries).unwrap_err();
+            assert_eq!(err.key, None);
+            assert_eq!(err.to_string(), "missing field `name`");
+
+            let entries = located(&[
+                ("charset", "UTF-8"),
+                ("name", "master"),
+                ("sakura.balloon.offsety", "1"),
+            ]);
+            let err = from_entries::<Descript>(&entries).unwrap_err();
+            assert_eq!(err.key.as_deref(), Some("sakura.balloon"));
+            assert_eq!(err.line, None);
+        }
+    }
+}
@@ -0,0 +1,836 @@
+//! # `descript`
+//!
+//! Settings defined in `descript.txt`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::descript::BalloonOffsets;
+//! use shell_parser_common_rs::scope::Scope;
+//!
+//! let mut offsets = BalloonOffsets::default();
+//! offsets.apply(&["sakura", "balloon", "offsetx"], "-10").unwrap();
+//!
+//! assert_eq!(offsets.get(Scope::Sakura).unwrap().x, Some(-10));
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{all_consuming, value},
+    error::context,
+    IResult,
+};
+
+use crate::{
+    binding::BindGroups,
+    charset::{parse_charset, Charset},
+    cursor::Cursors,
+    error::{Located, ParseFailure},
+    keys::{classify_key, KnownKey},
+    kv::KeyValue,
+    scope::{split_scope, FallbackChain, Scope, ScopedSettings},
+    value::{parse_bool, parse_module_filename, parse_u32, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// `BalloonAlignment` type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum BalloonAlignment {
+    None,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// parser for [`BalloonAlignment`].
+///
+/// [`BalloonAlignment`]: crate::descript::BalloonAlignment
+pub fn parse_balloon_alignment(
+    input: &str,
+) -> IResult<&str, BalloonAlignment, ShellParseError<'_>> {
+    alt((
+        value(BalloonAlignment::None, tag("none")),
+        value(BalloonAlignment::Left, tag("left")),
+        value(BalloonAlignment::Right, tag("right")),
+        value(BalloonAlignment::Top, tag("top")),
+        value(BalloonAlignment::Bottom, tag("bottom")),
+    ))(input)
+}
+
+/// Balloon offset and alignment of one scope.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BalloonOffset {
+    /// `balloon.offsetx`
+    pub x: Option<i32>,
+    /// `balloon.offsety`
+    pub y: Option<i32>,
+    /// `balloon.alignment`
+    pub alignment: Option<BalloonAlignment>,
+}
+
+impl BalloonOffset {
+    /// Applies one entry, `key_segments` without the scope prefix
+    /// (e.g. `["balloon", "offsetx"]`).
+    ///
+    /// Returns `Ok(false)` when the key is not handled here.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        self.apply_with_numbers(
+            key_segments,
+            value,
+            &NumericReader::new(NumericPolicy::DEFAULT),
+        )
+    }
+
+    /// Same as [`BalloonOffset::apply`], reading numbers by `numbers`.
+    ///
+    /// [`BalloonOffset::apply`]: crate::descript::BalloonOffset::apply
+    pub(crate) fn apply_with_numbers<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+        numbers: &NumericReader,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match key_segments {
+            ["balloon", "offsetx"] => {
+                self.x = Some(all_consuming(|i| numbers.i32(i))(value)?.1);
+            }
+            ["balloon", "offsety"] => {
+                self.y = Some(all_consuming(|i| numbers.i32(i))(value)?.1);
+            }
+            ["balloon", "alignment"] => {
+                self.alignment = Some(all_consuming(parse_balloon_alignment)(value)?.1);
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the values of `self`, falling back to `base` field by field.
+    pub fn or(&self, base: &BalloonOffset) -> BalloonOffset {
+        BalloonOffset {
+            x: self.x.or(base.x),
+            y: self.y.or(base.y),
+            alignment: self.alignment.or(base.alignment),
+        }
+    }
+}
+
+/// Balloon offsets per scope, defined by `<scope>.balloon.*` keys.
+///
+/// Offsets of `charN` fall back to those of `kero` by [`FallbackChain::CHAR_TO_KERO`].
+///
+/// [`FallbackChain::CHAR_TO_KERO`]: crate::scope::FallbackChain::CHAR_TO_KERO
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BalloonOffsets {
+    offsets: ScopedSettings<BalloonOffset>,
+}
+
+impl Default for BalloonOffsets {
+    fn default() -> Self {
+        BalloonOffsets {
+            offsets: ScopedSettings::new(FallbackChain::CHAR_TO_KERO),
+        }
+    }
+}
+
+impl BalloonOffsets {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a scoped balloon key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let Some((scope, rest)) = split_scope(key_segments) else {
+            return Ok(false);
+        };
+
+        let mut offset = self.offsets.get(scope).copied().unwrap_or_default();
+        let applied = offset.apply(rest, value)?;
+        if applied {
+            self.offsets.insert(scope, offset);
+        }
+
+        Ok(applied)
+    }
+
+    /// Returns the offset of `scope`.
+    pub fn get(&self, scope: Scope) -> Option<&BalloonOffset> {
+        self.offsets.get(scope)
+    }
+
+    /// Returns the offset of `scope`, or of the scope it falls back to.
+    pub fn resolve(&self, scope: Scope) -> Option<&BalloonOffset> {
+        self.offsets.resolve(scope)
+    }
+
+    /// Iterates offsets ordered by scope.
+    pub fn iter(&self) -> impl Iterator<Item = (&Scope, &BalloonOffset)> {
+        self.offsets.iter()
+    }
+}
+
+/// Default surfaces per scope, defined by `<scope>.seriko.defaultsurface` keys.
+///
+/// Surfaces of `charN` fall back to that of `kero` by [`FallbackChain::CHAR_TO_KERO`].
+///
+/// [`FallbackChain::CHAR_TO_KERO`]: crate::scope::FallbackChain::CHAR_TO_KERO
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DefaultSurfaces {
+    surfaces: ScopedSettings<u32>,
+}
+
+impl Default for DefaultSurfaces {
+    fn default() -> Self {
+        DefaultSurfaces {
+            surfaces: ScopedSettings::new(FallbackChain::CHAR_TO_KERO),
+        }
+    }
+}
+
+impl DefaultSurfaces {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a scoped default surface.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let Some((scope, ["seriko", "defaultsurface"])) = split_scope(key_segments) else {
+            return Ok(false);
+        };
+
+        let (_, id) = all_consuming(parse_u32)(value)?;
+        self.surfaces.insert(scope, id);
+        Ok(true)
+    }
+
+    /// Returns the default surface of `scope`, as defined.
+    pub fn get(&self, scope: Scope) -> Option<u32> {
+        self.surfaces.get(scope).copied()
+    }
+
+    /// Returns the default surface of `scope`, or of the scope it falls back to.
+    pub fn resolve(&self, scope: Scope) -> Option<u32> {
+        self.surfaces.resolve(scope).copied()
+    }
+
+    /// Iterates default surfaces ordered by scope.
+    pub fn iter(&self) -> impl Iterator<Item = (&Scope, &u32)> {
+        self.surfaces.iter()
+    }
+}
+
+/// SERIKO settings, defined by `seriko.*` keys.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub struct SerikoSettings {
+    /// `seriko.use_self_alpha`. Uses the alpha channel of PNG images instead of PNA files.
+    pub use_self_alpha: bool,
+}
+
+impl SerikoSettings {
+    pub const fn use_self_alpha(mut self, enabled: bool) -> SerikoSettings {
+        self.use_self_alpha = enabled;
+        self
+    }
+
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a SERIKO setting.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match key_segments {
+            ["seriko", "use_self_alpha"] => {
+                self.use_self_alpha = all_consuming(parse_bool)(value)?.1;
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Names of one scope, defined by `<scope>.name` and `<scope>.name2` keys.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct CharacterName {
+    /// `<scope>.name`
+    pub name: Option<String>,
+    /// `<scope>.name2`
+    pub name2: Option<String>,
+}
+
+/// Problem of a name found by [`ScopeNames::validate`].
+///
+/// [`ScopeNames::validate`]: crate::descript::ScopeNames::validate
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NameWarning {
+    /// Name containing control characters, such as tabs.
+    ControlCharacter { scope: Scope, name: String },
+    /// Name longer than the limit, with its length in chars.
+    TooLong {
+        scope: Scope,
+        name: String,
+        length: usize,
+    },
+}
+
+/// Names per scope, defined by `<scope>.name` and `<scope>.name2` keys.
+///
+/// Names do not fall back to other scopes, by [`FallbackChain::NONE`].
+///
+/// [`FallbackChain::NONE`]: crate::scope::FallbackChain::NONE
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScopeNames {
+    names: ScopedSettings<CharacterName>,
+}
+
+impl Default for ScopeNames {
+    fn default() -> Self {
+        ScopeNames {
+            names: ScopedSettings::new(FallbackChain::NONE),
+        }
+    }
+}
+
+impl ScopeNames {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `false` when the key is not a scoped name key.
+    pub fn apply(&mut self, key_segments: &[&str], value: &str) -> bool {
+        let Some((scope, rest)) = split_scope(key_segments) else {
+            return false;
+        };
+
+        let name = Some(value.to_string());
+        match rest {
+            ["name"] => self.names.get_or_insert_default(scope).name = name,
+            ["name2"] => self.names.get_or_insert_default(scope).name2 = name,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Returns `<scope>.name`.
+    ///
+    /// An undefined name of [`Scope::Kero`] is the empty string, as baseware shows it.
+    /// Undefined names of other scopes are `None`.
+    ///
+    /// [`Scope::Kero`]: crate::scope::Scope::Kero
+    pub fn get(&self, scope: Scope) -> Option<&str> {
+        match self.names.resolve(scope).and_then(|n| n.name.as_deref()) {
+            Some(name) => Some(name),
+            None if scope == Scope::Kero => Some(""),
+            None => None,
+        }
+    }
+
+    /// Returns the names of `scope`, as defined.
+    pub fn entry(&self, scope: Scope) -> Option<&CharacterName> {
+        self.names.get(scope)
+    }
+
+    /// Iterates names ordered by scope.
+    pub fn iter(&self) -> impl Iterator<Item = (&Scope, &CharacterName)> {
+        self.names.iter()
+    }
+
+    /// Checks names for control characters and for more than `max_length` chars, ordered by scope.
+    pub fn validate(&self, max_length: usize) -> Vec<NameWarning> {
+        let mut warnings = Vec::new();
+
+        for (scope, names) in self.names.iter() {
+            for name in [&names.name, &names.name2].into_iter().flatten() {
+                if name.chars().any(char::is_control) {
+                    warnings.push(NameWarning::ControlCharacter {
+                        scope: *scope,
+                        name: name.clone(),
+                    });
+                }
+                let length = name.chars().count();
+                if length > max_length {
+                    warnings.push(NameWarning::TooLong {
+                        scope: *scope,
+                        name: name.clone(),
+                        length,
+                    });
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Extensions of filenames of `shiori`.
+const SHIORI_EXTENSIONS: &[&str] = &["dll"];
+
+/// Fields of ghost `descript.txt` not in shell `descript.txt`.
+///
+/// Each field is `None` when the corresponding key is absent.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct GhostFields {
+    /// `shiori`, the filename of the SHIORI, read by [`parse_module_filename`] with the extension `dll`.
+    ///
+    /// [`parse_module_filename`]: crate::value::parse_module_filename
+    pub shiori: Option<String>,
+    /// `shiori.version`
+    pub shiori_version: Option<String>,
+    /// `<scope>.name` and `<scope>.name2`, per scope.
+    pub names: ScopeNames,
+    /// `sstp.allowunspecifiedsend`
+    pub sstp_allow_unspecified_send: Option<bool>,
+    /// `sstp.alwaystranslate`
+    pub sstp_always_translate: Option<bool>,
+}
+
+impl GhostFields {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a ghost field.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match key_segments {
+            ["shiori"] => {
+                let (_, filename) = context(
+                    "shiori",
+                    all_consuming(|i| parse_module_filename(i, SHIORI_EXTENSIONS)),
+                )(value)?;
+                self.shiori = Some(filename.to_string());
+            }
+            ["shiori", "version"] => self.shiori_version = Some(value.to_string()),
+            ["sstp", "allowunspecifiedsend"] => {
+                self.sstp_allow_unspecified_send = Some(all_consuming(parse_bool)(value)?.1);
+            }
+            ["sstp", "alwaystranslate"] => {
+                self.sstp_always_translate = Some(all_consuming(parse_bool)(value)?.1);
+            }
+            _ => return Ok(self.names.apply(key_segments, value)),
+        }
+
+        Ok(true)
+    }
+
+    /// Applies `entries`, returning those which are not ghost fields in order.
+    ///
+    /// Fails at the first entry of a ghost field with an invalid value.
+    pub fn from_entries<'a>(
+        entries: &[Located<KeyValue<'a>>],
+    ) -> Result<(GhostFields, Vec<Located<KeyValue<'a>>>), Located<ParseFailure>> {
+        let mut fields = GhostFields::default();
+        let mut rest = Vec::new();
+
+        for entry in entries.iter() {
+            let applied = fields
+                .apply(&entry.value.key_segments(), entry.value.value)
+                .map_err(|e| Located::new(entry.line, e.into()))?;
+            if !applied {
+                rest.push(entry.clone());
+            }
+        }
+
+        Ok((fields, rest))
+    }
+}
+
+/// Fields of `descript.txt` parsed by this crate, each key routed by [`classify_key`].
+///
+/// [`classify_key`]: crate::keys::classify_key
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DescriptFields {
+    /// `charset`
+    pub charset: Option<Charset>,
+    pub ghost: GhostFields,
+    pub balloon_offsets: BalloonOffsets,
+    pub default_surfaces: DefaultSurfaces,
+    pub seriko: SerikoSettings,
+    pub bind_groups: BindGroups,
+    pub cursors: Cursors,
+}
+
+impl DescriptFields {
+    /// Applies one entry.
+    ///
+    /// Returns `Ok(false)` when the key is not parsed by this crate.
+    pub fn apply<'a>(
+        &mut self,
+        key: &str,
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let known = classify_key(key);
+        if known == KnownKey::Unknown {
+            return Ok(false);
+        }
+
+        let segments: Vec<_> = key.split('.').collect();
+        match known {
+            KnownKey::Charset => {
+                self.charset = Some(all_consuming(parse_charset)(value)?.1);
+                Ok(true)
+            }
+            KnownKey::Shiori
+            | KnownKey::ShioriVersion
+            | KnownKey::SstpAllowUnspecifiedSend
+            | KnownKey::SstpAlwaysTranslate
+            | KnownKey::Name(_)
+            | KnownKey::Name2(_) => self.ghost.apply(&segments, value),
+            KnownKey::BalloonOffsetX(_)
+            | KnownKey::BalloonOffsetY(_)
+            | KnownKey::BalloonAlignment(_) => self.balloon_offsets.apply(&segments, value),
+            KnownKey::DefaultSurface(_) => self.default_surfaces.apply(&segments, value),
+            KnownKey::SerikoUseSelfAlpha => self.seriko.apply(&segments, value),
+            KnownKey::BindGroupName(..) | KnownKey::BindGroupDefault(..) => {
+                self.bind_groups.apply(&segments, value)
+            }
+            KnownKey::Cursor(..) => self.cursors.apply(&segments, value),
+            KnownKey::Unknown => Ok(false),
+        }
+    }
+
+    /// Applies `entries`, returning those with keys not parsed by this crate in order.
+    ///
+    /// Fails at the first entry with an invalid value.
+    pub fn from_entries<'a>(
+        entries: &[Located<KeyValue<'a>>],
+    ) -> Result<(DescriptFields, Vec<Located<KeyValue<'a>>>), Located<ParseFailure>> {
+        let mut fields = DescriptFields::default();
+        let mut rest = Vec::new();
+
+        for entry in entries.iter() {
+            let applied = fields
+                .apply(entry.value.key, entry.value.value)
+                .map_err(|e| Located::new(entry.line, e.into()))?;
+            if !applied {
+                rest.push(entry.clone());
+            }
+        }
+
+        Ok((fields, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_balloon_alignment {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            assert_eq!(
+                parse_balloon_alignment("left").unwrap(),
+                ("", BalloonAlignment::Left)
+            );
+            assert_eq!(
+                parse_balloon_alignment("none").unwrap(),
+                ("", BalloonAlignment::None)
+            );
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            assert!(parse_balloon_alignment("center").is_err());
+        }
+    }
+
+    mod balloon_offsets {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_entries() {
+            let mut offsets = BalloonOffsets::default();
+            assert!(offsets
+                .apply(&["sakura", "balloon", "offsetx"], "10")
+                .unwrap());
+            assert!(offsets
+                .apply(&["kero", "balloon", "offsety"], "-5")
+                .unwrap());
+            assert!(offsets
+                .apply(&["char2", "balloon", "alignment"], "right")
+                .unwrap());
+
+            assert_eq!(offsets.get(Scope::Sakura).unwrap().x, Some(10));
+            assert_eq!(offsets.get(Scope::Kero).unwrap().y, Some(-5));
+            assert_eq!(
+                offsets.get(Scope::Char(2)).unwrap().alignment,
+                Some(BalloonAlignment::Right)
+            );
+        }
+
+        #[test]
+        fn success_when_char_falls_back_to_kero() {
+            let mut offsets = BalloonOffsets::default();
+            offsets
+                .apply(&["kero", "balloon", "offsetx"], "-20")
+                .unwrap();
+            offsets
+                .apply(&["char2", "balloon", "offsetx"], "30")
+                .unwrap();
+
+            assert_eq!(offsets.resolve(Scope::Char(3)).unwrap().x, Some(-20));
+            assert_eq!(offsets.resolve(Scope::Char(2)).unwrap().x, Some(30));
+            assert_eq!(offsets.resolve(Scope::Sakura), None);
+            assert_eq!(offsets.get(Scope::Char(3)), None);
+        }
+
+        #[test]
+        fn ignored_when_unknown_key() {
+            let mut offsets = BalloonOffsets::default();
+            assert!(!offsets.apply(&["sakura", "name"], "x").unwrap());
+            assert!(!offsets.apply(&["balloon", "offsetx"], "10").unwrap());
+            assert!(offsets.get(Scope::Sakura).is_none());
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let mut offsets = BalloonOffsets::default();
+            assert!(offsets
+                .apply(&["sakura", "balloon", "offsetx"], "ten")
+                .is_err());
+        }
+    }
+
+    mod default_surfaces {
+        use super::*;
+
+        #[test]
+        fn success_when_char_falls_back_to_kero() {
+            let mut surfaces = DefaultSurfaces::default();
+            assert!(surfaces
+                .apply(&["sakura", "seriko", "defaultsurface"], "0")
+                .unwrap());
+            assert!(!surfaces.apply(&["seriko", "defaultsurface"], "0").unwrap());
+
+            assert_eq!(surfaces.resolve(Scope::Kero), None);
+            surfaces
+                .apply(&["kero", "seriko", "defaultsurface"], "10")
+                .unwrap();
+            assert_eq!(surfaces.resolve(Scope::Char(3)), Some(10));
+            assert_eq!(surfaces.get(Scope::Char(3)), None);
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let mut surfaces = DefaultSurfaces::default();
+            assert!(surfaces
+                .apply(&["sakura", "seriko", "defaultsurface"], "-1")
+                .is_err());
+        }
+    }
+
+    mod seriko_settings {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_entry() {
+            let mut settings = SerikoSettings::default();
+            assert!(settings.apply(&["seriko", "use_self_alpha"], "1").unwrap());
+            assert!(settings.use_self_alpha);
+            assert!(!settings
+                .apply(&["seriko", "alignmenttodesktop"], "1")
+                .unwrap());
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let mut settings = SerikoSettings::default();
+            assert!(settings.apply(&["seriko", "use_self_alpha"], "2").is_err());
+        }
+    }
+
+    mod ghost_fields {
+        use super::*;
+
+        use crate::kv::parse_key_value;
+
+        const GHOST: &str = "charset,UTF-8\r\ntype,ghost\r\nname,Emily\r\nshiori,yaya.dll\r\nshiori.version,yaya/1.0\r\nsakura.name,Emily\r\nsakura.name2,Emi\r\nkero.name,Teddy\r\nsstp.allowunspecifiedsend,0\r\nsstp.alwaystranslate,1\r\nhomeurl,https://example.com/\r\n";
+
+        fn entries(case: &str) -> Vec<Located<KeyValue<'_>>> {
+            case.lines()
+                .enumerate()
+                .map(|(i, line)| Located::new(i + 1, parse_key_value(line).unwrap().1))
+                .collect()
+        }
+
+        #[test]
+        fn success_when_ghost_descript() {
+            let (fields, rest) = GhostFields::from_entries(&entries(GHOST)).unwrap();
+
+            assert_eq!(fields.shiori.as_deref(), Some("yaya.dll"));
+            assert_eq!(fields.shiori_version.as_deref(), Some("yaya/1.0"));
+            assert_eq!(
+                fields.names.entry(Scope::Sakura),
+                Some(&CharacterName {
+                    name: Some("Emily".to_string()),
+                    name2: Some("Emi".to_string()),
+                })
+            );
+            assert_eq!(fields.names.get(Scope::Kero), Some("Teddy"));
+            assert_eq!(fields.sstp_allow_unspecified_send, Some(false));
+            assert_eq!(fields.sstp_always_translate, Some(true));
+
+            let keys: Vec<_> = rest.iter().map(|e| e.value.key).collect();
+            assert_eq!(keys, vec!["charset", "type", "name", "homeurl"]);
+        }
+
+        #[test]
+        fn success_when_shiori_missing() {
+            let (fields, rest) =
+                GhostFields::from_entries(&entries("name,Emily\r\nchar2.name,Lily\r\n")).unwrap();
+            assert_eq!(fields.shiori, None);
+            assert_eq!(fields.names.get(Scope::Char(2)), Some("Lily"));
+            assert_eq!(rest.len(), 1);
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let error =
+                GhostFields::from_entries(&entries("name,a\r\nshiori,a|b.dll\r\n")).unwrap_err();
+            assert_eq!(error.line, 2);
+            assert!(error.value.contexts().contains(&"shiori"));
+            let error = GhostFields::from_entries(&entries("shiori,bin/yaya.dll\r\n")).unwrap_err();
+            assert_eq!(error.value.contexts(), ["module:path", "shiori"]);
+
+            let mut fields = GhostFields::default();
+            assert!(fields.apply(&["sstp", "alwaystranslate"], "yes").is_err());
+            assert!(!fields.apply(&["sakura", "name3"], "x").unwrap());
+            assert!(fields.names.entry(Scope::Sakura).is_none());
+            assert!(!fields.apply(&["sstp", "unknown"], "1").unwrap());
+        }
+    }
+
+    mod scope_names {
+        use super::*;
+
+        fn names(case: &[(&str, &str)]) -> ScopeNames {
+            let mut names = ScopeNames::default();
+            for (key, value) in case.iter() {
+                let segments: Vec<_> = key.split('.').collect();
+                assert!(names.apply(&segments, value));
+            }
+            names
+        }
+
+        #[test]
+        fn success_when_sakura_and_kero() {
+            let names = names(&[
+                ("sakura.name", "さくら"),
+                ("sakura.name2", "Sakura"),
+                ("kero.name", "うにゅう"),
+            ]);
+            assert_eq!(names.get(Scope::Sakura), Some("さくら"));
+            assert_eq!(
+                names.entry(Scope::Sakura).unwrap().name2.as_deref(),
+                Some("Sakura")
+            );
+            assert_eq!(names.get(Scope::Kero), Some("うにゅう"));
+            assert!(!ScopeNames::default().apply(&["name"], "x"));
+            assert!(!ScopeNames::default().apply(&["sakura", "balloon", "offsetx"], "10"));
+        }
+
+        #[test]
+        fn success_when_char3() {
+            let names = names(&[("char3.name", "Lily")]);
+            assert_eq!(names.get(Scope::Char(3)), Some("Lily"));
+            assert_eq!(names.get(Scope::Char(2)), None);
+            assert_eq!(names.iter().count(), 1);
+        }
+
+        #[test]
+        fn success_when_kero_missing() {
+            let names = names(&[("sakura.name", "Emily"), ("kero.name2", "Teddy")]);
+            assert_eq!(names.get(Scope::Kero), Some(""));
+            assert_eq!(ScopeNames::default().get(Scope::Sakura), None);
+        }
+
+        #[test]
+        fn warned_when_over_length() {
+            let names = names(&[
+                ("sakura.name", "Emily"),
+                ("kero.name", "Teddy\tBear"),
+                ("char2.name2", "Alexandrina"),
+            ]);
+            assert_eq!(
+                names.validate(8),
+                vec![
+                    NameWarning::ControlCharacter {
+                        scope: Scope::Kero,
+                        name: "Teddy\tBear".to_string(),
+                    },
+                    NameWarning::TooLong {
+                        scope: Scope::Kero,
+                        name: "Teddy\tBear".to_string(),
+                        length: 10,
+                    },
+                    NameWarning::TooLong {
+                        scope: Scope::Char(2),
+                        name: "Alexandrina".to_string(),
+                        length: 11,
+                    },
+                ]
+            );
+            assert_eq!(names.validate(11).len(), 1);
+        }
+    }
+
+    mod descript_fields {
+        use super::*;
+
+        use crate::{cursor::CursorKind, kv::parse_key_value};
+
+        #[test]
+        fn success_when_entries_routed() {
+            let case = "charset,UTF-8\r\nshiori,yaya.dll\r\nsakura.name,Emily\r\nkero.balloon.offsetx,-10\r\nseriko.use_self_alpha,1\r\nsakura.bindgroup0.name,Hat,Ribbon\r\nsakura.cursor.mouseup0,Head,head.cur\r\nhomeurl,https://example.com/\r\n";
+            let entries: Vec<_> = case
+                .lines()
+                .enumerate()
+                .map(|(i, line)| Located::new(i + 1, parse_key_value(line).unwrap().1))
+                .collect();
+            let (fields, rest) = DescriptFields::from_entries(&entries).unwrap();
+
+            assert_eq!(fields.charset, Some(Charset::UTF8));
+            assert_eq!(fields.ghost.shiori.as_deref(), Some("yaya.dll"));
+            assert_eq!(fields.ghost.names.get(Scope::Sakura), Some("Emily"));
+            assert_eq!(
+                fields.balloon_offsets.get(Scope::Kero).unwrap().x,
+                Some(-10)
+            );
+            assert!(fields.seriko.use_self_alpha);
+            assert_eq!(
+                fields.bind_groups.get(Scope::Sakura, 0).unwrap().part,
+                "Ribbon"
+            );
+            assert_eq!(
+                fields.cursors.get(Scope::Sakura)[0].kind,
+                CursorKind::MouseUp
+            );
+
+            let keys: Vec<_> = rest.iter().map(|e| e.value.key).collect();
+            assert_eq!(keys, vec!["homeurl"]);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! # `diagnostic`
+//!
+//! Parsing a whole file in one pass, reporting all problems with their line numbers.
+//! Hard errors and style warnings are told apart by [`Severities`],
+//! which can promote warnings to errors.
+//!
+//! [`Severities`]: crate::diagnostic::Severities
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::diagnostic::{
+//!     parse_with_diagnostics, parse_with_severities, Severities, Severity, WarningKind,
+//! };
+//!
+//! let case = "name,master \r\ncharset,UTF-8\r\n";
+//! let outcome = parse_with_diagnostics(case);
+//!
+//! assert!(outcome.errors.is_empty());
+//! assert_eq!(outcome.warnings.len(), 2);
+//! assert_eq!(outcome.document.get("name"), Some("master "));
+//!
+//! let mut severities = Severities::default();
+//! severities.set(WarningKind::LateCharset, Severity::Error);
+//! let outcome = parse_with_severities(case, &severities);
+//!
+//! assert_eq!(outcome.errors[0].line, 2);
+//! assert_eq!(outcome.warnings.len(), 1);
+//! ```
+
+use alloc::{collections::BTreeMap, string::String};
+use core::fmt;
+
+use crate::{charset::Charset, keys::ValueKind, line::LineKind};
+
+#[cfg(all(feature = "surfaces", feature = "document"))]
+mod outcome;
+
+#[cfg(all(feature = "surfaces", feature = "document"))]
+pub use outcome::{
+    parse_with_diagnostics, parse_with_options, parse_with_severities, ParseOutcome,
+};
+
+/// Style problem, which baseware accepts.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum Warning {
+    /// `charset` line after other entries.
+    LateCharset,
+    /// Spaces or tabs at the end of the line.
+    TrailingWhitespace,
+    /// The key is not known in a surface block.
+    UnknownKey(String),
+    /// Full-width characters were read as half-width, with [`ParseOptions::normalize_fullwidth`].
+    ///
+    /// [`ParseOptions::normalize_fullwidth`]: crate::options::ParseOptions::normalize_fullwidth
+    FullWidth,
+    /// The tooltip names a collision not in the surface, including its appends.
+    UnmatchedTooltip(String),
+    /// The `charset` line starts with a byte order mark, but names a charset other than UTF-8.
+    BomMismatch(Charset),
+    /// `charset` line after a line which [`CharsetPositionPolicy`] does not allow before it,
+    /// with the kind of the first such line.
+    ///
+    /// [`CharsetPositionPolicy`]: crate::lint::CharsetPositionPolicy
+    MisplacedCharset(LineKind),
+    /// The value is empty, with [`EmptyValuePolicy::Warn`].
+    ///
+    /// [`EmptyValuePolicy::Warn`]: crate::options::EmptyValuePolicy::Warn
+    EmptyValue,
+    /// The line has a key and no separator, with [`BareKeyPolicy::Warn`].
+    ///
+    /// [`BareKeyPolicy::Warn`]: crate::options::BareKeyPolicy::Warn
+    BareKey,
+    /// The surface is defined by another `surfaceN` block before, not `surface.appendN`.
+    /// The last one is kept.
+    DuplicateSurface(u32),
+    /// The header of a surface block is not read as IDs, such as `surface5-1`.
+    UnreadableSurfaceHeader(String),
+    /// The surface referred, such as by `sakura.seriko.defaultsurface`, is not defined in any surfaces file.
+    UndefinedSurface(u32),
+    /// The bind animation has no bind group of the same ID in any scope.
+    BindAnimationWithoutGroup(u32),
+    /// The bind group is used by no bind animation.
+    BindGroupWithoutAnimation(u32),
+    /// The collision name is in no surface.
+    UnresolvedCollision(String),
+    /// The value ends with spaces or tabs, which baseware differ in keeping, with the kind of the value.
+    TrailingValueWhitespace(ValueKind),
+    /// A number out of the range of its field is clamped, with [`Overflow::Saturate`].
+    ///
+    /// [`Overflow::Saturate`]: crate::value::Overflow::Saturate
+    Saturated,
+}
+
+impl Warning {
+    pub fn kind(&self) -> WarningKind {
+        match self {
+            Warning::LateCharset => WarningKind::LateCharset,
+            Warning::TrailingWhitespace => WarningKind::TrailingWhitespace,
+            Warning::UnknownKey(_) => WarningKind::UnknownKey,
+            Warning::FullWidth => WarningKind::FullWidth,
+            Warning::UnmatchedTooltip(_) => WarningKind::UnmatchedTooltip,
+            Warning::BomMismatch(_) => WarningKind::BomMismatch,
+            Warning::MisplacedCharset(_) => WarningKind::MisplacedCharset,
+            Warning::EmptyValue => WarningKind::EmptyValue,
+            Warning::BareKey => WarningKind::BareKey,
+            Warning::DuplicateSurface(_) => WarningKind::DuplicateSurface,
+            Warning::UnreadableSurfaceHeader(_) => WarningKind::UnreadableSurfaceHeader,
+            Warning::UndefinedSurface(_) => WarningKind::UndefinedSurface,
+            Warning::BindAnimationWithoutGroup(_) => WarningKind::BindAnimationWithoutGroup,
+            Warning::BindGroupWithoutAnimation(_) => WarningKind::BindGroupWithoutAnimation,
+            Warning::UnresolvedCollision(_) => WarningKind::UnresolvedCollision,
+            Warning::TrailingValueWhitespace(_) => WarningKind::TrailingValueWhitespace,
+            Warning::Saturated => WarningKind::Saturated,
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::LateCharset => write!(f, "charset after other entries"),
+            Warning::TrailingWhitespace => write!(f, "trailing whitespace"),
+            Warning::UnknownKey(key) => write!(f, "unknown key {:?}", key),
+            Warning::FullWidth => write!(f, "full-width characters in numbers"),
+            Warning::UnmatchedTooltip(collision) => {
+                write!(f, "tooltip of unknown collision {:?}", collision)
+            }
+            Warning::BomMismatch(charset) => {
+                write!(f, "byte order mark in a file of {:?}", charset)
+            }
+            Warning::MisplacedCharset(kind) => match kind {
+                LineKind::Blank => write!(f, "charset after a blank line"),
+                LineKind::Comment => write!(f, "charset after a comment"),
+                LineKind::KeyValue => write!(f, "charset after other entries"),
+                _ => write!(f, "charset after other lines"),
+            },
+            Warning::EmptyValue => write!(f, "empty value"),
+            Warning::BareKey => write!(f, "key without a separator"),
+            Warning::DuplicateSurface(id) => write!(f, "surface{} defined again", id),
+            Warning::UnreadableSurfaceHeader(header) => {
+                write!(f, "surface header {:?} not read", header)
+            }
+            Warning::UndefinedSurface(id) => write!(f, "surface{} not defined", id),
+            Warning::BindAnimationWithoutGroup(id) => {
+                write!(f, "bind animation{} without bindgroup{}", id, id)
+            }
+            Warning::BindGroupWithoutAnimation(id) => {
+                write!(f, "bindgroup{} without bind animation{}", id, id)
+            }
+            Warning::UnresolvedCollision(collision) => {
+                write!(f, "collision {:?} in no surface", collision)
+            }
+            Warning::TrailingValueWhitespace(kind) => match kind {
+                ValueKind::Filename => write!(f, "trailing whitespace in a filename"),
+                ValueKind::Identifier => write!(f, "trailing whitespace in a name"),
+                ValueKind::Text => write!(f, "trailing whitespace in text"),
+            },
+            Warning::Saturated => write!(f, "number clamped to the range of its field"),
+        }
+    }
+}
+
+/// Kind of a [`Warning`], without its data.
+///
+/// [`Warning`]: crate::diagnostic::Warning
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum WarningKind {
+    LateCharset,
+    TrailingWhitespace,
+    UnknownKey,
+    FullWidth,
+    UnmatchedTooltip,
+    BomMismatch,
+    MisplacedCharset,
+    EmptyValue,
+    BareKey,
+    DuplicateSurface,
+    UnreadableSurfaceHeader,
+    UndefinedSurface,
+    BindAnimationWithoutGroup,
+    BindGroupWithoutAnimation,
+    UnresolvedCollision,
+    TrailingValueWhitespace,
+    Saturated,
+}
+
+/// `Severity` type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Error,
+    #[default]
+    Warning,
+    /// Reported with warnings, as a note which may be intended.
+    Info,
+}
+
+/// Severities of warning kinds. Kinds not set are [`Severity::Warning`].
+///
+/// [`Severity::Warning`]: crate::diagnostic::Severity::Warning
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Severities(BTreeMap<WarningKind, Severity>);
+
+impl Severities {
+    pub fn set(&mut self, kind: WarningKind, severity: Severity) {
+        self.0.insert(kind, severity);
+    }
+
+    pub fn get(&self, kind: WarningKind) -> Severity {
+        self.0.get(&kind).copied().unwrap_or_default()
+    }
+}
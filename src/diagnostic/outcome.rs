@@ -0,0 +1,390 @@
+//! Parsing a whole file, with the warnings of [`Severities`].
+//!
+//! [`Severities`]: crate::diagnostic::Severities
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    block::{parse_blocks_with, BlockCursor},
+    charset::{parse_charset_with, Charset, BOM},
+    document::Document,
+    error::{Located, ParseFailure},
+    kv::{is_bare_key, parse_key_value_with},
+    line::{lines, LineKind},
+    options::{BareKeyPolicy, EmptyValuePolicy, ParseOptions},
+    surfaces::{
+        canonical_line, is_surface_header, merge_append, parse_block_body_lenient_with, SurfaceBody,
+    },
+    tooltip::parse_block_tooltip,
+};
+
+use super::{Severities, Severity, Warning};
+
+/// Result of [`parse_with_diagnostics`].
+///
+/// [`parse_with_diagnostics`]: crate::diagnostic::parse_with_diagnostics
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParseOutcome {
+    /// Not serialized, as the nodes repeat the lines of the source.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub document: Document,
+    /// Errors in line order. Promoted warnings are [`ParseFailure::Warning`].
+    ///
+    /// [`ParseFailure::Warning`]: crate::error::ParseFailure::Warning
+    pub errors: Vec<Located<ParseFailure>>,
+    /// Warnings in line order.
+    pub warnings: Vec<Located<Warning>>,
+}
+
+/// Parses `text` with default [`Severities`].
+///
+/// [`Severities`]: crate::diagnostic::Severities
+pub fn parse_with_diagnostics(text: &str) -> ParseOutcome {
+    parse_with_severities(text, &Severities::default())
+}
+
+/// Parses `text`, classifying warnings by `severities`.
+///
+/// Lines outside blocks must be entries. Bodies of surface blocks are checked
+/// as [`parse_block_body_lenient`] does, and their unknown keys are warnings.
+/// Tooltips in them naming collisions not in the surface merged with its appends are warnings.
+/// When braces are broken, the error is reported and blocks are not checked.
+/// The value of the `charset` line must name a charset.
+/// A byte order mark before it is skipped, and is a warning unless the charset is UTF-8.
+/// Lines without a separator are errors.
+///
+/// [`parse_block_body_lenient`]: crate::surfaces::parse_block_body_lenient
+pub fn parse_with_severities(text: &str, severities: &Severities) -> ParseOutcome {
+    parse_with_options(text, &ParseOptions::STRICT, severities)
+}
+
+/// Same as [`parse_with_severities`], with the lenient behaviors of `options`
+/// in entries and bodies of surface blocks.
+///
+/// Empty values and lines without a separator outside blocks are warned as their policies in `options` tell.
+///
+/// [`parse_with_severities`]: crate::diagnostic::parse_with_severities
+pub fn parse_with_options(
+    text: &str,
+    options: &ParseOptions,
+    severities: &Severities,
+) -> ParseOutcome {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+    let mut report =
+        |errors: &mut Vec<_>, line: usize, warning: Warning| match severities.get(warning.kind()) {
+            Severity::Error => errors.push(Located::new(line, ParseFailure::Warning(warning))),
+            Severity::Warning | Severity::Info => warnings.push(Located::new(line, warning)),
+        };
+
+    let blocks = match parse_blocks_with(text, options) {
+        Ok(blocks) => blocks,
+        Err(e) => {
+            errors.push(e.map(ParseFailure::Block));
+            Vec::new()
+        }
+    };
+    let mut in_block = BlockCursor::new(&blocks);
+
+    let mut has_entries = false;
+    for (line, kind) in lines(text).classified_with(options) {
+        if line.text != line.text.trim_end() {
+            report(&mut errors, line.number, Warning::TrailingWhitespace);
+        }
+        if in_block.contains(line.number) {
+            continue;
+        }
+        match kind {
+            LineKind::KeyValue | LineKind::Unknown => {}
+            // Broken braces are reported as a block error.
+            _ => continue,
+        }
+
+        match all_consuming(|input| parse_key_value_with(input, options))(line.text) {
+            Ok((_, kv)) => {
+                if is_bare_key(line.text, options) {
+                    if options.bare_key == BareKeyPolicy::Warn {
+                        report(&mut errors, line.number, Warning::BareKey);
+                    }
+                } else if kv.value.is_empty() && options.empty_value == EmptyValuePolicy::Warn {
+                    report(&mut errors, line.number, Warning::EmptyValue);
+                }
+                let key = kv.key.trim();
+                let (bom, key) = match key.strip_prefix(BOM) {
+                    Some(key) => (true, key.trim_start()),
+                    None => (false, key),
+                };
+                if options.key_eq(key, "charset") {
+                    if has_entries {
+                        report(&mut errors, line.number, Warning::LateCharset);
+                    }
+                    match all_consuming(|input| parse_charset_with(input, options))(kv.value.trim())
+                    {
+                        Ok((_, charset)) if bom && charset != Charset::UTF8 => {
+                            report(&mut errors, line.number, Warning::BomMismatch(charset));
+                        }
+                        Ok(_) => {}
+                        Err(e) => errors.push(Located::new(line.number, e.into())),
+                    }
+                }
+                has_entries = true;
+            }
+            Err(e) => errors.push(Located::new(line.number, e.into())),
+        }
+    }
+
+    let mut surfaces: BTreeMap<u32, SurfaceBody> = BTreeMap::new();
+    let mut surface_blocks = Vec::new();
+    for block in blocks.iter().filter(|b| is_surface_header(b.header)) {
+        for line in block.body.iter() {
+            if let Ok((_, true)) = canonical_line(line.text, options) {
+                report(&mut errors, line.number, Warning::FullWidth);
+            }
+        }
+        let (body, failures) = parse_block_body_lenient_with(&block.body, options);
+        for failure in failures {
+            match failure.value {
+                ParseFailure::UnknownKey(key) => {
+                    report(&mut errors, failure.line, Warning::UnknownKey(key))
+                }
+                ParseFailure::Warning(warning) => report(&mut errors, failure.line, warning),
+                _ => errors.push(failure),
+            }
+        }
+
+        let ids = surface_ids(block.header);
+        for id in ids.iter() {
+            merge_append(surfaces.entry(*id).or_default(), &body);
+        }
+        surface_blocks.push((ids, block));
+    }
+
+    // Tooltips are checked against the surfaces merged with all their blocks.
+    for (ids, block) in surface_blocks {
+        for line in block.body.iter() {
+            let Ok((text, _)) = canonical_line(line.text, options) else {
+                continue;
+            };
+            let Ok((_, tooltip)) = all_consuming(parse_block_tooltip)(&text) else {
+                continue;
+            };
+            let matched = ids.iter().all(|id| {
+                surfaces[id]
+                    .all_collision_names()
+                    .contains(&tooltip.collision.as_str())
+            });
+            if !matched {
+                report(
+                    &mut errors,
+                    line.number,
+                    Warning::UnmatchedTooltip(tooltip.collision),
+                );
+            }
+        }
+    }
+
+    errors.sort_by_key(|e| e.line);
+    warnings.sort_by_key(|w| w.line);
+
+    ParseOutcome {
+        document: Document::parse_with(text, options),
+        errors,
+        warnings,
+    }
+}
+
+/// Returns IDs of surfaces in `header`, such as `surface0,surface.append1`.
+///
+/// Empty when any of them is not a plain ID, such as a range or an exclusion.
+fn surface_ids(header: &str) -> Vec<u32> {
+    header
+        .split(',')
+        .map(|part| {
+            let part = part.trim();
+            part.strip_prefix("surface.append")
+                .or_else(|| part.strip_prefix("surface"))
+                .and_then(|id| id.parse().ok())
+        })
+        .collect::<Option<_>>()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::diagnostic::WarningKind;
+
+    const MESSY: &str = "// messy\r\nname,master\r\ncharset,UTF-8\r\nid,master \r\n?? broken\r\n\t\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\nelement1,base,b.png,x,0\r\nsparkle,1\r\n}\r\ndescript\r\n{\r\nversion,1\r\n}\r\n";
+
+    mod parse_with_diagnostics {
+        use super::*;
+
+        #[test]
+        fn success_when_messy_fixture() {
+            let outcome = parse_with_diagnostics(MESSY);
+
+            let errors: Vec<_> = outcome.errors.iter().map(|e| e.line).collect();
+            assert_eq!(errors, vec![5, 10]);
+            assert!(matches!(
+                outcome.errors[0].value,
+                ParseFailure::Syntax { .. }
+            ));
+
+            assert_eq!(
+                outcome.warnings,
+                vec![
+                    Located::new(3, Warning::LateCharset),
+                    Located::new(4, Warning::TrailingWhitespace),
+                    Located::new(6, Warning::TrailingWhitespace),
+                    Located::new(11, Warning::UnknownKey("sparkle".to_string())),
+                ]
+            );
+            assert_eq!(outcome.document.to_string(), MESSY);
+        }
+
+        #[test]
+        fn success_when_clean() {
+            let case = "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n";
+            let outcome = parse_with_diagnostics(case);
+            assert!(outcome.errors.is_empty());
+            assert!(outcome.warnings.is_empty());
+        }
+
+        #[test]
+        fn failed_when_unclosed_block() {
+            let outcome = parse_with_diagnostics("name,a\r\nsurface0\r\n{\r\nelement0,x\r\n");
+            assert_eq!(outcome.errors.len(), 1);
+            assert_eq!(outcome.errors[0].line, 2);
+            assert!(matches!(outcome.errors[0].value, ParseFailure::Block(_)));
+        }
+    }
+
+    mod parse_with_severities {
+        use super::*;
+
+        #[test]
+        fn promoted_when_set_to_error() {
+            let mut severities = Severities::default();
+            severities.set(WarningKind::UnknownKey, Severity::Error);
+            severities.set(WarningKind::TrailingWhitespace, Severity::Error);
+            let outcome = parse_with_severities(MESSY, &severities);
+
+            let errors: Vec<_> = outcome.errors.iter().map(|e| e.line).collect();
+            assert_eq!(errors, vec![4, 5, 6, 10, 11]);
+            assert_eq!(
+                outcome.errors[4].value,
+                ParseFailure::Warning(Warning::UnknownKey("sparkle".to_string()))
+            );
+            assert_eq!(
+                outcome.warnings,
+                vec![Located::new(3, Warning::LateCharset)]
+            );
+        }
+    }
+    mod parse_with_options {
+        use super::*;
+
+        const LENIENT: &str = "charset,sjis\r\nname\tmaster\r\nhomeurl , http://a/b // home\r\nsurface0\r\n{\r\nelement0,overlay,a.png,0,0 // eye\r\nelement1 , base,b.png,0,0\r\n}\r\n";
+
+        #[test]
+        fn failed_when_strict() {
+            let outcome =
+                parse_with_options(LENIENT, &ParseOptions::STRICT, &Severities::default());
+            let errors: Vec<_> = outcome.errors.iter().map(|e| e.line).collect();
+            assert_eq!(errors, vec![1, 2, 6]);
+            assert_eq!(
+                outcome.warnings,
+                vec![Located::new(
+                    7,
+                    Warning::UnknownKey("element1 ".to_string())
+                )]
+            );
+        }
+
+        #[test]
+        fn success_when_compatible() {
+            let outcome =
+                parse_with_options(LENIENT, &ParseOptions::COMPATIBLE, &Severities::default());
+            assert_eq!(outcome.errors, vec![]);
+            assert_eq!(outcome.warnings, vec![]);
+        }
+
+        #[test]
+        fn warned_when_empty_value_or_bare_key() {
+            let case = "craftmanurl,\r\nhomeurl\r\nname,master\r\n";
+            let outcome =
+                parse_with_options(case, &ParseOptions::COMPATIBLE, &Severities::default());
+            assert_eq!(outcome.errors, vec![]);
+            assert_eq!(outcome.warnings, vec![Located::new(2, Warning::BareKey)]);
+            assert_eq!(outcome.document.get("homeurl"), Some(""));
+
+            let options = ParseOptions::COMPATIBLE
+                .empty_value(EmptyValuePolicy::Warn)
+                .bare_key(BareKeyPolicy::TreatAsEmptyValue);
+            let outcome = parse_with_options(case, &options, &Severities::default());
+            assert_eq!(outcome.errors, vec![]);
+            assert_eq!(outcome.warnings, vec![Located::new(1, Warning::EmptyValue)]);
+
+            let options = ParseOptions::COMPATIBLE
+                .empty_value(EmptyValuePolicy::Error)
+                .bare_key(BareKeyPolicy::Error);
+            let outcome = parse_with_options(case, &options, &Severities::default());
+            let errors: Vec<_> = outcome.errors.iter().map(|e| e.line).collect();
+            assert_eq!(errors, vec![1, 2]);
+            assert_eq!(outcome.warnings, vec![]);
+        }
+
+        #[test]
+        fn warned_when_bom_with_shift_jis() {
+            let case = "\u{feff}charset,Shift_JIS\r\nname,master\r\n";
+            let outcome = parse_with_options(case, &ParseOptions::STRICT, &Severities::default());
+            assert_eq!(outcome.errors, vec![]);
+            assert_eq!(
+                outcome.warnings,
+                vec![Located::new(1, Warning::BomMismatch(Charset::ShiftJIS))]
+            );
+
+            let case = "\u{feff}charset,UTF-8\r\nname,master\r\n";
+            let outcome = parse_with_options(case, &ParseOptions::STRICT, &Severities::default());
+            assert_eq!(outcome.warnings, vec![]);
+        }
+
+        #[test]
+        fn warned_when_tooltip_unmatched() {
+            let case = "surface0,surface1\r\n{\r\ncollision0,0,0,10,10,Head\r\ntooltip.Head,head, top\r\ntooltip.Bust,bust\r\ntooltip.Tail,tail\r\n}\r\nsurface.append0,surface.append1\r\n{\r\ncollision1,0,10,10,20,Bust\r\n}\r\nsurface5-9\r\n{\r\ntooltip.Tail,tail\r\n}\r\n";
+            let outcome = parse_with_options(case, &ParseOptions::STRICT, &Severities::default());
+            assert_eq!(outcome.errors, vec![]);
+            assert_eq!(
+                outcome.warnings,
+                vec![Located::new(
+                    6,
+                    Warning::UnmatchedTooltip("Tail".to_string())
+                )]
+            );
+            assert_eq!(surface_ids("surface0, surface.append12"), vec![0, 12]);
+            assert!(surface_ids("surface0,!surface1").is_empty());
+        }
+
+        #[test]
+        fn warned_when_fullwidth_normalized() {
+            let case = "surface0\r\n{\r\nanimation0.pattern0,overlay,１２,50,0,0\r\ncollision0,０，０，１０，１０,頭１\r\ntooltip.頭１,第１話\r\n}\r\n";
+            let outcome = parse_with_options(case, &ParseOptions::STRICT, &Severities::default());
+            assert_eq!(outcome.errors.len(), 2);
+
+            let options = ParseOptions::STRICT.normalize_fullwidth(true);
+            let outcome = parse_with_options(case, &options, &Severities::default());
+            assert_eq!(outcome.errors, vec![]);
+            assert_eq!(
+                outcome.warnings,
+                vec![
+                    Located::new(3, Warning::FullWidth),
+                    Located::new(4, Warning::FullWidth)
+                ]
+            );
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! # `diff`
+//!
+//! Changes of entries between two versions of a file, compared by key rather than by text.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::diff::{diff_entries, EntryChange};
+//! use shell_parser_common_rs::kv::KeyValue;
+//!
+//! let old = [KeyValue::new("name", "master"), KeyValue::new("craftman", "a")];
+//! let new = [KeyValue::new("Name", "Master"), KeyValue::new("craftman", "a")];
+//!
+//! assert_eq!(
+//!     diff_entries(&old, &new),
+//!     vec![EntryChange::Modified {
+//!         key: "Name",
+//!         old_value: "master",
+//!         new_value: "Master",
+//!     }]
+//! );
+//! ```
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::{
+    ascii::starts_with_ignore_case,
+    document::{Document, NodeKind},
+    error::Located,
+    kv::{segment_index, EntryMap, KeyValue, NormalizedKey},
+};
+
+/// Prefixes of keys compared by position rather than by index.
+const INDEXED_PREFIXES: [&str; 3] = ["element", "collisionex", "collision"];
+
+/// Change of one entry.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EntryChange<'a> {
+    /// Entry only in the new version.
+    Added(KeyValue<'a>),
+    /// Entry only in the old version.
+    Removed(KeyValue<'a>),
+    /// Entry whose value changed, with the key as in the new version.
+    Modified {
+        key: &'a str,
+        old_value: &'a str,
+        new_value: &'a str,
+    },
+}
+
+/// Change of one entry in a block of a [`Document`].
+///
+/// [`Document`]: crate::document::Document
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DocumentChange<'a> {
+    /// Header of the block, such as `surface0`, or `None` outside blocks.
+    pub block: Option<String>,
+    pub change: EntryChange<'a>,
+}
+
+/// Diffs entries of one scope, such as the top level of a file or the body of one block.
+///
+/// Keys are matched ignoring ASCII case, and the last of repeated keys is compared, as baseware reads it.
+/// `elementN`, `collisionN` and `collisionexN` are matched by their position among keys of the same prefix,
+/// so renumbering them without changing values is not a change.
+///
+/// Modified and added entries are given in order of `new`, followed by removed entries in order of `old`.
+pub fn diff_entries<'a>(old: &[KeyValue<'a>], new: &[KeyValue<'a>]) -> Vec<EntryChange<'a>> {
+    let (old_indexed, old_keyed) = split_indexed(old);
+    let (new_indexed, new_keyed) = split_indexed(new);
+    let mut changes = Vec::new();
+
+    for entry in effective(&new_keyed) {
+        match old_keyed.get(entry.key) {
+            Some(o) if o.value.value != entry.value => changes.push(EntryChange::Modified {
+                key: entry.key,
+                old_value: o.value.value,
+                new_value: entry.value,
+            }),
+            Some(_) => {}
+            None => changes.push(EntryChange::Added(entry)),
+        }
+    }
+
+    let mut removed = Vec::new();
+    for prefix in INDEXED_PREFIXES.iter() {
+        let old = old_indexed.get(prefix).map_or(&[][..], Vec::as_slice);
+        let new = new_indexed.get(prefix).map_or(&[][..], Vec::as_slice);
+        for (o, n) in old.iter().zip(new.iter()) {
+            if o.value != n.value {
+                changes.push(EntryChange::Modified {
+                    key: n.key,
+                    old_value: o.value,
+                    new_value: n.value,
+                });
+            }
+        }
+        changes.extend(new.iter().skip(old.len()).copied().map(EntryChange::Added));
+        removed.extend(
+            old.iter()
+                .skip(new.len())
+                .copied()
+                .map(EntryChange::Removed),
+        );
+    }
+
+    for entry in effective(&old_keyed) {
+        if !new_keyed.contains_key(entry.key) {
+            changes.push(EntryChange::Removed(entry));
+        }
+    }
+    changes.extend(removed);
+
+    changes
+}
+
+/// Diffs entries of [`Document`]s, block by block.
+///
+/// Blocks are matched by their headers ignoring ASCII case, and entries in each by [`diff_entries`].
+/// Blocks of the same header are taken as one.
+/// Changes outside blocks come first, followed by blocks in order of `new` and then blocks only in `old`.
+///
+/// [`Document`]: crate::document::Document
+/// [`diff_entries`]: crate::diff::diff_entries
+pub fn diff_documents<'a>(old: &'a Document, new: &'a Document) -> Vec<DocumentChange<'a>> {
+    let old_scopes = scopes(old);
+    let new_scopes = scopes(new);
+
+    let mut headers: Vec<_> = new_scopes.iter().map(|(h, _)| h).collect();
+    headers.extend(
+        old_scopes
+            .iter()
+            .map(|(h, _)| h)
+            .filter(|h| scope_entries(&new_scopes, h).is_none()),
+    );
+
+    let mut changes = Vec::new();
+    for header in headers {
+        let block = header.as_ref().map(|h| h.original().to_string());
+        changes.extend(
+            diff_entries(
+                scope_entries(&old_scopes, header).unwrap_or_default(),
+                scope_entries(&new_scopes, header).unwrap_or_default(),
+            )
+            .into_iter()
+            .map(|change| DocumentChange {
+                block: block.clone(),
+                change,
+            }),
+        );
+    }
+
+    changes
+}
+
+type Scopes<'a> = Vec<(Option<NormalizedKey<'a>>, Vec<KeyValue<'a>>)>;
+
+/// Groups entries of `document` by the header of the block containing them, in order.
+fn scopes(document: &Document) -> Scopes<'_> {
+    let mut scopes: Scopes<'_> = vec![(None, Vec::new())];
+    let mut current = 0;
+
+    for node in document.nodes().iter() {
+        match node.kind {
+            NodeKind::BlockHeader => {
+                let header = Some(NormalizedKey::new(
+                    node.text.trim().trim_end_matches('{').trim_end(),
+                ));
+                current = match scopes.iter().position(|(h, _)| *h == header) {
+                    Some(i) => i,
+                    None => {
+                        scopes.push((header, Vec::new()));
+                        scopes.len() - 1
+                    }
+                };
+            }
+            NodeKind::BlockClose => current = 0,
+            NodeKind::Entry => {
+                if let Some(kv) = node.key_value() {
+                    scopes[current].1.push(kv);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    scopes
+}
+
+fn scope_entries<'s, 'a>(
+    scopes: &'s Scopes<'a>,
+    header: &Option<NormalizedKey<'a>>,
+) -> Option<&'s [KeyValue<'a>]> {
+    scopes
+        .iter()
+        .find(|(h, _)| h == header)
+        .map(|(_, entries)| entries.as_slice())
+}
+
+/// Splits indexed entries by their prefix from the others.
+fn split_indexed<'a>(
+    entries: &[KeyValue<'a>],
+) -> (BTreeMap<&'static str, Vec<KeyValue<'a>>>, EntryMap<'a>) {
+    let mut indexed: BTreeMap<&'static str, Vec<KeyValue<'a>>> = BTreeMap::new();
+    let mut keyed = EntryMap::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        match INDEXED_PREFIXES.iter().find(|p| {
+            starts_with_ignore_case(entry.key, p)
+                && segment_index(&entry.key[p.len()..], "").is_some()
+        }) {
+            Some(prefix) => indexed.entry(prefix).or_default().push(*entry),
+            None => keyed.insert(Located::new(i, *entry)),
+        }
+    }
+
+    (indexed, keyed)
+}
+
+/// Returns the entry in effect of each key of `map`, in order.
+fn effective<'a, 's>(map: &'s EntryMap<'a>) -> impl Iterator<Item = KeyValue<'a>> + 's {
+    map.iter()
+        .filter(|e| map.get(e.value.key).is_some_and(|last| last.line == e.line))
+        .map(|e| e.value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod diff_entries {
+        use super::*;
+
+        fn entries<'a>(case: &[(&'a str, &'a str)]) -> Vec<KeyValue<'a>> {
+            case.iter().map(|(k, v)| KeyValue::new(k, v)).collect()
+        }
+
+        #[test]
+        fn success_when_value_changed() {
+            let old = entries(&[("charset", "Shift_JIS"), ("name", "a"), ("name", "b")]);
+            let new = entries(&[("Charset", "UTF-8"), ("name", "b")]);
+            assert_eq!(
+                diff_entries(&old, &new),
+                vec![EntryChange::Modified {
+                    key: "Charset",
+                    old_value: "Shift_JIS",
+                    new_value: "UTF-8",
+                }]
+            );
+        }
+
+        #[test]
+        fn success_when_key_added_and_removed() {
+            let old = entries(&[("name", "a"), ("homeurl", "x")]);
+            let new = entries(&[("craftman", "b"), ("name", "a")]);
+            assert_eq!(
+                diff_entries(&old, &new),
+                vec![
+                    EntryChange::Added(KeyValue::new("craftman", "b")),
+                    EntryChange::Removed(KeyValue::new("homeurl", "x")),
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_element_reindexed() {
+            let old = entries(&[
+                ("element0", "base,a.png,0,0"),
+                ("element1", "overlay,b.png,0,0"),
+                ("collision0", "0,0,10,10,Head"),
+            ]);
+            let new = entries(&[
+                ("element1", "base,a.png,0,0"),
+                ("element2", "overlay,c.png,0,0"),
+                ("element3", "overlay,d.png,0,0"),
+            ]);
+            assert_eq!(
+                diff_entries(&old, &new),
+                vec![
+                    EntryChange::Modified {
+                        key: "element2",
+                        old_value: "overlay,b.png,0,0",
+                        new_value: "overlay,c.png,0,0",
+                    },
+                    EntryChange::Added(KeyValue::new("element3", "overlay,d.png,0,0")),
+                    EntryChange::Removed(KeyValue::new("collision0", "0,0,10,10,Head")),
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_identical() {
+            let case = entries(&[("name", "a"), ("element0", "base,a.png,0,0")]);
+            assert!(diff_entries(&case, &case).is_empty());
+            assert!(diff_entries(&[], &[]).is_empty());
+        }
+    }
+
+    mod diff_documents {
+        use super::*;
+
+        #[test]
+        fn success_when_changes_in_blocks() {
+            let old = Document::parse(
+                "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\nsurface1\r\n{\r\nelement0,base,b.png,0,0\r\n}\r\n",
+            );
+            let new = Document::parse(
+                "charset,UTF-8\r\n\r\nSurface0 {\r\n  // same\r\nelement0,base,a.png,0,0\r\npoint.centerx,10\r\n}\r\nsurface2\r\n{\r\nelement0,base,b.png,0,0\r\n}\r\n",
+            );
+            let changes = diff_documents(&old, &new);
+
+            assert_eq!(
+                changes,
+                vec![
+                    DocumentChange {
+                        block: Some("Surface0".to_string()),
+                        change: EntryChange::Added(KeyValue::new("point.centerx", "10")),
+                    },
+                    DocumentChange {
+                        block: Some("surface2".to_string()),
+                        change: EntryChange::Added(KeyValue::new("element0", "base,b.png,0,0")),
+                    },
+                    DocumentChange {
+                        block: Some("surface1".to_string()),
+                        change: EntryChange::Removed(KeyValue::new("element0", "base,b.png,0,0")),
+                    },
+                ]
+            );
+            assert!(diff_documents(&old, &old).is_empty());
+        }
+    }
+}
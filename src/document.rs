@@ -0,0 +1,33 @@
+//! # `document`
+//!
+//! Whole files of shell settings.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::charset::Charset;
+//! use shell_parser_common_rs::document::ShellFile;
+//!
+//! let case = b"charset,UTF-8\r\n// shell\r\nname,master\r\n";
+//! let file = ShellFile::parse(case, Charset::ShiftJIS).unwrap();
+//!
+//! assert_eq!(file.charset(), &Charset::UTF8);
+//! assert_eq!(file.entries()[1].value.key, "name");
+//! assert_eq!(file.comments()[0].line, 2);
+//! ```
+
+mod model;
+mod parsed;
+mod shell_file;
+mod stream;
+mod structure;
+mod token;
+
+pub use model::{BlockMut, Document, EntrySpans, FieldSpans, Node, NodeKind, ReparseResult};
+pub use parsed::{parse_bytes, parse_bytes_with, ParsedDocument};
+#[cfg(feature = "std")]
+pub use shell_file::OpenError;
+pub use shell_file::ShellFile;
+pub use stream::{for_each_entry, BLOCK_CLOSE, BLOCK_OPEN};
+pub use structure::{ParsedBlock, Structure};
+pub use token::{tokenize, Token, TokenKind};
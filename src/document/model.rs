@@ -0,0 +1,918 @@
+//! Round-trip document model, keeping comments, order and formatting.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, ops::Range};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    ascii::eq_ignore_case,
+    intern::{InternedEntry, Interner},
+    kv::{is_bare_key, parse_key_value, parse_key_value_with, KeyValue},
+    line::{lines, LineEnding, LineKind},
+    options::{BareKeyPolicy, EmptyValuePolicy, ParseOptions},
+    span::span_of,
+    writer::{check_key, write_key_value, WriteError},
+};
+#[cfg(feature = "surfaces")]
+use crate::{
+    kv::segment_index,
+    seriko::{parse_pattern_spanned, PatternSpans},
+    surfaces::{parse_collision_spanned, parse_element_spanned, CollisionSpans, ElementSpans},
+};
+
+mod reparse;
+
+pub use reparse::ReparseResult;
+
+/// Kind of a [`Node`].
+///
+/// [`Node`]: crate::document::Node
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum NodeKind {
+    Entry,
+    Comment,
+    Blank,
+    /// Header of a block, including `header {` on one line.
+    BlockHeader,
+    /// `{`
+    BlockOpen,
+    /// `}`
+    BlockClose,
+    /// Text not recognized by the parsers, kept as is.
+    Other,
+}
+
+/// Spans of an entry in the input of [`Document::parse_spanned`].
+///
+/// [`Document::parse_spanned`]: crate::document::Document::parse_spanned
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EntrySpans {
+    pub key: Range<usize>,
+    pub value: Range<usize>,
+    /// Spans of the fields, when the entry is one of [`FieldSpans`].
+    ///
+    /// [`FieldSpans`]: crate::document::FieldSpans
+    pub fields: Option<FieldSpans>,
+}
+
+/// Spans of the fields of an entry, by its key. All are of surface blocks, given with the `surfaces` feature.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum FieldSpans {
+    /// `elementN`
+    #[cfg(feature = "surfaces")]
+    Element(ElementSpans),
+    /// `collisionN`
+    #[cfg(feature = "surfaces")]
+    Collision(CollisionSpans),
+    /// `animationN.patternM`
+    #[cfg(feature = "surfaces")]
+    Pattern(PatternSpans),
+}
+
+/// One source line.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Node {
+    pub kind: NodeKind,
+    /// Original text without the line ending, or the rewritten text.
+    pub text: String,
+    pub ending: LineEnding,
+    /// Whether the line was rewritten or added by an edit, rather than kept from the source.
+    pub dirty: bool,
+    /// Spans of the entry, kept by [`Document::parse_spanned`] until the line is rewritten.
+    ///
+    /// [`Document::parse_spanned`]: crate::document::Document::parse_spanned
+    pub spans: Option<EntrySpans>,
+}
+
+impl Node {
+    fn entry(text: String, ending: LineEnding) -> Node {
+        Node {
+            kind: NodeKind::Entry,
+            text,
+            ending,
+            dirty: true,
+            spans: None,
+        }
+    }
+
+    /// Replaces the text by an edit, dropping the spans.
+    fn rewrite(&mut self, text: String) {
+        self.text = text;
+        self.dirty = true;
+        self.spans = None;
+    }
+
+    /// Returns the key and value when the node is an entry.
+    pub fn key_value(&self) -> Option<KeyValue<'_>> {
+        if self.kind != NodeKind::Entry {
+            return None;
+        }
+        if !self.text.contains(',') {
+            // Entries without a separator are kept only by `BareKeyPolicy`.
+            return Some(KeyValue::new(&self.text, &self.text[self.text.len()..]));
+        }
+        all_consuming(parse_key_value)(&self.text)
+            .ok()
+            .map(|(_, kv)| kv)
+    }
+}
+
+/// Document keeping every source line as a [`Node`].
+///
+/// Entries are looked up by key in document order, including entries inside blocks.
+/// Lookups and edits by key act on the last entry with the key, which is in effect as baseware reads the file,
+/// except those of `_all` names acting on all of them.
+/// Unmodified documents are written back byte-identically by `to_string`.
+///
+/// [`Node`]: crate::document::Node
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Document {
+    nodes: Vec<Node>,
+    /// Whether entries have spans, given by [`Document::parse_spanned`].
+    ///
+    /// [`Document::parse_spanned`]: crate::document::Document::parse_spanned
+    spanned: bool,
+}
+
+impl Document {
+    /// Splits `input` into nodes. Lines are recognized as [`Lines::classified`] does.
+    ///
+    /// [`Lines::classified`]: crate::line::Lines::classified
+    pub fn parse(input: &str) -> Document {
+        Document::parse_with(input, &ParseOptions::STRICT)
+    }
+
+    /// Same as [`Document::parse`], keeping lines of [`ParseOptions::comment_prefixes`] as comments.
+    ///
+    /// Lines without a separator are entries unless [`ParseOptions::bare_key`] is [`BareKeyPolicy::Error`],
+    /// and entries with an empty value are not when [`ParseOptions::empty_value`] is [`EmptyValuePolicy::Error`].
+    ///
+    /// [`Document::parse`]: crate::document::Document::parse
+    /// [`ParseOptions::comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+    /// [`ParseOptions::bare_key`]: crate::options::ParseOptions::bare_key
+    /// [`BareKeyPolicy::Error`]: crate::options::BareKeyPolicy::Error
+    /// [`ParseOptions::empty_value`]: crate::options::ParseOptions::empty_value
+    /// [`EmptyValuePolicy::Error`]: crate::options::EmptyValuePolicy::Error
+    pub fn parse_with(input: &str, options: &ParseOptions) -> Document {
+        let is_entry =
+            |text: &str| all_consuming(|input| parse_key_value_with(input, options))(text).is_ok();
+        let nodes = lines(input)
+            .classified_with(options)
+            .map(|(line, kind)| Node {
+                kind: match kind {
+                    LineKind::Blank => NodeKind::Blank,
+                    LineKind::Comment => NodeKind::Comment,
+                    LineKind::KeyValue
+                        if options.empty_value == EmptyValuePolicy::Error
+                            && !is_entry(line.text) =>
+                    {
+                        NodeKind::Other
+                    }
+                    LineKind::KeyValue => NodeKind::Entry,
+                    LineKind::Unknown
+                        if options.bare_key != BareKeyPolicy::Error
+                            && is_bare_key(line.text, options)
+                            && is_entry(line.text) =>
+                    {
+                        NodeKind::Entry
+                    }
+                    LineKind::BlockHeader => NodeKind::BlockHeader,
+                    LineKind::BraceOpen => NodeKind::BlockOpen,
+                    LineKind::BraceClose => NodeKind::BlockClose,
+                    LineKind::Unknown => NodeKind::Other,
+                },
+                text: line.text.to_string(),
+                ending: line.ending,
+                dirty: false,
+                spans: None,
+            })
+            .collect();
+
+        Document {
+            nodes,
+            spanned: false,
+        }
+    }
+
+    /// Same as [`Document::parse_with`], keeping [`EntrySpans`] of entries.
+    ///
+    /// Spans are byte offsets in `input`. Edits drop the spans of lines they rewrite,
+    /// but do not shift those of the other lines, except [`Document::reparse_range`].
+    ///
+    /// [`Document::parse_with`]: crate::document::Document::parse_with
+    /// [`EntrySpans`]: crate::document::EntrySpans
+    /// [`Document::reparse_range`]: crate::document::Document::reparse_range
+    pub fn parse_spanned(input: &str, options: &ParseOptions) -> Document {
+        let mut document = Document::parse_with(input, options);
+        document.spanned = true;
+        for (node, line) in document.nodes.iter_mut().zip(lines(input)) {
+            if node.kind == NodeKind::Entry {
+                node.spans = entry_spans(line.text, input, options);
+            }
+        }
+
+        document
+    }
+
+    /// Returns all nodes in order.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Returns all entries with their keys and fields shared through `interner`,
+    /// so that keys and fields repeated across the document, such as `overlay`, are stored once.
+    pub fn intern_entries(&self, interner: &mut Interner) -> Vec<InternedEntry> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| node.key_value().map(|kv| (i + 1, kv)))
+            .map(|(line, kv)| InternedEntry {
+                line,
+                key: interner.intern(kv.key),
+                fields: kv.value.split(',').map(|f| interner.intern(f)).collect(),
+            })
+            .collect()
+    }
+
+    /// Returns the value of the last entry with `key`, which is in effect.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.last_position(key)
+            .and_then(|i| self.nodes[i].key_value())
+            .map(|kv| kv.value)
+    }
+
+    /// Same as [`Document::set_value`].
+    ///
+    /// [`Document::set_value`]: crate::document::Document::set_value
+    pub fn set(&mut self, key: &str, value: &str) -> Result<bool, WriteError> {
+        self.set_value(key, value)
+    }
+
+    /// Inserts a new entry after the last entry with `after`.
+    ///
+    /// The new line takes the line ending of the line before it.
+    /// Returns `Ok(false)` when there is no such entry.
+    pub fn insert_after(
+        &mut self,
+        after: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<bool, WriteError> {
+        let Some(index) = self.last_position(after) else {
+            return Ok(false);
+        };
+
+        let text = entry_text(key, value)?;
+        self.insert_node(index + 1, text);
+        Ok(true)
+    }
+
+    /// Same as [`Document::remove_entry`].
+    ///
+    /// [`Document::remove_entry`]: crate::document::Document::remove_entry
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.remove_entry(key)
+    }
+
+    /// Sets the value of the last entry with `key`, which is in effect, rewriting only its line.
+    ///
+    /// Returns `Ok(false)` when there is no such entry.
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<bool, WriteError> {
+        Ok(self.set_values(None, key, value, false)? > 0)
+    }
+
+    /// Same as [`Document::set_value`], setting all entries with `key`. Returns the number of them.
+    ///
+    /// [`Document::set_value`]: crate::document::Document::set_value
+    pub fn set_value_all(&mut self, key: &str, value: &str) -> Result<usize, WriteError> {
+        self.set_values(None, key, value, true)
+    }
+
+    /// Renames the key of the last entry with `old`, keeping the rest of its line as is.
+    ///
+    /// Returns `Ok(false)` when there is no such entry.
+    pub fn rename_key(&mut self, old: &str, new: &str) -> Result<bool, WriteError> {
+        Ok(self.rename_keys(None, old, new, false)? > 0)
+    }
+
+    /// Same as [`Document::rename_key`], renaming all entries with `old`. Returns the number of them.
+    ///
+    /// [`Document::rename_key`]: crate::document::Document::rename_key
+    pub fn rename_key_all(&mut self, old: &str, new: &str) -> Result<usize, WriteError> {
+        self.rename_keys(None, old, new, true)
+    }
+
+    /// Removes the last entry with `key`, returning whether it existed.
+    pub fn remove_entry(&mut self, key: &str) -> bool {
+        self.remove_entries(None, key, false) > 0
+    }
+
+    /// Same as [`Document::remove_entry`], removing all entries with `key`. Returns the number of them.
+    ///
+    /// [`Document::remove_entry`]: crate::document::Document::remove_entry
+    pub fn remove_entry_all(&mut self, key: &str) -> usize {
+        self.remove_entries(None, key, true)
+    }
+
+    /// Appends a new entry at the end of the document.
+    pub fn push_entry(&mut self, key: &str, value: &str) -> Result<(), WriteError> {
+        let text = entry_text(key, value)?;
+        self.insert_node(self.nodes.len(), text);
+        Ok(())
+    }
+
+    /// Trims spaces and tabs at the end of the value of the entry at 1-based line `number`,
+    /// rewriting only its line.
+    ///
+    /// Returns `false` when the line is not an entry or its value does not end with them.
+    pub fn trim_value_end(&mut self, number: usize) -> bool {
+        let Some(node) = number.checked_sub(1).and_then(|i| self.nodes.get_mut(i)) else {
+            return false;
+        };
+        if node.kind != NodeKind::Entry {
+            return false;
+        }
+        let trimmed = node.text.trim_end_matches([' ', '\t']).len();
+        if trimmed == node.text.len() || !node.text[..trimmed].contains(',') {
+            return false;
+        }
+
+        let mut text = core::mem::take(&mut node.text);
+        text.truncate(trimmed);
+        node.rewrite(text);
+        true
+    }
+
+    /// Returns edits restricted to the bodies of blocks with `header`, compared ignoring ASCII case.
+    ///
+    /// Blocks of the same header are taken as one, with headers naming blocks separated by `,`,
+    /// such as `surface0,surface1`. Ranges, such as `surface1-3`, are compared as written.
+    /// Returns `None` when there is no such block.
+    pub fn block_mut(&mut self, header: &str) -> Option<BlockMut<'_>> {
+        if !self.nodes.iter().any(|n| is_header_of(n, header)) {
+            return None;
+        }
+
+        Some(BlockMut {
+            document: self,
+            header: header.to_string(),
+        })
+    }
+
+    fn set_values(
+        &mut self,
+        block: Option<&str>,
+        key: &str,
+        value: &str,
+        all: bool,
+    ) -> Result<usize, WriteError> {
+        let text = entry_text(key, value)?;
+        let targets = self.targets(block, key, all);
+        for &i in targets.iter() {
+            self.nodes[i].rewrite(text.clone());
+        }
+        Ok(targets.len())
+    }
+
+    fn rename_keys(
+        &mut self,
+        block: Option<&str>,
+        old: &str,
+        new: &str,
+        all: bool,
+    ) -> Result<usize, WriteError> {
+        check_key(new)?;
+        let targets = self.targets(block, old, all);
+        for &i in targets.iter() {
+            let node = &mut self.nodes[i];
+            // The key is the start of the line, as the parser of entries reads it.
+            node.text.replace_range(..old.len(), new);
+            node.dirty = true;
+            node.spans = None;
+        }
+        Ok(targets.len())
+    }
+
+    fn remove_entries(&mut self, block: Option<&str>, key: &str, all: bool) -> usize {
+        let targets = self.targets(block, key, all);
+        for &i in targets.iter().rev() {
+            self.remove_node(i);
+        }
+        targets.len()
+    }
+
+    /// Returns positions of entries with `key` in the bodies of blocks with `block`, or in the whole document.
+    /// Only the last is returned unless `all`.
+    fn targets(&self, block: Option<&str>, key: &str, all: bool) -> Vec<usize> {
+        let mut in_scope = block.is_none();
+        let mut targets: Vec<_> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| match block {
+                Some(header) if is_header_of(node, header) => {
+                    in_scope = true;
+                    false
+                }
+                Some(_) if node.kind == NodeKind::BlockClose => {
+                    in_scope = false;
+                    false
+                }
+                _ => in_scope && node.key_value().is_some_and(|kv| kv.key == key),
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        if !all {
+            targets.drain(..targets.len().saturating_sub(1));
+        }
+        targets
+    }
+
+    /// Inserts an entry of `text` at `index`, taking the line ending of the line before it.
+    fn insert_node(&mut self, index: usize, text: String) {
+        let default = self.line_ending();
+        let ending = match index.checked_sub(1).map(|i| &mut self.nodes[i]) {
+            Some(prev) if prev.ending == LineEnding::None => {
+                prev.ending = default;
+                prev.dirty = true;
+                LineEnding::None
+            }
+            Some(prev) => prev.ending,
+            None => default,
+        };
+        self.nodes.insert(index, Node::entry(text, ending));
+    }
+
+    /// Removes the node at `index`, moving the lack of a final line ending to the line before it.
+    fn remove_node(&mut self, index: usize) {
+        let removed = self.nodes.remove(index);
+        if removed.ending == LineEnding::None && index > 0 {
+            self.nodes[index - 1].ending = LineEnding::None;
+            self.nodes[index - 1].dirty = true;
+        }
+    }
+
+    fn last_position(&self, key: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .rposition(|n| n.key_value().is_some_and(|kv| kv.key == key))
+    }
+
+    /// Returns the line ending used in the document, `\r\n` when there is none.
+    fn line_ending(&self) -> LineEnding {
+        self.nodes
+            .iter()
+            .map(|n| n.ending)
+            .find(|e| *e != LineEnding::None)
+            .unwrap_or_default()
+    }
+}
+
+/// Edits of a [`Document`] restricted to the bodies of blocks of one header, given by [`Document::block_mut`].
+///
+/// Entries outside the blocks are left untouched, even with the same key.
+///
+/// [`Document`]: crate::document::Document
+/// [`Document::block_mut`]: crate::document::Document::block_mut
+#[derive(Debug)]
+pub struct BlockMut<'a> {
+    document: &'a mut Document,
+    header: String,
+}
+
+impl BlockMut<'_> {
+    /// Same as [`Document::set_value`], in the blocks.
+    ///
+    /// [`Document::set_value`]: crate::document::Document::set_value
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<bool, WriteError> {
+        Ok(self
+            .document
+            .set_values(Some(&self.header), key, value, false)?
+            > 0)
+    }
+
+    /// Same as [`Document::set_value_all`], in the blocks.
+    ///
+    /// [`Document::set_value_all`]: crate::document::Document::set_value_all
+    pub fn set_value_all(&mut self, key: &str, value: &str) -> Result<usize, WriteError> {
+        self.document
+            .set_values(Some(&self.header), key, value, true)
+    }
+
+    /// Same as [`Document::rename_key`], in the blocks.
+    ///
+    /// [`Document::rename_key`]: crate::document::Document::rename_key
+    pub fn rename_key(&mut self, old: &str, new: &str) -> Result<bool, WriteError> {
+        Ok(self
+            .document
+            .rename_keys(Some(&self.header), old, new, false)?
+            > 0)
+    }
+
+    /// Same as [`Document::rename_key_all`], in the blocks.
+    ///
+    /// [`Document::rename_key_all`]: crate::document::Document::rename_key_all
+    pub fn rename_key_all(&mut self, old: &str, new: &str) -> Result<usize, WriteError> {
+        self.document
+            .rename_keys(Some(&self.header), old, new, true)
+    }
+
+    /// Same as [`Document::remove_entry`], in the blocks.
+    ///
+    /// [`Document::remove_entry`]: crate::document::Document::remove_entry
+    pub fn remove_entry(&mut self, key: &str) -> bool {
+        self.document.remove_entries(Some(&self.header), key, false) > 0
+    }
+
+    /// Same as [`Document::remove_entry_all`], in the blocks.
+    ///
+    /// [`Document::remove_entry_all`]: crate::document::Document::remove_entry_all
+    pub fn remove_entry_all(&mut self, key: &str) -> usize {
+        self.document.remove_entries(Some(&self.header), key, true)
+    }
+
+    /// Appends a new entry before the closing brace of the last block.
+    ///
+    /// The entry is appended at the end of the document when the block is not closed.
+    pub fn push_entry(&mut self, key: &str, value: &str) -> Result<(), WriteError> {
+        let text = entry_text(key, value)?;
+        let nodes = &self.document.nodes;
+        let header = nodes
+            .iter()
+            .rposition(|n| is_header_of(n, &self.header))
+            .unwrap_or_default();
+        let index = nodes[header..]
+            .iter()
+            .position(|n| n.kind == NodeKind::BlockClose)
+            .map_or(nodes.len(), |i| header + i);
+
+        self.document.insert_node(index, text);
+        Ok(())
+    }
+}
+
+/// Checks whether `node` is the header of a block with `header`, such as `surface0` or `Surface0 {`.
+fn is_header_of(node: &Node, header: &str) -> bool {
+    node.kind == NodeKind::BlockHeader
+        && node
+            .text
+            .trim()
+            .trim_end_matches('{')
+            .split(',')
+            .any(|name| eq_ignore_case(name.trim(), header))
+}
+
+/// Returns spans of the entry `text`, a subslice of `source`.
+fn entry_spans(text: &str, source: &str, options: &ParseOptions) -> Option<EntrySpans> {
+    let (_, kv) = all_consuming(|input| parse_key_value_with(input, options))(text).ok()?;
+
+    Some(EntrySpans {
+        key: span_of(source, kv.key),
+        value: span_of(source, kv.value),
+        fields: field_spans(&kv, text, source, options),
+    })
+}
+
+/// Returns spans of the fields of `kv`, the entry `text`, a subslice of `source`.
+#[cfg(feature = "surfaces")]
+fn field_spans(
+    kv: &KeyValue<'_>,
+    text: &str,
+    source: &str,
+    options: &ParseOptions,
+) -> Option<FieldSpans> {
+    let mut segments = kv.key.split('.');
+    let first = segments.next().unwrap_or_default();
+    if segment_index(first, "element").is_some() {
+        all_consuming(|input| parse_element_spanned(input, source, options))(text)
+            .ok()
+            .map(|(_, e)| FieldSpans::Element(e.value))
+    } else if segment_index(first, "collision").is_some() {
+        all_consuming(|input| parse_collision_spanned(input, source, options))(text)
+            .ok()
+            .map(|(_, c)| FieldSpans::Collision(c.value))
+    } else if segment_index(first, "animation").is_some()
+        && segments
+            .next()
+            .and_then(|s| segment_index(s, "pattern"))
+            .is_some()
+        && segments.next().is_none()
+    {
+        all_consuming(|input| parse_pattern_spanned(input, source))(kv.value)
+            .ok()
+            .map(|(_, p)| FieldSpans::Pattern(p.value))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(feature = "surfaces"))]
+fn field_spans(
+    _kv: &KeyValue<'_>,
+    _text: &str,
+    _source: &str,
+    _options: &ParseOptions,
+) -> Option<FieldSpans> {
+    None
+}
+
+fn entry_text(key: &str, value: &str) -> Result<String, WriteError> {
+    let mut text = String::new();
+    write_key_value(&mut text, key, value, LineEnding::None)?;
+    Ok(text)
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in self.nodes.iter() {
+            f.write_str(&node.text)?;
+            f.write_str(node.ending.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASE: &str = "charset,UTF-8\r\n\r\n// master shell\r\nname,master\r\n  sakura.balloon.offsetx , 10\r\nsurface0\r\n{\r\nelement0,base,surface0.png,0,0\r\n}\r\n?? broken\r\ncraftman,someone";
+
+    mod document {
+        use super::*;
+
+        #[test]
+        fn identity_when_unmodified() {
+            let document = Document::parse(CASE);
+            assert_eq!(document.to_string(), CASE);
+            assert_eq!(document.nodes()[2].kind, NodeKind::Comment);
+            assert_eq!(document.nodes()[5].kind, NodeKind::BlockHeader);
+            assert_eq!(document.nodes()[9].kind, NodeKind::Other);
+            assert_eq!(document.get("element0"), Some("base,surface0.png,0,0"));
+        }
+
+        #[test]
+        fn one_line_changed_when_set() {
+            let mut document = Document::parse(CASE);
+            assert!(document.set("name", "new").unwrap());
+            assert!(!document.set("homeurl", "x").unwrap());
+
+            let output = document.to_string();
+            let changed: Vec<_> = CASE
+                .split("\r\n")
+                .zip(output.split("\r\n"))
+                .filter(|(a, b)| a != b)
+                .collect();
+            assert_eq!(changed, vec![("name,master", "name,new")]);
+            assert_eq!(document.get("name"), Some("new"));
+        }
+
+        #[test]
+        fn inserted_after_existing_key() {
+            let mut document = Document::parse(CASE);
+            assert!(document.insert_after("name", "id", "master_id").unwrap());
+            assert!(document
+                .insert_after("craftman", "craftmanw", "誰か")
+                .unwrap());
+            assert!(!document.insert_after("homeurl", "a", "b").unwrap());
+
+            let output = document.to_string();
+            assert!(output.contains("name,master\r\nid,master_id\r\n  sakura"));
+            assert!(output.ends_with("craftman,someone\r\ncraftmanw,誰か"));
+        }
+
+        #[test]
+        fn last_changed_when_duplicates() {
+            let case = "name,a\r\nid,x\r\nname,b\r\n";
+            let mut document = Document::parse(case);
+            assert_eq!(document.get("name"), Some("b"));
+
+            assert!(document.set("name", "c").unwrap());
+            assert!(document.insert_after("name", "id", "y").unwrap());
+            assert_eq!(document.to_string(), "name,a\r\nid,x\r\nname,c\r\nid,y\r\n");
+            assert!(document.remove("name"));
+            assert_eq!(document.get("name"), Some("a"));
+        }
+
+        #[test]
+        fn removed_by_key() {
+            let mut document = Document::parse("a,1\nb,2");
+            assert!(document.remove("b"));
+            assert!(!document.remove("b"));
+            assert_eq!(document.to_string(), "a,1");
+            assert!(document.set("a", "x\ny").is_err());
+        }
+
+        #[test]
+        fn comments_when_comment_prefixes() {
+            let case = "# generated,by tool\r\nname,master\r\n";
+            let options = ParseOptions::STRICT.comment_prefixes(&["//", "#"]).unwrap();
+
+            let document = Document::parse_with(case, &options);
+            assert_eq!(document.nodes()[0].kind, NodeKind::Comment);
+            assert_eq!(document.get("# generated"), None);
+            assert_eq!(document.to_string(), case);
+
+            let document = Document::parse(case);
+            assert_eq!(document.nodes()[0].kind, NodeKind::Entry);
+        }
+    }
+
+    #[cfg(feature = "surfaces")]
+    mod parse_spanned {
+        use super::*;
+
+        #[test]
+        fn success_when_element_line() {
+            let case = "name,master\r\nsurface0\r\n{\r\nelement0 , overlay,a.png,5,10\r\nanimation0.pattern0,base,1,50\r\n}\r\n";
+            let options = ParseOptions::STRICT.trim_whitespace(true);
+            let mut document = Document::parse_spanned(case, &options);
+
+            let spans = document.nodes()[0].spans.clone().unwrap();
+            assert_eq!((spans.key, spans.value, spans.fields), (0..4, 5..11, None));
+
+            let spans = document.nodes()[3].spans.clone().unwrap();
+            assert_eq!(&case[spans.key.clone()], "element0");
+            let Some(FieldSpans::Element(element)) = spans.fields else {
+                panic!("{:?}", spans.fields);
+            };
+            assert_eq!(element.method.span, 37..44);
+            assert_eq!(&case[element.filename.span.clone()], "a.png");
+            assert_eq!(element.offset.unwrap().y.span, 53..55);
+
+            let spans = document.nodes()[4].spans.clone().unwrap();
+            let Some(FieldSpans::Pattern(pattern)) = spans.fields else {
+                panic!("{:?}", spans.fields);
+            };
+            assert_eq!(pattern.method, 77..81);
+            assert_eq!(pattern.fields, vec![82..83, 84..86]);
+
+            assert!(Document::parse(case)
+                .nodes()
+                .iter()
+                .all(|n| n.spans.is_none()));
+            assert!(document.set_value("name", "new").unwrap());
+            assert_eq!(document.nodes()[0].spans, None);
+            assert!(document.nodes()[3].spans.is_some());
+        }
+    }
+
+    mod policies {
+        use super::*;
+
+        const CASE: &str = "craftmanurl,\r\nhomeurl\r\nname,master\r\n";
+
+        fn kinds(document: &Document) -> Vec<NodeKind> {
+            document.nodes().iter().map(|n| n.kind).collect()
+        }
+
+        #[test]
+        fn success_when_empty_value_kept() {
+            for policy in [EmptyValuePolicy::Keep, EmptyValuePolicy::Warn] {
+                let options = ParseOptions::COMPATIBLE.empty_value(policy);
+                let mut document = Document::parse_with(CASE, &options);
+                assert_eq!(document.get("craftmanurl"), Some(""));
+                assert_eq!(document.to_string(), CASE);
+
+                assert!(document.set("name", "new").unwrap());
+                assert_eq!(
+                    document.to_string(),
+                    CASE.replace("name,master", "name,new")
+                );
+            }
+
+            let options = ParseOptions::COMPATIBLE.empty_value(EmptyValuePolicy::Error);
+            let document = Document::parse_with(CASE, &options);
+            assert_eq!(
+                kinds(&document),
+                vec![NodeKind::Other, NodeKind::Entry, NodeKind::Entry]
+            );
+            assert_eq!(document.get("craftmanurl"), None);
+            assert_eq!(document.to_string(), CASE);
+        }
+
+        #[test]
+        fn success_when_bare_key_treated_as_empty_value() {
+            for policy in [BareKeyPolicy::TreatAsEmptyValue, BareKeyPolicy::Warn] {
+                let options = ParseOptions::STRICT.bare_key(policy);
+                let mut document = Document::parse_with(CASE, &options);
+                assert_eq!(document.get("homeurl"), Some(""));
+                assert_eq!(document.to_string(), CASE);
+
+                assert!(document.set_value("homeurl", "http://a/b").unwrap());
+                assert_eq!(
+                    document.to_string(),
+                    CASE.replace("homeurl\r\n", "homeurl,http://a/b\r\n")
+                );
+            }
+
+            let options = ParseOptions::STRICT.bare_key(BareKeyPolicy::Error);
+            let document = Document::parse_with(CASE, &options);
+            assert_eq!(
+                kinds(&document),
+                vec![NodeKind::Entry, NodeKind::Other, NodeKind::Entry]
+            );
+            assert_eq!(document.get("homeurl"), None);
+            assert_eq!(Document::parse(CASE), document);
+        }
+    }
+
+    mod edits {
+        use super::*;
+
+        const BLOCKS: &str = "name,a\r\nid,x\r\nname,b\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\nname,c\r\n}\r\nsurface1 {\r\nelement0,base,a.png,0,0\r\n}\r\n";
+
+        fn dirty_lines(document: &Document) -> Vec<&str> {
+            document
+                .nodes()
+                .iter()
+                .filter(|n| n.dirty)
+                .map(|n| n.text.as_str())
+                .collect()
+        }
+
+        #[test]
+        fn success_when_renamed_with_duplicates() {
+            let mut document = Document::parse(BLOCKS);
+            assert!(document.rename_key("name", "title").unwrap());
+            assert_eq!(dirty_lines(&document), vec!["title,c"]);
+
+            let mut document = Document::parse(BLOCKS);
+            assert_eq!(document.rename_key_all("name", "title").unwrap(), 3);
+            assert_eq!(document.to_string(), BLOCKS.replace("name,", "title,"));
+            assert_eq!(document.rename_key_all("name", "title").unwrap(), 0);
+            assert!(document.rename_key("id", "a,b").is_err());
+        }
+
+        #[test]
+        fn success_when_edited_in_block() {
+            let mut document = Document::parse(BLOCKS);
+            let mut block = document.block_mut("Surface1").unwrap();
+            assert!(block.set_value("element0", "base,b.png,0,0").unwrap());
+            assert!(!block.remove_entry("name"));
+            block.push_entry("element1", "overlay,c.png,0,0").unwrap();
+
+            assert_eq!(
+                document.to_string(),
+                BLOCKS.replace(
+                    "surface1 {\r\nelement0,base,a.png,0,0\r\n",
+                    "surface1 {\r\nelement0,base,b.png,0,0\r\nelement1,overlay,c.png,0,0\r\n"
+                )
+            );
+            assert_eq!(
+                dirty_lines(&document),
+                vec!["element0,base,b.png,0,0", "element1,overlay,c.png,0,0"]
+            );
+            assert!(document.block_mut("surface2").is_none());
+
+            // The last `name` is in `surface0`, and those outside it are kept.
+            assert!(document.set_value("name", "d").unwrap());
+            assert_eq!(
+                document
+                    .block_mut("surface0")
+                    .unwrap()
+                    .remove_entry_all("name"),
+                1
+            );
+            assert_eq!(document.remove_entry_all("name"), 2);
+        }
+
+        #[test]
+        fn success_when_combined_header() {
+            let case = "surface0, surface1\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\nsurface1-3\r\n{\r\n}\r\n";
+            let mut document = Document::parse(case);
+            let mut block = document.block_mut("surface1").unwrap();
+            assert!(block.set_value("element0", "base,b.png,0,0").unwrap());
+
+            assert_eq!(document.to_string(), case.replace("a.png", "b.png"));
+            assert!(document.block_mut("surface2").is_none());
+            assert!(document.block_mut("surface1-3").is_some());
+        }
+
+        #[test]
+        fn success_when_removed_and_pushed_again() {
+            let mut document = Document::parse(CASE);
+            assert!(document.remove_entry("name"));
+            document.push_entry("name", "master").unwrap();
+
+            let output = document.to_string();
+            assert_eq!(
+                output,
+                CASE.replace("name,master\r\n", "") + "\r\nname,master"
+            );
+            assert_eq!(
+                dirty_lines(&document),
+                vec!["craftman,someone", "name,master"]
+            );
+            assert_eq!(document.remove_entry_all("name"), 1);
+            assert_eq!(document.to_string(), CASE.replace("name,master\r\n", ""));
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Re-parsing the lines touched by an edit of the text of a [`Document`].
+//!
+//! [`Document`]: crate::document::Document
+
+use alloc::{string::String, vec, vec::Vec};
+use core::ops::Range;
+
+use crate::{
+    block::{parse_blocks_with, BlockError},
+    error::Located,
+    options::ParseOptions,
+};
+
+#[cfg(feature = "surfaces")]
+use super::FieldSpans;
+use super::{Document, EntrySpans, Node, NodeKind};
+
+/// What [`Document::reparse_range`] changed.
+///
+/// Ranges and indices are of nodes after the edit.
+///
+/// [`Document::reparse_range`]: crate::document::Document::reparse_range
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ReparseResult {
+    /// Nodes re-parsed, replacing the lines touched by the edit and the line before them.
+    pub nodes: Range<usize>,
+    /// Number of nodes replaced.
+    pub removed: usize,
+    /// Entries of `nodes` with text not kept from before the edit.
+    pub entries: Vec<usize>,
+    /// Nodes of the blocks `nodes` are in, with the blocks opened or closed by the edit.
+    /// It is `nodes` when they are outside blocks.
+    pub blocks: Range<usize>,
+    /// Errors of braces in `blocks`, at line numbers of the document.
+    pub diagnostics: Vec<Located<BlockError>>,
+    /// Shift of byte offsets after the edit.
+    pub delta: isize,
+}
+
+impl Document {
+    /// Replaces `edited`, a byte range of the text the document writes, with `new_text`,
+    /// re-parsing only the lines it touches as [`Document::parse`] does.
+    ///
+    /// See [`Document::reparse_range_with`] for the region re-parsed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `edited` is out of the text or not on `char` boundaries.
+    ///
+    /// [`Document::parse`]: crate::document::Document::parse
+    /// [`Document::reparse_range_with`]: crate::document::Document::reparse_range_with
+    pub fn reparse_range(&mut self, edited: Range<usize>, new_text: &str) -> ReparseResult {
+        self.reparse_range_with(edited, new_text, &ParseOptions::STRICT)
+    }
+
+    /// Same as [`Document::reparse_range`], re-parsing lines with `options`,
+    /// which are expected to be those the document was parsed with.
+    ///
+    /// Lines touched by the edit are re-parsed with the line before them, which may become a header,
+    /// and the blocks they are in are checked again for braces.
+    /// Spans of documents given by [`Document::parse_spanned`] are kept for entries re-parsed,
+    /// and shifted for the lines after them.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `edited` is out of the text or not on `char` boundaries.
+    ///
+    /// [`Document::reparse_range`]: crate::document::Document::reparse_range
+    /// [`Document::parse_spanned`]: crate::document::Document::parse_spanned
+    pub fn reparse_range_with(
+        &mut self,
+        edited: Range<usize>,
+        new_text: &str,
+        options: &ParseOptions,
+    ) -> ReparseResult {
+        let offsets = self.offsets();
+        let len = offsets[self.nodes.len()];
+        assert!(
+            edited.start <= edited.end && edited.end <= len,
+            "edited range {:?} out of the text of {} bytes",
+            edited,
+            len
+        );
+
+        // Lines touched by the edit, including the line starting at its end, which joins the line before it.
+        let mut first = line_at(&offsets, edited.start);
+        let end = (line_at(&offsets, edited.end) + 1).min(self.nodes.len());
+        // Whether a line is a header depends on the next line which is not blank or comment.
+        first = self.nodes[..first]
+            .iter()
+            .rposition(|n| !is_skipped(n))
+            .unwrap_or(first);
+        let lookahead = self.nodes[end..]
+            .iter()
+            .position(|n| !is_skipped(n))
+            .map_or(self.nodes.len(), |i| end + i + 1);
+
+        let start = offsets[first];
+        let mut text = String::new();
+        for node in self.nodes[first..end].iter() {
+            text.push_str(&node.text);
+            text.push_str(node.ending.as_str());
+        }
+        text.replace_range(edited.start - start..edited.end - start, new_text);
+        let region = text.len();
+        for node in self.nodes[end..lookahead].iter() {
+            text.push_str(&node.text);
+            text.push_str(node.ending.as_str());
+        }
+
+        let parsed = if self.spanned {
+            Document::parse_spanned(&text, options)
+        } else {
+            Document::parse_with(&text, options)
+        };
+        let delta = region as isize - (offsets[end] - start) as isize;
+        let mut nodes = parsed.nodes;
+        nodes.truncate(nodes.len() - (lookahead - end));
+        for spans in nodes.iter_mut().filter_map(|n| n.spans.as_mut()) {
+            shift_spans(spans, start as isize);
+        }
+        for spans in self.nodes[end..]
+            .iter_mut()
+            .filter_map(|n| n.spans.as_mut())
+        {
+            shift_spans(spans, delta);
+        }
+
+        // Lines kept at both ends, compared with those replaced.
+        let old = &self.nodes[first..end];
+        let same =
+            |a: &Node, b: &Node| a.kind == b.kind && a.text == b.text && a.ending == b.ending;
+        let head = old
+            .iter()
+            .zip(nodes.iter())
+            .take_while(|(a, b)| same(a, b))
+            .count();
+        let tail = old[head..]
+            .iter()
+            .rev()
+            .zip(nodes[head..].iter().rev())
+            .take_while(|(a, b)| same(a, b))
+            .count();
+        let count = nodes.len();
+        let changed = head..count - tail;
+        for (i, node) in nodes.iter_mut().enumerate() {
+            node.dirty = if changed.contains(&i) {
+                true
+            } else if i < head {
+                old[i].dirty
+            } else {
+                old[old.len() - (count - i)].dirty
+            };
+        }
+
+        self.nodes.splice(first..end, nodes);
+        let replaced = first..first + count;
+        let entries = (first + changed.start..first + changed.end)
+            .filter(|&i| self.nodes[i].kind == NodeKind::Entry)
+            .collect();
+
+        let blocks = self.enclosing_blocks(replaced.clone());
+        let mut block_text = String::new();
+        for node in self.nodes[blocks.clone()].iter() {
+            block_text.push_str(&node.text);
+            block_text.push_str(node.ending.as_str());
+        }
+        let diagnostics = match parse_blocks_with(&block_text, options) {
+            Ok(_) => Vec::new(),
+            Err(e) => vec![Located::new(blocks.start + e.line, e.value)],
+        };
+
+        ReparseResult {
+            nodes: replaced,
+            removed: end - first,
+            entries,
+            blocks,
+            diagnostics,
+            delta,
+        }
+    }
+
+    /// Returns the byte offset of the start of each node, followed by the length of the text.
+    fn offsets(&self) -> Vec<usize> {
+        let mut offsets = Vec::with_capacity(self.nodes.len() + 1);
+        let mut offset = 0;
+        offsets.push(offset);
+        for node in self.nodes.iter() {
+            offset += node.text.len() + node.ending.as_str().len();
+            offsets.push(offset);
+        }
+        offsets
+    }
+
+    /// Extends `nodes` to the blocks they are in, up to the closing brace of the last,
+    /// or the next header when it is not closed.
+    fn enclosing_blocks(&self, nodes: Range<usize>) -> Range<usize> {
+        let mut start = nodes.start;
+        for (i, node) in self.nodes[..nodes.start].iter().enumerate().rev() {
+            match node.kind {
+                NodeKind::BlockClose => break,
+                NodeKind::BlockHeader => {
+                    start = i;
+                    break;
+                }
+                // A brace without the header is a block by itself.
+                NodeKind::BlockOpen => start = i,
+                _ => {}
+            }
+        }
+
+        let mut open = start < nodes.start;
+        for node in self.nodes[nodes.clone()].iter() {
+            match node.kind {
+                NodeKind::BlockHeader | NodeKind::BlockOpen => open = true,
+                NodeKind::BlockClose => open = false,
+                _ => {}
+            }
+        }
+
+        let mut end = nodes.end;
+        if open {
+            end = self.nodes.len();
+            for (i, node) in self.nodes.iter().enumerate().skip(nodes.end) {
+                match node.kind {
+                    NodeKind::BlockClose => {
+                        end = i + 1;
+                        break;
+                    }
+                    NodeKind::BlockHeader => {
+                        end = i;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        start..end
+    }
+}
+
+/// Returns the index of the node `offset` is in, the last node for the end of the text.
+fn line_at(offsets: &[usize], offset: usize) -> usize {
+    let nodes = offsets.len() - 1;
+    offsets[..nodes]
+        .partition_point(|&o| o <= offset)
+        .saturating_sub(1)
+}
+
+/// Checks whether `node` is skipped in finding the header of `{`.
+fn is_skipped(node: &Node) -> bool {
+    matches!(node.kind, NodeKind::Blank | NodeKind::Comment)
+}
+
+fn shift_spans(spans: &mut EntrySpans, delta: isize) {
+    let shift = |range: &mut Range<usize>| {
+        range.start = range.start.wrapping_add_signed(delta);
+        range.end = range.end.wrapping_add_signed(delta);
+    };
+
+    shift(&mut spans.key);
+    shift(&mut spans.value);
+    #[cfg(feature = "surfaces")]
+    match &mut spans.fields {
+        Some(FieldSpans::Element(e)) => {
+            shift(&mut e.id.span);
+            shift(&mut e.method.span);
+            shift(&mut e.filename.span);
+            if let Some(offset) = &mut e.offset {
+                shift(&mut offset.x.span);
+                shift(&mut offset.y.span);
+            }
+            if let Some(alpha) = &mut e.alpha {
+                shift(&mut alpha.span);
+            }
+        }
+        Some(FieldSpans::Collision(c)) => {
+            shift(&mut c.id.span);
+            shift(&mut c.rect.left.span);
+            shift(&mut c.rect.top.span);
+            shift(&mut c.rect.right.span);
+            shift(&mut c.rect.bottom.span);
+            shift(&mut c.name.span);
+        }
+        Some(FieldSpans::Pattern(p)) => {
+            shift(&mut p.method);
+            p.fields.iter_mut().for_each(shift);
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASE: &str = "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n// second\r\nsurface1\r\n{\r\nelement0,base,b.png,0,0\r\nanimation0.pattern0,overlay,10,50,0,0\r\ncollision0,0,0,10,10,Head\r\n}\r\n";
+
+    /// Checks that `document` has the nodes and spans of its text parsed again.
+    fn assert_reparsed(document: &Document) {
+        let text = document.to_string();
+        let parsed = Document::parse_spanned(&text, &ParseOptions::STRICT);
+        let strip = |d: &Document| {
+            d.nodes()
+                .iter()
+                .map(|n| (n.kind, n.text.clone(), n.ending, n.spans.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(strip(document), strip(&parsed));
+    }
+
+    fn offset_of(document: &Document, part: &str) -> Range<usize> {
+        let start = document.to_string().find(part).unwrap();
+        start..start + part.len()
+    }
+
+    mod reparse_range {
+        use super::*;
+
+        #[test]
+        fn success_when_value_edited() {
+            let mut document = Document::parse_spanned(CASE, &ParseOptions::STRICT);
+            let edited = offset_of(&document, "a.png");
+            let result = document.reparse_range(edited, "body.png");
+
+            assert_eq!(document.to_string(), CASE.replace("a.png", "body.png"));
+            assert_eq!(result.nodes, 2..4);
+            assert_eq!(result.removed, 2);
+            assert_eq!(result.entries, vec![3]);
+            assert_eq!(result.blocks, 1..5);
+            assert!(result.diagnostics.is_empty());
+            assert_eq!(result.delta, 3);
+            assert!(document.nodes()[3].dirty);
+            assert!(!document.nodes()[2].dirty);
+            assert_reparsed(&document);
+        }
+
+        #[test]
+        fn success_when_line_inserted() {
+            let mut document = Document::parse_spanned(CASE, &ParseOptions::STRICT);
+            let at = offset_of(&document, "collision0").start;
+            let result = document.reparse_range(at..at, "element1,overlay,c.png,5,5\r\n");
+
+            assert_eq!(result.entries, vec![10]);
+            assert_eq!(result.blocks, 6..13);
+            assert!(result.diagnostics.is_empty());
+            assert_eq!(document.get("element1"), Some("overlay,c.png,5,5"));
+            assert_reparsed(&document);
+        }
+
+        #[test]
+        fn detected_when_closing_brace_deleted() {
+            let mut document = Document::parse_spanned(CASE, &ParseOptions::STRICT);
+            let close = offset_of(&document, "}\r\n// second");
+            let result = document.reparse_range(close.start..close.start + 3, "");
+
+            assert_eq!(
+                result.diagnostics,
+                vec![Located::new(2, BlockError::Unclosed)]
+            );
+            assert!(result.entries.is_empty());
+            assert_eq!(result.blocks, 1..5);
+            assert_reparsed(&document);
+
+            let at = offset_of(&document, "// second").start;
+            let result = document.reparse_range(at..at, "}\r\n");
+
+            assert_eq!(document.to_string(), CASE);
+            assert!(result.diagnostics.is_empty());
+            assert_reparsed(&document);
+            let spans = document.nodes()[10].spans.as_ref().unwrap();
+            assert_eq!(&CASE[spans.key.clone()], "collision0");
+        }
+
+        #[test]
+        fn success_when_header_joined() {
+            let mut document = Document::parse("name,a\r\nsurface0\r\n\r\n{\r\n}\r\n");
+            let brace = offset_of(&document, "{\r\n");
+            let result = document.reparse_range(brace, "");
+
+            assert_eq!(document.nodes()[1].kind, NodeKind::Other);
+            assert_eq!(result.nodes, 1..4);
+            assert_eq!(
+                result.diagnostics,
+                vec![Located::new(4, BlockError::UnexpectedClose)]
+            );
+            assert_eq!(document.to_string(), "name,a\r\nsurface0\r\n\r\n}\r\n");
+        }
+    }
+}
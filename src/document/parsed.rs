@@ -0,0 +1,259 @@
+//! `ParsedDocument`, decoded once from bytes, with entries of all lines as spans.
+
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    block::parse_blocks,
+    charset::{detect_charset_with, Charset},
+    error::{Located, ParseFailure},
+    kv::{parse_key_value_with, KeyValue},
+    line::{is_comment, lines, Line},
+    options::ParseOptions,
+};
+
+use super::shell_file::span;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct EntrySpan {
+    key: Range<usize>,
+    value: Range<usize>,
+    /// Index of the block containing the entry.
+    block: Option<usize>,
+}
+
+/// Decoded text owning its buffer, with entries in and outside blocks.
+///
+/// Entries are kept as byte ranges of the text, and borrow from it when accessed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParsedDocument {
+    charset: Charset,
+    text: String,
+    entries: Vec<Located<EntrySpan>>,
+    /// Spans of block headers, located at the header lines.
+    headers: Vec<Located<Range<usize>>>,
+}
+
+/// Decodes `bytes` into a single buffer and parses its entries.
+///
+/// The charset is detected on the raw bytes, falling back to `fallback`.
+/// All lines must be blank, comments, braces of blocks, block headers or entries.
+pub fn parse_bytes(bytes: &[u8], fallback: Charset) -> Result<ParsedDocument, ParseFailure> {
+    parse_bytes_with(bytes, fallback, &ParseOptions::STRICT)
+}
+
+/// Same as [`parse_bytes`], with the lenient behaviors of `options`
+/// in the `charset` line, control characters and the entries.
+///
+/// [`parse_bytes`]: crate::document::parse_bytes
+pub fn parse_bytes_with(
+    bytes: &[u8],
+    fallback: Charset,
+    options: &ParseOptions,
+) -> Result<ParsedDocument, ParseFailure> {
+    let charset = detect_charset_with(bytes, options).unwrap_or(fallback);
+    let text = charset
+        .decode_with(bytes, options)
+        .map_err(ParseFailure::from)?
+        .into_owned();
+
+    let blocks = parse_blocks(&text).map_err(|e| ParseFailure::Block(e.value))?;
+
+    let mut headers = Vec::with_capacity(blocks.len());
+    let mut entries = Vec::new();
+    let mut block_lines = Vec::new();
+    for (index, block) in blocks.iter().enumerate() {
+        headers.push(Located::new(block.header_line, span(&text, block.header)));
+        for line in block.body.iter() {
+            push_entry(&mut entries, &text, line, Some(index), options)?;
+        }
+        block_lines.push(block.header_line..=block.close_line);
+    }
+
+    let mut blocks = block_lines.iter().peekable();
+    for line in lines(&text) {
+        while blocks.next_if(|b| *b.end() < line.number).is_some() {}
+        if blocks.peek().is_some_and(|b| b.contains(&line.number)) {
+            continue;
+        }
+        push_entry(&mut entries, &text, &line, None, options)?;
+    }
+    entries.sort_by_key(|e| e.line);
+
+    Ok(ParsedDocument {
+        charset,
+        text,
+        entries,
+        headers,
+    })
+}
+
+fn push_entry(
+    entries: &mut Vec<Located<EntrySpan>>,
+    text: &str,
+    line: &Line<'_>,
+    block: Option<usize>,
+    options: &ParseOptions,
+) -> Result<(), ParseFailure> {
+    if line.text.trim().is_empty() || is_comment(line.text) {
+        return Ok(());
+    }
+
+    let (_, kv) = all_consuming(|input| parse_key_value_with(input, options))(line.text)?;
+    entries.push(Located::new(
+        line.number,
+        EntrySpan {
+            key: span(text, kv.key),
+            value: span(text, kv.value),
+            block,
+        },
+    ));
+    Ok(())
+}
+
+impl ParsedDocument {
+    /// Returns the charset used to decode.
+    pub fn charset(&self) -> &Charset {
+        &self.charset
+    }
+
+    /// Returns the decoded text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the decoded text, dropping the entries.
+    pub fn into_text(self) -> String {
+        self.text
+    }
+
+    /// Returns all entries in and outside blocks, in order.
+    pub fn entries(&self) -> impl Iterator<Item = Located<KeyValue<'_>>> {
+        self.entries.iter().map(|e| self.entry(e))
+    }
+
+    /// Returns entries outside blocks, in order.
+    pub fn top_level_entries(&self) -> impl Iterator<Item = Located<KeyValue<'_>>> {
+        self.entries
+            .iter()
+            .filter(|e| e.value.block.is_none())
+            .map(|e| self.entry(e))
+    }
+
+    /// Returns headers of blocks with their line numbers, in order.
+    pub fn block_headers(&self) -> impl Iterator<Item = Located<&str>> {
+        self.headers
+            .iter()
+            .map(|h| Located::new(h.line, &self.text[h.value.clone()]))
+    }
+
+    /// Returns entries in the block at `index` of [`block_headers`], in order.
+    ///
+    /// [`block_headers`]: crate::document::ParsedDocument::block_headers
+    pub fn block_entries(&self, index: usize) -> impl Iterator<Item = Located<KeyValue<'_>>> {
+        self.entries
+            .iter()
+            .filter(move |e| e.value.block == Some(index))
+            .map(|e| self.entry(e))
+    }
+
+    fn entry(&self, e: &Located<EntrySpan>) -> Located<KeyValue<'_>> {
+        Located::new(
+            e.line,
+            KeyValue::new(
+                &self.text[e.value.key.clone()],
+                &self.text[e.value.value.clone()],
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_bytes {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_shift_jis_not_utf_8() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode(
+                "charset,Shift_JIS\r\n// シェル\r\nname,マスター\r\nsurface0\r\n{\r\nelement0,base,体.png,0,0\r\n}\r\ncraftman,誰か\r\n",
+            );
+            assert!(std::str::from_utf8(&case).is_err());
+            let document = parse_bytes(&case, Charset::UTF8).unwrap();
+
+            assert_eq!(document.charset(), &Charset::ShiftJIS);
+            let entries: Vec<_> = document.entries().collect();
+            assert_eq!(entries.len(), 4);
+            assert_eq!(
+                entries[1],
+                Located::new(3, KeyValue::new("name", "マスター"))
+            );
+            assert_eq!(entries[2].value.value, "base,体.png,0,0");
+            assert_eq!(document.top_level_entries().count(), 3);
+            assert_eq!(
+                document.block_headers().collect::<Vec<_>>(),
+                vec![Located::new(4, "surface0")]
+            );
+            assert_eq!(document.block_entries(0).next().unwrap().line, 6);
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_utf_8_blocks() {
+            let case = "descript\r\n{\r\nversion,1\r\n}\r\nsurface0 {\r\n\r\nelement0,base,a.png,0,0\r\n}\r\nsurface1\r\n{\r\n}\r\n";
+            let document = parse_bytes(case.as_bytes(), Charset::ShiftJIS).unwrap();
+
+            assert_eq!(document.charset(), &Charset::ShiftJIS);
+            assert_eq!(document.top_level_entries().count(), 0);
+            assert_eq!(document.block_entries(1).next().unwrap().line, 7);
+            assert_eq!(document.block_entries(2).count(), 0);
+            assert_eq!(document.into_text(), case);
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn failed_when_broken() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター\r\n");
+            assert_eq!(
+                parse_bytes(&case, Charset::UTF8).unwrap_err(),
+                ParseFailure::Decode(Charset::UTF8)
+            );
+            assert!(parse_bytes(b"surface0\r\n{\r\nbroken\r\n}\r\n", Charset::UTF8).is_err());
+            assert_eq!(
+                parse_bytes(b"}\r\n", Charset::UTF8).unwrap_err(),
+                ParseFailure::Block(crate::block::BlockError::UnexpectedClose)
+            );
+        }
+
+        #[test]
+        fn success_when_options() {
+            let case = b"surface0\r\n{\r\nelement0 , base,a.png,0,0\r\n}\r\n";
+            let document = parse_bytes_with(
+                case,
+                Charset::UTF8,
+                &ParseOptions::STRICT.trim_whitespace(true),
+            )
+            .unwrap();
+            assert_eq!(
+                document.block_entries(0).next().unwrap().value,
+                KeyValue::new("element0", "base,a.png,0,0")
+            );
+        }
+
+        #[test]
+        #[cfg(not(feature = "encodings"))]
+        fn failed_when_encoding_support_disabled() {
+            let case = b"charset,Shift_JIS\r\nname,master\r\n";
+            assert_eq!(
+                parse_bytes(case, Charset::UTF8).unwrap_err(),
+                ParseFailure::Decode(Charset::ShiftJIS)
+            );
+            assert!(parse_bytes(b"name,master\r\n", Charset::UTF8).is_ok());
+        }
+    }
+}
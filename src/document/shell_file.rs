@@ -0,0 +1,313 @@
+//! `ShellFile`, decoded file with entries, comments and blocks.
+
+use alloc::{string::String, vec::Vec};
+use core::ops::Range;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    block::{parse_block_spans, Block, BlockCursor, BlockSpan},
+    charset::{detect_charset_with, Charset},
+    error::{Located, ParseFailure},
+    kv::{parse_key_value_with, KeyValue},
+    line::{is_comment, lines},
+    options::ParseOptions,
+};
+
+/// Decoded file with its entries, comments and blocks.
+///
+/// Entries and blocks are kept as byte ranges of the text, and borrow from it when accessed.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ShellFile {
+    charset: Charset,
+    text: String,
+    /// Spans of keys and values of entries outside blocks.
+    entries: Vec<Located<(Range<usize>, Range<usize>)>>,
+    /// Spans of comment lines.
+    comments: Vec<Located<Range<usize>>>,
+    /// Spans of blocks, in order.
+    blocks: Vec<BlockSpan>,
+}
+
+impl ShellFile {
+    /// Decodes `bytes` with the charset named by its `charset` line,
+    /// or with `fallback` when there is no such line, then splits it into entries and blocks.
+    ///
+    /// Lines outside blocks must be blank, comments or entries.
+    pub fn parse(bytes: &[u8], fallback: Charset) -> Result<ShellFile, ParseFailure> {
+        ShellFile::parse_with(bytes, fallback, &ParseOptions::STRICT)
+    }
+
+    /// Same as [`ShellFile::parse`], with the lenient behaviors of `options`
+    /// in the `charset` line, control characters and the entries.
+    ///
+    /// [`ShellFile::parse`]: crate::document::ShellFile::parse
+    pub fn parse_with(
+        bytes: &[u8],
+        fallback: Charset,
+        options: &ParseOptions,
+    ) -> Result<ShellFile, ParseFailure> {
+        let charset = detect_charset_with(bytes, options).unwrap_or(fallback);
+        let text = charset
+            .decode_with(bytes, options)
+            .map_err(ParseFailure::from)?
+            .into_owned();
+
+        let blocks = parse_block_spans(&text).map_err(|e| ParseFailure::Block(e.value))?;
+        let mut in_block = BlockCursor::new(&blocks);
+
+        let mut entries = Vec::new();
+        let mut comments = Vec::new();
+        for line in lines(&text) {
+            if is_comment(line.text) {
+                comments.push(Located::new(line.number, span(&text, line.text)));
+                continue;
+            }
+            if line.text.trim().is_empty() || in_block.contains(line.number) {
+                continue;
+            }
+
+            let (_, kv) = all_consuming(|input| parse_key_value_with(input, options))(line.text)?;
+            entries.push(Located::new(
+                line.number,
+                (span(&text, kv.key), span(&text, kv.value)),
+            ));
+        }
+
+        Ok(ShellFile {
+            charset,
+            text,
+            entries,
+            comments,
+            blocks,
+        })
+    }
+
+    /// Reads the file at `path` and parses it as [`ShellFile::parse_with`] does,
+    /// falling back to Shift_JIS as baseware does. Symbolic links are followed.
+    ///
+    /// An empty file is an empty document.
+    ///
+    /// [`ShellFile::parse_with`]: crate::document::ShellFile::parse_with
+    #[cfg(feature = "std")]
+    pub fn open(path: &std::path::Path, options: &ParseOptions) -> Result<ShellFile, OpenError> {
+        let bytes = std::fs::read(path).map_err(|e| OpenError::Io(path.to_path_buf(), e))?;
+        ShellFile::parse_with(&bytes, Charset::ShiftJIS, options)
+            .map_err(|e| OpenError::Parse(path.to_path_buf(), e))
+    }
+
+    /// Returns the charset used to decode.
+    pub fn charset(&self) -> &Charset {
+        &self.charset
+    }
+
+    /// Returns the decoded text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns entries outside blocks, in order.
+    pub fn entries(&self) -> Vec<Located<KeyValue<'_>>> {
+        self.entries
+            .iter()
+            .map(|e| {
+                Located::new(
+                    e.line,
+                    KeyValue::new(&self.text[e.value.0.clone()], &self.text[e.value.1.clone()]),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns comment lines, including those in blocks, in order.
+    pub fn comments(&self) -> Vec<Located<&str>> {
+        self.comments
+            .iter()
+            .map(|c| Located::new(c.line, &self.text[c.value.clone()]))
+            .collect()
+    }
+
+    /// Returns blocks, or `None` when the file has no braces.
+    pub fn blocks(&self) -> Option<Vec<Block<'_>>> {
+        if self.blocks.is_empty() {
+            return None;
+        }
+        Some(self.blocks.iter().map(|b| b.block(&self.text)).collect())
+    }
+}
+
+/// Error of [`ShellFile::open`], with the path of the file.
+///
+/// [`ShellFile::open`]: crate::document::ShellFile::open
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum OpenError {
+    /// The file can not be read.
+    Io(std::path::PathBuf, std::io::Error),
+    /// The file can not be parsed.
+    Parse(std::path::PathBuf, ParseFailure),
+}
+
+#[cfg(feature = "std")]
+impl OpenError {
+    pub fn path(&self) -> &std::path::Path {
+        match self {
+            OpenError::Io(path, _) | OpenError::Parse(path, _) => path,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for OpenError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            OpenError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            OpenError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OpenError::Io(_, e) => Some(e),
+            OpenError::Parse(_, e) => Some(e),
+        }
+    }
+}
+
+/// Returns the span of `part`, a subslice of `text`.
+pub(super) fn span(text: &str, part: &str) -> Range<usize> {
+    let start = part.as_ptr() as usize - text.as_ptr() as usize;
+    start..start + part.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod shell_file {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_shift_jis_descript() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode(
+                "charset,Shift_JIS\r\n// シェル\r\nname,マスター\r\nsakura.balloon.offsetx,10\r\n",
+            );
+            let file = ShellFile::parse(&case, Charset::UTF8).unwrap();
+
+            assert_eq!(file.charset(), &Charset::ShiftJIS);
+            let entries = file.entries();
+            assert_eq!(entries.len(), 3);
+            assert_eq!(
+                entries[1],
+                Located::new(3, KeyValue::new("name", "マスター"))
+            );
+            assert_eq!(file.comments(), vec![Located::new(2, "// シェル")]);
+            assert!(file.blocks().is_none());
+        }
+
+        #[test]
+        fn success_when_utf_8_surfaces_with_blocks() {
+            let case = "charset,UTF-8\r\n\r\ndescript\r\n{\r\nversion,1\r\n}\r\n\r\nsurface0\r\n{\r\n// base\r\nelement0,base,surface0.png,0,0\r\n}\r\n";
+            let file = ShellFile::parse(case.as_bytes(), Charset::ShiftJIS).unwrap();
+
+            assert_eq!(file.charset(), &Charset::UTF8);
+            assert_eq!(
+                file.entries(),
+                vec![Located::new(1, KeyValue::new("charset", "UTF-8"))]
+            );
+            assert_eq!(file.comments()[0].line, 10);
+            let blocks = file.blocks().unwrap();
+            assert_eq!(blocks.len(), 2);
+            assert_eq!(blocks[1].header, "surface0");
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_fallback() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター\r\n");
+            let file = ShellFile::parse(&case, Charset::ShiftJIS).unwrap();
+            assert_eq!(file.charset(), &Charset::ShiftJIS);
+            assert_eq!(file.entries()[0].value.value, "マスター");
+        }
+
+        #[test]
+        fn success_when_options() {
+            let case = b"CHARSET,utf8\r\nname\tmaster // comment\r\n";
+            assert!(ShellFile::parse(case, Charset::UTF8).is_err());
+
+            let file =
+                ShellFile::parse_with(case, Charset::ASCII, &ParseOptions::COMPATIBLE).unwrap();
+            assert_eq!(file.charset(), &Charset::UTF8);
+            assert_eq!(file.entries()[1].value, KeyValue::new("name", "master"));
+        }
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn failed_when_broken() {
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode("name,マスター\r\n");
+            assert_eq!(
+                ShellFile::parse(&case, Charset::UTF8).unwrap_err(),
+                ParseFailure::Decode(Charset::UTF8)
+            );
+            assert!(ShellFile::parse(b"name\r\n", Charset::UTF8).is_err());
+            assert_eq!(
+                ShellFile::parse(b"surface0\r\n{\r\n", Charset::UTF8).unwrap_err(),
+                ParseFailure::Block(crate::block::BlockError::Unclosed)
+            );
+        }
+    }
+    #[cfg(feature = "std")]
+    mod open {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "encodings")]
+        fn success_when_shift_jis_file() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("descript.txt");
+            let (case, _, _) =
+                encoding_rs::SHIFT_JIS.encode("charset,Shift_JIS\r\nname,マスター\r\n");
+            std::fs::write(&path, &case).unwrap();
+
+            let file = ShellFile::open(&path, &ParseOptions::STRICT).unwrap();
+            assert_eq!(file.charset(), &Charset::ShiftJIS);
+            assert_eq!(file.entries()[1].value, KeyValue::new("name", "マスター"));
+
+            #[cfg(unix)]
+            {
+                let link = dir.path().join("link.txt");
+                std::os::unix::fs::symlink(&path, &link).unwrap();
+                assert_eq!(ShellFile::open(&link, &ParseOptions::STRICT).unwrap(), file);
+            }
+        }
+
+        #[test]
+        fn success_when_empty_file() {
+            let file = tempfile::NamedTempFile::new().unwrap();
+            let result = ShellFile::open(file.path(), &ParseOptions::COMPATIBLE).unwrap();
+            assert!(result.entries().is_empty());
+            assert!(result.blocks().is_none());
+        }
+
+        #[test]
+        fn failed_when_not_found_or_broken() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("descript.txt");
+            let e = ShellFile::open(&path, &ParseOptions::STRICT).unwrap_err();
+            assert!(matches!(&e, OpenError::Io(_, e) if e.kind() == std::io::ErrorKind::NotFound));
+            assert_eq!(e.path(), path);
+
+            std::fs::write(&path, b"charset,UTF-8\r\nname\r\n").unwrap();
+            let e = ShellFile::open(&path, &ParseOptions::STRICT).unwrap_err();
+            assert!(matches!(
+                e,
+                OpenError::Parse(_, ParseFailure::Syntax { .. })
+            ));
+            assert!(e.to_string().starts_with(&path.display().to_string()));
+        }
+    }
+}
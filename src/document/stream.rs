@@ -0,0 +1,249 @@
+//! `for_each_entry`, entries streamed from raw bytes one line at a time.
+
+use alloc::string::String;
+use core::ops::ControlFlow;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    block::BlockError,
+    charset::{detect_charset_with, Charset, DecodeError, DecodedLines},
+    error::{Located, ParseFailure},
+    kv::{parse_key_value_with, KeyValue},
+    line::is_comment,
+    options::{is_control_char, ControlCharPolicy, ParseOptions},
+};
+
+/// Key of the entry [`for_each_entry`] gives for the header of a block, with the header as the value.
+///
+/// [`for_each_entry`]: crate::document::for_each_entry
+pub const BLOCK_OPEN: &str = "{";
+
+/// Key of the entry [`for_each_entry`] gives for the `}` of a block, with an empty value.
+///
+/// [`for_each_entry`]: crate::document::for_each_entry
+pub const BLOCK_CLOSE: &str = "}";
+
+/// Decodes `bytes` one line at a time and calls `f` with each entry, in and outside blocks, in order,
+/// until `f` returns [`ControlFlow::Break`].
+///
+/// Memory taken is bounded by the longest line, not by the size of `bytes`.
+/// The charset is detected as [`parse_bytes_with`] does, and lines are decoded as [`DecodedLines`] does.
+///
+/// Blocks are given as entries too: the header as [`BLOCK_OPEN`] with the header as the value,
+/// located at the header line, and the `}` as [`BLOCK_CLOSE`] with an empty value.
+/// Errors are those of [`parse_bytes_with`], found as the lines are read, so `f` has been called
+/// with the entries before the error, and an unclosed block fails only at the end.
+///
+/// [`ControlFlow::Break`]: core::ops::ControlFlow::Break
+/// [`parse_bytes_with`]: crate::document::parse_bytes_with
+/// [`DecodedLines`]: crate::charset::DecodedLines
+/// [`BLOCK_OPEN`]: crate::document::BLOCK_OPEN
+/// [`BLOCK_CLOSE`]: crate::document::BLOCK_CLOSE
+pub fn for_each_entry(
+    bytes: &[u8],
+    fallback: Charset,
+    options: &ParseOptions,
+    mut f: impl FnMut(Located<KeyValue<'_>>) -> ControlFlow<()>,
+) -> Result<(), ParseFailure> {
+    let charset = detect_charset_with(bytes, options).unwrap_or(fallback);
+    let mut lines = DecodedLines::new(bytes, charset);
+    // Last line outside blocks, which is the header when `{` follows.
+    let mut pending: Option<Located<String>> = None;
+    let mut in_block = false;
+    let mut number = 0;
+
+    loop {
+        let offset = lines.offset();
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = control_chars(line?, offset, number + 1, options)?;
+        number += 1;
+        let text = line.trim();
+        if text.is_empty() || is_comment(text) {
+            continue;
+        }
+
+        if in_block {
+            if text == "}" {
+                in_block = false;
+                if f(Located::new(number, KeyValue::new(BLOCK_CLOSE, ""))).is_break() {
+                    return Ok(());
+                }
+            } else if text.starts_with('{') {
+                return Err(ParseFailure::Block(BlockError::NestedOpen));
+            } else if entry(&line, number, options, &mut f)?.is_break() {
+                return Ok(());
+            }
+            continue;
+        }
+
+        if text == "}" {
+            return Err(ParseFailure::Block(BlockError::UnexpectedClose));
+        }
+        let Some(head) = text.strip_suffix('{') else {
+            if let Some(previous) = pending.replace(Located::new(number, line)) {
+                if entry(&previous.value, previous.line, options, &mut f)?.is_break() {
+                    return Ok(());
+                }
+            }
+            continue;
+        };
+
+        in_block = true;
+        let head = head.trim_end();
+        let flow = if head.is_empty() {
+            let header = pending
+                .take()
+                .ok_or(ParseFailure::Block(BlockError::MissingHeader))?;
+            f(Located::new(
+                header.line,
+                KeyValue::new(BLOCK_OPEN, header.value.trim()),
+            ))
+        } else {
+            if let Some(previous) = pending.take() {
+                if entry(&previous.value, previous.line, options, &mut f)?.is_break() {
+                    return Ok(());
+                }
+            }
+            f(Located::new(number, KeyValue::new(BLOCK_OPEN, head)))
+        };
+        if flow.is_break() {
+            return Ok(());
+        }
+    }
+
+    if let Some(previous) = pending {
+        if entry(&previous.value, previous.line, options, &mut f)?.is_break() {
+            return Ok(());
+        }
+    }
+    if in_block {
+        return Err(ParseFailure::Block(BlockError::Unclosed));
+    }
+    Ok(())
+}
+
+/// Handles control characters of `line` by [`ParseOptions::control_chars`], as [`Charset::decode_with`] does.
+///
+/// [`ParseOptions::control_chars`]: crate::options::ParseOptions::control_chars
+/// [`Charset::decode_with`]: crate::charset::Charset::decode_with
+fn control_chars(
+    mut line: String,
+    offset: usize,
+    number: usize,
+    options: &ParseOptions,
+) -> Result<String, DecodeError> {
+    let Some(i) = line.find(is_control_char) else {
+        return Ok(line);
+    };
+
+    match options.control_chars {
+        ControlCharPolicy::Keep => {}
+        ControlCharPolicy::Strip => line.retain(|c| !is_control_char(c)),
+        ControlCharPolicy::Reject => {
+            return Err(DecodeError::ControlCharacter {
+                character: line[i..].chars().next().unwrap_or_default(),
+                byte_offset: offset + i,
+                line: number,
+            })
+        }
+    }
+    Ok(line)
+}
+
+fn entry(
+    line: &str,
+    number: usize,
+    options: &ParseOptions,
+    f: &mut impl FnMut(Located<KeyValue<'_>>) -> ControlFlow<()>,
+) -> Result<ControlFlow<()>, ParseFailure> {
+    let (_, kv) = all_consuming(|input| parse_key_value_with(input, options))(line)?;
+    Ok(f(Located::new(number, kv)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::{string::ToString, vec::Vec};
+
+    mod for_each_entry {
+        use super::*;
+
+        fn collect(case: &[u8]) -> Result<Vec<(usize, String, String)>, ParseFailure> {
+            let mut entries = Vec::new();
+            for_each_entry(case, Charset::UTF8, &ParseOptions::STRICT, |e| {
+                entries.push((e.line, e.value.key.to_string(), e.value.value.to_string()));
+                ControlFlow::Continue(())
+            })?;
+            Ok(entries)
+        }
+
+        #[test]
+        fn success_when_blocks_given_as_entries() {
+            let case = b"charset,UTF-8\r\nsurface0\r\n// body\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\nsurface1 {\r\n}\r\nname,x";
+            let entries = collect(case).unwrap();
+            let expected = [
+                (1, "charset", "UTF-8"),
+                (2, "{", "surface0"),
+                (5, "element0", "base,a.png,0,0"),
+                (6, "}", ""),
+                (7, "{", "surface1"),
+                (8, "}", ""),
+                (9, "name", "x"),
+            ];
+            let expected: Vec<_> = expected
+                .iter()
+                .map(|(l, k, v)| (*l, k.to_string(), v.to_string()))
+                .collect();
+            assert_eq!(entries, expected);
+        }
+
+        #[test]
+        fn stopped_when_break() {
+            let mut keys = Vec::new();
+            for_each_entry(
+                b"a,1\r\nb,2\r\nbroken\r\n",
+                Charset::UTF8,
+                &ParseOptions::STRICT,
+                |e| {
+                    keys.push(e.value.key.to_string());
+                    ControlFlow::Break(())
+                },
+            )
+            .unwrap();
+            assert_eq!(keys, vec!["a"]);
+        }
+
+        #[test]
+        fn failed_when_broken() {
+            assert_eq!(
+                collect(b"surface0\r\n{\r\nelement0,base,a.png,0,0\r\n"),
+                Err(ParseFailure::Block(BlockError::Unclosed))
+            );
+            assert_eq!(
+                collect(b"a,1\r\n}\r\n"),
+                Err(ParseFailure::Block(BlockError::UnexpectedClose))
+            );
+            assert!(collect(b"a,1\r\nbroken\r\n").is_err());
+
+            let options = ParseOptions {
+                control_chars: ControlCharPolicy::Reject,
+                ..ParseOptions::STRICT
+            };
+            let result = for_each_entry(b"a,1\r\nb,\x01\r\n", Charset::UTF8, &options, |_| {
+                ControlFlow::Continue(())
+            });
+            assert_eq!(
+                result,
+                Err(ParseFailure::from(DecodeError::ControlCharacter {
+                    character: '\u{1}',
+                    byte_offset: 7,
+                    line: 2,
+                }))
+            );
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! `Structure`, entries outside blocks split by their position around blocks.
+
+use alloc::vec::Vec;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    block::Block,
+    error::Located,
+    kv::{parse_key_value, KeyValue},
+    line::{is_comment, lines, Line},
+};
+
+/// Block of a [`Structure`], with the entries of its body.
+///
+/// [`Structure`]: crate::document::Structure
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParsedBlock<'a> {
+    /// Block, whose `close_line` is the last line of the body when it is not closed.
+    pub block: Block<'a>,
+    /// Entries of the body, in order.
+    pub entries: Vec<Located<KeyValue<'a>>>,
+    /// Whether the block is closed by `}`.
+    pub closed: bool,
+}
+
+/// Entries outside blocks, split into those before the first block, between blocks and after the last block.
+///
+/// Entries between blocks are flagged apart, as they often follow a block missing its `}` earlier.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Structure<'a> {
+    /// Entries before the first block, or all entries when there are no blocks.
+    pub preamble: Vec<Located<KeyValue<'a>>>,
+    pub blocks: Vec<ParsedBlock<'a>>,
+    /// Entries after a block and before another.
+    pub between: Vec<Located<KeyValue<'a>>>,
+    /// Entries after the last block.
+    pub trailing: Vec<Located<KeyValue<'a>>>,
+}
+
+impl<'a> Structure<'a> {
+    /// Splits `text` into blocks and entries outside them, recovering from broken braces.
+    ///
+    /// A header opening a block inside another closes the one before it, and a block left open
+    /// at the end takes the rest of `text`, both marked not [`ParsedBlock::closed`].
+    /// A `{` without a header opens a block with an empty header, and a `}` outside blocks is skipped.
+    /// Lines which are not entries are skipped, as comments and blank lines are.
+    ///
+    /// [`ParsedBlock::closed`]: crate::document::ParsedBlock::closed
+    pub fn analyze(text: &'a str) -> Structure<'a> {
+        let mut structure = Structure::default();
+        // Entries after the last block seen, between blocks if another follows.
+        let mut after = Vec::new();
+        // Last line outside blocks, which is the header when `{` follows.
+        let mut pending: Option<Line<'a>> = None;
+        let mut current: Option<Block<'a>> = None;
+
+        for line in lines(text) {
+            let trimmed = line.text.trim();
+            if trimmed.is_empty() || is_comment(trimmed) {
+                if let Some(block) = current.as_mut() {
+                    block.body.push(line);
+                }
+                continue;
+            }
+
+            if let Some(mut block) = current.take() {
+                if trimmed == "}" {
+                    block.close_line = line.number;
+                    structure.push_block(block, true);
+                    continue;
+                }
+                let Some(head) = trimmed.strip_suffix('{') else {
+                    block.body.push(line);
+                    current = Some(block);
+                    continue;
+                };
+
+                let head = head.trim_end();
+                let (header, header_line) = if head.is_empty() {
+                    take_header(&mut block)
+                } else {
+                    (head, line.number)
+                };
+                block.close_line = block.body.last().map_or(block.header_line, |l| l.number);
+                structure.push_block(block, false);
+                current = Some(open(header, header_line));
+                continue;
+            }
+
+            if trimmed == "}" {
+                continue;
+            }
+            if let Some(head) = trimmed.strip_suffix('{') {
+                let head = head.trim_end();
+                let (header, header_line) = match (head.is_empty(), pending.take()) {
+                    (false, pending) => {
+                        push_entry(&mut structure, &mut after, pending);
+                        (head, line.number)
+                    }
+                    (true, Some(pending)) => (pending.text.trim(), pending.number),
+                    (true, None) => ("", line.number),
+                };
+                structure.between.append(&mut after);
+                current = Some(open(header, header_line));
+                continue;
+            }
+
+            push_entry(&mut structure, &mut after, pending.replace(line));
+        }
+
+        push_entry(&mut structure, &mut after, pending);
+        if let Some(mut block) = current {
+            block.close_line = block.body.last().map_or(block.header_line, |l| l.number);
+            structure.push_block(block, false);
+        }
+        structure.trailing = after;
+        structure
+    }
+
+    fn push_block(&mut self, block: Block<'a>, closed: bool) {
+        let entries = block.body.iter().filter_map(entry).collect();
+        self.blocks.push(ParsedBlock {
+            block,
+            entries,
+            closed,
+        });
+    }
+}
+
+fn open(header: &str, header_line: usize) -> Block<'_> {
+    Block {
+        header,
+        header_line,
+        body: Vec::new(),
+        close_line: 0,
+    }
+}
+
+/// Takes the last line of the body of `block` as the header of the block opened by `{` after it,
+/// or an empty header at the line after the body.
+fn take_header<'a>(block: &mut Block<'a>) -> (&'a str, usize) {
+    let position = block.body.iter().rposition(|l| {
+        let text = l.text.trim();
+        !text.is_empty() && !is_comment(text)
+    });
+    match position {
+        Some(i) => {
+            let header = block.body[i];
+            block.body.truncate(i);
+            (header.text.trim(), header.number)
+        }
+        None => (
+            "",
+            block.body.last().map_or(block.header_line, |l| l.number) + 1,
+        ),
+    }
+}
+
+fn push_entry<'a>(
+    structure: &mut Structure<'a>,
+    after: &mut Vec<Located<KeyValue<'a>>>,
+    line: Option<Line<'a>>,
+) {
+    let Some(entry) = line.as_ref().and_then(entry) else {
+        return;
+    };
+    if structure.blocks.is_empty() {
+        structure.preamble.push(entry);
+    } else {
+        after.push(entry);
+    }
+}
+
+fn entry<'a>(line: &Line<'a>) -> Option<Located<KeyValue<'a>>> {
+    all_consuming(parse_key_value)(line.text)
+        .ok()
+        .map(|(_, kv)| Located::new(line.number, kv))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod analyze {
+        use super::*;
+
+        #[test]
+        fn success_when_preamble_only() {
+            let case = "charset,UTF-8\r\n// balloon\r\nname,master\r\n";
+            let structure = Structure::analyze(case);
+            assert_eq!(
+                structure.preamble,
+                vec![
+                    Located::new(1, KeyValue::new("charset", "UTF-8")),
+                    Located::new(3, KeyValue::new("name", "master")),
+                ]
+            );
+            assert!(structure.blocks.is_empty());
+            assert!(structure.between.is_empty());
+            assert!(structure.trailing.is_empty());
+        }
+
+        #[test]
+        fn detected_when_entries_between_blocks() {
+            let case = "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,surface0.png,0,0\r\n}\r\nsakura.name,Emily\r\nsurface1 {\r\n}\r\n";
+            let structure = Structure::analyze(case);
+            assert_eq!(
+                structure.preamble,
+                vec![Located::new(1, KeyValue::new("charset", "UTF-8"))]
+            );
+            assert_eq!(
+                structure.between,
+                vec![Located::new(6, KeyValue::new("sakura.name", "Emily"))]
+            );
+            assert!(structure.trailing.is_empty());
+
+            let headers: Vec<_> = structure.blocks.iter().map(|b| b.block.header).collect();
+            assert_eq!(headers, vec!["surface0", "surface1"]);
+            assert_eq!(
+                structure.blocks[0].entries,
+                vec![Located::new(
+                    4,
+                    KeyValue::new("element0", "base,surface0.png,0,0")
+                )]
+            );
+            assert!(structure.blocks.iter().all(|b| b.closed));
+        }
+
+        #[test]
+        fn success_when_trailing_entries() {
+            let case = "surface0\r\n{\r\n}\r\n\r\nsakura.name,Emily\r\nkero.name,Teddy\r\n";
+            let structure = Structure::analyze(case);
+            assert!(structure.preamble.is_empty());
+            assert!(structure.between.is_empty());
+            assert_eq!(
+                structure.trailing,
+                vec![
+                    Located::new(5, KeyValue::new("sakura.name", "Emily")),
+                    Located::new(6, KeyValue::new("kero.name", "Teddy")),
+                ]
+            );
+            assert_eq!(structure.blocks[0].block.close_line, 3);
+        }
+
+        #[test]
+        fn success_when_unclosed_blocks_recovered() {
+            let case = "surface0\r\n{\r\nelement0,base,surface0.png,0,0\r\n\r\nsurface1\r\n{\r\nelement0,base,surface1.png,0,0\r\n";
+            let structure = Structure::analyze(case);
+            assert_eq!(structure.blocks.len(), 2);
+
+            let first = &structure.blocks[0];
+            assert_eq!(first.block.header, "surface0");
+            assert_eq!(first.block.close_line, 4);
+            assert_eq!(first.entries.len(), 1);
+            assert!(!first.closed);
+
+            let second = &structure.blocks[1];
+            assert_eq!(second.block.header, "surface1");
+            assert_eq!(second.block.header_line, 5);
+            assert_eq!(second.block.close_line, 7);
+            assert!(!second.closed);
+            assert!(structure.trailing.is_empty());
+        }
+    }
+}
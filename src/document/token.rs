@@ -0,0 +1,206 @@
+//! Lossless tokenizer with byte spans, for editor tooling.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::line::{lines, LineKind};
+
+/// Kind of a [`Token`].
+///
+/// [`Token`]: crate::document::Token
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum TokenKind {
+    /// Key of an entry.
+    Key,
+    /// `,` between the key and the value.
+    Comma,
+    /// Value of an entry, up to the line ending.
+    Value,
+    /// Header of a block.
+    Header,
+    BraceOpen,
+    BraceClose,
+    /// Comment line from `//`.
+    Comment,
+    /// Spaces and tabs around other tokens.
+    Whitespace,
+    /// `\r\n`, `\n` or `\r`.
+    Newline,
+    /// Text not recognized by the parsers.
+    Unknown,
+}
+
+/// Token with its byte range in the input.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+/// Splits `input` into tokens.
+///
+/// Spans are contiguous and in order, so concatenating them reconstructs `input` exactly.
+/// Lines are recognized as [`Lines::classified`] does.
+///
+/// [`Lines::classified`]: crate::line::Lines::classified
+pub fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+
+    for (line, kind) in lines(input).classified() {
+        let start = line.offset;
+        let text = line.text;
+
+        match kind {
+            LineKind::Blank => push(&mut tokens, start, text.len(), TokenKind::Whitespace),
+            LineKind::Comment => push_trimmed(&mut tokens, start, text, TokenKind::Comment),
+            LineKind::BraceOpen => push_trimmed(&mut tokens, start, text, TokenKind::BraceOpen),
+            LineKind::BraceClose => push_trimmed(&mut tokens, start, text, TokenKind::BraceClose),
+            LineKind::BlockHeader => {
+                let trimmed = text.trim_end();
+                match trimmed.strip_suffix('{') {
+                    Some(head) => {
+                        push_trimmed(&mut tokens, start, head, TokenKind::Header);
+                        push(&mut tokens, start + head.len(), 1, TokenKind::BraceOpen);
+                        let rest = head.len() + 1;
+                        push(
+                            &mut tokens,
+                            start + rest,
+                            text.len() - rest,
+                            TokenKind::Whitespace,
+                        );
+                    }
+                    None => push_trimmed(&mut tokens, start, text, TokenKind::Header),
+                }
+            }
+            LineKind::KeyValue => {
+                // `classify` has accepted it as an entry, so the comma exists.
+                let comma = text.find(',').unwrap_or(text.len());
+                push_trimmed(&mut tokens, start, &text[..comma], TokenKind::Key);
+                push(&mut tokens, start + comma, 1, TokenKind::Comma);
+                push(
+                    &mut tokens,
+                    start + comma + 1,
+                    text.len() - comma - 1,
+                    TokenKind::Value,
+                );
+            }
+            LineKind::Unknown => push_trimmed(&mut tokens, start, text, TokenKind::Unknown),
+        }
+
+        push(
+            &mut tokens,
+            start + text.len(),
+            line.ending.as_str().len(),
+            TokenKind::Newline,
+        );
+    }
+
+    tokens
+}
+
+fn push(tokens: &mut Vec<Token>, start: usize, len: usize, kind: TokenKind) {
+    if len > 0 {
+        tokens.push(Token {
+            kind,
+            span: start..start + len,
+        });
+    }
+}
+
+/// Pushes `text` at `start` as `kind`, with surrounding whitespace as separate tokens.
+fn push_trimmed(tokens: &mut Vec<Token>, start: usize, text: &str, kind: TokenKind) {
+    let leading = text.len() - text.trim_start().len();
+    let core = text.trim().len();
+
+    push(tokens, start, leading, TokenKind::Whitespace);
+    push(tokens, start + leading, core, kind);
+    push(
+        tokens,
+        start + leading + core,
+        text.len() - leading - core,
+        TokenKind::Whitespace,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::XorShift;
+
+    fn reconstruct(input: &str, tokens: &[Token]) -> String {
+        let mut end = 0;
+        let mut output = String::new();
+        for token in tokens {
+            assert_eq!(token.span.start, end, "gap or overlap at {:?}", token);
+            assert!(token.span.start < token.span.end);
+            output.push_str(&input[token.span.clone()]);
+            end = token.span.end;
+        }
+        output
+    }
+
+    fn kinds<'a>(input: &'a str, tokens: &[Token]) -> Vec<(TokenKind, &'a str)> {
+        tokens
+            .iter()
+            .map(|t| (t.kind, &input[t.span.clone()]))
+            .collect()
+    }
+
+    mod tokenize {
+        use super::*;
+
+        #[test]
+        fn round_trip_property() {
+            const PIECES: &[&str] = &[
+                "a", "key", ",", "{", "}", "//", " ", "\t", "\r\n", "\n", "\r", "あ", "0",
+            ];
+            let mut rng = XorShift::new(0x2545_f491);
+
+            for _ in 0..500 {
+                let case = rng.concat(PIECES, 40);
+                let tokens = tokenize(&case);
+                assert_eq!(reconstruct(&case, &tokens), case);
+            }
+        }
+
+        #[test]
+        fn success_when_blocks_and_entries() {
+            let case = "charset,UTF-8\r\n// c\r\nsurface0 {\r\n  element0,base,a.png,0,0\r\n}\r\n";
+            let tokens = tokenize(case);
+            assert_eq!(reconstruct(case, &tokens), case);
+            assert_eq!(
+                kinds(case, &tokens),
+                vec![
+                    (TokenKind::Key, "charset"),
+                    (TokenKind::Comma, ","),
+                    (TokenKind::Value, "UTF-8"),
+                    (TokenKind::Newline, "\r\n"),
+                    (TokenKind::Comment, "// c"),
+                    (TokenKind::Newline, "\r\n"),
+                    (TokenKind::Header, "surface0"),
+                    (TokenKind::Whitespace, " "),
+                    (TokenKind::BraceOpen, "{"),
+                    (TokenKind::Newline, "\r\n"),
+                    (TokenKind::Whitespace, "  "),
+                    (TokenKind::Key, "element0"),
+                    (TokenKind::Comma, ","),
+                    (TokenKind::Value, "base,a.png,0,0"),
+                    (TokenKind::Newline, "\r\n"),
+                    (TokenKind::BraceClose, "}"),
+                    (TokenKind::Newline, "\r\n"),
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_no_trailing_newline() {
+            let case = "descript\n{\nversion,1\n}";
+            let tokens = tokenize(case);
+            assert_eq!(reconstruct(case, &tokens), case);
+            assert_eq!(tokens[0].kind, TokenKind::Header);
+            assert_eq!(tokens.last().unwrap().kind, TokenKind::BraceClose);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! # `dump`
+//!
+//! Text dumps of parsed files and shells with their diagnostics, for golden tests of tools.
+//!
+//! A dump is a list of sections, each a name followed by `:` and its items indented by two spaces.
+//! Items are in line order or sorted, as the collections they come from are, and written by `Display`
+//! or `Debug` without addresses, so that a dump is the same byte for byte on every run and platform.
+//! Paths are written as file names, so that dumps do not depend on where a shell is.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::charset::Charset;
+//! use shell_parser_common_rs::document::ShellFile;
+//! use shell_parser_common_rs::dump::dump_file;
+//!
+//! let file = ShellFile::parse(b"charset,UTF-8\r\nsakura.name,Emily \r\n", Charset::ShiftJIS).unwrap();
+//!
+//! assert_eq!(
+//!     dump_file(&file),
+//!     "charset: UTF8\nentries:\n  1: charset,\"UTF-8\"\n  2: sakura.name,\"Emily \"\nwarnings:\n  line 2: trailing whitespace\n"
+//! );
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{self, Write};
+use std::path::Path;
+
+use crate::{
+    diagnostic::parse_with_diagnostics,
+    document::ShellFile,
+    project::{validate, Inventory, ProjectFile, ShellProject},
+    surfaces::{build_surfaces, parse_blocks_with},
+};
+
+/// Returns the dump of `file`, with its charset, entries with quoted values, comments, blocks,
+/// and errors and warnings of [`parse_with_diagnostics`].
+///
+/// [`parse_with_diagnostics`]: crate::diagnostic::parse_with_diagnostics
+pub fn dump_file(file: &ShellFile) -> String {
+    let mut out = String::new();
+    writeln!(out, "charset: {:?}", file.charset()).unwrap();
+    section(
+        &mut out,
+        "entries",
+        file.entries()
+            .iter()
+            .map(|e| format!("{}: {},{:?}", e.line, e.value.key, e.value.value)),
+    );
+    section(
+        &mut out,
+        "comments",
+        file.comments()
+            .iter()
+            .map(|c| format!("{}: {}", c.line, c.value)),
+    );
+    section(
+        &mut out,
+        "blocks",
+        file.blocks().unwrap_or_default().iter().map(|b| {
+            format!(
+                "{}-{}: {} ({} lines)",
+                b.header_line,
+                b.close_line,
+                b.header,
+                b.body.len()
+            )
+        }),
+    );
+
+    let outcome = parse_with_diagnostics(file.text());
+    section(&mut out, "errors", outcome.errors.iter());
+    section(&mut out, "warnings", outcome.warnings.iter());
+    out
+}
+
+/// Returns the dump of `project`: each file with its charset, errors and warnings,
+/// then the warnings of [`validate`], the [`Inventory`], the aliases of each `surfaces*.txt`,
+/// and the surfaces built from all of them.
+///
+/// [`validate`]: crate::project::validate
+/// [`Inventory`]: crate::project::Inventory
+pub fn dump_project(project: &ShellProject) -> String {
+    let mut out = String::new();
+    for file in core::iter::once(project.descript()).chain(project.surfaces().iter()) {
+        dump_project_file(&mut out, file);
+    }
+
+    writeln!(out, "# shell").unwrap();
+    section(
+        &mut out,
+        "validation",
+        validate(project)
+            .iter()
+            .map(|(path, w)| format!("{}: {}", file_name(path), w)),
+    );
+
+    let inventory = Inventory::from(project);
+    writeln!(out, "surface count: {}", inventory.surface_count).unwrap();
+    section(
+        &mut out,
+        "surface ranges",
+        inventory
+            .surface_ranges
+            .iter()
+            .map(|r| format!("{}-{}", r.start(), r.end())),
+    );
+    section(
+        &mut out,
+        "animation counts",
+        inventory
+            .animation_counts
+            .iter()
+            .map(|(id, count)| format!("{}: {}", id, count)),
+    );
+    section(
+        &mut out,
+        "charsets",
+        inventory
+            .charsets
+            .iter()
+            .map(|(path, charset)| format!("{}: {:?}", file_name(path), charset)),
+    );
+    section(
+        &mut out,
+        "files",
+        inventory
+            .files
+            .iter()
+            .flat_map(|(kind, names)| names.iter().map(move |n| format!("{:?}: {}", kind, n))),
+    );
+
+    let mut aliases = Vec::new();
+    let mut blocks = Vec::new();
+    for file in project.surfaces() {
+        // Broken braces are among the errors of the file.
+        let Ok(parsed) = parse_blocks_with(file.text(), file.options()) else {
+            continue;
+        };
+        for (scope, table) in parsed.aliases.tables.iter() {
+            for (name, ids) in table.aliases.iter() {
+                let path = file_name(file.path());
+                aliases.push(format!(
+                    "{}:{}: {}.{},{}",
+                    path, ids.line, scope, name, ids.value
+                ));
+            }
+        }
+        blocks.extend(parsed.blocks);
+    }
+    section(&mut out, "aliases", aliases.iter());
+
+    let (surfaces, warnings) = build_surfaces(&blocks);
+    section(&mut out, "build warnings", warnings.iter());
+    section(
+        &mut out,
+        "surfaces",
+        surfaces.values().map(|s| format!("{:#?}", s)),
+    );
+    out
+}
+
+fn dump_project_file(out: &mut String, file: &ProjectFile) {
+    writeln!(out, "# {}", file_name(file.path())).unwrap();
+    writeln!(out, "charset: {:?}", file.charset()).unwrap();
+    section(out, "errors", file.outcome().errors.iter());
+    section(out, "warnings", file.outcome().warnings.iter());
+}
+
+/// Writes the section `name` of `items`, each line indented, or nothing when there are no items.
+fn section<T: fmt::Display>(out: &mut String, name: &str, items: impl Iterator<Item = T>) {
+    let mut items = items.peekable();
+    if items.peek().is_none() {
+        return;
+    }
+    writeln!(out, "{}:", name).unwrap();
+    for item in items {
+        for line in item.to_string().lines() {
+            writeln!(out, "  {}", line).unwrap();
+        }
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
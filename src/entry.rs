@@ -0,0 +1,441 @@
+//! # `entry`
+//!
+//! Conversions of single entries into the typed values of other modules,
+//! such as [`BalloonOffset`] and [`Element`], without knowing which module reads them.
+//!
+//! Each type converts by [`TryFrom<&KeyValue>`], and [`classify_entry`] tells which conversion
+//! an entry is for by its key without running it.
+//! [`EntryKind`] converts into [`TypedEntry`] as an [`EntryConverter`], the object-safe trait of registries.
+//!
+//! [`BalloonOffset`]: crate::descript::BalloonOffset
+//! [`Element`]: crate::surfaces::Element
+//! [`classify_entry`]: crate::entry::classify_entry
+//! [`EntryKind`]: crate::entry::EntryKind
+//! [`TypedEntry`]: crate::entry::TypedEntry
+//! [`EntryConverter`]: crate::entry::EntryConverter
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::entry::{classify_entry, EntryKind};
+//! use shell_parser_common_rs::kv::KeyValue;
+//! use shell_parser_common_rs::surfaces::Collision;
+//! use shell_parser_common_rs::value::Rect;
+//!
+//! let entry = KeyValue::new("collision0", "10,10,50,50,Head");
+//! assert_eq!(classify_entry(&entry), EntryKind::Collision);
+//!
+//! let collision = Collision::try_from(&entry).unwrap();
+//! assert_eq!(collision.rect, Rect::new(10, 10, 50, 50));
+//! assert_eq!(collision.name, "Head");
+//! ```
+
+use alloc::{format, string::ToString};
+use core::fmt;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    charset::{parse_charset_directive, CharsetDirective, BOM},
+    cursor::{is_cursor_segment, CursorBinding},
+    descript::BalloonOffset,
+    error::ParseFailure,
+    keys::{classify_key, KnownKey},
+    kv::{segment_index, KeyValue},
+    scope::{split_scope, Scope},
+    surfaces::{parse_alias_entry, parse_collision, parse_element, AliasEntry, Collision, Element},
+    tooltip::Tooltip,
+};
+
+/// Kind of an entry, by the typed value it converts into.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum EntryKind {
+    /// `<scope>.balloon.offsetx`, `offsety` or `alignment`, into [`BalloonOffset`].
+    ///
+    /// [`BalloonOffset`]: crate::descript::BalloonOffset
+    BalloonOffset,
+    /// `cursor.mouseupN` or `mousedownN`, scoped or not, into [`CursorBinding`].
+    ///
+    /// [`CursorBinding`]: crate::cursor::CursorBinding
+    CursorBinding,
+    /// `tooltip.<collision>`, scoped or not, into [`Tooltip`].
+    ///
+    /// [`Tooltip`]: crate::tooltip::Tooltip
+    Tooltip,
+    /// `name,[targets]` of an alias block, into [`AliasEntry`].
+    ///
+    /// [`AliasEntry`]: crate::surfaces::AliasEntry
+    Alias,
+    /// `charset`, into [`CharsetDirective`].
+    ///
+    /// [`CharsetDirective`]: crate::charset::CharsetDirective
+    Charset,
+    /// `collisionN`, into [`Collision`].
+    ///
+    /// [`Collision`]: crate::surfaces::Collision
+    Collision,
+    /// `elementN`, into [`Element`].
+    ///
+    /// [`Element`]: crate::surfaces::Element
+    Element,
+    /// None of the above.
+    Other,
+}
+
+/// Returns the kind of `entry` by its key, and by the brackets of the value for aliases, without parsing the value.
+///
+/// An entry of a kind other than [`EntryKind::Other`] converts into the value of the kind
+/// unless the value is broken, and an entry of [`EntryKind::Other`] into none.
+///
+/// [`EntryKind::Other`]: crate::entry::EntryKind::Other
+pub fn classify_entry(entry: &KeyValue<'_>) -> EntryKind {
+    let key = entry.key;
+    match classify_key(key) {
+        KnownKey::Charset => return EntryKind::Charset,
+        KnownKey::BalloonOffsetX(_)
+        | KnownKey::BalloonOffsetY(_)
+        | KnownKey::BalloonAlignment(_) => return EntryKind::BalloonOffset,
+        KnownKey::Cursor(..) => return EntryKind::CursorBinding,
+        _ => {}
+    }
+
+    if is_charset_key(key) {
+        EntryKind::Charset
+    } else if segment_index(key, "collision").is_some() {
+        EntryKind::Collision
+    } else if segment_index(key, "element").is_some() {
+        EntryKind::Element
+    } else if matches!(entry.key_segments()[..], ["cursor", segment] if is_cursor_segment(segment))
+    {
+        EntryKind::CursorBinding
+    } else if tooltip(entry).is_some() {
+        EntryKind::Tooltip
+    } else if !key.contains('.') && entry.value.starts_with('[') && entry.value.ends_with(']') {
+        EntryKind::Alias
+    } else {
+        EntryKind::Other
+    }
+}
+
+/// Error of conversions of [`TryFrom<&KeyValue>`] and [`EntryConverter::convert`].
+///
+/// [`EntryConverter::convert`]: crate::entry::EntryConverter::convert
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EntryError {
+    /// Entry of another kind.
+    Kind {
+        expected: EntryKind,
+        found: EntryKind,
+    },
+    /// Entry of the kind with a broken value.
+    Value(ParseFailure),
+}
+
+impl fmt::Display for EntryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryError::Kind { expected, found } => {
+                write!(f, "entry of {:?}, not of {:?}", found, expected)
+            }
+            EntryError::Value(e) => write!(f, "broken value: {}", e),
+        }
+    }
+}
+
+impl core::error::Error for EntryError {}
+
+impl From<nom::Err<crate::ShellParseError<'_>>> for EntryError {
+    fn from(value: nom::Err<crate::ShellParseError<'_>>) -> Self {
+        EntryError::Value(value.into())
+    }
+}
+
+/// Typed value converted from one entry, with the scope of its key.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum TypedEntry {
+    /// Offset with the one field of the entry.
+    BalloonOffset(Scope, BalloonOffset),
+    /// Cursor, of `descript.txt` when scoped or of a surface block when not.
+    CursorBinding(Option<Scope>, CursorBinding),
+    /// Tooltip, of `descript.txt` when scoped or of a surface block when not.
+    Tooltip(Option<Scope>, Tooltip),
+    Alias(AliasEntry),
+    Charset(CharsetDirective),
+    Collision(Collision),
+    Element(Element),
+}
+
+/// Conversion of entries of one kind, object safe so that a registry holds `Box<dyn EntryConverter>`.
+///
+/// [`EntryKind`] is the converter of its own kind.
+///
+/// [`EntryKind`]: crate::entry::EntryKind
+pub trait EntryConverter {
+    /// Returns the kind of entries converted.
+    fn kind(&self) -> EntryKind;
+
+    /// Converts `entry`, failing with [`EntryError::Kind`] when it is not of [`EntryConverter::kind`].
+    ///
+    /// [`EntryError::Kind`]: crate::entry::EntryError::Kind
+    /// [`EntryConverter::kind`]: crate::entry::EntryConverter::kind
+    fn convert(&self, entry: &KeyValue<'_>) -> Result<TypedEntry, EntryError>;
+}
+
+impl EntryConverter for EntryKind {
+    fn kind(&self) -> EntryKind {
+        *self
+    }
+
+    fn convert(&self, entry: &KeyValue<'_>) -> Result<TypedEntry, EntryError> {
+        expect(entry, *self)?;
+        let segments = entry.key_segments();
+        let scope = split_scope(&segments).map(|(scope, _)| scope);
+        Ok(match self {
+            EntryKind::BalloonOffset => {
+                let scope = scope.expect("offsets are scoped");
+                TypedEntry::BalloonOffset(scope, BalloonOffset::try_from(entry)?)
+            }
+            EntryKind::CursorBinding => {
+                TypedEntry::CursorBinding(scope, CursorBinding::try_from(entry)?)
+            }
+            EntryKind::Tooltip => TypedEntry::Tooltip(scope, Tooltip::try_from(entry)?),
+            EntryKind::Alias => TypedEntry::Alias(AliasEntry::try_from(entry)?),
+            EntryKind::Charset => TypedEntry::Charset(CharsetDirective::try_from(entry)?),
+            EntryKind::Collision => TypedEntry::Collision(Collision::try_from(entry)?),
+            EntryKind::Element => TypedEntry::Element(Element::try_from(entry)?),
+            EntryKind::Other => unreachable!("no entry is expected as other"),
+        })
+    }
+}
+
+/// Checks that `entry` is of `expected`, which is not [`EntryKind::Other`].
+///
+/// [`EntryKind::Other`]: crate::entry::EntryKind::Other
+fn expect(entry: &KeyValue<'_>, expected: EntryKind) -> Result<(), EntryError> {
+    let found = classify_entry(entry);
+    if found == expected && found != EntryKind::Other {
+        Ok(())
+    } else {
+        Err(EntryError::Kind { expected, found })
+    }
+}
+
+fn is_charset_key(key: &str) -> bool {
+    key.trim_start_matches(BOM).trim_start_matches([' ', '\t']) == "charset"
+}
+
+/// Returns segments of the key without the scope, if any.
+fn unscoped<'a, 'b>(segments: &'b [&'a str]) -> &'b [&'a str] {
+    split_scope(segments).map_or(segments, |(_, rest)| rest)
+}
+
+fn tooltip(entry: &KeyValue<'_>) -> Option<Tooltip> {
+    let segments = entry.key_segments();
+    let ["tooltip", rest @ ..] = unscoped(&segments) else {
+        return None;
+    };
+    Tooltip::from_segments(rest, entry.value)
+}
+
+impl TryFrom<&KeyValue<'_>> for BalloonOffset {
+    type Error = EntryError;
+
+    /// Converts `<scope>.balloon.*` into an offset of the one field, dropping the scope.
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::BalloonOffset)?;
+        let segments = entry.key_segments();
+        let (_, rest) = split_scope(&segments).expect("offsets are scoped");
+        let mut offset = BalloonOffset::default();
+        offset.apply(rest, entry.value)?;
+        Ok(offset)
+    }
+}
+
+impl TryFrom<&KeyValue<'_>> for CursorBinding {
+    type Error = EntryError;
+
+    /// Converts `cursor.mouseupN` and the others, dropping the scope if any.
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::CursorBinding)?;
+        let segments = entry.key_segments();
+        let ["cursor", segment] = unscoped(&segments) else {
+            unreachable!("cursor keys have two segments");
+        };
+        Ok(CursorBinding::parse(segment, entry.value).expect("cursor keys are classified")?)
+    }
+}
+
+impl TryFrom<&KeyValue<'_>> for Tooltip {
+    type Error = EntryError;
+
+    /// Converts `tooltip.<collision>`, dropping the scope if any.
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::Tooltip)?;
+        Ok(tooltip(entry).expect("tooltip keys are classified"))
+    }
+}
+
+impl TryFrom<&KeyValue<'_>> for AliasEntry {
+    type Error = EntryError;
+
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::Alias)?;
+        let line = format!("{},{}", entry.key, entry.value);
+        let (_, (name, targets)) = all_consuming(parse_alias_entry)(&line)?;
+        Ok(AliasEntry {
+            name: name.to_string(),
+            targets,
+        })
+    }
+}
+
+impl TryFrom<&KeyValue<'_>> for CharsetDirective {
+    type Error = EntryError;
+
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::Charset)?;
+        let line = format!("{},{}", entry.key, entry.value);
+        let (_, value) = all_consuming(parse_charset_directive)(&line)?;
+        Ok(value)
+    }
+}
+
+impl TryFrom<&KeyValue<'_>> for Collision {
+    type Error = EntryError;
+
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::Collision)?;
+        let line = format!("{},{}", entry.key, entry.value);
+        let (_, value) = all_consuming(parse_collision)(&line)?;
+        Ok(value)
+    }
+}
+
+impl TryFrom<&KeyValue<'_>> for Element {
+    type Error = EntryError;
+
+    fn try_from(entry: &KeyValue<'_>) -> Result<Self, Self::Error> {
+        expect(entry, EntryKind::Element)?;
+        let line = format!("{},{}", entry.key, entry.value);
+        let (_, value) = all_consuming(parse_element)(&line)?;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        charset::Charset,
+        cursor::CursorKind,
+        seriko::DrawMethod,
+        value::{Coordinate, IdList, Rect},
+    };
+
+    mod try_from {
+        use super::*;
+
+        #[test]
+        fn success_when_representative_entries() {
+            assert_eq!(
+                BalloonOffset::try_from(&KeyValue::new("sakura.balloon.offsetx", "-10")).unwrap(),
+                BalloonOffset {
+                    x: Some(-10),
+                    ..BalloonOffset::default()
+                }
+            );
+            assert_eq!(
+                CursorBinding::try_from(&KeyValue::new("cursor.mousedown1", "Head,grab.cur"))
+                    .unwrap(),
+                CursorBinding {
+                    kind: CursorKind::MouseDown,
+                    index: 1,
+                    collision: "Head".to_string(),
+                    filename: "grab.cur".to_string(),
+                }
+            );
+            assert_eq!(
+                Tooltip::try_from(&KeyValue::new("kero.tooltip.Head", "head")).unwrap(),
+                Tooltip {
+                    collision: "Head".to_string(),
+                    text: "head".to_string(),
+                }
+            );
+            assert_eq!(
+                AliasEntry::try_from(&KeyValue::new("smile", "[1,0]")).unwrap(),
+                AliasEntry {
+                    name: "smile".to_string(),
+                    targets: IdList(vec![1, 0]),
+                }
+            );
+            assert_eq!(
+                CharsetDirective::try_from(&KeyValue::new("\u{feff}charset", "UTF-8")).unwrap(),
+                CharsetDirective {
+                    charset: Charset::UTF8,
+                    bom: true,
+                    indented: false,
+                }
+            );
+            assert_eq!(
+                Collision::try_from(&KeyValue::new("collision0", "10,10,50,50,Head")).unwrap(),
+                Collision {
+                    id: 0,
+                    rect: Rect::new(10, 10, 50, 50),
+                    name: "Head".to_string(),
+                }
+            );
+            let element =
+                Element::try_from(&KeyValue::new("element2", "add,glow.png,5,5")).unwrap();
+            assert_eq!(element.id, 2);
+            assert_eq!(element.method, DrawMethod::Add);
+            assert_eq!(element.offset, Coordinate::new(5, 5));
+        }
+
+        #[test]
+        fn failed_when_other_kind_or_broken() {
+            let entry = KeyValue::new("name", "master");
+            assert_eq!(
+                Collision::try_from(&entry),
+                Err(EntryError::Kind {
+                    expected: EntryKind::Collision,
+                    found: EntryKind::Other,
+                })
+            );
+            assert!(matches!(
+                Element::try_from(&KeyValue::new("element0", "base")),
+                Err(EntryError::Value(_))
+            ));
+            assert!(matches!(
+                EntryKind::Element.convert(&KeyValue::new("collision0", "0,0,1,1,Head")),
+                Err(EntryError::Kind { .. })
+            ));
+        }
+    }
+
+    mod entry_converter {
+        use super::*;
+
+        use alloc::{boxed::Box, vec, vec::Vec};
+
+        #[test]
+        fn success_when_registry_of_kinds() {
+            let registry: Vec<Box<dyn EntryConverter>> = vec![
+                Box::new(EntryKind::Tooltip),
+                Box::new(EntryKind::BalloonOffset),
+            ];
+            let entry = KeyValue::new("char2.balloon.alignment", "left");
+            let converted: Vec<_> = registry
+                .iter()
+                .filter(|c| c.kind() == classify_entry(&entry))
+                .map(|c| c.convert(&entry).unwrap())
+                .collect();
+            assert!(matches!(
+                converted[..],
+                [TypedEntry::BalloonOffset(Scope::Char(2), _)]
+            ));
+        }
+    }
+}
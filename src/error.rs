@@ -0,0 +1,422 @@
+//! # `error`
+//!
+//! Owned error types carrying positions, for reporting failures after parsing.
+//!
+//! ## Example
+//!
+//! ```
+//! use nom::combinator::all_consuming;
+//!
+//! use shell_parser_common_rs::error::{Located, ParseFailure};
+//! use shell_parser_common_rs::value::parse_rect;
+//!
+//! let err = all_consuming(parse_rect)("0,0,x,10").unwrap_err();
+//! let located = Located::new(3, ParseFailure::from(err));
+//!
+//! assert_eq!(located.line, 3);
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use nom::error::{
+    ContextError, ErrorKind, FromExternalError, ParseError, VerboseError, VerboseErrorKind,
+};
+
+#[cfg(feature = "block")]
+use crate::block::BlockError;
+use crate::{
+    charset::{Bom, Charset, DecodeError},
+    text::excerpt,
+};
+#[cfg(feature = "kv")]
+use crate::{diagnostic::Warning, options::LimitExceeded, path::PathError};
+
+/// All-purpose Error type, the errors of a [`VerboseError`] with the tags a [`tag_table`] parser expected.
+///
+/// Convert it into a [`VerboseError`] for the helpers of nom, such as `convert_error`.
+///
+/// [`VerboseError`]: nom::error::VerboseError
+/// [`tag_table`]: crate::value::tag_table
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShellParseError<'a> {
+    /// Errors from the innermost, as in [`VerboseError`].
+    ///
+    /// [`VerboseError`]: nom::error::VerboseError
+    pub errors: Vec<(&'a str, VerboseErrorKind)>,
+    /// Tags which would have been accepted, when a [`tag_table`] parser failed.
+    ///
+    /// [`tag_table`]: crate::value::tag_table
+    pub expected: Vec<&'static str>,
+}
+
+impl<'a> ShellParseError<'a> {
+    /// Makes the error of a [`tag_table`] parser failing at `input`, expecting `tags`.
+    ///
+    /// [`tag_table`]: crate::value::tag_table
+    pub(crate) fn expecting(input: &'a str, tags: Vec<&'static str>) -> ShellParseError<'a> {
+        ShellParseError {
+            expected: tags,
+            ..ShellParseError::from_error_kind(input, ErrorKind::Tag)
+        }
+    }
+}
+
+impl<'a> ParseError<&'a str> for ShellParseError<'a> {
+    fn from_error_kind(input: &'a str, kind: ErrorKind) -> Self {
+        ShellParseError {
+            errors: alloc::vec![(input, VerboseErrorKind::Nom(kind))],
+            expected: Vec::new(),
+        }
+    }
+
+    fn append(input: &'a str, kind: ErrorKind, mut other: Self) -> Self {
+        other.errors.push((input, VerboseErrorKind::Nom(kind)));
+        other
+    }
+
+    fn from_char(input: &'a str, c: char) -> Self {
+        ShellParseError {
+            errors: alloc::vec![(input, VerboseErrorKind::Char(c))],
+            expected: Vec::new(),
+        }
+    }
+}
+
+impl<'a> ContextError<&'a str> for ShellParseError<'a> {
+    fn add_context(input: &'a str, ctx: &'static str, mut other: Self) -> Self {
+        other.errors.push((input, VerboseErrorKind::Context(ctx)));
+        other
+    }
+}
+
+impl<'a, E> FromExternalError<&'a str, E> for ShellParseError<'a> {
+    fn from_external_error(input: &'a str, kind: ErrorKind, _e: E) -> Self {
+        ShellParseError::from_error_kind(input, kind)
+    }
+}
+
+impl<'a> From<ShellParseError<'a>> for VerboseError<&'a str> {
+    fn from(value: ShellParseError<'a>) -> Self {
+        VerboseError {
+            errors: value.errors,
+        }
+    }
+}
+
+impl fmt::Display for ShellParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Parse error:")?;
+        for (input, kind) in self.errors.iter() {
+            match kind {
+                VerboseErrorKind::Nom(e) => writeln!(f, "{:?} at: {}", e, input)?,
+                VerboseErrorKind::Char(c) => writeln!(f, "expected '{}' at: {}", c, input)?,
+                VerboseErrorKind::Context(s) => writeln!(f, "in section '{}', at: {}", s, input)?,
+            }
+        }
+        if !self.expected.is_empty() {
+            writeln!(f, "expected one of: {}", self.expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for ShellParseError<'_> {}
+
+/// Number of expected tags shown by `Display` of [`ParseFailure`].
+///
+/// [`ParseFailure`]: crate::error::ParseFailure
+const MAX_EXPECTED_SHOWN: usize = 12;
+
+/// Number of characters of the fragment shown by `Display` of [`ParseFailure`].
+///
+/// [`ParseFailure`]: crate::error::ParseFailure
+const MAX_FRAGMENT_SHOWN: usize = 40;
+
+/// Value with the line number it came from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Located<T> {
+    /// 1-based line number.
+    pub line: usize,
+    pub value: T,
+}
+
+impl<T> Located<T> {
+    pub fn new(line: usize, value: T) -> Located<T> {
+        Located { line, value }
+    }
+
+    /// Maps the value, keeping the line number.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Located<U> {
+        Located {
+            line: self.line,
+            value: f(self.value),
+        }
+    }
+}
+
+/// `ParseFailure` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "snake_case")
+)]
+pub enum ParseFailure {
+    /// The text does not match the syntax.
+    Syntax {
+        /// Context labels, from the innermost.
+        contexts: Vec<&'static str>,
+        /// Rest of the line where the innermost error occurred.
+        fragment: String,
+        /// Tags which would have been accepted, when a [`tag_table`] parser failed.
+        ///
+        /// [`tag_table`]: crate::value::tag_table
+        expected: Vec<&'static str>,
+    },
+    /// Braces of blocks are broken.
+    #[cfg(feature = "block")]
+    Block(BlockError),
+    /// The line is well-formed, but the key is not known.
+    UnknownKey(String),
+    /// The bytes can not be decoded with the charset.
+    Decode(Charset),
+    /// The bytes start with the byte order mark of another encoding than the charset.
+    BomMismatch { declared: Charset, bom: Bom },
+    /// The decoded text has a control character rejected by [`ControlCharPolicy::Reject`].
+    ///
+    /// [`ControlCharPolicy::Reject`]: crate::options::ControlCharPolicy::Reject
+    ControlCharacter {
+        character: char,
+        /// Offset of the character in the decoded text.
+        byte_offset: usize,
+        /// 1-based line number of the character.
+        line: usize,
+    },
+    /// Warning promoted to an error.
+    #[cfg(feature = "kv")]
+    Warning(Warning),
+    /// The path is not a relative path inside the directory.
+    #[cfg(feature = "kv")]
+    Path(PathError),
+    /// One of [`ParseOptions::limits`] is exceeded.
+    ///
+    /// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+    #[cfg(feature = "kv")]
+    LimitExceeded(LimitExceeded),
+}
+
+impl ParseFailure {
+    /// Returns context labels when the failure is a syntax error.
+    pub fn contexts(&self) -> &[&'static str] {
+        match self {
+            ParseFailure::Syntax { contexts, .. } => contexts,
+            _ => &[],
+        }
+    }
+
+    /// Returns tags which would have been accepted when the failure is a syntax error of a [`tag_table`] parser.
+    ///
+    /// [`tag_table`]: crate::value::tag_table
+    pub fn expected(&self) -> &[&'static str] {
+        match self {
+            ParseFailure::Syntax { expected, .. } => expected,
+            _ => &[],
+        }
+    }
+}
+
+impl From<nom::Err<ShellParseError<'_>>> for ParseFailure {
+    fn from(value: nom::Err<ShellParseError<'_>>) -> Self {
+        match value {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                let contexts = e
+                    .errors
+                    .iter()
+                    .filter_map(|(_, kind)| match kind {
+                        VerboseErrorKind::Context(c) => Some(*c),
+                        _ => None,
+                    })
+                    .collect();
+                let fragment = e
+                    .errors
+                    .first()
+                    .map(|(input, _)| {
+                        input
+                            .split(['\r', '\n'])
+                            .next()
+                            .unwrap_or_default()
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                ParseFailure::Syntax {
+                    contexts,
+                    fragment,
+                    expected: e.expected,
+                }
+            }
+            nom::Err::Incomplete(_) => ParseFailure::Syntax {
+                contexts: Vec::new(),
+                fragment: String::new(),
+                expected: Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(feature = "block")]
+impl From<BlockError> for ParseFailure {
+    fn from(value: BlockError) -> Self {
+        ParseFailure::Block(value)
+    }
+}
+
+impl From<DecodeError> for ParseFailure {
+    fn from(value: DecodeError) -> Self {
+        match value {
+            DecodeError::Malformed { charset, .. }
+            | DecodeError::EncodingSupportDisabled(charset) => ParseFailure::Decode(charset),
+            DecodeError::BomMismatch { declared, bom } => {
+                ParseFailure::BomMismatch { declared, bom }
+            }
+            DecodeError::ControlCharacter {
+                character,
+                byte_offset,
+                line,
+            } => ParseFailure::ControlCharacter {
+                character,
+                byte_offset,
+                line,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "kv")]
+impl From<PathError> for ParseFailure {
+    fn from(value: PathError) -> Self {
+        ParseFailure::Path(value)
+    }
+}
+
+impl fmt::Display for ParseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseFailure::Syntax {
+                contexts,
+                fragment,
+                expected,
+            } => {
+                write!(f, "syntax error")?;
+                if let Some(context) = contexts.first() {
+                    write!(f, " in {}", context)?;
+                }
+                let shown = excerpt(fragment, MAX_FRAGMENT_SHOWN);
+                write!(f, " at {:?}", shown)?;
+                if shown.len() < fragment.len() {
+                    write!(f, "...")?;
+                }
+                if !expected.is_empty() {
+                    write!(f, ", expected one of: ")?;
+                    for (i, tag) in expected.iter().take(MAX_EXPECTED_SHOWN).enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", tag)?;
+                    }
+                    if expected.len() > MAX_EXPECTED_SHOWN {
+                        write!(f, ", ...")?;
+                    }
+                }
+                Ok(())
+            }
+            #[cfg(feature = "block")]
+            ParseFailure::Block(e) => write!(f, "{}", e),
+            ParseFailure::UnknownKey(key) => write!(f, "unknown key {:?}", key),
+            ParseFailure::Decode(charset) => write!(f, "can not decode as {:?}", charset),
+            ParseFailure::BomMismatch { declared, bom } => {
+                write!(f, "byte order mark of {} in a file of {:?}", bom, declared)
+            }
+            ParseFailure::ControlCharacter {
+                character,
+                byte_offset,
+                line,
+            } => write!(
+                f,
+                "control character U+{:04X} at line {} (byte {})",
+                *character as u32, line, byte_offset
+            ),
+            #[cfg(feature = "kv")]
+            ParseFailure::Warning(warning) => write!(f, "{}", warning),
+            #[cfg(feature = "kv")]
+            ParseFailure::Path(e) => write!(f, "invalid path: {}", e),
+            #[cfg(feature = "kv")]
+            ParseFailure::LimitExceeded(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for ParseFailure {}
+
+impl<T: fmt::Display> fmt::Display for Located<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nom::{bytes::complete::tag, combinator::all_consuming, error::context, IResult};
+
+    fn labeled(input: &str) -> IResult<&str, &str, ShellParseError<'_>> {
+        context("outer", context("inner", tag("abc")))(input)
+    }
+
+    mod parse_failure {
+        use super::*;
+
+        #[test]
+        fn from_nom_error_keeps_contexts_and_fragment() {
+            let err = all_consuming(labeled)("abd\r\nnext").unwrap_err();
+            let result = ParseFailure::from(err);
+            assert_eq!(result.contexts(), &["inner", "outer"]);
+            assert_eq!(
+                result,
+                ParseFailure::Syntax {
+                    contexts: vec!["inner", "outer"],
+                    fragment: "abd".to_string(),
+                    expected: vec![],
+                }
+            );
+        }
+
+        #[test]
+        fn display_with_line() {
+            let err = all_consuming(labeled)("abd").unwrap_err();
+            let result = Located::new(4, ParseFailure::from(err));
+            assert_eq!(
+                result.to_string(),
+                "line 4: syntax error in inner at \"abd\""
+            );
+        }
+
+        #[test]
+        fn display_with_long_fragment_excerpted() {
+            let failure = ParseFailure::Syntax {
+                contexts: vec!["inner"],
+                fragment: "あ".repeat(50),
+                expected: vec![],
+            };
+            assert_eq!(
+                failure.to_string(),
+                format!("syntax error in inner at {:?}...", "あ".repeat(40))
+            );
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! # `fingerprint`
+//!
+//! Hashes of the meaning of parsed files and surfaces, for caches and update checkers.
+//! Comments, blank lines, line endings, whitespace around `,` and the order of entries outside blocks
+//! do not change them.
+//!
+//! Hashes are FNV-1a of a canonical text, written by [`canonical_text`] for documents:
+//!
+//! - entries outside blocks first, sorted by key, as `key,value`;
+//! - then headers, braces, entries inside blocks and lines not recognized, in order,
+//!   with a header and `{` on the same line written on two lines;
+//! - keys and headers trimmed and with ASCII letters lowercased, and fields of values trimmed;
+//! - each line followed by `\n`, and comments and blank lines dropped.
+//!
+//! Values are stable across runs and platforms, and may change between versions of the crate.
+//!
+//! [`canonical_text`]: crate::fingerprint::canonical_text
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::document::Document;
+//! use shell_parser_common_rs::fingerprint::semantic_hash;
+//!
+//! let a = Document::parse("name,master\r\nsakura.name,Sakura\r\n");
+//! let b = Document::parse("// shell\nSakura.Name , Sakura\nname,master\n");
+//!
+//! assert_eq!(semantic_hash(&a), semantic_hash(&b));
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::{fmt::Write, hash::Hasher};
+
+use crate::{
+    document::{Document, NodeKind},
+    surfaces::Surface,
+};
+
+#[cfg(feature = "std")]
+use crate::project::ShellProject;
+
+/// 64-bit FNV-1a hasher, giving the same value for the same bytes on every run and platform.
+///
+/// Unlike [`Hash`] of integers, which depends on the platform, only bytes written are hashed.
+///
+/// [`Hash`]: core::hash::Hash
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> StableHasher {
+        StableHasher {
+            state: StableHasher::OFFSET_BASIS,
+        }
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter() {
+            self.state ^= u64::from(*byte);
+            self.state = self.state.wrapping_mul(StableHasher::PRIME);
+        }
+    }
+}
+
+/// Returns the hash of [`canonical_text`] of `doc`.
+///
+/// [`canonical_text`]: crate::fingerprint::canonical_text
+pub fn semantic_hash(doc: &Document) -> u64 {
+    hash(&canonical_text(doc))
+}
+
+/// Writes `doc` into the canonical text described in the [module documentation].
+///
+/// [module documentation]: crate::fingerprint
+pub fn canonical_text(doc: &Document) -> String {
+    let mut entries = Vec::new();
+    let mut rest = String::new();
+    let mut in_block = false;
+    for node in doc.nodes() {
+        match node.kind {
+            NodeKind::Entry => {
+                let Some(kv) = node.key_value() else {
+                    continue;
+                };
+                let line = entry_line(kv.key, kv.value);
+                if in_block {
+                    rest.push_str(&line);
+                } else {
+                    entries.push((kv.key.trim().to_ascii_lowercase(), line));
+                }
+            }
+            NodeKind::BlockHeader => {
+                let text = node.text.trim();
+                let header = text.trim_end_matches('{').trim_end();
+                let _ = writeln!(rest, "{}", header.to_ascii_lowercase());
+                if header.len() != text.len() {
+                    rest.push_str("{\n");
+                    in_block = true;
+                }
+            }
+            NodeKind::BlockOpen => {
+                rest.push_str("{\n");
+                in_block = true;
+            }
+            NodeKind::BlockClose => {
+                rest.push_str("}\n");
+                in_block = false;
+            }
+            NodeKind::Other => {
+                let _ = writeln!(rest, "{}", node.text.trim());
+            }
+            NodeKind::Comment | NodeKind::Blank => {}
+        }
+    }
+
+    // Entries of the same key keep their order, as the last is in effect.
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut out: String = entries.into_iter().map(|(_, line)| line).collect();
+    out.push_str(&rest);
+    out
+}
+
+/// Returns the hash of the definitions of `surface`, without its ID,
+/// so that surfaces sharing their definitions hash equal.
+///
+/// Definitions are written by their [`Debug`] format in the order of the fields,
+/// keeping the order of elements, collisions and other sequences.
+///
+/// [`Debug`]: core::fmt::Debug
+pub fn surface_hash(surface: &Surface) -> u64 {
+    let mut text = String::new();
+    let _ = write!(
+        text,
+        "{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n{:?}\n",
+        surface.elements,
+        surface.animations,
+        surface.collisions,
+        surface.collisionexes,
+        surface.points,
+        surface.balloon,
+        surface.tooltips,
+        surface.cursors,
+    );
+    hash(&text)
+}
+
+/// Returns the hash of `descript.txt` and the `surfaces*.txt` of `project` in merge order,
+/// each as its file name followed by [`canonical_text`].
+///
+/// [`canonical_text`]: crate::fingerprint::canonical_text
+#[cfg(feature = "std")]
+pub fn project_hash(project: &ShellProject) -> u64 {
+    let mut hasher = StableHasher::new();
+    for file in core::iter::once(project.descript()).chain(project.surfaces().iter()) {
+        let name = file.path().file_name().unwrap_or_default();
+        hasher.write(name.to_string_lossy().as_bytes());
+        hasher.write(b"\n");
+        let doc = Document::parse_with(file.text(), file.options());
+        hasher.write(canonical_text(&doc).as_bytes());
+    }
+    hasher.finish()
+}
+
+fn hash(text: &str) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(text.as_bytes());
+    hasher.finish()
+}
+
+fn entry_line(key: &str, value: &str) -> String {
+    let fields: Vec<_> = value
+        .split(',')
+        .map(|f| f.trim_matches([' ', '\t']))
+        .collect();
+    [
+        key.trim().to_ascii_lowercase().as_str(),
+        ",",
+        &fields.join(","),
+        "\n",
+    ]
+    .concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        block::parse_blocks,
+        surfaces::{build_surfaces, parse_block_body, SurfaceBlock},
+    };
+
+    const CASE: &str = "charset,UTF-8\r\nname,master\r\nsakura.name,Sakura\r\nsurface0\r\n{\r\nelement0,base,body.png,0,0\r\ncollision0,10,10,50,50,Head\r\ncollision1,10,60,50,90,Bust\r\n}\r\n";
+
+    fn surface(text: &str) -> Surface {
+        let blocks: Vec<_> = parse_blocks(text)
+            .unwrap()
+            .into_iter()
+            .map(|block| {
+                let body = parse_block_body(&block.body).unwrap();
+                SurfaceBlock { block, body }
+            })
+            .collect();
+        build_surfaces(&blocks).0.remove(&0).unwrap()
+    }
+
+    mod semantic_hash {
+        use super::*;
+
+        #[test]
+        fn success_when_only_formatting_differs() {
+            let messy = "// master\nsakura.name , Sakura\n\nCharset,UTF-8\nname,master\nSurface0 {\n  element0, base, body.png,0,0\n\t// head\n  collision0,10,10,50,50,Head\ncollision1,10,60,50,90,Bust\n}";
+            let a = Document::parse(CASE);
+            let b = Document::parse(messy);
+            assert_eq!(canonical_text(&a), canonical_text(&b));
+            assert_eq!(semantic_hash(&a), semantic_hash(&b));
+            assert_eq!(
+                semantic_hash(&a),
+                semantic_hash(&Document::parse(&CASE.replace("\r\n", "\n")))
+            );
+        }
+
+        #[test]
+        fn detected_when_coordinate_changed() {
+            let changed = CASE.replace("collision1,10,60", "collision1,10,61");
+            assert_ne!(
+                semantic_hash(&Document::parse(CASE)),
+                semantic_hash(&Document::parse(&changed))
+            );
+
+            let swapped = CASE.replace(
+                "collision0,10,10,50,50,Head\r\ncollision1,10,60,50,90,Bust",
+                "collision1,10,60,50,90,Bust\r\ncollision0,10,10,50,50,Head",
+            );
+            assert_ne!(
+                semantic_hash(&Document::parse(CASE)),
+                semantic_hash(&Document::parse(&swapped))
+            );
+        }
+
+        #[test]
+        fn success_when_pinned() {
+            assert_eq!(
+                canonical_text(&Document::parse(CASE)),
+                "charset,UTF-8\nname,master\nsakura.name,Sakura\nsurface0\n{\nelement0,base,body.png,0,0\ncollision0,10,10,50,50,Head\ncollision1,10,60,50,90,Bust\n}\n"
+            );
+            assert_eq!(semantic_hash(&Document::parse(CASE)), 0x0b77_978d_0fa2_0cb8);
+            assert_eq!(hash(""), 0xcbf2_9ce4_8422_2325);
+            assert_eq!(hash("a"), 0xaf63_dc4c_8601_ec8c);
+        }
+    }
+
+    mod surface_hash {
+        use super::*;
+
+        #[test]
+        fn success_when_only_formatting_differs() {
+            let messy = CASE
+                .replace("\r\n", "\n")
+                .replace("collision0,", "// c\ncollision0,");
+            assert_eq!(surface_hash(&surface(CASE)), surface_hash(&surface(&messy)));
+        }
+
+        #[test]
+        fn detected_when_coordinate_changed() {
+            let changed = CASE.replace("collision0,10,10,50", "collision0,10,10,51");
+            assert_ne!(
+                surface_hash(&surface(CASE)),
+                surface_hash(&surface(&changed))
+            );
+        }
+    }
+}
@@ -0,0 +1,309 @@
+//! # `fixture`
+//!
+//! Generated settings files of a given number of lines, for benchmarks and tests of tools.
+//!
+//! A [`FixtureGenerator`] is seeded, so that the same seed gives the same files on every run and platform.
+//! [`Variant::Clean`] files parse without diagnostics, and [`Variant::Messy`] files have mixed line endings
+//! and the problems counted by [`Fixture::diagnostics`], as reported by [`parse_with_diagnostics`].
+//!
+//! [`FixtureGenerator`]: crate::fixture::FixtureGenerator
+//! [`Variant::Clean`]: crate::fixture::Variant::Clean
+//! [`Variant::Messy`]: crate::fixture::Variant::Messy
+//! [`Fixture::diagnostics`]: crate::fixture::Fixture::diagnostics
+//! [`parse_with_diagnostics`]: crate::diagnostic::parse_with_diagnostics
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::diagnostic::parse_with_diagnostics;
+//! use shell_parser_common_rs::fixture::{FixtureGenerator, Variant};
+//!
+//! let fixture = FixtureGenerator::new(1).surfaces(1000, Variant::Messy);
+//! let outcome = parse_with_diagnostics(&fixture.text);
+//!
+//! assert!(fixture.lines >= 1000);
+//! assert!(outcome.errors.is_empty());
+//! assert_eq!(outcome.warnings.len(), fixture.diagnostics);
+//! ```
+
+use alloc::{format, string::String};
+
+/// Kind of files generated.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Variant {
+    /// Files as written by careful authors, with `\r\n` line endings.
+    Clean,
+    /// Files with `\n` line endings here and there, and text values, comments and blank lines ending with
+    /// spaces or tabs and unknown keys in surface blocks, each reported as a warning.
+    Messy,
+}
+
+/// Generated file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Fixture {
+    pub text: String,
+    /// Number of lines.
+    pub lines: usize,
+    /// Number of warnings of [`parse_with_diagnostics`], which gives no errors.
+    ///
+    /// [`parse_with_diagnostics`]: crate::diagnostic::parse_with_diagnostics
+    pub diagnostics: usize,
+}
+
+const REGIONS: &[&str] = &["Head", "Face", "Bust", "Hand", "Shoulder", "Skirt"];
+const INTERVALS: &[&str] = &["sometimes", "rarely", "always", "runonce", "random,3"];
+const METHODS: &[&str] = &["overlay", "base", "overlayfast", "interpolate", "reduce"];
+
+/// Generator of files from a seed, by SplitMix64.
+#[derive(Debug, Clone)]
+pub struct FixtureGenerator {
+    state: u64,
+}
+
+impl FixtureGenerator {
+    pub fn new(seed: u64) -> FixtureGenerator {
+        FixtureGenerator { state: seed }
+    }
+
+    /// Returns a `surfaces.txt` of at least `lines` lines, in surface blocks of elements,
+    /// collisions and animations, with comments in Japanese.
+    pub fn surfaces(&mut self, lines: usize, variant: Variant) -> Fixture {
+        let mut file = Writer::new(variant);
+        file.line(self, "charset,UTF-8");
+        file.line(self, "");
+
+        let mut id = 0;
+        while file.lines < lines {
+            file.line(self, &format!("surface{}", id));
+            file.line(self, "{");
+            if self.chance(4) {
+                file.text(self, &format!("// 表情{}", id));
+            }
+
+            file.line(self, &format!("element0,base,surface{}.png,0,0", id));
+            for n in 1..=self.below(4) {
+                let entry = format!(
+                    "element{},{},parts\\part{}.png,{},{}",
+                    n,
+                    self.pick(METHODS),
+                    self.below(50),
+                    self.below(300),
+                    self.below(400)
+                );
+                file.line(self, &entry);
+            }
+            for n in 0..self.below(4) {
+                let (x, y) = (self.below(200), self.below(300));
+                let entry = format!(
+                    "collision{},{},{},{},{},{}",
+                    n,
+                    x,
+                    y,
+                    x + 1 + self.below(100),
+                    y + 1 + self.below(100),
+                    self.pick(REGIONS)
+                );
+                file.line(self, &entry);
+            }
+            for n in 0..self.below(3) {
+                let entry = format!("animation{}.interval,{}", n, self.pick(INTERVALS));
+                file.line(self, &entry);
+                for p in 0..=self.below(3) {
+                    let entry = format!(
+                        "animation{}.pattern{},overlay,{},{},0,0",
+                        n,
+                        p,
+                        1000 + self.below(100),
+                        50 * (1 + self.below(4))
+                    );
+                    file.line(self, &entry);
+                }
+            }
+            if variant == Variant::Messy && self.chance(8) {
+                file.line(self, &format!("sparkle{},1", id));
+                file.diagnostics += 1;
+            }
+
+            file.line(self, "}");
+            file.text(self, "");
+            id += 1;
+        }
+        file.finish()
+    }
+
+    /// Returns a `descript.txt` of at least `lines` lines, of names, bind groups and menu items,
+    /// some in Japanese.
+    pub fn descript(&mut self, lines: usize, variant: Variant) -> Fixture {
+        let mut file = Writer::new(variant);
+        file.line(self, "charset,UTF-8");
+        file.line(self, "name,master");
+        file.text(self, "sakura.name,エミリ");
+        file.text(self, "kero.name,テディ");
+
+        let mut n = 0;
+        while file.lines < lines {
+            let scope = if self.chance(2) { "sakura" } else { "kero" };
+            match self.below(4) {
+                0 => file.text(self, &format!("{}.bindgroup{}.name,服,衣装{}", scope, n, n)),
+                1 => {
+                    let entry = format!("{}.bindgroup{}.default,{}", scope, n, self.below(2));
+                    file.line(self, &entry)
+                }
+                2 => file.line(self, &format!("{}.menuitem{},{}", scope, n, n)),
+                _ => file.text(self, &format!("// コメント{}", n)),
+            }
+            n += 1;
+        }
+        file.finish()
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`.
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    /// Returns `true` once in `n` times.
+    fn chance(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    fn pick<'a>(&mut self, words: &[&'a str]) -> &'a str {
+        words[self.below(words.len() as u64) as usize]
+    }
+}
+
+/// Text of a [`Fixture`] being written, with its line count and diagnostics.
+struct Writer {
+    variant: Variant,
+    text: String,
+    lines: usize,
+    diagnostics: usize,
+}
+
+impl Writer {
+    fn new(variant: Variant) -> Writer {
+        Writer {
+            variant,
+            text: String::new(),
+            lines: 0,
+            diagnostics: 0,
+        }
+    }
+
+    fn line(&mut self, generator: &mut FixtureGenerator, text: &str) {
+        self.text.push_str(text);
+        let ending = match self.variant {
+            Variant::Messy if generator.chance(4) => "\n",
+            _ => "\r\n",
+        };
+        self.text.push_str(ending);
+        self.lines += 1;
+    }
+
+    /// Writes `text`, a value of text, a comment or a blank line, which a messy file ends with whitespace at times.
+    fn text(&mut self, generator: &mut FixtureGenerator, text: &str) {
+        let trailing = match (self.variant, generator.below(8)) {
+            (Variant::Clean, _) => "",
+            (Variant::Messy, 0) => " ",
+            (Variant::Messy, 1) => "\t",
+            (Variant::Messy, _) => "",
+        };
+        if !trailing.is_empty() {
+            self.diagnostics += 1;
+        }
+        self.line(generator, &format!("{}{}", text, trailing));
+    }
+
+    fn finish(self) -> Fixture {
+        Fixture {
+            text: self.text,
+            lines: self.lines,
+            diagnostics: self.diagnostics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec::Vec;
+
+    use crate::{charset::Charset, diagnostic::parse_with_diagnostics, document::ShellFile};
+
+    const SIZES: &[usize] = &[1, 1_000, 10_000];
+
+    mod surfaces {
+        use super::*;
+
+        #[test]
+        fn success_when_clean() {
+            for &lines in SIZES {
+                let fixture = FixtureGenerator::new(7).surfaces(lines, Variant::Clean);
+                let outcome = parse_with_diagnostics(&fixture.text);
+                assert_eq!(outcome.errors, Vec::new(), "{} lines", lines);
+                assert_eq!(outcome.warnings, Vec::new(), "{} lines", lines);
+                assert_eq!(fixture.diagnostics, 0);
+                assert_eq!(fixture.text.lines().count(), fixture.lines);
+                assert!(fixture.lines >= lines);
+                assert!(ShellFile::parse(fixture.text.as_bytes(), Charset::ShiftJIS).is_ok());
+            }
+        }
+
+        #[test]
+        fn warned_when_messy() {
+            for &lines in SIZES {
+                let fixture = FixtureGenerator::new(7).surfaces(lines, Variant::Messy);
+                let outcome = parse_with_diagnostics(&fixture.text);
+                assert_eq!(outcome.errors, Vec::new(), "{} lines", lines);
+                assert_eq!(
+                    outcome.warnings.len(),
+                    fixture.diagnostics,
+                    "{} lines",
+                    lines
+                );
+                assert!(ShellFile::parse(fixture.text.as_bytes(), Charset::ShiftJIS).is_ok());
+            }
+            let fixture = FixtureGenerator::new(7).surfaces(1_000, Variant::Messy);
+            assert!(fixture.diagnostics > 0);
+            assert!(fixture.text.matches('\n').count() > fixture.text.matches("\r\n").count());
+        }
+
+        #[test]
+        fn success_when_same_seed() {
+            let generated = |seed| FixtureGenerator::new(seed).surfaces(500, Variant::Messy);
+            assert_eq!(generated(3), generated(3));
+            assert_ne!(generated(3).text, generated(4).text);
+        }
+    }
+
+    mod descript {
+        use super::*;
+
+        #[test]
+        fn success_when_clean() {
+            let fixture = FixtureGenerator::new(7).descript(1_000, Variant::Clean);
+            let outcome = parse_with_diagnostics(&fixture.text);
+            assert_eq!(outcome.errors, Vec::new());
+            assert_eq!(outcome.warnings, Vec::new());
+            assert!(fixture.lines >= 1_000);
+        }
+
+        #[test]
+        fn warned_when_messy() {
+            let fixture = FixtureGenerator::new(7).descript(1_000, Variant::Messy);
+            let outcome = parse_with_diagnostics(&fixture.text);
+            assert_eq!(outcome.errors, Vec::new());
+            assert_eq!(outcome.warnings.len(), fixture.diagnostics);
+            assert!(fixture.diagnostics > 0);
+        }
+    }
+}
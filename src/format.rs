@@ -0,0 +1,327 @@
+//! # `format`
+//!
+//! Canonical formatting of a [`Document`], so that diffs between versions of a file show only changes of entries.
+//!
+//! [`Document`]: crate::document::Document
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::document::Document;
+//! use shell_parser_common_rs::format::{canonicalize, FormatStyle};
+//!
+//! let case = "sakura.name,Sakura\nname , master \n// comment\nsurface0\n{\nelement0, base,a.png,0,0\n}";
+//! let style = FormatStyle::DEFAULT.sort_keys(true).indent("\t");
+//!
+//! assert_eq!(
+//!     canonicalize(&Document::parse(case), &style),
+//!     "name,master\r\nsakura.name,Sakura\r\n// comment\r\nsurface0\r\n{\r\n\telement0,base,a.png,0,0\r\n}\r\n"
+//! );
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    document::{Document, NodeKind},
+    keys::{value_kind, ValueKind},
+    kv::segment_index,
+    line::LineEnding,
+    scope::Scope,
+};
+
+/// Style of [`canonicalize`].
+///
+/// [`canonicalize`]: crate::format::canonicalize
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub struct FormatStyle {
+    /// Line ending of every line, including the last. [`LineEnding::None`] is taken as `\r\n`.
+    ///
+    /// [`LineEnding::None`]: crate::line::LineEnding::None
+    pub line_ending: LineEnding,
+    /// Whether entries outside blocks are sorted, within each run of entries not split by other lines.
+    ///
+    /// Unscoped keys, such as `name`, come before scoped keys, such as `sakura.name`,
+    /// which are in order of [`Scope`]. Keys with an indexed segment, such as `sakura.bindgroup2.name`,
+    /// are sorted by the segment without the index, so that entries of the same kind keep their order.
+    ///
+    /// [`Scope`]: crate::scope::Scope
+    pub sort_keys: bool,
+    /// Indentation of entries in block bodies. It is expected to be spaces or tabs.
+    pub indent: &'static str,
+    /// Whether spaces and tabs around `,` of entries are removed.
+    ///
+    /// Only the separator after the key is normalized in text values, such as names and tooltips,
+    /// which are kept as is after it. [`ValueKind::Text`] values of `animationN.*` are normalized throughout.
+    ///
+    /// [`ValueKind::Text`]: crate::keys::ValueKind::Text
+    pub normalize_separators: bool,
+}
+
+impl FormatStyle {
+    /// `\r\n`, no sorting, no indentation and normalized separators.
+    pub const DEFAULT: FormatStyle = FormatStyle {
+        line_ending: LineEnding::CrLf,
+        sort_keys: false,
+        indent: "",
+        normalize_separators: true,
+    };
+
+    pub const fn line_ending(mut self, ending: LineEnding) -> FormatStyle {
+        self.line_ending = ending;
+        self
+    }
+
+    pub const fn sort_keys(mut self, enabled: bool) -> FormatStyle {
+        self.sort_keys = enabled;
+        self
+    }
+
+    pub const fn indent(mut self, indent: &'static str) -> FormatStyle {
+        self.indent = indent;
+        self
+    }
+
+    pub const fn normalize_separators(mut self, enabled: bool) -> FormatStyle {
+        self.normalize_separators = enabled;
+        self
+    }
+}
+
+impl Default for FormatStyle {
+    fn default() -> Self {
+        FormatStyle::DEFAULT
+    }
+}
+
+/// Writes `doc` in `style`.
+///
+/// Entries, headers, braces and blank lines are trimmed, and entries in block bodies are indented.
+/// Comments and lines not recognized are kept as they are, and entries inside blocks are never reordered.
+///
+/// Formatting is idempotent: the text given by [`Document::parse`] of the result is formatted into the same result.
+///
+/// [`Document::parse`]: crate::document::Document::parse
+pub fn canonicalize(doc: &Document, style: &FormatStyle) -> String {
+    let ending = match style.line_ending {
+        LineEnding::None => LineEnding::CrLf,
+        ending => ending,
+    };
+
+    // Lines with the keys of entries outside blocks, which may be sorted.
+    let mut lines: Vec<(String, Option<SortKey>)> = Vec::new();
+    let mut in_block = false;
+    for node in doc.nodes() {
+        let line = match node.kind {
+            NodeKind::Entry => {
+                let text = entry_text(&node.text, style);
+                if in_block {
+                    ([style.indent, &text].concat(), None)
+                } else {
+                    let key = node.key_value().map(|kv| sort_key(kv.key.trim()));
+                    (text, key)
+                }
+            }
+            NodeKind::BlockHeader => {
+                in_block = node.text.trim_end().ends_with('{');
+                (node.text.trim().to_string(), None)
+            }
+            NodeKind::BlockOpen => {
+                in_block = true;
+                (node.text.trim().to_string(), None)
+            }
+            NodeKind::BlockClose => {
+                in_block = false;
+                (node.text.trim().to_string(), None)
+            }
+            NodeKind::Blank => (String::new(), None),
+            NodeKind::Comment | NodeKind::Other => (node.text.clone(), None),
+        };
+        lines.push(line);
+    }
+
+    if style.sort_keys {
+        for run in lines.chunk_by_mut(|(_, a), (_, b)| a.is_some() && b.is_some()) {
+            run.sort_by(|(_, a), (_, b)| a.cmp(b));
+        }
+    }
+
+    let mut out = String::new();
+    for (text, _) in lines {
+        out.push_str(&text);
+        out.push_str(ending.as_str());
+    }
+    out
+}
+
+/// Group, scope and key without indices, in the order entries are sorted.
+type SortKey = (bool, Option<Scope>, String);
+
+fn sort_key(key: &str) -> SortKey {
+    let mut segments = key.split('.').peekable();
+    let scope = segments.peek().and_then(|s| Scope::from_segment(s));
+    if scope.is_some() {
+        segments.next();
+    }
+
+    let mut rest = String::new();
+    for segment in segments {
+        if !rest.is_empty() {
+            rest.push('.');
+        }
+        let name = segment.trim_end_matches(|c: char| c.is_ascii_digit());
+        rest.push_str(name);
+        if name.len() != segment.len() {
+            break;
+        }
+    }
+
+    (scope.is_some(), scope, rest)
+}
+
+fn entry_text(text: &str, style: &FormatStyle) -> String {
+    let text = text.trim();
+    let Some((key, value)) = text.split_once(',').filter(|_| style.normalize_separators) else {
+        return text.to_string();
+    };
+
+    let key = key.trim_end_matches([' ', '\t']);
+    let fixed = fixed_fields(key);
+    let mut out = String::from(key);
+    for (i, field) in value.splitn(fixed.saturating_add(1), ',').enumerate() {
+        out.push(',');
+        out.push_str(if i < fixed {
+            field.trim_matches([' ', '\t'])
+        } else {
+            field.trim_start_matches([' ', '\t'])
+        });
+    }
+    out
+}
+
+/// Number of leading fields of the value of `key` with whitespace around them removed.
+fn fixed_fields(key: &str) -> usize {
+    let first = key.split('.').next().unwrap_or_default();
+    if value_kind(key) != ValueKind::Text || segment_index(first, "animation").is_some() {
+        usize::MAX
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::XorShift;
+
+    const MESSY: &str = "// header comment  \nsakura.name,Sakura\r\nkero.name , Unyu\r\n  name,master  \r\nsakura.bindgroup2.name,Arm,Bag\r\nsakura.balloon.offsetx,10\r\nsakura.bindgroup1.name , Hat , Ribbon\r\ncharset,UTF-8\r\n \t\r\n//\tkeep  this ,  as is \r\nsurface0\r\n{\r\n  element1,overlay, b.png,0,0\r\n\telement0 ,base,a.png,0,0\r\n    // inner comment\r\n}\r\nsurface1 { \r\nelement0,base,c.png,0,0\r\n}";
+
+    mod canonicalize {
+        use super::*;
+
+        #[test]
+        fn success_when_messy_fixture() {
+            let style = FormatStyle::DEFAULT.sort_keys(true).indent("\t");
+
+            assert_eq!(
+                canonicalize(&Document::parse(MESSY), &style),
+                "// header comment  \r\ncharset,UTF-8\r\nname,master\r\nsakura.balloon.offsetx,10\r\nsakura.bindgroup2.name,Arm,Bag\r\nsakura.bindgroup1.name,Hat , Ribbon\r\nsakura.name,Sakura\r\nkero.name,Unyu\r\n\r\n//\tkeep  this ,  as is \r\nsurface0\r\n{\r\n\telement1,overlay,b.png,0,0\r\n\telement0,base,a.png,0,0\r\n    // inner comment\r\n}\r\nsurface1 {\r\n\telement0,base,c.png,0,0\r\n}\r\n"
+            );
+        }
+
+        #[test]
+        fn success_when_default_style() {
+            let case =
+                "kero.name , Unyu \nname,master\n\nsurface0\n{\n element0, base,a.png,0,0\n}";
+            let style = FormatStyle::DEFAULT
+                .line_ending(LineEnding::Lf)
+                .normalize_separators(false);
+
+            assert_eq!(
+                canonicalize(&Document::parse(case), &style),
+                "kero.name , Unyu\nname,master\n\nsurface0\n{\nelement0, base,a.png,0,0\n}\n"
+            );
+            assert_eq!(
+                canonicalize(&Document::parse(case), &FormatStyle::default()),
+                "kero.name,Unyu\r\nname,master\r\n\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n"
+            );
+        }
+
+        #[test]
+        fn success_when_text_values_kept() {
+            let case = "surface0\n{\ncollision0 , 0, 0,10 ,10, Head \ntooltip.Head , head, top\nanimation0.pattern0 , overlay, 1,0,0,0\n}\nsakura.name, Sakura ,  chan \n";
+
+            assert_eq!(
+                canonicalize(&Document::parse(case), &FormatStyle::DEFAULT),
+                "surface0\r\n{\r\ncollision0,0,0,10,10,Head\r\ntooltip.Head,head, top\r\nanimation0.pattern0,overlay,1,0,0,0\r\n}\r\nsakura.name,Sakura ,  chan\r\n"
+            );
+        }
+
+        #[test]
+        fn success_when_comments_kept() {
+            let style = FormatStyle::DEFAULT.sort_keys(true).indent("  ");
+            let comments = |text: &str| -> Vec<String> {
+                Document::parse(text)
+                    .nodes()
+                    .iter()
+                    .filter(|n| n.kind == NodeKind::Comment)
+                    .map(|n| n.text.clone())
+                    .collect()
+            };
+
+            let formatted = canonicalize(&Document::parse(MESSY), &style);
+            assert_eq!(comments(&formatted), comments(MESSY));
+        }
+
+        #[test]
+        fn success_when_idempotent_property() {
+            const PIECES: &[&str] = &[
+                "name,master",
+                "sakura.name , a",
+                "kero.bindgroup10.name,x ,y",
+                "kero.bindgroup2.name,z,w",
+                "char2.name,c",
+                "charset,UTF-8",
+                "surface0",
+                "surface1 {",
+                "{",
+                "}",
+                " ",
+                "\t// c , d ",
+                "animation0.pattern10,overlay,1,0,0,0",
+                "  animation0.pattern2 , overlay,2,0,0,0",
+                "tooltip.Head,head, top",
+                "sakura.name , a ,\tb",
+                "??",
+                "",
+            ];
+            const ENDINGS: &[&str] = &["\r\n", "\n", "\r"];
+            const STYLES: &[FormatStyle] = &[
+                FormatStyle::DEFAULT,
+                FormatStyle::DEFAULT.sort_keys(true).indent("\t"),
+                FormatStyle::DEFAULT
+                    .line_ending(LineEnding::Lf)
+                    .normalize_separators(false)
+                    .indent("  "),
+            ];
+            let mut rng = XorShift::new(0x9e37_79b9);
+
+            for _ in 0..2000 {
+                let mut case = String::new();
+                for _ in 0..rng.below(20) {
+                    case.push_str(rng.pick(PIECES));
+                    case.push_str(rng.pick(ENDINGS));
+                }
+                let style = rng.pick(STYLES);
+
+                let once = canonicalize(&Document::parse(&case), &style);
+                let twice = canonicalize(&Document::parse(&once), &style);
+                assert_eq!(once, twice, "{:?}", case);
+            }
+        }
+    }
+}
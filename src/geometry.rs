@@ -0,0 +1,194 @@
+//! # `geometry`
+//!
+//! Parser for coordinate lists and hit-testing helpers for them.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::geometry::{parse_point_list, polygon_contains};
+//! use shell_parser_common_rs::value::Coordinate;
+//!
+//! let (_remain, points) = parse_point_list("0,0,10,0,10,10,0,10\r\n").unwrap();
+//!
+//! assert!(polygon_contains(&points, Coordinate::new(5, 5)));
+//! assert!(!polygon_contains(&points, Coordinate::new(15, 5)));
+//! ```
+
+use alloc::vec::Vec;
+
+use nom::{combinator::verify, multi::separated_list1, IResult};
+
+use crate::{
+    value::{separator, Coordinate, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// parser for the list of points written as `x1,y1,x2,y2,...`.
+///
+/// The number of values must be even and at least three points are required.
+pub fn parse_point_list(input: &str) -> IResult<&str, Vec<Coordinate>, ShellParseError<'_>> {
+    point_list(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+pub(crate) fn point_list<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Vec<Coordinate>, ShellParseError<'a>> {
+    let (input, values) = verify(
+        verify(
+            separated_list1(separator, |i| numbers.i32(i)),
+            |v: &Vec<i32>| v.len().is_multiple_of(2),
+        ),
+        |v: &Vec<i32>| v.len() >= 6,
+    )(input)?;
+
+    let points = values
+        .chunks(2)
+        .map(|pair| Coordinate::new(pair[0], pair[1]))
+        .collect();
+
+    Ok((input, points))
+}
+
+/// Checks whether `p` is inside the polygon `points` by the even-odd rule.
+///
+/// Points exactly on an edge or a vertex are treated as inside.
+/// Because of that, a degenerate polygon (all points collinear) contains
+/// only the points lying on its edges.
+/// Less than three points never contain anything.
+pub fn polygon_contains(points: &[Coordinate], p: Coordinate) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    for (i, a) in points.iter().enumerate() {
+        let b = &points[(i + 1) % points.len()];
+
+        if on_segment(a, b, &p) {
+            return true;
+        }
+
+        let (ax, ay) = (a.x as i64, a.y as i64);
+        let (bx, by) = (b.x as i64, b.y as i64);
+        let (px, py) = (p.x as i64, p.y as i64);
+
+        if (ay > py) != (by > py) {
+            // Compares the x of the intersection with the horizontal ray against `px`
+            // without division.
+            let dy = by - ay;
+            let lhs = (bx - ax) * (py - ay);
+            let rhs = (px - ax) * dy;
+            let crosses = if dy > 0 { lhs > rhs } else { lhs < rhs };
+            if crosses {
+                inside = !inside;
+            }
+        }
+    }
+
+    inside
+}
+
+fn on_segment(a: &Coordinate, b: &Coordinate, p: &Coordinate) -> bool {
+    let (ax, ay) = (a.x as i64, a.y as i64);
+    let (bx, by) = (b.x as i64, b.y as i64);
+    let (px, py) = (p.x as i64, p.y as i64);
+
+    let cross = (bx - ax) * (py - ay) - (by - ay) * (px - ax);
+    cross == 0 && ax.min(bx) <= px && px <= ax.max(bx) && ay.min(by) <= py && py <= ay.max(by)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_point_list {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "0,0,10,0,5,-10\r\n";
+            let (remain, result) = parse_point_list(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                vec![
+                    Coordinate::new(0, 0),
+                    Coordinate::new(10, 0),
+                    Coordinate::new(5, -10)
+                ]
+            );
+        }
+
+        #[test]
+        fn failed_when_odd_count() {
+            let case = "0,0,10,0,5,-10,3\r\n";
+            assert!(parse_point_list(case).is_err());
+        }
+
+        #[test]
+        fn failed_when_too_few_points() {
+            let case = "0,0,10,0\r\n";
+            assert!(parse_point_list(case).is_err());
+        }
+    }
+
+    mod polygon_contains {
+        use super::*;
+
+        #[test]
+        fn triangle_containment() {
+            let triangle = vec![
+                Coordinate::new(0, 0),
+                Coordinate::new(10, 0),
+                Coordinate::new(5, 10),
+            ];
+            assert!(polygon_contains(&triangle, Coordinate::new(5, 3)));
+            assert!(!polygon_contains(&triangle, Coordinate::new(1, 8)));
+            assert!(!polygon_contains(&triangle, Coordinate::new(-1, 0)));
+        }
+
+        #[test]
+        fn concave_polygon() {
+            // U shape opening upward.
+            let shape = vec![
+                Coordinate::new(0, 0),
+                Coordinate::new(30, 0),
+                Coordinate::new(30, 30),
+                Coordinate::new(20, 30),
+                Coordinate::new(20, 10),
+                Coordinate::new(10, 10),
+                Coordinate::new(10, 30),
+                Coordinate::new(0, 30),
+            ];
+            assert!(polygon_contains(&shape, Coordinate::new(5, 20)));
+            assert!(polygon_contains(&shape, Coordinate::new(25, 20)));
+            assert!(polygon_contains(&shape, Coordinate::new(15, 5)));
+            assert!(!polygon_contains(&shape, Coordinate::new(15, 20)));
+        }
+
+        #[test]
+        fn point_on_vertex_and_edge() {
+            let square = vec![
+                Coordinate::new(0, 0),
+                Coordinate::new(10, 0),
+                Coordinate::new(10, 10),
+                Coordinate::new(0, 10),
+            ];
+            assert!(polygon_contains(&square, Coordinate::new(10, 10)));
+            assert!(polygon_contains(&square, Coordinate::new(0, 5)));
+        }
+
+        #[test]
+        fn degenerate_collinear_polygon() {
+            let line = vec![
+                Coordinate::new(0, 0),
+                Coordinate::new(5, 5),
+                Coordinate::new(10, 10),
+            ];
+            assert!(polygon_contains(&line, Coordinate::new(3, 3)));
+            assert!(!polygon_contains(&line, Coordinate::new(3, 4)));
+            assert!(!polygon_contains(&line, Coordinate::new(11, 11)));
+        }
+    }
+}
@@ -0,0 +1,112 @@
+//! # `intern`
+//!
+//! Opt-in sharing of repeated strings, such as keys, method names and filenames of shells with thousands of surfaces.
+//! Parsers without an [`Interner`] are not affected.
+//!
+//! [`Interner`]: crate::intern::Interner
+//!
+//! ## Example
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use shell_parser_common_rs::document::Document;
+//! use shell_parser_common_rs::intern::Interner;
+//!
+//! let document = Document::parse("surface0\r\n{\r\nelement0,overlay,a.png,0,0\r\n}\r\nsurface1\r\n{\r\nelement0,overlay,a.png,0,0\r\n}\r\n");
+//! let mut interner = Interner::new();
+//! let entries = document.intern_entries(&mut interner);
+//!
+//! assert!(Arc::ptr_eq(&entries[0].key, &entries[1].key));
+//! assert!(Arc::ptr_eq(&entries[0].fields[1], &entries[1].fields[1]));
+//! assert_eq!(interner.len(), 4);
+//! ```
+
+use alloc::{collections::BTreeSet, sync::Arc, vec::Vec};
+
+#[cfg(feature = "surfaces")]
+use crate::{seriko::DrawMethod, value::Coordinate};
+
+/// Cache of strings keyed by their content, giving one shared allocation per distinct string.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: BTreeSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Returns the string equal to `text`, stored on the first call with it.
+    pub fn intern(&mut self, text: &str) -> Arc<str> {
+        if let Some(interned) = self.strings.get(text) {
+            return interned.clone();
+        }
+        let interned: Arc<str> = Arc::from(text);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// Returns the number of distinct strings.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns bytes of the distinct strings, without the cache itself.
+    pub fn stored_bytes(&self) -> usize {
+        self.strings.iter().map(|s| s.len()).sum()
+    }
+}
+
+/// Entry with its key and fields interned, given by [`Document::intern_entries`].
+///
+/// [`Document::intern_entries`]: crate::document::Document::intern_entries
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InternedEntry {
+    /// 1-based line number in the document.
+    pub line: usize,
+    pub key: Arc<str>,
+    /// The value split by `,`.
+    pub fields: Vec<Arc<str>>,
+}
+
+/// [`Element`] with its filename interned, given by [`intern_elements`].
+///
+/// [`Element`]: crate::surfaces::Element
+/// [`intern_elements`]: crate::surfaces::intern_elements
+#[cfg(feature = "surfaces")]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InternedElement {
+    pub id: u32,
+    pub method: DrawMethod,
+    pub filename: Arc<str>,
+    pub offset: Coordinate,
+    pub alpha: Option<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod interner {
+        use super::*;
+
+        #[test]
+        fn success_when_duplicates_shared() {
+            let mut interner = Interner::new();
+            let first = interner.intern("overlay");
+            let second = interner.intern(&["over", "lay"].concat());
+            let other = interner.intern("base");
+
+            assert!(Arc::ptr_eq(&first, &second));
+            assert!(!Arc::ptr_eq(&first, &other));
+            assert_eq!(interner.len(), 2);
+            assert_eq!(interner.stored_bytes(), "overlay".len() + "base".len());
+        }
+    }
+}
@@ -0,0 +1,439 @@
+//! # `io`
+//!
+//! Adapters between [`std::io`] and charsets.
+//!
+//! ## Example
+//!
+//! ```
+//! use std::io::BufRead;
+//!
+//! use shell_parser_common_rs::charset::Charset;
+//! use shell_parser_common_rs::io::DecodingReader;
+//!
+//! let (case, _, _) = encoding_rs::SHIFT_JIS.encode("charset,Shift_JIS\r\nname,マスター\r\n");
+//! let reader = DecodingReader::new(&case[..], Charset::ShiftJIS);
+//! let lines: Vec<_> = reader.lines().collect::<Result<_, _>>().unwrap();
+//!
+//! assert_eq!(lines, vec!["charset,Shift_JIS", "name,マスター"]);
+//! ```
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read, Write},
+};
+
+use encoding_rs::{Decoder, Encoder, EncoderResult};
+
+use crate::charset::{decode_to_string, new_decoder, Charset, DecodeError, EncodeError};
+
+/// Size of the byte buffer of [`DecodingReader::new`] and [`EncodingWriter::new`].
+///
+/// [`DecodingReader::new`]: crate::io::DecodingReader::new
+/// [`EncodingWriter::new`]: crate::io::EncodingWriter::new
+pub const DEFAULT_BUFFER_SIZE: usize = 8192;
+
+/// Reader of decoded UTF-8 text from a reader of bytes in a charset.
+///
+/// Bytes are read into a fixed-size buffer and decoded as they come,
+/// so chars split across reads are decoded once complete.
+/// [`Charset::Default`] is decoded as UTF-8, replacing invalid sequences.
+///
+/// A decode error is returned as [`io::ErrorKind::InvalidData`] carrying the [`DecodeError`],
+/// after the text decoded before it.
+///
+/// [`Charset::Default`]: crate::charset::Charset::Default
+/// [`DecodeError`]: crate::charset::DecodeError
+pub struct DecodingReader<R> {
+    inner: R,
+    charset: Charset,
+    decoder: Decoder,
+    /// Bytes read but not yet decoded are `buffer[start..end]`.
+    buffer: Box<[u8]>,
+    start: usize,
+    end: usize,
+    /// Decoded text, of which `text[pos..]` is not yet consumed.
+    text: String,
+    pos: usize,
+    /// Whether `inner` has reached its end.
+    eof: bool,
+    /// Number of bytes decoded, and of `\n` in them, for locating a decode error.
+    decoded: usize,
+    lines: usize,
+    error: Option<DecodeError>,
+}
+
+impl<R: Read> DecodingReader<R> {
+    pub fn new(inner: R, charset: Charset) -> DecodingReader<R> {
+        DecodingReader::with_capacity(inner, charset, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Same as [`DecodingReader::new`], but reads at most `capacity` bytes at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// [`DecodingReader::new`]: crate::io::DecodingReader::new
+    pub fn with_capacity(inner: R, charset: Charset, capacity: usize) -> DecodingReader<R> {
+        assert!(capacity > 0, "capacity must not be zero");
+
+        DecodingReader {
+            inner,
+            decoder: new_decoder(&charset),
+            charset,
+            buffer: vec![0; capacity].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            text: String::new(),
+            pos: 0,
+            eof: false,
+            decoded: 0,
+            lines: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the charset decoded from.
+    pub fn charset(&self) -> &Charset {
+        &self.charset
+    }
+
+    /// Returns the inner reader, dropping text not yet consumed.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads and decodes until some text is decoded or the end is reached.
+    fn decode_more(&mut self) -> io::Result<()> {
+        self.text.clear();
+        self.pos = 0;
+
+        while self.text.is_empty() {
+            if self.start == self.end {
+                if self.eof {
+                    break;
+                }
+                self.start = 0;
+                self.end = match self.inner.read(&mut self.buffer) {
+                    Ok(read) => read,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                };
+                self.eof = self.end == 0;
+            }
+
+            let src = &self.buffer[self.start..self.end];
+            match decode_to_string(
+                &mut self.decoder,
+                &self.charset,
+                src,
+                &mut self.text,
+                self.eof,
+            ) {
+                Ok(read) => {
+                    self.start += read;
+                    self.decoded += read;
+                    self.lines += memchr::memchr_iter(b'\n', &src[..read]).count();
+                }
+                Err(offset) => {
+                    self.error = Some(DecodeError::Malformed {
+                        charset: self.charset.clone(),
+                        byte_offset: self.decoded + offset,
+                        line: self.lines + memchr::memchr_iter(b'\n', &src[..offset]).count() + 1,
+                    });
+                    self.start = self.end;
+                    self.eof = true;
+                    break;
+                }
+            }
+        }
+
+        if self.text.is_empty() {
+            if let Some(e) = &self.error {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e.clone()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: Read> BufRead for DecodingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.text.len() {
+            self.decode_more()?;
+        }
+        Ok(&self.text.as_bytes()[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.text.len());
+    }
+}
+
+/// Writer of UTF-8 text into a writer of bytes in a charset.
+///
+/// Encoded bytes are buffered and written when the buffer is full, on [`flush`] and on [`finish`].
+/// [`Charset::Default`] is encoded as UTF-8.
+///
+/// A char which cannot be encoded is returned as [`io::ErrorKind::Other`] carrying the [`EncodeError`].
+/// Text before it is still written.
+///
+/// ```
+/// use shell_parser_common_rs::charset::Charset;
+/// use shell_parser_common_rs::io::EncodingWriter;
+///
+/// let mut writer = EncodingWriter::new(Vec::new(), Charset::ShiftJIS);
+/// let name = "マスター";
+/// write!(writer, "name,{}\r\n", name).unwrap();
+/// let result = writer.finish().unwrap();
+///
+/// assert_eq!(result, *encoding_rs::SHIFT_JIS.encode("name,マスター\r\n").0);
+/// ```
+///
+/// [`flush`]: crate::io::EncodingWriter::flush
+/// [`finish`]: crate::io::EncodingWriter::finish
+/// [`Charset::Default`]: crate::charset::Charset::Default
+/// [`EncodeError`]: crate::charset::EncodeError
+pub struct EncodingWriter<W: Write> {
+    inner: W,
+    charset: Charset,
+    encoder: Encoder,
+    buffer: Vec<u8>,
+    /// Error of the last [`fmt::Write::write_str`].
+    error: Option<io::Error>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    pub fn new(inner: W, charset: Charset) -> EncodingWriter<W> {
+        EncodingWriter::with_capacity(inner, charset, DEFAULT_BUFFER_SIZE)
+    }
+
+    /// Same as [`EncodingWriter::new`], but buffers about `capacity` bytes before writing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    ///
+    /// [`EncodingWriter::new`]: crate::io::EncodingWriter::new
+    pub fn with_capacity(inner: W, charset: Charset, capacity: usize) -> EncodingWriter<W> {
+        assert!(capacity > 0, "capacity must not be zero");
+
+        let encoding = charset.encoding().unwrap_or(encoding_rs::UTF_8);
+        EncodingWriter {
+            inner,
+            charset,
+            encoder: encoding.new_encoder(),
+            buffer: Vec::with_capacity(capacity),
+            error: None,
+        }
+    }
+
+    /// Returns the charset encoded into.
+    pub fn charset(&self) -> &Charset {
+        &self.charset
+    }
+
+    /// Encodes `text` into the buffer, writing the buffer out when it is full.
+    pub fn write_str(&mut self, text: &str) -> io::Result<()> {
+        self.encode(text, false)
+    }
+
+    /// Writes formatted text, so that `write!` can be used.
+    pub fn write_fmt(&mut self, args: fmt::Arguments<'_>) -> io::Result<()> {
+        match fmt::Write::write_fmt(self, args) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self
+                .error
+                .take()
+                .unwrap_or_else(|| io::Error::other("formatter error"))),
+        }
+    }
+
+    /// Writes the buffer out and flushes the inner writer.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer()?;
+        self.inner.flush()
+    }
+
+    /// Ends the text, such as shifting ISO-2022-JP back to ASCII, and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.encode("", true)?;
+        self.flush()?;
+        Ok(self.inner)
+    }
+
+    fn encode(&mut self, mut text: &str, last: bool) -> io::Result<()> {
+        loop {
+            let (result, read) = self.encoder.encode_from_utf8_to_vec_without_replacement(
+                text,
+                &mut self.buffer,
+                last,
+            );
+            text = &text[read..];
+            match result {
+                EncoderResult::InputEmpty => return Ok(()),
+                EncoderResult::OutputFull if self.buffer.is_empty() => {
+                    self.buffer.reserve(self.buffer.capacity() * 2);
+                }
+                EncoderResult::OutputFull => self.write_buffer()?,
+                EncoderResult::Unmappable(c) => {
+                    return Err(io::Error::other(EncodeError::Unmappable(
+                        self.charset.clone(),
+                        c,
+                    )));
+                }
+            }
+        }
+    }
+
+    fn write_buffer(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> fmt::Write for EncodingWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        EncodingWriter::write_str(self, s).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reader giving at most `size` bytes at a time.
+    struct ChunkedReader<'a> {
+        input: &'a [u8],
+        size: usize,
+    }
+
+    impl Read for ChunkedReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.input.len().min(self.size).min(buf.len());
+            let (chunk, rest) = self.input.split_at(len);
+            buf[..len].copy_from_slice(chunk);
+            self.input = rest;
+            Ok(len)
+        }
+    }
+
+    mod decoding_reader {
+        use super::*;
+
+        #[test]
+        fn success_when_chars_split_across_reads() {
+            let text =
+                "charset,Shift_JIS\r\n// シェルの設定\r\nname,マスター\r\nkero.name,相方\r\n";
+            let (case, _, _) = encoding_rs::SHIFT_JIS.encode(text);
+            for offset in 0..7 {
+                let mut input = vec![b'a'; offset];
+                input.extend_from_slice(&case);
+                let inner = ChunkedReader {
+                    input: &input,
+                    size: 7,
+                };
+                let mut reader = DecodingReader::with_capacity(inner, Charset::ShiftJIS, 7);
+
+                let mut result = String::new();
+                while reader.read_line(&mut result).unwrap() > 0 {}
+                assert_eq!(result, format!("{}{}", "a".repeat(offset), text));
+            }
+        }
+
+        #[test]
+        fn success_when_read_to_string() {
+            let mut reader = DecodingReader::new(&b"\xef\xbb\xbfa,\xff\r\n"[..], Charset::Default);
+            let mut result = String::new();
+            reader.read_to_string(&mut result).unwrap();
+            assert_eq!(result, "a,\u{fffd}\r\n");
+        }
+
+        #[test]
+        fn failed_when_invalid_sequence() {
+            let mut case = Vec::new();
+            for i in 0..3 {
+                case.extend_from_slice(format!("line{},ok\r\n", i).as_bytes());
+            }
+            case.extend_from_slice(b"broken,\xff\r\nafter,x\r\n");
+            let inner = ChunkedReader {
+                input: &case,
+                size: 7,
+            };
+            let mut reader = DecodingReader::with_capacity(inner, Charset::UTF8, 7);
+
+            let mut line = String::new();
+            for i in 0..3 {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                assert_eq!(line, format!("line{},ok\r\n", i));
+            }
+            let e = reader.read_line(&mut line).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            assert_eq!(
+                e.get_ref().and_then(|e| e.downcast_ref::<DecodeError>()),
+                Some(&DecodeError::Malformed {
+                    charset: Charset::UTF8,
+                    byte_offset: 37,
+                    line: 4,
+                })
+            );
+        }
+    }
+
+    mod encoding_writer {
+        use super::*;
+
+        #[test]
+        fn success_when_shift_jis() {
+            let text = "charset,Shift_JIS\r\n// シェルの設定\r\nname,マスター\r\n";
+            let mut writer = EncodingWriter::with_capacity(Vec::new(), Charset::ShiftJIS, 7);
+            for line in text.split_inclusive('\n') {
+                writer.write_str(line).unwrap();
+            }
+            let name = "相方";
+            write!(writer, "kero.name,{}\r\n", name).unwrap();
+            let result = writer.finish().unwrap();
+
+            let expected = format!("{}kero.name,相方\r\n", text);
+            assert_eq!(result, *encoding_rs::SHIFT_JIS.encode(&expected).0);
+        }
+
+        #[test]
+        fn success_when_iso_2022_jp_finished() {
+            let text = "name,マスター";
+            let mut writer = EncodingWriter::new(Vec::new(), Charset::ISO2022JP);
+            writer.write_str(text).unwrap();
+            let result = writer.finish().unwrap();
+            assert_eq!(result, *encoding_rs::ISO_2022_JP.encode(text).0);
+            assert!(result.ends_with(b"\x1b(B"));
+        }
+
+        #[test]
+        fn failed_when_unmappable() {
+            let mut writer = EncodingWriter::new(Vec::new(), Charset::ShiftJIS);
+            let c = '😀';
+            let e = write!(writer, "name,{}\r\n", c).unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::Other);
+            assert_eq!(
+                e.get_ref().and_then(|e| e.downcast_ref::<EncodeError>()),
+                Some(&EncodeError::Unmappable(Charset::ShiftJIS, '😀'))
+            );
+            assert_eq!(writer.finish().unwrap(), b"name,");
+        }
+    }
+}
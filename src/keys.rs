@@ -0,0 +1,320 @@
+//! # `keys`
+//!
+//! Classification of `descript.txt` keys parsed by this crate, in one pass over the key.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::keys::{classify_key, KnownKey};
+//! use shell_parser_common_rs::scope::Scope;
+//!
+//! assert_eq!(
+//!     classify_key("char2.balloon.offsetx"),
+//!     KnownKey::BalloonOffsetX(Scope::Char(2))
+//! );
+//! assert_eq!(classify_key("homeurl"), KnownKey::Unknown);
+//! ```
+
+use core::fmt;
+
+use crate::{cursor::CursorKind, diagnostic::Severity, scope::Scope};
+
+/// Key of `descript.txt` which a parser of this crate reads.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum KnownKey {
+    /// `charset`
+    Charset,
+    /// `shiori`
+    Shiori,
+    /// `shiori.version`
+    ShioriVersion,
+    /// `sstp.allowunspecifiedsend`
+    SstpAllowUnspecifiedSend,
+    /// `sstp.alwaystranslate`
+    SstpAlwaysTranslate,
+    /// `seriko.use_self_alpha`
+    SerikoUseSelfAlpha,
+    /// `<scope>.name`
+    Name(Scope),
+    /// `<scope>.name2`
+    Name2(Scope),
+    /// `<scope>.balloon.offsetx`
+    BalloonOffsetX(Scope),
+    /// `<scope>.balloon.offsety`
+    BalloonOffsetY(Scope),
+    /// `<scope>.balloon.alignment`
+    BalloonAlignment(Scope),
+    /// `<scope>.seriko.defaultsurface`
+    DefaultSurface(Scope),
+    /// `<scope>.bindgroupN.name`
+    BindGroupName(Scope, u32),
+    /// `<scope>.bindgroupN.default`
+    BindGroupDefault(Scope, u32),
+    /// `<scope>.cursor.mouseupN` and `<scope>.cursor.mousedownN`
+    Cursor(Scope, CursorKind, u32),
+    /// Any other key. It is written as the empty string.
+    Unknown,
+}
+
+/// Classifies `key`, comparing it byte by byte once rather than against each known prefix.
+///
+/// Keys are compared as written, as the parsers of each key do.
+pub fn classify_key(key: &str) -> KnownKey {
+    match key {
+        "charset" => return KnownKey::Charset,
+        "shiori" => return KnownKey::Shiori,
+        "shiori.version" => return KnownKey::ShioriVersion,
+        "sstp.allowunspecifiedsend" => return KnownKey::SstpAllowUnspecifiedSend,
+        "sstp.alwaystranslate" => return KnownKey::SstpAlwaysTranslate,
+        "seriko.use_self_alpha" => return KnownKey::SerikoUseSelfAlpha,
+        _ => {}
+    }
+
+    let Some((scope, rest)) = split_scope_prefix(key) else {
+        return KnownKey::Unknown;
+    };
+
+    match rest {
+        "name" => KnownKey::Name(scope),
+        "name2" => KnownKey::Name2(scope),
+        "balloon.offsetx" => KnownKey::BalloonOffsetX(scope),
+        "balloon.offsety" => KnownKey::BalloonOffsetY(scope),
+        "balloon.alignment" => KnownKey::BalloonAlignment(scope),
+        "seriko.defaultsurface" => KnownKey::DefaultSurface(scope),
+        _ => classify_indexed(scope, rest).unwrap_or(KnownKey::Unknown),
+    }
+}
+
+/// Kind of the value of a key, telling how much whitespace in it matters.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ValueKind {
+    /// Filenames, such as of `elementN` and cursors, which do not match files with whitespace.
+    Filename,
+    /// Names and keywords matched against others, such as collision names and charsets.
+    Identifier,
+    /// Text shown as is, such as names of characters and tooltips, and values of unknown keys.
+    Text,
+}
+
+impl ValueKind {
+    /// Returns the severity of whitespace around values of the kind,
+    /// [`Severity::Info`] for [`ValueKind::Text`] and [`Severity::Warning`] for the others.
+    ///
+    /// [`Severity::Info`]: crate::diagnostic::Severity::Info
+    /// [`Severity::Warning`]: crate::diagnostic::Severity::Warning
+    /// [`ValueKind::Text`]: crate::keys::ValueKind::Text
+    pub fn severity(&self) -> Severity {
+        match self {
+            ValueKind::Filename | ValueKind::Identifier => Severity::Warning,
+            ValueKind::Text => Severity::Info,
+        }
+    }
+}
+
+/// Returns the kind of the value of `key`, of `descript.txt` or of a surface block.
+///
+/// Keys of `descript.txt` are classified by [`classify_key`].
+///
+/// [`classify_key`]: crate::keys::classify_key
+pub fn value_kind(key: &str) -> ValueKind {
+    match classify_key(key) {
+        KnownKey::Shiori | KnownKey::Cursor(..) => return ValueKind::Filename,
+        KnownKey::Name(_) | KnownKey::Name2(_) | KnownKey::BindGroupName(..) => {
+            return ValueKind::Text
+        }
+        KnownKey::Unknown => {}
+        _ => return ValueKind::Identifier,
+    }
+
+    let first = key.split('.').next().unwrap_or_default();
+    if is_indexed(first, "element")
+        || first == "cursor"
+        || (first == "menu" && key.ends_with(".bitmap.filename"))
+    {
+        ValueKind::Filename
+    } else if is_indexed(first, "collisionex") || is_indexed(first, "collision") {
+        ValueKind::Identifier
+    } else {
+        ValueKind::Text
+    }
+}
+
+fn is_indexed(segment: &str, prefix: &str) -> bool {
+    segment.strip_prefix(prefix).and_then(index_of).is_some()
+}
+
+/// Classifies `bindgroupN.*` and `cursor.*` following a scope.
+fn classify_indexed(scope: Scope, rest: &str) -> Option<KnownKey> {
+    if let Some(segment) = rest.strip_prefix("cursor.") {
+        if let Some(index) = segment.strip_prefix("mouseup") {
+            return Some(KnownKey::Cursor(
+                scope,
+                CursorKind::MouseUp,
+                index_of(index)?,
+            ));
+        }
+        let index = segment.strip_prefix("mousedown")?;
+        return Some(KnownKey::Cursor(
+            scope,
+            CursorKind::MouseDown,
+            index_of(index)?,
+        ));
+    }
+
+    let (group, field) = rest.strip_prefix("bindgroup")?.split_once('.')?;
+    let id = index_of(group)?;
+    match field {
+        "name" => Some(KnownKey::BindGroupName(scope, id)),
+        "default" => Some(KnownKey::BindGroupDefault(scope, id)),
+        _ => None,
+    }
+}
+
+/// Splits the leading `sakura.`, `kero.` or `charN.` from `key`.
+fn split_scope_prefix(key: &str) -> Option<(Scope, &str)> {
+    let (first, rest) = key.split_once('.')?;
+    let scope = match first {
+        "sakura" => Scope::Sakura,
+        "kero" => Scope::Kero,
+        _ => Scope::Char(index_of(first.strip_prefix("char")?)?),
+    };
+
+    Some((scope, rest))
+}
+
+/// Reads `digits` as an index, as [`parse_u32`] does for a whole segment.
+///
+/// [`parse_u32`]: crate::value::parse_u32
+fn index_of(digits: &str) -> Option<u32> {
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+impl fmt::Display for KnownKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KnownKey::Charset => write!(f, "charset"),
+            KnownKey::Shiori => write!(f, "shiori"),
+            KnownKey::ShioriVersion => write!(f, "shiori.version"),
+            KnownKey::SstpAllowUnspecifiedSend => write!(f, "sstp.allowunspecifiedsend"),
+            KnownKey::SstpAlwaysTranslate => write!(f, "sstp.alwaystranslate"),
+            KnownKey::SerikoUseSelfAlpha => write!(f, "seriko.use_self_alpha"),
+            KnownKey::Name(scope) => write!(f, "{}.name", scope),
+            KnownKey::Name2(scope) => write!(f, "{}.name2", scope),
+            KnownKey::BalloonOffsetX(scope) => write!(f, "{}.balloon.offsetx", scope),
+            KnownKey::BalloonOffsetY(scope) => write!(f, "{}.balloon.offsety", scope),
+            KnownKey::BalloonAlignment(scope) => write!(f, "{}.balloon.alignment", scope),
+            KnownKey::DefaultSurface(scope) => write!(f, "{}.seriko.defaultsurface", scope),
+            KnownKey::BindGroupName(scope, id) => write!(f, "{}.bindgroup{}.name", scope, id),
+            KnownKey::BindGroupDefault(scope, id) => {
+                write!(f, "{}.bindgroup{}.default", scope, id)
+            }
+            KnownKey::Cursor(scope, CursorKind::MouseUp, index) => {
+                write!(f, "{}.cursor.mouseup{}", scope, index)
+            }
+            KnownKey::Cursor(scope, CursorKind::MouseDown, index) => {
+                write!(f, "{}.cursor.mousedown{}", scope, index)
+            }
+            KnownKey::Unknown => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod classify_key {
+        use super::*;
+
+        #[cfg(feature = "descript")]
+        use crate::descript::DescriptFields;
+
+        /// Every known key with a value its parser accepts.
+        const KNOWN: &[(&str, &str)] = &[
+            ("charset", "UTF-8"),
+            ("shiori", "yaya.dll"),
+            ("shiori.version", "3.0"),
+            ("sstp.allowunspecifiedsend", "1"),
+            ("sstp.alwaystranslate", "0"),
+            ("seriko.use_self_alpha", "1"),
+            ("sakura.name", "a"),
+            ("kero.name2", "b"),
+            ("char2.name", "c"),
+            ("sakura.balloon.offsetx", "10"),
+            ("kero.balloon.offsety", "-10"),
+            ("char3.balloon.alignment", "left"),
+            ("kero.seriko.defaultsurface", "10"),
+            ("sakura.bindgroup10.name", "Hat,Ribbon"),
+            ("kero.bindgroup0.default", "1"),
+            ("sakura.cursor.mouseup0", "Head,head.cur"),
+            ("char2.cursor.mousedown1", "Bust,bust.cur"),
+        ];
+
+        #[test]
+        fn success_when_known_keys_round_trip() {
+            for (key, _) in KNOWN.iter() {
+                let known = classify_key(key);
+                assert_ne!(known, KnownKey::Unknown, "{}", key);
+                assert_eq!(known.to_string(), *key);
+            }
+        }
+
+        #[test]
+        #[cfg(feature = "descript")]
+        fn success_when_known_keys_applied() {
+            for (key, value) in KNOWN.iter() {
+                let mut fields = DescriptFields::default();
+                assert_eq!(fields.apply(key, value), Ok(true), "{}", key);
+            }
+        }
+
+        #[test]
+        fn ignored_when_unknown_keys() {
+            for key in [
+                "homeurl",
+                "Charset",
+                "char.name",
+                "char2x.name",
+                "sakura.balloon",
+                "sakura.bindgroupx.name",
+                "sakura.bindgroup1.thumbnail",
+                "sakura.cursor.mouseover0",
+                "sakura.cursor.mouseup",
+                "seriko.alignmenttodesktop",
+            ] {
+                assert_eq!(classify_key(key), KnownKey::Unknown, "{}", key);
+                #[cfg(feature = "descript")]
+                assert_eq!(DescriptFields::default().apply(key, "1"), Ok(false));
+            }
+        }
+    }
+
+    mod value_kind {
+        use super::*;
+
+        #[test]
+        fn success_when_keys_of_descript_and_blocks() {
+            for (key, expected) in [
+                ("shiori", ValueKind::Filename),
+                ("sakura.cursor.mouseup0", ValueKind::Filename),
+                ("element0", ValueKind::Filename),
+                ("menu.sidebar.bitmap.filename", ValueKind::Filename),
+                ("charset", ValueKind::Identifier),
+                ("collision1", ValueKind::Identifier),
+                ("collisionex0", ValueKind::Identifier),
+                ("sakura.name", ValueKind::Text),
+                ("tooltip.Head", ValueKind::Text),
+                ("homeurl", ValueKind::Text),
+                ("elements", ValueKind::Text),
+            ] {
+                assert_eq!(value_kind(key), expected, "{}", key);
+            }
+        }
+    }
+}
@@ -0,0 +1,371 @@
+//! # `kv`
+//!
+//! `KeyValue` and parser for it. Key-value is the basic line syntax, `key,value`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::kv::parse_key_value;
+//!
+//! let (remain, kv) = parse_key_value("sakura.balloon.offsetx,10\r\n").unwrap();
+//!
+//! assert_eq!(remain, "\r\n");
+//! assert_eq!(kv.key, "sakura.balloon.offsetx");
+//! assert_eq!(kv.value, "10");
+//! assert_eq!(kv.key_segments(), vec!["sakura", "balloon", "offsetx"]);
+//! ```
+
+#[cfg(feature = "kv")]
+use alloc::vec::Vec;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{take_till, take_till1},
+    character::complete::char,
+    combinator::{map, verify},
+    error::context,
+    IResult,
+};
+#[cfg(feature = "kv")]
+use nom::{bytes::complete::tag, combinator::all_consuming, sequence::preceded, Parser};
+
+#[cfg(feature = "kv")]
+use crate::value::parse_u32;
+use crate::{
+    options::{is_control_char, BareKeyPolicy, EmptyValuePolicy, ParseOptions},
+    value::separator,
+    ShellParseError,
+};
+
+#[cfg(feature = "kv")]
+mod entry_map;
+
+#[cfg(feature = "kv")]
+pub use entry_map::{EntryMap, NormalizedKey};
+
+/// Key and value borrowed from the input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyValue<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+}
+
+impl<'a> KeyValue<'a> {
+    #[cfg(feature = "kv")]
+    pub fn new(key: &'a str, value: &'a str) -> KeyValue<'a> {
+        KeyValue { key, value }
+    }
+
+    /// Splits the key by `.`.
+    #[cfg(feature = "kv")]
+    pub fn key_segments(&self) -> Vec<&'a str> {
+        self.key.split('.').collect()
+    }
+}
+
+/// parser for [`KeyValue`].
+///
+/// Key is until the first separator, without control characters other than tab, such as NUL.
+/// Value is the rest of the line.
+/// The line ending is not consumed.
+///
+/// [`KeyValue`]: crate::kv::KeyValue
+#[cfg(feature = "kv")]
+pub fn parse_key_value(input: &str) -> IResult<&str, KeyValue<'_>, ShellParseError<'_>> {
+    let key = take_till1(|c| matches!(c, ',' | '\r' | '\n') || is_control_char(c));
+    let (input, (keys, value)) = context("kv", fields_then_rest(1, key))(input)?;
+
+    Ok((
+        input,
+        KeyValue {
+            key: keys[0],
+            value,
+        },
+    ))
+}
+
+/// parser for `n` fields parsed by `field`, each followed by a separator, and the rest of the line.
+///
+/// The rest is taken verbatim, with its commas and whitespace, and may be empty.
+/// Fails when fewer than `n` fields are given. The line ending is not consumed.
+///
+/// ```
+/// use shell_parser_common_rs::kv::fields_then_rest;
+/// use shell_parser_common_rs::value::parse_i32;
+///
+/// let (remain, (fields, rest)) = fields_then_rest(2, parse_i32)("10,20,a, b \r\n").unwrap();
+///
+/// assert_eq!(remain, "\r\n");
+/// assert_eq!(fields, vec![10, 20]);
+/// assert_eq!(rest, "a, b ");
+/// ```
+#[cfg(feature = "kv")]
+pub fn fields_then_rest<'a, O, F>(
+    n: usize,
+    mut field: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, (Vec<O>, &'a str), ShellParseError<'a>>
+where
+    F: Parser<&'a str, O, ShellParseError<'a>>,
+{
+    move |mut input| {
+        let mut fields = Vec::with_capacity(n);
+        for _ in 0..n {
+            let (remain, value) = field.parse(input)?;
+            let (remain, _) = separator(remain)?;
+            fields.push(value);
+            input = remain;
+        }
+        let (input, rest) = take_till(|c| c == '\r' || c == '\n')(input)?;
+
+        Ok((input, (fields, rest)))
+    }
+}
+
+/// Same as [`parse_key_value`], with the lenient behaviors of `options`.
+///
+/// The key and the value are still borrowed from the input, without whitespace or comments dropped by `options`.
+/// A line without a separator is a key with the empty value unless [`ParseOptions::bare_key`] is [`BareKeyPolicy::Error`],
+/// and an empty value fails when [`ParseOptions::empty_value`] is [`EmptyValuePolicy::Error`].
+///
+/// [`parse_key_value`]: crate::kv::parse_key_value
+/// [`ParseOptions::bare_key`]: crate::options::ParseOptions::bare_key
+/// [`BareKeyPolicy::Error`]: crate::options::BareKeyPolicy::Error
+/// [`ParseOptions::empty_value`]: crate::options::ParseOptions::empty_value
+/// [`EmptyValuePolicy::Error`]: crate::options::EmptyValuePolicy::Error
+pub fn parse_key_value_with<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, KeyValue<'a>, ShellParseError<'a>> {
+    let key_end = if options.tab_separator {
+        ",\t\r\n"
+    } else {
+        ",\r\n"
+    };
+    let trim = |text: &'a str| {
+        let text = if options.trailing_comments {
+            strip_trailing_comment(text)
+        } else {
+            text
+        };
+        if options.trim_whitespace {
+            text.trim()
+        } else {
+            text
+        }
+    };
+    let pair = |input: &'a str| {
+        let (input, key) = take_till1(|c| key_end.contains(c) || is_control_char(c))(input)?;
+        let (input, value) = match separator_of(input, options) {
+            Ok((input, _)) => map(take_till(|c| c == '\r' || c == '\n'), trim)(input)?,
+            Err(_) if options.bare_key != BareKeyPolicy::Error && at_line_end(input) => {
+                return Ok((input, (trim(key), &input[..0], true)));
+            }
+            Err(e) => return Err(e),
+        };
+        let key = if options.trim_whitespace {
+            key.trim()
+        } else {
+            key
+        };
+
+        Ok((input, (key, value, false)))
+    };
+
+    let (input, (key, value, _)) = context(
+        "kv",
+        verify(pair, |(key, value, bare): &(&str, &str, bool)| {
+            !key.is_empty()
+                && (*bare || !value.is_empty() || options.empty_value != EmptyValuePolicy::Error)
+        }),
+    )(input)?;
+
+    Ok((input, KeyValue { key, value }))
+}
+
+/// Checks whether `text`, parsed by [`parse_key_value_with`], is a key without a separator.
+///
+/// [`parse_key_value_with`]: crate::kv::parse_key_value_with
+#[cfg(feature = "document")]
+pub(crate) fn is_bare_key(text: &str, options: &ParseOptions) -> bool {
+    !(text.contains(',') || options.tab_separator && text.contains('\t'))
+}
+
+fn separator_of<'a>(
+    input: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, char, ShellParseError<'a>> {
+    if options.tab_separator {
+        alt((separator, char('\t')))(input)
+    } else {
+        separator(input)
+    }
+}
+
+fn at_line_end(input: &str) -> bool {
+    input.is_empty() || input.starts_with(['\r', '\n'])
+}
+
+/// Drops `//` after whitespace to the end, with the whitespace before it.
+///
+/// `//` not after whitespace, such as in URLs, is kept.
+pub(crate) fn strip_trailing_comment(value: &str) -> &str {
+    value
+        .match_indices("//")
+        .find(|(i, _)| value[..*i].ends_with([' ', '\t']))
+        .map_or(value, |(i, _)| value[..i].trim_end())
+}
+
+/// Returns the index of an indexed key segment, such as `3` of `element3` with `prefix` `"element"`.
+#[cfg(feature = "kv")]
+pub fn segment_index(segment: &str, prefix: &str) -> Option<u32> {
+    all_consuming(preceded(tag(prefix), parse_u32))(segment)
+        .ok()
+        .map(|(_, id)| id)
+}
+
+#[cfg(all(test, feature = "kv"))]
+mod tests {
+    use super::*;
+
+    mod parse_key_value {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "element0,base,surface0.png,0,0\r\n";
+            let (remain, result) = parse_key_value(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, KeyValue::new("element0", "base,surface0.png,0,0"));
+        }
+
+        #[test]
+        fn success_when_empty_value() {
+            let case = "craftmanurl,\r\n";
+            let (remain, result) = parse_key_value(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, KeyValue::new("craftmanurl", ""));
+        }
+
+        #[test]
+        fn failed_when_no_separator() {
+            assert!(parse_key_value("homeurl\r\n").is_err());
+            assert!(parse_key_value(",value").is_err());
+        }
+    }
+
+    mod fields_then_rest {
+        use super::*;
+
+        use crate::value::parse_i32;
+
+        #[test]
+        fn success_when_rest_has_commas() {
+            let (remain, (fields, rest)) =
+                fields_then_rest(2, parse_i32)("1,-2,a,b,,\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(fields, vec![1, -2]);
+            assert_eq!(rest, "a,b,,");
+
+            let (_, (fields, rest)) = fields_then_rest(0, parse_i32)("a , b \t\n").unwrap();
+            assert!(fields.is_empty());
+            assert_eq!(rest, "a , b \t");
+        }
+
+        #[test]
+        fn failed_when_fields_missing() {
+            assert!(fields_then_rest(3, parse_i32)("1,2\r\n").is_err());
+            assert!(fields_then_rest(3, parse_i32)("1,2,3").is_err());
+            assert!(fields_then_rest(2, parse_i32)("1,x,rest").is_err());
+            let (_, (_, rest)) = fields_then_rest(3, parse_i32)("1,2,3,").unwrap();
+            assert_eq!(rest, "");
+        }
+    }
+
+    mod parse_key_value_with {
+        use super::*;
+
+        #[test]
+        fn success_when_strict() {
+            let case = "name , master // comment\r\n";
+            let (remain, result) = parse_key_value_with(case, &ParseOptions::STRICT).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, KeyValue::new("name ", " master // comment"));
+            assert!(parse_key_value_with("name\tmaster", &ParseOptions::STRICT).is_err());
+        }
+
+        #[test]
+        fn success_when_trim_whitespace() {
+            let options = ParseOptions::STRICT.trim_whitespace(true);
+            let (_, result) = parse_key_value_with(" name , master \r\n", &options).unwrap();
+            assert_eq!(result, KeyValue::new("name", "master"));
+            assert!(parse_key_value_with("  ,master", &options).is_err());
+        }
+
+        #[test]
+        fn success_when_tab_separator() {
+            let options = ParseOptions::STRICT.tab_separator(true);
+            let (_, result) = parse_key_value_with("name\tmaster,x\r\n", &options).unwrap();
+            assert_eq!(result, KeyValue::new("name", "master,x"));
+            let (_, result) = parse_key_value_with("name,a\tb", &options).unwrap();
+            assert_eq!(result, KeyValue::new("name", "a\tb"));
+        }
+
+        #[test]
+        fn success_when_trailing_comments() {
+            let options = ParseOptions::STRICT.trailing_comments(true);
+            let (_, result) = parse_key_value_with("point.centerx,60 // eye", &options).unwrap();
+            assert_eq!(result, KeyValue::new("point.centerx", "60"));
+            let (_, result) = parse_key_value_with("homeurl,http://a/b", &options).unwrap();
+            assert_eq!(result, KeyValue::new("homeurl", "http://a/b"));
+        }
+
+        #[test]
+        fn success_when_empty_value_policy() {
+            for policy in [EmptyValuePolicy::Keep, EmptyValuePolicy::Warn] {
+                let options = ParseOptions::STRICT.empty_value(policy);
+                let (remain, result) = parse_key_value_with("craftmanurl,\r\n", &options).unwrap();
+                assert_eq!(remain, "\r\n");
+                assert_eq!(result, KeyValue::new("craftmanurl", ""));
+            }
+
+            let options = ParseOptions::COMPATIBLE.empty_value(EmptyValuePolicy::Error);
+            assert!(parse_key_value_with("craftmanurl,\r\n", &options).is_err());
+            assert!(parse_key_value_with("craftmanurl , // none", &options).is_err());
+            let (_, result) = parse_key_value_with("homeurl", &options).unwrap();
+            assert_eq!(result, KeyValue::new("homeurl", ""));
+        }
+
+        #[test]
+        fn success_when_bare_key_policy() {
+            for policy in [BareKeyPolicy::TreatAsEmptyValue, BareKeyPolicy::Warn] {
+                let options = ParseOptions::COMPATIBLE.bare_key(policy);
+                let (remain, result) = parse_key_value_with("homeurl\r\n", &options).unwrap();
+                assert_eq!(remain, "\r\n");
+                assert_eq!(result, KeyValue::new("homeurl", ""));
+                let (_, result) = parse_key_value_with(" homeurl // none", &options).unwrap();
+                assert_eq!(result, KeyValue::new("homeurl", ""));
+                let (_, result) = parse_key_value_with("craftmanurl,", &options).unwrap();
+                assert_eq!(result, KeyValue::new("craftmanurl", ""));
+            }
+
+            let options = ParseOptions::COMPATIBLE.bare_key(BareKeyPolicy::Error);
+            assert!(parse_key_value_with("homeurl\r\n", &options).is_err());
+            assert!(parse_key_value_with("homeurl", &ParseOptions::STRICT).is_err());
+            let (_, result) = parse_key_value_with("craftmanurl,", &options).unwrap();
+            assert_eq!(result, KeyValue::new("craftmanurl", ""));
+        }
+    }
+
+    mod segment_index {
+        use super::*;
+
+        #[test]
+        fn success_when_indexed() {
+            assert_eq!(segment_index("element12", "element"), Some(12));
+            assert_eq!(segment_index("element", "element"), None);
+            assert_eq!(segment_index("element1x", "element"), None);
+            assert_eq!(segment_index("collisionex1", "collision"), None);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! `NormalizedKey` and `EntryMap`, looking up entries ignoring ASCII case of keys.
+
+use alloc::{borrow::Cow, collections::BTreeMap, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::{
+    ascii::{cmp_ignore_case, eq_ignore_case, hash_ignore_case},
+    error::Located,
+};
+
+use super::KeyValue;
+
+/// Key compared ignoring ASCII case, keeping its original spelling.
+///
+/// `Eq`, `Ord` and `Hash` are over the key with ASCII lowercased, so `Charset` equals `charset`.
+/// They are by the functions of [`ascii`], so that neither creating nor comparing keys allocates.
+///
+/// [`ascii`]: crate::ascii
+#[derive(Debug, Clone, Copy)]
+pub struct NormalizedKey<'a> {
+    original: &'a str,
+}
+
+impl<'a> NormalizedKey<'a> {
+    pub fn new(original: &'a str) -> NormalizedKey<'a> {
+        NormalizedKey { original }
+    }
+
+    /// Returns the key as written.
+    pub fn original(&self) -> &'a str {
+        self.original
+    }
+
+    /// Returns the key with ASCII lowercased, allocating only when it has ASCII uppercase letters.
+    pub fn normalized(&self) -> Cow<'a, str> {
+        if self.original.bytes().any(|b| b.is_ascii_uppercase()) {
+            Cow::Owned(self.original.to_ascii_lowercase())
+        } else {
+            Cow::Borrowed(self.original)
+        }
+    }
+}
+
+impl PartialEq for NormalizedKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        eq_ignore_case(self.original, other.original)
+    }
+}
+
+impl Eq for NormalizedKey<'_> {}
+
+impl PartialOrd for NormalizedKey<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NormalizedKey<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_ignore_case(self.original, other.original)
+    }
+}
+
+impl Hash for NormalizedKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_ignore_case(self.original, state);
+    }
+}
+
+impl fmt::Display for NormalizedKey<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.original)
+    }
+}
+
+/// Entries looked up by key ignoring ASCII case, as baseware does.
+///
+/// A key given more than once is looked up as its last occurrence, which is in effect.
+/// Iteration yields every entry in order, with keys as written.
+///
+/// ```
+/// use shell_parser_common_rs::error::Located;
+/// use shell_parser_common_rs::kv::{EntryMap, KeyValue};
+///
+/// let map: EntryMap = [
+///     Located::new(1, KeyValue::new("Charset", "UTF-8")),
+///     Located::new(2, KeyValue::new("sakura.balloon.offsetX", "10")),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// assert_eq!(map.get("charset").unwrap().value.key, "Charset");
+/// assert_eq!(map.get_path(&["sakura", "balloon", "offsetx"]).unwrap().line, 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct EntryMap<'a> {
+    entries: Vec<Located<KeyValue<'a>>>,
+    /// Index in `entries` of the last occurrence of each key.
+    index: BTreeMap<NormalizedKey<'a>, usize>,
+}
+
+impl<'a> EntryMap<'a> {
+    pub fn new() -> EntryMap<'a> {
+        EntryMap::default()
+    }
+
+    /// Appends `entry`, which takes effect over earlier entries of the same key.
+    pub fn insert(&mut self, entry: Located<KeyValue<'a>>) {
+        self.index
+            .insert(NormalizedKey::new(entry.value.key), self.entries.len());
+        self.entries.push(entry);
+    }
+
+    /// Returns the entry in effect of `key`, in any casing.
+    pub fn get(&self, key: &str) -> Option<&Located<KeyValue<'a>>> {
+        self.index
+            .get(&NormalizedKey::new(key))
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Returns the entry in effect of the key `segments` joined by `.`, in any casing.
+    pub fn get_path(&self, segments: &[&str]) -> Option<&Located<KeyValue<'a>>> {
+        self.get(&segments.join("."))
+    }
+
+    /// Checks whether `key` is given, in any casing.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(&NormalizedKey::new(key))
+    }
+
+    /// Returns the number of distinct keys.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns all entries in order, with keys as written.
+    pub fn iter(&self) -> impl Iterator<Item = &Located<KeyValue<'a>>> {
+        self.entries.iter()
+    }
+}
+
+impl<'a> FromIterator<Located<KeyValue<'a>>> for EntryMap<'a> {
+    fn from_iter<T: IntoIterator<Item = Located<KeyValue<'a>>>>(iter: T) -> Self {
+        let mut map = EntryMap::new();
+        for entry in iter {
+            map.insert(entry);
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod normalized_key {
+        use super::*;
+
+        #[test]
+        fn success_when_cases_differ() {
+            let key = NormalizedKey::new("Sakura.Balloon.OffsetX");
+            assert_eq!(key, NormalizedKey::new("sakura.balloon.offsetx"));
+            assert_ne!(key, NormalizedKey::new("sakura.balloon.offsety"));
+            assert_eq!(key.original(), "Sakura.Balloon.OffsetX");
+            assert_eq!(key.normalized(), "sakura.balloon.offsetx");
+
+            // Non-ASCII letters are compared as they are.
+            assert_ne!(NormalizedKey::new("Ａ"), NormalizedKey::new("ａ"));
+        }
+    }
+
+    mod entry_map {
+        use super::*;
+
+        fn map<'a>(case: &[(&'a str, &'a str)]) -> EntryMap<'a> {
+            case.iter()
+                .enumerate()
+                .map(|(i, (k, v))| Located::new(i + 1, KeyValue::new(k, v)))
+                .collect()
+        }
+
+        #[test]
+        fn success_when_mixed_case_lookup() {
+            let map = map(&[("Charset", "UTF-8"), ("name", "a"), ("NAME", "b")]);
+            assert_eq!(map.get("charset").unwrap().line, 1);
+            assert_eq!(map.get("CHARSET").unwrap().line, 1);
+            assert_eq!(map.get("Name").unwrap().value, KeyValue::new("NAME", "b"));
+            assert!(map.contains_key("nAmE"));
+            assert!(map.get("craftman").is_none());
+            assert_eq!(map.len(), 2);
+        }
+
+        #[test]
+        fn success_when_original_spellings_kept() {
+            let map = map(&[("Charset", "UTF-8"), ("name", "a"), ("NAME", "b")]);
+            let keys: Vec<_> = map.iter().map(|e| e.value.key).collect();
+            assert_eq!(keys, vec!["Charset", "name", "NAME"]);
+        }
+
+        #[test]
+        fn success_when_path_lookup_across_scopes() {
+            let map = map(&[
+                ("sakura.balloon.offsetx", "10"),
+                ("Kero.Balloon.OffsetX", "20"),
+                ("char2.balloon.offsetx", "30"),
+            ]);
+            let value = |segments: &[&str]| map.get_path(segments).map(|e| e.value.value);
+            assert_eq!(value(&["sakura", "balloon", "offsetx"]), Some("10"));
+            assert_eq!(value(&["kero", "balloon", "offsetx"]), Some("20"));
+            assert_eq!(value(&["CHAR2", "balloon", "offsetX"]), Some("30"));
+            assert_eq!(value(&["char3", "balloon", "offsetx"]), None);
+            assert_eq!(value(&["balloon", "offsetx"]), None);
+        }
+    }
+}
@@ -1,8 +1,224 @@
 //! Parsing utility for shell settings on Ukagaka.
+//!
+//! Without the default `std` feature, the crate is `no_std` and needs the `alloc` feature.
+//!
+//! ## Features
+//!
+//! Modules are compiled by features of the settings files they read, all enabled by the default `full`.
+//! With only `charset`, the crate exposes [`charset`] and [`error`]. `kv` adds entries and values,
+//! `block` and `document` add blocks and documents, and `seriko`, `surfaces`, `descript`, `balloon`
+//! and `io` add each module. See the features of `README.md` for their dependencies.
+//!
+//! ## Stability
+//!
+//! Enums of the vocabulary of shells, such as [`DrawMethod`], [`Pattern`], [`Interval`] and [`LineKind`],
+//! and diagnostics such as [`Warning`] are `#[non_exhaustive]`, so that variants are added later
+//! without breaking downstream code. Where parsing must not fail, words not known by this crate
+//! are kept by `Unknown` variants, such as [`AnimationOption::Unknown`] and [`LineKind::Unknown`].
+//! Match them with a wildcard arm.
+//!
+//! ```
+//! use shell_parser_common_rs::seriko::{parse_pattern, DrawMethod, Pattern};
+//!
+//! fn blends(pattern: &Pattern) -> bool {
+//!     match pattern {
+//!         Pattern::Draw { method, .. } => matches!(method, DrawMethod::Overlay | DrawMethod::Add),
+//!         _ => false,
+//!     }
+//! }
+//!
+//! let (_, pattern) = parse_pattern("overlay,10,50,0,0").unwrap();
+//! assert!(blends(&pattern));
+//! ```
+//!
+//! Matching every variant without a wildcard does not compile.
+//!
+//! ```compile_fail,E0004
+//! use shell_parser_common_rs::line::LineKind;
+//!
+//! fn is_entry(kind: LineKind) -> bool {
+//!     match kind {
+//!         LineKind::KeyValue => true,
+//!         LineKind::Blank
+//!         | LineKind::Comment
+//!         | LineKind::BlockHeader
+//!         | LineKind::BraceOpen
+//!         | LineKind::BraceClose
+//!         | LineKind::Unknown => false,
+//!     }
+//! }
+//! ```
+//!
+//! Options and settings, such as [`ParseOptions`], [`Limits`] and [`FormatStyle`], are also `#[non_exhaustive]`.
+//! Build them from a preset or the default, changing fields one by one.
+//!
+//! ```
+//! use shell_parser_common_rs::options::{Limits, ParseOptions};
+//!
+//! let options = ParseOptions::strict()
+//!     .trim_whitespace(true)
+//!     .limits(Limits::DEFAULT.max_block_lines(1000));
+//! assert_eq!(options.limits.max_block_lines, 1000);
+//! ```
+//!
+//! ## Iteration order
+//!
+//! Iteration over every collection of this crate is deterministic, so that reports and files written from them
+//! are reproducible. No hashed container is used.
+//!
+//! - Lists, such as elements of a surface, diagnostics and entries not known, are in the order of lines,
+//!   unless documented otherwise.
+//! - Maps and sets, such as animations and aliases, are sorted by their keys, IDs, scopes or names.
+//! - Files of [`load_shell_dir`] are in the documented order, regardless of the order of the directory listing.
+//!
+//! ## Serialization
+//!
+//! With the `serde` feature, parsed data, such as [`KeyValue`], [`Block`], [`Surface`], [`ShellFile`]
+//! and diagnostics, is `Serialize`, and values, such as [`Coordinate`] and [`DrawMethod`], are `Deserialize` as well.
+//! The scheme is stable:
+//!
+//! - Fields of structs keep their snake_case names, with `None` as `null`.
+//!   Maps keyed by IDs, such as animations and their patterns, are objects sorted by the IDs.
+//! - Values with a syntax of their own in files are strings of that syntax, read back by the parsers,
+//!   such as `"overlayfast"` of [`DrawMethod`], `"sometimes+talk,3"` of [`Intervals`], `"(1,3)"` of [`IdGroup`],
+//!   `"char2"` of [`Scope`] and `"Shift_JIS"` of [`Charset`], which is `"default"` for [`Charset::Default`].
+//! - Other enums without data are lowercase strings, snake_case for names of more than one word,
+//!   such as `"info"` of [`Severity`] and `"trailing_value_whitespace"` of [`WarningKind`].
+//! - Enums with data, such as [`Warning`], [`ParseFailure`] and [`CollisionExShape`],
+//!   are objects of the variant as `kind` and its data as `value`.
+//! - Patterns are objects of their `method`, with the fields of drawing, the `target` or the `group`.
+//! - Lines of blocks are `{"text", "span"}`, with the span as `{"start", "end", "line"}`,
+//!   where `start` and `end` are byte offsets of the text and `line` is the 1-based line number.
+//! - [`ShellFile`] is `{"charset", "entries", "comments", "blocks"}`, and the document of [`ParseOutcome`] is skipped.
+//!
+//! [`load_shell_dir`]: crate::project::load_shell_dir
+//! [`KeyValue`]: crate::kv::KeyValue
+//! [`Block`]: crate::block::Block
+//! [`Surface`]: crate::surfaces::Surface
+//! [`ShellFile`]: crate::document::ShellFile
+//! [`Coordinate`]: crate::value::Coordinate
+//! [`Intervals`]: crate::seriko::Intervals
+//! [`IdGroup`]: crate::seriko::IdGroup
+//! [`Scope`]: crate::scope::Scope
+//! [`Charset`]: crate::charset::Charset
+//! [`Charset::Default`]: crate::charset::Charset::Default
+//! [`Severity`]: crate::diagnostic::Severity
+//! [`WarningKind`]: crate::diagnostic::WarningKind
+//! [`ParseFailure`]: crate::error::ParseFailure
+//! [`CollisionExShape`]: crate::surfaces::CollisionExShape
+//! [`ParseOutcome`]: crate::diagnostic::ParseOutcome
+//! [`DrawMethod`]: crate::seriko::DrawMethod
+//! [`Pattern`]: crate::seriko::Pattern
+//! [`Interval`]: crate::seriko::Interval
+//! [`LineKind`]: crate::line::LineKind
+//! [`LineKind::Unknown`]: crate::line::LineKind::Unknown
+//! [`Warning`]: crate::diagnostic::Warning
+//! [`AnimationOption::Unknown`]: crate::seriko::AnimationOption::Unknown
+//! [`ParseOptions`]: crate::options::ParseOptions
+//! [`Limits`]: crate::options::Limits
+//! [`FormatStyle`]: crate::format::FormatStyle
 
-use nom::error::VerboseError;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+#[cfg(not(feature = "alloc"))]
+compile_error!("either the `std` or the `alloc` feature must be enabled");
+
+extern crate alloc;
+
+pub mod ascii;
+// Modules of `kv` used by `charset` and `error`, public with `kv`.
+#[cfg(feature = "kv")]
+pub mod kv;
+#[cfg(not(feature = "kv"))]
+mod kv;
+#[cfg(feature = "kv")]
+pub mod options;
+#[cfg(not(feature = "kv"))]
+mod options;
+#[cfg(feature = "kv")]
+pub mod span;
+#[cfg(feature = "kv")]
+pub mod text;
+#[cfg(not(feature = "kv"))]
+mod text;
+#[cfg(feature = "kv")]
+pub mod value;
+#[cfg(not(feature = "kv"))]
+mod value;
+
+#[cfg(feature = "kv")]
+pub mod arena;
+#[cfg(feature = "balloon")]
+pub mod balloon;
+#[cfg(feature = "descript")]
+pub mod binding;
+#[cfg(feature = "block")]
+pub mod block;
+#[cfg(all(feature = "surfaces", feature = "document"))]
+pub mod builder;
 pub mod charset;
+#[cfg(all(feature = "std", feature = "surfaces", feature = "document"))]
+pub mod compat;
+#[cfg(feature = "kv")]
+pub mod cursor;
+#[cfg(all(feature = "serde", feature = "kv"))]
+pub mod de;
+#[cfg(feature = "descript")]
+pub mod descript;
+#[cfg(feature = "kv")]
+pub mod diagnostic;
+#[cfg(feature = "document")]
+pub mod diff;
+#[cfg(feature = "document")]
+pub mod document;
+#[cfg(feature = "test-util")]
+pub mod dump;
+#[cfg(feature = "surfaces")]
+pub mod entry;
+pub mod error;
+#[cfg(all(feature = "surfaces", feature = "document"))]
+pub mod fingerprint;
+#[cfg(feature = "test-util")]
+pub mod fixture;
+#[cfg(feature = "document")]
+pub mod format;
+#[cfg(feature = "kv")]
+pub mod geometry;
+#[cfg(feature = "kv")]
+pub mod intern;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "kv")]
+pub mod keys;
+#[cfg(feature = "kv")]
+pub mod line;
+#[cfg(all(feature = "surfaces", feature = "document"))]
+pub mod lint;
+#[cfg(feature = "descript")]
+pub mod menu;
+#[cfg(feature = "kv")]
+pub mod path;
+#[cfg(all(feature = "std", feature = "surfaces", feature = "document"))]
+pub mod project;
+#[cfg(feature = "kv")]
+pub mod query;
+#[cfg(feature = "diagnostics")]
+pub mod report;
+#[cfg(feature = "kv")]
+pub mod scope;
+#[cfg(feature = "serde")]
+mod ser;
+#[cfg(feature = "seriko")]
+pub mod seriko;
+#[cfg(feature = "surfaces")]
+pub mod surfaces;
+#[cfg(all(test, feature = "kv"))]
+mod testing;
+#[cfg(feature = "descript")]
+pub mod tooltip;
+#[cfg(feature = "kv")]
+pub mod updates;
+#[cfg(feature = "kv")]
+pub mod writer;
 
-/// All-purpose Error type.
-pub type ShellParseError<'a> = VerboseError<&'a str>;
+pub use error::ShellParseError;
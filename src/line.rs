@@ -0,0 +1,546 @@
+//! # `line`
+//!
+//! Iterator over lines with line numbers and line endings.
+//! `\r\n`, `\n` and bare `\r` are all recognized as line endings.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::line::{lines, LineEnding};
+//!
+//! let mut iter = lines("charset,UTF-8\r\ndescript\n{");
+//!
+//! let first = iter.next().unwrap();
+//! assert_eq!(first.number, 1);
+//! assert_eq!(first.text, "charset,UTF-8");
+//! assert_eq!(first.ending, LineEnding::CrLf);
+//!
+//! assert_eq!(iter.next().unwrap().text, "descript");
+//! assert_eq!(iter.next().unwrap().ending, LineEnding::None);
+//! assert!(iter.next().is_none());
+//! ```
+
+#[cfg(feature = "block")]
+use core::ops::Range;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    error::Located,
+    kv::parse_key_value,
+    options::{Limit, LimitExceeded, ParseOptions},
+};
+
+/// Line ending of a [`Line`].
+///
+/// [`Line`]: crate::line::Line
+///
+/// The default is `\r\n`, which baseware expects.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum LineEnding {
+    #[default]
+    CrLf,
+    Lf,
+    Cr,
+    /// The last line without line ending.
+    None,
+}
+
+impl LineEnding {
+    /// Text of the line ending.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::CrLf => "\r\n",
+            LineEnding::Lf => "\n",
+            LineEnding::Cr => "\r",
+            LineEnding::None => "",
+        }
+    }
+}
+
+/// One line of the input.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Line<'a> {
+    /// 1-based line number.
+    pub number: usize,
+    /// Byte offset of the line in the input.
+    pub offset: usize,
+    /// Text without the line ending.
+    pub text: &'a str,
+    pub ending: LineEnding,
+}
+
+/// Checks whether `text` is a comment line, starting with `//` after leading whitespace.
+pub fn is_comment(text: &str) -> bool {
+    text.trim_start().starts_with("//")
+}
+
+/// Iterator created by [`lines`].
+///
+/// [`lines`]: crate::line::lines
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+    input: &'a str,
+    offset: usize,
+    end: usize,
+    number: usize,
+}
+
+/// Returns an iterator over lines of `input`.
+///
+/// An empty input yields no lines, and a trailing line ending does not yield an empty line.
+pub fn lines(input: &str) -> Lines<'_> {
+    Lines {
+        input,
+        offset: 0,
+        end: input.len(),
+        number: 0,
+    }
+}
+
+/// Returns an iterator over lines of `input` in `range`, the first of which is numbered `number`.
+///
+/// `range` starts at a line and ends at the end of a line, after its line ending.
+#[cfg(feature = "block")]
+pub(crate) fn lines_in(input: &str, range: Range<usize>, number: usize) -> Lines<'_> {
+    Lines {
+        input,
+        offset: range.start,
+        end: range.end,
+        number: number - 1,
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.end {
+            return None;
+        }
+
+        let rest = &self.input[self.offset..self.end];
+        let bytes = rest.as_bytes();
+        let (text, ending) = match memchr::memchr2(b'\r', b'\n', bytes) {
+            Some(i) => {
+                let ending = match (bytes[i], bytes.get(i + 1)) {
+                    (b'\r', Some(b'\n')) => LineEnding::CrLf,
+                    (b'\r', _) => LineEnding::Cr,
+                    _ => LineEnding::Lf,
+                };
+                (&rest[..i], ending)
+            }
+            None => (rest, LineEnding::None),
+        };
+
+        self.number += 1;
+        let line = Line {
+            number: self.number,
+            offset: self.offset,
+            text,
+            ending,
+        };
+        self.offset += text.len() + ending.as_str().len();
+
+        Some(line)
+    }
+}
+
+impl<'a> Lines<'a> {
+    /// Adapts the iterator to yield lines with their [`LineKind`].
+    ///
+    /// Unlike [`classify`], a line followed by `{` (skipping blank and comment lines)
+    /// is classified as [`LineKind::BlockHeader`], as [`parse_blocks`] does.
+    ///
+    /// [`LineKind`]: crate::line::LineKind
+    /// [`classify`]: crate::line::classify
+    /// [`LineKind::BlockHeader`]: crate::line::LineKind::BlockHeader
+    /// [`parse_blocks`]: crate::block::parse_blocks
+    pub fn classified(self) -> ClassifiedLines<'a> {
+        self.classified_with(&ParseOptions::STRICT)
+    }
+
+    /// Same as [`Lines::classified`], with the comment lines of [`ParseOptions::comment_prefixes`].
+    ///
+    /// [`Lines::classified`]: crate::line::Lines::classified
+    /// [`ParseOptions::comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+    pub fn classified_with(self, options: &ParseOptions) -> ClassifiedLines<'a> {
+        ClassifiedLines {
+            lines: self,
+            options: *options,
+        }
+    }
+
+    /// Adapts the iterator to check lines against [`ParseOptions::limits`],
+    /// yielding the first line over [`Limits::max_line_length`] or [`Limits::max_entries`] as an error and stopping.
+    ///
+    /// Entries are counted as [`classify_with`] recognizes them.
+    ///
+    /// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+    /// [`Limits::max_line_length`]: crate::options::Limits::max_line_length
+    /// [`Limits::max_entries`]: crate::options::Limits::max_entries
+    /// [`classify_with`]: crate::line::classify_with
+    pub fn limited(self, options: &ParseOptions) -> LimitedLines<'a> {
+        LimitedLines {
+            lines: self,
+            options: *options,
+            entries: 0,
+            exceeded: false,
+        }
+    }
+}
+
+/// Iterator created by [`Lines::limited`].
+///
+/// [`Lines::limited`]: crate::line::Lines::limited
+#[derive(Debug, Clone)]
+pub struct LimitedLines<'a> {
+    lines: Lines<'a>,
+    options: ParseOptions,
+    entries: usize,
+    exceeded: bool,
+}
+
+impl<'a> Iterator for LimitedLines<'a> {
+    type Item = Result<Line<'a>, Located<LimitExceeded>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exceeded {
+            return None;
+        }
+        let line = self.lines.next()?;
+        let limits = self.options.limits;
+
+        let exceeded = if line.text.len() > limits.max_line_length {
+            Some((Limit::LineLength, limits.max_line_length))
+        } else if classify_with(line.text, &self.options) == LineKind::KeyValue {
+            self.entries += 1;
+            (self.entries > limits.max_entries).then_some((Limit::Entries, limits.max_entries))
+        } else {
+            None
+        };
+
+        match exceeded {
+            Some((limit, max)) => {
+                self.exceeded = true;
+                Some(Err(Located::new(line.number, LimitExceeded { limit, max })))
+            }
+            None => Some(Ok(line)),
+        }
+    }
+}
+
+/// Kind of a raw line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum LineKind {
+    Blank,
+    /// Starting with `//` after leading whitespace.
+    Comment,
+    /// `key,value`
+    KeyValue,
+    /// Header of a block. `header {` on one line is also a header.
+    BlockHeader,
+    /// `{` alone.
+    BraceOpen,
+    /// `}` alone.
+    BraceClose,
+    /// Anything else, such as `}` followed by other text.
+    Unknown,
+}
+
+/// Classifies one line by itself, with the recognition rules of the parsers.
+///
+/// Without the following lines, a header on its own line is not distinguished
+/// from [`LineKind::KeyValue`] or [`LineKind::Unknown`]. Use [`Lines::classified`] for it.
+///
+/// [`LineKind::KeyValue`]: crate::line::LineKind::KeyValue
+/// [`LineKind::Unknown`]: crate::line::LineKind::Unknown
+/// [`Lines::classified`]: crate::line::Lines::classified
+pub fn classify(line: &str) -> LineKind {
+    classify_with(line, &ParseOptions::STRICT)
+}
+
+/// Same as [`classify`], with the comment lines of [`ParseOptions::comment_prefixes`].
+///
+/// Entries are recognized as [`classify`] does, without the other options.
+///
+/// [`classify`]: crate::line::classify
+/// [`ParseOptions::comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+pub fn classify_with(line: &str, options: &ParseOptions) -> LineKind {
+    let text = line.trim();
+
+    if text.is_empty() {
+        LineKind::Blank
+    } else if options.is_comment(text) {
+        LineKind::Comment
+    } else if text == "{" {
+        LineKind::BraceOpen
+    } else if text == "}" {
+        LineKind::BraceClose
+    } else if text.ends_with('{') {
+        LineKind::BlockHeader
+    } else if all_consuming(parse_key_value)(line).is_ok() {
+        LineKind::KeyValue
+    } else {
+        LineKind::Unknown
+    }
+}
+
+/// Iterator created by [`Lines::classified`].
+///
+/// [`Lines::classified`]: crate::line::Lines::classified
+#[derive(Debug, Clone)]
+pub struct ClassifiedLines<'a> {
+    lines: Lines<'a>,
+    options: ParseOptions,
+}
+
+impl<'a> Iterator for ClassifiedLines<'a> {
+    type Item = (Line<'a>, LineKind);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        let kind = match classify_with(line.text, &self.options) {
+            LineKind::KeyValue | LineKind::Unknown if self.followed_by_open() => {
+                LineKind::BlockHeader
+            }
+            kind => kind,
+        };
+
+        Some((line, kind))
+    }
+}
+
+impl ClassifiedLines<'_> {
+    fn followed_by_open(&self) -> bool {
+        self.lines
+            .clone()
+            .map(|l| classify_with(l.text, &self.options))
+            .find(|k| !matches!(k, LineKind::Blank | LineKind::Comment))
+            == Some(LineKind::BraceOpen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::XorShift;
+
+    mod lines {
+        use super::*;
+
+        #[test]
+        fn success_when_mixed_endings() {
+            let case = "a\r\nb\nc\rd";
+            let result: Vec<_> = lines(case).map(|l| (l.text, l.ending)).collect();
+            assert_eq!(
+                result,
+                vec![
+                    ("a", LineEnding::CrLf),
+                    ("b", LineEnding::Lf),
+                    ("c", LineEnding::Cr),
+                    ("d", LineEnding::None),
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_empty_lines() {
+            let case = "\r\n\r\nx\r\n";
+            let result: Vec<_> = lines(case).map(|l| (l.number, l.offset, l.text)).collect();
+            assert_eq!(result, vec![(1, 0, ""), (2, 2, ""), (3, 4, "x")]);
+        }
+
+        #[test]
+        fn success_when_empty_input() {
+            assert!(lines("").next().is_none());
+        }
+
+        /// Reference splitting, one character at a time.
+        fn reference(input: &str) -> Vec<Line<'_>> {
+            let mut result = Vec::new();
+            let mut start = 0;
+            let mut chars = input.char_indices().peekable();
+            while let Some((i, c)) = chars.next() {
+                let ending = match c {
+                    '\r' if chars.peek().map(|(_, c)| *c) == Some('\n') => {
+                        chars.next();
+                        LineEnding::CrLf
+                    }
+                    '\r' => LineEnding::Cr,
+                    '\n' => LineEnding::Lf,
+                    _ => continue,
+                };
+                result.push(Line {
+                    number: result.len() + 1,
+                    offset: start,
+                    text: &input[start..i],
+                    ending,
+                });
+                start = i + ending.as_str().len();
+            }
+            if start < input.len() {
+                result.push(Line {
+                    number: result.len() + 1,
+                    offset: start,
+                    text: &input[start..],
+                    ending: LineEnding::None,
+                });
+            }
+            result
+        }
+
+        #[test]
+        fn same_as_reference_property() {
+            const PIECES: &[&str] = &["a", "key,1", "\r", "\n", "\r\n", "\n\r", " ", "あ", "\r\r"];
+            let mut rng = XorShift::new(0x9e37_79b9);
+
+            for _ in 0..500 {
+                let case = rng.concat(PIECES, 40);
+                assert_eq!(
+                    lines(&case).collect::<Vec<_>>(),
+                    reference(&case),
+                    "{:?}",
+                    case
+                );
+            }
+        }
+    }
+
+    mod limited {
+        use super::*;
+
+        use alloc::{string::String, vec::Vec};
+
+        use crate::options::Limits;
+
+        fn options(f: impl FnOnce(&mut Limits)) -> ParseOptions {
+            let mut limits = Limits::DEFAULT;
+            f(&mut limits);
+            ParseOptions::STRICT.limits(limits)
+        }
+
+        #[test]
+        fn failed_when_line_too_long() {
+            let case = String::from("a,1\r\n") + &"x".repeat(65) + "\r\nb,2\r\n";
+            let options = options(|l| l.max_line_length = 64);
+            let result: Vec<_> = lines(&case).limited(&options).collect();
+
+            assert_eq!(result.len(), 2);
+            assert_eq!(
+                result[1],
+                Err(Located::new(
+                    2,
+                    LimitExceeded {
+                        limit: Limit::LineLength,
+                        max: 64
+                    }
+                ))
+            );
+            assert_eq!(
+                result[1].clone().unwrap_err().value.to_string(),
+                "line length over the limit of 64"
+            );
+        }
+
+        #[test]
+        fn failed_when_too_many_entries() {
+            let case = "a,1\r\n// c,1\r\n\r\nb,2\r\nc,3\r\n";
+            let options = options(|l| l.max_entries = 2);
+            let result: Vec<_> = lines(case).limited(&options).collect();
+
+            assert!(result[..4].iter().all(|l| l.is_ok()));
+            assert_eq!(
+                result[4],
+                Err(Located::new(
+                    5,
+                    LimitExceeded {
+                        limit: Limit::Entries,
+                        max: 2
+                    }
+                ))
+            );
+        }
+    }
+
+    mod is_comment {
+        use super::*;
+
+        #[test]
+        fn checks_leading_slashes() {
+            assert!(is_comment("// comment"));
+            assert!(is_comment("  //comment"));
+            assert!(!is_comment("element0,base,a//b.png,0,0"));
+        }
+    }
+
+    mod classified {
+        use super::*;
+
+        #[test]
+        fn success_when_fixture() {
+            let case = "charset,UTF-8\r\n\r\n// surfaces\r\nsurface0\r\n\r\n{\r\nelement0,base,surface0.png,0,0\r\n} junk\r\n}\r\nsurface1,surface2 {\r\nbroken line\r\n  }  \r\n";
+            let result: Vec<_> = lines(case).classified().map(|(_, k)| k).collect();
+            assert_eq!(
+                result,
+                vec![
+                    LineKind::KeyValue,
+                    LineKind::Blank,
+                    LineKind::Comment,
+                    LineKind::BlockHeader,
+                    LineKind::Blank,
+                    LineKind::BraceOpen,
+                    LineKind::KeyValue,
+                    LineKind::Unknown,
+                    LineKind::BraceClose,
+                    LineKind::BlockHeader,
+                    LineKind::Unknown,
+                    LineKind::BraceClose,
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_comment_prefixes() {
+            let case = "# note,x\r\n#surface0\r\n{\r\n}\r\n; a\r\n";
+            let kinds = |options: &ParseOptions| {
+                lines(case)
+                    .classified_with(options)
+                    .map(|(_, k)| k)
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(
+                kinds(&ParseOptions::STRICT),
+                vec![
+                    LineKind::KeyValue,
+                    LineKind::BlockHeader,
+                    LineKind::BraceOpen,
+                    LineKind::BraceClose,
+                    LineKind::Unknown,
+                ]
+            );
+            let options = ParseOptions::STRICT.comment_prefixes(&["//", "#"]).unwrap();
+            assert_eq!(
+                kinds(&options),
+                vec![
+                    LineKind::Comment,
+                    LineKind::Comment,
+                    LineKind::BraceOpen,
+                    LineKind::BraceClose,
+                    LineKind::Unknown,
+                ]
+            );
+        }
+
+        #[test]
+        fn header_needs_following_lines() {
+            assert_eq!(classify("surface0"), LineKind::Unknown);
+            assert_eq!(classify("surface0,surface1"), LineKind::KeyValue);
+        }
+    }
+}
@@ -0,0 +1,1384 @@
+//! # `lint`
+//!
+//! Checks for mistakes which baseware accepts silently.
+//!
+//! Each check returns values of its own, and has a pass of a `lint_` name streaming [`Warning`]s
+//! with a code, a severity and a line into a [`WarningSink`], such as a `Vec` or a [`SeverityFilter`].
+//!
+//! [`Warning`]: crate::lint::Warning
+//! [`WarningSink`]: crate::lint::WarningSink
+//! [`SeverityFilter`]: crate::lint::SeverityFilter
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::error::Located;
+//! use shell_parser_common_rs::kv::KeyValue;
+//! use shell_parser_common_rs::lint::find_duplicates;
+//!
+//! let entries = vec![
+//!     Located::new(1, KeyValue::new("name", "master")),
+//!     Located::new(2, KeyValue::new("name", "Master")),
+//! ];
+//! let reports = find_duplicates(&entries);
+//!
+//! assert_eq!(reports[0].lines, vec![1, 2]);
+//! assert!(reports[0].values_differ);
+//! assert_eq!(reports[0].winner, 2);
+//! ```
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    ascii::eq_ignore_case,
+    block::Block,
+    charset::{detect_charsets, detect_located, Charset, DEFAULT_SCAN_LIMIT},
+    diagnostic::{self, Severity},
+    document::{Document, ShellFile},
+    error::Located,
+    keys::value_kind,
+    kv::{parse_key_value, segment_index, KeyValue},
+    line::{classify, is_comment, lines, LineKind},
+    options::ParseOptions,
+    seriko::DrawMethod,
+    surfaces::{suggest_region, Element, Surface},
+    value::parse_u32,
+};
+
+mod warning;
+
+pub use warning::{codes, SeverityFilter, Warning, WarningSink};
+
+/// Key given more than once in one scope.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DuplicateReport {
+    pub key: String,
+    /// Line numbers of all occurrences, in order.
+    pub lines: Vec<usize>,
+    /// Whether any two occurrences have different values.
+    pub values_differ: bool,
+    /// Line number of the occurrence in effect, the last one.
+    pub winner: usize,
+}
+
+/// Prefixes of indexed keys of surface blocks, repeated in each block.
+const BLOCK_INDEXED_PREFIXES: [&str; 4] = ["element", "collisionex", "collision", "animation"];
+
+/// Segment of a key, compared by its index when it is indexed, such as `pattern0`.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum KeySegment<'a> {
+    Indexed(&'a str, u32),
+    Plain(&'a str),
+}
+
+/// Returns the segments of `key`, so that `element0` and `element00` are the same key.
+fn index_tuple(key: &str) -> Vec<KeySegment<'_>> {
+    key.split('.')
+        .map(|segment| {
+            let prefix = segment.trim_end_matches(|c: char| c.is_ascii_digit());
+            match segment_index(segment, prefix) {
+                Some(index) if !prefix.is_empty() => KeySegment::Indexed(prefix, index),
+                _ => KeySegment::Plain(segment),
+            }
+        })
+        .collect()
+}
+
+fn is_block_indexed(key: &str) -> bool {
+    let first = key.split('.').next().unwrap_or_default();
+    BLOCK_INDEXED_PREFIXES
+        .iter()
+        .any(|prefix| segment_index(first, prefix).is_some())
+}
+
+/// Finds keys given more than once in `entries`, in order of their first occurrence.
+///
+/// `entries` are taken as one scope, such as the top level of a file.
+/// Keys are compared by the indexes of their segments found by [`segment_index`],
+/// so that `element0` and `element00` are the same key.
+/// Indexed keys of surface blocks, such as `element0` and `animation1.pattern0`,
+/// are repeated in each block and not reported. Use [`find_block_duplicates`] for the bodies of blocks.
+///
+/// [`segment_index`]: crate::kv::segment_index
+/// [`find_block_duplicates`]: crate::lint::find_block_duplicates
+pub fn find_duplicates(entries: &[Located<KeyValue<'_>>]) -> Vec<DuplicateReport> {
+    let scoped: Vec<_> = entries
+        .iter()
+        .filter(|entry| !is_block_indexed(entry.value.key))
+        .collect();
+    duplicates(&scoped)
+}
+
+fn duplicates(entries: &[&Located<KeyValue<'_>>]) -> Vec<DuplicateReport> {
+    let mut occurrences: BTreeMap<Vec<KeySegment<'_>>, Vec<&Located<KeyValue<'_>>>> =
+        BTreeMap::new();
+    for entry in entries.iter() {
+        occurrences
+            .entry(index_tuple(entry.value.key))
+            .or_default()
+            .push(entry);
+    }
+
+    let mut reports: Vec<_> = occurrences
+        .into_values()
+        .filter(|found| found.len() > 1)
+        .map(|found| DuplicateReport {
+            key: found[0].value.key.to_string(),
+            lines: found.iter().map(|e| e.line).collect(),
+            values_differ: found.iter().any(|e| e.value.value != found[0].value.value),
+            winner: found.last().map(|e| e.line).unwrap_or_default(),
+        })
+        .collect();
+    reports.sort_by_key(|r| r.lines[0]);
+
+    reports
+}
+
+/// Finds keys given more than once in the body of each block, in order.
+///
+/// Keys are compared as [`find_duplicates`] does, with indexed keys, but not across blocks.
+/// Lines which are not entries are skipped.
+///
+/// [`find_duplicates`]: crate::lint::find_duplicates
+pub fn find_block_duplicates(blocks: &[Block<'_>]) -> Vec<DuplicateReport> {
+    blocks
+        .iter()
+        .flat_map(|block| {
+            let entries: Vec<_> = block
+                .body
+                .iter()
+                .filter(|line| !is_comment(line.text))
+                .filter_map(|line| {
+                    all_consuming(parse_key_value)(line.text)
+                        .ok()
+                        .map(|(_, kv)| Located::new(line.number, kv))
+                })
+                .collect();
+            duplicates(&entries.iter().collect::<Vec<_>>())
+        })
+        .collect()
+}
+
+/// Where the `charset` line must be, as baseware differ in it.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CharsetPositionPolicy {
+    /// The first line of the file.
+    FirstLine,
+    /// Before any line other than blank lines and comments.
+    FirstEffectiveLine,
+    /// Any line.
+    Anywhere,
+}
+
+/// Checks the position of the `charset` line of `file` against `policy`.
+///
+/// Returns [`Warning::MisplacedCharset`] at the `charset` line, with the kind of the first line preceding it
+/// which `policy` does not allow, or `None` when it is allowed or the file has no `charset` line.
+///
+/// [`Warning::MisplacedCharset`]: crate::diagnostic::Warning::MisplacedCharset
+pub fn check_charset_position(
+    file: &ShellFile,
+    policy: CharsetPositionPolicy,
+) -> Option<Located<diagnostic::Warning>> {
+    let directive = file
+        .entries()
+        .into_iter()
+        .find(|e| e.value.key == "charset")?
+        .line;
+
+    let mut preceding = lines(file.text())
+        .take_while(|line| line.number < directive)
+        .map(|line| classify(line.text));
+    let kind = match policy {
+        CharsetPositionPolicy::FirstLine => preceding.next(),
+        CharsetPositionPolicy::FirstEffectiveLine => {
+            preceding.find(|kind| !matches!(kind, LineKind::Blank | LineKind::Comment))
+        }
+        CharsetPositionPolicy::Anywhere => None,
+    }?;
+
+    Some(Located::new(
+        directive,
+        diagnostic::Warning::MisplacedCharset(kind),
+    ))
+}
+
+/// Checks whether raw `bytes` start with a UTF-8 byte order mark but name another charset in the `charset` line.
+///
+/// Such files cannot be decoded into a [`ShellFile`], so the bytes are examined as [`detect_charset`] does.
+/// Returns [`Warning::BomMismatch`] at the `charset` line.
+///
+/// [`ShellFile`]: crate::document::ShellFile
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`Warning::BomMismatch`]: crate::diagnostic::Warning::BomMismatch
+pub fn check_charset_bom(bytes: &[u8]) -> Option<Located<diagnostic::Warning>> {
+    if !bytes.starts_with(b"\xEF\xBB\xBF") {
+        return None;
+    }
+
+    let directive = detect_located(bytes, DEFAULT_SCAN_LIMIT, &ParseOptions::STRICT)?;
+    (directive.value != Charset::UTF8).then(|| directive.map(diagnostic::Warning::BomMismatch))
+}
+
+/// Suspicious layering of `element`s of one surface.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ElementWarning {
+    /// Indices from `missing_from` to before `index` are not given.
+    Gap { index: u32, missing_from: u32 },
+    /// `index` is given again. Baseware differ in which one is drawn.
+    Duplicate { index: u32 },
+    /// The first element is drawn by `method` onto nothing, not by `base` or `overlay`.
+    NonBaseFirst { index: u32, method: DrawMethod },
+    /// The element draws the same file at the same offset as the element `original`, likely copied by mistake.
+    SameImage { index: u32, original: u32 },
+}
+
+impl ElementWarning {
+    /// Returns the index of the element warned.
+    pub fn index(&self) -> u32 {
+        match self {
+            ElementWarning::Gap { index, .. }
+            | ElementWarning::Duplicate { index }
+            | ElementWarning::NonBaseFirst { index, .. }
+            | ElementWarning::SameImage { index, .. } => *index,
+        }
+    }
+}
+
+impl fmt::Display for ElementWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ElementWarning::Gap {
+                index,
+                missing_from,
+            } if index - missing_from == 1 => {
+                write!(f, "element{} without element{}", index, missing_from)
+            }
+            ElementWarning::Gap {
+                index,
+                missing_from,
+            } => write!(
+                f,
+                "element{} without element{} to element{}",
+                index,
+                missing_from,
+                index - 1
+            ),
+            ElementWarning::Duplicate { index } => write!(f, "element{} given again", index),
+            ElementWarning::NonBaseFirst { index, method } => {
+                write!(f, "first element{} drawn by {:?}", index, method)
+            }
+            ElementWarning::SameImage { index, original } => {
+                write!(f, "element{} same as element{}", index, original)
+            }
+        }
+    }
+}
+
+/// Checks the layering of `elements` of one surface, in order of their indices.
+///
+/// Indices are expected to be continuous from `0`.
+/// Filenames are compared ignoring ASCII case, as file systems of baseware do.
+/// Elements of the same index are only reported as [`ElementWarning::Duplicate`].
+///
+/// [`ElementWarning::Duplicate`]: crate::lint::ElementWarning::Duplicate
+pub fn check_elements(elements: &[Element]) -> Vec<ElementWarning> {
+    let mut sorted: Vec<_> = elements.iter().collect();
+    sorted.sort_by_key(|e| e.id);
+
+    let mut warnings = Vec::new();
+    let mut expected = 0;
+    for (i, element) in sorted.iter().enumerate() {
+        let index = element.id;
+        if i > 0 && sorted[i - 1].id == index {
+            warnings.push(ElementWarning::Duplicate { index });
+            continue;
+        }
+        if index > expected {
+            warnings.push(ElementWarning::Gap {
+                index,
+                missing_from: expected,
+            });
+        }
+        expected = index.saturating_add(1);
+
+        if i == 0 && !matches!(element.method, DrawMethod::Base | DrawMethod::Overlay) {
+            warnings.push(ElementWarning::NonBaseFirst {
+                index,
+                method: element.method,
+            });
+        }
+        let original = sorted[..i].iter().find(|other| {
+            other.offset == element.offset && eq_ignore_case(&other.filename, &element.filename)
+        });
+        if let Some(original) = original {
+            warnings.push(ElementWarning::SameImage {
+                index,
+                original: original.id,
+            });
+        }
+    }
+
+    warnings
+}
+
+/// Key superseded by another, in [`DEPRECATED_KEYS`].
+///
+/// In `pattern` and `replacement`, `N` stands for an index, such as `3` of `animation3`.
+/// Indices of a matched key are put into `replacement` in order.
+///
+/// [`DEPRECATED_KEYS`]: crate::lint::DEPRECATED_KEYS
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Deprecation {
+    pub pattern: &'static str,
+    pub replacement: &'static str,
+    pub severity: Severity,
+}
+
+/// Deprecated keys checked by [`check_deprecated`].
+///
+/// Extend it by chaining with more and passing them to [`check_deprecated_with`].
+///
+/// [`check_deprecated`]: crate::lint::check_deprecated
+/// [`check_deprecated_with`]: crate::lint::check_deprecated_with
+pub const DEPRECATED_KEYS: &[Deprecation] = &[
+    Deprecation {
+        pattern: "seriko.paint_transparent_region_black",
+        replacement: "seriko.use_self_alpha",
+        severity: Severity::Warning,
+    },
+    Deprecation {
+        pattern: "Ninterval",
+        replacement: "animationN.interval",
+        severity: Severity::Warning,
+    },
+    Deprecation {
+        pattern: "NpatternN",
+        replacement: "animationN.patternN",
+        severity: Severity::Warning,
+    },
+    Deprecation {
+        pattern: "Noption",
+        replacement: "animationN.option",
+        severity: Severity::Warning,
+    },
+];
+
+/// Deprecated key found by [`check_deprecated`].
+///
+/// [`check_deprecated`]: crate::lint::check_deprecated
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DeprecationWarning {
+    pub line: usize,
+    pub key: String,
+    /// Key to write instead, with the indices of `key`.
+    pub replacement: String,
+    pub severity: Severity,
+}
+
+impl fmt::Display for DeprecationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: {} is deprecated, use {}",
+            self.line, self.key, self.replacement
+        )
+    }
+}
+
+/// Finds keys of [`DEPRECATED_KEYS`] in `entries`, in order.
+///
+/// [`DEPRECATED_KEYS`]: crate::lint::DEPRECATED_KEYS
+pub fn check_deprecated(entries: &[Located<KeyValue<'_>>]) -> Vec<DeprecationWarning> {
+    check_deprecated_with(entries, DEPRECATED_KEYS)
+}
+
+/// Same as [`check_deprecated`], with keys of `table`. The first matching one is taken.
+///
+/// [`check_deprecated`]: crate::lint::check_deprecated
+pub fn check_deprecated_with(
+    entries: &[Located<KeyValue<'_>>],
+    table: &[Deprecation],
+) -> Vec<DeprecationWarning> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let segments = entry.value.key_segments();
+            table.iter().find_map(|deprecation| {
+                let indices = match_key(deprecation.pattern, &segments)?;
+                Some(DeprecationWarning {
+                    line: entry.line,
+                    key: entry.value.key.to_string(),
+                    replacement: fill_indices(deprecation.replacement, &indices),
+                    severity: deprecation.severity,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Returns the indices of `segments` in place of `N` of `pattern`, or `None` when they do not match.
+fn match_key(pattern: &str, segments: &[&str]) -> Option<Vec<u32>> {
+    if pattern.split('.').count() != segments.len() {
+        return None;
+    }
+
+    let mut indices = Vec::new();
+    for (pattern, segment) in pattern.split('.').zip(segments.iter()) {
+        let mut pieces = pattern.split('N');
+        let mut rest = segment.strip_prefix(pieces.next().unwrap_or_default())?;
+        for piece in pieces {
+            let (remain, index) = parse_u32(rest).ok()?;
+            indices.push(index);
+            rest = remain.strip_prefix(piece)?;
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+    }
+
+    Some(indices)
+}
+
+/// Puts `indices` into `N` of `replacement` in order. `N` without an index is kept.
+fn fill_indices(replacement: &str, indices: &[u32]) -> String {
+    let mut indices = indices.iter();
+    let mut result = String::new();
+    for (i, piece) in replacement.split('N').enumerate() {
+        if i > 0 {
+            match indices.next() {
+                Some(index) => result.push_str(&index.to_string()),
+                None => result.push('N'),
+            }
+        }
+        result.push_str(piece);
+    }
+    result
+}
+
+/// Finds values of `entries` ending with spaces or tabs, in order.
+///
+/// Each is [`Warning::TrailingValueWhitespace`] with the kind of the value by [`value_kind`],
+/// whose [`ValueKind::severity`] tells filenames and names, which then do not match, from text.
+///
+/// [`Warning::TrailingValueWhitespace`]: crate::diagnostic::Warning::TrailingValueWhitespace
+/// [`value_kind`]: crate::keys::value_kind
+/// [`ValueKind::severity`]: crate::keys::ValueKind::severity
+pub fn check_trailing_whitespace(
+    entries: &[Located<KeyValue<'_>>],
+) -> Vec<Located<diagnostic::Warning>> {
+    entries
+        .iter()
+        .filter(|entry| entry.value.value.ends_with([' ', '\t']))
+        .map(|entry| {
+            Located::new(
+                entry.line,
+                diagnostic::Warning::TrailingValueWhitespace(value_kind(entry.value.key)),
+            )
+        })
+        .collect()
+}
+
+/// Trims the values of `doc` flagged by [`Warning::TrailingValueWhitespace`] in `warnings`,
+/// returning the number of lines rewritten.
+///
+/// Other warnings are skipped, so filter `warnings` by severity to fix only some of them.
+/// Lines not flagged are kept byte-identical.
+///
+/// [`Warning::TrailingValueWhitespace`]: crate::diagnostic::Warning::TrailingValueWhitespace
+pub fn fix_trailing_whitespace(
+    doc: &mut Document,
+    warnings: &[Located<diagnostic::Warning>],
+) -> usize {
+    warnings
+        .iter()
+        .filter(|w| matches!(w.value, diagnostic::Warning::TrailingValueWhitespace(_)))
+        .filter(|w| doc.trim_value_end(w.line))
+        .count()
+}
+
+/// Streams the keys of [`find_duplicates`] into `sink` at the lines in effect, with [`codes::DUPLICATE_KEY`].
+///
+/// Duplicates of different values are [`Severity::Warning`], and those of the same value [`Severity::Info`].
+///
+/// [`find_duplicates`]: crate::lint::find_duplicates
+/// [`codes::DUPLICATE_KEY`]: crate::lint::codes::DUPLICATE_KEY
+/// [`Severity::Warning`]: crate::diagnostic::Severity::Warning
+/// [`Severity::Info`]: crate::diagnostic::Severity::Info
+pub fn lint_duplicates(entries: &[Located<KeyValue<'_>>], sink: &mut impl WarningSink) {
+    emit_duplicates(find_duplicates(entries), sink);
+}
+
+/// Same as [`lint_duplicates`], of the keys of [`find_block_duplicates`].
+///
+/// [`lint_duplicates`]: crate::lint::lint_duplicates
+/// [`find_block_duplicates`]: crate::lint::find_block_duplicates
+pub fn lint_block_duplicates(blocks: &[Block<'_>], sink: &mut impl WarningSink) {
+    emit_duplicates(find_block_duplicates(blocks), sink);
+}
+
+fn emit_duplicates(reports: Vec<DuplicateReport>, sink: &mut impl WarningSink) {
+    for report in reports {
+        let severity = if report.values_differ {
+            Severity::Warning
+        } else {
+            Severity::Info
+        };
+        let message = format!("{} given {} times", report.key, report.lines.len());
+        sink.emit(Warning::new(
+            codes::DUPLICATE_KEY,
+            severity,
+            report.winner,
+            message,
+        ));
+    }
+}
+
+/// Streams the warning of [`check_charset_position`] into `sink`, with [`codes::MISPLACED_CHARSET`].
+///
+/// [`check_charset_position`]: crate::lint::check_charset_position
+/// [`codes::MISPLACED_CHARSET`]: crate::lint::codes::MISPLACED_CHARSET
+pub fn lint_charset_position(
+    file: &ShellFile,
+    policy: CharsetPositionPolicy,
+    sink: &mut impl WarningSink,
+) {
+    if let Some(warning) = check_charset_position(file, policy) {
+        sink.emit(Warning::new(
+            codes::MISPLACED_CHARSET,
+            Severity::Warning,
+            warning.line,
+            warning.value.to_string(),
+        ));
+    }
+}
+
+/// Streams the warning of [`check_charset_bom`] into `sink`, with [`codes::BOM_MISMATCH`].
+///
+/// [`check_charset_bom`]: crate::lint::check_charset_bom
+/// [`codes::BOM_MISMATCH`]: crate::lint::codes::BOM_MISMATCH
+pub fn lint_charset_bom(bytes: &[u8], sink: &mut impl WarningSink) {
+    if let Some(warning) = check_charset_bom(bytes) {
+        sink.emit(Warning::new(
+            codes::BOM_MISMATCH,
+            Severity::Warning,
+            warning.line,
+            warning.value.to_string(),
+        ));
+    }
+}
+
+/// Streams `charset` lines of `bytes` after the first, found by [`detect_charsets`], into `sink`.
+///
+/// Lines naming other charsets are [`codes::CONFLICTING_CHARSET`] of [`Severity::Warning`],
+/// and those naming the same charset again [`codes::DUPLICATE_KEY`] of [`Severity::Info`], each with the first as the suggestion.
+/// When no line starts within [`DEFAULT_SCAN_LIMIT`] bytes, all lines are [`codes::CONFLICTING_CHARSET`],
+/// ignored as [`detect_charset`] does.
+///
+/// [`detect_charsets`]: crate::charset::detect_charsets
+/// [`DEFAULT_SCAN_LIMIT`]: crate::charset::DEFAULT_SCAN_LIMIT
+/// [`detect_charset`]: crate::charset::detect_charset
+/// [`codes::CONFLICTING_CHARSET`]: crate::lint::codes::CONFLICTING_CHARSET
+/// [`codes::DUPLICATE_KEY`]: crate::lint::codes::DUPLICATE_KEY
+/// [`Severity::Warning`]: crate::diagnostic::Severity::Warning
+/// [`Severity::Info`]: crate::diagnostic::Severity::Info
+pub fn lint_charset_directives(bytes: &[u8], sink: &mut impl WarningSink) {
+    let directives = detect_charsets(bytes);
+    let Some(used) = detect_located(bytes, DEFAULT_SCAN_LIMIT, &ParseOptions::STRICT) else {
+        for directive in directives {
+            sink.emit(Warning::new(
+                codes::CONFLICTING_CHARSET,
+                Severity::Warning,
+                directive.line,
+                format!(
+                    "charset {:?} ignored after the first {} bytes",
+                    directive.value, DEFAULT_SCAN_LIMIT
+                ),
+            ));
+        }
+        return;
+    };
+    // The line used is the first of all, as it starts within the limit.
+    let rest = &directives[1..];
+    let name = used.value.whatwg_name().unwrap_or("ASCII");
+    for directive in rest {
+        let warning = if directive.value == used.value {
+            Warning::new(
+                codes::DUPLICATE_KEY,
+                Severity::Info,
+                directive.line,
+                format!("charset given again after line {}", used.line),
+            )
+        } else {
+            Warning::new(
+                codes::CONFLICTING_CHARSET,
+                Severity::Warning,
+                directive.line,
+                format!(
+                    "charset {:?} ignored for {:?} of line {}",
+                    directive.value, used.value, used.line
+                ),
+            )
+        };
+        sink.emit(warning.suggestion(format!("charset,{}", name)));
+    }
+}
+
+/// Streams the warnings of [`check_elements`] into `sink` at `line`, the header of the surface,
+/// as elements have no lines of their own.
+///
+/// Elements drawing the same image as another are [`Severity::Info`], and the others [`Severity::Warning`].
+///
+/// [`check_elements`]: crate::lint::check_elements
+/// [`Severity::Info`]: crate::diagnostic::Severity::Info
+/// [`Severity::Warning`]: crate::diagnostic::Severity::Warning
+pub fn lint_elements(elements: &[Element], line: usize, sink: &mut impl WarningSink) {
+    for warning in check_elements(elements) {
+        let (code, severity) = match warning {
+            ElementWarning::Gap { .. } => (codes::ELEMENT_GAP, Severity::Warning),
+            ElementWarning::Duplicate { .. } => (codes::ELEMENT_DUPLICATE, Severity::Warning),
+            ElementWarning::NonBaseFirst { .. } => {
+                (codes::ELEMENT_NON_BASE_FIRST, Severity::Warning)
+            }
+            ElementWarning::SameImage { .. } => (codes::ELEMENT_SAME_IMAGE, Severity::Info),
+        };
+        sink.emit(Warning::new(code, severity, line, warning.to_string()));
+    }
+}
+
+/// Streams the keys of [`check_deprecated_with`] into `sink`, with [`codes::DEPRECATED_KEY`],
+/// the severity of the deprecation, and the replacement as the suggestion.
+///
+/// [`check_deprecated_with`]: crate::lint::check_deprecated_with
+/// [`codes::DEPRECATED_KEY`]: crate::lint::codes::DEPRECATED_KEY
+pub fn lint_deprecated(
+    entries: &[Located<KeyValue<'_>>],
+    table: &[Deprecation],
+    sink: &mut impl WarningSink,
+) {
+    for warning in check_deprecated_with(entries, table) {
+        sink.emit(
+            Warning::new(
+                codes::DEPRECATED_KEY,
+                warning.severity,
+                warning.line,
+                format!("{} is deprecated", warning.key),
+            )
+            .suggestion(warning.replacement),
+        );
+    }
+}
+
+/// Streams the values of [`check_trailing_whitespace`] into `sink`, with [`codes::TRAILING_WHITESPACE`],
+/// the severity of the kind of the value, and the trimmed value as the suggestion.
+///
+/// [`check_trailing_whitespace`]: crate::lint::check_trailing_whitespace
+/// [`codes::TRAILING_WHITESPACE`]: crate::lint::codes::TRAILING_WHITESPACE
+pub fn lint_trailing_whitespace(entries: &[Located<KeyValue<'_>>], sink: &mut impl WarningSink) {
+    for entry in entries
+        .iter()
+        .filter(|entry| entry.value.value.ends_with([' ', '\t']))
+    {
+        let kind = value_kind(entry.value.key);
+        let message = diagnostic::Warning::TrailingValueWhitespace(kind).to_string();
+        sink.emit(
+            Warning::new(
+                codes::TRAILING_WHITESPACE,
+                kind.severity(),
+                entry.line,
+                message,
+            )
+            .suggestion(entry.value.value.trim_end_matches([' ', '\t'])),
+        );
+    }
+}
+
+/// Streams names of collisions and collisionexes of `surface` near a known region into `sink` at `line`,
+/// the header of the surface, with [`codes::REGION_NEAR_MISS`] and the canonical name as the suggestion.
+///
+/// Names of [`suggest_region`] only are flagged, so that custom names are never.
+///
+/// [`codes::REGION_NEAR_MISS`]: crate::lint::codes::REGION_NEAR_MISS
+/// [`suggest_region`]: crate::surfaces::suggest_region
+pub fn lint_regions(surface: &Surface, line: usize, sink: &mut impl WarningSink) {
+    let names = surface
+        .collisions
+        .iter()
+        .map(|c| c.name.as_str())
+        .chain(surface.collisionexes.iter().map(|c| c.name.as_str()));
+    for name in names {
+        if let Some(canonical) = suggest_region(name) {
+            sink.emit(
+                Warning::new(
+                    codes::REGION_NEAR_MISS,
+                    Severity::Warning,
+                    line,
+                    format!("{} is near the known region {}", name, canonical),
+                )
+                .suggestion(canonical),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{block::parse_blocks, document::parse_bytes};
+
+    mod find_duplicates {
+        use super::*;
+
+        fn located<'a>(case: &[(&'a str, &'a str)]) -> Vec<Located<KeyValue<'a>>> {
+            case.iter()
+                .enumerate()
+                .map(|(i, (k, v))| Located::new(i + 1, KeyValue::new(k, v)))
+                .collect()
+        }
+
+        #[test]
+        fn detected_when_exact_duplicate() {
+            let entries = located(&[("name", "master"), ("id", "x"), ("name", "master")]);
+            assert_eq!(
+                find_duplicates(&entries),
+                vec![DuplicateReport {
+                    key: "name".to_string(),
+                    lines: vec![1, 3],
+                    values_differ: false,
+                    winner: 3,
+                }]
+            );
+        }
+
+        #[test]
+        fn detected_when_differing_duplicate() {
+            let entries = located(&[
+                ("sakura.balloon.offsetx", "10"),
+                ("craftman", "a"),
+                ("craftman", "a"),
+                ("sakura.balloon.offsetx", "20"),
+                ("sakura.balloon.offsetx", "10"),
+            ]);
+            let reports = find_duplicates(&entries);
+            assert_eq!(reports.len(), 2);
+            assert_eq!(reports[0].key, "sakura.balloon.offsetx");
+            assert_eq!(reports[0].lines, vec![1, 4, 5]);
+            assert!(reports[0].values_differ);
+            assert_eq!(reports[0].winner, 5);
+            assert!(!reports[1].values_differ);
+        }
+
+        #[test]
+        fn detected_when_same_index() {
+            let entries = located(&[
+                ("sakura.bindgroup1.name", "a"),
+                ("sakura.bindgroup01.name", "b"),
+            ]);
+            let reports = find_duplicates(&entries);
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].key, "sakura.bindgroup1.name");
+            assert_eq!(reports[0].lines, vec![1, 2]);
+        }
+
+        #[test]
+        fn success_when_no_duplicate() {
+            let entries = located(&[("element0", "a"), ("element1", "a")]);
+            assert!(find_duplicates(&entries).is_empty());
+        }
+
+        #[test]
+        fn success_when_indexed_in_blocks() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,0,0\r\nanimation1.pattern0,overlay,5,50,0,0\r\n}\r\nsurface1\r\n{\r\nelement0,base,b.png,0,0\r\nanimation1.pattern0,overlay,6,50,0,0\r\n}\r\n";
+            let document = parse_bytes(case.as_bytes(), Charset::UTF8).unwrap();
+            let entries: Vec<_> = document.entries().collect();
+
+            assert_eq!(entries.len(), 4);
+            assert!(find_duplicates(&entries).is_empty());
+        }
+    }
+
+    mod check_charset_position {
+        use super::*;
+
+        const FIRST: &str = "charset,UTF-8\r\n// c\r\nname,a\r\n";
+        const AFTER_COMMENT: &str = "\r\n// c\r\ncharset,UTF-8\r\nname,a\r\n";
+        const AFTER_DATA: &str = "// c\r\nname,a\r\ncharset,UTF-8\r\n";
+
+        fn check(
+            case: &str,
+            policy: CharsetPositionPolicy,
+        ) -> Option<Located<diagnostic::Warning>> {
+            let file = ShellFile::parse(case.as_bytes(), Charset::UTF8).unwrap();
+            check_charset_position(&file, policy)
+        }
+
+        #[test]
+        fn success_when_first_line() {
+            assert_eq!(check(FIRST, CharsetPositionPolicy::FirstLine), None);
+            assert_eq!(
+                check(AFTER_COMMENT, CharsetPositionPolicy::FirstLine),
+                Some(Located::new(
+                    3,
+                    diagnostic::Warning::MisplacedCharset(LineKind::Blank)
+                ))
+            );
+            assert_eq!(
+                check(AFTER_DATA, CharsetPositionPolicy::FirstLine),
+                Some(Located::new(
+                    3,
+                    diagnostic::Warning::MisplacedCharset(LineKind::Comment)
+                ))
+            );
+        }
+
+        #[test]
+        fn success_when_first_effective_line() {
+            assert_eq!(
+                check(FIRST, CharsetPositionPolicy::FirstEffectiveLine),
+                None
+            );
+            assert_eq!(
+                check(AFTER_COMMENT, CharsetPositionPolicy::FirstEffectiveLine),
+                None
+            );
+            assert_eq!(
+                check(AFTER_DATA, CharsetPositionPolicy::FirstEffectiveLine),
+                Some(Located::new(
+                    3,
+                    diagnostic::Warning::MisplacedCharset(LineKind::KeyValue)
+                ))
+            );
+        }
+
+        #[test]
+        fn success_when_anywhere() {
+            for case in [FIRST, AFTER_COMMENT, AFTER_DATA, "name,a\r\n"] {
+                assert_eq!(check(case, CharsetPositionPolicy::Anywhere), None);
+            }
+            assert_eq!(check("name,a\r\n", CharsetPositionPolicy::FirstLine), None);
+        }
+    }
+
+    mod check_charset_bom {
+        use super::*;
+
+        #[test]
+        fn success_when_bom_mismatch() {
+            assert_eq!(
+                check_charset_bom(b"\xEF\xBB\xBF// c\r\ncharset,Shift_JIS\r\n"),
+                Some(Located::new(
+                    2,
+                    diagnostic::Warning::BomMismatch(Charset::ShiftJIS)
+                ))
+            );
+            assert_eq!(check_charset_bom(b"\xEF\xBB\xBFcharset,UTF-8\r\n"), None);
+            assert_eq!(check_charset_bom(b"charset,Shift_JIS\r\n"), None);
+        }
+    }
+
+    mod check_elements {
+        use super::*;
+
+        use crate::value::Coordinate;
+
+        fn element(id: u32, method: DrawMethod, filename: &str, x: i32) -> Element {
+            Element {
+                id,
+                method,
+                filename: filename.to_string(),
+                offset: Coordinate::new(x, 0),
+                alpha: None,
+            }
+        }
+
+        #[test]
+        fn success_when_clean() {
+            let elements = [
+                element(0, DrawMethod::Base, "body.png", 0),
+                element(1, DrawMethod::Overlay, "face.png", 0),
+                element(2, DrawMethod::Overlay, "face.png", 10),
+            ];
+            assert!(check_elements(&elements).is_empty());
+            assert!(check_elements(&[element(0, DrawMethod::Overlay, "a.png", 0)]).is_empty());
+            assert!(check_elements(&[]).is_empty());
+        }
+
+        #[test]
+        fn detected_when_gap() {
+            let elements = [
+                element(4, DrawMethod::Overlay, "c.png", 0),
+                element(0, DrawMethod::Base, "a.png", 0),
+                element(1, DrawMethod::Overlay, "b.png", 0),
+            ];
+            let warnings = check_elements(&elements);
+            assert_eq!(
+                warnings,
+                vec![ElementWarning::Gap {
+                    index: 4,
+                    missing_from: 2
+                }]
+            );
+            assert_eq!(warnings[0].index(), 4);
+            assert_eq!(
+                warnings[0].to_string(),
+                "element4 without element2 to element3"
+            );
+        }
+
+        #[test]
+        fn detected_when_duplicate() {
+            let elements = [
+                element(0, DrawMethod::Base, "a.png", 0),
+                element(1, DrawMethod::Overlay, "b.png", 0),
+                element(1, DrawMethod::Overlay, "c.png", 0),
+                element(2, DrawMethod::Overlay, "B.PNG", 0),
+            ];
+            assert_eq!(
+                check_elements(&elements),
+                vec![
+                    ElementWarning::Duplicate { index: 1 },
+                    ElementWarning::SameImage {
+                        index: 2,
+                        original: 1
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn detected_when_non_base_first() {
+            let elements = [
+                element(1, DrawMethod::Add, "glow.png", 0),
+                element(2, DrawMethod::Overlay, "b.png", 0),
+            ];
+            assert_eq!(
+                check_elements(&elements),
+                vec![
+                    ElementWarning::Gap {
+                        index: 1,
+                        missing_from: 0
+                    },
+                    ElementWarning::NonBaseFirst {
+                        index: 1,
+                        method: DrawMethod::Add
+                    },
+                ]
+            );
+        }
+    }
+
+    mod find_block_duplicates {
+        use super::*;
+
+        #[test]
+        fn success_when_repeated_across_blocks() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\nsurface1\r\n{\r\nelement0,base,b.png,0,0\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            assert!(find_block_duplicates(&blocks).is_empty());
+        }
+
+        #[test]
+        fn detected_when_repeated_in_one_block() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,0,0\r\n// c\r\nelement0,base,b.png,0,0\r\n}\r\nsurface1\r\n{\r\nelement0,base,b.png,0,0\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let reports = find_block_duplicates(&blocks);
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].key, "element0");
+            assert_eq!(reports[0].lines, vec![3, 5]);
+            assert_eq!(reports[0].winner, 5);
+        }
+
+        #[test]
+        fn detected_when_same_index_in_one_block() {
+            let case = "surface0\r\n{\r\nanimation1.pattern0,overlay,5,50,0,0\r\nanimation01.pattern0,overlay,6,50,0,0\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let reports = find_block_duplicates(&blocks);
+            assert_eq!(reports.len(), 1);
+            assert_eq!(reports[0].key, "animation1.pattern0");
+            assert_eq!(reports[0].lines, vec![3, 4]);
+        }
+    }
+
+    mod check_deprecated {
+        use super::*;
+
+        use alloc::vec;
+
+        #[test]
+        fn detected_when_old_style_seriko() {
+            let entries = vec![
+                Located::new(3, KeyValue::new("5pattern12", "0,100,50,overlay,0,0")),
+                Located::new(4, KeyValue::new("5interval", "sometimes")),
+                Located::new(
+                    5,
+                    KeyValue::new("seriko.paint_transparent_region_black", "0"),
+                ),
+            ];
+            let warnings = check_deprecated(&entries);
+
+            assert_eq!(
+                warnings,
+                vec![
+                    DeprecationWarning {
+                        line: 3,
+                        key: "5pattern12".to_string(),
+                        replacement: "animation5.pattern12".to_string(),
+                        severity: Severity::Warning,
+                    },
+                    DeprecationWarning {
+                        line: 4,
+                        key: "5interval".to_string(),
+                        replacement: "animation5.interval".to_string(),
+                        severity: Severity::Warning,
+                    },
+                    DeprecationWarning {
+                        line: 5,
+                        key: "seriko.paint_transparent_region_black".to_string(),
+                        replacement: "seriko.use_self_alpha".to_string(),
+                        severity: Severity::Warning,
+                    },
+                ]
+            );
+            assert_eq!(
+                warnings[1].to_string(),
+                "line 4: 5interval is deprecated, use animation5.interval"
+            );
+        }
+
+        #[test]
+        fn ignored_when_not_deprecated() {
+            let entries = vec![
+                Located::new(1, KeyValue::new("animation5.interval", "sometimes")),
+                Located::new(
+                    2,
+                    KeyValue::new("animation5.pattern0", "overlay,100,50,0,0"),
+                ),
+                Located::new(3, KeyValue::new("xinterval", "sometimes")),
+                Located::new(4, KeyValue::new("5pattern", "sometimes")),
+                Located::new(5, KeyValue::new("seriko.use_self_alpha", "1")),
+            ];
+
+            assert!(check_deprecated(&entries).is_empty());
+        }
+
+        #[test]
+        fn success_when_extended_with_severity() {
+            let table: Vec<_> = [Deprecation {
+                pattern: "sakura.balloon.alignmenttodesktop",
+                replacement: "seriko.alignmenttodesktop",
+                severity: Severity::Error,
+            }]
+            .into_iter()
+            .chain(DEPRECATED_KEYS.iter().copied())
+            .collect();
+            let entries = vec![
+                Located::new(
+                    1,
+                    KeyValue::new("sakura.balloon.alignmenttodesktop", "free"),
+                ),
+                Located::new(2, KeyValue::new("0interval", "never")),
+            ];
+            let warnings = check_deprecated_with(&entries, &table);
+
+            assert_eq!(warnings.len(), 2);
+            assert_eq!(warnings[0].severity, Severity::Error);
+            assert_eq!(warnings[0].replacement, "seriko.alignmenttodesktop");
+            assert_eq!(warnings[1].severity, Severity::Warning);
+        }
+    }
+
+    mod check_trailing_whitespace {
+        use super::*;
+
+        use alloc::vec;
+
+        use crate::keys::ValueKind;
+
+        const CASE: &str = "charset,UTF-8\r\nsakura.name,Sakura \r\nsurface0\r\n{\r\nelement0,base,body0.png \r\ncollision0,10,10,50,50,Head\t\r\ntooltip.Head,head \r\n}\r\n";
+
+        fn warnings(doc: &Document) -> Vec<Located<diagnostic::Warning>> {
+            let entries: Vec<_> = doc
+                .nodes()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, node)| node.key_value().map(|kv| Located::new(i + 1, kv)))
+                .collect();
+            check_trailing_whitespace(&entries)
+        }
+
+        #[test]
+        fn warned_when_filename_or_name() {
+            let warnings = warnings(&Document::parse(CASE));
+            assert_eq!(
+                warnings,
+                vec![
+                    Located::new(
+                        2,
+                        diagnostic::Warning::TrailingValueWhitespace(ValueKind::Text)
+                    ),
+                    Located::new(
+                        5,
+                        diagnostic::Warning::TrailingValueWhitespace(ValueKind::Filename)
+                    ),
+                    Located::new(
+                        6,
+                        diagnostic::Warning::TrailingValueWhitespace(ValueKind::Identifier)
+                    ),
+                    Located::new(
+                        7,
+                        diagnostic::Warning::TrailingValueWhitespace(ValueKind::Text)
+                    ),
+                ]
+            );
+            let diagnostic::Warning::TrailingValueWhitespace(kind) = warnings[1].value else {
+                unreachable!();
+            };
+            assert_eq!(kind.severity(), Severity::Warning);
+        }
+
+        #[test]
+        fn detected_when_text_as_info() {
+            let warnings = warnings(&Document::parse(CASE));
+            let diagnostic::Warning::TrailingValueWhitespace(kind) = warnings[3].value else {
+                unreachable!();
+            };
+            assert_eq!(kind.severity(), Severity::Info);
+        }
+
+        #[test]
+        fn success_when_fixing_only_flagged() {
+            let mut doc = Document::parse(CASE);
+            let flagged: Vec<_> = warnings(&doc)
+                .into_iter()
+                .filter(|w| match w.value {
+                    diagnostic::Warning::TrailingValueWhitespace(kind) => {
+                        kind.severity() == Severity::Warning
+                    }
+                    _ => false,
+                })
+                .collect();
+
+            assert_eq!(fix_trailing_whitespace(&mut doc, &flagged), 2);
+            assert_eq!(
+                doc.to_string(),
+                CASE.replace("body0.png ", "body0.png")
+                    .replace("Head\t", "Head")
+            );
+            assert_eq!(fix_trailing_whitespace(&mut doc, &flagged), 0);
+        }
+    }
+
+    mod lint_passes {
+        use super::*;
+
+        use crate::value::Coordinate;
+
+        fn assert_known(warnings: &[Warning]) {
+            for warning in warnings {
+                assert!(codes::ALL.contains(&warning.code), "{}", warning.code);
+            }
+        }
+
+        #[test]
+        fn detected_when_duplicates() {
+            let entries = vec![
+                Located::new(1, KeyValue::new("name", "a")),
+                Located::new(2, KeyValue::new("name", "b")),
+                Located::new(3, KeyValue::new("id", "x")),
+                Located::new(4, KeyValue::new("id", "x")),
+            ];
+            let mut warnings = Vec::new();
+            lint_duplicates(&entries, &mut warnings);
+
+            assert_known(&warnings);
+            assert_eq!(
+                warnings,
+                vec![
+                    Warning::new(
+                        codes::DUPLICATE_KEY,
+                        Severity::Warning,
+                        2,
+                        "name given 2 times"
+                    ),
+                    Warning::new(codes::DUPLICATE_KEY, Severity::Info, 4, "id given 2 times"),
+                ]
+            );
+
+            let blocks = parse_blocks(
+                "surface0\r\n{\r\nelement0,base,a.png,0,0\r\nelement0,base,b.png,0,0\r\n}\r\n",
+            )
+            .unwrap();
+            let mut warnings = Vec::new();
+            lint_block_duplicates(&blocks, &mut warnings);
+            assert_known(&warnings);
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].line, 4);
+        }
+
+        #[test]
+        fn detected_when_charset_misplaced_or_bom_mismatch() {
+            let file = ShellFile::parse(b"name,a\r\ncharset,UTF-8\r\n", Charset::UTF8).unwrap();
+            let mut warnings = Vec::new();
+            lint_charset_position(&file, CharsetPositionPolicy::FirstLine, &mut warnings);
+            lint_charset_bom(b"\xEF\xBB\xBFcharset,Shift_JIS\r\n", &mut warnings);
+
+            assert_known(&warnings);
+            let codes: Vec<_> = warnings.iter().map(|w| (w.code, w.line)).collect();
+            assert_eq!(
+                codes,
+                [(codes::MISPLACED_CHARSET, 2), (codes::BOM_MISMATCH, 1)]
+            );
+        }
+
+        #[test]
+        fn detected_when_elements_wrong() {
+            let element = |id, method, filename: &str| Element {
+                id,
+                method,
+                filename: filename.to_string(),
+                offset: Coordinate::new(0, 0),
+                alpha: None,
+            };
+            let elements = [
+                element(0, DrawMethod::Add, "a.png"),
+                element(2, DrawMethod::Overlay, "a.png"),
+            ];
+            let mut warnings = Vec::new();
+            lint_elements(&elements, 7, &mut warnings);
+
+            assert_known(&warnings);
+            assert!(warnings.iter().all(|w| w.line == 7));
+            let codes: Vec<_> = warnings.iter().map(|w| (w.code, w.severity)).collect();
+            assert!(codes.contains(&(codes::ELEMENT_GAP, Severity::Warning)));
+            assert!(codes.contains(&(codes::ELEMENT_NON_BASE_FIRST, Severity::Warning)));
+            assert!(codes.contains(&(codes::ELEMENT_SAME_IMAGE, Severity::Info)));
+        }
+
+        #[test]
+        fn detected_when_deprecated() {
+            let entries = vec![Located::new(3, KeyValue::new("5interval", "sometimes"))];
+            let mut warnings = Vec::new();
+            lint_deprecated(&entries, DEPRECATED_KEYS, &mut warnings);
+
+            assert_known(&warnings);
+            assert_eq!(
+                warnings,
+                vec![Warning::new(
+                    codes::DEPRECATED_KEY,
+                    Severity::Warning,
+                    3,
+                    "5interval is deprecated"
+                )
+                .suggestion("animation5.interval")]
+            );
+        }
+
+        #[test]
+        fn detected_when_trailing_whitespace() {
+            let entries = vec![
+                Located::new(1, KeyValue::new("sakura.name", "Emily \t")),
+                Located::new(2, KeyValue::new("kero.name", "Teddy")),
+            ];
+            let mut warnings = Vec::new();
+            lint_trailing_whitespace(&entries, &mut warnings);
+
+            assert_known(&warnings);
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].code, codes::TRAILING_WHITESPACE);
+            assert_eq!(warnings[0].line, 1);
+            assert_eq!(warnings[0].suggestion.as_deref(), Some("Emily"));
+        }
+
+        #[test]
+        fn detected_when_charset_directives_repeated() {
+            let mut warnings = Vec::new();
+            lint_charset_directives(
+                b"charset,Shift_JIS\r\nname,x\r\ncharset,UTF-8\r\ncharset,Shift_JIS\r\n",
+                &mut warnings,
+            );
+
+            assert_known(&warnings);
+            assert_eq!(
+                warnings,
+                vec![
+                    Warning::new(
+                        codes::CONFLICTING_CHARSET,
+                        Severity::Warning,
+                        3,
+                        "charset UTF8 ignored for ShiftJIS of line 1"
+                    )
+                    .suggestion("charset,Shift_JIS"),
+                    Warning::new(
+                        codes::DUPLICATE_KEY,
+                        Severity::Info,
+                        4,
+                        "charset given again after line 1"
+                    )
+                    .suggestion("charset,Shift_JIS"),
+                ]
+            );
+
+            let mut warnings = Vec::new();
+            lint_charset_directives(b"charset,UTF-8\r\nname,x\r\n", &mut warnings);
+            assert!(warnings.is_empty());
+
+            let mut case = "/".repeat(DEFAULT_SCAN_LIMIT).into_bytes();
+            case.extend_from_slice(b"\r\ncharset,UTF-8\r\n");
+            let mut warnings = Vec::new();
+            lint_charset_directives(&case, &mut warnings);
+            assert_eq!(
+                warnings,
+                vec![Warning::new(
+                    codes::CONFLICTING_CHARSET,
+                    Severity::Warning,
+                    2,
+                    "charset UTF8 ignored after the first 4096 bytes"
+                )]
+            );
+        }
+
+        #[test]
+        fn detected_when_region_near_miss() {
+            let parsed = crate::surfaces::parse_blocks_with(
+                "surface0\r\n{\r\ncollision0,0,0,10,10,head\r\ncollision1,0,10,10,20,Tail\r\ncollisionex2,Heed,rect,0,0,5,5\r\ncollision3,0,20,10,30,Bust\r\n}\r\n",
+                &ParseOptions::STRICT,
+            )
+            .unwrap();
+            let (surfaces, _) = crate::surfaces::build_surfaces(&parsed.blocks);
+            let mut warnings = Vec::new();
+            lint_regions(&surfaces[&0], 1, &mut warnings);
+
+            assert_known(&warnings);
+            let suggestions: Vec<_> = warnings
+                .iter()
+                .map(|w| w.suggestion.as_deref().unwrap())
+                .collect();
+            assert_eq!(suggestions, vec!["Head", "Head"]);
+            assert_eq!(warnings[0].message, "head is near the known region Head");
+        }
+
+        #[test]
+        fn success_when_filtered_by_severity() {
+            let entries = vec![
+                Located::new(1, KeyValue::new("name", "a")),
+                Located::new(2, KeyValue::new("name", "b")),
+                Located::new(3, KeyValue::new("id", "x")),
+                Located::new(4, KeyValue::new("id", "x")),
+            ];
+            let mut filter = SeverityFilter::new(Vec::new(), Severity::Warning);
+            lint_duplicates(&entries, &mut filter);
+
+            let lines: Vec<_> = filter.into_inner().iter().map(|w| w.line).collect();
+            assert_eq!(lines, [2]);
+        }
+    }
+}
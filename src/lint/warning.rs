@@ -0,0 +1,218 @@
+//! Warnings shared by the passes of [`lint`], and sinks they are streamed into.
+//!
+//! [`lint`]: crate::lint
+
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+
+use crate::diagnostic::Severity;
+
+/// Codes of [`Warning`], each of one kind of mistake of a pass.
+///
+/// [`Warning`]: crate::lint::Warning
+pub mod codes {
+    /// Key given more than once in one scope.
+    pub const DUPLICATE_KEY: &str = "W0001-duplicate-key";
+    /// `charset` line in a position the policy does not allow.
+    pub const MISPLACED_CHARSET: &str = "W0002-misplaced-charset";
+    /// UTF-8 byte order mark before another charset.
+    pub const BOM_MISMATCH: &str = "W0003-bom-mismatch";
+    /// Indices of elements not continuous.
+    pub const ELEMENT_GAP: &str = "W0004-element-gap";
+    /// Index of an element given again.
+    pub const ELEMENT_DUPLICATE: &str = "W0005-element-duplicate";
+    /// First element drawn onto nothing.
+    pub const ELEMENT_NON_BASE_FIRST: &str = "W0006-element-non-base-first";
+    /// Element drawing the same image as another.
+    pub const ELEMENT_SAME_IMAGE: &str = "W0007-element-same-image";
+    /// Key superseded by another.
+    pub const DEPRECATED_KEY: &str = "W0008-deprecated-key";
+    /// Value ending with spaces or tabs.
+    pub const TRAILING_WHITESPACE: &str = "W0009-trailing-whitespace";
+    /// Name of a collision near a known region, in another case or misspelled.
+    pub const REGION_NEAR_MISS: &str = "W0010-region-near-miss";
+    /// `charset` line naming a charset other than the first, which is used.
+    pub const CONFLICTING_CHARSET: &str = "W0011-conflicting-charset";
+
+    /// All the codes, in order.
+    pub const ALL: &[&str] = &[
+        DUPLICATE_KEY,
+        MISPLACED_CHARSET,
+        BOM_MISMATCH,
+        ELEMENT_GAP,
+        ELEMENT_DUPLICATE,
+        ELEMENT_NON_BASE_FIRST,
+        ELEMENT_SAME_IMAGE,
+        DEPRECATED_KEY,
+        TRAILING_WHITESPACE,
+        REGION_NEAR_MISS,
+        CONFLICTING_CHARSET,
+    ];
+}
+
+/// Warning of a lint pass.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Warning {
+    /// One of [`codes`], such as `W0001-duplicate-key`.
+    ///
+    /// [`codes`]: crate::lint::codes
+    pub code: &'static str,
+    pub severity: Severity,
+    /// 1-based line number.
+    pub line: usize,
+    pub message: String,
+    /// Text to write instead, such as the key superseding a deprecated one.
+    pub suggestion: Option<String>,
+}
+
+impl Warning {
+    pub fn new(
+        code: &'static str,
+        severity: Severity,
+        line: usize,
+        message: impl Into<String>,
+    ) -> Warning {
+        Warning {
+            code,
+            severity,
+            line,
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    pub fn suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        };
+        write!(
+            f,
+            "line {}: {}[{}]: {}",
+            self.line, severity, self.code, self.message
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", write {:?}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Receiver of warnings streamed by lint passes.
+pub trait WarningSink {
+    fn emit(&mut self, warning: Warning);
+}
+
+impl WarningSink for Vec<Warning> {
+    fn emit(&mut self, warning: Warning) {
+        self.push(warning);
+    }
+}
+
+impl<S: WarningSink + ?Sized> WarningSink for &mut S {
+    fn emit(&mut self, warning: Warning) {
+        (**self).emit(warning);
+    }
+}
+
+/// Sink passing warnings of `min` or a higher severity to `sink`, dropping the others.
+///
+/// Severities are ordered [`Severity::Info`], [`Severity::Warning`] and [`Severity::Error`] from the lowest.
+///
+/// [`Severity::Info`]: crate::diagnostic::Severity::Info
+/// [`Severity::Warning`]: crate::diagnostic::Severity::Warning
+/// [`Severity::Error`]: crate::diagnostic::Severity::Error
+#[derive(Debug)]
+pub struct SeverityFilter<S> {
+    pub sink: S,
+    pub min: Severity,
+}
+
+impl<S: WarningSink> SeverityFilter<S> {
+    pub fn new(sink: S, min: Severity) -> SeverityFilter<S> {
+        SeverityFilter { sink, min }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S: WarningSink> WarningSink for SeverityFilter<S> {
+    fn emit(&mut self, warning: Warning) {
+        if rank(warning.severity) >= rank(self.min) {
+            self.sink.emit(warning);
+        }
+    }
+}
+
+fn rank(severity: Severity) -> u8 {
+    match severity {
+        Severity::Info => 0,
+        Severity::Warning => 1,
+        Severity::Error => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod warning_codes {
+        use super::*;
+
+        #[test]
+        fn success_when_codes_unique() {
+            for (i, code) in codes::ALL.iter().enumerate() {
+                let (number, name) = code.split_once('-').unwrap();
+                assert_eq!(number, format!("W{:04}", i + 1));
+                assert!(
+                    codes::ALL[..i].iter().all(|other| !other.ends_with(name)),
+                    "{} given twice",
+                    name
+                );
+            }
+        }
+    }
+
+    mod severity_filter {
+        use super::*;
+
+        #[test]
+        fn success_when_lower_severities_dropped() {
+            let mut filter = SeverityFilter::new(Vec::new(), Severity::Warning);
+            for severity in [Severity::Info, Severity::Warning, Severity::Error] {
+                filter.emit(Warning::new(codes::DUPLICATE_KEY, severity, 1, "x"));
+            }
+            let severities: Vec<_> = filter.into_inner().iter().map(|w| w.severity).collect();
+            assert_eq!(severities, [Severity::Warning, Severity::Error]);
+        }
+    }
+
+    mod warning {
+        use super::*;
+
+        #[test]
+        fn display_with_code_and_suggestion() {
+            let warning = Warning::new(
+                codes::DEPRECATED_KEY,
+                Severity::Warning,
+                3,
+                "0interval is deprecated",
+            )
+            .suggestion("animation0.interval");
+            assert_eq!(
+                warning.to_string(),
+                "line 3: warning[W0008-deprecated-key]: 0interval is deprecated, write \"animation0.interval\""
+            );
+        }
+    }
+}
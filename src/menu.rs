@@ -0,0 +1,361 @@
+//! # `menu`
+//!
+//! Settings in `descript.txt` of the owner-drawn menu, `menu.*`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::menu::{BitmapLayout, MenuSettings, SidebarAlignment};
+//!
+//! let mut menu = MenuSettings::default();
+//! menu.apply(&["menu", "sidebar", "bitmap", "filename"], "side.png").unwrap();
+//! menu.apply(&["menu", "sidebar", "alignment"], "bottom").unwrap();
+//! menu.apply(&["menu", "sidebar", "bitmap", "layout"], "stretch").unwrap();
+//!
+//! assert_eq!(menu.sidebar.alignment, Some(SidebarAlignment::Bottom));
+//! assert_eq!(menu.sidebar.layout, Some(BitmapLayout::Stretch));
+//! assert!(menu.validate().is_empty());
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{combinator::all_consuming, error::context, IResult};
+
+use crate::{
+    value::{parse_filename, tag_table},
+    ShellParseError,
+};
+
+/// Alignment of the sidebar bitmap, `menu.sidebar.alignment`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SidebarAlignment {
+    Top,
+    Bottom,
+}
+
+/// Alignment of the background and foreground bitmaps, `menu.background.alignment`
+/// and `menu.foreground.alignment`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BitmapAlignment {
+    LeftTop,
+    CenterTop,
+    RightTop,
+}
+
+/// How a bitmap fills the region of its part, `menu.<part>.bitmap.layout`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BitmapLayout {
+    /// Repeated from the alignment.
+    Tile,
+    /// Scaled to the region.
+    Stretch,
+    /// Drawn once at the center.
+    Center,
+}
+
+const SIDEBAR_ALIGNMENTS: &[(&str, SidebarAlignment)] = &[
+    ("top", SidebarAlignment::Top),
+    ("bottom", SidebarAlignment::Bottom),
+];
+
+const BITMAP_ALIGNMENTS: &[(&str, BitmapAlignment)] = &[
+    ("lefttop", BitmapAlignment::LeftTop),
+    ("centertop", BitmapAlignment::CenterTop),
+    ("righttop", BitmapAlignment::RightTop),
+];
+
+const BITMAP_LAYOUTS: &[(&str, BitmapLayout)] = &[
+    ("tile", BitmapLayout::Tile),
+    ("stretch", BitmapLayout::Stretch),
+    ("center", BitmapLayout::Center),
+];
+
+/// parser for [`SidebarAlignment`].
+///
+/// [`SidebarAlignment`]: crate::menu::SidebarAlignment
+pub fn parse_sidebar_alignment(
+    input: &str,
+) -> IResult<&str, SidebarAlignment, ShellParseError<'_>> {
+    context("menu:alignment", tag_table(SIDEBAR_ALIGNMENTS))(input)
+}
+
+/// parser for [`BitmapAlignment`].
+///
+/// [`BitmapAlignment`]: crate::menu::BitmapAlignment
+pub fn parse_bitmap_alignment(input: &str) -> IResult<&str, BitmapAlignment, ShellParseError<'_>> {
+    context("menu:alignment", tag_table(BITMAP_ALIGNMENTS))(input)
+}
+
+/// parser for [`BitmapLayout`].
+///
+/// [`BitmapLayout`]: crate::menu::BitmapLayout
+pub fn parse_bitmap_layout(input: &str) -> IResult<&str, BitmapLayout, ShellParseError<'_>> {
+    context("menu:layout", tag_table(BITMAP_LAYOUTS))(input)
+}
+
+/// Bitmap of one part of the menu, keys following `menu.<part>.`.
+///
+/// Each field is `None` when the corresponding key is absent.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MenuBitmap<A> {
+    /// `bitmap.filename`
+    pub filename: Option<String>,
+    /// `alignment`
+    pub alignment: Option<A>,
+    /// `bitmap.layout`
+    pub layout: Option<BitmapLayout>,
+}
+
+impl<A> Default for MenuBitmap<A> {
+    fn default() -> Self {
+        MenuBitmap {
+            filename: None,
+            alignment: None,
+            layout: None,
+        }
+    }
+}
+
+impl<A> MenuBitmap<A> {
+    fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+        alignment: fn(&'a str) -> IResult<&'a str, A, ShellParseError<'a>>,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        match key_segments {
+            ["bitmap", "filename"] => {
+                let (_, filename) = context("menu:filename", all_consuming(parse_filename))(value)?;
+                self.filename = Some(filename.to_string());
+            }
+            ["bitmap", "layout"] => {
+                self.layout = Some(all_consuming(parse_bitmap_layout)(value)?.1)
+            }
+            ["alignment"] => self.alignment = Some(all_consuming(alignment)(value)?.1),
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+}
+
+/// Part of the menu with a bitmap.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MenuPart {
+    Background,
+    Foreground,
+    Sidebar,
+}
+
+/// Problem found by [`MenuSettings::validate`].
+///
+/// [`MenuSettings::validate`]: crate::menu::MenuSettings::validate
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MenuIssue {
+    /// `menu.<part>.alignment` is given without `menu.<part>.bitmap.filename`.
+    AlignmentWithoutBitmap(MenuPart),
+    /// `menu.<part>.bitmap.layout` is given without `menu.<part>.bitmap.filename`.
+    LayoutWithoutBitmap(MenuPart),
+}
+
+/// Settings of the menu, `menu.*`.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MenuSettings {
+    /// `menu.background.*`
+    pub background: MenuBitmap<BitmapAlignment>,
+    /// `menu.foreground.*`
+    pub foreground: MenuBitmap<BitmapAlignment>,
+    /// `menu.sidebar.*`
+    pub sidebar: MenuBitmap<SidebarAlignment>,
+    /// Other `menu.*` entries, such as colors and fonts, in order.
+    pub unknown: Vec<(String, String)>,
+}
+
+impl MenuSettings {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a `menu.*` key.
+    /// Unknown `menu.*` keys are kept in `unknown`.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let applied = match key_segments {
+            ["menu", "background", rest @ ..] => {
+                self.background.apply(rest, value, parse_bitmap_alignment)?
+            }
+            ["menu", "foreground", rest @ ..] => {
+                self.foreground.apply(rest, value, parse_bitmap_alignment)?
+            }
+            ["menu", "sidebar", rest @ ..] => {
+                self.sidebar.apply(rest, value, parse_sidebar_alignment)?
+            }
+            ["menu", ..] => false,
+            _ => return Ok(false),
+        };
+        if !applied {
+            self.unknown
+                .push((key_segments.join("."), value.to_string()));
+        }
+
+        Ok(true)
+    }
+
+    /// Checks that parts with alignment or layout have their bitmaps.
+    pub fn validate(&self) -> Vec<MenuIssue> {
+        let parts = [
+            (
+                MenuPart::Background,
+                self.background.filename.is_some(),
+                self.background.alignment.is_some(),
+                self.background.layout.is_some(),
+            ),
+            (
+                MenuPart::Foreground,
+                self.foreground.filename.is_some(),
+                self.foreground.alignment.is_some(),
+                self.foreground.layout.is_some(),
+            ),
+            (
+                MenuPart::Sidebar,
+                self.sidebar.filename.is_some(),
+                self.sidebar.alignment.is_some(),
+                self.sidebar.layout.is_some(),
+            ),
+        ];
+
+        let mut issues = Vec::new();
+        for (part, bitmap, alignment, layout) in parts {
+            if !bitmap && alignment {
+                issues.push(MenuIssue::AlignmentWithoutBitmap(part));
+            }
+            if !bitmap && layout {
+                issues.push(MenuIssue::LayoutWithoutBitmap(part));
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    mod parse_sidebar_alignment {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            assert_eq!(
+                parse_sidebar_alignment("top").unwrap(),
+                ("", SidebarAlignment::Top)
+            );
+            assert_eq!(
+                parse_sidebar_alignment("bottom").unwrap(),
+                ("", SidebarAlignment::Bottom)
+            );
+            assert!(parse_sidebar_alignment("lefttop").is_err());
+        }
+    }
+
+    mod parse_bitmap_layout {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            for (case, expected) in [
+                ("tile", BitmapLayout::Tile),
+                ("stretch", BitmapLayout::Stretch),
+                ("center", BitmapLayout::Center),
+            ] {
+                assert_eq!(parse_bitmap_layout(case).unwrap(), ("", expected));
+            }
+            assert!(parse_bitmap_layout("fit").is_err());
+        }
+    }
+
+    mod menu_settings {
+        use super::*;
+
+        #[test]
+        fn success_when_full_spec() {
+            let mut menu = MenuSettings::default();
+            for (key, value) in [
+                (
+                    &["menu", "background", "bitmap", "filename"][..],
+                    "back.png",
+                ),
+                (&["menu", "background", "alignment"], "righttop"),
+                (&["menu", "background", "bitmap", "layout"], "tile"),
+                (&["menu", "sidebar", "bitmap", "filename"], "side.png"),
+                (&["menu", "sidebar", "alignment"], "top"),
+            ] {
+                assert!(menu.apply(key, value).unwrap());
+            }
+
+            assert_eq!(menu.background.filename.as_deref(), Some("back.png"));
+            assert_eq!(menu.background.alignment, Some(BitmapAlignment::RightTop));
+            assert_eq!(menu.background.layout, Some(BitmapLayout::Tile));
+            assert_eq!(menu.sidebar.alignment, Some(SidebarAlignment::Top));
+            assert_eq!(menu.foreground, MenuBitmap::default());
+            assert!(menu.unknown.is_empty());
+            assert!(menu.validate().is_empty());
+        }
+
+        #[test]
+        fn failed_when_alignment_of_other_part() {
+            let mut menu = MenuSettings::default();
+            assert!(menu
+                .apply(&["menu", "sidebar", "alignment"], "lefttop")
+                .is_err());
+            assert!(menu
+                .apply(&["menu", "foreground", "alignment"], "top")
+                .is_err());
+        }
+
+        #[test]
+        fn warned_when_alignment_without_bitmap() {
+            let mut menu = MenuSettings::default();
+            menu.apply(&["menu", "sidebar", "alignment"], "bottom")
+                .unwrap();
+            menu.apply(&["menu", "foreground", "bitmap", "layout"], "center")
+                .unwrap();
+
+            assert_eq!(
+                menu.validate(),
+                vec![
+                    MenuIssue::LayoutWithoutBitmap(MenuPart::Foreground),
+                    MenuIssue::AlignmentWithoutBitmap(MenuPart::Sidebar),
+                ]
+            );
+        }
+
+        #[test]
+        fn ignored_when_unknown_key() {
+            let mut menu = MenuSettings::default();
+            assert!(menu
+                .apply(&["menu", "background", "font", "color", "r"], "255")
+                .unwrap());
+            assert!(menu.apply(&["menu", "hidden"], "1").unwrap());
+            assert!(!menu.apply(&["sakura", "name"], "a").unwrap());
+
+            assert_eq!(
+                menu.unknown,
+                vec![
+                    (
+                        "menu.background.font.color.r".to_string(),
+                        "255".to_string()
+                    ),
+                    ("menu.hidden".to_string(), "1".to_string()),
+                ]
+            );
+            assert_eq!(menu.background, MenuBitmap::default());
+        }
+    }
+}
@@ -0,0 +1,504 @@
+//! # `options`
+//!
+//! `ParseOptions`, the lenient behaviors parsers accept beyond the strict syntax.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::kv::{parse_key_value_with, KeyValue};
+//! use shell_parser_common_rs::options::ParseOptions;
+//!
+//! let case = "name , master // comment\r\n";
+//! let options = ParseOptions::strict()
+//!     .trim_whitespace(true)
+//!     .trailing_comments(true);
+//! let (_, kv) = parse_key_value_with(case, &options).unwrap();
+//!
+//! assert_eq!(kv, KeyValue::new("name", "master"));
+//! ```
+
+use core::fmt;
+
+use crate::{ascii::eq_ignore_case, value::NumericPolicy};
+
+/// Prefixes of comment lines in both presets.
+pub const DEFAULT_COMMENT_PREFIXES: &[&str] = &["//"];
+
+/// Handling of entries with an empty value, such as `craftmanurl,`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum EmptyValuePolicy {
+    /// The entry has the empty string as its value.
+    #[default]
+    Keep,
+    /// Same as [`EmptyValuePolicy::Keep`], and [`diagnostic`] warns.
+    ///
+    /// [`EmptyValuePolicy::Keep`]: crate::options::EmptyValuePolicy::Keep
+    /// [`diagnostic`]: crate::diagnostic
+    Warn,
+    /// The line is not an entry, and parsers fail.
+    Error,
+}
+
+/// Handling of lines with a key and no separator, such as `homeurl`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum BareKeyPolicy {
+    /// The line is an entry with the empty string as its value.
+    TreatAsEmptyValue,
+    /// Same as [`BareKeyPolicy::TreatAsEmptyValue`], and [`diagnostic`] warns.
+    ///
+    /// [`BareKeyPolicy::TreatAsEmptyValue`]: crate::options::BareKeyPolicy::TreatAsEmptyValue
+    /// [`diagnostic`]: crate::diagnostic
+    #[default]
+    Warn,
+    /// The line is not an entry, and parsers fail.
+    Error,
+}
+
+/// Handling of C0 control characters other than tab, CR and LF in decoded text, such as NUL of corrupted archives.
+///
+/// Keys never contain them, so that a line with one in its key is not an entry under any policy.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ControlCharPolicy {
+    /// Decoding fails with [`DecodeError::ControlCharacter`] at the first of them.
+    ///
+    /// [`DecodeError::ControlCharacter`]: crate::charset::DecodeError::ControlCharacter
+    Reject,
+    /// They are removed from the decoded text.
+    Strip,
+    /// They are kept in the decoded text.
+    #[default]
+    Keep,
+}
+
+/// Returns whether `c` is handled by [`ControlCharPolicy`], a C0 control other than tab, CR and LF.
+///
+/// [`ControlCharPolicy`]: crate::options::ControlCharPolicy
+pub(crate) fn is_control_char(c: char) -> bool {
+    c < '\u{20}' && !matches!(c, '\t' | '\r' | '\n')
+}
+
+/// Limits on the size of input, so that crafted files, such as with a header `surface0-4294967295`,
+/// fail with [`LimitExceeded`] rather than exhausting memory.
+///
+/// [`LimitExceeded`]: crate::options::LimitExceeded
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub struct Limits {
+    /// Bytes of a line without its line ending, checked by [`Lines::limited`].
+    ///
+    /// [`Lines::limited`]: crate::line::Lines::limited
+    pub max_line_length: usize,
+    /// Entries of a file, checked by [`Lines::limited`].
+    ///
+    /// [`Lines::limited`]: crate::line::Lines::limited
+    pub max_entries: usize,
+    /// IDs a surface header is expanded into, checked by [`build_surfaces_with`],
+    /// and IDs of a list, checked by [`SurfaceAliases::apply_block`].
+    ///
+    /// [`build_surfaces_with`]: crate::surfaces::build_surfaces_with
+    /// [`SurfaceAliases::apply_block`]: crate::surfaces::SurfaceAliases::apply_block
+    pub max_id_set: usize,
+    /// Lines of the body of a block, checked by [`parse_blocks_with`]. Blocks are never nested.
+    ///
+    /// [`parse_blocks_with`]: crate::block::parse_blocks_with
+    pub max_block_lines: usize,
+}
+
+impl Limits {
+    /// Limits far above those of any known shell, in both presets of [`ParseOptions`].
+    ///
+    /// [`ParseOptions`]: crate::options::ParseOptions
+    pub const DEFAULT: Limits = Limits {
+        max_line_length: 1 << 20,
+        max_entries: 1 << 20,
+        max_id_set: 1 << 16,
+        max_block_lines: 1 << 20,
+    };
+
+    /// No limits.
+    pub const NONE: Limits = Limits {
+        max_line_length: usize::MAX,
+        max_entries: usize::MAX,
+        max_id_set: usize::MAX,
+        max_block_lines: usize::MAX,
+    };
+
+    pub const fn max_line_length(mut self, max: usize) -> Limits {
+        self.max_line_length = max;
+        self
+    }
+
+    pub const fn max_entries(mut self, max: usize) -> Limits {
+        self.max_entries = max;
+        self
+    }
+
+    pub const fn max_id_set(mut self, max: usize) -> Limits {
+        self.max_id_set = max;
+        self
+    }
+
+    pub const fn max_block_lines(mut self, max: usize) -> Limits {
+        self.max_block_lines = max;
+        self
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::DEFAULT
+    }
+}
+
+/// One of [`Limits`].
+///
+/// [`Limits`]: crate::options::Limits
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Limit {
+    /// [`Limits::max_line_length`]
+    ///
+    /// [`Limits::max_line_length`]: crate::options::Limits::max_line_length
+    LineLength,
+    /// [`Limits::max_entries`]
+    ///
+    /// [`Limits::max_entries`]: crate::options::Limits::max_entries
+    Entries,
+    /// [`Limits::max_id_set`]
+    ///
+    /// [`Limits::max_id_set`]: crate::options::Limits::max_id_set
+    IdSet,
+    /// [`Limits::max_block_lines`]
+    ///
+    /// [`Limits::max_block_lines`]: crate::options::Limits::max_block_lines
+    BlockLines,
+}
+
+/// `LimitExceeded` type, with the limit tripped and its value.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub max: usize,
+}
+
+#[cfg(feature = "kv")]
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self.limit {
+            Limit::LineLength => "line length",
+            Limit::Entries => "entries",
+            Limit::IdSet => "IDs of the header",
+            Limit::BlockLines => "lines of the block",
+        };
+        write!(f, "{} over the limit of {}", limit, self.max)
+    }
+}
+
+#[cfg(feature = "kv")]
+impl core::error::Error for LimitExceeded {}
+
+/// Lenient behaviors of parsers, each off in [`ParseOptions::STRICT`].
+///
+/// Options are built from a preset, changing knobs one by one.
+/// The default is [`ParseOptions::STRICT`], which is the syntax parsers accept without options.
+///
+/// [`ParseOptions::STRICT`]: crate::options::ParseOptions::STRICT
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Whether keys are compared ignoring ASCII case, such as `Charset` for `charset`.
+    pub case_insensitive_keys: bool,
+    /// Whether charset names are matched ignoring ASCII case, `-` and `_`, and with aliases such as `SJIS`.
+    pub charset_aliases: bool,
+    /// Whether whitespace around the separator of key and value is ignored, such as in `key , value`.
+    pub trim_whitespace: bool,
+    /// Whether a tab separates key and value as `,` does.
+    pub tab_separator: bool,
+    /// Whether `//` after whitespace ends the value, such as in `key,value // comment`.
+    pub trailing_comments: bool,
+    /// Whether full-width digits and commas are read as half-width in numeric fields, such as `１０`.
+    ///
+    /// Off in both presets. Free-text fields, such as names, filenames and tooltips, are kept.
+    pub normalize_fullwidth: bool,
+    /// Policy of numbers in surface blocks, read by [`parse_with_options`], [`parse_block_body_lenient_with`]
+    /// and [`SurfaceBody::apply_line_with`].
+    ///
+    /// [`NumericPolicy::DEFAULT`] in both presets.
+    ///
+    /// [`parse_with_options`]: crate::diagnostic::parse_with_options
+    /// [`parse_block_body_lenient_with`]: crate::surfaces::parse_block_body_lenient_with
+    /// [`SurfaceBody::apply_line_with`]: crate::surfaces::SurfaceBody::apply_line_with
+    /// [`NumericPolicy::DEFAULT`]: crate::value::NumericPolicy::DEFAULT
+    pub numeric: NumericPolicy,
+    /// Prefixes of comment lines after leading whitespace, used in classifying lines,
+    /// in [`Document::parse_with`] and in parsers of surface block bodies.
+    ///
+    /// [`DEFAULT_COMMENT_PREFIXES`] in both presets. Set with [`ParseOptions::comment_prefixes`].
+    ///
+    /// [`Document::parse_with`]: crate::document::Document::parse_with
+    /// [`DEFAULT_COMMENT_PREFIXES`]: crate::options::DEFAULT_COMMENT_PREFIXES
+    /// [`ParseOptions::comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+    pub comment_prefixes: &'static [&'static str],
+    /// Policy of entries with an empty value.
+    ///
+    /// [`EmptyValuePolicy::Keep`] in both presets.
+    ///
+    /// [`EmptyValuePolicy::Keep`]: crate::options::EmptyValuePolicy::Keep
+    pub empty_value: EmptyValuePolicy,
+    /// Policy of lines with a key and no separator.
+    ///
+    /// [`BareKeyPolicy::Error`] in [`ParseOptions::STRICT`], and [`BareKeyPolicy::Warn`] in [`ParseOptions::COMPATIBLE`].
+    ///
+    /// [`BareKeyPolicy::Error`]: crate::options::BareKeyPolicy::Error
+    /// [`BareKeyPolicy::Warn`]: crate::options::BareKeyPolicy::Warn
+    /// [`ParseOptions::STRICT`]: crate::options::ParseOptions::STRICT
+    /// [`ParseOptions::COMPATIBLE`]: crate::options::ParseOptions::COMPATIBLE
+    pub bare_key: BareKeyPolicy,
+    /// Limits on the size of input.
+    ///
+    /// [`Limits::DEFAULT`] in both presets.
+    ///
+    /// [`Limits::DEFAULT`]: crate::options::Limits::DEFAULT
+    pub limits: Limits,
+    /// Policy of control characters in text decoded by [`Charset::decode_with`].
+    ///
+    /// [`ControlCharPolicy::Keep`] in both presets.
+    ///
+    /// [`Charset::decode_with`]: crate::charset::Charset::decode_with
+    /// [`ControlCharPolicy::Keep`]: crate::options::ControlCharPolicy::Keep
+    pub control_chars: ControlCharPolicy,
+}
+
+/// `OptionsError` type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionsError {
+    /// The comment prefix is empty, contains `,` or whitespace, starts with a brace of blocks,
+    /// or can start a key, starting with an ASCII alphanumeric or `_`.
+    InvalidCommentPrefix(&'static str),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::InvalidCommentPrefix(prefix) => {
+                write!(f, "invalid comment prefix {:?}", prefix)
+            }
+        }
+    }
+}
+
+impl core::error::Error for OptionsError {}
+
+impl ParseOptions {
+    /// All knobs off.
+    pub const STRICT: ParseOptions = ParseOptions {
+        case_insensitive_keys: false,
+        charset_aliases: false,
+        trim_whitespace: false,
+        tab_separator: false,
+        trailing_comments: false,
+        normalize_fullwidth: false,
+        numeric: NumericPolicy::DEFAULT,
+        comment_prefixes: DEFAULT_COMMENT_PREFIXES,
+        empty_value: EmptyValuePolicy::Keep,
+        bare_key: BareKeyPolicy::Error,
+        limits: Limits::DEFAULT,
+        control_chars: ControlCharPolicy::Keep,
+    };
+
+    /// Knobs on as mainstream baseware tolerates, except [`ParseOptions::normalize_fullwidth`].
+    ///
+    /// [`ParseOptions::normalize_fullwidth`]: crate::options::ParseOptions::normalize_fullwidth
+    pub const COMPATIBLE: ParseOptions = ParseOptions {
+        case_insensitive_keys: true,
+        charset_aliases: true,
+        trim_whitespace: true,
+        tab_separator: true,
+        trailing_comments: true,
+        normalize_fullwidth: false,
+        numeric: NumericPolicy::DEFAULT,
+        comment_prefixes: DEFAULT_COMMENT_PREFIXES,
+        empty_value: EmptyValuePolicy::Keep,
+        bare_key: BareKeyPolicy::Warn,
+        limits: Limits::DEFAULT,
+        control_chars: ControlCharPolicy::Keep,
+    };
+
+    /// Returns [`ParseOptions::STRICT`].
+    ///
+    /// [`ParseOptions::STRICT`]: crate::options::ParseOptions::STRICT
+    pub const fn strict() -> ParseOptions {
+        ParseOptions::STRICT
+    }
+
+    /// Returns [`ParseOptions::COMPATIBLE`].
+    ///
+    /// [`ParseOptions::COMPATIBLE`]: crate::options::ParseOptions::COMPATIBLE
+    pub const fn compatible() -> ParseOptions {
+        ParseOptions::COMPATIBLE
+    }
+
+    pub const fn case_insensitive_keys(mut self, enabled: bool) -> ParseOptions {
+        self.case_insensitive_keys = enabled;
+        self
+    }
+
+    pub const fn charset_aliases(mut self, enabled: bool) -> ParseOptions {
+        self.charset_aliases = enabled;
+        self
+    }
+
+    pub const fn trim_whitespace(mut self, enabled: bool) -> ParseOptions {
+        self.trim_whitespace = enabled;
+        self
+    }
+
+    pub const fn tab_separator(mut self, enabled: bool) -> ParseOptions {
+        self.tab_separator = enabled;
+        self
+    }
+
+    pub const fn trailing_comments(mut self, enabled: bool) -> ParseOptions {
+        self.trailing_comments = enabled;
+        self
+    }
+
+    pub const fn normalize_fullwidth(mut self, enabled: bool) -> ParseOptions {
+        self.normalize_fullwidth = enabled;
+        self
+    }
+
+    pub const fn numeric(mut self, policy: NumericPolicy) -> ParseOptions {
+        self.numeric = policy;
+        self
+    }
+
+    pub const fn empty_value(mut self, policy: EmptyValuePolicy) -> ParseOptions {
+        self.empty_value = policy;
+        self
+    }
+
+    pub const fn bare_key(mut self, policy: BareKeyPolicy) -> ParseOptions {
+        self.bare_key = policy;
+        self
+    }
+
+    pub const fn limits(mut self, limits: Limits) -> ParseOptions {
+        self.limits = limits;
+        self
+    }
+
+    pub const fn control_chars(mut self, policy: ControlCharPolicy) -> ParseOptions {
+        self.control_chars = policy;
+        self
+    }
+
+    /// Sets the prefixes of comment lines, rejecting those which can be read as keys or braces of blocks.
+    pub fn comment_prefixes(
+        mut self,
+        prefixes: &'static [&'static str],
+    ) -> Result<ParseOptions, OptionsError> {
+        let invalid = prefixes.iter().find(|p| {
+            p.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_' || c == '{' || c == '}')
+                || p.contains(|c: char| c == ',' || c.is_whitespace())
+                || p.is_empty()
+        });
+        if let Some(prefix) = invalid {
+            return Err(OptionsError::InvalidCommentPrefix(prefix));
+        }
+
+        self.comment_prefixes = prefixes;
+        Ok(self)
+    }
+
+    /// Checks whether `text` is a comment line, starting with one of [`comment_prefixes`] after leading whitespace.
+    ///
+    /// [`comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+    pub fn is_comment(&self, text: &str) -> bool {
+        let text = text.trim_start();
+        self.comment_prefixes.iter().any(|p| text.starts_with(p))
+    }
+
+    /// Checks whether `key` is `expected`, ignoring ASCII case when [`case_insensitive_keys`] is on.
+    ///
+    /// [`case_insensitive_keys`]: crate::options::ParseOptions::case_insensitive_keys
+    pub fn key_eq(&self, key: &str, expected: &str) -> bool {
+        if self.case_insensitive_keys {
+            eq_ignore_case(key, expected)
+        } else {
+            key == expected
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::STRICT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_options {
+        use super::*;
+
+        #[test]
+        fn success_when_built_from_preset() {
+            let options = ParseOptions::compatible().tab_separator(false);
+            assert!(!options.tab_separator);
+            assert_eq!(options, ParseOptions::COMPATIBLE.tab_separator(false));
+            assert_ne!(options, ParseOptions::COMPATIBLE);
+            assert_eq!(ParseOptions::default(), ParseOptions::strict());
+
+            let policy = NumericPolicy::DEFAULT.allow_leading_zeros(false);
+            assert_eq!(ParseOptions::compatible().numeric(policy).numeric, policy);
+            assert_eq!(ParseOptions::COMPATIBLE.numeric, NumericPolicy::default());
+
+            assert_eq!(
+                ParseOptions::COMPATIBLE.empty_value,
+                EmptyValuePolicy::default()
+            );
+            assert_eq!(ParseOptions::COMPATIBLE.bare_key, BareKeyPolicy::default());
+            assert_eq!(ParseOptions::STRICT.bare_key, BareKeyPolicy::Error);
+        }
+
+        #[test]
+        fn success_when_comment_prefixes() {
+            assert!(ParseOptions::STRICT.is_comment("  // a"));
+            assert!(!ParseOptions::STRICT.is_comment("# a"));
+
+            let options = ParseOptions::STRICT
+                .comment_prefixes(&["//", "#", ";"])
+                .unwrap();
+            assert!(options.is_comment("# a"));
+            assert!(options.is_comment("\t;a"));
+            assert!(!options.is_comment("a#b,c"));
+        }
+
+        #[test]
+        fn failed_when_invalid_comment_prefix() {
+            for prefix in ["", "rem", "_", "0", "# ", "#,", " #", "{", "}", "}#"] {
+                let prefixes: &'static [&'static str] = Box::leak(Box::new([prefix]));
+                assert_eq!(
+                    ParseOptions::STRICT.comment_prefixes(prefixes),
+                    Err(OptionsError::InvalidCommentPrefix(prefix))
+                );
+            }
+        }
+
+        #[test]
+        fn success_when_key_compared() {
+            assert!(!ParseOptions::STRICT.key_eq("Charset", "charset"));
+            assert!(ParseOptions::STRICT
+                .case_insensitive_keys(true)
+                .key_eq("Charset", "charset"));
+            assert!(!ParseOptions::COMPATIBLE.key_eq("charsets", "charset"));
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! # `path`
+//!
+//! Relative paths written in shell settings.
+//! Separators are normalized to `/`, since shells are written with both `\` and `/`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::path::RelativePath;
+//!
+//! let path = RelativePath::new("images\\surface0.png");
+//! assert_eq!(path.as_str(), "images/surface0.png");
+//! assert_eq!(path.with_extension("pna").as_str(), "images/surface0.pna");
+//! ```
+
+use alloc::{format, string::String};
+use core::fmt;
+
+/// Path relative to the shell directory.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct RelativePath(String);
+
+/// Reason a path is rejected by [`RelativePath::parse`].
+///
+/// [`RelativePath::parse`]: crate::path::RelativePath::parse
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PathError {
+    Empty,
+    /// Starting with a separator or a drive, such as `/a` or `C:\a`.
+    Absolute,
+    /// Containing a `..` component.
+    Traversal,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "empty path"),
+            PathError::Absolute => write!(f, "absolute path"),
+            PathError::Traversal => write!(f, "path containing `..`"),
+        }
+    }
+}
+
+impl core::error::Error for PathError {}
+
+impl RelativePath {
+    /// Creates from `path`, normalizing `\` to `/`.
+    pub fn new(path: &str) -> RelativePath {
+        RelativePath(path.replace('\\', "/"))
+    }
+
+    /// Same as [`RelativePath::new`], rejecting paths which can point outside the directory.
+    ///
+    /// [`RelativePath::new`]: crate::path::RelativePath::new
+    pub fn parse(path: &str) -> Result<RelativePath, PathError> {
+        let path = RelativePath::new(path);
+        let bytes = path.0.as_bytes();
+
+        if bytes.is_empty() {
+            Err(PathError::Empty)
+        } else if bytes[0] == b'/'
+            || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        {
+            Err(PathError::Absolute)
+        } else if path.0.split('/').any(|c| c == "..") {
+            Err(PathError::Traversal)
+        } else {
+            Ok(path)
+        }
+    }
+
+    /// Checks whether `self` is `other` or a directory containing it, ignoring ASCII case as Windows does.
+    pub fn contains_ignore_case(&self, other: &RelativePath) -> bool {
+        let dir = self.0.trim_end_matches('/').as_bytes();
+        let other = other.0.as_bytes();
+        other.len() >= dir.len()
+            && other[..dir.len()].eq_ignore_ascii_case(dir)
+            && matches!(other.get(dir.len()), None | Some(b'/'))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the last component.
+    pub fn file_name(&self) -> &str {
+        self.0.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Returns the extension of the last component, without `.`.
+    pub fn extension(&self) -> Option<&str> {
+        match self.file_name().rsplit_once('.') {
+            Some((stem, ext)) if !stem.is_empty() => Some(ext),
+            _ => None,
+        }
+    }
+
+    /// Returns the path whose extension is replaced with (or appended) `extension`.
+    pub fn with_extension(&self, extension: &str) -> RelativePath {
+        let base = match self.extension() {
+            Some(ext) => &self.0[..self.0.len() - ext.len() - 1],
+            None => self.0.as_str(),
+        };
+
+        RelativePath(format!("{}.{}", base, extension))
+    }
+}
+
+impl fmt::Display for RelativePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod relative_path {
+        use super::*;
+
+        #[test]
+        fn success_when_extension() {
+            let path = RelativePath::new("a.b\\surface0.PNG");
+            assert_eq!(path.file_name(), "surface0.PNG");
+            assert_eq!(path.extension(), Some("PNG"));
+            assert_eq!(path.with_extension("pna").as_str(), "a.b/surface0.pna");
+        }
+
+        #[test]
+        fn success_when_parsed() {
+            assert_eq!(
+                RelativePath::parse("ghost\\master/a..b.dic")
+                    .unwrap()
+                    .as_str(),
+                "ghost/master/a..b.dic"
+            );
+            assert_eq!(RelativePath::parse(""), Err(PathError::Empty));
+            assert_eq!(RelativePath::parse("\\a.txt"), Err(PathError::Absolute));
+            assert_eq!(RelativePath::parse("C:/a.txt"), Err(PathError::Absolute));
+            assert_eq!(RelativePath::parse("a/../../b"), Err(PathError::Traversal));
+            assert_eq!(RelativePath::parse(".."), Err(PathError::Traversal));
+        }
+
+        #[test]
+        fn success_when_contains_ignore_case() {
+            let dir = RelativePath::new("Ghost/Old/");
+            assert!(dir.contains_ignore_case(&RelativePath::new("ghost\\old\\a.dic")));
+            assert!(dir.contains_ignore_case(&RelativePath::new("ghost/old")));
+            assert!(!dir.contains_ignore_case(&RelativePath::new("ghost/older/a.dic")));
+            assert!(!dir.contains_ignore_case(&RelativePath::new("ghost")));
+        }
+
+        #[test]
+        fn success_when_no_extension() {
+            let path = RelativePath::new("a.b/.hidden");
+            assert_eq!(path.extension(), None);
+            assert_eq!(path.with_extension("pna").as_str(), "a.b/.hidden.pna");
+        }
+    }
+}
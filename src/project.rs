@@ -0,0 +1,766 @@
+//! # `project`
+//!
+//! Shell directories, with `descript.txt` and all `surfaces*.txt` loaded together.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use shell_parser_common_rs::options::ParseOptions;
+//! use shell_parser_common_rs::project::load_shell_dir;
+//!
+//! let project = load_shell_dir(Path::new("shell/master"), &ParseOptions::STRICT).unwrap();
+//! for file in project.surfaces() {
+//!     for warning in file.outcome().warnings.iter() {
+//!         eprintln!("{}: {}", file.path().display(), warning);
+//!     }
+//! }
+//! println!("{} surface blocks", project.blocks().len());
+//! ```
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+use core::fmt;
+use std::path::{Path, PathBuf};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    binding::{BindGroups, Dressup},
+    block::{parse_block_spans, parse_blocks, Block, BlockCursor},
+    charset::{detect_charset_with, Charset, DecodeError},
+    cursor::Cursors,
+    diagnostic::{parse_with_options, ParseOutcome, Severities, Warning},
+    error::{Located, ParseFailure},
+    kv::{parse_key_value, parse_key_value_with, segment_index, KeyValue},
+    line::{is_comment, lines},
+    options::{LimitExceeded, ParseOptions},
+    scope::split_scope,
+    seriko::Animation,
+    surfaces::{
+        alias_targets, build_surfaces, is_surface_header, parse_block_body_lenient_with, Collision,
+        SurfaceBlock, SurfaceBody, SurfacesDescript,
+    },
+    tooltip::Tooltips,
+    value::parse_u32,
+};
+
+mod arena;
+mod inventory;
+
+pub use arena::{load_shell_dir_in, ArenaBlock, ArenaFile, ArenaProject};
+pub use inventory::{FileKind, Inventory};
+
+/// File of a [`ShellProject`], decoded and checked.
+///
+/// [`ShellProject`]: crate::project::ShellProject
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ProjectFile {
+    path: PathBuf,
+    charset: Charset,
+    text: String,
+    outcome: ParseOutcome,
+    options: ParseOptions,
+}
+
+impl ProjectFile {
+    /// Reads the file at `path`, decoding it with the charset of its `charset` line or Shift_JIS,
+    /// and checks it as [`parse_with_options`] does with default severities.
+    ///
+    /// [`parse_with_options`]: crate::diagnostic::parse_with_options
+    fn load(path: PathBuf, options: &ParseOptions) -> Result<ProjectFile, ProjectError> {
+        let (charset, text) = read_text(&path, options)?;
+        let outcome = parse_with_options(&text, options, &Severities::default());
+
+        Ok(ProjectFile {
+            path,
+            charset,
+            text,
+            outcome,
+            options: *options,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the charset used to decode.
+    pub fn charset(&self) -> &Charset {
+        &self.charset
+    }
+
+    /// Returns the decoded text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the document with its errors and warnings.
+    pub fn outcome(&self) -> &ParseOutcome {
+        &self.outcome
+    }
+
+    /// Returns the options the file was parsed with.
+    pub(crate) fn options(&self) -> &ParseOptions {
+        &self.options
+    }
+
+    /// Returns entries outside blocks, in order. Lines which are not entries are skipped.
+    pub fn entries(&self) -> Vec<Located<KeyValue<'_>>> {
+        let spans = parse_block_spans(&self.text).unwrap_or_default();
+        let mut in_block = BlockCursor::new(&spans);
+
+        lines(&self.text)
+            .filter(|line| !in_block.contains(line.number) && !is_comment(line.text))
+            .filter_map(|line| {
+                all_consuming(|input| parse_key_value_with(input, &self.options))(line.text)
+                    .ok()
+                    .map(|(_, kv)| Located::new(line.number, kv))
+            })
+            .collect()
+    }
+
+    /// Returns blocks, or none when braces are broken.
+    pub fn blocks(&self) -> Vec<Block<'_>> {
+        parse_blocks(&self.text).unwrap_or_default()
+    }
+}
+
+/// Shell directory, with `descript.txt` and the `surfaces*.txt` in the order they are merged.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ShellProject {
+    descript: ProjectFile,
+    surfaces: Vec<ProjectFile>,
+    surfaces_descript: SurfacesDescript,
+}
+
+impl ShellProject {
+    pub fn descript(&self) -> &ProjectFile {
+        &self.descript
+    }
+
+    /// Returns `surfaces.txt` followed by `surfacesN.txt` in order of `N`.
+    pub fn surfaces(&self) -> &[ProjectFile] {
+        &self.surfaces
+    }
+
+    /// Returns the `descript` block of the first [`surfaces`] file with one, or the defaults.
+    ///
+    /// [`surfaces`]: crate::project::ShellProject::surfaces
+    pub fn surfaces_descript(&self) -> &SurfacesDescript {
+        &self.surfaces_descript
+    }
+
+    /// Returns collisions of `body` in the order of `collision-sort` of [`surfaces_descript`].
+    ///
+    /// [`surfaces_descript`]: crate::project::ShellProject::surfaces_descript
+    pub fn collisions_ordered<'b>(
+        &self,
+        body: &'b SurfaceBody,
+    ) -> impl Iterator<Item = &'b Collision> {
+        body.collisions_ordered(self.surfaces_descript.collision_sort)
+    }
+
+    /// Returns animations of `body` in the order of `animation-sort` of [`surfaces_descript`].
+    ///
+    /// [`surfaces_descript`]: crate::project::ShellProject::surfaces_descript
+    pub fn animations_ordered<'b>(
+        &self,
+        body: &'b SurfaceBody,
+    ) -> impl Iterator<Item = &'b Animation> {
+        body.animations_ordered(self.surfaces_descript.animation_sort)
+    }
+
+    /// Returns entries outside blocks of all [`surfaces`] files, with the path of each, in order.
+    ///
+    /// [`surfaces`]: crate::project::ShellProject::surfaces
+    pub fn surfaces_entries(&self) -> Vec<(&Path, Located<KeyValue<'_>>)> {
+        self.surfaces
+            .iter()
+            .flat_map(|file| file.entries().into_iter().map(|e| (file.path(), e)))
+            .collect()
+    }
+
+    /// Returns blocks of all [`surfaces`] files, with the path of each, in order.
+    ///
+    /// [`surfaces`]: crate::project::ShellProject::surfaces
+    pub fn blocks(&self) -> Vec<(&Path, Block<'_>)> {
+        self.surfaces
+            .iter()
+            .flat_map(|file| file.blocks().into_iter().map(|b| (file.path(), b)))
+            .collect()
+    }
+}
+
+/// `ProjectError` type.
+#[derive(Debug)]
+pub enum ProjectError {
+    /// The directory has no `descript.txt`.
+    MissingDescript(PathBuf),
+    /// The directory or a file can not be read.
+    Io(PathBuf, std::io::Error),
+    /// A file can not be decoded.
+    Decode(PathBuf, DecodeError),
+    /// The `descript` block of a surfaces file is malformed.
+    SurfacesDescript(PathBuf, Located<ParseFailure>),
+    /// A line of a file is over [`ParseOptions::limits`], as [`Lines::limited`] checks it.
+    ///
+    /// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+    /// [`Lines::limited`]: crate::line::Lines::limited
+    LimitExceeded(PathBuf, Located<LimitExceeded>),
+}
+
+impl ProjectError {
+    pub fn path(&self) -> &Path {
+        match self {
+            ProjectError::MissingDescript(path)
+            | ProjectError::Io(path, _)
+            | ProjectError::Decode(path, _)
+            | ProjectError::SurfacesDescript(path, _)
+            | ProjectError::LimitExceeded(path, _) => path,
+        }
+    }
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::MissingDescript(path) => {
+                write!(f, "{}: descript.txt not found", path.display())
+            }
+            ProjectError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ProjectError::Decode(path, e) => write!(f, "{}: {}", path.display(), e),
+            ProjectError::SurfacesDescript(path, e) => write!(f, "{}: {}", path.display(), e),
+            ProjectError::LimitExceeded(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProjectError::MissingDescript(_) => None,
+            ProjectError::Io(_, e) => Some(e),
+            ProjectError::Decode(_, e) => Some(e),
+            ProjectError::SurfacesDescript(_, e) => Some(&e.value),
+            ProjectError::LimitExceeded(_, e) => Some(&e.value),
+        }
+    }
+}
+
+/// Loads the shell directory at `path`.
+///
+/// Files are found ignoring ASCII case of their names, as Windows does.
+/// `surfaces.txt` and `surfacesN.txt` are optional, and loaded in order of `N` after `surfaces.txt`,
+/// so that `surfaces2.txt` comes before `surfaces10.txt`.
+/// Names differing only in case, on file systems keeping both, are taken in order of their paths,
+/// and the first `descript.txt` is loaded, so that the order of the directory listing never matters.
+/// Each file is decoded with the charset of its `charset` line or Shift_JIS, and checked with `options`.
+/// Files with a line over [`ParseOptions::limits`] fail with [`ProjectError::LimitExceeded`].
+///
+/// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+/// [`ProjectError::LimitExceeded`]: crate::project::ProjectError::LimitExceeded
+pub fn load_shell_dir(path: &Path, options: &ParseOptions) -> Result<ShellProject, ProjectError> {
+    let (descript, surfaces) = shell_files(path)?;
+    let surfaces = surfaces
+        .into_iter()
+        .map(|path| ProjectFile::load(path, options))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut surfaces_descript = SurfacesDescript::default();
+    if let Some((file, block)) = surfaces.iter().find_map(|file| {
+        file.blocks()
+            .into_iter()
+            .find(|b| b.header == "descript")
+            .map(|b| (file, b))
+    }) {
+        surfaces_descript = SurfacesDescript::from_block(&block)
+            .map_err(|e| ProjectError::SurfacesDescript(file.path.clone(), e))?;
+    }
+
+    Ok(ShellProject {
+        descript: ProjectFile::load(descript, options)?,
+        surfaces,
+        surfaces_descript,
+    })
+}
+
+/// Finds `descript.txt` and the surfaces files of the shell directory at `path`, in the order of [`load_shell_dir`].
+///
+/// [`load_shell_dir`]: crate::project::load_shell_dir
+fn shell_files(path: &Path) -> Result<(PathBuf, Vec<PathBuf>), ProjectError> {
+    let io_error = |e| ProjectError::Io(path.to_path_buf(), e);
+
+    let mut descript = None;
+    let mut surfaces = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        if !entry.path().is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_ascii_lowercase();
+        if name == "descript.txt" {
+            let path = entry.path();
+            if descript.as_ref().is_none_or(|d| path < *d) {
+                descript = Some(path);
+            }
+        } else if let Some(order) = surfaces_order(&name) {
+            surfaces.push((order, name, entry.path()));
+        }
+    }
+    surfaces.sort();
+
+    let descript = descript.ok_or_else(|| ProjectError::MissingDescript(path.to_path_buf()))?;
+    Ok((
+        descript,
+        surfaces.into_iter().map(|(_, _, path)| path).collect(),
+    ))
+}
+
+/// Reads the file at `path`, decoding it with the charset of its `charset` line or Shift_JIS,
+/// and checks its lines against [`ParseOptions::limits`].
+///
+/// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+fn read_text(path: &Path, options: &ParseOptions) -> Result<(Charset, String), ProjectError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) => return Err(ProjectError::Io(path.to_path_buf(), e)),
+    };
+    let charset = detect_charset_with(&bytes, options).unwrap_or(Charset::ShiftJIS);
+    let text = match charset.decode_with(&bytes, options) {
+        Ok(text) => text.into_owned(),
+        Err(e) => return Err(ProjectError::Decode(path.to_path_buf(), e)),
+    };
+    if let Some(Err(e)) = lines(&text).limited(options).find(|l| l.is_err()) {
+        return Err(ProjectError::LimitExceeded(path.to_path_buf(), e));
+    }
+    Ok((charset, text))
+}
+
+/// Returns the order of `surfaces.txt` or `surfacesN.txt` given in lowercase, where `surfaces.txt` is first.
+fn surfaces_order(name: &str) -> Option<Option<u32>> {
+    let digits = name.strip_prefix("surfaces")?.strip_suffix(".txt")?;
+    if digits.is_empty() {
+        return Some(None);
+    }
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().map(Some)
+}
+
+/// Cross-checks `descript.txt` against the surfaces files of `project`, returning warnings with the path
+/// of the file of each, in order of [`ShellProject::descript`] then [`ShellProject::surfaces`].
+///
+/// - [`Warning::UndefinedSurface`] for `<scope>.seriko.defaultsurface` and targets of
+///   `<scope>.surface.alias` blocks, such as `smile,[0,5]`, which are not defined by any surface block.
+/// - [`Warning::BindAnimationWithoutGroup`] at `animationN.interval` of bind animations,
+///   and [`Warning::BindGroupWithoutAnimation`] at `<scope>.bindgroupN.name`, paired as [`Dressup::build`] does.
+/// - [`Warning::UnresolvedCollision`] for cursors and tooltips in `descript.txt`
+///   whose collision is in no surface, including collisions of animations.
+///
+/// Malformed lines are skipped, as they are reported by [`ProjectFile::outcome`].
+///
+/// [`ShellProject::descript`]: crate::project::ShellProject::descript
+/// [`ShellProject::surfaces`]: crate::project::ShellProject::surfaces
+/// [`Warning::UndefinedSurface`]: crate::diagnostic::Warning::UndefinedSurface
+/// [`Warning::BindAnimationWithoutGroup`]: crate::diagnostic::Warning::BindAnimationWithoutGroup
+/// [`Warning::BindGroupWithoutAnimation`]: crate::diagnostic::Warning::BindGroupWithoutAnimation
+/// [`Warning::UnresolvedCollision`]: crate::diagnostic::Warning::UnresolvedCollision
+/// [`Dressup::build`]: crate::binding::Dressup::build
+/// [`ProjectFile::outcome`]: crate::project::ProjectFile::outcome
+pub fn validate(project: &ShellProject) -> Vec<(&Path, Located<Warning>)> {
+    // Warnings keyed by the index of the file, where `descript.txt` is `0`.
+    let mut warnings: Vec<(usize, Located<Warning>)> = Vec::new();
+
+    let mut blocks = Vec::new();
+    let mut aliases = Vec::new();
+    for (index, file) in project.surfaces.iter().enumerate() {
+        for block in file.blocks() {
+            if is_surface_header(block.header) {
+                let (body, _) = parse_block_body_lenient_with(&block.body, &file.options);
+                blocks.push((index + 1, SurfaceBlock { block, body }));
+            } else if block.header.ends_with(".surface.alias") {
+                aliases.push((index + 1, block));
+            }
+        }
+    }
+    let surface_blocks: Vec<_> = blocks.iter().map(|(_, b)| b.clone()).collect();
+    let (surfaces, _) = build_surfaces(&surface_blocks);
+    let collisions: BTreeSet<&str> = blocks
+        .iter()
+        .flat_map(|(_, b)| b.body.all_collision_names())
+        .collect();
+
+    let mut groups = BindGroups::default();
+    let mut group_lines = BTreeMap::new();
+    let descript = &project.descript;
+    for entry in descript.entries() {
+        let segments = entry.value.key_segments();
+        let value = entry.value.value;
+        let mut cursors = Cursors::default();
+        let mut tooltips = Tooltips::default();
+
+        let collision = match split_scope(&segments) {
+            Some((_, ["seriko", "defaultsurface"])) => {
+                if let Ok((_, id)) = all_consuming(parse_u32)(value) {
+                    if !surfaces.contains_key(&id) {
+                        warnings.push((0, Located::new(entry.line, Warning::UndefinedSurface(id))));
+                    }
+                }
+                continue;
+            }
+            Some((scope, [group, "name"])) => {
+                if let Ok(true) = groups.apply(&segments, value) {
+                    if let Some(id) = segment_index(group, "bindgroup") {
+                        group_lines.entry((scope, id)).or_insert(entry.line);
+                    }
+                }
+                continue;
+            }
+            Some((scope, _)) if cursors.apply(&segments, value) == Ok(true) => {
+                cursors.get(scope)[0].collision.clone()
+            }
+            Some((scope, _)) if tooltips.apply(&segments, value) => {
+                tooltips.get(scope)[0].collision.clone()
+            }
+            _ => continue,
+        };
+        if !collisions.contains(collision.as_str()) {
+            warnings.push((
+                0,
+                Located::new(entry.line, Warning::UnresolvedCollision(collision)),
+            ));
+        }
+    }
+
+    for (file, block) in aliases.iter() {
+        for line in block.body.iter() {
+            let Ok((_, kv)) = all_consuming(parse_key_value)(line.text) else {
+                continue;
+            };
+            let Ok((_, targets)) = all_consuming(alias_targets)(kv.value) else {
+                continue;
+            };
+            for id in targets.0 {
+                if !surfaces.contains_key(&id) {
+                    warnings.push((
+                        *file,
+                        Located::new(line.number, Warning::UndefinedSurface(id)),
+                    ));
+                }
+            }
+        }
+    }
+
+    // Blocks are given as surfaces by their indices, so that orphans are found per block.
+    let animations: Vec<Vec<Animation>> = blocks
+        .iter()
+        .map(|(_, b)| b.body.animations.values().cloned().collect())
+        .collect();
+    let animations: Vec<(u32, &[Animation])> = animations
+        .iter()
+        .enumerate()
+        .map(|(index, a)| (index as u32, a.as_slice()))
+        .collect();
+    let dressups: Vec<_> = groups
+        .scopes()
+        .into_iter()
+        .map(|scope| (scope, Dressup::build(&animations, &groups, scope)))
+        .collect();
+
+    for (index, (file, block)) in blocks.iter().enumerate() {
+        for animation in block.body.animations.values().filter(|a| a.is_bind()) {
+            let orphan = (index as u32, animation.id);
+            if dressups
+                .iter()
+                .all(|(_, d)| d.orphan_animations.contains(&orphan))
+            {
+                let line = interval_line(&block.block, animation.id);
+                warnings.push((
+                    *file,
+                    Located::new(line, Warning::BindAnimationWithoutGroup(animation.id)),
+                ));
+            }
+        }
+    }
+    for (scope, dressup) in dressups.iter() {
+        for id in dressup.orphan_groups.iter() {
+            warnings.push((
+                0,
+                Located::new(
+                    group_lines[&(*scope, *id)],
+                    Warning::BindGroupWithoutAnimation(*id),
+                ),
+            ));
+        }
+    }
+
+    warnings.sort_by_key(|(file, w)| (*file, w.line));
+    warnings
+        .into_iter()
+        .map(|(file, w)| match file {
+            0 => (descript.path(), w),
+            _ => (project.surfaces[file - 1].path(), w),
+        })
+        .collect()
+}
+
+/// Returns the line of `animationN.interval` in `block`, or of its header.
+fn interval_line(block: &Block, id: u32) -> usize {
+    let key = format!("animation{}.interval", id);
+    block
+        .body
+        .iter()
+        .find(|line| line.text.trim_start().starts_with(key.as_str()))
+        .map_or(block.header_line, |line| line.number)
+}
+
+#[cfg(all(test, feature = "encodings"))]
+mod tests {
+    use super::*;
+
+    mod load_shell_dir {
+        use super::*;
+
+        use crate::surfaces::{parse_block_body, SortOrder};
+
+        fn write(dir: &Path, name: &str, text: &str) {
+            std::fs::write(dir.join(name), text).unwrap();
+        }
+
+        #[test]
+        fn success_when_surfaces_in_order() {
+            let dir = tempfile::tempdir().unwrap();
+            write(
+                dir.path(),
+                "descript.txt",
+                "charset,UTF-8\r\nname,master\r\n",
+            );
+            write(dir.path(), "surfaces10.txt", "surface10\r\n{\r\n}\r\n");
+            write(dir.path(), "surfaces2.txt", "surface2\r\n{\r\n}\r\n");
+            write(
+                dir.path(),
+                "surfaces.txt",
+                "charset,UTF-8\r\nsurface0\r\n{\r\n}\r\n",
+            );
+            write(dir.path(), "surfaces_old.txt", "surface9\r\n{\r\n}\r\n");
+
+            let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+            let names: Vec<_> = project
+                .surfaces()
+                .iter()
+                .map(|f| f.path().file_name().unwrap().to_str().unwrap())
+                .collect();
+            assert_eq!(
+                names,
+                vec!["surfaces.txt", "surfaces2.txt", "surfaces10.txt"]
+            );
+
+            let headers: Vec<_> = project.blocks().iter().map(|(_, b)| b.header).collect();
+            assert_eq!(headers, vec!["surface0", "surface2", "surface10"]);
+            assert_eq!(project.surfaces_entries().len(), 1);
+            assert_eq!(project.descript().entries()[1].value.value, "master");
+        }
+
+        #[test]
+        fn success_when_sort_from_surfaces_descript() {
+            let dir = tempfile::tempdir().unwrap();
+            write(dir.path(), "descript.txt", "name,master\r\n");
+            write(
+                dir.path(),
+                "surfaces.txt",
+                "descript\r\n{\r\ncollision-sort,descend\r\n}\r\nsurface0\r\n{\r\ncollision0,0,0,1,1,A\r\ncollision1,0,0,1,1,B\r\n}\r\n",
+            );
+            write(
+                dir.path(),
+                "surfaces2.txt",
+                "descript\r\n{\r\ncollision-sort,ascend\r\n}\r\n",
+            );
+
+            let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+            assert_eq!(
+                project.surfaces_descript().collision_sort,
+                SortOrder::Descend
+            );
+
+            let (_, block) = &project.blocks()[1];
+            let body = parse_block_body(&block.body).unwrap();
+            let names: Vec<_> = project
+                .collisions_ordered(&body)
+                .map(|c| c.name.as_str())
+                .collect();
+            assert_eq!(names, vec!["B", "A"]);
+            assert_eq!(project.animations_ordered(&body).count(), 0);
+
+            write(
+                dir.path(),
+                "surfaces.txt",
+                "descript\r\n{\r\ncollision-sort,up\r\n}\r\n",
+            );
+            let e = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap_err();
+            assert!(matches!(
+                e,
+                ProjectError::SurfacesDescript(_, Located { line: 3, .. })
+            ));
+        }
+
+        #[test]
+        fn success_when_uppercase_names() {
+            let dir = tempfile::tempdir().unwrap();
+            write(
+                dir.path(),
+                "DESCRIPT.TXT",
+                "charset,UTF-8\r\nname,master\r\n",
+            );
+            write(
+                dir.path(),
+                "Surfaces.TXT",
+                "surface0\r\n{\r\nsparkle,1\r\n}\r\n",
+            );
+
+            let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+            assert_eq!(project.descript().charset(), &Charset::UTF8);
+            assert_eq!(project.surfaces().len(), 1);
+            assert_eq!(project.surfaces()[0].charset(), &Charset::ShiftJIS);
+            assert_eq!(project.surfaces()[0].outcome().warnings.len(), 1);
+        }
+
+        #[test]
+        fn success_when_names_differ_in_case() {
+            let dir = tempfile::tempdir().unwrap();
+            write(dir.path(), "descript.txt", "name,lower\r\n");
+            write(dir.path(), "DESCRIPT.TXT", "name,upper\r\n");
+            write(dir.path(), "surfaces.txt", "surface1\r\n{\r\n}\r\n");
+            write(dir.path(), "SURFACES.TXT", "surface0\r\n{\r\n}\r\n");
+
+            let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+            assert_eq!(project.descript().entries()[0].value.value, "upper");
+            let headers: Vec<_> = project.blocks().iter().map(|(_, b)| b.header).collect();
+            assert_eq!(headers, vec!["surface0", "surface1"]);
+        }
+
+        #[test]
+        fn failed_when_missing_descript() {
+            let dir = tempfile::tempdir().unwrap();
+            write(dir.path(), "surfaces.txt", "surface0\r\n{\r\n}\r\n");
+            std::fs::create_dir(dir.path().join("descript.txt.d")).unwrap();
+
+            let e = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap_err();
+            assert!(matches!(&e, ProjectError::MissingDescript(p) if p == dir.path()));
+
+            write(dir.path(), "descript.txt", "");
+            let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+            assert!(project.descript().entries().is_empty());
+        }
+
+        #[test]
+        fn failed_when_line_over_limit() {
+            use crate::options::{Limit, Limits};
+
+            let dir = tempfile::tempdir().unwrap();
+            write(dir.path(), "descript.txt", "name,master\r\n");
+            write(
+                dir.path(),
+                "surfaces2.txt",
+                &format!("surface0\r\n{{\r\n//{}\r\n}}\r\n", "a".repeat(100)),
+            );
+            let options = ParseOptions::STRICT.limits(Limits::DEFAULT.max_line_length(99));
+
+            let e = load_shell_dir(dir.path(), &options).unwrap_err();
+            assert_eq!(e.path(), dir.path().join("surfaces2.txt"));
+            assert!(matches!(
+                e,
+                ProjectError::LimitExceeded(
+                    _,
+                    Located {
+                        line: 3,
+                        value: LimitExceeded {
+                            limit: Limit::LineLength,
+                            max: 99
+                        }
+                    }
+                )
+            ));
+
+            let arena = crate::arena::ParseArena::new();
+            let e = load_shell_dir_in(dir.path(), &options, &arena).unwrap_err();
+            assert!(matches!(e, ProjectError::LimitExceeded(..)));
+            assert!(load_shell_dir(dir.path(), &ParseOptions::STRICT).is_ok());
+        }
+    }
+
+    mod validate {
+        use super::*;
+
+        use alloc::string::ToString;
+
+        const DESCRIPT: &str = "charset,UTF-8\r\nsakura.seriko.defaultsurface,0\r\nkero.seriko.defaultsurface,10\r\nsakura.bindgroup1.name,Hat,Ribbon\r\nsakura.bindgroup2.name,Arm,Bag\r\nsakura.cursor.mouseup0,Head,head.cur\r\nsakura.cursor.mouseup1,Tail,tail.cur\r\nsakura.tooltip.Bust,bust\r\nsakura.tooltip.Wing,wing\r\n";
+        const SURFACES: &str = "charset,UTF-8\r\nsurface0-1\r\n{\r\ncollision0,0,0,10,10,Head\r\ncollisionex1,Bust,rect,0,0,10,10\r\nanimation1.interval,bind\r\nanimation1.pattern0,overlay,100,0,0,0\r\nanimation3.interval,bind\r\n}\r\nsakura.surface.alias\r\n{\r\n0,[0]\r\nsmile,[1,5]\r\n}\r\n";
+
+        fn load(descript: &str, surfaces: &str) -> (tempfile::TempDir, ShellProject) {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("descript.txt"), descript).unwrap();
+            std::fs::write(dir.path().join("surfaces.txt"), surfaces).unwrap();
+            let project = load_shell_dir(dir.path(), &ParseOptions::COMPATIBLE).unwrap();
+            (dir, project)
+        }
+
+        #[test]
+        fn detected_when_each_problem() {
+            let (_dir, project) = load(DESCRIPT, SURFACES);
+            let warnings: Vec<_> = validate(&project)
+                .into_iter()
+                .map(|(path, w)| (path.file_name().unwrap().to_str().unwrap(), w))
+                .collect();
+
+            assert_eq!(
+                warnings,
+                vec![
+                    (
+                        "descript.txt",
+                        Located::new(3, Warning::UndefinedSurface(10))
+                    ),
+                    (
+                        "descript.txt",
+                        Located::new(5, Warning::BindGroupWithoutAnimation(2))
+                    ),
+                    (
+                        "descript.txt",
+                        Located::new(7, Warning::UnresolvedCollision("Tail".to_string()))
+                    ),
+                    (
+                        "descript.txt",
+                        Located::new(9, Warning::UnresolvedCollision("Wing".to_string()))
+                    ),
+                    (
+                        "surfaces.txt",
+                        Located::new(8, Warning::BindAnimationWithoutGroup(3))
+                    ),
+                    (
+                        "surfaces.txt",
+                        Located::new(13, Warning::UndefinedSurface(5))
+                    ),
+                ]
+            );
+            assert_eq!(
+                warnings[4].1.value.to_string(),
+                "bind animation3 without bindgroup3"
+            );
+        }
+
+        #[test]
+        fn success_when_clean_project() {
+            let descript = "charset,UTF-8\r\nsakura.seriko.defaultsurface,1\r\nsakura.bindgroup1.name,Hat,Ribbon\r\nsakura.cursor.mouseup0,Head,head.cur\r\nsakura.tooltip.Bust,bust\r\n";
+            let surfaces = "charset,UTF-8\r\nsurface0-1\r\n{\r\ncollision0,0,0,10,10,Head\r\nanimation1.interval,bind\r\nanimation1.pattern0,overlay,100,0,0,0\r\n}\r\nsurface.append1\r\n{\r\ncollisionex1,Bust,rect,0,0,10,10\r\n}\r\nsakura.surface.alias\r\n{\r\nsmile,[0,1]\r\n}\r\n";
+            let (_dir, project) = load(descript, surfaces);
+
+            assert_eq!(validate(&project), vec![]);
+        }
+    }
+}
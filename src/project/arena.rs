@@ -0,0 +1,274 @@
+//! Shell directories loaded into a [`ParseArena`].
+//!
+//! [`ParseArena`]: crate::arena::ParseArena
+
+use alloc::vec::Vec;
+use std::path::{Path, PathBuf};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    arena::ParseArena,
+    block::{parse_block_spans, Block, BlockCursor},
+    charset::Charset,
+    error::Located,
+    kv::{parse_key_value_with, KeyValue},
+    line::{is_comment, lines, Line},
+    options::ParseOptions,
+    surfaces::SurfacesDescript,
+};
+
+use super::{read_text, shell_files, ProjectError};
+
+/// Block of an [`ArenaFile`], with its lines in the arena.
+///
+/// [`ArenaFile`]: crate::project::ArenaFile
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ArenaBlock<'a> {
+    /// Header text, trimmed.
+    pub header: &'a str,
+    /// Line number of the header.
+    pub header_line: usize,
+    /// Lines between the braces, including blank and comment lines.
+    pub body: &'a [Line<'a>],
+    /// Line number of the closing brace.
+    pub close_line: usize,
+}
+
+impl<'a> ArenaBlock<'a> {
+    /// Makes the [`Block`] of the same lines, for functions taking one.
+    ///
+    /// [`Block`]: crate::block::Block
+    pub fn to_block(&self) -> Block<'a> {
+        Block {
+            header: self.header,
+            header_line: self.header_line,
+            body: self.body.to_vec(),
+            close_line: self.close_line,
+        }
+    }
+}
+
+impl PartialEq<Block<'_>> for ArenaBlock<'_> {
+    fn eq(&self, other: &Block<'_>) -> bool {
+        self.header == other.header
+            && self.header_line == other.header_line
+            && self.body == other.body.as_slice()
+            && self.close_line == other.close_line
+    }
+}
+
+/// File of an [`ArenaProject`], decoded and parsed into the arena.
+///
+/// Diagnostics are not kept, as they are by [`ProjectFile::outcome`].
+///
+/// [`ArenaProject`]: crate::project::ArenaProject
+/// [`ProjectFile::outcome`]: crate::project::ProjectFile::outcome
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ArenaFile<'a> {
+    path: PathBuf,
+    charset: Charset,
+    text: &'a str,
+    entries: &'a [Located<KeyValue<'a>>],
+    blocks: &'a [ArenaBlock<'a>],
+}
+
+impl<'a> ArenaFile<'a> {
+    fn load(
+        path: PathBuf,
+        options: &ParseOptions,
+        arena: &'a ParseArena,
+        scratch: &mut Scratch<'a>,
+    ) -> Result<ArenaFile<'a>, ProjectError> {
+        let (charset, decoded) = read_text(&path, options)?;
+        let text = arena.alloc_str(&decoded);
+        drop(decoded);
+
+        let spans = parse_block_spans(text).unwrap_or_default();
+        scratch.blocks.clear();
+        for span in spans.iter() {
+            scratch.lines.clear();
+            scratch.lines.extend(span.body_lines(text));
+            scratch.blocks.push(ArenaBlock {
+                header: span.header(text),
+                header_line: span.header_line,
+                body: arena.alloc_slice_clone(&scratch.lines),
+                close_line: span.close_line,
+            });
+        }
+
+        let mut in_block = BlockCursor::new(&spans);
+        scratch.entries.clear();
+        scratch.entries.extend(
+            lines(text)
+                .filter(|line| !in_block.contains(line.number) && !is_comment(line.text))
+                .filter_map(|line| {
+                    all_consuming(|input| parse_key_value_with(input, options))(line.text)
+                        .ok()
+                        .map(|(_, kv)| Located::new(line.number, kv))
+                }),
+        );
+
+        Ok(ArenaFile {
+            path,
+            charset,
+            text,
+            entries: arena.alloc_slice_clone(&scratch.entries),
+            blocks: arena.alloc_slice_clone(&scratch.blocks),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the charset used to decode.
+    pub fn charset(&self) -> &Charset {
+        &self.charset
+    }
+
+    /// Returns the decoded text.
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns entries outside blocks, in order, as [`ProjectFile::entries`] does.
+    ///
+    /// [`ProjectFile::entries`]: crate::project::ProjectFile::entries
+    pub fn entries(&self) -> &'a [Located<KeyValue<'a>>] {
+        self.entries
+    }
+
+    /// Returns blocks, or none when braces are broken, as [`ProjectFile::blocks`] does.
+    ///
+    /// [`ProjectFile::blocks`]: crate::project::ProjectFile::blocks
+    pub fn blocks(&self) -> &'a [ArenaBlock<'a>] {
+        self.blocks
+    }
+}
+
+/// Buffers reused by the files of a project, copied into the arena for each file.
+#[derive(Default)]
+struct Scratch<'a> {
+    lines: Vec<Line<'a>>,
+    entries: Vec<Located<KeyValue<'a>>>,
+    blocks: Vec<ArenaBlock<'a>>,
+}
+
+/// [`ShellProject`] loaded by [`load_shell_dir_in`], with texts, entries and blocks in a [`ParseArena`].
+///
+/// [`ShellProject`]: crate::project::ShellProject
+/// [`load_shell_dir_in`]: crate::project::load_shell_dir_in
+/// [`ParseArena`]: crate::arena::ParseArena
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArenaProject<'a> {
+    descript: ArenaFile<'a>,
+    surfaces: Vec<ArenaFile<'a>>,
+    surfaces_descript: SurfacesDescript,
+}
+
+impl<'a> ArenaProject<'a> {
+    pub fn descript(&self) -> &ArenaFile<'a> {
+        &self.descript
+    }
+
+    /// Returns `surfaces.txt` followed by `surfacesN.txt` in order of `N`.
+    pub fn surfaces(&self) -> &[ArenaFile<'a>] {
+        &self.surfaces
+    }
+
+    /// Returns the `descript` block of the first [`surfaces`] file with one, or the defaults.
+    ///
+    /// [`surfaces`]: crate::project::ArenaProject::surfaces
+    pub fn surfaces_descript(&self) -> &SurfacesDescript {
+        &self.surfaces_descript
+    }
+
+    /// Returns blocks of all [`surfaces`] files, with the path of each, in order.
+    ///
+    /// [`surfaces`]: crate::project::ArenaProject::surfaces
+    pub fn blocks(&self) -> impl Iterator<Item = (&Path, &ArenaBlock<'a>)> {
+        self.surfaces
+            .iter()
+            .flat_map(|file| file.blocks.iter().map(|b| (file.path(), b)))
+    }
+}
+
+/// Same as [`load_shell_dir`], with the texts, entries and blocks of the files allocated in `arena`,
+/// instead of many small allocations of each.
+///
+/// Files are found, decoded and checked against limits as [`load_shell_dir`] does, and their diagnostics are not kept.
+///
+/// [`load_shell_dir`]: crate::project::load_shell_dir
+pub fn load_shell_dir_in<'a>(
+    path: &Path,
+    options: &ParseOptions,
+    arena: &'a ParseArena,
+) -> Result<ArenaProject<'a>, ProjectError> {
+    let (descript, surfaces) = shell_files(path)?;
+    let mut scratch = Scratch::default();
+    let surfaces = surfaces
+        .into_iter()
+        .map(|path| ArenaFile::load(path, options, arena, &mut scratch))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut surfaces_descript = SurfacesDescript::default();
+    if let Some((file, block)) = surfaces.iter().find_map(|file| {
+        file.blocks
+            .iter()
+            .find(|b| b.header == "descript")
+            .map(|b| (file, b))
+    }) {
+        surfaces_descript = SurfacesDescript::from_block(&block.to_block())
+            .map_err(|e| ProjectError::SurfacesDescript(file.path.clone(), e))?;
+    }
+
+    Ok(ArenaProject {
+        descript: ArenaFile::load(descript, options, arena, &mut scratch)?,
+        surfaces,
+        surfaces_descript,
+    })
+}
+
+#[cfg(all(test, feature = "encodings"))]
+mod tests {
+    use super::*;
+
+    use crate::project::load_shell_dir;
+
+    mod load_shell_dir_in {
+        use super::*;
+
+        #[test]
+        fn success_when_same_as_load_shell_dir() {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(
+                dir.path().join("descript.txt"),
+                "charset,UTF-8\r\nname,master\r\n",
+            )
+            .unwrap();
+            std::fs::write(
+                dir.path().join("surfaces.txt"),
+                "charset,UTF-8\r\ndescript\r\n{\r\ncollision-sort,descend\r\n}\r\n// body\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\nsakura.name,Emily\r\n",
+            )
+            .unwrap();
+            std::fs::write(dir.path().join("surfaces2.txt"), "surface1\r\n{\r\n").unwrap();
+
+            let project = load_shell_dir(dir.path(), &ParseOptions::STRICT).unwrap();
+            let arena = ParseArena::new();
+            let in_arena = load_shell_dir_in(dir.path(), &ParseOptions::STRICT, &arena).unwrap();
+
+            assert_eq!(in_arena.surfaces().len(), 2);
+            for (file, expected) in in_arena.surfaces().iter().zip(project.surfaces()) {
+                assert_eq!(file.path(), expected.path());
+                assert_eq!(file.text(), expected.text());
+                assert_eq!(file.entries(), expected.entries());
+                assert_eq!(file.blocks(), expected.blocks());
+            }
+            assert_eq!(in_arena.descript().entries(), project.descript().entries());
+            assert_eq!(in_arena.surfaces_descript(), project.surfaces_descript());
+            assert!(in_arena.surfaces()[1].blocks().is_empty());
+            assert!(arena.used_bytes() > 0);
+        }
+    }
+}
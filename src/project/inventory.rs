@@ -0,0 +1,234 @@
+//! Facts about a shell, such as its surfaces and the files it refers.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    charset::{parse_charset, Charset},
+    cursor::Cursors,
+    descript::SerikoSettings,
+    path::RelativePath,
+    surfaces::{
+        build_surfaces, is_surface_header, parse_block_body_lenient_with, AlphaSource, SurfaceBlock,
+    },
+    value::parse_filename,
+};
+
+use super::{ProjectFile, ShellProject};
+
+/// Kind of a file referred by a shell.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+#[non_exhaustive]
+pub enum FileKind {
+    /// Image of `elementN` in a surface block.
+    ElementImage,
+    /// `menu.background.bitmap.filename` and the other bitmaps of the menu.
+    MenuBitmap,
+    /// Cursor, in `descript.txt` or in a surface block.
+    Cursor,
+    /// PNA file giving the alpha of an element image, unless `seriko.use_self_alpha` is set.
+    Pna,
+}
+
+/// Facts about a [`ShellProject`], given by [`Inventory::from`].
+///
+/// Filenames are taken from parsed entries, with separators normalized by [`RelativePath`].
+/// Images of surfaces themselves are not listed, as no entry refers them.
+///
+/// [`ShellProject`]: crate::project::ShellProject
+/// [`Inventory::from`]: crate::project::Inventory::from
+/// [`RelativePath`]: crate::path::RelativePath
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Inventory {
+    /// Number of surface IDs defined, including those defined only by `surface.appendN`.
+    pub surface_count: usize,
+    /// Continuous ranges of the surface IDs, in order.
+    pub surface_ranges: Vec<RangeInclusive<u32>>,
+    /// Number of animations of each surface, with `surface.appendN` applied.
+    pub animation_counts: BTreeMap<u32, usize>,
+    /// Charset of the `charset` line of each file, `descript.txt` first,
+    /// or `None` when it has no such line.
+    pub charsets: Vec<(PathBuf, Option<Charset>)>,
+    /// Filenames referred, deduplicated and sorted per kind.
+    pub files: BTreeMap<FileKind, BTreeSet<String>>,
+}
+
+/// Keys of `descript.txt` with bitmaps of the menu.
+const MENU_BITMAP_KEYS: &[&str] = &[
+    "menu.background.bitmap.filename",
+    "menu.foreground.bitmap.filename",
+    "menu.sidebar.bitmap.filename",
+];
+
+impl Inventory {
+    /// Returns the filenames of `kind` in order.
+    pub fn files_of(&self, kind: FileKind) -> impl Iterator<Item = &str> {
+        self.files
+            .get(&kind)
+            .into_iter()
+            .flat_map(|files| files.iter().map(|f| f.as_str()))
+    }
+
+    fn insert_file(&mut self, kind: FileKind, filename: &str) {
+        let filename = RelativePath::new(filename).to_string();
+        self.files.entry(kind).or_default().insert(filename);
+    }
+}
+
+impl From<&ShellProject> for Inventory {
+    fn from(project: &ShellProject) -> Self {
+        let mut inventory = Inventory::default();
+
+        let mut seriko = SerikoSettings::default();
+        let mut cursors = Cursors::default();
+        for entry in project.descript.entries() {
+            let segments = entry.value.key_segments();
+            let value = entry.value.value;
+            if MENU_BITMAP_KEYS.contains(&entry.value.key) {
+                if let Ok((_, filename)) = all_consuming(parse_filename)(value) {
+                    inventory.insert_file(FileKind::MenuBitmap, filename);
+                }
+            }
+            // Malformed lines are skipped, as they are reported by `ProjectFile::outcome`.
+            let _ = seriko.apply(&segments, value);
+            let _ = cursors.apply(&segments, value);
+        }
+        for binding in cursors.iter().flat_map(|(_, c)| c.iter()) {
+            inventory.insert_file(FileKind::Cursor, &binding.filename);
+        }
+
+        let mut blocks = Vec::new();
+        for file in project.surfaces.iter() {
+            for block in file.blocks() {
+                if is_surface_header(block.header) {
+                    let (body, _) = parse_block_body_lenient_with(&block.body, &file.options);
+                    blocks.push(SurfaceBlock { block, body });
+                }
+            }
+        }
+        for body in blocks.iter().map(|b| &b.body) {
+            for element in body.elements.iter() {
+                inventory.insert_file(FileKind::ElementImage, &element.filename);
+                if let AlphaSource::PnaFile(pna) = AlphaSource::resolve(&seriko, &element.filename)
+                {
+                    inventory.insert_file(FileKind::Pna, pna.as_str());
+                }
+            }
+            for binding in body.cursors.cursors.iter() {
+                inventory.insert_file(FileKind::Cursor, &binding.filename);
+            }
+        }
+
+        let (surfaces, _) = build_surfaces(&blocks);
+        inventory.surface_count = surfaces.len();
+        for id in surfaces.keys().copied() {
+            match inventory.surface_ranges.last_mut() {
+                Some(range) if range.end().checked_add(1) == Some(id) => {
+                    *range = *range.start()..=id;
+                }
+                _ => inventory.surface_ranges.push(id..=id),
+            }
+        }
+        inventory.animation_counts = surfaces
+            .iter()
+            .map(|(id, s)| (*id, s.animations.len()))
+            .collect();
+
+        inventory.charsets = core::iter::once(&project.descript)
+            .chain(project.surfaces.iter())
+            .map(|file| (file.path().to_path_buf(), declared_charset(file)))
+            .collect();
+
+        inventory
+    }
+}
+
+fn declared_charset(file: &ProjectFile) -> Option<Charset> {
+    file.entries()
+        .into_iter()
+        .find(|e| e.value.key == "charset")
+        .and_then(|e| all_consuming(parse_charset)(e.value.value).ok())
+        .map(|(_, charset)| charset)
+}
+
+#[cfg(all(test, feature = "encodings"))]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::{options::ParseOptions, project::load_shell_dir};
+
+    const DESCRIPT: &str = "charset,UTF-8\r\nmenu.background.bitmap.filename,menu\\back.png\r\nmenu.sidebar.bitmap.filename,side.png\r\nsakura.cursor.mouseup0,Head,head.cur\r\nkero.cursor.mousedown0,Tail,head.cur\r\n";
+    const SURFACES: &str = "charset,Shift_JIS\r\nsurface0-2,surface10\r\n{\r\nelement0,base,body.png,0,0\r\nelement1,overlay,parts\\arm.png,0,0\r\nanimation0.interval,sometimes\r\nanimation0.pattern0,overlay,100,0,0,0\r\n}\r\nsurface.append1\r\n{\r\nelement2,overlay,face.bmp,0,0\r\nanimation1.interval,bind\r\ncursor.mouseup0,Bust,bust.cur\r\n}\r\n";
+
+    fn load(descript: &str) -> (tempfile::TempDir, ShellProject) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("descript.txt"), descript).unwrap();
+        std::fs::write(dir.path().join("surfaces.txt"), SURFACES).unwrap();
+        std::fs::write(dir.path().join("surfaces2.txt"), "surface11\r\n{\r\n}\r\n").unwrap();
+        let project = load_shell_dir(dir.path(), &ParseOptions::COMPATIBLE).unwrap();
+        (dir, project)
+    }
+
+    fn names(paths: &[(PathBuf, Option<Charset>)]) -> Vec<(&str, Option<Charset>)> {
+        paths
+            .iter()
+            .map(|(p, c)| (p.file_name().unwrap().to_str().unwrap(), c.clone()))
+            .collect()
+    }
+
+    mod inventory {
+        use super::*;
+
+        #[test]
+        fn success_when_fixture_project() {
+            let (_dir, project) = load(DESCRIPT);
+            let inventory = Inventory::from(&project);
+
+            assert_eq!(inventory.surface_count, 5);
+            assert_eq!(inventory.surface_ranges, vec![0..=2, 10..=11]);
+            assert_eq!(
+                inventory.animation_counts,
+                BTreeMap::from([(0, 1), (1, 2), (2, 1), (10, 1), (11, 0)])
+            );
+            assert_eq!(
+                names(&inventory.charsets),
+                vec![
+                    ("descript.txt", Some(Charset::UTF8)),
+                    ("surfaces.txt", Some(Charset::ShiftJIS)),
+                    ("surfaces2.txt", None),
+                ]
+            );
+
+            let files = |kind| inventory.files_of(kind).collect::<Vec<_>>();
+            assert_eq!(
+                files(FileKind::ElementImage),
+                vec!["body.png", "face.bmp", "parts/arm.png"]
+            );
+            assert_eq!(
+                files(FileKind::MenuBitmap),
+                vec!["menu/back.png", "side.png"]
+            );
+            assert_eq!(files(FileKind::Cursor), vec!["bust.cur", "head.cur"]);
+            assert_eq!(files(FileKind::Pna), vec!["body.pna", "parts/arm.pna"]);
+        }
+
+        #[test]
+        fn success_when_self_alpha() {
+            let descript = [DESCRIPT, "seriko.use_self_alpha,1\r\n"].concat();
+            let (_dir, project) = load(&descript);
+            let inventory = Inventory::from(&project);
+
+            assert_eq!(inventory.files_of(FileKind::Pna).count(), 0);
+            assert_eq!(inventory.files_of(FileKind::ElementImage).count(), 3);
+        }
+    }
+}
@@ -0,0 +1,196 @@
+//! # `query`
+//!
+//! Queries over parsed entries, by key prefix, scope and embedded index.
+//! Results borrow from the entries.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::error::Located;
+//! use shell_parser_common_rs::kv::KeyValue;
+//! use shell_parser_common_rs::query::EntryQuery;
+//! use shell_parser_common_rs::scope::Scope;
+//!
+//! let entries = vec![
+//!     Located::new(1, KeyValue::new("sakura.bindgroup0.name", "Hat,Ribbon")),
+//!     Located::new(2, KeyValue::new("kero.bindgroup0.name", "Hat,Cap")),
+//!     Located::new(3, KeyValue::new("menu.font.name", "MS Gothic")),
+//! ];
+//!
+//! assert_eq!(entries.entries_with_prefix("menu.").count(), 1);
+//! assert_eq!(entries.entries_in_scope(Scope::Kero).next().unwrap().line, 2);
+//! assert_eq!(entries.grouped_by_index("sakura.bindgroup")[&0].len(), 1);
+//! ```
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use crate::{
+    error::Located,
+    kv::{segment_index, KeyValue},
+    scope::Scope,
+};
+
+/// Query helpers on a collection of located entries.
+pub trait EntryQuery<'a> {
+    /// Returns entries whose key starts with `prefix`, in order.
+    fn entries_with_prefix<'s>(
+        &'s self,
+        prefix: &'s str,
+    ) -> impl Iterator<Item = &'s Located<KeyValue<'a>>>
+    where
+        'a: 's;
+
+    /// Returns entries whose first key segment is `scope`, in order.
+    fn entries_in_scope<'s>(
+        &'s self,
+        scope: Scope,
+    ) -> impl Iterator<Item = &'s Located<KeyValue<'a>>>
+    where
+        'a: 's;
+
+    /// Buckets entries such as `bindgroup0.*` and `bindgroup1.*` by their index,
+    /// with `prefix` `"bindgroup"`.
+    ///
+    /// Segments before the indexed one are given in `prefix` (e.g. `"sakura.bindgroup"`).
+    /// Entries keep their order in each bucket.
+    fn grouped_by_index<'s>(
+        &'s self,
+        prefix: &str,
+    ) -> BTreeMap<u32, Vec<&'s Located<KeyValue<'a>>>>
+    where
+        'a: 's;
+}
+
+impl<'a> EntryQuery<'a> for [Located<KeyValue<'a>>] {
+    fn entries_with_prefix<'s>(
+        &'s self,
+        prefix: &'s str,
+    ) -> impl Iterator<Item = &'s Located<KeyValue<'a>>>
+    where
+        'a: 's,
+    {
+        self.iter().filter(move |e| e.value.key.starts_with(prefix))
+    }
+
+    fn entries_in_scope<'s>(
+        &'s self,
+        scope: Scope,
+    ) -> impl Iterator<Item = &'s Located<KeyValue<'a>>>
+    where
+        'a: 's,
+    {
+        self.iter().filter(move |e| {
+            let first = e.value.key.split('.').next().unwrap_or_default();
+            Scope::from_segment(first) == Some(scope)
+        })
+    }
+
+    fn grouped_by_index<'s>(&'s self, prefix: &str) -> BTreeMap<u32, Vec<&'s Located<KeyValue<'a>>>>
+    where
+        'a: 's,
+    {
+        let mut groups: BTreeMap<u32, Vec<_>> = BTreeMap::new();
+        for entry in self.iter() {
+            if let Some(index) = index_after(entry.value.key, prefix) {
+                groups.entry(index).or_default().push(entry);
+            }
+        }
+        groups
+    }
+}
+
+/// Returns the index of the segment following the leading segments of `prefix`.
+fn index_after(key: &str, prefix: &str) -> Option<u32> {
+    let (rest, last) = match prefix.rsplit_once('.') {
+        Some((head, last)) => (key.strip_prefix(head)?.strip_prefix('.')?, last),
+        None => (key, prefix),
+    };
+    let segment = rest.split('.').next()?;
+    segment_index(segment, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASE: &[(&str, &str)] = &[
+        ("charset", "UTF-8"),
+        ("sakura.bindgroup0.name", "Hat,Ribbon"),
+        ("sakura.bindgroup0.default", "1"),
+        ("sakura.bindgroup3.name", "Arm,Bag"),
+        ("sakura.bindgroup10.name", "Hat,Cap"),
+        ("sakura.bindgroupx.name", "x"),
+        ("kero.bindgroup1.name", "Hat,Wig"),
+        ("char2.balloon.offsetx", "10"),
+        ("character.name", "x"),
+        ("menu.font.name", "MS Gothic"),
+        ("menu.background.bitmap.filename", "menu.png"),
+        ("menuitem.x", "x"),
+    ];
+
+    fn entries() -> Vec<Located<KeyValue<'static>>> {
+        CASE.iter()
+            .enumerate()
+            .map(|(i, (k, v))| Located::new(i + 1, KeyValue::new(k, v)))
+            .collect()
+    }
+
+    fn lines<'s>(entries: impl Iterator<Item = &'s Located<KeyValue<'static>>>) -> Vec<usize> {
+        entries.map(|e| e.line).collect()
+    }
+
+    mod entries_with_prefix {
+        use super::*;
+
+        #[test]
+        fn success_when_dotted_prefix() {
+            let entries = entries();
+            assert_eq!(lines(entries.entries_with_prefix("menu.")), vec![10, 11]);
+            assert_eq!(
+                lines(entries.entries_with_prefix("sakura.bindgroup")),
+                vec![2, 3, 4, 5, 6]
+            );
+            assert_eq!(entries.entries_with_prefix("homeurl").count(), 0);
+        }
+    }
+
+    mod entries_in_scope {
+        use super::*;
+
+        #[test]
+        fn success_when_each_scope() {
+            let entries = entries();
+            assert_eq!(
+                lines(entries.entries_in_scope(Scope::Sakura)),
+                vec![2, 3, 4, 5, 6]
+            );
+            assert_eq!(lines(entries.entries_in_scope(Scope::Kero)), vec![7]);
+            assert_eq!(lines(entries.entries_in_scope(Scope::Char(2))), vec![8]);
+            assert_eq!(entries.entries_in_scope(Scope::Char(3)).count(), 0);
+        }
+    }
+
+    mod grouped_by_index {
+        use super::*;
+
+        #[test]
+        fn success_when_gaps_in_indices() {
+            let entries = entries();
+            let groups = entries.grouped_by_index("sakura.bindgroup");
+            assert_eq!(groups.keys().copied().collect::<Vec<_>>(), vec![0, 3, 10]);
+            assert_eq!(lines(groups[&0].iter().copied()), vec![2, 3]);
+            assert_eq!(groups[&10][0].value.value, "Hat,Cap");
+        }
+
+        #[test]
+        fn success_when_no_leading_segments() {
+            let entries = [
+                Located::new(1, KeyValue::new("element2", "base,a.png,0,0")),
+                Located::new(2, KeyValue::new("element0", "base,b.png,0,0")),
+                Located::new(3, KeyValue::new("elements", "x")),
+            ];
+            let groups = entries.grouped_by_index("element");
+            assert_eq!(groups.keys().copied().collect::<Vec<_>>(), vec![0, 2]);
+        }
+    }
+}
@@ -0,0 +1,510 @@
+//! # `report`
+//!
+//! Reports of located errors and warnings with labeled spans, for renderers of rich diagnostics.
+//!
+//! A [`Report`] has a severity, a message and labels on byte spans of the source.
+//! It is rendered as plain text by [`Report::render`].
+//! Errors and warnings are also `miette::Diagnostic` with their source through [`Sourced`],
+//! labeled as in their reports.
+//!
+//! [`Report`]: crate::report::Report
+//! [`Report::render`]: crate::report::Report::render
+//! [`Sourced`]: crate::report::Sourced
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::diagnostic::parse_with_diagnostics;
+//! use shell_parser_common_rs::report::reports;
+//!
+//! let case = "name,a\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n";
+//! let outcome = parse_with_diagnostics(case);
+//! let reports = reports(case, &outcome);
+//!
+//! assert_eq!(reports[0].message, "unclosed block");
+//! assert_eq!(reports[0].labels.len(), 2);
+//! assert!(reports[0].render(case).contains("block opened here"));
+//! ```
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{fmt, ops::Range};
+
+use crate::{
+    block::BlockError,
+    diagnostic::{ParseOutcome, Severity, Warning},
+    error::{Located, ParseFailure},
+    line::{lines, Line},
+    lint,
+    text::excerpt_around,
+};
+
+/// Number of characters of a line rendered by [`Report::render`] without excerpting it.
+///
+/// [`Report::render`]: crate::report::Report::render
+const MAX_RENDERED_CHARS: usize = 80;
+
+/// Message on a byte span of the source.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Label {
+    pub span: Range<usize>,
+    pub message: String,
+    /// Whether the label is where the problem is, rather than a note on related lines.
+    pub primary: bool,
+}
+
+impl Label {
+    fn primary(span: Range<usize>, message: impl Into<String>) -> Label {
+        Label {
+            span,
+            message: message.into(),
+            primary: true,
+        }
+    }
+
+    fn secondary(span: Range<usize>, message: impl Into<String>) -> Label {
+        Label {
+            span,
+            message: message.into(),
+            primary: false,
+        }
+    }
+}
+
+/// Error or warning with labels on the source.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Report {
+    pub severity: Severity,
+    pub message: String,
+    /// Labels in order of their spans. One of them is primary.
+    pub labels: Vec<Label>,
+}
+
+impl Report {
+    /// Makes a report of `failure` in `source`, the text it was parsed from.
+    ///
+    /// [`BlockError::Unclosed`] is labeled at the end of `source`, with a note at the header.
+    /// Syntax errors are labeled at the field where they occurred.
+    ///
+    /// [`BlockError::Unclosed`]: crate::block::BlockError::Unclosed
+    pub fn from_failure(source: &str, failure: &Located<ParseFailure>) -> Report {
+        let line = nth_line(source, failure.line);
+        let labels = match &failure.value {
+            ParseFailure::Block(BlockError::Unclosed) => vec![
+                Label::secondary(trimmed_span(&line), "block opened here"),
+                Label::primary(source.len()..source.len(), "expected `}` before the end"),
+            ],
+            ParseFailure::Syntax {
+                contexts, fragment, ..
+            } => {
+                let message = contexts.first().copied().unwrap_or("here");
+                vec![Label::primary(fragment_span(&line, fragment), message)]
+            }
+            ParseFailure::Warning(warning) => warning_labels(&line, warning),
+            _ => vec![Label::primary(trimmed_span(&line), "")],
+        };
+
+        Report {
+            severity: Severity::Error,
+            message: failure.value.to_string(),
+            labels,
+        }
+    }
+
+    /// Makes a report of `warning` in `source`, the text it was parsed from.
+    pub fn from_warning(source: &str, warning: &Located<Warning>) -> Report {
+        let line = nth_line(source, warning.line);
+
+        Report {
+            severity: Severity::Warning,
+            message: warning.value.to_string(),
+            labels: warning_labels(&line, &warning.value),
+        }
+    }
+
+    /// Makes a report of `warning` of a lint pass in `source`, with its code before the message.
+    ///
+    /// The line is labeled with the suggestion of `warning`, or its code when it has none.
+    pub fn from_lint(source: &str, warning: &lint::Warning) -> Report {
+        let line = nth_line(source, warning.line);
+        let label = match &warning.suggestion {
+            Some(suggestion) => format!("write {:?}", suggestion),
+            None => warning.code.to_string(),
+        };
+
+        Report {
+            severity: warning.severity,
+            message: format!("[{}] {}", warning.code, warning.message),
+            labels: vec![Label::primary(trimmed_span(&line), label)],
+        }
+    }
+
+    /// Returns the primary label.
+    pub fn primary(&self) -> Option<&Label> {
+        self.labels.iter().find(|l| l.primary)
+    }
+
+    /// Renders the report with the lines of `source` its labels are on, marking the spans with carets.
+    ///
+    /// Lines longer than 80 characters are excerpted around the start of the spans, marked by `...`.
+    pub fn render(&self, source: &str) -> String {
+        let located: Vec<_> = self
+            .labels
+            .iter()
+            .map(|label| (line_at(source, label.span.start), label))
+            .collect();
+        let width = located
+            .iter()
+            .map(|(line, _)| line.number.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = " ".repeat(width);
+
+        let mut out = format!("{}\n", self);
+        if let Some((line, label)) = located.iter().find(|(_, l)| l.primary) {
+            let column = column(line, label.span.start);
+            out.push_str(&format!("{}--> {}:{}\n", gutter, line.number, column + 1));
+        }
+        for (line, label) in located.iter() {
+            let column = column(line, label.span.start);
+            let (text, caret_offset) = if line.text.chars().count() > MAX_RENDERED_CHARS {
+                excerpt_around(line.text, column, MAX_RENDERED_CHARS / 2)
+            } else {
+                (line.text, column)
+            };
+            let start = column - caret_offset;
+            let prefix = if start > 0 { "..." } else { "" };
+            let suffix = if start + text.len() < line.text.len() {
+                "..."
+            } else {
+                ""
+            };
+            let end = label
+                .span
+                .end
+                .saturating_sub(line.offset)
+                .clamp(column, start + text.len());
+            let marked = line.text[column..end].chars().count().max(1);
+            let indent = prefix.len() + text[..caret_offset].chars().count();
+            let caret = if label.primary { "^" } else { "-" };
+
+            out.push_str(&format!("{} |\n", gutter));
+            out.push_str(&format!(
+                "{:>width$} | {}{}{}\n",
+                line.number, prefix, text, suffix
+            ));
+            out.push_str(&format!(
+                "{} | {}{} {}",
+                gutter,
+                " ".repeat(indent),
+                caret.repeat(marked),
+                label.message
+            ));
+            let trimmed = out.trim_end().len();
+            out.truncate(trimmed);
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+            Severity::Info => write!(f, "info: {}", self.message),
+        }
+    }
+}
+
+/// Makes reports of errors and then warnings of `outcome`, parsed from `source`.
+pub fn reports(source: &str, outcome: &ParseOutcome) -> Vec<Report> {
+    outcome
+        .errors
+        .iter()
+        .map(|e| Report::from_failure(source, e))
+        .chain(
+            outcome
+                .warnings
+                .iter()
+                .map(|w| Report::from_warning(source, w)),
+        )
+        .collect()
+}
+
+/// [`ParseFailure`] or [`Warning`] with the source it was parsed from, which is a `miette::Diagnostic`.
+///
+/// The labels are those of [`Report::from_failure`] and [`Report::from_warning`].
+///
+/// [`ParseFailure`]: crate::error::ParseFailure
+/// [`Warning`]: crate::diagnostic::Warning
+/// [`Report::from_failure`]: crate::report::Report::from_failure
+/// [`Report::from_warning`]: crate::report::Report::from_warning
+#[derive(Debug, Clone, Copy)]
+pub struct Sourced<'s, T> {
+    pub source: &'s str,
+    pub located: &'s Located<T>,
+}
+
+impl<'s, T> Sourced<'s, T> {
+    pub fn new(source: &'s str, located: &'s Located<T>) -> Sourced<'s, T> {
+        Sourced { source, located }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Sourced<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.located.value.fmt(f)
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> core::error::Error for Sourced<'_, T> {}
+
+impl miette::Diagnostic for Sourced<'_, ParseFailure> {
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Error)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        labeled_spans(Report::from_failure(self.source, self.located))
+    }
+}
+
+impl miette::Diagnostic for Sourced<'_, Warning> {
+    fn severity(&self) -> Option<miette::Severity> {
+        Some(miette::Severity::Warning)
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        labeled_spans(Report::from_warning(self.source, self.located))
+    }
+}
+
+fn labeled_spans(report: Report) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan>>> {
+    Some(Box::new(report.labels.into_iter().map(|label| {
+        let message = (!label.message.is_empty()).then_some(label.message);
+        if label.primary {
+            miette::LabeledSpan::new_primary_with_span(message, label.span)
+        } else {
+            miette::LabeledSpan::new_with_span(message, label.span)
+        }
+    })))
+}
+
+fn warning_labels(line: &Line<'_>, warning: &Warning) -> Vec<Label> {
+    match warning {
+        Warning::TrailingWhitespace | Warning::TrailingValueWhitespace(_) => {
+            let start = line.offset + line.text.trim_end().len();
+            vec![Label::primary(start..line.offset + line.text.len(), "")]
+        }
+        _ => vec![Label::primary(trimmed_span(line), "")],
+    }
+}
+
+/// Returns the line of 1-based `number`, or an empty line at the end of `source` when there is no such line.
+fn nth_line(source: &str, number: usize) -> Line<'_> {
+    lines(source)
+        .nth(number.saturating_sub(1))
+        .unwrap_or_else(|| end_line(source))
+}
+
+/// Returns the last line starting at or before `offset`.
+fn line_at(source: &str, offset: usize) -> Line<'_> {
+    lines(source)
+        .take_while(|line| line.offset <= offset)
+        .last()
+        .unwrap_or_else(|| end_line(source))
+}
+
+fn end_line(source: &str) -> Line<'_> {
+    Line {
+        number: lines(source).count() + 1,
+        offset: source.len(),
+        text: &source[source.len()..],
+        ending: Default::default(),
+    }
+}
+
+/// Returns the column of `offset` in `line`, in bytes, at most the end of the text.
+fn column(line: &Line<'_>, offset: usize) -> usize {
+    let mut column = offset.saturating_sub(line.offset).min(line.text.len());
+    while !line.text.is_char_boundary(column) {
+        column -= 1;
+    }
+    column
+}
+
+fn trimmed_span(line: &Line<'_>) -> Range<usize> {
+    let start = line.offset + (line.text.len() - line.text.trim_start().len());
+    start..(line.offset + line.text.trim_end().len()).max(start)
+}
+
+/// Returns the span of the field starting `fragment`, the rest of `line` where a syntax error occurred.
+///
+/// The whole line is taken when `fragment` is not the rest of `line`, such as after normalization.
+fn fragment_span(line: &Line<'_>, fragment: &str) -> Range<usize> {
+    if !line.text.ends_with(fragment) {
+        return trimmed_span(line);
+    }
+    let mut start = line.offset + line.text.len() - fragment.len();
+    // Optional fields fail at the separator before them.
+    let fragment = match fragment.strip_prefix(',') {
+        Some(rest) => {
+            start += 1;
+            rest
+        }
+        None => fragment,
+    };
+    let field = fragment.find(',').unwrap_or(fragment.len());
+    start..start + field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::diagnostic::parse_with_diagnostics;
+
+    mod report {
+        use super::*;
+
+        #[test]
+        fn success_when_unclosed_block() {
+            let case = "name,a\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n";
+            let outcome = parse_with_diagnostics(case);
+            let report = Report::from_failure(case, &outcome.errors[0]);
+
+            assert_eq!(
+                report.labels,
+                vec![
+                    Label::secondary(8..16, "block opened here"),
+                    Label::primary(46..46, "expected `}` before the end"),
+                ]
+            );
+            assert_eq!(
+                report.render(case),
+                "error: unclosed block\n --> 4:24\n  |\n2 | surface0\n  | -------- block opened here\n  |\n4 | element0,base,a.png,0,0\n  |                        ^ expected `}` before the end\n"
+            );
+        }
+
+        #[test]
+        fn success_when_syntax_error_and_warning() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,x,0 \r\n}\r\n";
+            let outcome = parse_with_diagnostics(case);
+            let reports = reports(case, &outcome);
+
+            assert_eq!(reports.len(), 2);
+            assert_eq!(reports[0].severity, Severity::Error);
+            assert_eq!(&case[reports[0].primary().unwrap().span.clone()], "x");
+            assert_eq!(reports[1].severity, Severity::Warning);
+            assert_eq!(reports[1].primary().unwrap().span, 36..37);
+            assert!(reports[1]
+                .render(case)
+                .starts_with("warning: trailing whitespace\n --> 3:24\n"));
+        }
+
+        #[test]
+        fn success_when_long_line_excerpted() {
+            let name = "あ".repeat(100);
+            let case = format!("surface0\r\n{{\r\nelement0,base,{}.png,x,0\r\n}}\r\n", name);
+            let outcome = parse_with_diagnostics(&case);
+            let rendered = Report::from_failure(&case, &outcome.errors[0]).render(&case);
+
+            let lines: Vec<_> = rendered.lines().collect();
+            assert_eq!(lines[1], " --> 3:320");
+            let text = lines[3].strip_prefix("3 | ").unwrap();
+            assert!(text.starts_with("...あ") && text.ends_with(",0"));
+            assert_eq!(text.chars().count(), 3 + 40 + 3);
+            let marks = lines[4].strip_prefix("  | ").unwrap();
+            let indent = marks.len() - marks.trim_start().len();
+            assert_eq!(text.chars().nth(indent), Some('x'));
+            assert!(marks.trim_start().starts_with("^ "));
+        }
+
+        #[test]
+        fn success_when_lint_warning() {
+            let case = "charset,UTF-8\r\n0interval,1\r\n";
+            let warning = lint::Warning::new(
+                lint::codes::DEPRECATED_KEY,
+                Severity::Warning,
+                2,
+                "0interval is deprecated",
+            )
+            .suggestion("animation0.interval");
+            let report = Report::from_lint(case, &warning);
+
+            assert_eq!(
+                report.labels,
+                vec![Label::primary(15..26, "write \"animation0.interval\"")]
+            );
+            assert_eq!(
+                report.render(case),
+                "warning: [W0008-deprecated-key] 0interval is deprecated\n --> 2:1\n  |\n2 | 0interval,1\n  | ^^^^^^^^^^^ write \"animation0.interval\"\n"
+            );
+        }
+    }
+
+    mod sourced {
+        use super::*;
+
+        use miette::{Diagnostic, NarratableReportHandler};
+
+        #[test]
+        fn success_when_failure_rendered() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,x,0\r\n}\r\n";
+            let outcome = parse_with_diagnostics(case);
+            let diagnostic = Sourced::new(case, &outcome.errors[0]);
+
+            let labels: Vec<_> = diagnostic.labels().unwrap().collect();
+            assert_eq!(labels.len(), 1);
+            assert!(labels[0].primary());
+            assert_eq!(
+                labels[0].offset()..labels[0].offset() + labels[0].len(),
+                33..34
+            );
+
+            let mut rendered = String::new();
+            NarratableReportHandler::new()
+                .render_report(&mut rendered, &diagnostic)
+                .unwrap();
+            assert!(rendered.contains(&outcome.errors[0].value.to_string()));
+            assert!(rendered.contains("snippet line 3: element0,base,a.png,x,0\n    label at line 3, column 21: element:alpha\n"));
+        }
+
+        #[test]
+        fn success_when_warning_rendered() {
+            let case = "name,a \r\n";
+            let warning = Located::new(1, Warning::TrailingWhitespace);
+            let diagnostic = Sourced::new(case, &warning);
+
+            assert_eq!(diagnostic.severity(), Some(miette::Severity::Warning));
+            let labels: Vec<_> = diagnostic.labels().unwrap().collect();
+            assert_eq!((labels[0].offset(), labels[0].len()), (6, 1));
+            assert_eq!(labels[0].label(), None);
+
+            let mut rendered = String::new();
+            NarratableReportHandler::new()
+                .render_report(&mut rendered, &diagnostic)
+                .unwrap();
+            assert_eq!(
+                rendered,
+                "trailing whitespace\n    Diagnostic severity: warning\nBegin snippet starting at line 1, column 1\n\nsnippet line 1: name,a \n    label at line 1, column 7\n"
+            );
+        }
+    }
+}
@@ -0,0 +1,250 @@
+//! # `scope`
+//!
+//! enum `Scope` and parser for it.
+//! Scope is the character prefix of keys, such as `sakura.` or `char2.`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::scope::{parse_scope, Scope};
+//!
+//! let (remain, scope) = parse_scope("char2.balloon.offsetx").unwrap();
+//! assert_eq!(remain, ".balloon.offsetx");
+//! assert_eq!(scope, Scope::Char(2));
+//! ```
+//!
+//! Settings of `charN` falling back to other scopes are resolved by [`ScopedSettings`].
+//!
+//! [`ScopedSettings`]: crate::scope::ScopedSettings
+//!
+//! ```
+//! use shell_parser_common_rs::scope::{FallbackChain, Scope, ScopedSettings};
+//!
+//! let mut offsets = ScopedSettings::new(FallbackChain::CHAR_TO_KERO);
+//! offsets.insert(Scope::Kero, 10);
+//!
+//! assert_eq!(offsets.resolve(Scope::Char(3)), Some(&10));
+//! assert_eq!(offsets.resolve(Scope::Sakura), None);
+//! ```
+
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{all_consuming, map, value},
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{value::parse_u32, ShellParseError};
+
+/// `Scope` type.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Scope {
+    Sakura,
+    Kero,
+    Char(u32),
+}
+
+impl Scope {
+    /// Converts a whole key segment (e.g. `"kero"`) into `Scope`.
+    pub fn from_segment(segment: &str) -> Option<Scope> {
+        all_consuming(parse_scope)(segment).ok().map(|(_, s)| s)
+    }
+}
+
+/// Key segment of the scope, such as `char2`.
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Sakura => write!(f, "sakura"),
+            Scope::Kero => write!(f, "kero"),
+            Scope::Char(n) => write!(f, "char{}", n),
+        }
+    }
+}
+
+/// parser for [`Scope`].
+///
+/// [`Scope`]: crate::scope::Scope
+pub fn parse_scope(input: &str) -> IResult<&str, Scope, ShellParseError<'_>> {
+    alt((
+        value(Scope::Sakura, tag("sakura")),
+        value(Scope::Kero, tag("kero")),
+        map(preceded(tag("char"), parse_u32), Scope::Char),
+    ))(input)
+}
+
+/// Scopes tried in order when a scope has no value of a setting, chosen per family of settings.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FallbackChain {
+    /// Scopes tried for [`Scope::Kero`].
+    ///
+    /// [`Scope::Kero`]: crate::scope::Scope::Kero
+    pub kero: &'static [Scope],
+    /// Scopes tried for each [`Scope::Char`].
+    ///
+    /// [`Scope::Char`]: crate::scope::Scope::Char
+    pub char: &'static [Scope],
+}
+
+impl FallbackChain {
+    /// No fallbacks. Each scope has only its own values.
+    pub const NONE: FallbackChain = FallbackChain {
+        kero: &[],
+        char: &[],
+    };
+
+    /// `charN` falls back to `kero`, as baseware places the third and later characters.
+    pub const CHAR_TO_KERO: FallbackChain = FallbackChain {
+        kero: &[],
+        char: &[Scope::Kero],
+    };
+
+    /// `charN` falls back to `kero`, and `kero` to `sakura`.
+    pub const KERO_THEN_SAKURA: FallbackChain = FallbackChain {
+        kero: &[Scope::Sakura],
+        char: &[Scope::Kero, Scope::Sakura],
+    };
+
+    /// Returns `scope` followed by the scopes it falls back to.
+    pub fn scopes(&self, scope: Scope) -> impl Iterator<Item = Scope> + '_ {
+        let fallbacks = match scope {
+            Scope::Sakura => &[][..],
+            Scope::Kero => self.kero,
+            Scope::Char(_) => self.char,
+        };
+        core::iter::once(scope).chain(fallbacks.iter().copied())
+    }
+}
+
+/// Values of a setting per scope, resolved along a [`FallbackChain`].
+///
+/// [`FallbackChain`]: crate::scope::FallbackChain
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScopedSettings<T> {
+    values: BTreeMap<Scope, T>,
+    chain: FallbackChain,
+}
+
+impl<T> ScopedSettings<T> {
+    pub fn new(chain: FallbackChain) -> ScopedSettings<T> {
+        ScopedSettings {
+            values: BTreeMap::new(),
+            chain,
+        }
+    }
+
+    /// Returns the chain of fallbacks.
+    pub fn chain(&self) -> &FallbackChain {
+        &self.chain
+    }
+
+    /// Sets the value of `scope`, returning the previous one.
+    pub fn insert(&mut self, scope: Scope, value: T) -> Option<T> {
+        self.values.insert(scope, value)
+    }
+
+    /// Returns the value of `scope`, as defined.
+    pub fn get(&self, scope: Scope) -> Option<&T> {
+        self.values.get(&scope)
+    }
+
+    /// Returns the value of `scope`, inserting the default value when it is not defined.
+    pub fn get_or_insert_default(&mut self, scope: Scope) -> &mut T
+    where
+        T: Default,
+    {
+        self.values.entry(scope).or_default()
+    }
+
+    /// Returns the value of `scope`, or of the first scope in the chain with one.
+    pub fn resolve(&self, scope: Scope) -> Option<&T> {
+        self.chain.scopes(scope).find_map(|s| self.values.get(&s))
+    }
+
+    /// Iterates values ordered by scope, as defined.
+    pub fn iter(&self) -> impl Iterator<Item = (&Scope, &T)> {
+        self.values.iter()
+    }
+}
+
+/// Splits the leading scope segment from `key_segments`.
+pub(crate) fn split_scope<'a, 'b>(key_segments: &'b [&'a str]) -> Option<(Scope, &'b [&'a str])> {
+    let (first, rest) = key_segments.split_first()?;
+    Scope::from_segment(first).map(|scope| (scope, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_scope {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            assert_eq!(parse_scope("sakura.x").unwrap(), (".x", Scope::Sakura));
+            assert_eq!(parse_scope("kero.x").unwrap(), (".x", Scope::Kero));
+            assert_eq!(parse_scope("char3.x").unwrap(), (".x", Scope::Char(3)));
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            assert!(parse_scope("char.x").is_err());
+            assert!(parse_scope("menu.x").is_err());
+        }
+    }
+
+    mod scoped_settings {
+        use super::*;
+
+        #[test]
+        fn success_when_char_falls_back_to_kero() {
+            let mut settings = ScopedSettings::new(FallbackChain::CHAR_TO_KERO);
+            settings.insert(Scope::Sakura, 0);
+            settings.insert(Scope::Kero, 10);
+
+            assert_eq!(settings.resolve(Scope::Char(3)), Some(&10));
+            assert_eq!(settings.get(Scope::Char(3)), None);
+        }
+
+        #[test]
+        fn success_when_kero_without_fallback() {
+            let mut settings = ScopedSettings::new(FallbackChain::CHAR_TO_KERO);
+            settings.insert(Scope::Sakura, 0);
+
+            assert_eq!(settings.resolve(Scope::Kero), None);
+            assert_eq!(settings.resolve(Scope::Char(2)), None);
+
+            let settings = ScopedSettings {
+                chain: FallbackChain::KERO_THEN_SAKURA,
+                ..settings
+            };
+            assert_eq!(settings.resolve(Scope::Kero), Some(&0));
+            assert_eq!(settings.resolve(Scope::Char(2)), Some(&0));
+        }
+
+        #[test]
+        fn success_when_char_defined() {
+            let mut settings = ScopedSettings::new(FallbackChain::KERO_THEN_SAKURA);
+            settings.insert(Scope::Kero, 10);
+            settings.insert(Scope::Char(3), 30);
+
+            assert_eq!(settings.resolve(Scope::Char(3)), Some(&30));
+            assert_eq!(settings.resolve(Scope::Char(4)), Some(&10));
+        }
+    }
+
+    mod scope {
+        use super::*;
+
+        #[test]
+        fn from_segment_requires_whole_segment() {
+            assert_eq!(Scope::from_segment("kero"), Some(Scope::Kero));
+            assert_eq!(Scope::from_segment("kerox"), None);
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Serialization of parsed data written by hand, where derived fields are not enough.
+//!
+//! Types with a syntax of their own in files, such as [`DrawMethod`] and [`Intervals`],
+//! are written as strings in that syntax and read back by the parsers of the crate.
+//!
+//! [`DrawMethod`]: crate::seriko::DrawMethod
+//! [`Intervals`]: crate::seriko::Intervals
+
+use core::{fmt, marker::PhantomData};
+
+use nom::{combinator::all_consuming, IResult};
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{charset::Charset, ShellParseError};
+
+/// Visitor reading a string by `parser`, which must consume all of it.
+struct SyntaxVisitor<T> {
+    parser: fn(&str) -> IResult<&str, T, ShellParseError<'_>>,
+    expecting: &'static str,
+    value: PhantomData<T>,
+}
+
+impl<T> Visitor<'_> for SyntaxVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        all_consuming(self.parser)(v)
+            .map(|(_, value)| value)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+    }
+}
+
+fn deserialize_syntax<'de, D: Deserializer<'de>, T>(
+    deserializer: D,
+    parser: fn(&str) -> IResult<&str, T, ShellParseError<'_>>,
+    expecting: &'static str,
+) -> Result<T, D::Error> {
+    deserializer.deserialize_str(SyntaxVisitor {
+        parser,
+        expecting,
+        value: PhantomData,
+    })
+}
+
+/// Writes `$ty` as the string of its `Display`, and reads it back by `$parser`.
+#[cfg(feature = "kv")]
+macro_rules! syntax_serde {
+    ($ty:ty, $parser:expr, $expecting:literal) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_syntax(deserializer, $parser, $expecting)
+            }
+        }
+    };
+}
+
+/// Written as the name of `charset` lines, or `default` for [`Charset::Default`].
+///
+/// [`Charset::Default`]: crate::charset::Charset::Default
+impl Serialize for Charset {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self {
+            Charset::ASCII => "ASCII",
+            Charset::Default => "default",
+            _ => self.whatwg_name().expect("every encoding has a name"),
+        };
+        serializer.serialize_str(name)
+    }
+}
+
+impl<'de> Deserialize<'de> for Charset {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        fn charset(input: &str) -> IResult<&str, Charset, ShellParseError<'_>> {
+            match input {
+                "default" => Ok(("", Charset::Default)),
+                _ => crate::charset::parse_charset(input),
+            }
+        }
+        deserialize_syntax(deserializer, charset, "name of a charset")
+    }
+}
+
+#[cfg(feature = "kv")]
+mod kv {
+    use serde::ser::SerializeStruct;
+
+    use super::*;
+
+    use crate::{
+        line::Line,
+        scope::{parse_scope, Scope},
+    };
+
+    syntax_serde!(Scope, parse_scope, "scope such as `sakura` or `char2`");
+
+    /// Written as `{"text": ..., "span": {"start": ..., "end": ..., "line": ...}}`,
+    /// with the byte range of the text without the line ending.
+    impl Serialize for Line<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut line = serializer.serialize_struct("Line", 2)?;
+            line.serialize_field("text", self.text)?;
+            line.serialize_field(
+                "span",
+                &Span {
+                    start: self.offset,
+                    end: self.offset + self.text.len(),
+                    line: self.number,
+                },
+            )?;
+            line.end()
+        }
+    }
+
+    /// Byte range with the 1-based line number it starts on.
+    #[derive(Serialize)]
+    struct Span {
+        start: usize,
+        end: usize,
+        line: usize,
+    }
+}
+
+#[cfg(feature = "seriko")]
+mod seriko {
+    use alloc::{string::ToString, vec::Vec};
+
+    use nom::combinator::map_res;
+    use serde::ser::SerializeMap;
+
+    use super::*;
+
+    use crate::seriko::{
+        parse_animation_option, parse_draw_method, parse_id_group_with_syntax, parse_interval,
+        parse_intervals, parse_wait, AnimationOption, DrawMethod, IdGroup, Interval, Intervals,
+        Pattern, Wait,
+    };
+
+    syntax_serde!(
+        DrawMethod,
+        parse_draw_method,
+        "drawing method such as `overlay`"
+    );
+    syntax_serde!(Wait, parse_wait, "wait such as `50` or `50-100`");
+    syntax_serde!(
+        Interval,
+        parse_interval,
+        "interval such as `sometimes` or `talk,3`"
+    );
+    syntax_serde!(
+        Intervals,
+        parse_intervals,
+        "intervals such as `sometimes+talk,3`"
+    );
+    syntax_serde!(
+        IdGroup,
+        parse_id_group_with_syntax,
+        "group of IDs such as `(1,3)`"
+    );
+    syntax_serde!(
+        AnimationOption,
+        animation_option,
+        "option such as `exclusive,(1,3)`"
+    );
+
+    /// Reads one option of [`parse_animation_option`].
+    ///
+    /// [`parse_animation_option`]: crate::seriko::parse_animation_option
+    fn animation_option(input: &str) -> IResult<&str, AnimationOption, ShellParseError<'_>> {
+        map_res(
+            parse_animation_option,
+            |mut options: Vec<AnimationOption>| match options.len() {
+                1 => Ok(options.remove(0)),
+                _ => Err(()),
+            },
+        )(input)
+    }
+
+    /// Written as an object with the method of the pattern,
+    /// and the fields of drawing, the `target` animation, or the `group` of animations.
+    impl Serialize for Pattern {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(None)?;
+            map.serialize_entry("method", &self.method().to_string())?;
+            match self {
+                Pattern::Draw {
+                    surface,
+                    wait,
+                    offset,
+                    alpha,
+                    ..
+                } => {
+                    map.serialize_entry("surface", surface)?;
+                    map.serialize_entry("wait", wait)?;
+                    map.serialize_entry("offset", offset)?;
+                    map.serialize_entry("alpha", alpha)?;
+                }
+                Pattern::Insert(id) | Pattern::Start(id) | Pattern::Stop(id) => {
+                    map.serialize_entry("target", id)?;
+                }
+                Pattern::AlternativeStart(group)
+                | Pattern::AlternativeStop(group)
+                | Pattern::ParallelStart(group)
+                | Pattern::ParallelStop(group) => {
+                    map.serialize_entry("group", group)?;
+                }
+            }
+            map.end()
+        }
+    }
+}
+
+#[cfg(feature = "document")]
+mod document {
+    use serde::ser::SerializeStruct;
+
+    use super::*;
+
+    use crate::document::ShellFile;
+
+    /// Written as `{"charset", "entries", "comments", "blocks"}`, with `blocks` `null` when there are none.
+    impl Serialize for ShellFile {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut file = serializer.serialize_struct("ShellFile", 4)?;
+            file.serialize_field("charset", self.charset())?;
+            file.serialize_field("entries", &self.entries())?;
+            file.serialize_field("comments", &self.comments())?;
+            file.serialize_field("blocks", &self.blocks())?;
+            file.end()
+        }
+    }
+}
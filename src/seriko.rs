@@ -0,0 +1,57 @@
+//! # `seriko`
+//!
+//! Parsers for SERIKO animation definitions in `surfaces.txt`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::seriko::{parse_animation_option, AnimationOption};
+//!
+//! let (_remain, options) = parse_animation_option("exclusive,(1,3)+background\r\n").unwrap();
+//!
+//! assert_eq!(
+//!     options,
+//!     vec![
+//!         AnimationOption::Exclusive(Some(vec![1, 3])),
+//!         AnimationOption::Background,
+//!     ]
+//! );
+//! ```
+
+mod animation;
+#[cfg(feature = "surfaces")]
+mod collision;
+mod id_group;
+mod interval;
+mod option;
+mod pattern;
+mod refs;
+mod set;
+mod simulator;
+
+pub use animation::Animation;
+#[cfg(feature = "surfaces")]
+pub use collision::{parse_animation_collision, AnimationCollision};
+pub use id_group::{
+    parse_id_group, parse_id_group_with_policy, parse_id_group_with_syntax, IdGroup,
+    IdGroupDelimiter, IdGroupSeparator,
+};
+pub use interval::{
+    parse_interval, parse_intervals, CombinedTrigger, EventKind, Interval, Intervals, Trigger,
+};
+pub use option::{parse_animation_option, AnimationOption};
+pub use pattern::{
+    parse_alpha, parse_draw_method, parse_pattern, parse_pattern_spanned,
+    parse_pattern_with_policy, parse_wait, Arity, DrawMethod, Pattern, PatternMethod, PatternSpans,
+    Wait,
+};
+pub use refs::{validate_animation_refs, RefError};
+pub use set::{AnimationIssue, AnimationSet};
+pub use simulator::{EventSimulator, RandomSource};
+
+#[cfg(feature = "descript")]
+pub(crate) use option::animation_options;
+#[cfg(feature = "surfaces")]
+pub(crate) use pattern::trailing_alpha;
+#[cfg(feature = "surfaces")]
+pub(crate) use set::apply_animation_entry;
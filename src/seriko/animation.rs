@@ -0,0 +1,43 @@
+//! Animation aggregating interval, patterns and options.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+#[cfg(feature = "surfaces")]
+use super::AnimationCollision;
+use super::{AnimationOption, Intervals, Pattern};
+
+/// Animation defined by `animationN.*` keys.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Animation {
+    /// Animation ID, `N` of `animationN`.
+    pub id: u32,
+    /// `animationN.interval`
+    pub intervals: Option<Intervals>,
+    /// `animationN.patternM`, keyed by `M`.
+    pub patterns: BTreeMap<u32, Pattern>,
+    /// `animationN.option`
+    pub options: Vec<AnimationOption>,
+    /// `animationN.collisionM` and `animationN.collisionexM`, in order, given with the `surfaces` feature.
+    #[cfg(feature = "surfaces")]
+    pub collisions: Vec<AnimationCollision>,
+}
+
+impl Animation {
+    /// Creates an animation without definitions.
+    pub fn new(id: u32) -> Animation {
+        Animation {
+            id,
+            intervals: None,
+            patterns: BTreeMap::new(),
+            options: Vec::new(),
+            #[cfg(feature = "surfaces")]
+            collisions: Vec::new(),
+        }
+    }
+
+    /// Checks whether the interval includes `bind`.
+    pub fn is_bind(&self) -> bool {
+        self.intervals.as_ref().is_some_and(|i| i.is_bind())
+    }
+}
@@ -0,0 +1,169 @@
+//! Collisions of animations, `animationN.collisionM,...` and `animationN.collisionexM,...`.
+
+use alloc::format;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    error::ParseFailure,
+    kv::segment_index,
+    surfaces::{collision_ref, collisionex, Collision, CollisionEx},
+    value::{NumericPolicy, NumericReader},
+};
+
+/// Collision which exists only while its animation is displayed.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value"))]
+pub enum AnimationCollision {
+    /// `animationN.collisionM,left,top,right,bottom,name`
+    #[cfg_attr(feature = "serde", serde(rename = "collision"))]
+    Rect(Collision),
+    /// `animationN.collisionexM,name,shape,...`
+    #[cfg_attr(feature = "serde", serde(rename = "collisionex"))]
+    Ex(CollisionEx),
+}
+
+impl AnimationCollision {
+    /// Returns `M` of `collisionM` or `collisionexM`.
+    pub fn id(&self) -> u32 {
+        match self {
+            AnimationCollision::Rect(c) => c.id,
+            AnimationCollision::Ex(c) => c.id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            AnimationCollision::Rect(c) => &c.name,
+            AnimationCollision::Ex(c) => &c.name,
+        }
+    }
+}
+
+/// parser for collisions of animations, with the animation ID `N`.
+///
+/// `key_segments` is the key split by `.`, and `value` is parsed as
+/// [`parse_collision`] or [`parse_collisionex`] does after `collisionM,`.
+/// Returns `Ok(None)` when the key is not of a collision of an animation.
+///
+/// [`parse_collision`]: crate::surfaces::parse_collision
+/// [`parse_collisionex`]: crate::surfaces::parse_collisionex
+///
+/// ```
+/// use shell_parser_common_rs::seriko::{parse_animation_collision, AnimationCollision};
+///
+/// let (id, collision) = parse_animation_collision(&["animation3", "collision0"], "10,10,40,40,Hand")
+///     .unwrap()
+///     .unwrap();
+///
+/// assert_eq!(id, 3);
+/// assert!(matches!(collision, AnimationCollision::Rect(_)));
+/// assert_eq!(collision.name(), "Hand");
+/// ```
+pub fn parse_animation_collision(
+    key_segments: &[&str],
+    value: &str,
+) -> Result<Option<(u32, AnimationCollision)>, ParseFailure> {
+    animation_collision(
+        key_segments,
+        value,
+        &NumericReader::new(NumericPolicy::DEFAULT),
+    )
+}
+
+pub(crate) fn animation_collision(
+    key_segments: &[&str],
+    value: &str,
+    numbers: &NumericReader,
+) -> Result<Option<(u32, AnimationCollision)>, ParseFailure> {
+    let [animation, field] = key_segments else {
+        return Ok(None);
+    };
+    let Some(id) = segment_index(animation, "animation") else {
+        return Ok(None);
+    };
+
+    let text = format!("{},{}", field, value);
+    let collision = if segment_index(field, "collisionex").is_some() {
+        AnimationCollision::Ex(all_consuming(|i| collisionex(i, numbers))(&text)?.1)
+    } else if segment_index(field, "collision").is_some() {
+        let (_, collision) = all_consuming(|i| collision_ref(i, numbers))(&text)?;
+        AnimationCollision::Rect(collision.to_owned())
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some((id, collision)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{surfaces::CollisionExShape, value::Rect};
+
+    mod parse_animation_collision {
+        use super::*;
+
+        #[test]
+        fn success_when_rect() {
+            let (id, collision) =
+                parse_animation_collision(&["animation3", "collision0"], "10,10,40,40,Hand")
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(id, 3);
+            assert_eq!(
+                collision,
+                AnimationCollision::Rect(Collision {
+                    id: 0,
+                    rect: Rect::new(10, 10, 40, 40),
+                    name: "Hand".to_string(),
+                })
+            );
+        }
+
+        #[test]
+        fn success_when_collisionex() {
+            let (id, collision) =
+                parse_animation_collision(&["animation12", "collisionex4"], "Hand,circle,25,25,10")
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(id, 12);
+            assert_eq!(collision.id(), 4);
+            assert!(matches!(
+                collision,
+                AnimationCollision::Ex(CollisionEx {
+                    shape: CollisionExShape::Circle { radius: 10, .. },
+                    ..
+                })
+            ));
+        }
+
+        #[test]
+        fn ignored_when_malformed_animation_index() {
+            for key in [
+                ["animation", "collision0"],
+                ["animation1x", "collision0"],
+                ["animation1", "collision"],
+                ["animation1", "pattern0"],
+            ] {
+                assert_eq!(parse_animation_collision(&key, "0,0,1,1,a"), Ok(None));
+            }
+            assert_eq!(
+                parse_animation_collision(&["animation1", "collision0", "x"], "0,0,1,1,a"),
+                Ok(None)
+            );
+        }
+
+        #[test]
+        fn failed_when_malformed_payload() {
+            let error = parse_animation_collision(&["animation1", "collision0"], "0,0,10,Hand")
+                .unwrap_err();
+            assert!(error.contexts().contains(&"collision:rect"));
+            assert!(
+                parse_animation_collision(&["animation1", "collisionex0"], "Hand,star").is_err()
+            );
+        }
+    }
+}
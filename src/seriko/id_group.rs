@@ -0,0 +1,219 @@
+//! Groups of animation IDs, `(1,3,5)` or legacy `[1.3.5]`.
+
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+use nom::{
+    branch::alt,
+    character::complete::{char, one_of},
+    combinator::{map, not},
+    error::context,
+    multi::many0,
+    sequence::{preceded, terminated},
+    IResult,
+};
+
+use crate::{
+    value::{Checked, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// Delimiters of [`IdGroup`].
+///
+/// [`IdGroup`]: crate::seriko::IdGroup
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IdGroupDelimiter {
+    /// `(` and `)`
+    Paren,
+    /// `[` and `]`
+    Bracket,
+}
+
+/// Separator of IDs in [`IdGroup`].
+///
+/// [`IdGroup`]: crate::seriko::IdGroup
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IdGroupSeparator {
+    /// `,`
+    Comma,
+    /// `.`
+    Dot,
+}
+
+/// Group of animation IDs with the syntax it was written in.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IdGroup {
+    pub ids: Vec<u32>,
+    pub delimiter: IdGroupDelimiter,
+    /// For a single ID, the conventional separator of the delimiter
+    /// (`,` for parentheses, `.` for brackets).
+    pub separator: IdGroupSeparator,
+}
+
+/// Written in the syntax of the group.
+impl fmt::Display for IdGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (open, close) = match self.delimiter {
+            IdGroupDelimiter::Paren => ('(', ')'),
+            IdGroupDelimiter::Bracket => ('[', ']'),
+        };
+        let separator = match self.separator {
+            IdGroupSeparator::Comma => ",",
+            IdGroupSeparator::Dot => ".",
+        };
+
+        write!(f, "{}", open)?;
+        for (i, id) in self.ids.iter().enumerate() {
+            if i > 0 {
+                f.write_str(separator)?;
+            }
+            write!(f, "{}", id)?;
+        }
+        write!(f, "{}", close)
+    }
+}
+
+/// parser for the group of animation IDs.
+///
+/// Accepts `(1,3,5)`, `[1.3.5]` and the other combinations of delimiters and separators.
+/// Mixed delimiters, mixed separators, empty groups and non-numeric members are rejected.
+pub fn parse_id_group(input: &str) -> IResult<&str, Vec<u32>, ShellParseError<'_>> {
+    map(parse_id_group_with_syntax, |group| group.ids)(input)
+}
+
+/// parser for [`IdGroup`], same as [`parse_id_group`] but keeps the syntax.
+///
+/// [`IdGroup`]: crate::seriko::IdGroup
+/// [`parse_id_group`]: crate::seriko::parse_id_group
+pub fn parse_id_group_with_syntax(input: &str) -> IResult<&str, IdGroup, ShellParseError<'_>> {
+    id_group(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+/// Same as [`parse_id_group_with_syntax`], reading IDs under `policy`.
+///
+/// [`parse_id_group_with_syntax`]: crate::seriko::parse_id_group_with_syntax
+pub fn parse_id_group_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<IdGroup>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| id_group(i, r))
+}
+
+pub(crate) fn id_group<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, IdGroup, ShellParseError<'a>> {
+    context(
+        "seriko:id_group",
+        alt((
+            |i| group(i, numbers, '(', ')', IdGroupDelimiter::Paren),
+            |i| group(i, numbers, '[', ']', IdGroupDelimiter::Bracket),
+        )),
+    )(input)
+}
+
+fn group<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+    open: char,
+    close: char,
+    delimiter: IdGroupDelimiter,
+) -> IResult<&'a str, IdGroup, ShellParseError<'a>> {
+    let (input, _) = char(open)(input)?;
+    let (input, first) = numbers.u32(input)?;
+
+    let (input, separator, rest) = match input.chars().next() {
+        Some(c @ (',' | '.')) => {
+            let (input, rest) = many0(preceded(char(c), |i| numbers.u32(i)))(input)?;
+            let separator = if c == ',' {
+                IdGroupSeparator::Comma
+            } else {
+                IdGroupSeparator::Dot
+            };
+            (input, separator, rest)
+        }
+        _ => {
+            let separator = match delimiter {
+                IdGroupDelimiter::Paren => IdGroupSeparator::Comma,
+                IdGroupDelimiter::Bracket => IdGroupSeparator::Dot,
+            };
+            (input, separator, Vec::new())
+        }
+    };
+
+    // A separator of the other kind, a wrong or doubled closing delimiter fails here.
+    let (input, _) = terminated(char(close), not(one_of(")]")))(input)?;
+
+    let mut ids = vec![first];
+    ids.extend(rest);
+
+    Ok((
+        input,
+        IdGroup {
+            ids,
+            delimiter,
+            separator,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_id_group {
+        use super::*;
+
+        #[test]
+        fn success_when_paren_comma() {
+            let case = "(1,3,5)\r\n";
+            let (remain, result) = parse_id_group_with_syntax(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.ids, vec![1, 3, 5]);
+            assert_eq!(result.delimiter, IdGroupDelimiter::Paren);
+            assert_eq!(result.separator, IdGroupSeparator::Comma);
+        }
+
+        #[test]
+        fn success_when_bracket_dot() {
+            let case = "[1.3.5]\r\n";
+            let (remain, result) = parse_id_group_with_syntax(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.ids, vec![1, 3, 5]);
+            assert_eq!(result.delimiter, IdGroupDelimiter::Bracket);
+            assert_eq!(result.separator, IdGroupSeparator::Dot);
+        }
+
+        #[test]
+        fn success_when_other_combinations() {
+            assert_eq!(parse_id_group("(1.3)").unwrap(), ("", vec![1, 3]));
+            assert_eq!(parse_id_group("[1,3]").unwrap(), ("", vec![1, 3]));
+            assert_eq!(parse_id_group("[7]").unwrap(), ("", vec![7]));
+        }
+
+        #[test]
+        fn failed_when_mixed_delimiters() {
+            assert!(parse_id_group("(1.3]").is_err());
+            assert!(parse_id_group("[1,3)").is_err());
+        }
+
+        #[test]
+        fn failed_when_mixed_separators() {
+            assert!(parse_id_group("(1,3.5)").is_err());
+        }
+
+        #[test]
+        fn failed_when_nested_or_unbalanced() {
+            assert!(parse_id_group("((1,3))").is_err());
+            assert!(parse_id_group("(1,3").is_err());
+            assert!(parse_id_group("(1,3))").is_err());
+        }
+
+        #[test]
+        fn failed_when_empty_or_non_numeric() {
+            assert!(parse_id_group("()").is_err());
+            assert!(parse_id_group("[]").is_err());
+            assert!(parse_id_group("(1,a)").is_err());
+        }
+    }
+}
@@ -0,0 +1,459 @@
+//! Intervals of animations, `animationN.interval`.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use nom::{
+    character::complete::char, combinator::map, error::context, multi::separated_list1,
+    sequence::preceded, IResult,
+};
+
+use crate::{
+    value::{separator, tag_table, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// `Interval` type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum Interval {
+    Sometimes,
+    Rarely,
+    /// `random,N`
+    Random(u32),
+    /// `periodic,N`
+    Periodic(u32),
+    Always,
+    RunOnce,
+    Never,
+    YenE,
+    /// `talk,N`
+    Talk(u32),
+    Bind,
+}
+
+/// Event starting an animation of [`Trigger::OnEvent`], or given by [`Trigger::event`].
+///
+/// [`Trigger::OnEvent`]: crate::seriko::Trigger::OnEvent
+/// [`Trigger::event`]: crate::seriko::Trigger::event
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum EventKind {
+    /// The surface is shown, for `runonce`.
+    SurfaceShown,
+    /// The script ends with `\e`, for `yen-e`.
+    ScriptEnd,
+    /// The `N`th talk of the character since the last one, for `talk,N`.
+    TalkCount(u32),
+    /// The dress-up part is put on or off, for `bind`.
+    BindToggle,
+}
+
+/// When an animation starts, as given by [`Interval::trigger`].
+///
+/// Only the mapping is given here. Runtimes keep their own timers and random numbers.
+///
+/// [`Interval::trigger`]: crate::seriko::Interval::trigger
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum Trigger {
+    /// Starts with the probability of `1 / denominator`, tested once per second.
+    Probabilistic {
+        denominator: u32,
+    },
+    /// Starts every `seconds` seconds.
+    Periodic {
+        seconds: u32,
+    },
+    /// Starts every `count` talks of the character.
+    OnTalk {
+        count: u32,
+    },
+    OnEvent(EventKind),
+    /// Plays repeatedly while the surface is shown.
+    Always,
+    /// Shown while the dress-up part is on.
+    Bind,
+    /// Started only by other animations or scripts.
+    Never,
+}
+
+impl Interval {
+    /// Returns when the animation starts.
+    ///
+    /// - `sometimes` is a probability of 1/2 per second, and `rarely` of 1/4.
+    /// - `random,N` is 1/N per second, and `periodic,N` every N seconds.
+    /// - `talk,N` is every N talks.
+    /// - `runonce` is once when the surface is shown, and `yen-e` at the end of the script.
+    ///
+    /// `N` is kept as written, even `0`.
+    pub fn trigger(&self) -> Trigger {
+        match self {
+            Interval::Sometimes => Trigger::Probabilistic { denominator: 2 },
+            Interval::Rarely => Trigger::Probabilistic { denominator: 4 },
+            Interval::Random(n) => Trigger::Probabilistic { denominator: *n },
+            Interval::Periodic(n) => Trigger::Periodic { seconds: *n },
+            Interval::Always => Trigger::Always,
+            Interval::RunOnce => Trigger::OnEvent(EventKind::SurfaceShown),
+            Interval::Never => Trigger::Never,
+            Interval::YenE => Trigger::OnEvent(EventKind::ScriptEnd),
+            Interval::Talk(n) => Trigger::OnTalk { count: *n },
+            Interval::Bind => Trigger::Bind,
+        }
+    }
+}
+
+impl Trigger {
+    /// Returns the event starting the animation, counting talks for [`Trigger::OnTalk`],
+    /// or `None` for triggers by time, `always` and `never`.
+    ///
+    /// [`Trigger::OnTalk`]: crate::seriko::Trigger::OnTalk
+    pub fn event(&self) -> Option<EventKind> {
+        match self {
+            Trigger::OnTalk { count } => Some(EventKind::TalkCount(*count)),
+            Trigger::OnEvent(kind) => Some(*kind),
+            Trigger::Bind => Some(EventKind::BindToggle),
+            Trigger::Probabilistic { .. } | Trigger::Periodic { .. } => None,
+            Trigger::Always | Trigger::Never => None,
+        }
+    }
+}
+
+/// Triggers of [`Intervals`] resolved by [`Intervals::combined_trigger`].
+///
+/// [`Intervals`]: crate::seriko::Intervals
+/// [`Intervals::combined_trigger`]: crate::seriko::Intervals::combined_trigger
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CombinedTrigger {
+    /// Whether `bind` is included, so that the animation is played only while the dress-up part is on.
+    pub bound: bool,
+    /// Triggers of the other intervals, any of which starts the animation, in order without duplicates.
+    pub triggers: Vec<Trigger>,
+}
+
+/// Intervals combined by `+`, such as `bind+sometimes`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Intervals(pub Vec<Interval>);
+
+impl Intervals {
+    /// Checks whether `interval` is included.
+    pub fn contains(&self, interval: &Interval) -> bool {
+        self.0.contains(interval)
+    }
+
+    /// Checks whether the animation is a dress-up part.
+    pub fn is_bind(&self) -> bool {
+        self.contains(&Interval::Bind)
+    }
+
+    /// Resolves the intervals into triggers, any of which starts the animation.
+    ///
+    /// `bind` is taken as [`CombinedTrigger::bound`]. `never` is dropped when other triggers are given,
+    /// and is the only trigger when none are, except for `bind` alone, which has no triggers.
+    ///
+    /// [`CombinedTrigger::bound`]: crate::seriko::CombinedTrigger::bound
+    pub fn combined_trigger(&self) -> CombinedTrigger {
+        let mut triggers = Vec::new();
+        for trigger in self.0.iter().map(Interval::trigger) {
+            if !matches!(trigger, Trigger::Bind | Trigger::Never) && !triggers.contains(&trigger) {
+                triggers.push(trigger);
+            }
+        }
+
+        let bound = self.is_bind();
+        if triggers.is_empty() && !bound {
+            triggers.push(Trigger::Never);
+        }
+        CombinedTrigger { bound, triggers }
+    }
+}
+
+/// Keyword of an [`Interval`], with the constructor of those followed by `,N`.
+///
+/// [`Interval`]: crate::seriko::Interval
+#[derive(Clone, Copy)]
+enum IntervalTag {
+    Plain(Interval),
+    WithArg(fn(u32) -> Interval),
+}
+
+const INTERVALS: &[(&str, IntervalTag)] = &[
+    ("sometimes", IntervalTag::Plain(Interval::Sometimes)),
+    ("rarely", IntervalTag::Plain(Interval::Rarely)),
+    ("random", IntervalTag::WithArg(Interval::Random)),
+    ("periodic", IntervalTag::WithArg(Interval::Periodic)),
+    ("always", IntervalTag::Plain(Interval::Always)),
+    ("runonce", IntervalTag::Plain(Interval::RunOnce)),
+    ("never", IntervalTag::Plain(Interval::Never)),
+    ("yen-e", IntervalTag::Plain(Interval::YenE)),
+    ("talk", IntervalTag::WithArg(Interval::Talk)),
+    ("bind", IntervalTag::Plain(Interval::Bind)),
+];
+
+/// Written as [`parse_interval`] reads it.
+///
+/// [`parse_interval`]: crate::seriko::parse_interval
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arg = match self {
+            Interval::Random(n) | Interval::Periodic(n) | Interval::Talk(n) => Some(*n),
+            _ => None,
+        };
+        let (name, _) = INTERVALS
+            .iter()
+            .find(|(_, tag)| match (tag, arg) {
+                (IntervalTag::Plain(interval), None) => interval == self,
+                (IntervalTag::WithArg(make), Some(n)) => make(n) == *self,
+                _ => false,
+            })
+            .expect("every interval has a name");
+
+        f.write_str(name)?;
+        match arg {
+            Some(n) => write!(f, ",{}", n),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Written as [`parse_intervals`] reads it, joined by `+`.
+///
+/// [`parse_intervals`]: crate::seriko::parse_intervals
+impl fmt::Display for Intervals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, interval) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("+")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+        Ok(())
+    }
+}
+
+/// parser for [`Interval`].
+///
+/// [`Interval`]: crate::seriko::Interval
+pub fn parse_interval(input: &str) -> IResult<&str, Interval, ShellParseError<'_>> {
+    interval(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+fn interval<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Interval, ShellParseError<'a>> {
+    context("seriko:interval", |input| {
+        match tag_table(INTERVALS)(input)? {
+            (input, IntervalTag::Plain(interval)) => Ok((input, interval)),
+            (input, IntervalTag::WithArg(f)) => {
+                map(preceded(separator, |i| numbers.u32(i)), f)(input)
+            }
+        }
+    })(input)
+}
+
+/// parser for [`Intervals`].
+///
+/// [`Intervals`]: crate::seriko::Intervals
+pub fn parse_intervals(input: &str) -> IResult<&str, Intervals, ShellParseError<'_>> {
+    intervals(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+pub(crate) fn intervals<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Intervals, ShellParseError<'a>> {
+    map(
+        separated_list1(char('+'), |i| interval(i, numbers)),
+        Intervals,
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::error::ParseFailure;
+
+    mod parse_interval {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            assert_eq!(
+                parse_interval("sometimes").unwrap(),
+                ("", Interval::Sometimes)
+            );
+            assert_eq!(
+                parse_interval("random,4\r\n").unwrap(),
+                ("\r\n", Interval::Random(4))
+            );
+            assert_eq!(parse_interval("talk,2").unwrap(), ("", Interval::Talk(2)));
+            assert_eq!(parse_interval("yen-e").unwrap(), ("", Interval::YenE));
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            assert!(parse_interval("random").is_err());
+            assert!(parse_interval("often").is_err());
+        }
+
+        #[test]
+        fn failed_when_typo_with_expected() {
+            let err = parse_interval("somtimes\r\n").unwrap_err();
+            assert_eq!(
+                ParseFailure::from(err).to_string(),
+                "syntax error in seriko:interval at \"somtimes\", expected one of: \
+                 sometimes, rarely, random, periodic, always, runonce, never, yen-e, talk, bind"
+            );
+        }
+
+        #[test]
+        fn failed_when_typo_without_tags_in_contexts() {
+            let nom::Err::Error(err) = parse_interval("somtimes").unwrap_err() else {
+                panic!("not an error");
+            };
+            let contexts: Vec<_> = err
+                .errors
+                .iter()
+                .filter_map(|(_, kind)| match kind {
+                    nom::error::VerboseErrorKind::Context(c) => Some(*c),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(contexts, vec!["seriko:interval"]);
+            assert_eq!(err.expected.len(), 10);
+            assert_eq!(
+                nom::error::convert_error("somtimes", err.into()),
+                "0: at line 1, in Tag:\nsomtimes\n^\n\n1: at line 1, in seriko:interval:\nsomtimes\n^\n\n"
+            );
+        }
+    }
+
+    mod parse_intervals {
+        use super::*;
+
+        #[test]
+        fn success_when_combined() {
+            let (remain, result) = parse_intervals("bind+random,3\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, Intervals(vec![Interval::Bind, Interval::Random(3)]));
+            assert!(result.is_bind());
+        }
+
+        #[test]
+        fn success_when_written_back() {
+            for case in ["bind+random,3", "yen-e", "periodic,5+talk,2+never"] {
+                let (_, result) = parse_intervals(case).unwrap();
+                assert_eq!(result.to_string(), case);
+            }
+        }
+    }
+
+    mod trigger {
+        use super::*;
+
+        #[test]
+        fn success_when_each_interval() {
+            let cases = [
+                (
+                    Interval::Sometimes,
+                    Trigger::Probabilistic { denominator: 2 },
+                ),
+                (Interval::Rarely, Trigger::Probabilistic { denominator: 4 }),
+                (
+                    Interval::Random(6),
+                    Trigger::Probabilistic { denominator: 6 },
+                ),
+                (Interval::Periodic(5), Trigger::Periodic { seconds: 5 }),
+                (Interval::Always, Trigger::Always),
+                (Interval::RunOnce, Trigger::OnEvent(EventKind::SurfaceShown)),
+                (Interval::Never, Trigger::Never),
+                (Interval::YenE, Trigger::OnEvent(EventKind::ScriptEnd)),
+                (Interval::Talk(3), Trigger::OnTalk { count: 3 }),
+                (Interval::Bind, Trigger::Bind),
+            ];
+            for (interval, trigger) in cases {
+                assert_eq!(interval.trigger(), trigger, "{:?}", interval);
+            }
+        }
+
+        #[test]
+        fn success_when_event_of_each_interval() {
+            let cases = [
+                (Interval::YenE, Some(EventKind::ScriptEnd)),
+                (Interval::Talk(3), Some(EventKind::TalkCount(3))),
+                (Interval::Bind, Some(EventKind::BindToggle)),
+                (Interval::RunOnce, Some(EventKind::SurfaceShown)),
+                (Interval::Always, None),
+                (Interval::Periodic(5), None),
+            ];
+            for (interval, event) in cases {
+                assert_eq!(interval.trigger().event(), event, "{:?}", interval);
+            }
+        }
+
+        #[test]
+        fn success_when_zero_kept() {
+            assert_eq!(
+                Interval::Random(0).trigger(),
+                Trigger::Probabilistic { denominator: 0 }
+            );
+            assert_eq!(
+                Interval::Periodic(0).trigger(),
+                Trigger::Periodic { seconds: 0 }
+            );
+        }
+    }
+
+    mod combined_trigger {
+        use super::*;
+
+        fn combined(input: &str) -> CombinedTrigger {
+            parse_intervals(input).unwrap().1.combined_trigger()
+        }
+
+        #[test]
+        fn success_when_bind_combined() {
+            assert_eq!(
+                combined("bind+sometimes+talk,2"),
+                CombinedTrigger {
+                    bound: true,
+                    triggers: vec![
+                        Trigger::Probabilistic { denominator: 2 },
+                        Trigger::OnTalk { count: 2 }
+                    ],
+                }
+            );
+            assert_eq!(
+                combined("bind"),
+                CombinedTrigger {
+                    bound: true,
+                    triggers: vec![],
+                }
+            );
+        }
+
+        #[test]
+        fn success_when_never_and_duplicates() {
+            assert_eq!(
+                combined("never+random,2+sometimes+yen-e"),
+                CombinedTrigger {
+                    bound: false,
+                    triggers: vec![
+                        Trigger::Probabilistic { denominator: 2 },
+                        Trigger::OnEvent(EventKind::ScriptEnd)
+                    ],
+                }
+            );
+            assert_eq!(
+                combined("never+never"),
+                CombinedTrigger {
+                    bound: false,
+                    triggers: vec![Trigger::Never],
+                }
+            );
+        }
+    }
+}
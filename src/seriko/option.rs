@@ -0,0 +1,204 @@
+//! Options of animations, `animationN.option`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::take_while1,
+    character::complete::char,
+    combinator::{map, opt},
+    multi::separated_list1,
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{
+    value::{separator, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+use super::id_group::id_group;
+
+/// `AnimationOption` type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum AnimationOption {
+    /// `exclusive`, optionally with the group of animation IDs, `exclusive,(1,3)`.
+    Exclusive(Option<Vec<u32>>),
+    /// `background`
+    Background,
+    /// `shared-index`
+    SharedIndex,
+    /// `bind`, an alternate spelling of the interval `bind`
+    /// normalized by [`normalize_bind_entry`].
+    ///
+    /// [`normalize_bind_entry`]: crate::binding::normalize_bind_entry
+    Bind,
+    /// Option word not known by this crate.
+    Unknown(String),
+}
+
+/// Written as [`parse_animation_option`] reads one option, with the group in parentheses,
+/// such as `exclusive,(1,3)`.
+///
+/// Join options by `+` to write `animationN.option`, as [`write_animation`] does.
+///
+/// [`parse_animation_option`]: crate::seriko::parse_animation_option
+/// [`write_animation`]: crate::writer::write_animation
+impl fmt::Display for AnimationOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimationOption::Exclusive(None) => f.write_str("exclusive"),
+            AnimationOption::Exclusive(Some(ids)) => {
+                f.write_str("exclusive,(")?;
+                for (i, id) in ids.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", id)?;
+                }
+                f.write_str(")")
+            }
+            AnimationOption::Background => f.write_str("background"),
+            AnimationOption::SharedIndex => f.write_str("shared-index"),
+            AnimationOption::Bind => f.write_str("bind"),
+            AnimationOption::Unknown(word) => f.write_str(word),
+        }
+    }
+}
+
+/// parser for options of an animation.
+///
+/// Options are separated by `,` or `+`.
+/// Unknown words are kept as [`AnimationOption::Unknown`] instead of failing.
+///
+/// [`AnimationOption::Unknown`]: crate::seriko::AnimationOption::Unknown
+pub fn parse_animation_option(
+    input: &str,
+) -> IResult<&str, Vec<AnimationOption>, ShellParseError<'_>> {
+    animation_options(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+pub(crate) fn animation_options<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Vec<AnimationOption>, ShellParseError<'a>> {
+    separated_list1(alt((separator, char('+'))), |i| {
+        animation_option(i, numbers)
+    })(input)
+}
+
+fn animation_option<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, AnimationOption, ShellParseError<'a>> {
+    let (input, word) = option_word(input)?;
+    let option = match word {
+        "exclusive" => return option_exclusive(input, numbers),
+        "background" => AnimationOption::Background,
+        "shared-index" => AnimationOption::SharedIndex,
+        "bind" => AnimationOption::Bind,
+        _ => AnimationOption::Unknown(word.to_string()),
+    };
+
+    Ok((input, option))
+}
+
+/// Arguments of `exclusive`, after the word.
+fn option_exclusive<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, AnimationOption, ShellParseError<'a>> {
+    let (input, group) = opt(preceded(
+        separator,
+        map(|i| id_group(i, numbers), |group| group.ids),
+    ))(input)?;
+
+    Ok((input, AnimationOption::Exclusive(group)))
+}
+
+fn option_word(input: &str) -> IResult<&str, &str, ShellParseError<'_>> {
+    take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_')(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_animation_option {
+        use super::*;
+
+        #[test]
+        fn success_when_single_option() {
+            let case = "exclusive\r\n";
+            let (remain, result) = parse_animation_option(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, vec![AnimationOption::Exclusive(None)]);
+        }
+
+        #[test]
+        fn success_when_option_with_id_group() {
+            let case = "exclusive,(1,3,5)\r\n";
+            let (remain, result) = parse_animation_option(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                vec![AnimationOption::Exclusive(Some(vec![1, 3, 5]))]
+            );
+        }
+
+        #[test]
+        fn success_when_multiple_options() {
+            let case = "background,shared-index+exclusive,(2)\r\n";
+            let (remain, result) = parse_animation_option(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                vec![
+                    AnimationOption::Background,
+                    AnimationOption::SharedIndex,
+                    AnimationOption::Exclusive(Some(vec![2])),
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_unknown_option() {
+            let case = "background,future-option\r\n";
+            let (remain, result) = parse_animation_option(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                vec![
+                    AnimationOption::Background,
+                    AnimationOption::Unknown("future-option".to_string()),
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_unknown_option_has_known_prefix() {
+            let case = "backgrounds,shared-indexes+exclusivex,bindings\r\n";
+            let (remain, result) = parse_animation_option(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                vec![
+                    AnimationOption::Unknown("backgrounds".to_string()),
+                    AnimationOption::Unknown("shared-indexes".to_string()),
+                    AnimationOption::Unknown("exclusivex".to_string()),
+                    AnimationOption::Unknown("bindings".to_string()),
+                ]
+            );
+        }
+
+        #[test]
+        fn failed_when_empty() {
+            assert!(parse_animation_option("\r\n").is_err());
+        }
+    }
+}
@@ -0,0 +1,666 @@
+//! Patterns of animations, `animationN.patternM,method,...`.
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{cut, map, map_res, not, opt},
+    error::context,
+    sequence::{pair, preceded, terminated},
+    IResult,
+};
+
+use alloc::{vec, vec::Vec};
+use core::{fmt, ops::Range};
+
+use crate::{
+    span::{in_source, span_of, spanned, Spanned},
+    value::{parse_u32, separator, tag_table, Checked, Coordinate, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+use super::{id_group::id_group, IdGroup};
+
+/// Method drawing a surface, used by patterns and elements.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum DrawMethod {
+    Base,
+    Overlay,
+    OverlayFast,
+    OverlayMultiply,
+    Replace,
+    Interpolate,
+    Asis,
+    Move,
+    Bind,
+    Add,
+    Reduce,
+}
+
+impl DrawMethod {
+    /// Checks whether the method takes the optional trailing alpha.
+    ///
+    /// Only methods blending the image onto the base accept it:
+    /// `overlay`, `overlayfast`, `overlaymultiply`, `interpolate` and `add`.
+    pub fn supports_alpha(&self) -> bool {
+        matches!(
+            self,
+            DrawMethod::Overlay
+                | DrawMethod::OverlayFast
+                | DrawMethod::OverlayMultiply
+                | DrawMethod::Interpolate
+                | DrawMethod::Add
+        )
+    }
+}
+
+/// Names of [`DrawMethod`]s.
+///
+/// [`DrawMethod`]: crate::seriko::DrawMethod
+const DRAW_METHODS: &[(&str, DrawMethod)] = &[
+    ("base", DrawMethod::Base),
+    ("overlay", DrawMethod::Overlay),
+    ("overlayfast", DrawMethod::OverlayFast),
+    ("overlaymultiply", DrawMethod::OverlayMultiply),
+    ("replace", DrawMethod::Replace),
+    ("interpolate", DrawMethod::Interpolate),
+    ("asis", DrawMethod::Asis),
+    ("move", DrawMethod::Move),
+    ("bind", DrawMethod::Bind),
+    ("add", DrawMethod::Add),
+    ("reduce", DrawMethod::Reduce),
+];
+
+/// Written as [`parse_draw_method`] reads it.
+///
+/// [`parse_draw_method`]: crate::seriko::parse_draw_method
+impl fmt::Display for DrawMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = DRAW_METHODS
+            .iter()
+            .find(|(_, method)| method == self)
+            .expect("every method has a name");
+        f.write_str(name)
+    }
+}
+
+/// parser for [`DrawMethod`].
+///
+/// [`DrawMethod`]: crate::seriko::DrawMethod
+pub fn parse_draw_method(input: &str) -> IResult<&str, DrawMethod, ShellParseError<'_>> {
+    tag_table(DRAW_METHODS)(input)
+}
+
+/// Wait of a pattern in milliseconds.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Wait {
+    /// `50`
+    Fixed(u32),
+    /// `50-100`, random in the range.
+    Range(u32, u32),
+}
+
+/// Written as [`parse_wait`] reads it.
+///
+/// [`parse_wait`]: crate::seriko::parse_wait
+impl fmt::Display for Wait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Wait::Fixed(wait) => write!(f, "{}", wait),
+            Wait::Range(min, max) => write!(f, "{}-{}", min, max),
+        }
+    }
+}
+
+/// parser for [`Wait`].
+///
+/// [`Wait`]: crate::seriko::Wait
+pub fn parse_wait(input: &str) -> IResult<&str, Wait, ShellParseError<'_>> {
+    wait(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+fn wait<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Wait, ShellParseError<'a>> {
+    map(
+        pair(
+            |i| numbers.u32(i),
+            opt(preceded(char('-'), |i| numbers.u32(i))),
+        ),
+        |(min, max)| match max {
+            Some(max) => Wait::Range(min, max),
+            None => Wait::Fixed(min),
+        },
+    )(input)
+}
+
+/// parser for alpha (opacity) of patterns and elements.
+///
+/// It is an integer from `0` (transparent) to `255` (opaque). Fractions such as `0.5` are not accepted.
+pub fn parse_alpha(input: &str) -> IResult<&str, u8, ShellParseError<'_>> {
+    context(
+        "seriko:alpha",
+        terminated(map_res(parse_u32, u8::try_from), not(char('.'))),
+    )(input)
+}
+
+/// Number of comma-separated fields following a method.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Arity {
+    pub required: usize,
+    /// Fields which can be omitted from the end.
+    pub optional: usize,
+}
+
+impl Arity {
+    /// Checks whether `count` fields are acceptable.
+    pub fn accepts(&self, count: usize) -> bool {
+        self.required <= count && count <= self.required + self.optional
+    }
+}
+
+/// Method of a pattern.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub enum PatternMethod {
+    Draw(DrawMethod),
+    Insert,
+    Start,
+    Stop,
+    AlternativeStart,
+    AlternativeStop,
+    ParallelStart,
+    ParallelStop,
+}
+
+impl PatternMethod {
+    /// Returns the number of fields following the method.
+    ///
+    /// Draw methods take `surface,wait` and optional `x,y`,
+    /// plus optional alpha when [`DrawMethod::supports_alpha`].
+    ///
+    /// [`DrawMethod::supports_alpha`]: crate::seriko::DrawMethod::supports_alpha
+    pub fn arity(&self) -> Arity {
+        match self {
+            PatternMethod::Draw(method) => Arity {
+                required: 2,
+                optional: if method.supports_alpha() { 3 } else { 2 },
+            },
+            _ => Arity {
+                required: 1,
+                optional: 0,
+            },
+        }
+    }
+}
+
+/// Written as the method of [`parse_pattern`], such as `overlay` and `alternativestart`.
+///
+/// [`parse_pattern`]: crate::seriko::parse_pattern
+impl fmt::Display for PatternMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternMethod::Draw(method) => write!(f, "{}", method),
+            PatternMethod::Insert => f.write_str("insert"),
+            PatternMethod::Start => f.write_str("start"),
+            PatternMethod::Stop => f.write_str("stop"),
+            PatternMethod::AlternativeStart => f.write_str("alternativestart"),
+            PatternMethod::AlternativeStop => f.write_str("alternativestop"),
+            PatternMethod::ParallelStart => f.write_str("parallelstart"),
+            PatternMethod::ParallelStop => f.write_str("parallelstop"),
+        }
+    }
+}
+
+/// Pattern of an animation.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum Pattern {
+    /// `method,surface,wait,x,y,alpha`. `x,y` is `0,0` when omitted.
+    /// `alpha` can follow `x,y` only when [`DrawMethod::supports_alpha`].
+    ///
+    /// [`DrawMethod::supports_alpha`]: crate::seriko::DrawMethod::supports_alpha
+    Draw {
+        method: DrawMethod,
+        surface: i32,
+        wait: Wait,
+        offset: Coordinate,
+        alpha: Option<u8>,
+    },
+    /// `insert,ID`
+    Insert(u32),
+    /// `start,ID`
+    Start(u32),
+    /// `stop,ID`
+    Stop(u32),
+    /// `alternativestart,(ID,ID,...)`
+    AlternativeStart(IdGroup),
+    /// `alternativestop,(ID,ID,...)`
+    AlternativeStop(IdGroup),
+    /// `parallelstart,(ID,ID,...)`
+    ParallelStart(IdGroup),
+    /// `parallelstop,(ID,ID,...)`
+    ParallelStop(IdGroup),
+}
+
+impl Pattern {
+    /// Returns the method of the pattern.
+    pub fn method(&self) -> PatternMethod {
+        match self {
+            Pattern::Draw { method, .. } => PatternMethod::Draw(*method),
+            Pattern::Insert(_) => PatternMethod::Insert,
+            Pattern::Start(_) => PatternMethod::Start,
+            Pattern::Stop(_) => PatternMethod::Stop,
+            Pattern::AlternativeStart(_) => PatternMethod::AlternativeStart,
+            Pattern::AlternativeStop(_) => PatternMethod::AlternativeStop,
+            Pattern::ParallelStart(_) => PatternMethod::ParallelStart,
+            Pattern::ParallelStop(_) => PatternMethod::ParallelStop,
+        }
+    }
+
+    /// Returns IDs of animations the pattern controls, empty for drawing patterns.
+    pub fn targets(&self) -> &[u32] {
+        match self {
+            Pattern::Draw { .. } => &[],
+            Pattern::Insert(id) | Pattern::Start(id) | Pattern::Stop(id) => {
+                core::slice::from_ref(id)
+            }
+            Pattern::AlternativeStart(group)
+            | Pattern::AlternativeStop(group)
+            | Pattern::ParallelStart(group)
+            | Pattern::ParallelStop(group) => &group.ids,
+        }
+    }
+}
+
+/// Written as [`parse_pattern`] reads it, with `x,y` always and `alpha` when given.
+///
+/// [`parse_pattern`]: crate::seriko::parse_pattern
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.method())?;
+        match self {
+            Pattern::Draw {
+                surface,
+                wait,
+                offset,
+                alpha,
+                ..
+            } => {
+                write!(f, ",{},{},{}", surface, wait, offset)?;
+                match alpha {
+                    Some(alpha) => write!(f, ",{}", alpha),
+                    None => Ok(()),
+                }
+            }
+            Pattern::Insert(id) | Pattern::Start(id) | Pattern::Stop(id) => write!(f, ",{}", id),
+            Pattern::AlternativeStart(group)
+            | Pattern::AlternativeStop(group)
+            | Pattern::ParallelStart(group)
+            | Pattern::ParallelStop(group) => write!(f, ",{}", group),
+        }
+    }
+}
+
+/// parser for [`Pattern`], the value of `animationN.patternM`.
+///
+/// [`Pattern`]: crate::seriko::Pattern
+pub fn parse_pattern(input: &str) -> IResult<&str, Pattern, ShellParseError<'_>> {
+    pattern(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+/// [`Pattern`] with the spans of its method and the fields following it.
+///
+/// [`Pattern`]: crate::seriko::Pattern
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PatternSpans {
+    pub pattern: Pattern,
+    /// Span of the method name.
+    pub method: Range<usize>,
+    /// Spans of the fields following the method, in order.
+    ///
+    /// `surface`, `wait`, `x`, `y` and `alpha`, as many as given, for drawing patterns,
+    /// and the ID or the whole ID group for the others.
+    pub fields: Vec<Range<usize>>,
+}
+
+/// Same as [`parse_pattern`], with spans in `source`, of which `input` is a subslice.
+///
+/// [`parse_pattern`]: crate::seriko::parse_pattern
+pub fn parse_pattern_spanned<'a>(
+    input: &'a str,
+    source: &'a str,
+) -> IResult<&'a str, Spanned<PatternSpans>, ShellParseError<'a>> {
+    let (input, _) = in_source(input, source)?;
+    let (input, pattern) = spanned(source, parse_pattern)(input)?;
+    let text = &source[pattern.span.clone()];
+    // Every method is followed by a separator.
+    let (method, rest) = text.split_once(',').unwrap_or((text, &text[text.len()..]));
+    let fields = match pattern.value {
+        Pattern::Draw { .. } => rest.split(',').map(|f| span_of(source, f)).collect(),
+        _ => vec![span_of(source, rest)],
+    };
+
+    Ok((
+        input,
+        Spanned::new(
+            PatternSpans {
+                pattern: pattern.value,
+                method: span_of(source, method),
+                fields,
+            },
+            pattern.span,
+        ),
+    ))
+}
+
+/// Same as [`parse_pattern`], reading numbers under `policy`.
+///
+/// [`parse_pattern`]: crate::seriko::parse_pattern
+pub fn parse_pattern_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<Pattern>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| pattern(i, r))
+}
+
+pub(crate) fn pattern<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Pattern, ShellParseError<'a>> {
+    let group_arg = |i| preceded(separator, |i| id_group(i, numbers))(i);
+    let id_arg = |i| preceded(separator, |i| numbers.u32(i))(i);
+
+    context(
+        "seriko:pattern",
+        alt((
+            map(
+                preceded(tag("alternativestart"), group_arg),
+                Pattern::AlternativeStart,
+            ),
+            map(
+                preceded(tag("alternativestop"), group_arg),
+                Pattern::AlternativeStop,
+            ),
+            map(
+                preceded(tag("parallelstart"), group_arg),
+                Pattern::ParallelStart,
+            ),
+            map(
+                preceded(tag("parallelstop"), group_arg),
+                Pattern::ParallelStop,
+            ),
+            map(preceded(tag("insert"), id_arg), Pattern::Insert),
+            map(preceded(tag("start"), id_arg), Pattern::Start),
+            map(preceded(tag("stop"), id_arg), Pattern::Stop),
+            |i| pattern_draw(i, numbers),
+        )),
+    )(input)
+}
+
+fn pattern_draw<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, Pattern, ShellParseError<'a>> {
+    let (input, method) = parse_draw_method(input)?;
+    let (input, surface) = preceded(separator, |i| numbers.i32(i))(input)?;
+    let (input, wait) = preceded(separator, |i| wait(i, numbers))(input)?;
+    let (input, offset) = opt(preceded(separator, |i| numbers.coordinate(i)))(input)?;
+    let (input, alpha) = match offset {
+        Some(_) if method.supports_alpha() => trailing_alpha(input)?,
+        _ => (input, None),
+    };
+    let (input, _) = context("seriko:pattern:alpha", not(char(',')))(input)?;
+
+    Ok((
+        input,
+        Pattern::Draw {
+            method,
+            surface,
+            wait,
+            offset: offset.unwrap_or_default(),
+            alpha,
+        },
+    ))
+}
+
+/// parser for the optional `,alpha` following `x,y`.
+pub(crate) fn trailing_alpha(input: &str) -> IResult<&str, Option<u8>, ShellParseError<'_>> {
+    opt(preceded(separator, cut(parse_alpha)))(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::seriko::{IdGroupDelimiter, IdGroupSeparator};
+
+    mod parse_draw_method {
+        use super::*;
+
+        #[test]
+        fn success_when_longer_names() {
+            assert_eq!(
+                parse_draw_method("overlayfast,").unwrap(),
+                (",", DrawMethod::OverlayFast)
+            );
+            assert_eq!(
+                parse_draw_method("overlay,").unwrap(),
+                (",", DrawMethod::Overlay)
+            );
+        }
+    }
+
+    mod arity {
+        use super::*;
+
+        #[test]
+        fn reflects_optional_alpha() {
+            let overlay = PatternMethod::Draw(DrawMethod::Overlay).arity();
+            assert!(overlay.accepts(2));
+            assert!(overlay.accepts(5));
+            assert!(!overlay.accepts(6));
+
+            let base = PatternMethod::Draw(DrawMethod::Base).arity();
+            assert!(base.accepts(4));
+            assert!(!base.accepts(5));
+
+            assert_eq!(
+                PatternMethod::Start.arity(),
+                Arity {
+                    required: 1,
+                    optional: 0
+                }
+            );
+        }
+    }
+
+    mod parse_pattern_spanned {
+        use super::*;
+
+        #[test]
+        fn success_when_draw_and_group() {
+            let source = "animation0.pattern0,overlay,100,50-80,-10,20\r\n";
+            let (remain, result) = parse_pattern_spanned(&source[20..], source).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.span, 20..44);
+            assert_eq!(result.value.method, 20..27);
+            assert_eq!(result.value.fields, vec![28..31, 32..37, 38..41, 42..44]);
+            assert_eq!(
+                result.value.pattern,
+                parse_pattern(&source[20..]).unwrap().1
+            );
+
+            let source = "animation0.pattern1,alternativestart,(1,2)";
+            let (_, result) = parse_pattern_spanned(&source[20..], source).unwrap();
+            assert_eq!(result.value.method, 20..36);
+            assert_eq!(result.value.fields, vec![37..42]);
+        }
+    }
+
+    mod parse_wait {
+        use super::*;
+
+        #[test]
+        fn success_when_fixed_and_range() {
+            assert_eq!(parse_wait("50,").unwrap(), (",", Wait::Fixed(50)));
+            assert_eq!(parse_wait("50-100,").unwrap(), (",", Wait::Range(50, 100)));
+        }
+    }
+
+    mod parse_pattern {
+        use super::*;
+
+        #[test]
+        fn success_when_written_back() {
+            let cases = [
+                "overlay,10,50-100,-5,3,128",
+                "base,-1,0,0,0",
+                "insert,2",
+                "alternativestart,(1,3)",
+                "parallelstop,[2.4]",
+                "alternativestop,[5]",
+            ];
+            for case in cases {
+                let (_, pattern) = parse_pattern(case).unwrap();
+                assert_eq!(pattern.to_string(), case);
+            }
+        }
+
+        #[test]
+        fn success_when_draw() {
+            let case = "overlay,100,50,-10,20\r\n";
+            let (remain, result) = parse_pattern(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Pattern::Draw {
+                    method: DrawMethod::Overlay,
+                    surface: 100,
+                    wait: Wait::Fixed(50),
+                    offset: Coordinate::new(-10, 20),
+                    alpha: None,
+                }
+            );
+        }
+
+        #[test]
+        fn success_when_draw_without_offset() {
+            let case = "base,-1,0-200\r\n";
+            let (remain, result) = parse_pattern(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Pattern::Draw {
+                    method: DrawMethod::Base,
+                    surface: -1,
+                    wait: Wait::Range(0, 200),
+                    offset: Coordinate::new(0, 0),
+                    alpha: None,
+                }
+            );
+        }
+
+        #[test]
+        fn success_when_draw_with_alpha() {
+            let (remain, result) = parse_pattern("overlay,100,50,0,0,128\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert!(matches!(
+                result,
+                Pattern::Draw {
+                    alpha: Some(128),
+                    ..
+                }
+            ));
+        }
+
+        #[test]
+        fn failed_when_alpha_forbidden() {
+            assert!(parse_pattern("base,100,50,0,0,128").is_err());
+            assert!(parse_pattern("move,-1,50,10,10,255").is_err());
+        }
+
+        #[test]
+        fn failed_when_alpha_out_of_range() {
+            assert!(parse_pattern("overlay,100,50,0,0,256").is_err());
+            assert!(parse_pattern("overlay,100,50,0,0,0.5").is_err());
+        }
+
+        #[test]
+        fn success_when_control() {
+            assert_eq!(parse_pattern("insert,3").unwrap(), ("", Pattern::Insert(3)));
+            assert_eq!(parse_pattern("start,4").unwrap(), ("", Pattern::Start(4)));
+            assert_eq!(parse_pattern("stop,5").unwrap(), ("", Pattern::Stop(5)));
+        }
+
+        #[test]
+        fn success_when_id_group() {
+            let (remain, result) = parse_pattern("alternativestart,(1,2)\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Pattern::AlternativeStart(IdGroup {
+                    ids: vec![1, 2],
+                    delimiter: IdGroupDelimiter::Paren,
+                    separator: IdGroupSeparator::Comma,
+                })
+            );
+
+            let (remain, result) = parse_pattern("parallelstart,[3.4.5]\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Pattern::ParallelStart(IdGroup {
+                    ids: vec![3, 4, 5],
+                    delimiter: IdGroupDelimiter::Bracket,
+                    separator: IdGroupSeparator::Dot,
+                })
+            );
+            assert_eq!(result.method(), PatternMethod::ParallelStart);
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            assert!(parse_pattern("alternativestart,(1.2]").is_err());
+            assert!(parse_pattern("flash,100,50").is_err());
+            assert!(parse_pattern("overlay,100").is_err());
+        }
+    }
+
+    mod parse_pattern_with_policy {
+        use super::*;
+
+        use crate::value::Overflow;
+
+        #[test]
+        fn success_when_overflow_saturated() {
+            let policy = NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate);
+            let (_, result) =
+                parse_pattern_with_policy("overlay,10,99999999999,0,-5000000000", &policy).unwrap();
+            assert!(result.saturated);
+            assert_eq!(
+                result.value,
+                Pattern::Draw {
+                    method: DrawMethod::Overlay,
+                    surface: 10,
+                    wait: Wait::Fixed(u32::MAX),
+                    offset: Coordinate::new(0, i32::MIN),
+                    alpha: None,
+                }
+            );
+
+            let (_, result) = parse_pattern_with_policy("parallelstart,(1,2)", &policy).unwrap();
+            assert!(!result.saturated);
+        }
+
+        #[test]
+        fn failed_when_leading_zeros_rejected() {
+            let policy = NumericPolicy::DEFAULT.allow_leading_zeros(false);
+            assert!(parse_pattern_with_policy("overlay,010,50", &policy).is_err());
+            assert!(parse_pattern_with_policy("parallelstart,[1.02]", &policy).is_err());
+            assert!(parse_pattern_with_policy("overlay,10,50", &policy).is_ok());
+        }
+    }
+}
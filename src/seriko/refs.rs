@@ -0,0 +1,242 @@
+//! Validation of animation IDs referred by patterns, such as `insert,ID`.
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use core::fmt;
+
+use super::{Animation, Pattern};
+
+/// Problem of a reference found by [`validate_animation_refs`].
+///
+/// `animation` and `pattern` are `N` and `M` of `animationN.patternM` referring.
+///
+/// [`validate_animation_refs`]: crate::seriko::validate_animation_refs
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RefError {
+    /// Referring an animation not defined in the surface.
+    Dangling {
+        animation: u32,
+        pattern: u32,
+        target: u32,
+    },
+    /// `insert` of the animation itself.
+    SelfInsert { animation: u32, pattern: u32 },
+    /// `insert` returning to the animation through others.
+    ///
+    /// `cycle` is IDs of the animations in order of insertion, from `animation`.
+    InsertCycle {
+        animation: u32,
+        pattern: u32,
+        cycle: Vec<u32>,
+    },
+}
+
+impl fmt::Display for RefError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RefError::Dangling {
+                animation,
+                pattern,
+                target,
+            } => write!(
+                f,
+                "animation{}.pattern{} refers undefined animation {}",
+                animation, pattern, target
+            ),
+            RefError::SelfInsert { animation, pattern } => write!(
+                f,
+                "animation{}.pattern{} inserts itself",
+                animation, pattern
+            ),
+            RefError::InsertCycle {
+                animation,
+                pattern,
+                cycle,
+            } => write!(
+                f,
+                "animation{}.pattern{} inserts in a cycle {:?}",
+                animation, pattern, cycle
+            ),
+        }
+    }
+}
+
+impl core::error::Error for RefError {}
+
+/// Checks animation IDs referred by patterns of `animations`, the animations of one surface.
+///
+/// Each cycle of `insert` is reported once, at the pattern of the animation of the smallest ID in it.
+/// Problems are in order of `animations` and their patterns.
+pub fn validate_animation_refs(animations: &[Animation]) -> Vec<RefError> {
+    let inserts: BTreeMap<u32, Vec<u32>> = animations
+        .iter()
+        .map(|a| {
+            let targets = a
+                .patterns
+                .values()
+                .filter_map(|p| match p {
+                    Pattern::Insert(id) => Some(*id),
+                    _ => None,
+                })
+                .collect();
+            (a.id, targets)
+        })
+        .collect();
+
+    let mut errors = Vec::new();
+    for animation in animations.iter() {
+        for (index, pattern) in animation.patterns.iter() {
+            for target in pattern.targets().iter() {
+                if !inserts.contains_key(target) {
+                    errors.push(RefError::Dangling {
+                        animation: animation.id,
+                        pattern: *index,
+                        target: *target,
+                    });
+                }
+            }
+
+            let Pattern::Insert(target) = pattern else {
+                continue;
+            };
+            if *target == animation.id {
+                errors.push(RefError::SelfInsert {
+                    animation: animation.id,
+                    pattern: *index,
+                });
+            } else if *target > animation.id {
+                if let Some(cycle) = insert_path(&inserts, *target, animation.id) {
+                    errors.push(RefError::InsertCycle {
+                        animation: animation.id,
+                        pattern: *index,
+                        cycle,
+                    });
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// Finds the shortest path of `insert` from `from` to `to`, through animations of IDs greater than `to`.
+///
+/// The path starts with `to` and does not repeat it at the end.
+fn insert_path(inserts: &BTreeMap<u32, Vec<u32>>, from: u32, to: u32) -> Option<Vec<u32>> {
+    let mut previous: BTreeMap<u32, u32> = BTreeMap::new();
+    previous.insert(from, to);
+    let mut queue = vec![from];
+
+    while !queue.is_empty() {
+        let mut next = Vec::new();
+        for id in queue {
+            for target in inserts.get(&id).into_iter().flatten() {
+                if *target == to {
+                    let mut path = vec![id];
+                    while let Some(p) = previous.get(path.last().unwrap()).filter(|p| **p != to) {
+                        path.push(*p);
+                    }
+                    path.push(to);
+                    path.reverse();
+                    return Some(path);
+                }
+                if *target > to && !previous.contains_key(target) {
+                    previous.insert(*target, id);
+                    next.push(*target);
+                }
+            }
+        }
+        queue = next;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::seriko::parse_pattern;
+
+    fn animations(case: &[(u32, &[&str])]) -> Vec<Animation> {
+        case.iter()
+            .map(|(id, patterns)| {
+                let mut animation = Animation::new(*id);
+                for (i, pattern) in patterns.iter().enumerate() {
+                    let (_, pattern) = parse_pattern(pattern).unwrap();
+                    animation.patterns.insert(i as u32, pattern);
+                }
+                animation
+            })
+            .collect()
+    }
+
+    mod validate_animation_refs {
+        use super::*;
+
+        #[test]
+        fn failed_when_dangling_insert() {
+            let case = animations(&[
+                (1, &["overlay,10,50,0,0", "insert,5"]),
+                (2, &["alternativestart,(1,7)"]),
+            ]);
+            assert_eq!(
+                validate_animation_refs(&case),
+                vec![
+                    RefError::Dangling {
+                        animation: 1,
+                        pattern: 1,
+                        target: 5,
+                    },
+                    RefError::Dangling {
+                        animation: 2,
+                        pattern: 0,
+                        target: 7,
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_valid_chain() {
+            let case = animations(&[
+                (1, &["insert,2", "start,3"]),
+                (2, &["insert,3"]),
+                (3, &["overlay,10,50,0,0", "stop,1"]),
+            ]);
+            assert!(validate_animation_refs(&case).is_empty());
+        }
+
+        #[test]
+        fn failed_when_insert_cycle() {
+            let case = animations(&[(4, &["insert,2"]), (2, &["overlay,10,50,0,0", "insert,4"])]);
+            assert_eq!(
+                validate_animation_refs(&case),
+                vec![RefError::InsertCycle {
+                    animation: 2,
+                    pattern: 1,
+                    cycle: vec![2, 4],
+                }]
+            );
+
+            let case = animations(&[(1, &["insert,2"]), (2, &["insert,3"]), (3, &["insert,1"])]);
+            let errors = validate_animation_refs(&case);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(
+                errors[0].to_string(),
+                "animation1.pattern0 inserts in a cycle [1, 2, 3]"
+            );
+        }
+
+        #[test]
+        fn failed_when_self_insert() {
+            let case = animations(&[(3, &["overlay,10,50,0,0", "insert,3"])]);
+            assert_eq!(
+                validate_animation_refs(&case),
+                vec![RefError::SelfInsert {
+                    animation: 3,
+                    pattern: 1,
+                }]
+            );
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Animations assembled from `animationN.*` entries given in any order.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::fmt;
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    error::ParseFailure,
+    kv::segment_index,
+    value::{NumericPolicy, NumericReader},
+};
+
+#[cfg(feature = "surfaces")]
+use super::collision::animation_collision;
+use super::{interval::intervals, option::animation_options, pattern::pattern, Animation};
+
+/// Animations of one surface, gathered by [`AnimationSet::insert`] from entries in any order.
+///
+/// [`AnimationSet::insert`]: crate::seriko::AnimationSet::insert
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AnimationSet {
+    animations: BTreeMap<u32, Animation>,
+    /// `(animation, pattern)` of patterns given again, in order.
+    duplicates: Vec<(u32, u32)>,
+}
+
+/// Problem with an animation found by [`AnimationSet::finalize`].
+///
+/// [`AnimationSet::finalize`]: crate::seriko::AnimationSet::finalize
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum AnimationIssue {
+    /// `animationN.interval` is not given.
+    MissingInterval { id: u32 },
+    /// `animationN.patternM` is given again. The last one is kept.
+    DuplicatePattern { id: u32, index: u32 },
+    /// Patterns from `missing_from` to before `index` are not given.
+    PatternGap {
+        id: u32,
+        index: u32,
+        missing_from: u32,
+    },
+}
+
+impl AnimationIssue {
+    /// Returns the ID of the animation.
+    pub fn id(&self) -> u32 {
+        match self {
+            AnimationIssue::MissingInterval { id }
+            | AnimationIssue::DuplicatePattern { id, .. }
+            | AnimationIssue::PatternGap { id, .. } => *id,
+        }
+    }
+}
+
+impl fmt::Display for AnimationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnimationIssue::MissingInterval { id } => write!(f, "animation{} without interval", id),
+            AnimationIssue::DuplicatePattern { id, index } => {
+                write!(f, "animation{}.pattern{} given again", id, index)
+            }
+            AnimationIssue::PatternGap {
+                id,
+                index,
+                missing_from,
+            } => write!(
+                f,
+                "animation{}.pattern{} without pattern{} to pattern{}",
+                id,
+                index,
+                missing_from,
+                index - 1
+            ),
+        }
+    }
+}
+
+/// Animations already assembled, such as those of [`SurfaceBody::animations`].
+///
+/// [`SurfaceBody::animations`]: crate::surfaces::SurfaceBody::animations
+impl From<BTreeMap<u32, Animation>> for AnimationSet {
+    fn from(animations: BTreeMap<u32, Animation>) -> Self {
+        AnimationSet {
+            animations,
+            duplicates: Vec::new(),
+        }
+    }
+}
+
+impl AnimationSet {
+    pub fn new() -> AnimationSet {
+        AnimationSet::default()
+    }
+
+    /// Applies an entry of `key_segments`, such as `["animation5", "pattern0"]`, creating the animation of its index on demand.
+    ///
+    /// Returns `false` for keys other than `interval`, `option`, `patternM`, `collisionM` and `collisionexM` of `animationN`,
+    /// the last two given with the `surfaces` feature.
+    pub fn insert(&mut self, key_segments: &[&str], value: &str) -> Result<bool, ParseFailure> {
+        let given = match key_segments {
+            [animation, field] => {
+                segment_index(animation, "animation").zip(segment_index(field, "pattern"))
+            }
+            _ => None,
+        };
+        let given = given.filter(|(id, index)| {
+            self.animations
+                .get(id)
+                .is_some_and(|a| a.patterns.contains_key(index))
+        });
+
+        let applied = apply_animation_entry(
+            &mut self.animations,
+            key_segments,
+            value,
+            &NumericReader::new(NumericPolicy::DEFAULT),
+        )?;
+        if let Some(duplicate) = given {
+            self.duplicates.push(duplicate);
+        }
+        Ok(applied)
+    }
+
+    /// Returns the animation of `id`.
+    pub fn get(&self, id: u32) -> Option<&Animation> {
+        self.animations.get(&id)
+    }
+
+    /// Returns the number of animations.
+    pub fn len(&self) -> usize {
+        self.animations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    /// Returns the animations keyed by their IDs, with patterns keyed by their indices,
+    /// and problems in order of the IDs.
+    ///
+    /// Patterns are expected to be continuous from `0`.
+    pub fn finalize(self) -> (BTreeMap<u32, Animation>, Vec<AnimationIssue>) {
+        let mut issues = Vec::new();
+        for (id, animation) in self.animations.iter() {
+            let id = *id;
+            if animation.intervals.is_none() {
+                issues.push(AnimationIssue::MissingInterval { id });
+            }
+            issues.extend(
+                self.duplicates
+                    .iter()
+                    .filter(|(a, _)| *a == id)
+                    .map(|(_, index)| AnimationIssue::DuplicatePattern { id, index: *index }),
+            );
+
+            let mut expected = 0;
+            for index in animation.patterns.keys().copied() {
+                if index > expected {
+                    issues.push(AnimationIssue::PatternGap {
+                        id,
+                        index,
+                        missing_from: expected,
+                    });
+                }
+                expected = index.saturating_add(1);
+            }
+        }
+
+        (self.animations, issues)
+    }
+}
+
+/// Applies an `animationN.*` entry to `animations`, returning `false` for other keys.
+///
+/// The value is parsed before the animation is created, so a malformed value creates none.
+/// Numbers are read by `numbers`.
+pub(crate) fn apply_animation_entry(
+    animations: &mut BTreeMap<u32, Animation>,
+    key_segments: &[&str],
+    value: &str,
+    numbers: &NumericReader,
+) -> Result<bool, ParseFailure> {
+    let [animation, field] = key_segments else {
+        return Ok(false);
+    };
+    let Some(id) = segment_index(animation, "animation") else {
+        return Ok(false);
+    };
+    #[cfg(feature = "surfaces")]
+    if let Some((_, collision)) = animation_collision(key_segments, value, numbers)? {
+        animation_mut(animations, id).collisions.push(collision);
+        return Ok(true);
+    }
+    if *field == "interval" {
+        let (_, intervals) = all_consuming(|i| intervals(i, numbers))(value)?;
+        animation_mut(animations, id).intervals = Some(intervals);
+    } else if *field == "option" {
+        let (_, options) = all_consuming(|i| animation_options(i, numbers))(value)?;
+        animation_mut(animations, id).options = options;
+    } else if let Some(index) = segment_index(field, "pattern") {
+        let (_, pattern) = all_consuming(|i| pattern(i, numbers))(value)?;
+        animation_mut(animations, id)
+            .patterns
+            .insert(index, pattern);
+    } else {
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+fn animation_mut(animations: &mut BTreeMap<u32, Animation>, id: u32) -> &mut Animation {
+    animations.entry(id).or_insert_with(|| Animation::new(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::seriko::parse_pattern;
+
+    fn insert_all(cases: &[(&str, &str)]) -> AnimationSet {
+        let mut set = AnimationSet::new();
+        for (key, value) in cases {
+            let segments: Vec<_> = key.split('.').collect();
+            assert!(set.insert(&segments, value).unwrap(), "{}", key);
+        }
+        set
+    }
+
+    mod insert {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "surfaces")]
+        fn success_when_out_of_order() {
+            let set = insert_all(&[
+                ("animation5.pattern2", "overlay,102,50,0,0"),
+                ("animation5.pattern0", "overlay,100,50,0,0"),
+                ("animation5.collision0", "0,0,10,10,Hand"),
+                ("animation5.interval", "sometimes"),
+                ("animation5.pattern1", "overlay,101,50,0,0"),
+                ("animation5.option", "exclusive"),
+            ]);
+            let (animations, issues) = set.finalize();
+            let animation = &animations[&5];
+
+            assert!(issues.is_empty());
+            assert_eq!(
+                animation.patterns.keys().copied().collect::<Vec<_>>(),
+                vec![0, 1, 2]
+            );
+            assert!(animation.intervals.is_some());
+            assert_eq!(animation.options.len(), 1);
+            assert_eq!(animation.collisions.len(), 1);
+        }
+
+        #[test]
+        fn success_when_interleaved() {
+            let set = insert_all(&[
+                ("animation1.interval", "always"),
+                ("animation0.pattern0", "overlay,100,50,0,0"),
+                ("animation1.pattern0", "overlay,200,50,0,0"),
+                ("animation0.interval", "sometimes"),
+                ("animation0.pattern1", "overlay,101,50,0,0"),
+            ]);
+
+            assert_eq!(set.get(0).unwrap().patterns.len(), 2);
+            assert_eq!(set.get(1).unwrap().patterns.len(), 1);
+            let (animations, issues) = set.finalize();
+            assert_eq!(animations.keys().copied().collect::<Vec<_>>(), vec![0, 1]);
+            assert!(issues.is_empty());
+        }
+
+        #[test]
+        fn ignored_when_other_key() {
+            let mut set = AnimationSet::new();
+
+            assert!(!set.insert(&["animation0", "speed"], "2").unwrap());
+            assert!(!set.insert(&["element0"], "base,a.png,0,0").unwrap());
+            assert!(set.insert(&["animation0", "pattern0"], "x").is_err());
+            assert_eq!(set.get(0), None);
+        }
+    }
+
+    mod finalize {
+        use super::*;
+
+        #[test]
+        fn detected_when_duplicate_pattern() {
+            let set = insert_all(&[
+                ("animation2.interval", "never"),
+                ("animation2.pattern0", "overlay,100,50,0,0"),
+                ("animation2.pattern0", "overlay,101,50,0,0"),
+            ]);
+            let (animations, issues) = set.finalize();
+
+            assert_eq!(
+                issues,
+                vec![AnimationIssue::DuplicatePattern { id: 2, index: 0 }]
+            );
+            assert_eq!(
+                animations[&2].patterns[&0],
+                parse_pattern("overlay,101,50,0,0").unwrap().1
+            );
+            assert_eq!(issues[0].to_string(), "animation2.pattern0 given again");
+        }
+
+        #[test]
+        fn detected_when_missing_interval_and_gap() {
+            let set = insert_all(&[
+                ("animation3.pattern0", "overlay,100,50,0,0"),
+                ("animation3.pattern3", "overlay,103,50,0,0"),
+            ]);
+            let (_, issues) = set.finalize();
+
+            assert_eq!(
+                issues,
+                vec![
+                    AnimationIssue::MissingInterval { id: 3 },
+                    AnimationIssue::PatternGap {
+                        id: 3,
+                        index: 3,
+                        missing_from: 1,
+                    },
+                ]
+            );
+            assert_eq!(
+                issues[1].to_string(),
+                "animation3.pattern3 without pattern1 to pattern2"
+            );
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! `EventSimulator`, deciding which animations start by the events and the time passed.
+
+use alloc::{collections::BTreeSet, vec::Vec};
+
+use super::{CombinedTrigger, EventKind, Intervals, Trigger};
+
+/// Source of random numbers for probabilistic intervals, such as `sometimes`.
+pub trait RandomSource {
+    /// Returns a number from `0` to before `denominator`, which is not `0`.
+    fn below(&mut self, denominator: u32) -> u32;
+}
+
+/// State machine of the intervals of animations, giving which of them start.
+///
+/// The surface is taken as shown when the simulator is made, so `runonce` and `always` start
+/// at the first [`EventSimulator::tick`]. The runtime repeats `always` animations while the surface is shown.
+/// Animations with `bind` start only while their dress-up part is on, by [`EventSimulator::set_bound`].
+///
+/// Returned IDs are in ascending order, each at most once per call.
+///
+/// [`EventSimulator::tick`]: crate::seriko::EventSimulator::tick
+/// [`EventSimulator::set_bound`]: crate::seriko::EventSimulator::set_bound
+#[derive(Debug, Clone)]
+pub struct EventSimulator<R> {
+    animations: Vec<(u32, CombinedTrigger)>,
+    random: R,
+    talks: u32,
+    seconds: u64,
+    shown: bool,
+    bound: BTreeSet<u32>,
+}
+
+impl<R: RandomSource> EventSimulator<R> {
+    /// Makes a simulator of `animations`, IDs with their intervals, with no dress-up parts on.
+    pub fn new<'a>(
+        animations: impl IntoIterator<Item = (u32, &'a Intervals)>,
+        random: R,
+    ) -> EventSimulator<R> {
+        let mut animations: Vec<_> = animations
+            .into_iter()
+            .map(|(id, intervals)| (id, intervals.combined_trigger()))
+            .collect();
+        animations.sort_by_key(|(id, _)| *id);
+
+        EventSimulator {
+            animations,
+            random,
+            talks: 0,
+            seconds: 0,
+            shown: false,
+            bound: BTreeSet::new(),
+        }
+    }
+
+    /// Puts the dress-up part of `id` on or off, returning animations starting by it.
+    ///
+    /// Animations of `bind` alone start when the part is put on.
+    pub fn set_bound(&mut self, id: u32, on: bool) -> Vec<u32> {
+        let changed = if on {
+            self.bound.insert(id)
+        } else {
+            self.bound.remove(&id)
+        };
+        if !changed || !on {
+            return Vec::new();
+        }
+        self.fire(|animation, trigger| {
+            animation == id && trigger.bound && trigger.triggers.is_empty()
+        })
+    }
+
+    /// Ends a script, returning animations of `yen-e`.
+    pub fn on_script_end(&mut self) -> Vec<u32> {
+        self.fire_each(|trigger| *trigger == Trigger::OnEvent(EventKind::ScriptEnd))
+    }
+
+    /// Counts a talk, returning animations of `talk,N` whose `N` divides the count.
+    /// `talk,0` never starts.
+    pub fn on_talk(&mut self) -> Vec<u32> {
+        self.talks = self.talks.wrapping_add(1);
+        let talks = self.talks;
+        self.fire_each(
+            |trigger| matches!(trigger, Trigger::OnTalk { count } if *count != 0 && talks.is_multiple_of(*count)),
+        )
+    }
+
+    /// Passes `seconds`, returning animations of probabilistic and periodic intervals starting in them,
+    /// and of `runonce` and `always` at the first tick.
+    ///
+    /// Probabilistic intervals draw one number of [`RandomSource`] per second, each starting by `0`.
+    /// `random,0` and `periodic,0` never start.
+    ///
+    /// [`RandomSource`]: crate::seriko::RandomSource
+    pub fn tick(&mut self, seconds: u32) -> Vec<u32> {
+        let first = !self.shown;
+        self.shown = true;
+
+        let mut fired = self.fire_each(|trigger| {
+            first
+                && matches!(
+                    trigger,
+                    Trigger::OnEvent(EventKind::SurfaceShown) | Trigger::Always
+                )
+        });
+        for _ in 0..seconds {
+            self.seconds += 1;
+            let now = self.seconds;
+            let Self {
+                animations,
+                random,
+                bound,
+                ..
+            } = self;
+            for (id, trigger) in animations.iter() {
+                if trigger.bound && !bound.contains(id) {
+                    continue;
+                }
+                let starts = trigger.triggers.iter().any(|t| match t {
+                    Trigger::Probabilistic { denominator } => {
+                        *denominator != 0 && random.below(*denominator) == 0
+                    }
+                    Trigger::Periodic { seconds } => {
+                        *seconds != 0 && now.is_multiple_of(u64::from(*seconds))
+                    }
+                    _ => false,
+                });
+                if starts {
+                    fired.push(*id);
+                }
+            }
+        }
+        fired.sort_unstable();
+        fired.dedup();
+        fired
+    }
+
+    /// Returns animations with any trigger matching `matches`, of those not waiting for their part.
+    fn fire_each(&self, matches: impl Fn(&Trigger) -> bool) -> Vec<u32> {
+        self.fire(|_, trigger| trigger.triggers.iter().any(&matches))
+    }
+
+    fn fire(&self, matches: impl Fn(u32, &CombinedTrigger) -> bool) -> Vec<u32> {
+        self.animations
+            .iter()
+            .filter(|(id, trigger)| !trigger.bound || self.bound.contains(id))
+            .filter(|(id, trigger)| matches(*id, trigger))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::seriko::parse_intervals;
+
+    /// Returns numbers of `sequence` in turn, modulo the denominator.
+    struct Sequence(Vec<u32>, usize);
+
+    impl RandomSource for Sequence {
+        fn below(&mut self, denominator: u32) -> u32 {
+            let n = self.0[self.1 % self.0.len()];
+            self.1 += 1;
+            n % denominator
+        }
+    }
+
+    fn simulator(cases: &[(u32, &str)], random: Vec<u32>) -> EventSimulator<Sequence> {
+        let intervals: Vec<_> = cases
+            .iter()
+            .map(|(id, text)| (*id, parse_intervals(text).unwrap().1))
+            .collect();
+        EventSimulator::new(
+            intervals.iter().map(|(id, i)| (*id, i)),
+            Sequence(random, 0),
+        )
+    }
+
+    mod event_simulator {
+        use super::*;
+
+        #[test]
+        fn success_when_talk_every_n() {
+            let mut sim = simulator(&[(1, "talk,3"), (2, "talk,0"), (3, "yen-e")], vec![1]);
+            let fired: Vec<_> = (0..7).map(|_| sim.on_talk()).collect();
+            assert_eq!(
+                fired,
+                vec![vec![], vec![], vec![1], vec![], vec![], vec![1], vec![]]
+            );
+            assert_eq!(sim.on_script_end(), vec![3]);
+        }
+
+        #[test]
+        fn success_when_runonce_exactly_once() {
+            let mut sim = simulator(&[(0, "runonce"), (1, "periodic,2")], vec![1]);
+            assert_eq!(sim.tick(1), vec![0]);
+            assert_eq!(sim.tick(1), vec![1]);
+            assert_eq!(sim.tick(4), vec![1]);
+            assert!(sim.on_script_end().is_empty());
+            assert!(sim.on_talk().is_empty());
+        }
+
+        #[test]
+        fn success_when_bind_and_runonce_both_required() {
+            let cases = [(4, "bind+runonce"), (5, "bind")];
+            let mut off = simulator(&cases, vec![1]);
+            assert!(off.tick(1).is_empty());
+            assert_eq!(off.set_bound(4, true), Vec::<u32>::new());
+            assert!(off.tick(1).is_empty());
+
+            let mut on = simulator(&cases, vec![1]);
+            assert_eq!(on.set_bound(5, true), vec![5]);
+            assert!(on.set_bound(5, true).is_empty());
+            assert!(on.set_bound(4, true).is_empty());
+            assert_eq!(on.tick(1), vec![4]);
+            assert!(on.tick(1).is_empty());
+        }
+
+        #[test]
+        fn success_when_random_injected() {
+            let mut sim = simulator(&[(1, "sometimes"), (2, "random,0")], vec![1, 0, 3, 2]);
+            // `sometimes` draws 1, 0, 1, 0 modulo 2.
+            assert!(sim.tick(1).is_empty());
+            assert_eq!(sim.tick(1), vec![1]);
+            assert_eq!(sim.tick(2), vec![1]);
+        }
+    }
+}
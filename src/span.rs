@@ -0,0 +1,97 @@
+//! # `span`
+//!
+//! `Spanned`, parsed values with the byte range they came from, for editor tooling.
+//!
+//! Parsers of `*_spanned` names take the input and the `source` it is a subslice of,
+//! such as the whole decoded file, and give spans as byte offsets in `source`.
+//! They fail with the context `span:source` when the input is not a subslice of `source`.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::value::{parse_rect_spanned, Rect};
+//!
+//! let source = "collision0,10,20,110,220,Head\r\n";
+//! let (_, rect) = parse_rect_spanned(&source[11..], source).unwrap();
+//!
+//! assert_eq!(rect.value.value(), Rect::new(10, 20, 110, 220));
+//! assert_eq!(rect.span, 11..24);
+//! assert_eq!(&source[rect.value.right.span.clone()], "110");
+//! ```
+
+use core::ops::Range;
+
+use nom::{
+    error::{context, ErrorKind, ParseError},
+    IResult, Parser,
+};
+
+use crate::ShellParseError;
+
+/// Value with the byte range it came from.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Range<usize>,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Range<usize>) -> Spanned<T> {
+        Spanned { value, span }
+    }
+
+    /// Maps the value, keeping the span.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned {
+            value: f(self.value),
+            span: self.span,
+        }
+    }
+}
+
+/// Returns the offset of `part` in `source`, or `None` when it is not a subslice of `source`.
+fn offset_in(source: &str, part: &str) -> Option<usize> {
+    let start = (part.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+    (start + part.len() <= source.len()).then_some(start)
+}
+
+/// Returns the span of `part`, a subslice of `source`.
+///
+/// # Panics
+///
+/// Panics when `part` is not a subslice of `source`. Check inputs of public parsers with [`in_source`] first.
+pub(crate) fn span_of(source: &str, part: &str) -> Range<usize> {
+    let start = offset_in(source, part).expect("a subslice of the source");
+    start..start + part.len()
+}
+
+/// Checks that `input` is a subslice of `source`, failing with `span:source` otherwise.
+pub(crate) fn in_source<'a>(
+    input: &'a str,
+    source: &str,
+) -> IResult<&'a str, (), ShellParseError<'a>> {
+    match offset_in(source, input) {
+        Some(_) => Ok((input, ())),
+        None => context("span:source", |i| {
+            Err(nom::Err::Error(ShellParseError::from_error_kind(
+                i,
+                ErrorKind::Verify,
+            )))
+        })(input),
+    }
+}
+
+/// Wraps `parser` to give its output with the span of the input it consumed in `source`.
+pub(crate) fn spanned<'a, O, F>(
+    source: &'a str,
+    mut parser: F,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Spanned<O>, ShellParseError<'a>>
+where
+    F: Parser<&'a str, O, ShellParseError<'a>>,
+{
+    move |input| {
+        let (remain, value) = parser.parse(input)?;
+        let part = &input[..input.len() - remain.len()];
+        Ok((remain, Spanned::new(value, span_of(source, part))))
+    }
+}
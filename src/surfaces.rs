@@ -0,0 +1,91 @@
+//! # `surfaces`
+//!
+//! Parsers for definitions in `surfaces.txt`.
+//!
+//! With the `seriko` feature and without `surfaces`, only collisions are given, for collisions of animations.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::surfaces::{parse_collisionex, CollisionExShape};
+//! use shell_parser_common_rs::value::Rect;
+//!
+//! let case = "collisionex0,Head,ellipse,10,10,50,40\r\n";
+//! let (_remain, collision) = parse_collisionex(case).unwrap();
+//!
+//! assert_eq!(collision.id, 0);
+//! assert_eq!(collision.name, "Head");
+//! assert_eq!(collision.shape, CollisionExShape::Ellipse(Rect::new(10, 10, 50, 40)));
+//! ```
+
+#[cfg(feature = "surfaces")]
+mod alias;
+#[cfg(feature = "surfaces")]
+mod alpha;
+#[cfg(feature = "surfaces")]
+mod append;
+#[cfg(feature = "surfaces")]
+mod balloon;
+#[cfg(feature = "surfaces")]
+mod base_image;
+#[cfg(feature = "surfaces")]
+mod blocks;
+#[cfg(feature = "surfaces")]
+mod body;
+#[cfg(feature = "surfaces")]
+mod build;
+mod collision;
+mod collisionex;
+#[cfg(feature = "surfaces")]
+mod descript;
+#[cfg(feature = "surfaces")]
+mod element;
+#[cfg(feature = "surfaces")]
+mod points;
+mod region;
+
+#[cfg(all(feature = "std", feature = "document"))]
+pub(crate) use alias::alias_targets;
+#[cfg(feature = "surfaces")]
+pub use alias::{parse_alias_entry, AliasConflict, AliasEntry, AliasTable, SurfaceAliases};
+#[cfg(feature = "surfaces")]
+pub use alpha::AlphaSource;
+#[cfg(feature = "surfaces")]
+pub use append::{merge_append, Definition, MergeConflict, MergeReport};
+#[cfg(feature = "surfaces")]
+pub use balloon::{KeyForm, SurfaceBalloonSettings};
+#[cfg(feature = "surfaces")]
+pub use base_image::{missing_base_images, BaseImage};
+#[cfg(all(feature = "surfaces", feature = "document"))]
+pub(crate) use blocks::is_surface_header;
+#[cfg(feature = "rayon")]
+pub use blocks::parse_blocks_parallel;
+#[cfg(feature = "surfaces")]
+pub use blocks::{parse_blocks_with, SurfaceBlock, SurfaceBlocks};
+#[cfg(feature = "surfaces")]
+pub(crate) use body::canonical_line;
+#[cfg(feature = "surfaces")]
+pub use body::{
+    parse_block_body, parse_block_body_lenient, parse_block_body_lenient_with,
+    parse_block_body_ref, SurfaceBody, SurfaceBodyRef,
+};
+#[cfg(feature = "surfaces")]
+pub use build::{build_surfaces, build_surfaces_with, intern_elements, Surface};
+pub(crate) use collision::collision_ref;
+pub use collision::{
+    parse_collision, parse_collision_ref, parse_collision_spanned, Collision, CollisionRef,
+    CollisionSpans,
+};
+pub(crate) use collisionex::collisionex;
+pub use collisionex::{parse_collisionex, CollisionEx, CollisionExShape};
+#[cfg(feature = "surfaces")]
+pub use descript::{parse_sort_order, SortOrder, SurfacesDescript};
+#[cfg(feature = "surfaces")]
+pub(crate) use element::element_ref;
+#[cfg(feature = "surfaces")]
+pub use element::{
+    parse_element, parse_element_ref, parse_element_spanned, Element, ElementRef, ElementSpans,
+};
+#[cfg(feature = "surfaces")]
+pub use points::{Points, ResolvedPoints};
+pub use region::{known_regions, suggest_region, KnownRegion};
@@ -0,0 +1,332 @@
+//! Surface aliases of `<scope>.surface.alias` blocks, such as `smile,[2,10]`.
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{character::complete::char, combinator::all_consuming, sequence::delimited, IResult};
+
+use crate::{
+    block::Block,
+    diagnostic::Warning,
+    error::{Located, ParseFailure},
+    kv::{parse_key_value, KeyValue},
+    options::ParseOptions,
+    scope::{split_scope, Scope},
+    value::{IdList, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+use super::canonical_line;
+
+/// Aliases of one scope, keyed by name, each with the line defining it.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct AliasTable {
+    /// Sorted by name.
+    pub aliases: BTreeMap<String, Located<IdList>>,
+}
+
+/// Alias tables keyed by scope.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SurfaceAliases {
+    pub tables: BTreeMap<Scope, AliasTable>,
+}
+
+/// Alias defined both by a block and by `descript.txt` with different targets, found by [`SurfaceAliases::merge`].
+///
+/// [`SurfaceAliases::merge`]: crate::surfaces::SurfaceAliases::merge
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AliasConflict {
+    pub scope: Scope,
+    pub name: String,
+    /// Targets and line of the block, which are kept.
+    pub block: Located<IdList>,
+    /// Targets and line of `descript.txt`, which are dropped.
+    pub descript: Located<IdList>,
+}
+
+/// Alias of one entry of a `<scope>.surface.alias` block, such as `smile,[2,10]`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AliasEntry {
+    pub name: String,
+    pub targets: IdList,
+}
+
+/// parser for an alias entry, `name,[2,10]`.
+pub fn parse_alias_entry(input: &str) -> IResult<&str, (&str, IdList), ShellParseError<'_>> {
+    alias_entry(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+fn alias_entry<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, (&'a str, IdList), ShellParseError<'a>> {
+    let (input, kv) = parse_key_value(input)?;
+    let (_, targets) = all_consuming(|i| targets(i, numbers))(kv.value)?;
+
+    Ok((input, (kv.key, targets)))
+}
+
+/// parser for targets of an alias, written as `[0,5]`.
+#[cfg(all(feature = "std", feature = "document"))]
+pub(crate) fn alias_targets(input: &str) -> IResult<&str, IdList, ShellParseError<'_>> {
+    targets(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+fn targets<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, IdList, ShellParseError<'a>> {
+    delimited(char('['), |i| numbers.id_list(i), char(']'))(input)
+}
+
+/// Returns the scope of a `<scope>.surface.alias` block header.
+pub(crate) fn alias_scope(header: &str) -> Option<Scope> {
+    let segments: Vec<_> = header.split('.').collect();
+    match split_scope(&segments) {
+        Some((scope, ["surface", "alias"])) => Some(scope),
+        _ => None,
+    }
+}
+
+impl AliasTable {
+    /// Returns targets of the alias `name`.
+    pub fn get(&self, name: &str) -> Option<&IdList> {
+        self.aliases.get(name).map(|targets| &targets.value)
+    }
+
+    /// Applies an alias entry at `line`, replacing the alias of the same name.
+    pub fn apply_entry(&mut self, line: usize, text: &str) -> Result<(), ParseFailure> {
+        self.apply_entry_numbers(line, text, &NumericReader::new(NumericPolicy::DEFAULT))
+    }
+
+    fn apply_entry_numbers(
+        &mut self,
+        line: usize,
+        text: &str,
+        numbers: &NumericReader,
+    ) -> Result<(), ParseFailure> {
+        let (_, (name, targets)) = all_consuming(|i| alias_entry(i, numbers))(text)?;
+        self.aliases
+            .insert(name.to_string(), Located::new(line, targets));
+        Ok(())
+    }
+}
+
+impl SurfaceAliases {
+    /// Returns the alias table of `scope`.
+    pub fn get(&self, scope: Scope) -> Option<&AliasTable> {
+        self.tables.get(&scope)
+    }
+
+    /// Applies a `<scope>.surface.alias` block, returning its scope and the skipped lines of the body,
+    /// or `None` when `block` is not an alias block.
+    ///
+    /// Blank lines and lines of [`ParseOptions::comment_prefixes`] are ignored.
+    /// IDs are read under [`ParseOptions::numeric`], and lines with IDs clamped by [`Overflow::Saturate`]
+    /// are applied and returned with the skipped ones as [`ParseFailure::Warning`] of [`Warning::Saturated`].
+    /// Lines with more targets than [`Limits::max_id_set`] are skipped as [`ParseFailure::LimitExceeded`].
+    ///
+    /// [`ParseOptions::comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+    /// [`ParseOptions::numeric`]: crate::options::ParseOptions::numeric
+    /// [`Limits::max_id_set`]: crate::options::Limits::max_id_set
+    /// [`ParseFailure::LimitExceeded`]: crate::error::ParseFailure::LimitExceeded
+    /// [`Overflow::Saturate`]: crate::value::Overflow::Saturate
+    /// [`ParseFailure::Warning`]: crate::error::ParseFailure::Warning
+    /// [`Warning::Saturated`]: crate::diagnostic::Warning::Saturated
+    pub fn apply_block(
+        &mut self,
+        block: &Block<'_>,
+        options: &ParseOptions,
+    ) -> Option<(Scope, Vec<Located<ParseFailure>>)> {
+        let scope = alias_scope(block.header)?;
+        let table = self.tables.entry(scope).or_default();
+        let mut failures = Vec::new();
+        for line in block.body.iter() {
+            if line.text.trim().is_empty() || options.is_comment(line.text) {
+                continue;
+            }
+            let numbers = NumericReader::of(options);
+            let applied = canonical_line(line.text, options)
+                .and_then(|(text, _)| table.apply_entry_numbers(line.number, &text, &numbers));
+            match applied {
+                Err(e) => failures.push(Located::new(
+                    line.number,
+                    numbers.exceeded().map_or(e, ParseFailure::LimitExceeded),
+                )),
+                Ok(()) if numbers.saturated() => failures.push(Located::new(
+                    line.number,
+                    ParseFailure::Warning(Warning::Saturated),
+                )),
+                Ok(()) => {}
+            }
+        }
+        Some((scope, failures))
+    }
+
+    /// Applies an entry of `descript.txt`, `<scope>.surface.alias,name,[2,10]`.
+    ///
+    /// Returns `Ok(false)` when the key is not an alias key.
+    pub fn apply_descript(&mut self, entry: &Located<KeyValue<'_>>) -> Result<bool, ParseFailure> {
+        let segments = entry.value.key_segments();
+        let Some((scope, ["surface", "alias"])) = split_scope(&segments) else {
+            return Ok(false);
+        };
+        self.tables
+            .entry(scope)
+            .or_default()
+            .apply_entry(entry.line, entry.value.value)?;
+        Ok(true)
+    }
+
+    /// Adds aliases of `descript` not defined by `self`, the aliases of blocks.
+    ///
+    /// Aliases of blocks win, and those defined by both with different targets are returned in order of scope and name.
+    pub fn merge(&mut self, descript: &SurfaceAliases) -> Vec<AliasConflict> {
+        let mut conflicts = Vec::new();
+        for (scope, table) in descript.tables.iter() {
+            let merged = self.tables.entry(*scope).or_default();
+            for (name, targets) in table.aliases.iter() {
+                match merged.aliases.get(name) {
+                    Some(block) if block.value != targets.value => conflicts.push(AliasConflict {
+                        scope: *scope,
+                        name: name.clone(),
+                        block: block.clone(),
+                        descript: targets.clone(),
+                    }),
+                    Some(_) => {}
+                    None => {
+                        merged.aliases.insert(name.clone(), targets.clone());
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::block::parse_blocks;
+
+    const CASE: &str = "surface0\r\n{\r\nelement0,base,body.png,0,0\r\n}\r\nsakura.surface.alias\r\n{\r\n// smiles\r\nsmile,[2,10]\r\n0,[0]\r\n}\r\nkero.surface.alias\r\n{\r\nsmile,[10]\r\n}\r\n";
+
+    fn aliases(text: &str) -> (SurfaceAliases, Vec<Located<ParseFailure>>) {
+        let mut aliases = SurfaceAliases::default();
+        let mut failures = Vec::new();
+        for block in parse_blocks(text).unwrap() {
+            if let Some((_, f)) = aliases.apply_block(&block, &ParseOptions::STRICT) {
+                failures.extend(f);
+            }
+        }
+        (aliases, failures)
+    }
+
+    mod parse_alias_entry {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let (remain, result) = parse_alias_entry("smile,[2,10]\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, ("smile", IdList(vec![2, 10])));
+            assert!(parse_alias_entry("smile,2,10").is_err());
+        }
+    }
+
+    mod surface_aliases {
+        use super::*;
+
+        #[test]
+        fn success_when_sakura_and_kero_blocks() {
+            let (aliases, failures) = aliases(CASE);
+            assert!(failures.is_empty());
+
+            let sakura = aliases.get(Scope::Sakura).unwrap();
+            assert_eq!(sakura.get("smile"), Some(&IdList(vec![2, 10])));
+            assert_eq!(sakura.aliases["smile"].line, 8);
+            assert_eq!(sakura.get("0"), Some(&IdList(vec![0])));
+            assert_eq!(
+                aliases.get(Scope::Kero).unwrap().get("smile"),
+                Some(&IdList(vec![10]))
+            );
+            assert!(aliases.get(Scope::Char(2)).is_none());
+            assert_eq!(alias_scope("surface.alias"), None);
+        }
+
+        #[test]
+        fn ignored_when_comment_line() {
+            let case = "sakura.surface.alias\r\n{\r\n// 0,[5]\r\n\r\nsmile,[1]\r\nbroken\r\n}\r\n";
+            let (aliases, failures) = aliases(case);
+            let sakura = aliases.get(Scope::Sakura).unwrap();
+            assert_eq!(sakura.aliases.len(), 1);
+            assert_eq!(failures.len(), 1);
+            assert_eq!(failures[0].line, 6);
+        }
+
+        #[test]
+        fn failed_when_targets_over_limit() {
+            use crate::options::{Limit, LimitExceeded, Limits};
+
+            let case = "sakura.surface.alias\r\n{\r\nsmile,[1,2]\r\nangry,[1,2,3]\r\n}\r\n";
+            let options = ParseOptions::STRICT.limits(Limits::DEFAULT.max_id_set(2));
+            let mut aliases = SurfaceAliases::default();
+            let (_, failures) = aliases
+                .apply_block(&parse_blocks(case).unwrap()[0], &options)
+                .unwrap();
+
+            assert_eq!(
+                failures,
+                vec![Located::new(
+                    4,
+                    ParseFailure::LimitExceeded(LimitExceeded {
+                        limit: Limit::IdSet,
+                        max: 2
+                    })
+                )]
+            );
+            let sakura = aliases.get(Scope::Sakura).unwrap();
+            assert_eq!(sakura.get("smile"), Some(&IdList(vec![1, 2])));
+            assert_eq!(sakura.get("angry"), None);
+        }
+
+        #[test]
+        fn warned_when_conflict_with_descript() {
+            let (mut aliases, _) = aliases(CASE);
+            let mut descript = SurfaceAliases::default();
+            for (line, key, value) in [
+                (3, "sakura.surface.alias", "smile,[3]"),
+                (4, "sakura.surface.alias", "0,[0]"),
+                (5, "kero.surface.alias", "angry,[11]"),
+            ] {
+                let entry = Located::new(line, KeyValue::new(key, value));
+                assert!(descript.apply_descript(&entry).unwrap());
+            }
+            let entry = Located::new(6, KeyValue::new("sakura.name", "Sakura"));
+            assert!(!descript.apply_descript(&entry).unwrap());
+
+            assert_eq!(
+                aliases.merge(&descript),
+                vec![AliasConflict {
+                    scope: Scope::Sakura,
+                    name: "smile".to_string(),
+                    block: Located::new(8, IdList(vec![2, 10])),
+                    descript: Located::new(3, IdList(vec![3])),
+                }]
+            );
+            let sakura = aliases.get(Scope::Sakura).unwrap();
+            assert_eq!(sakura.get("smile"), Some(&IdList(vec![2, 10])));
+            assert_eq!(sakura.aliases["0"].line, 9);
+            assert_eq!(
+                aliases.get(Scope::Kero).unwrap().get("angry"),
+                Some(&IdList(vec![11]))
+            );
+        }
+    }
+}
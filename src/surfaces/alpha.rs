@@ -0,0 +1,73 @@
+//! Where the alpha of surface and element images comes from.
+
+use crate::{ascii::eq_ignore_case, descript::SerikoSettings, path::RelativePath};
+
+/// Source of the alpha of an image.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AlphaSource {
+    /// The alpha channel of the PNG image itself, `seriko.use_self_alpha,1`.
+    SelfAlpha,
+    /// The companion PNA file, such as `surface0.pna` for `surface0.png`.
+    PnaFile(RelativePath),
+    /// The image is not PNG, so it has no alpha source.
+    None,
+}
+
+impl AlphaSource {
+    /// Resolves the alpha source of `filename` (a surface or element image).
+    ///
+    /// The extension is compared case-insensitively.
+    pub fn resolve(settings: &SerikoSettings, filename: &str) -> AlphaSource {
+        let path = RelativePath::new(filename);
+
+        match path.extension() {
+            Some(ext) if eq_ignore_case(ext, "png") => {
+                if settings.use_self_alpha {
+                    AlphaSource::SelfAlpha
+                } else {
+                    AlphaSource::PnaFile(path.with_extension("pna"))
+                }
+            }
+            _ => AlphaSource::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod resolve {
+        use super::*;
+
+        #[test]
+        fn self_alpha_shell() {
+            let settings = SerikoSettings {
+                use_self_alpha: true,
+            };
+            assert_eq!(
+                AlphaSource::resolve(&settings, "surface0.png"),
+                AlphaSource::SelfAlpha
+            );
+        }
+
+        #[test]
+        fn pna_shell() {
+            let settings = SerikoSettings::default();
+            assert_eq!(
+                AlphaSource::resolve(&settings, "parts\\arm.PNG"),
+                AlphaSource::PnaFile(RelativePath::new("parts/arm.pna"))
+            );
+        }
+
+        #[test]
+        fn non_png_element() {
+            let settings = SerikoSettings::default();
+            assert_eq!(
+                AlphaSource::resolve(&settings, "parts/arm.bmp"),
+                AlphaSource::None
+            );
+            assert_eq!(AlphaSource::resolve(&settings, "png"), AlphaSource::None);
+        }
+    }
+}
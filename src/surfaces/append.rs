@@ -0,0 +1,452 @@
+//! Merging `surface.appendN` blocks onto base `surfaceN` definitions.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::{
+    cursor::CursorKind,
+    descript::BalloonOffset,
+    seriko::{Animation, AnimationCollision, Intervals},
+};
+
+use super::{Collision, CollisionEx, Element, SurfaceBody};
+
+/// Definition of a surface body, as reported by [`merge_append`].
+///
+/// [`merge_append`]: crate::surfaces::merge_append
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Definition {
+    /// `elementN`
+    Element(u32),
+    /// `collisionN`
+    Collision(u32),
+    /// `collisionexN`
+    CollisionEx(u32),
+    /// `animationN.*`, an animation not defined by the base.
+    Animation(u32),
+    /// `animationN.interval` of an animation defined by the base.
+    Interval(u32),
+    /// `animationN.patternM` of an animation defined by the base.
+    Pattern { animation: u32, index: u32 },
+    /// Options of `animationN.option` not given by the base.
+    Options(u32),
+    /// `point.*`
+    Points,
+    /// `balloon.*` and `<scope>.balloon.*`
+    Balloon,
+    /// `cursor.mouseupN` and `cursor.mousedownN`
+    Cursor { kind: CursorKind, index: u32 },
+    /// `tooltip.<collision>`
+    Tooltip(String),
+}
+
+/// Result of [`merge_append`].
+///
+/// [`merge_append`]: crate::surfaces::merge_append
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MergeReport {
+    /// Definitions replacing those of the base, in order.
+    pub overridden: Vec<Definition>,
+    /// Definitions added to the base, in order.
+    pub added: Vec<Definition>,
+    /// Whether the base had no definitions, such as when no `surfaceN` is given for the append.
+    pub without_base: bool,
+    /// Definitions of the append disagreeing with those of the base, in order.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Disagreement of an append with its base, reported by [`merge_append`].
+///
+/// [`merge_append`]: crate::surfaces::merge_append
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MergeConflict {
+    /// `animationN.interval` of the append differs from that of the base. The append is kept.
+    Interval {
+        id: u32,
+        base: Intervals,
+        append: Intervals,
+    },
+}
+
+/// Applies `append`, the body of a `surface.appendN` block, onto `base`.
+///
+/// Elements, collisions and collisionexes replace those of the same ID in `base`,
+/// keeping their position, and the others are added after them.
+/// Animations of an ID not in `base` are added. Those of an ID in `base` are merged:
+/// the interval given in `append` replaces that of `base`, reported as a conflict when they differ,
+/// patterns and animation collisions replace those of the same index or are added,
+/// and options not in `base` are added after those of `base`.
+/// `point.*` and balloon keys given in `append` replace those of `base`,
+/// and cursors, tooltips and bind notes are added after those of `base`.
+///
+/// Appends are applied in order by calling this once per `surface.appendN` block.
+pub fn merge_append(base: &mut SurfaceBody, append: &SurfaceBody) -> MergeReport {
+    let mut report = MergeReport {
+        without_base: *base == SurfaceBody::default(),
+        ..MergeReport::default()
+    };
+
+    for element in append.elements.iter() {
+        let overridden = replace_or_push(&mut base.elements, element, |e: &Element| e.id);
+        report.push(overridden, Definition::Element(element.id));
+    }
+    for collision in append.collisions.iter() {
+        let overridden = replace_or_push(&mut base.collisions, collision, |c: &Collision| c.id);
+        report.push(overridden, Definition::Collision(collision.id));
+    }
+    for collisionex in append.collisionexes.iter() {
+        let overridden =
+            replace_or_push(&mut base.collisionexes, collisionex, |c: &CollisionEx| c.id);
+        report.push(overridden, Definition::CollisionEx(collisionex.id));
+    }
+    for (id, animation) in append.animations.iter() {
+        match base.animations.get_mut(id) {
+            Some(base) => merge_animation(base, animation, &mut report),
+            None => {
+                base.animations.insert(*id, animation.clone());
+                report.added.push(Definition::Animation(*id));
+            }
+        }
+    }
+
+    let points = &append.points;
+    let merged = [
+        merge_field(&mut base.points.center_x, points.center_x),
+        merge_field(&mut base.points.center_y, points.center_y),
+        merge_field(&mut base.points.kinoko_center_x, points.kinoko_center_x),
+        merge_field(&mut base.points.kinoko_center_y, points.kinoko_center_y),
+        merge_field(&mut base.points.basepos_x, points.basepos_x),
+        merge_field(&mut base.points.basepos_y, points.basepos_y),
+    ];
+    report.push_fields(&merged, Definition::Points);
+
+    let mut merged = merge_offset(&mut base.balloon.unscoped, &append.balloon.unscoped).to_vec();
+    for (scope, offset) in append.balloon.scoped.iter() {
+        merged.extend(merge_offset(
+            base.balloon.scoped.entry(*scope).or_default(),
+            offset,
+        ));
+    }
+    report.push_fields(&merged, Definition::Balloon);
+
+    for cursor in append.cursors.cursors.iter() {
+        base.cursors.cursors.push(cursor.clone());
+        report.added.push(Definition::Cursor {
+            kind: cursor.kind,
+            index: cursor.index,
+        });
+    }
+    for tooltip in append.tooltips.tooltips.iter() {
+        base.tooltips.tooltips.push(tooltip.clone());
+        report
+            .added
+            .push(Definition::Tooltip(tooltip.collision.clone()));
+    }
+    base.bind_notes.extend(append.bind_notes.iter().copied());
+
+    report
+}
+
+impl MergeReport {
+    fn push(&mut self, overridden: bool, definition: Definition) {
+        if overridden {
+            self.overridden.push(definition);
+        } else {
+            self.added.push(definition);
+        }
+    }
+
+    /// Reports `definition` as overridden when any of `merged` fields replaced one of the base,
+    /// and as added when any is new.
+    fn push_fields(&mut self, merged: &[Option<bool>], definition: Definition) {
+        if merged.contains(&Some(true)) {
+            self.overridden.push(definition.clone());
+        }
+        if merged.contains(&Some(false)) {
+            self.added.push(definition);
+        }
+    }
+}
+
+/// Merges `append` onto `base` of the same ID, as documented in [`merge_append`].
+///
+/// [`merge_append`]: crate::surfaces::merge_append
+fn merge_animation(base: &mut Animation, append: &Animation, report: &mut MergeReport) {
+    let id = base.id;
+    if let Some(intervals) = &append.intervals {
+        match base.intervals.replace(intervals.clone()) {
+            Some(previous) => {
+                if previous != *intervals {
+                    report.conflicts.push(MergeConflict::Interval {
+                        id,
+                        base: previous,
+                        append: intervals.clone(),
+                    });
+                }
+                report.overridden.push(Definition::Interval(id));
+            }
+            None => report.added.push(Definition::Interval(id)),
+        }
+    }
+    for (index, pattern) in append.patterns.iter() {
+        let overridden = base.patterns.insert(*index, pattern.clone()).is_some();
+        report.push(
+            overridden,
+            Definition::Pattern {
+                animation: id,
+                index: *index,
+            },
+        );
+    }
+    let mut options_added = false;
+    for option in append.options.iter() {
+        if !base.options.contains(option) {
+            base.options.push(option.clone());
+            options_added = true;
+        }
+    }
+    if options_added {
+        report.added.push(Definition::Options(id));
+    }
+    for collision in append.collisions.iter() {
+        replace_or_push(&mut base.collisions, collision, |c: &AnimationCollision| {
+            (matches!(c, AnimationCollision::Ex(_)), c.id())
+        });
+    }
+}
+
+/// Replaces the definition of the same ID in `base`, or pushes it.
+/// Returns whether one is replaced.
+fn replace_or_push<T: Clone, K: PartialEq>(
+    base: &mut Vec<T>,
+    definition: &T,
+    id: impl Fn(&T) -> K,
+) -> bool {
+    match base.iter_mut().find(|d| id(d) == id(definition)) {
+        Some(d) => {
+            *d = definition.clone();
+            true
+        }
+        None => {
+            base.push(definition.clone());
+            false
+        }
+    }
+}
+
+/// Sets `base` to `append` when given. Returns whether `base` is replaced, or `None` when not given.
+fn merge_field<T>(base: &mut Option<T>, append: Option<T>) -> Option<bool> {
+    let append = append?;
+    Some(base.replace(append).is_some())
+}
+
+fn merge_offset(base: &mut BalloonOffset, append: &BalloonOffset) -> [Option<bool>; 3] {
+    [
+        merge_field(&mut base.x, append.x),
+        merge_field(&mut base.y, append.y),
+        merge_field(&mut base.alignment, append.alignment),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod merge_append {
+        use super::*;
+
+        use crate::seriko::{parse_pattern, AnimationOption, Interval, Pattern};
+
+        fn base_pattern(value: &str) -> Pattern {
+            parse_pattern(value).unwrap().1
+        }
+
+        fn body(lines: &[&str]) -> SurfaceBody {
+            let mut body = SurfaceBody::default();
+            for line in lines.iter() {
+                body.apply_line(line).unwrap();
+            }
+            body
+        }
+
+        #[test]
+        fn success_when_element_overridden() {
+            let mut base = body(&[
+                "element0,base,body.png,0,0",
+                "element1,overlay,face.png,0,0",
+                "point.centerx,10",
+            ]);
+            let append = body(&["element0,base,body2.png,0,0", "point.centerx,20"]);
+            let report = merge_append(&mut base, &append);
+
+            assert_eq!(base.elements.len(), 2);
+            assert_eq!(base.elements[0].filename, "body2.png");
+            assert_eq!(base.elements[1].filename, "face.png");
+            assert_eq!(base.points.center_x, Some(20));
+            assert_eq!(
+                report.overridden,
+                vec![Definition::Element(0), Definition::Points]
+            );
+            assert!(report.added.is_empty());
+            assert!(!report.without_base);
+        }
+
+        #[test]
+        fn success_when_collision_added() {
+            let mut base = body(&["collision0,0,0,10,10,Head", "tooltip.Head,head"]);
+            let append = body(&["collision1,0,10,10,20,Bust", "tooltip.Bust,bust"]);
+            let report = merge_append(&mut base, &append);
+
+            assert_eq!(base.collision_names(), vec!["Head", "Bust"]);
+            assert_eq!(base.tooltips.tooltips.len(), 2);
+            assert!(report.overridden.is_empty());
+            assert_eq!(
+                report.added,
+                vec![Definition::Collision(1), Definition::Tooltip("Bust".into())]
+            );
+        }
+
+        #[test]
+        fn success_when_without_base() {
+            let mut base = SurfaceBody::default();
+            let append = body(&[
+                "element0,overlay,arm.png,0,0",
+                "animation0.pattern0,overlay,100,50,0,0",
+                "sakura.balloon.offsetx,10",
+            ]);
+            let report = merge_append(&mut base, &append);
+
+            assert!(report.without_base);
+            assert_eq!(base, append);
+            assert_eq!(
+                report.added,
+                vec![
+                    Definition::Element(0),
+                    Definition::Animation(0),
+                    Definition::Balloon
+                ]
+            );
+        }
+
+        #[test]
+        fn success_when_appends_applied_in_order() {
+            let mut base = body(&["element0,base,body.png,0,0"]);
+            let first = body(&["element1,overlay,a.png,0,0"]);
+            let second = body(&["element1,overlay,b.png,0,0", "element2,overlay,c.png,0,0"]);
+
+            let report = merge_append(&mut base, &first);
+            assert_eq!(report.added, vec![Definition::Element(1)]);
+            let report = merge_append(&mut base, &second);
+            assert_eq!(report.overridden, vec![Definition::Element(1)]);
+            assert_eq!(report.added, vec![Definition::Element(2)]);
+
+            let filenames: Vec<_> = base.elements.iter().map(|e| e.filename.as_str()).collect();
+            assert_eq!(filenames, vec!["body.png", "b.png", "c.png"]);
+        }
+
+        #[test]
+        fn success_when_interval_replaced() {
+            let mut base = body(&[
+                "animation5.interval,sometimes",
+                "animation5.pattern0,overlay,100,50,0,0",
+                "animation6.pattern0,overlay,200,50,0,0",
+            ]);
+            let append = body(&[
+                "animation5.interval,sometimes",
+                "animation6.interval,talk,2",
+            ]);
+            let report = merge_append(&mut base, &append);
+
+            assert_eq!(
+                base.animations[&5].intervals,
+                Some(Intervals(vec![Interval::Sometimes]))
+            );
+            assert_eq!(base.animations[&5].patterns.len(), 1);
+            assert_eq!(
+                base.animations[&6].intervals,
+                Some(Intervals(vec![Interval::Talk(2)]))
+            );
+            assert_eq!(report.overridden, vec![Definition::Interval(5)]);
+            assert_eq!(report.added, vec![Definition::Interval(6)]);
+            assert!(report.conflicts.is_empty());
+        }
+
+        #[test]
+        fn detected_when_intervals_disagree() {
+            let mut base = body(&["animation5.interval,sometimes"]);
+            let append = body(&["animation5.interval,bind+rarely"]);
+            let report = merge_append(&mut base, &append);
+
+            assert_eq!(
+                base.animations[&5].intervals,
+                Some(Intervals(vec![Interval::Bind, Interval::Rarely]))
+            );
+            assert_eq!(report.overridden, vec![Definition::Interval(5)]);
+            assert_eq!(
+                report.conflicts,
+                vec![MergeConflict::Interval {
+                    id: 5,
+                    base: Intervals(vec![Interval::Sometimes]),
+                    append: Intervals(vec![Interval::Bind, Interval::Rarely]),
+                }]
+            );
+        }
+
+        #[test]
+        fn success_when_patterns_merged_by_index() {
+            let mut base = body(&[
+                "animation5.interval,sometimes",
+                "animation5.pattern0,overlay,100,50,0,0",
+                "animation5.pattern1,overlay,101,50,0,0",
+            ]);
+            let append = body(&[
+                "animation5.pattern1,overlay,201,50,0,0",
+                "animation5.pattern2,overlay,202,50,0,0",
+            ]);
+            let report = merge_append(&mut base, &append);
+
+            let patterns = &base.animations[&5].patterns;
+            assert_eq!(patterns.len(), 3);
+            assert_eq!(patterns[&0], base_pattern("overlay,100,50,0,0"));
+            assert_eq!(patterns[&1], base_pattern("overlay,201,50,0,0"));
+            assert_eq!(patterns[&2], base_pattern("overlay,202,50,0,0"));
+            assert_eq!(
+                report.overridden,
+                vec![Definition::Pattern {
+                    animation: 5,
+                    index: 1
+                }]
+            );
+            assert_eq!(
+                report.added,
+                vec![Definition::Pattern {
+                    animation: 5,
+                    index: 2
+                }]
+            );
+        }
+
+        #[test]
+        fn success_when_options_united() {
+            let mut base = body(&[
+                "animation5.interval,sometimes",
+                "animation5.option,exclusive",
+            ]);
+            let append = body(&["animation5.option,exclusive+background"]);
+            let report = merge_append(&mut base, &append);
+
+            assert_eq!(
+                base.animations[&5].options,
+                vec![
+                    AnimationOption::Exclusive(None),
+                    AnimationOption::Background
+                ]
+            );
+            assert_eq!(report.added, vec![Definition::Options(5)]);
+            assert!(report.overridden.is_empty());
+
+            let report = merge_append(&mut base, &append);
+            assert!(report.added.is_empty());
+            assert_eq!(base.animations[&5].options.len(), 2);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Balloon settings overridden inside a surface block.
+
+use alloc::collections::BTreeMap;
+
+use crate::{
+    descript::{BalloonOffset, BalloonOffsets},
+    scope::{split_scope, Scope},
+    value::{NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// Form of the key used in a surface block.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeyForm {
+    /// `balloon.offsetx`
+    Unscoped,
+    /// `sakura.balloon.offsetx`
+    Scoped(Scope),
+}
+
+/// Balloon settings inside a `surfaceN` block.
+///
+/// Unscoped keys apply to whichever scope displays the surface,
+/// scoped keys only to the scope. Both are kept apart in order to record the form used.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SurfaceBalloonSettings {
+    pub unscoped: BalloonOffset,
+    /// Keyed by scope, in order of [`Scope`].
+    ///
+    /// [`Scope`]: crate::scope::Scope
+    pub scoped: BTreeMap<Scope, BalloonOffset>,
+}
+
+impl SurfaceBalloonSettings {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns the form of the key, or `Ok(None)` when the key is not a balloon key.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<Option<KeyForm>, nom::Err<ShellParseError<'a>>> {
+        self.apply_with_numbers(
+            key_segments,
+            value,
+            &NumericReader::new(NumericPolicy::DEFAULT),
+        )
+    }
+
+    /// Same as [`SurfaceBalloonSettings::apply`], reading numbers by `numbers`.
+    ///
+    /// [`SurfaceBalloonSettings::apply`]: crate::surfaces::SurfaceBalloonSettings::apply
+    pub(crate) fn apply_with_numbers<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+        numbers: &NumericReader,
+    ) -> Result<Option<KeyForm>, nom::Err<ShellParseError<'a>>> {
+        if let Some((scope, rest)) = split_scope(key_segments) {
+            let mut offset = self.scoped.get(&scope).copied().unwrap_or_default();
+            if !offset.apply_with_numbers(rest, value, numbers)? {
+                return Ok(None);
+            }
+            self.scoped.insert(scope, offset);
+            return Ok(Some(KeyForm::Scoped(scope)));
+        }
+
+        if self
+            .unscoped
+            .apply_with_numbers(key_segments, value, numbers)?
+        {
+            Ok(Some(KeyForm::Unscoped))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Resolves the settings of `scope` over the descript-level `descript`.
+    ///
+    /// Precedence: scoped block value, unscoped block value, then descript value.
+    pub fn resolve(&self, scope: Scope, descript: &BalloonOffsets) -> BalloonOffset {
+        let base = descript.get(scope).copied().unwrap_or_default();
+        let block = match self.scoped.get(&scope) {
+            Some(scoped) => scoped.or(&self.unscoped),
+            None => self.unscoped,
+        };
+
+        block.or(&base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::descript::BalloonAlignment;
+
+    mod surface_balloon_settings {
+        use super::*;
+
+        fn descript() -> BalloonOffsets {
+            let mut offsets = BalloonOffsets::default();
+            offsets
+                .apply(&["sakura", "balloon", "offsetx"], "10")
+                .unwrap();
+            offsets
+                .apply(&["sakura", "balloon", "offsety"], "20")
+                .unwrap();
+            offsets
+                .apply(&["sakura", "balloon", "alignment"], "left")
+                .unwrap();
+            offsets
+        }
+
+        #[test]
+        fn block_overrides_one_axis_only() {
+            let mut settings = SurfaceBalloonSettings::default();
+            let form = settings.apply(&["balloon", "offsetx"], "-30").unwrap();
+            assert_eq!(form, Some(KeyForm::Unscoped));
+
+            let result = settings.resolve(Scope::Sakura, &descript());
+            assert_eq!(result.x, Some(-30));
+            assert_eq!(result.y, Some(20));
+        }
+
+        #[test]
+        fn alignment_value() {
+            let mut settings = SurfaceBalloonSettings::default();
+            let form = settings
+                .apply(&["sakura", "balloon", "alignment"], "right")
+                .unwrap();
+            assert_eq!(form, Some(KeyForm::Scoped(Scope::Sakura)));
+
+            let result = settings.resolve(Scope::Sakura, &descript());
+            assert_eq!(result.alignment, Some(BalloonAlignment::Right));
+
+            assert!(settings.apply(&["balloon", "alignment"], "middle").is_err());
+        }
+
+        #[test]
+        fn merge_precedence() {
+            let mut settings = SurfaceBalloonSettings::default();
+            settings.apply(&["balloon", "offsetx"], "1").unwrap();
+            settings.apply(&["balloon", "offsety"], "2").unwrap();
+            settings
+                .apply(&["sakura", "balloon", "offsetx"], "3")
+                .unwrap();
+
+            let sakura = settings.resolve(Scope::Sakura, &descript());
+            assert_eq!(sakura.x, Some(3));
+            assert_eq!(sakura.y, Some(2));
+            assert_eq!(sakura.alignment, Some(BalloonAlignment::Left));
+
+            let kero = settings.resolve(Scope::Kero, &descript());
+            assert_eq!(kero.x, Some(1));
+            assert_eq!(kero.y, Some(2));
+            assert_eq!(kero.alignment, None);
+        }
+
+        #[test]
+        fn ignored_when_unknown_key() {
+            let mut settings = SurfaceBalloonSettings::default();
+            assert_eq!(settings.apply(&["element0"], "x").unwrap(), None);
+            assert_eq!(settings.apply(&["kero", "name"], "x").unwrap(), None);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Where the base image of a surface comes from, given by its elements or by its ID.
+
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+
+use crate::{ascii::eq_ignore_case, descript::SerikoSettings};
+
+use super::{AlphaSource, Element, Surface};
+
+/// Base image of a [`Surface`].
+///
+/// [`Surface`]: crate::surfaces::Surface
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum BaseImage {
+    /// Drawn by the `elementN` lines of the surface.
+    Explicit,
+    /// No `elementN` lines, so the image is the file named after the ID, as ancient shells rely on.
+    Conventional {
+        /// `surfaceN.png` of the ID without padding, such as `surface5.png`.
+        expected_filename: String,
+    },
+}
+
+impl BaseImage {
+    /// Returns the base image of surface `id` drawn by `elements`.
+    pub fn of(id: u32, elements: &[Element]) -> BaseImage {
+        if elements.is_empty() {
+            BaseImage::Conventional {
+                expected_filename: format!("surface{}.png", id),
+            }
+        } else {
+            BaseImage::Explicit
+        }
+    }
+
+    /// Returns the first of `files` which is the conventional image, or `None` for [`BaseImage::Explicit`].
+    ///
+    /// Names are compared ignoring ASCII case, and the ID may be zero-padded to any width,
+    /// so that `Surface0005.PNG` is the image of surface 5. Names in directories never match.
+    ///
+    /// [`BaseImage::Explicit`]: crate::surfaces::BaseImage::Explicit
+    pub fn find<'f>(&self, files: &[&'f str]) -> Option<&'f str> {
+        let BaseImage::Conventional { expected_filename } = self else {
+            return None;
+        };
+        let id = conventional_id(expected_filename)?;
+        files
+            .iter()
+            .copied()
+            .find(|file| conventional_id(file) == Some(id))
+    }
+
+    /// Returns the alpha source of the conventional image, as [`AlphaSource::resolve`] does,
+    /// or `None` for [`BaseImage::Explicit`], whose alpha is that of each element.
+    ///
+    /// [`AlphaSource::resolve`]: crate::surfaces::AlphaSource::resolve
+    /// [`BaseImage::Explicit`]: crate::surfaces::BaseImage::Explicit
+    pub fn alpha_source(&self, settings: &SerikoSettings) -> Option<AlphaSource> {
+        match self {
+            BaseImage::Explicit => None,
+            BaseImage::Conventional { expected_filename } => {
+                Some(AlphaSource::resolve(settings, expected_filename))
+            }
+        }
+    }
+}
+
+/// Returns the ID of `surfaceN.png`, ignoring ASCII case and zeros padding `N`.
+fn conventional_id(file: &str) -> Option<u32> {
+    let (stem, extension) = file.rsplit_once('.')?;
+    if !eq_ignore_case(extension, "png") {
+        return None;
+    }
+    let (prefix, digits) = stem.split_at_checked("surface".len())?;
+    if !eq_ignore_case(prefix, "surface")
+        || digits.is_empty()
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Returns IDs of `surfaces` relying on a [`BaseImage::Conventional`] image which is none of `files`,
+/// such as the names of the shell directory, in order.
+///
+/// [`BaseImage::Conventional`]: crate::surfaces::BaseImage::Conventional
+pub fn missing_base_images(surfaces: &BTreeMap<u32, Surface>, files: &[&str]) -> Vec<u32> {
+    surfaces
+        .values()
+        .filter(|s| matches!(s.base_image, BaseImage::Conventional { .. }))
+        .filter(|s| s.base_image.find(files).is_none())
+        .map(|s| s.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::{
+        options::ParseOptions,
+        path::RelativePath,
+        surfaces::{build_surfaces, parse_blocks_with},
+    };
+
+    fn build(text: &str) -> BTreeMap<u32, Surface> {
+        let blocks = parse_blocks_with(text, &ParseOptions::COMPATIBLE).unwrap();
+        build_surfaces(&blocks.blocks).0
+    }
+
+    mod of {
+        use super::*;
+
+        #[test]
+        fn success_when_elements_given() {
+            let surfaces = build("surface0\r\n{\r\nelement0,base,body.png,0,0\r\n}\r\n");
+            assert_eq!(surfaces[&0].base_image, BaseImage::Explicit);
+        }
+
+        #[test]
+        fn success_when_no_elements() {
+            let surfaces = build("surface3-4\r\n{\r\ncollision0,0,0,10,10,Head\r\n}\r\n");
+            assert_eq!(
+                surfaces[&3].base_image,
+                BaseImage::Conventional {
+                    expected_filename: "surface3.png".into()
+                }
+            );
+            assert_eq!(
+                surfaces[&4].base_image,
+                BaseImage::Conventional {
+                    expected_filename: "surface4.png".into()
+                }
+            );
+
+            let pna = SerikoSettings::default();
+            assert_eq!(
+                surfaces[&3].base_image.alpha_source(&pna),
+                Some(AlphaSource::PnaFile(RelativePath::new("surface3.pna")))
+            );
+            let self_alpha = SerikoSettings {
+                use_self_alpha: true,
+            };
+            assert_eq!(
+                surfaces[&3].base_image.alpha_source(&self_alpha),
+                Some(AlphaSource::SelfAlpha)
+            );
+        }
+    }
+
+    mod find {
+        use super::*;
+
+        #[test]
+        fn success_when_id_zero_padded() {
+            let image = BaseImage::of(12, &[]);
+            assert_eq!(
+                image.find(&["surface0.png", "Surface0012.PNG"]),
+                Some("Surface0012.PNG")
+            );
+            assert_eq!(image.find(&["surface12.png"]), Some("surface12.png"));
+            assert_eq!(
+                BaseImage::of(0, &[]).find(&["surface0000.png"]),
+                Some("surface0000.png")
+            );
+        }
+
+        #[test]
+        fn none_when_other_names() {
+            let image = BaseImage::of(1, &[]);
+            assert_eq!(
+                image.find(&[
+                    "surface10.png",
+                    "surface1.bmp",
+                    "img/surface1.png",
+                    "surface.png"
+                ]),
+                None
+            );
+            assert_eq!(BaseImage::Explicit.find(&["surface1.png"]), None);
+        }
+    }
+
+    mod missing_base_images {
+        use super::*;
+
+        #[test]
+        fn detected_when_conventional_image_missing() {
+            let surfaces = build(
+                "surface0-2\r\n{\r\n}\r\nsurface5\r\n{\r\nelement0,base,body.png,0,0\r\n}\r\n",
+            );
+            assert_eq!(
+                missing_base_images(&surfaces, &["surface0.png", "surface002.png", "body.png"]),
+                vec![1]
+            );
+        }
+    }
+}
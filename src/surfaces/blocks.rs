@@ -0,0 +1,226 @@
+//! Surface blocks of a whole `surfaces.txt`, with their bodies parsed.
+
+use alloc::vec::Vec;
+
+use crate::{
+    block::{self, Block, BlockError},
+    error::{Located, ParseFailure},
+    options::ParseOptions,
+};
+
+use super::{parse_block_body_lenient_with, SurfaceAliases, SurfaceBody};
+
+/// Surface block and its body.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SurfaceBlock<'a> {
+    pub block: Block<'a>,
+    pub body: SurfaceBody,
+}
+
+/// Result of [`parse_blocks_with`] and [`parse_blocks_parallel`].
+///
+/// [`parse_blocks_with`]: crate::surfaces::parse_blocks_with
+/// [`parse_blocks_parallel`]: crate::surfaces::parse_blocks_parallel
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SurfaceBlocks<'a> {
+    /// Surface blocks, in order.
+    pub blocks: Vec<SurfaceBlock<'a>>,
+    /// Aliases of `<scope>.surface.alias` blocks, the later ones replacing aliases of the same name.
+    pub aliases: SurfaceAliases,
+    /// Skipped lines of the bodies, in line order.
+    pub failures: Vec<Located<ParseFailure>>,
+}
+
+/// Parses surface blocks of `text`, skipping malformed or unknown lines of the bodies
+/// as [`parse_block_body_lenient_with`] does.
+///
+/// `<scope>.surface.alias` blocks are read into [`SurfaceBlocks::aliases`],
+/// and other blocks than `surfaceN` and `surface.appendN`, such as `descript`, are not included.
+/// The text is checked against [`ParseOptions::limits`] as [`block::parse_blocks_with`] does.
+///
+/// [`SurfaceBlocks::aliases`]: crate::surfaces::SurfaceBlocks::aliases
+/// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+/// [`block::parse_blocks_with`]: crate::block::parse_blocks_with
+/// [`parse_block_body_lenient_with`]: crate::surfaces::parse_block_body_lenient_with
+pub fn parse_blocks_with<'a>(
+    text: &'a str,
+    options: &ParseOptions,
+) -> Result<SurfaceBlocks<'a>, Located<BlockError>> {
+    let (blocks, aliases) = surface_blocks(text, options)?;
+    Ok(collect(
+        blocks.into_iter().map(|block| parse_block(block, options)),
+        aliases,
+    ))
+}
+
+/// Same as [`parse_blocks_with`], parsing the bodies in parallel.
+///
+/// Blocks are split sequentially, then their bodies are parsed on the rayon thread pool.
+/// The result is the same as of [`parse_blocks_with`].
+///
+/// [`parse_blocks_with`]: crate::surfaces::parse_blocks_with
+#[cfg(feature = "rayon")]
+pub fn parse_blocks_parallel<'a>(
+    text: &'a str,
+    options: &ParseOptions,
+) -> Result<SurfaceBlocks<'a>, Located<BlockError>> {
+    use rayon::prelude::*;
+
+    let (blocks, aliases) = surface_blocks(text, options)?;
+    let parsed: Vec<_> = blocks
+        .into_par_iter()
+        .map(|block| parse_block(block, options))
+        .collect();
+    Ok(collect(parsed.into_iter(), aliases))
+}
+
+/// Checks whether `header` is of a surface block, such as `surface0,surface1` or `surface.append0`.
+pub(crate) fn is_surface_header(header: &str) -> bool {
+    header
+        .strip_prefix("surface")
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| c.is_ascii_digit() || c == '.')
+}
+
+/// Returns surface blocks, and aliases of alias blocks with their skipped lines.
+fn surface_blocks<'a>(
+    text: &'a str,
+    options: &ParseOptions,
+) -> Result<(Vec<Block<'a>>, Aliases), Located<BlockError>> {
+    let mut blocks = block::parse_blocks_with(text, options)?;
+    let mut aliases = SurfaceAliases::default();
+    let mut failures = Vec::new();
+    blocks.retain(|b| {
+        if let Some((_, skipped)) = aliases.apply_block(b, options) {
+            failures.extend(skipped);
+        }
+        is_surface_header(b.header)
+    });
+    Ok((blocks, (aliases, failures)))
+}
+
+/// Aliases with the skipped lines of their blocks.
+type Aliases = (SurfaceAliases, Vec<Located<ParseFailure>>);
+
+fn parse_block<'a>(
+    block: Block<'a>,
+    options: &ParseOptions,
+) -> (SurfaceBlock<'a>, Vec<Located<ParseFailure>>) {
+    let (body, failures) = parse_block_body_lenient_with(&block.body, options);
+    (SurfaceBlock { block, body }, failures)
+}
+
+/// Gathers blocks in order, with failures of alias blocks sorted in by line.
+fn collect<'a>(
+    parsed: impl Iterator<Item = (SurfaceBlock<'a>, Vec<Located<ParseFailure>>)>,
+    (aliases, alias_failures): Aliases,
+) -> SurfaceBlocks<'a> {
+    let mut result = SurfaceBlocks {
+        aliases,
+        ..SurfaceBlocks::default()
+    };
+    for (block, failures) in parsed {
+        result.blocks.push(block);
+        result.failures.extend(failures);
+    }
+    if !alias_failures.is_empty() {
+        result.failures.extend(alias_failures);
+        result.failures.sort_by_key(|f| f.line);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::{format, string::String};
+
+    fn fixture(count: u32) -> String {
+        let mut text = String::from("charset,UTF-8\r\n\r\ndescript\r\n{\r\nversion,1\r\n}\r\n");
+        for id in 0..count {
+            text.push_str(&format!(
+                "surface{id}\r\n{{\r\nelement0,base,surface{id}.png,0,0\r\ncollision0,10,10,50\r\nanimation{id}.interval,sometimes\r\nanimation{id}.pattern0,overlay,100,50,0,0\r\nsparkle{id},1\r\n}}\r\n"
+            ));
+        }
+        text
+    }
+
+    mod parse_blocks_with {
+        use super::*;
+
+        #[test]
+        fn success_when_valid() {
+            let case = fixture(3);
+            let result = parse_blocks_with(&case, &ParseOptions::STRICT).unwrap();
+
+            assert_eq!(result.blocks.len(), 3);
+            assert_eq!(result.blocks[1].block.header, "surface1");
+            assert_eq!(result.blocks[1].body.elements.len(), 1);
+            assert_eq!(result.blocks[2].body.animations[&2].patterns.len(), 1);
+            assert_eq!(result.failures.len(), 6);
+            assert!(result.failures.windows(2).all(|w| w[0].line < w[1].line));
+        }
+
+        #[test]
+        fn success_when_alias_blocks() {
+            let case = "surface0\r\n{\r\nsparkle,1\r\n}\r\nsakura.surface.alias\r\n{\r\nsmile,[0]\r\nbroken\r\n}\r\nsurface1\r\n{\r\nsparkle,1\r\n}\r\n";
+            let result = parse_blocks_with(case, &ParseOptions::STRICT).unwrap();
+
+            assert_eq!(result.blocks.len(), 2);
+            let sakura = result.aliases.get(crate::scope::Scope::Sakura).unwrap();
+            assert_eq!(sakura.get("smile").unwrap().0, [0]);
+            let lines: Vec<_> = result.failures.iter().map(|f| f.line).collect();
+            assert_eq!(lines, [3, 8, 12]);
+        }
+
+        #[test]
+        fn failed_when_unclosed() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,0,0\r\n";
+
+            assert_eq!(
+                parse_blocks_with(case, &ParseOptions::STRICT).unwrap_err(),
+                Located::new(1, BlockError::Unclosed)
+            );
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    mod parse_blocks_parallel {
+        use super::*;
+
+        #[test]
+        fn success_when_same_as_sequential() {
+            let case = fixture(500);
+            for options in [ParseOptions::STRICT, ParseOptions::COMPATIBLE] {
+                let sequential = parse_blocks_with(&case, &options).unwrap();
+                let parallel = parse_blocks_parallel(&case, &options).unwrap();
+
+                assert_eq!(parallel, sequential);
+                assert_eq!(format!("{:?}", parallel), format!("{:?}", sequential));
+            }
+        }
+
+        #[test]
+        fn success_when_alias_blocks() {
+            let case = "surface0\r\n{\r\nsparkle,1\r\n}\r\nsakura.surface.alias\r\n{\r\nsmile,[0]\r\nbroken\r\n}\r\nsurface1\r\n{\r\nsparkle,1\r\n}\r\n";
+            let result = parse_blocks_with(case, &ParseOptions::STRICT).unwrap();
+
+            assert_eq!(result.blocks.len(), 2);
+            let sakura = result.aliases.get(crate::scope::Scope::Sakura).unwrap();
+            assert_eq!(sakura.get("smile").unwrap().0, [0]);
+            let lines: Vec<_> = result.failures.iter().map(|f| f.line).collect();
+            assert_eq!(lines, [3, 8, 12]);
+        }
+
+        #[test]
+        fn failed_when_unclosed() {
+            let case = "surface0\r\n{\r\nelement0,base,a.png,0,0\r\n";
+
+            assert_eq!(
+                parse_blocks_parallel(case, &ParseOptions::STRICT),
+                parse_blocks_with(case, &ParseOptions::STRICT)
+            );
+        }
+    }
+}
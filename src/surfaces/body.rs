@@ -0,0 +1,674 @@
+//! Body of a `surfaceN` block.
+
+use alloc::{borrow::Cow, collections::BTreeMap, format, string::ToString, vec::Vec};
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    binding::{mark_bind, normalize_bind_entry_with, BindNote},
+    cursor::BlockCursors,
+    diagnostic::Warning,
+    error::{Located, ParseFailure},
+    kv::{parse_key_value, parse_key_value_with, segment_index},
+    line::{is_comment, Line},
+    options::ParseOptions,
+    seriko::{apply_animation_entry, Animation},
+    tooltip::{parse_block_tooltip, parse_block_tooltip_ref, BlockTooltips, Tooltip, TooltipRef},
+    value::{normalize_numeric_fields, NumericPolicy, NumericReader},
+};
+
+use super::{
+    collision_ref, collisionex, element_ref, parse_collision_ref, parse_element_ref, Collision,
+    CollisionEx, CollisionRef, Element, ElementRef, Points, SortOrder, SurfaceBalloonSettings,
+};
+
+/// Definitions in the body of a surface block.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SurfaceBody {
+    /// `elementN`, in order.
+    pub elements: Vec<Element>,
+    /// `collisionN`, in order.
+    pub collisions: Vec<Collision>,
+    /// `collisionexN`, in order.
+    pub collisionexes: Vec<CollisionEx>,
+    /// `animationN.*`, keyed by `N`.
+    pub animations: BTreeMap<u32, Animation>,
+    /// `point.*`
+    pub points: Points,
+    /// `balloon.*` and `<scope>.balloon.*`
+    pub balloon: SurfaceBalloonSettings,
+    /// `cursor.*`
+    pub cursors: BlockCursors,
+    /// `tooltip.*`
+    pub tooltips: BlockTooltips,
+    /// Bind animations written in alternate spellings, in order.
+    pub bind_notes: Vec<BindNote>,
+}
+
+impl SurfaceBody {
+    /// Applies one line of the body. Blank and comment lines are ignored.
+    ///
+    /// Lines with keys not handled here fail with [`ParseFailure::UnknownKey`].
+    ///
+    /// Bind animations in one of [`BIND_ALIASES`] are normalized into the interval `bind`
+    /// and noted in `bind_notes`. An interval given after them keeps `bind`.
+    ///
+    /// [`ParseFailure::UnknownKey`]: crate::error::ParseFailure::UnknownKey
+    /// [`BIND_ALIASES`]: crate::binding::BIND_ALIASES
+    pub fn apply_line(&mut self, text: &str) -> Result<(), ParseFailure> {
+        self.apply_line_numbers(text, &NumericReader::new(NumericPolicy::DEFAULT))
+    }
+
+    fn apply_line_numbers(
+        &mut self,
+        text: &str,
+        numbers: &NumericReader,
+    ) -> Result<(), ParseFailure> {
+        if text.trim().is_empty() || is_comment(text) {
+            return Ok(());
+        }
+
+        let (_, kv) = all_consuming(parse_key_value)(text)?;
+        let segments = kv.key_segments();
+
+        match segments.as_slice() {
+            [key] if is_indexed(key, "element") => {
+                let (_, element) = all_consuming(|i| element_ref(i, numbers))(text)?;
+                self.elements.push(element.to_owned());
+            }
+            [key] if is_indexed(key, "collisionex") => {
+                self.collisionexes
+                    .push(all_consuming(|i| collisionex(i, numbers))(text)?.1);
+            }
+            [key] if is_indexed(key, "collision") => {
+                let (_, collision) = all_consuming(|i| collision_ref(i, numbers))(text)?;
+                self.collisions.push(collision.to_owned());
+            }
+            ["tooltip", _, ..] => {
+                self.tooltips
+                    .tooltips
+                    .push(all_consuming(parse_block_tooltip)(text)?.1);
+            }
+            [key] if is_indexed(key, "bind") => {
+                if let Some(note) =
+                    normalize_bind_entry_with(&mut self.animations, &segments, kv.value, numbers)?
+                {
+                    self.bind_notes.push(note);
+                }
+            }
+            [animation, field] if is_indexed(animation, "animation") => {
+                if let Some(note) =
+                    normalize_bind_entry_with(&mut self.animations, &segments, kv.value, numbers)?
+                {
+                    self.bind_notes.push(note);
+                } else if !apply_animation_entry(
+                    &mut self.animations,
+                    &segments,
+                    kv.value,
+                    numbers,
+                )? {
+                    return Err(ParseFailure::UnknownKey(kv.key.to_string()));
+                } else if *field == "interval" {
+                    let id = segment_index(animation, "animation");
+                    if let Some(animation) = id
+                        .filter(|id| self.bind_notes.iter().any(|n| n.animation == *id))
+                        .and_then(|id| self.animations.get_mut(&id))
+                    {
+                        mark_bind(animation);
+                    }
+                }
+            }
+            _ => {
+                let applied = self
+                    .points
+                    .apply_with_numbers(&segments, kv.value, numbers)?
+                    || self
+                        .balloon
+                        .apply_with_numbers(&segments, kv.value, numbers)?
+                        .is_some()
+                    || self.cursors.apply(&segments, kv.value)?;
+                if !applied {
+                    return Err(ParseFailure::UnknownKey(kv.key.to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Same as [`SurfaceBody::apply_line`], with the lenient behaviors of `options`.
+    ///
+    /// The line is read as `key,value` with the key and the value `options` gives.
+    /// Lines of [`ParseOptions::comment_prefixes`] are ignored as comments.
+    /// Numbers are read under [`ParseOptions::numeric`].
+    ///
+    /// [`ParseOptions::comment_prefixes`]: crate::options::ParseOptions::comment_prefixes
+    /// [`ParseOptions::numeric`]: crate::options::ParseOptions::numeric
+    /// [`SurfaceBody::apply_line`]: crate::surfaces::SurfaceBody::apply_line
+    pub fn apply_line_with(
+        &mut self,
+        text: &str,
+        options: &ParseOptions,
+    ) -> Result<(), ParseFailure> {
+        self.apply_line_checked(text, options).map(|_| ())
+    }
+
+    /// Same as [`SurfaceBody::apply_line_with`], returning whether any number is clamped by [`Overflow::Saturate`].
+    ///
+    /// [`SurfaceBody::apply_line_with`]: crate::surfaces::SurfaceBody::apply_line_with
+    /// [`Overflow::Saturate`]: crate::value::Overflow::Saturate
+    fn apply_line_checked(
+        &mut self,
+        text: &str,
+        options: &ParseOptions,
+    ) -> Result<bool, ParseFailure> {
+        if options.is_comment(text) {
+            return Ok(false);
+        }
+        let (line, _) = canonical_line(text, options)?;
+        let numbers = NumericReader::new(options.numeric);
+        self.apply_line_numbers(&line, &numbers)?;
+        Ok(numbers.saturated())
+    }
+
+    /// Returns block tooltips naming collisions not in the body, in order.
+    ///
+    /// Collisions of animations are included. Check the body merged with [`merge_append`]
+    /// for surfaces given in more than one block.
+    ///
+    /// [`merge_append`]: crate::surfaces::merge_append
+    pub fn unmatched_tooltips(&self) -> Vec<&Tooltip> {
+        self.tooltips.unmatched(&self.all_collision_names())
+    }
+
+    /// Returns names of collisions, collisionexes and collisions of animations, in order.
+    pub(crate) fn all_collision_names(&self) -> Vec<&str> {
+        let mut collisions = self.collision_names();
+        collisions.extend(
+            self.animations
+                .values()
+                .flat_map(|a| a.collisions.iter().map(|c| c.name())),
+        );
+        collisions
+    }
+
+    /// Returns collisions sorted by `sort`, such as [`SurfacesDescript::collision_sort`].
+    ///
+    /// The sort is stable, so collisions of the same ID keep their order.
+    ///
+    /// [`SurfacesDescript::collision_sort`]: crate::surfaces::SurfacesDescript::collision_sort
+    pub fn collisions_ordered(&self, sort: SortOrder) -> impl Iterator<Item = &Collision> {
+        let mut collisions: Vec<_> = self.collisions.iter().collect();
+        sort.sort_by_key(&mut collisions, |c| c.id);
+        collisions.into_iter()
+    }
+
+    /// Returns animations sorted by `sort`, such as [`SurfacesDescript::animation_sort`].
+    ///
+    /// Animations are keyed by their IDs, so [`SortOrder::None`] gives them in ascending order too.
+    ///
+    /// [`SurfacesDescript::animation_sort`]: crate::surfaces::SurfacesDescript::animation_sort
+    /// [`SortOrder::None`]: crate::surfaces::SortOrder::None
+    pub fn animations_ordered(&self, sort: SortOrder) -> impl Iterator<Item = &Animation> {
+        let mut animations: Vec<_> = self.animations.values().collect();
+        sort.sort_by_key(&mut animations, |a| a.id);
+        animations.into_iter()
+    }
+
+    /// Returns names of collisions and collisionexes, in order.
+    pub fn collision_names(&self) -> Vec<&str> {
+        self.collisions
+            .iter()
+            .map(|c| c.name.as_str())
+            .chain(self.collisionexes.iter().map(|c| c.name.as_str()))
+            .collect()
+    }
+}
+
+/// Parses body `lines` of a surface block, failing at the first malformed or unknown line.
+pub fn parse_block_body(lines: &[Line<'_>]) -> Result<SurfaceBody, Located<ParseFailure>> {
+    let mut body = SurfaceBody::default();
+
+    for line in lines.iter() {
+        body.apply_line(line.text)
+            .map_err(|e| Located::new(line.number, e))?;
+    }
+
+    Ok(body)
+}
+
+/// Elements, collisions and tooltips of a surface block, borrowed from its lines.
+///
+/// For analyzers reading many surfaces, which need not copy filenames and names.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SurfaceBodyRef<'a> {
+    /// `elementN`, in order.
+    pub elements: Vec<ElementRef<'a>>,
+    /// `collisionN`, in order.
+    pub collisions: Vec<CollisionRef<'a>>,
+    /// `tooltip.*`, in order.
+    pub tooltips: Vec<TooltipRef<'a>>,
+}
+
+impl SurfaceBodyRef<'_> {
+    /// Returns the owned [`SurfaceBody`] with the definitions of `self`, and the others empty.
+    ///
+    /// [`SurfaceBody`]: crate::surfaces::SurfaceBody
+    pub fn to_owned(&self) -> SurfaceBody {
+        SurfaceBody {
+            elements: self.elements.iter().map(ElementRef::to_owned).collect(),
+            collisions: self.collisions.iter().map(CollisionRef::to_owned).collect(),
+            tooltips: BlockTooltips {
+                tooltips: self.tooltips.iter().map(TooltipRef::to_owned).collect(),
+            },
+            ..SurfaceBody::default()
+        }
+    }
+}
+
+/// Same as [`parse_block_body`] for elements, collisions and tooltips, borrowing from `lines`.
+///
+/// Blank and comment lines, and lines of other keys are skipped.
+/// Fails at the first malformed element, collision or tooltip.
+///
+/// [`parse_block_body`]: crate::surfaces::parse_block_body
+pub fn parse_block_body_ref<'a>(
+    lines: &[Line<'a>],
+) -> Result<SurfaceBodyRef<'a>, Located<ParseFailure>> {
+    let mut body = SurfaceBodyRef::default();
+
+    for line in lines.iter() {
+        let text = line.text;
+        if text.trim().is_empty() || is_comment(text) {
+            continue;
+        }
+        let applied = all_consuming(parse_key_value)(text).and_then(|(_, kv)| {
+            if is_indexed(kv.key, "element") {
+                body.elements
+                    .push(all_consuming(parse_element_ref)(text)?.1);
+            } else if is_indexed(kv.key, "collision") {
+                body.collisions
+                    .push(all_consuming(parse_collision_ref)(text)?.1);
+            } else if kv.key.starts_with("tooltip.") {
+                body.tooltips
+                    .push(all_consuming(parse_block_tooltip_ref)(text)?.1);
+            }
+            Ok(())
+        });
+        applied.map_err(|e| Located::new(line.number, ParseFailure::from(e)))?;
+    }
+
+    Ok(body)
+}
+
+/// Parses body `lines` of a surface block, skipping malformed or unknown lines
+/// as baseware does. Skipped lines are returned as warnings.
+pub fn parse_block_body_lenient(lines: &[Line<'_>]) -> (SurfaceBody, Vec<Located<ParseFailure>>) {
+    parse_block_body_lenient_with(lines, &ParseOptions::STRICT)
+}
+
+/// Returns `text` as `key,value` with the key and the value `options` gives,
+/// and whether full-width characters were normalized.
+///
+/// Full-width characters are normalized only in values of keys read as numbers and separators,
+/// not in tooltips or cursors.
+pub(crate) fn canonical_line<'a>(
+    text: &'a str,
+    options: &ParseOptions,
+) -> Result<(Cow<'a, str>, bool), ParseFailure> {
+    if *options == ParseOptions::STRICT || text.trim().is_empty() || options.is_comment(text) {
+        return Ok((Cow::Borrowed(text), false));
+    }
+
+    let (_, kv) = all_consuming(|input| parse_key_value_with(input, options))(text)?;
+    let value = if options.normalize_fullwidth && is_numeric_key(kv.key) {
+        normalize_numeric_fields(kv.value)
+    } else {
+        Cow::Borrowed(kv.value)
+    };
+
+    let normalized = matches!(value, Cow::Owned(_));
+    Ok((Cow::Owned(format!("{},{}", kv.key, value)), normalized))
+}
+
+/// Checks whether values of `key` are read as numbers and separators, with names and filenames.
+fn is_numeric_key(key: &str) -> bool {
+    let mut segments = key.split('.');
+    let first = segments.next().unwrap_or_default();
+    ["element", "collisionex", "collision", "animation"]
+        .iter()
+        .any(|prefix| is_indexed(first, prefix))
+        || first == "point"
+        || first == "balloon"
+        || segments.next() == Some("balloon")
+}
+
+/// Same as [`parse_block_body_lenient`], with the lenient behaviors of `options`.
+///
+/// Lines with numbers clamped by [`Overflow::Saturate`] are applied,
+/// and returned as [`ParseFailure::Warning`] of [`Warning::Saturated`].
+///
+/// [`parse_block_body_lenient`]: crate::surfaces::parse_block_body_lenient
+/// [`Overflow::Saturate`]: crate::value::Overflow::Saturate
+/// [`ParseFailure::Warning`]: crate::error::ParseFailure::Warning
+/// [`Warning::Saturated`]: crate::diagnostic::Warning::Saturated
+pub fn parse_block_body_lenient_with(
+    lines: &[Line<'_>],
+    options: &ParseOptions,
+) -> (SurfaceBody, Vec<Located<ParseFailure>>) {
+    let mut body = SurfaceBody::default();
+    let mut warnings = Vec::new();
+
+    for line in lines.iter() {
+        match body.apply_line_checked(line.text, options) {
+            Ok(false) => {}
+            Ok(true) => warnings.push(Located::new(
+                line.number,
+                ParseFailure::Warning(Warning::Saturated),
+            )),
+            Err(e) => warnings.push(Located::new(line.number, e)),
+        }
+    }
+
+    (body, warnings)
+}
+
+fn is_indexed(segment: &str, prefix: &str) -> bool {
+    segment_index(segment, prefix).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::block::parse_blocks;
+
+    const VALID: &str = "surface0\r\n{\r\n// body\r\nelement0,base,body.png,0,0\r\ncollision0,10,10,50,50,Head\r\ncollisionex1,Bust,circle,30,30,10\r\n\r\nanimation1.interval,sometimes\r\nanimation1.pattern0,overlay,100,50,0,0\r\npoint.centerx,60\r\nsakura.balloon.offsetx,20\r\ncursor.mouseup0,Head,head.cur\r\ntooltip.Head,head\r\n}\r\n";
+
+    mod parse_block_body_lenient {
+        use super::*;
+
+        #[test]
+        fn success_when_fully_valid() {
+            let blocks = parse_blocks(VALID).unwrap();
+            let (body, warnings) = parse_block_body_lenient(&blocks[0].body);
+
+            assert!(warnings.is_empty());
+            assert_eq!(body.elements.len(), 1);
+            assert_eq!(body.collision_names(), vec!["Head", "Bust"]);
+            assert_eq!(body.animations[&1].patterns.len(), 1);
+            assert_eq!(body.points.center_x, Some(60));
+            assert_eq!(body.cursors.cursors.len(), 1);
+            assert_eq!(body.tooltips.tooltips.len(), 1);
+            assert_eq!(parse_block_body(&blocks[0].body).unwrap(), body);
+        }
+
+        #[test]
+        fn warned_when_typo_line() {
+            let case = "surface0\r\n{\r\nelement0,base,body.png,0,0\r\ncollision0,10,10,50\r\ncolision1,10,10,50,50,Bust\r\ncollision2,0,0,5,5,Face\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let (body, warnings) = parse_block_body_lenient(&blocks[0].body);
+
+            assert_eq!(body.elements.len(), 1);
+            assert_eq!(body.collision_names(), vec!["Face"]);
+            assert_eq!(warnings.len(), 2);
+            assert_eq!(warnings[0].line, 4);
+            assert!(warnings[0].value.contexts().contains(&"collision:rect"));
+            assert_eq!(
+                warnings[1],
+                Located::new(5, ParseFailure::UnknownKey("colision1".to_string()))
+            );
+
+            assert_eq!(parse_block_body(&blocks[0].body).unwrap_err().line, 4);
+        }
+
+        #[test]
+        fn warned_when_unknown_well_formed_key() {
+            let case = "surface0\r\n{\r\nelement0,base,body.png,0,0\r\nsparkle.level,3\r\nanimation0.speed,2\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let (body, warnings) = parse_block_body_lenient(&blocks[0].body);
+
+            assert_eq!(body.elements.len(), 1);
+            assert_eq!(
+                warnings,
+                vec![
+                    Located::new(4, ParseFailure::UnknownKey("sparkle.level".to_string())),
+                    Located::new(5, ParseFailure::UnknownKey("animation0.speed".to_string())),
+                ]
+            );
+            assert!(body.animations.is_empty());
+        }
+    }
+
+    mod parse_block_body_lenient_with {
+        use super::*;
+
+        use crate::value::Overflow;
+
+        const OVERFLOW: &str =
+            "surface0\r\n{\r\ncollision0,0,0,99999999999,10,Head\r\ncollision1,0,0,5,5,Face\r\n}\r\n";
+
+        #[test]
+        fn warned_when_saturated() {
+            let blocks = parse_blocks(OVERFLOW).unwrap();
+            let options = ParseOptions::STRICT
+                .numeric(NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate));
+            let (body, warnings) = parse_block_body_lenient_with(&blocks[0].body, &options);
+
+            assert_eq!(body.collision_names(), vec!["Head", "Face"]);
+            assert_eq!(body.collisions[0].rect.right, i32::MAX);
+            assert_eq!(
+                warnings,
+                vec![Located::new(3, ParseFailure::Warning(Warning::Saturated))]
+            );
+        }
+
+        #[test]
+        fn failed_when_overflow_rejected() {
+            let blocks = parse_blocks(OVERFLOW).unwrap();
+            let options =
+                ParseOptions::STRICT.numeric(NumericPolicy::DEFAULT.on_overflow(Overflow::Error));
+            let (body, warnings) = parse_block_body_lenient_with(&blocks[0].body, &options);
+
+            assert_eq!(body.collision_names(), vec!["Face"]);
+            assert_eq!(warnings.len(), 1);
+            assert_eq!(warnings[0].line, 3);
+            assert!(warnings[0].value.contexts().contains(&"number:overflow"));
+        }
+    }
+
+    mod parse_block_body_ref {
+        use super::*;
+
+        #[test]
+        fn ignored_when_other_keys() {
+            let blocks = parse_blocks(VALID).unwrap();
+            let body = parse_block_body_ref(&blocks[0].body).unwrap();
+
+            assert_eq!(body.elements[0].filename, "body.png");
+            assert_eq!(body.collisions.len(), 1);
+            assert_eq!(body.tooltips[0].text, "head");
+
+            let owned = parse_block_body(&blocks[0].body).unwrap();
+            let converted = body.to_owned();
+            assert_eq!(converted.elements, owned.elements);
+            assert_eq!(converted.collisions, owned.collisions);
+            assert_eq!(converted.tooltips, owned.tooltips);
+            assert!(converted.animations.is_empty());
+        }
+    }
+
+    mod apply_line {
+        use super::*;
+
+        #[test]
+        fn success_when_free_text_has_commas() {
+            let mut body = SurfaceBody::default();
+            body.apply_line("collision0,0,0,10,10,Head, left").unwrap();
+            body.apply_line("tooltip.Head,smiling, head  ").unwrap();
+
+            assert_eq!(body.collision_names(), vec!["Head, left"]);
+            assert_eq!(body.tooltips.tooltips[0].collision, "Head");
+            assert_eq!(body.tooltips.tooltips[0].text, "smiling, head  ");
+        }
+
+        #[test]
+        fn success_when_tooltips_matched() {
+            let mut body = SurfaceBody::default();
+            body.apply_line("collision0,0,0,10,10,Head").unwrap();
+            body.apply_line("animation1.collision0,0,0,5,5,Hand")
+                .unwrap();
+            body.apply_line("tooltip.Head,head").unwrap();
+            body.apply_line("tooltip.Hand,hand").unwrap();
+            body.apply_line("tooltip.Bust,bust").unwrap();
+            let unmatched: Vec<_> = body
+                .unmatched_tooltips()
+                .iter()
+                .map(|t| t.collision.as_str())
+                .collect();
+            assert_eq!(unmatched, vec!["Bust"]);
+
+            let mut append = SurfaceBody::default();
+            append.apply_line("collision1,0,10,10,20,Bust").unwrap();
+            crate::surfaces::merge_append(&mut body, &append);
+            assert!(body.unmatched_tooltips().is_empty());
+        }
+
+        #[test]
+        fn success_when_animation_collisions() {
+            let mut body = SurfaceBody::default();
+            body.apply_line("collision0,0,0,10,10,Head").unwrap();
+            body.apply_line("animation3.collision0,10,10,40,40,Hand")
+                .unwrap();
+            body.apply_line("animation3.collisionex5,Arm,rect,0,0,5,5")
+                .unwrap();
+            body.apply_line("animation3.pattern0,overlay,100,50,0,0")
+                .unwrap();
+
+            assert_eq!(body.collision_names(), vec!["Head"]);
+            let collisions = &body.animations[&3].collisions;
+            assert_eq!(collisions.len(), 2);
+            assert_eq!(collisions[0].name(), "Hand");
+            assert_eq!(collisions[1].id(), 5);
+            assert_eq!(
+                body.apply_line("animation3x.collision0,0,0,1,1,a"),
+                Err(ParseFailure::UnknownKey(
+                    "animation3x.collision0".to_string()
+                ))
+            );
+        }
+    }
+
+    mod apply_line_with {
+        use super::*;
+
+        #[test]
+        fn success_when_fullwidth_normalized() {
+            let options = ParseOptions::STRICT.normalize_fullwidth(true);
+            let mut body = SurfaceBody::default();
+            body.apply_line_with("animation0.pattern0,overlay,１２,50,0,0", &options)
+                .unwrap();
+            body.apply_line_with("collision0,０，０，１０，１０,頭１", &options)
+                .unwrap();
+            body.apply_line_with("tooltip.頭１,第１話", &options)
+                .unwrap();
+
+            assert!(matches!(
+                body.animations[&0].patterns[&0],
+                crate::seriko::Pattern::Draw { surface: 12, .. }
+            ));
+            assert_eq!(body.collisions[0].name, "頭１");
+            assert_eq!(body.collisions[0].rect.right, 10);
+            assert!(body.apply_line("collision1,０，０，１０，１０,頭").is_err());
+        }
+
+        #[test]
+        fn ignored_when_comment_prefix() {
+            let options = ParseOptions::STRICT.comment_prefixes(&["//", ";"]).unwrap();
+            let mut body = SurfaceBody::default();
+            body.apply_line_with("  ; element0,base,a.png,0,0", &options)
+                .unwrap();
+            assert!(body.elements.is_empty());
+            assert!(body
+                .apply_line_with("; element0,base,a.png,0,0", &ParseOptions::STRICT)
+                .is_err());
+        }
+    }
+
+    mod collisions_ordered {
+        use super::*;
+
+        use crate::surfaces::merge_append;
+
+        fn body(lines: &[&str]) -> SurfaceBody {
+            let mut body = SurfaceBody::default();
+            for line in lines.iter() {
+                body.apply_line(line).unwrap();
+            }
+            body
+        }
+
+        fn names(body: &SurfaceBody, sort: SortOrder) -> Vec<&str> {
+            body.collisions_ordered(sort)
+                .map(|c| c.name.as_str())
+                .collect()
+        }
+
+        #[test]
+        fn success_when_sorted() {
+            let body = body(&[
+                "collision2,0,0,1,1,C",
+                "collision0,0,0,1,1,A",
+                "collision1,0,0,1,1,B",
+            ]);
+            assert_eq!(names(&body, SortOrder::Ascend), vec!["A", "B", "C"]);
+            assert_eq!(names(&body, SortOrder::Descend), vec!["C", "B", "A"]);
+            assert_eq!(names(&body, SortOrder::None), vec!["C", "A", "B"]);
+        }
+
+        #[test]
+        fn success_when_same_ids_keep_order() {
+            let mut base = body(&[
+                "collision1,0,0,1,1,First",
+                "collision0,0,0,1,1,Zero",
+                "collision1,0,0,1,1,Second",
+            ]);
+            merge_append(&mut base, &body(&["collision0,0,0,1,1,Appended"]));
+            merge_append(&mut base, &body(&["collision2,0,0,1,1,Two"]));
+
+            assert_eq!(
+                names(&base, SortOrder::Ascend),
+                vec!["Appended", "First", "Second", "Two"]
+            );
+            assert_eq!(
+                names(&base, SortOrder::Descend),
+                vec!["Two", "First", "Second", "Appended"]
+            );
+        }
+    }
+
+    mod animations_ordered {
+        use super::*;
+
+        #[test]
+        fn success_when_sorted() {
+            let mut body = SurfaceBody::default();
+            for line in [
+                "animation5.interval,always",
+                "animation1.interval,never",
+                "animation3.interval,runonce",
+            ] {
+                body.apply_line(line).unwrap();
+            }
+            let ids = |sort| {
+                body.animations_ordered(sort)
+                    .map(|a| a.id)
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(ids(SortOrder::Ascend), vec![1, 3, 5]);
+            assert_eq!(ids(SortOrder::Descend), vec![5, 3, 1]);
+            assert_eq!(ids(SortOrder::None), vec![1, 3, 5]);
+        }
+    }
+}
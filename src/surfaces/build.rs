@@ -0,0 +1,388 @@
+//! Surfaces of each ID, built from all the blocks covering it.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::ToString,
+    sync::Arc,
+    vec::Vec,
+};
+
+use crate::{
+    cursor::BlockCursors,
+    diagnostic::Warning,
+    error::Located,
+    intern::{InternedElement, Interner},
+    options::{Limit, LimitExceeded, Limits, ParseOptions},
+    seriko::AnimationSet,
+    tooltip::BlockTooltips,
+};
+
+use super::{
+    merge_append, BaseImage, Collision, CollisionEx, Element, Points, SurfaceBalloonSettings,
+    SurfaceBlock, SurfaceBody,
+};
+
+/// Definitions of one surface, with its `surface.appendN` blocks applied.
+///
+/// Surfaces defined by the same blocks, such as those of a range `surface0-999`,
+/// share the fields. Use [`Arc::make_mut`] to modify one of them alone.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Surface {
+    pub id: u32,
+    pub elements: Arc<Vec<Element>>,
+    pub animations: Arc<AnimationSet>,
+    pub collisions: Arc<Vec<Collision>>,
+    pub collisionexes: Arc<Vec<CollisionEx>>,
+    pub points: Arc<Points>,
+    pub balloon: Arc<SurfaceBalloonSettings>,
+    pub tooltips: Arc<BlockTooltips>,
+    pub cursors: Arc<BlockCursors>,
+    /// Whether the base image is drawn by elements or named after the ID.
+    pub base_image: BaseImage,
+}
+
+impl Surface {
+    pub(crate) fn new(id: u32, body: SurfaceBody) -> Surface {
+        Surface {
+            id,
+            base_image: BaseImage::of(id, &body.elements),
+            elements: Arc::new(body.elements),
+            animations: Arc::new(AnimationSet::from(body.animations)),
+            collisions: Arc::new(body.collisions),
+            collisionexes: Arc::new(body.collisionexes),
+            points: Arc::new(body.points),
+            balloon: Arc::new(body.balloon),
+            tooltips: Arc::new(body.tooltips),
+            cursors: Arc::new(body.cursors),
+        }
+    }
+}
+
+/// Builds surfaces of all IDs covered by headers of `blocks`, such as `surface0,surface3-5,!surface4`.
+///
+/// For each ID, the last `surfaceN` block covering it is the base, and `surface.appendN` blocks
+/// are applied onto it in file order by [`merge_append`], wherever they are in the file.
+/// IDs only covered by `surface.appendN` blocks are built on an empty base.
+///
+/// Warnings are in line order. IDs defined by `surfaceN` blocks before are
+/// [`Warning::DuplicateSurface`] at the header of each later block,
+/// and headers not read are [`Warning::UnreadableSurfaceHeader`], skipping their blocks.
+/// Headers of more IDs than [`Limits::DEFAULT`] allows are also not read. Use [`build_surfaces_with`] to fail on them.
+///
+/// [`merge_append`]: crate::surfaces::merge_append
+/// [`Warning::DuplicateSurface`]: crate::diagnostic::Warning::DuplicateSurface
+/// [`Warning::UnreadableSurfaceHeader`]: crate::diagnostic::Warning::UnreadableSurfaceHeader
+/// [`Limits::DEFAULT`]: crate::options::Limits::DEFAULT
+/// [`build_surfaces_with`]: crate::surfaces::build_surfaces_with
+pub fn build_surfaces(blocks: &[SurfaceBlock]) -> (BTreeMap<u32, Surface>, Vec<Located<Warning>>) {
+    build(blocks, &Limits::DEFAULT, false).unwrap_or_default()
+}
+
+/// Surfaces keyed by their IDs, with warnings.
+type Built = (BTreeMap<u32, Surface>, Vec<Located<Warning>>);
+
+/// Same as [`build_surfaces`], failing at the first header expanded into more IDs than [`Limits::max_id_set`]
+/// of `options`, counting those excluded by `!`.
+///
+/// [`build_surfaces`]: crate::surfaces::build_surfaces
+/// [`Limits::max_id_set`]: crate::options::Limits::max_id_set
+pub fn build_surfaces_with(
+    blocks: &[SurfaceBlock],
+    options: &ParseOptions,
+) -> Result<Built, Located<LimitExceeded>> {
+    build(blocks, &options.limits, true)
+}
+
+/// Returns elements of `surfaces` with their filenames shared through `interner`,
+/// so that a filename used by many surfaces, such as that of a common base, is stored once.
+pub fn intern_elements(
+    surfaces: &BTreeMap<u32, Surface>,
+    interner: &mut Interner,
+) -> BTreeMap<u32, Vec<InternedElement>> {
+    surfaces
+        .iter()
+        .map(|(id, surface)| {
+            let elements = surface
+                .elements
+                .iter()
+                .map(|e| InternedElement {
+                    id: e.id,
+                    method: e.method,
+                    filename: interner.intern(&e.filename),
+                    offset: e.offset,
+                    alpha: e.alpha,
+                })
+                .collect();
+            (*id, elements)
+        })
+        .collect()
+}
+
+fn build(
+    blocks: &[SurfaceBlock],
+    limits: &Limits,
+    fail_on_limit: bool,
+) -> Result<Built, Located<LimitExceeded>> {
+    let mut warnings = Vec::new();
+    // Indices of base and append blocks covering each ID, in file order.
+    let mut covering: BTreeMap<u32, (Vec<usize>, Vec<usize>)> = BTreeMap::new();
+
+    for (index, block) in blocks.iter().enumerate() {
+        let header = block.block.header;
+        let scope = match header_scope(header, limits.max_id_set) {
+            Err(e) if fail_on_limit => return Err(Located::new(block.block.header_line, e)),
+            Ok(scope) => scope,
+            Err(_) => None,
+        };
+        let Some(scope) = scope else {
+            warnings.push(Located::new(
+                block.block.header_line,
+                Warning::UnreadableSurfaceHeader(header.to_string()),
+            ));
+            continue;
+        };
+        for id in scope.bases {
+            covering.entry(id).or_default().0.push(index);
+        }
+        for id in scope.appends {
+            covering.entry(id).or_default().1.push(index);
+        }
+    }
+
+    // IDs covered by the same blocks share one built surface.
+    let mut built: BTreeMap<(Option<usize>, Vec<usize>), Surface> = BTreeMap::new();
+    let mut surfaces = BTreeMap::new();
+    for (id, (bases, appends)) in covering {
+        for later in bases.iter().skip(1) {
+            warnings.push(Located::new(
+                blocks[*later].block.header_line,
+                Warning::DuplicateSurface(id),
+            ));
+        }
+
+        let base = bases.last().copied();
+        let shared = built
+            .entry((base, appends))
+            .or_insert_with_key(|(base, appends)| {
+                let mut body = base.map(|b| blocks[b].body.clone()).unwrap_or_default();
+                for append in appends.iter() {
+                    merge_append(&mut body, &blocks[*append].body);
+                }
+                Surface::new(id, body)
+            });
+        surfaces.insert(
+            id,
+            Surface {
+                id,
+                base_image: BaseImage::of(id, &shared.elements),
+                ..shared.clone()
+            },
+        );
+    }
+
+    warnings.sort_by_key(|w| w.line);
+    Ok((surfaces, warnings))
+}
+
+/// IDs of a surface header.
+#[derive(Debug, PartialEq, Eq, Default)]
+struct HeaderScope {
+    bases: BTreeSet<u32>,
+    appends: BTreeSet<u32>,
+}
+
+/// Reads `header` of comma-separated `surfaceN`, `surfaceA-B` and `surface.appendN`,
+/// with `!` excluding IDs from those of the same kind wherever it is written.
+///
+/// `None` when any of them is not read, such as a range from a greater ID,
+/// and an error when more than `max` IDs are given, including those excluded.
+fn header_scope(header: &str, max: usize) -> Result<Option<HeaderScope>, LimitExceeded> {
+    let mut included = HeaderScope::default();
+    let mut excluded = HeaderScope::default();
+    let mut given: usize = 0;
+
+    for part in header.split(',') {
+        let part = part.trim();
+        let (scope, part) = match part.strip_prefix('!') {
+            Some(rest) => (&mut excluded, rest),
+            None => (&mut included, part),
+        };
+        let (ids, range) = match part.strip_prefix("surface.append") {
+            Some(range) => (&mut scope.appends, range),
+            None => match part.strip_prefix("surface") {
+                Some(range) => (&mut scope.bases, range),
+                None => return Ok(None),
+            },
+        };
+        let (from, to) = range.split_once('-').unwrap_or((range, range));
+        let (Ok(from), Ok(to)) = (from.parse::<u32>(), to.parse::<u32>()) else {
+            return Ok(None);
+        };
+        if from > to {
+            return Ok(None);
+        }
+        given = given.saturating_add((to - from) as usize + 1);
+        if given > max {
+            return Err(LimitExceeded {
+                limit: Limit::IdSet,
+                max,
+            });
+        }
+        ids.extend(from..=to);
+    }
+
+    included.bases.retain(|id| !excluded.bases.contains(id));
+    included.appends.retain(|id| !excluded.appends.contains(id));
+    Ok(Some(included))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::{string::String, vec};
+
+    use crate::{options::ParseOptions, surfaces::parse_blocks_with};
+
+    fn build(text: &str) -> (BTreeMap<u32, Surface>, Vec<Located<Warning>>) {
+        let blocks = parse_blocks_with(text, &ParseOptions::COMPATIBLE).unwrap();
+        build_surfaces(&blocks.blocks)
+    }
+
+    mod build_surfaces {
+        use super::*;
+
+        #[test]
+        fn success_when_range_expanded() {
+            let case = "surface0-3,surface10,!surface2\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n";
+            let (surfaces, warnings) = build(case);
+
+            assert!(warnings.is_empty());
+            assert_eq!(
+                surfaces.keys().copied().collect::<Vec<_>>(),
+                vec![0, 1, 3, 10]
+            );
+            assert_eq!(surfaces[&3].id, 3);
+            assert_eq!(surfaces[&10].elements[0].filename, "a.png");
+        }
+
+        #[test]
+        fn success_when_append_before_base() {
+            let case = "surface.append0-1\r\n{\r\nelement1,overlay,b.png,0,0\r\ncollision0,0,0,10,10,Head\r\n}\r\nsurface0,surface1\r\n{\r\nelement0,base,a.png,0,0\r\nanimation0.interval,never\r\n}\r\n";
+            let (surfaces, warnings) = build(case);
+
+            assert!(warnings.is_empty());
+            for surface in surfaces.values() {
+                let elements: Vec<_> = surface.elements.iter().map(|e| e.id).collect();
+                assert_eq!(elements, vec![0, 1]);
+                assert_eq!(surface.collisions.len(), 1);
+                assert!(surface.animations.get(0).is_some());
+            }
+        }
+
+        #[test]
+        fn warned_when_conflicting_bases() {
+            let case = "surface0-2\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n\r\nsurface1\r\n{\r\nelement0,base,b.png,0,0\r\n}\r\nsurface5-1\r\n{\r\n}\r\n";
+            let (surfaces, warnings) = build(case);
+
+            assert_eq!(
+                warnings,
+                vec![
+                    Located::new(6, Warning::DuplicateSurface(1)),
+                    Located::new(
+                        10,
+                        Warning::UnreadableSurfaceHeader(String::from("surface5-1"))
+                    ),
+                ]
+            );
+            assert_eq!(surfaces[&1].elements[0].filename, "b.png");
+            assert_eq!(surfaces[&2].elements[0].filename, "a.png");
+            assert_eq!(warnings[0].value.to_string(), "surface1 defined again");
+        }
+
+        #[test]
+        fn success_when_bodies_shared() {
+            let case = "surface0-4999\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\nsurface.append10\r\n{\r\nelement1,overlay,b.png,0,0\r\n}\r\n";
+            let (surfaces, _) = build(case);
+
+            assert_eq!(surfaces.len(), 5000);
+            assert!(Arc::ptr_eq(
+                &surfaces[&0].elements,
+                &surfaces[&4999].elements
+            ));
+            assert!(Arc::ptr_eq(&surfaces[&0].points, &surfaces[&4999].points));
+            assert!(!Arc::ptr_eq(
+                &surfaces[&0].elements,
+                &surfaces[&10].elements
+            ));
+            assert_eq!(Arc::strong_count(&surfaces[&0].elements), 4999);
+        }
+    }
+
+    mod intern_elements {
+        use super::*;
+
+        #[test]
+        fn success_when_filenames_shared() {
+            let case = "surface0\r\n{\r\nelement0,base,body.png,0,0\r\nelement1,overlay,face0.png,0,0\r\n}\r\nsurface1\r\n{\r\nelement0,base,body.png,0,0\r\nelement1,overlay,face1.png,0,0\r\n}\r\n";
+            let (surfaces, _) = build(case);
+            let mut interner = Interner::new();
+            let elements = intern_elements(&surfaces, &mut interner);
+
+            assert!(!Arc::ptr_eq(&surfaces[&0].elements, &surfaces[&1].elements));
+            assert!(Arc::ptr_eq(
+                &elements[&0][0].filename,
+                &elements[&1][0].filename
+            ));
+            assert!(!Arc::ptr_eq(
+                &elements[&0][1].filename,
+                &elements[&1][1].filename
+            ));
+            assert_eq!(&*elements[&1][1].filename, "face1.png");
+            assert_eq!(interner.len(), 3);
+        }
+    }
+
+    mod build_surfaces_with {
+        use super::*;
+
+        #[test]
+        fn failed_when_too_many_ids() {
+            let case = "surface0-9\r\n{\r\n}\r\nsurface0-4294967295\r\n{\r\n}\r\n";
+            let blocks = parse_blocks_with(case, &ParseOptions::COMPATIBLE).unwrap();
+
+            assert_eq!(
+                build_surfaces_with(&blocks.blocks, &ParseOptions::COMPATIBLE),
+                Err(Located::new(
+                    4,
+                    LimitExceeded {
+                        limit: Limit::IdSet,
+                        max: Limits::DEFAULT.max_id_set
+                    }
+                ))
+            );
+
+            let (surfaces, warnings) = build_surfaces(&blocks.blocks);
+            assert_eq!(surfaces.len(), 10);
+            assert_eq!(
+                warnings,
+                vec![Located::new(
+                    4,
+                    Warning::UnreadableSurfaceHeader(String::from("surface0-4294967295"))
+                )]
+            );
+        }
+
+        #[test]
+        fn success_when_large_range() {
+            let case =
+                "surface0-9999,!surface.append0-49999\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n";
+            let blocks = parse_blocks_with(case, &ParseOptions::COMPATIBLE).unwrap();
+            let (surfaces, _) =
+                build_surfaces_with(&blocks.blocks, &ParseOptions::COMPATIBLE).unwrap();
+
+            assert_eq!(surfaces.len(), 10000);
+        }
+    }
+}
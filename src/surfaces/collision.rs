@@ -0,0 +1,223 @@
+//! `collision` definition and parser for it.
+
+use alloc::string::{String, ToString};
+
+use nom::{
+    bytes::complete::{is_not, tag},
+    combinator::{all_consuming, map},
+    error::context,
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{
+    kv::{fields_then_rest, parse_key_value_with},
+    options::ParseOptions,
+    span::{in_source, span_of, spanned, Spanned},
+    value::{
+        parse_rect_spanned, parse_u32, separator, NumericPolicy, NumericReader, Rect, RectSpans,
+    },
+    ShellParseError,
+};
+
+/// Rectangular collision defined by `collisionN,left,top,right,bottom,name`.
+///
+/// `name` is the rest of the line, with its commas and whitespace.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Collision {
+    pub id: u32,
+    pub rect: Rect,
+    pub name: String,
+}
+
+/// [`Collision`] with the name borrowed from the input.
+///
+/// [`Collision`]: crate::surfaces::Collision
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CollisionRef<'a> {
+    pub id: u32,
+    pub rect: Rect,
+    pub name: &'a str,
+}
+
+impl CollisionRef<'_> {
+    /// Returns the owned [`Collision`], copying the name.
+    ///
+    /// [`Collision`]: crate::surfaces::Collision
+    pub fn to_owned(&self) -> Collision {
+        Collision {
+            id: self.id,
+            rect: self.rect,
+            name: self.name.to_string(),
+        }
+    }
+}
+
+impl From<CollisionRef<'_>> for Collision {
+    fn from(value: CollisionRef<'_>) -> Self {
+        value.to_owned()
+    }
+}
+
+/// parser for [`Collision`].
+///
+/// [`Collision`]: crate::surfaces::Collision
+pub fn parse_collision(input: &str) -> IResult<&str, Collision, ShellParseError<'_>> {
+    map(parse_collision_ref, Collision::from)(input)
+}
+
+/// Same as [`parse_collision`], borrowing the name from `input`.
+///
+/// [`parse_collision`]: crate::surfaces::parse_collision
+pub fn parse_collision_ref(input: &str) -> IResult<&str, CollisionRef<'_>, ShellParseError<'_>> {
+    collision_ref(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+pub(crate) fn collision_ref<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionRef<'a>, ShellParseError<'a>> {
+    let (input, id) = context(
+        "collision:id",
+        preceded(tag("collision"), |i| numbers.u32(i)),
+    )(input)?;
+    let (input, (rect, rest)) = context(
+        "collision:rect",
+        preceded(separator, fields_then_rest(4, |i| numbers.i32(i))),
+    )(input)?;
+    let (_, name) = context("collision:name", is_not("\r\n"))(rest)?;
+
+    Ok((
+        input,
+        CollisionRef {
+            id,
+            rect: Rect::new(rect[0], rect[1], rect[2], rect[3]),
+            name,
+        },
+    ))
+}
+
+/// [`Collision`] with the span of each field.
+///
+/// [`Collision`]: crate::surfaces::Collision
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CollisionSpans {
+    /// Digits of the ID in the key.
+    pub id: Spanned<u32>,
+    pub rect: RectSpans,
+    pub name: Spanned<String>,
+}
+
+impl CollisionSpans {
+    pub fn value(&self) -> Collision {
+        Collision {
+            id: self.id.value,
+            rect: self.rect.value(),
+            name: self.name.value.clone(),
+        }
+    }
+}
+
+/// Same as [`parse_collision`], with spans in `source`, of which `input` is a subslice.
+///
+/// The line is split as [`parse_element_spanned`] does.
+///
+/// [`parse_collision`]: crate::surfaces::parse_collision
+/// [`parse_element_spanned`]: crate::surfaces::parse_element_spanned
+pub fn parse_collision_spanned<'a>(
+    input: &'a str,
+    source: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, Spanned<CollisionSpans>, ShellParseError<'a>> {
+    let (input, _) = in_source(input, source)?;
+    let (input, kv) = parse_key_value_with(input, options)?;
+    let (_, id) = context(
+        "collision:id",
+        all_consuming(preceded(tag("collision"), spanned(source, parse_u32))),
+    )(kv.key)?;
+    let (value, rect) = context("collision:rect", |i| parse_rect_spanned(i, source))(kv.value)?;
+    let (_, name) = context(
+        "collision:name",
+        all_consuming(preceded(
+            separator,
+            map(spanned(source, is_not("\r\n")), |n| n.map(String::from)),
+        )),
+    )(value)?;
+
+    let span = span_of(source, kv.key).start..span_of(source, kv.value).end;
+    Ok((
+        input,
+        Spanned::new(
+            CollisionSpans {
+                id,
+                rect: rect.value,
+                name,
+            },
+            span,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_collision {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let (remain, result) = parse_collision("collision0,10,20,50,60,Head\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Collision {
+                    id: 0,
+                    rect: Rect::new(10, 20, 50, 60),
+                    name: "Head".to_string(),
+                }
+            );
+        }
+
+        #[test]
+        fn success_when_name_has_comma() {
+            let (remain, result) = parse_collision("collision1,0,0,5,5,Head, left \r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.name, "Head, left ");
+        }
+
+        #[test]
+        fn failed_when_name_missing() {
+            assert!(parse_collision("collision0,10,20,50,60").is_err());
+            assert!(parse_collision("collision0,10,20,50,60,\r\n").is_err());
+            assert!(parse_collision("collision0,10,20,50,Head").is_err());
+        }
+    }
+
+    mod parse_collision_spanned {
+        use super::*;
+
+        #[test]
+        fn success_when_name_has_comma() {
+            let source = "collision2,0,5,50,60,Head, left\r\n";
+            let (remain, result) =
+                parse_collision_spanned(source, source, &ParseOptions::STRICT).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.span, 0..31);
+            assert_eq!(result.value.id, Spanned::new(2, 9..10));
+            assert_eq!(result.value.rect.right, Spanned::new(50, 15..17));
+            assert_eq!(
+                result.value.name,
+                Spanned::new("Head, left".to_string(), 21..31)
+            );
+            assert_eq!(result.value.value(), parse_collision(source).unwrap().1);
+            assert!(parse_collision_spanned(
+                "collision0,1,2,3,4,",
+                "collision0,1,2,3,4,",
+                &ParseOptions::STRICT
+            )
+            .is_err());
+        }
+    }
+}
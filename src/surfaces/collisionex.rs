@@ -0,0 +1,299 @@
+//! `collisionex` definition and parser for it.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag},
+    character::complete::char,
+    combinator::{map, not},
+    error::context,
+    sequence::{preceded, terminated, tuple},
+    IResult,
+};
+
+use crate::{
+    geometry::point_list,
+    value::{
+        parse_filename, parse_rgb, separator, Coordinate, NumericPolicy, NumericReader, Rect, Rgb,
+    },
+    ShellParseError,
+};
+
+/// Collision defined by `collisionexN,name,shape,...`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CollisionEx {
+    pub id: u32,
+    pub name: String,
+    pub shape: CollisionExShape,
+}
+
+/// Shape of [`CollisionEx`] with its payload.
+///
+/// [`CollisionEx`]: crate::surfaces::CollisionEx
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(tag = "kind", content = "value", rename_all = "lowercase")
+)]
+#[non_exhaustive]
+pub enum CollisionExShape {
+    Rect(Rect),
+    Ellipse(Rect),
+    Circle { center: Coordinate, radius: u32 },
+    Polygon(Vec<Coordinate>),
+    Region { filename: String, color: Rgb },
+}
+
+/// parser for [`CollisionEx`].
+///
+/// Errors are labeled with the shape and the field that failed,
+/// e.g. `collisionex:circle:radius`.
+///
+/// [`CollisionEx`]: crate::surfaces::CollisionEx
+pub fn parse_collisionex(input: &str) -> IResult<&str, CollisionEx, ShellParseError<'_>> {
+    collisionex(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+pub(crate) fn collisionex<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionEx, ShellParseError<'a>> {
+    let (input, id) = context(
+        "collisionex:id",
+        preceded(tag("collisionex"), |i| numbers.u32(i)),
+    )(input)?;
+    let (input, name) = context("collisionex:name", preceded(separator, is_not(",\r\n")))(input)?;
+    let (input, shape) = preceded(separator, |i| collisionex_shape(i, numbers))(input)?;
+
+    Ok((
+        input,
+        CollisionEx {
+            id,
+            name: name.to_string(),
+            shape,
+        },
+    ))
+}
+
+fn collisionex_shape<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionExShape, ShellParseError<'a>> {
+    let (input, keyword) = context(
+        "collisionex:shape",
+        alt((
+            tag("rect"),
+            tag("ellipse"),
+            tag("circle"),
+            tag("polygon"),
+            tag("region"),
+        )),
+    )(input)?;
+
+    match keyword {
+        "rect" => shape_rect(input, numbers),
+        "ellipse" => shape_ellipse(input, numbers),
+        "circle" => shape_circle(input, numbers),
+        "polygon" => shape_polygon(input, numbers),
+        _ => shape_region(input),
+    }
+}
+
+fn shape_rect<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionExShape, ShellParseError<'a>> {
+    context(
+        "collisionex:rect:rect",
+        map(
+            preceded(separator, |i| numbers.rect(i)),
+            CollisionExShape::Rect,
+        ),
+    )(input)
+}
+
+fn shape_ellipse<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionExShape, ShellParseError<'a>> {
+    context(
+        "collisionex:ellipse:rect",
+        map(
+            preceded(separator, |i| numbers.rect(i)),
+            CollisionExShape::Ellipse,
+        ),
+    )(input)
+}
+
+fn shape_circle<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionExShape, ShellParseError<'a>> {
+    let (input, center) = context(
+        "collisionex:circle:center",
+        preceded(separator, |i| numbers.coordinate(i)),
+    )(input)?;
+    let (input, radius) = context(
+        "collisionex:circle:radius",
+        terminated(preceded(separator, |i| numbers.u32(i)), not(char(','))),
+    )(input)?;
+
+    Ok((input, CollisionExShape::Circle { center, radius }))
+}
+
+fn shape_polygon<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, CollisionExShape, ShellParseError<'a>> {
+    context(
+        "collisionex:polygon:coordinates",
+        map(
+            preceded(separator, |i| point_list(i, numbers)),
+            CollisionExShape::Polygon,
+        ),
+    )(input)
+}
+
+fn shape_region(input: &str) -> IResult<&str, CollisionExShape, ShellParseError<'_>> {
+    map(
+        tuple((
+            context(
+                "collisionex:region:filename",
+                preceded(separator, parse_filename),
+            ),
+            context("collisionex:region:color", preceded(separator, parse_rgb)),
+        )),
+        |(filename, color)| CollisionExShape::Region {
+            filename: filename.to_string(),
+            color,
+        },
+    )(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nom::error::VerboseErrorKind;
+
+    fn contexts<'a>(err: nom::Err<ShellParseError<'a>>) -> Vec<&'static str> {
+        match err {
+            nom::Err::Error(e) | nom::Err::Failure(e) => e
+                .errors
+                .iter()
+                .filter_map(|(_, kind)| match kind {
+                    VerboseErrorKind::Context(c) => Some(*c),
+                    _ => None,
+                })
+                .collect(),
+            nom::Err::Incomplete(_) => vec![],
+        }
+    }
+
+    mod parse_collisionex {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str_rect() {
+            let case = "collisionex0,Head,rect,10,20,30,40\r\n";
+            let (remain, result) = parse_collisionex(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.id, 0);
+            assert_eq!(result.name, "Head");
+            assert_eq!(
+                result.shape,
+                CollisionExShape::Rect(Rect::new(10, 20, 30, 40))
+            );
+        }
+
+        #[test]
+        fn success_when_valid_str_ellipse() {
+            let case = "collisionex1,Face,ellipse,10,20,30,40\r\n";
+            let (remain, result) = parse_collisionex(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.id, 1);
+            assert_eq!(
+                result.shape,
+                CollisionExShape::Ellipse(Rect::new(10, 20, 30, 40))
+            );
+        }
+
+        #[test]
+        fn success_when_valid_str_circle() {
+            let case = "collisionex2,Bust,circle,50,60,25\r\n";
+            let (remain, result) = parse_collisionex(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result.shape,
+                CollisionExShape::Circle {
+                    center: Coordinate::new(50, 60),
+                    radius: 25
+                }
+            );
+        }
+
+        #[test]
+        fn success_when_valid_str_polygon() {
+            let case = "collisionex3,Hand,polygon,0,0,10,0,10,10,0,10\r\n";
+            let (remain, result) = parse_collisionex(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result.shape,
+                CollisionExShape::Polygon(vec![
+                    Coordinate::new(0, 0),
+                    Coordinate::new(10, 0),
+                    Coordinate::new(10, 10),
+                    Coordinate::new(0, 10),
+                ])
+            );
+        }
+
+        #[test]
+        fn success_when_valid_str_region() {
+            let case = "collisionex4,Skirt,region,skirt.png,255,0,255\r\n";
+            let (remain, result) = parse_collisionex(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result.shape,
+                CollisionExShape::Region {
+                    filename: "skirt.png".to_string(),
+                    color: Rgb::new(255, 0, 255)
+                }
+            );
+        }
+
+        #[test]
+        fn failed_when_polygon_has_odd_coordinates() {
+            let case = "collisionex3,Hand,polygon,0,0,10,0,10,10,0\r\n";
+            let err = parse_collisionex(case).unwrap_err();
+            assert!(contexts(err).contains(&"collisionex:polygon:coordinates"));
+        }
+
+        #[test]
+        fn failed_when_polygon_has_too_few_points() {
+            let case = "collisionex3,Hand,polygon,0,0,10,0\r\n";
+            let err = parse_collisionex(case).unwrap_err();
+            assert!(contexts(err).contains(&"collisionex:polygon:coordinates"));
+        }
+
+        #[test]
+        fn failed_when_circle_has_extra_field() {
+            let case = "collisionex2,Bust,circle,50,60,25,10\r\n";
+            let err = parse_collisionex(case).unwrap_err();
+            assert!(contexts(err).contains(&"collisionex:circle:radius"));
+        }
+
+        #[test]
+        fn failed_when_unknown_shape() {
+            let case = "collisionex5,Head,triangle,0,0,10,0,10,10\r\n";
+            let err = parse_collisionex(case).unwrap_err();
+            assert!(contexts(err).contains(&"collisionex:shape"));
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! `descript` block at the top of `surfaces.txt`.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    combinator::{all_consuming, value},
+    IResult,
+};
+
+use crate::{
+    block::Block,
+    charset::{parse_charset, Charset},
+    error::{Located, ParseFailure},
+    kv::{parse_key_value, KeyValue},
+    line::is_comment,
+    value::parse_u32,
+    ShellParseError,
+};
+
+/// Order to sort collisions or animations by their indices.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum SortOrder {
+    Ascend,
+    Descend,
+    /// Keeps the order in the file.
+    #[default]
+    None,
+}
+
+impl SortOrder {
+    /// Sorts `items` by `key`. The sort is stable, and [`SortOrder::None`] keeps the order.
+    ///
+    /// [`SortOrder::None`]: crate::surfaces::SortOrder::None
+    pub fn sort_by_key<T>(&self, items: &mut [T], key: impl Fn(&T) -> u32) {
+        match self {
+            SortOrder::Ascend => items.sort_by_key(|v| key(v)),
+            SortOrder::Descend => items.sort_by_key(|v| core::cmp::Reverse(key(v))),
+            SortOrder::None => {}
+        }
+    }
+}
+
+/// parser for [`SortOrder`].
+///
+/// [`SortOrder`]: crate::surfaces::SortOrder
+pub fn parse_sort_order(input: &str) -> IResult<&str, SortOrder, ShellParseError<'_>> {
+    alt((
+        value(SortOrder::Ascend, tag("ascend")),
+        value(SortOrder::Descend, tag("descend")),
+        value(SortOrder::None, tag("none")),
+    ))(input)
+}
+
+/// Settings in the `descript` block of `surfaces.txt`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SurfacesDescript {
+    /// `version`. `0` (old definition) when absent.
+    pub version: u32,
+    /// `maxwidth`
+    pub maxwidth: Option<u32>,
+    /// `collision-sort`
+    pub collision_sort: SortOrder,
+    /// `animation-sort`
+    pub animation_sort: SortOrder,
+    /// `charset`
+    pub charset: Option<Charset>,
+    /// Entries with keys not handled here, in order.
+    pub unknown: Vec<(String, String)>,
+}
+
+impl SurfacesDescript {
+    /// Builds from the body lines of `block`. Blank and comment lines are skipped.
+    pub fn from_block(block: &Block<'_>) -> Result<SurfacesDescript, Located<ParseFailure>> {
+        let mut descript = SurfacesDescript::default();
+
+        for line in block.body.iter() {
+            if line.text.trim().is_empty() || is_comment(line.text) {
+                continue;
+            }
+
+            all_consuming(parse_key_value)(line.text)
+                .map_err(ParseFailure::from)
+                .and_then(|(_, kv)| descript.apply(&kv).map_err(ParseFailure::from))
+                .map_err(|e| Located::new(line.number, e))?;
+        }
+
+        Ok(descript)
+    }
+
+    /// Applies one entry. Unknown keys are kept in `unknown`.
+    pub fn apply<'a>(&mut self, kv: &KeyValue<'a>) -> Result<(), nom::Err<ShellParseError<'a>>> {
+        let v = kv.value;
+        match kv.key {
+            "version" => self.version = all_consuming(parse_u32)(v)?.1,
+            "maxwidth" => self.maxwidth = Some(all_consuming(parse_u32)(v)?.1),
+            "collision-sort" => self.collision_sort = all_consuming(parse_sort_order)(v)?.1,
+            "animation-sort" => self.animation_sort = all_consuming(parse_sort_order)(v)?.1,
+            "charset" => self.charset = Some(all_consuming(parse_charset)(v)?.1),
+            _ => self
+                .unknown
+                .push((kv.key.to_string(), kv.value.to_string())),
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::block::parse_blocks;
+
+    mod surfaces_descript {
+        use super::*;
+
+        #[test]
+        fn success_when_full_block() {
+            let case = "descript\r\n{\r\nversion,1\r\nmaxwidth,300\r\n// sort\r\ncollision-sort,ascend\r\nanimation-sort,descend\r\ncharset,UTF-8\r\nfuture-key,x\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let result = SurfacesDescript::from_block(&blocks[0]).unwrap();
+
+            assert_eq!(result.version, 1);
+            assert_eq!(result.maxwidth, Some(300));
+            assert_eq!(result.collision_sort, SortOrder::Ascend);
+            assert_eq!(result.animation_sort, SortOrder::Descend);
+            assert_eq!(result.charset, Some(Charset::UTF8));
+            assert_eq!(
+                result.unknown,
+                vec![("future-key".to_string(), "x".to_string())]
+            );
+        }
+
+        #[test]
+        fn success_when_version_missing() {
+            let case = "descript\r\n{\r\nmaxwidth,300\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let result = SurfacesDescript::from_block(&blocks[0]).unwrap();
+
+            assert_eq!(result.version, 0);
+            assert_eq!(result.collision_sort, SortOrder::None);
+        }
+
+        #[test]
+        fn failed_when_invalid_sort() {
+            let case = "descript\r\n{\r\nversion,1\r\ncollision-sort,upward\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let result = SurfacesDescript::from_block(&blocks[0]).unwrap_err();
+
+            assert_eq!(result.line, 4);
+        }
+    }
+
+    mod sort_order {
+        use super::*;
+
+        #[test]
+        fn sorts_stably() {
+            let mut items = vec![(2, 'a'), (1, 'b'), (2, 'c')];
+            SortOrder::Ascend.sort_by_key(&mut items, |v| v.0);
+            assert_eq!(items, vec![(1, 'b'), (2, 'a'), (2, 'c')]);
+
+            SortOrder::Descend.sort_by_key(&mut items, |v| v.0);
+            assert_eq!(items, vec![(2, 'a'), (2, 'c'), (1, 'b')]);
+
+            SortOrder::None.sort_by_key(&mut items, |v| v.0);
+            assert_eq!(items, vec![(2, 'a'), (2, 'c'), (1, 'b')]);
+        }
+    }
+}
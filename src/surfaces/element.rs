@@ -0,0 +1,307 @@
+//! `element` definition and parser for it.
+
+use alloc::string::{String, ToString};
+
+use nom::{
+    bytes::complete::tag,
+    character::complete::char,
+    combinator::{all_consuming, cut, map, not, opt},
+    error::context,
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{
+    kv::parse_key_value_with,
+    options::ParseOptions,
+    seriko::{parse_alpha, parse_draw_method, trailing_alpha, DrawMethod},
+    span::{in_source, span_of, spanned, Spanned},
+    value::{
+        parse_coordinate_spanned, parse_filename, parse_u32, separator, Coordinate,
+        CoordinateSpans, NumericPolicy, NumericReader,
+    },
+    ShellParseError,
+};
+
+/// Element composing a surface, defined by `elementN,method,filename,x,y,alpha`.
+///
+/// `x,y` is `0,0` when omitted.
+/// `alpha` can follow `x,y` only when [`DrawMethod::supports_alpha`].
+///
+/// [`DrawMethod::supports_alpha`]: crate::seriko::DrawMethod::supports_alpha
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Element {
+    pub id: u32,
+    pub method: DrawMethod,
+    pub filename: String,
+    pub offset: Coordinate,
+    pub alpha: Option<u8>,
+}
+
+/// [`Element`] with the filename borrowed from the input.
+///
+/// [`Element`]: crate::surfaces::Element
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ElementRef<'a> {
+    pub id: u32,
+    pub method: DrawMethod,
+    pub filename: &'a str,
+    pub offset: Coordinate,
+    pub alpha: Option<u8>,
+}
+
+impl ElementRef<'_> {
+    /// Returns the owned [`Element`], copying the filename.
+    ///
+    /// [`Element`]: crate::surfaces::Element
+    pub fn to_owned(&self) -> Element {
+        Element {
+            id: self.id,
+            method: self.method,
+            filename: self.filename.to_string(),
+            offset: self.offset,
+            alpha: self.alpha,
+        }
+    }
+}
+
+impl From<ElementRef<'_>> for Element {
+    fn from(value: ElementRef<'_>) -> Self {
+        value.to_owned()
+    }
+}
+
+/// parser for [`Element`].
+///
+/// [`Element`]: crate::surfaces::Element
+pub fn parse_element(input: &str) -> IResult<&str, Element, ShellParseError<'_>> {
+    map(parse_element_ref, Element::from)(input)
+}
+
+/// Same as [`parse_element`], borrowing the filename from `input`.
+///
+/// [`parse_element`]: crate::surfaces::parse_element
+pub fn parse_element_ref(input: &str) -> IResult<&str, ElementRef<'_>, ShellParseError<'_>> {
+    element_ref(input, &NumericReader::new(NumericPolicy::DEFAULT))
+}
+
+pub(crate) fn element_ref<'a>(
+    input: &'a str,
+    numbers: &NumericReader,
+) -> IResult<&'a str, ElementRef<'a>, ShellParseError<'a>> {
+    let (input, id) = context("element:id", preceded(tag("element"), |i| numbers.u32(i)))(input)?;
+    let (input, method) = context("element:method", preceded(separator, parse_draw_method))(input)?;
+    let (input, filename) =
+        context("element:filename", preceded(separator, parse_filename))(input)?;
+    let (input, offset) = context(
+        "element:offset",
+        opt(preceded(separator, |i| numbers.coordinate(i))),
+    )(input)?;
+    let (input, alpha) = match offset {
+        Some(_) if method.supports_alpha() => context("element:alpha", trailing_alpha)(input)?,
+        _ => (input, None),
+    };
+    let (input, _) = context("element:alpha", not(char(',')))(input)?;
+
+    Ok((
+        input,
+        ElementRef {
+            id,
+            method,
+            filename,
+            offset: offset.unwrap_or_default(),
+            alpha,
+        },
+    ))
+}
+
+/// [`Element`] with the span of each field.
+///
+/// [`Element`]: crate::surfaces::Element
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ElementSpans {
+    /// Digits of the ID in the key.
+    pub id: Spanned<u32>,
+    pub method: Spanned<DrawMethod>,
+    pub filename: Spanned<String>,
+    /// `None` when `x,y` is omitted.
+    pub offset: Option<CoordinateSpans>,
+    pub alpha: Option<Spanned<u8>>,
+}
+
+impl ElementSpans {
+    pub fn value(&self) -> Element {
+        Element {
+            id: self.id.value,
+            method: self.method.value,
+            filename: self.filename.value.clone(),
+            offset: self
+                .offset
+                .as_ref()
+                .map(CoordinateSpans::value)
+                .unwrap_or_default(),
+            alpha: self.alpha.as_ref().map(|a| a.value),
+        }
+    }
+}
+
+/// Same as [`parse_element`], with spans in `source`, of which `input` is a subslice.
+///
+/// The line is split into the key and the value by [`parse_key_value_with`] with `options`,
+/// so whitespace it drops is outside the spans. The span of the whole is from the key to the value.
+///
+/// [`parse_element`]: crate::surfaces::parse_element
+/// [`parse_key_value_with`]: crate::kv::parse_key_value_with
+pub fn parse_element_spanned<'a>(
+    input: &'a str,
+    source: &'a str,
+    options: &ParseOptions,
+) -> IResult<&'a str, Spanned<ElementSpans>, ShellParseError<'a>> {
+    let (input, _) = in_source(input, source)?;
+    let (input, kv) = parse_key_value_with(input, options)?;
+    let (_, id) = context(
+        "element:id",
+        all_consuming(preceded(tag("element"), spanned(source, parse_u32))),
+    )(kv.key)?;
+
+    let value = kv.value;
+    let (value, method) = context("element:method", spanned(source, parse_draw_method))(value)?;
+    let (value, filename) = context(
+        "element:filename",
+        preceded(
+            separator,
+            map(spanned(source, parse_filename), |f| f.map(String::from)),
+        ),
+    )(value)?;
+    let (value, offset) = context(
+        "element:offset",
+        opt(preceded(separator, |i| parse_coordinate_spanned(i, source))),
+    )(value)?;
+    let (value, alpha) = match offset {
+        Some(_) if method.value.supports_alpha() => context(
+            "element:alpha",
+            opt(preceded(separator, cut(spanned(source, parse_alpha)))),
+        )(value)?,
+        _ => (value, None),
+    };
+    context("element:alpha", all_consuming(not(char(','))))(value)?;
+
+    let span = span_of(source, kv.key).start..span_of(source, kv.value).end;
+    Ok((
+        input,
+        Spanned::new(
+            ElementSpans {
+                id,
+                method,
+                filename,
+                offset: offset.map(|o| o.value),
+                alpha,
+            },
+            span,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_element {
+        use super::*;
+
+        #[test]
+        fn success_when_without_alpha() {
+            let case = "element0,overlay,body.png,0,10\r\n";
+            let (remain, result) = parse_element(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Element {
+                    id: 0,
+                    method: DrawMethod::Overlay,
+                    filename: "body.png".to_string(),
+                    offset: Coordinate::new(0, 10),
+                    alpha: None,
+                }
+            );
+        }
+
+        #[test]
+        fn success_when_with_alpha() {
+            let (_, result) = parse_element("element1,add,glow.png,5,5,64").unwrap();
+            assert_eq!(result.alpha, Some(64));
+        }
+
+        #[test]
+        fn failed_when_alpha_forbidden_or_out_of_range() {
+            assert!(parse_element("element0,base,body.png,0,0,64").is_err());
+            assert!(parse_element("element0,overlay,body.png,0,0,300").is_err());
+        }
+    }
+
+    mod parse_element_spanned {
+        use super::*;
+
+        #[test]
+        fn success_when_strict() {
+            let source = "surface0\r\n{\r\nelement1,overlay,body.png,-5,10,64\r\n}\r\n";
+            let line = &source[13..47];
+            let (remain, result) =
+                parse_element_spanned(line, source, &ParseOptions::STRICT).unwrap();
+            assert_eq!(remain, "");
+            assert_eq!(result.span, 13..47);
+
+            let spans = result.value;
+            assert_eq!(spans.id, Spanned::new(1, 20..21));
+            assert_eq!(spans.method, Spanned::new(DrawMethod::Overlay, 22..29));
+            assert_eq!(spans.filename, Spanned::new("body.png".to_string(), 30..38));
+            let offset = spans.offset.as_ref().unwrap();
+            assert_eq!(offset.x, Spanned::new(-5, 39..41));
+            assert_eq!(offset.y, Spanned::new(10, 42..44));
+            assert_eq!(spans.alpha, Some(Spanned::new(64, 45..47)));
+            assert_eq!(spans.value(), parse_element(line).unwrap().1);
+        }
+
+        #[test]
+        fn success_when_whitespace_trimmed() {
+            let source = "  element0 , base,body.png,0,10 \r\n";
+            let options = ParseOptions::STRICT.trim_whitespace(true);
+            let (remain, result) = parse_element_spanned(source, source, &options).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.span, 2..31);
+
+            let spans = result.value;
+            assert_eq!(spans.id, Spanned::new(0, 9..10));
+            assert_eq!(spans.method, Spanned::new(DrawMethod::Base, 13..17));
+            assert_eq!(spans.filename.span, 18..26);
+            let offset = spans.offset.unwrap();
+            assert_eq!(offset.x, Spanned::new(0, 27..28));
+            assert_eq!(offset.y, Spanned::new(10, 29..31));
+            assert_eq!(spans.alpha, None);
+
+            assert!(parse_element_spanned(source, source, &ParseOptions::STRICT).is_err());
+        }
+
+        #[test]
+        fn failed_when_invalid_fields() {
+            let options = ParseOptions::STRICT;
+            for case in [
+                "element,base,a.png",
+                "element0x,base,a.png",
+                "element0,base,a.png,0,0,64",
+                "element0,overlay,a.png,0,0 junk",
+            ] {
+                assert!(
+                    parse_element_spanned(case, case, &options).is_err(),
+                    "{}",
+                    case
+                );
+            }
+            let (_, result) =
+                parse_element_spanned("element2,base,a.png", "element2,base,a.png", &options)
+                    .unwrap();
+            assert_eq!(result.value.offset, None);
+        }
+    }
+}
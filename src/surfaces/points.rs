@@ -0,0 +1,183 @@
+//! Anchor points defined by `point.*` keys in a surface block.
+
+use nom::combinator::all_consuming;
+
+use crate::{
+    value::{Coordinate, NumericPolicy, NumericReader},
+    ShellParseError,
+};
+
+/// Anchor points of a surface.
+///
+/// Each field is `None` when the corresponding key is absent.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Points {
+    /// `point.centerx`
+    pub center_x: Option<i32>,
+    /// `point.centery`
+    pub center_y: Option<i32>,
+    /// `point.kinoko.centerx`
+    pub kinoko_center_x: Option<i32>,
+    /// `point.kinoko.centery`
+    pub kinoko_center_y: Option<i32>,
+    /// `point.basepos.x`
+    pub basepos_x: Option<i32>,
+    /// `point.basepos.y`
+    pub basepos_y: Option<i32>,
+}
+
+/// [`Points`] with all defaults filled in.
+///
+/// [`Points`]: crate::surfaces::Points
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ResolvedPoints {
+    pub center: Coordinate,
+    pub kinoko_center: Coordinate,
+    pub basepos: Coordinate,
+}
+
+impl Points {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `Ok(false)` when the key is not a `point.*` key handled here.
+    pub fn apply<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        self.apply_with_numbers(
+            key_segments,
+            value,
+            &NumericReader::new(NumericPolicy::DEFAULT),
+        )
+    }
+
+    /// Same as [`Points::apply`], reading numbers by `numbers`.
+    ///
+    /// [`Points::apply`]: crate::surfaces::Points::apply
+    pub(crate) fn apply_with_numbers<'a>(
+        &mut self,
+        key_segments: &[&str],
+        value: &'a str,
+        numbers: &NumericReader,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let field = match key_segments {
+            ["point", "centerx"] => &mut self.center_x,
+            ["point", "centery"] => &mut self.center_y,
+            ["point", "kinoko", "centerx"] => &mut self.kinoko_center_x,
+            ["point", "kinoko", "centery"] => &mut self.kinoko_center_y,
+            ["point", "basepos", "x"] => &mut self.basepos_x,
+            ["point", "basepos", "y"] => &mut self.basepos_y,
+            _ => return Ok(false),
+        };
+
+        let (_, v) = all_consuming(|i| numbers.i32(i))(value)?;
+        *field = Some(v);
+
+        Ok(true)
+    }
+
+    /// Fills in defaults for the surface of `surface_size` (`(width, height)`).
+    ///
+    /// - center: the center of the image.
+    /// - kinoko center: the resolved center.
+    /// - basepos: the bottom center of the image.
+    pub fn resolve(&self, surface_size: (u32, u32)) -> ResolvedPoints {
+        let half_width = (surface_size.0 / 2) as i32;
+        let half_height = (surface_size.1 / 2) as i32;
+        let height = surface_size.1 as i32;
+
+        let center = Coordinate::new(
+            self.center_x.unwrap_or(half_width),
+            self.center_y.unwrap_or(half_height),
+        );
+        let kinoko_center = Coordinate::new(
+            self.kinoko_center_x.unwrap_or(center.x),
+            self.kinoko_center_y.unwrap_or(center.y),
+        );
+        let basepos = Coordinate::new(
+            self.basepos_x.unwrap_or(half_width),
+            self.basepos_y.unwrap_or(height),
+        );
+
+        ResolvedPoints {
+            center,
+            kinoko_center,
+            basepos,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod points {
+        use super::*;
+
+        #[test]
+        fn success_when_fully_specified() {
+            let mut points = Points::default();
+            let cases = [
+                (vec!["point", "centerx"], "10"),
+                (vec!["point", "centery"], "20"),
+                (vec!["point", "kinoko", "centerx"], "30"),
+                (vec!["point", "kinoko", "centery"], "40"),
+                (vec!["point", "basepos", "x"], "50"),
+                (vec!["point", "basepos", "y"], "60"),
+            ];
+            for (key, value) in cases.iter() {
+                assert!(points.apply(key, value).unwrap());
+            }
+
+            let result = points.resolve((200, 400));
+            assert_eq!(result.center, Coordinate::new(10, 20));
+            assert_eq!(result.kinoko_center, Coordinate::new(30, 40));
+            assert_eq!(result.basepos, Coordinate::new(50, 60));
+        }
+
+        #[test]
+        fn success_when_fully_defaulted() {
+            let result = Points::default().resolve((200, 400));
+            assert_eq!(result.center, Coordinate::new(100, 200));
+            assert_eq!(result.kinoko_center, Coordinate::new(100, 200));
+            assert_eq!(result.basepos, Coordinate::new(100, 400));
+        }
+
+        #[test]
+        fn success_when_negative_center() {
+            let mut points = Points::default();
+            assert!(points.apply(&["point", "centerx"], "-15").unwrap());
+            assert!(points.apply(&["point", "centery"], "-25").unwrap());
+
+            let result = points.resolve((200, 400));
+            assert_eq!(result.center, Coordinate::new(-15, -25));
+            assert_eq!(result.kinoko_center, Coordinate::new(-15, -25));
+        }
+
+        #[test]
+        fn success_when_kinoko_only() {
+            let mut points = Points::default();
+            assert!(points.apply(&["point", "kinoko", "centerx"], "5").unwrap());
+            assert!(points.apply(&["point", "kinoko", "centery"], "6").unwrap());
+
+            let result = points.resolve((200, 400));
+            assert_eq!(result.center, Coordinate::new(100, 200));
+            assert_eq!(result.kinoko_center, Coordinate::new(5, 6));
+        }
+
+        #[test]
+        fn ignored_when_unknown_key() {
+            let mut points = Points::default();
+            assert!(!points.apply(&["point", "unknown"], "5").unwrap());
+            assert_eq!(points, Points::default());
+        }
+
+        #[test]
+        fn failed_when_invalid_value() {
+            let mut points = Points::default();
+            assert!(points.apply(&["point", "centerx"], "5x").is_err());
+        }
+    }
+}
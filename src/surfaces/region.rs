@@ -0,0 +1,148 @@
+//! Names of collisions with meanings conventional among shells and baseware.
+
+use alloc::vec::Vec;
+
+/// Name of a collision, with its conventional meaning.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KnownRegion {
+    /// Canonical name, such as `Head`.
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+const KNOWN_REGIONS: &[KnownRegion] = &[
+    KnownRegion {
+        name: "Head",
+        description: "head, stroked to pat",
+    },
+    KnownRegion {
+        name: "Face",
+        description: "face, double-clicked to poke",
+    },
+    KnownRegion {
+        name: "Bust",
+        description: "chest",
+    },
+    KnownRegion {
+        name: "Hand",
+        description: "hand, held or shaken",
+    },
+    KnownRegion {
+        name: "Shoulder",
+        description: "shoulder, tapped to call",
+    },
+    KnownRegion {
+        name: "Skirt",
+        description: "skirt",
+    },
+    KnownRegion {
+        name: "Leg",
+        description: "leg",
+    },
+    KnownRegion {
+        name: "Ribbon",
+        description: "ribbon or other accessory",
+    },
+    KnownRegion {
+        name: "Hair",
+        description: "hair, stroked or pulled",
+    },
+    KnownRegion {
+        name: "Mouth",
+        description: "mouth",
+    },
+];
+
+/// Returns names of collisions with conventional meanings, such as `Head` stroked by users to pat the character.
+pub fn known_regions() -> &'static [KnownRegion] {
+    KNOWN_REGIONS
+}
+
+/// Returns the canonical name of the known region `name` is a near miss of,
+/// as it is in another ASCII case or within a few edits of it, such as `head` or `Heed` for `Head`.
+///
+/// A canonical name itself, also numbered such as `Hair2`, or a name far from all of them, has no suggestion.
+/// Names of up to 5 characters are within 1 edit, and longer ones within 2.
+pub fn suggest_region(name: &str) -> Option<&'static str> {
+    let unnumbered = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if KNOWN_REGIONS.iter().any(|r| r.name == unnumbered) {
+        return None;
+    }
+
+    let max = if name.chars().count() <= 5 { 1 } else { 2 };
+    KNOWN_REGIONS
+        .iter()
+        .filter_map(|r| edit_distance(name, r.name, max).map(|d| (d, r.name)))
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, canonical)| canonical)
+}
+
+/// Levenshtein distance of `a` and `b` ignoring ASCII case, or `None` when it is over `max`.
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let b: Vec<char> = b.chars().map(|c| c.to_ascii_lowercase()).collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = Vec::with_capacity(b.len() + 1);
+    for (i, ca) in a.iter().enumerate() {
+        current.clear();
+        current.push(i + 1);
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        // Distances only grow from the smallest of a row.
+        if current.iter().all(|&d| d > max) {
+            return None;
+        }
+        core::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&d| d <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod suggest_region {
+        use super::*;
+
+        #[test]
+        fn success_when_case_differs() {
+            assert_eq!(suggest_region("head"), Some("Head"));
+            assert_eq!(suggest_region("BUST"), Some("Bust"));
+        }
+
+        #[test]
+        fn success_when_one_letter_typo() {
+            assert_eq!(suggest_region("Heed"), Some("Head"));
+            assert_eq!(suggest_region("Shoulders"), Some("Shoulder"));
+            assert_eq!(suggest_region("Sholder"), Some("Shoulder"));
+        }
+
+        #[test]
+        fn none_when_canonical_or_custom() {
+            assert_eq!(suggest_region("Head"), None);
+            assert_eq!(suggest_region("Hair2"), None);
+            assert_eq!(suggest_region("Tail"), None);
+            assert_eq!(suggest_region("Glasses"), None);
+            assert_eq!(suggest_region(""), None);
+        }
+    }
+
+    mod known_regions {
+        use super::*;
+
+        #[test]
+        fn success_when_names_unique() {
+            let regions = known_regions();
+            for (i, region) in regions.iter().enumerate() {
+                assert!(regions[..i].iter().all(|r| r.name != region.name));
+            }
+        }
+    }
+}
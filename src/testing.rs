@@ -0,0 +1,35 @@
+//! Helpers shared by the tests of the modules.
+
+use alloc::string::String;
+
+/// xorshift, so that generated cases are deterministic.
+pub(crate) struct XorShift(u32);
+
+impl XorShift {
+    /// `seed` must not be 0.
+    pub(crate) fn new(seed: u32) -> XorShift {
+        XorShift(seed)
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// Returns a number less than `n`.
+    pub(crate) fn below(&mut self, n: u32) -> u32 {
+        self.next_u32() % n
+    }
+
+    pub(crate) fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.next_u32() as usize % items.len()]
+    }
+
+    /// Returns less than `max` of `pieces` joined.
+    pub(crate) fn concat(&mut self, pieces: &[&str], max: u32) -> String {
+        let len = self.below(max);
+        (0..len).map(|_| self.pick(pieces)).collect()
+    }
+}
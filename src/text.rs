@@ -0,0 +1,100 @@
+//! # `text`
+//!
+//! Excerpts of text for diagnostics, cut on character boundaries.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::text::{excerpt, excerpt_around};
+//!
+//! assert_eq!(excerpt("さくらの肩", 3), "さくら");
+//!
+//! let line = "sakura.name,さくら,ほかの値";
+//! let (text, caret) = excerpt_around(line, line.find('ほ').unwrap(), 2);
+//! assert_eq!(text, "ら,ほか");
+//! assert_eq!(&text[caret..], "ほか");
+//! ```
+
+/// Returns the first `max_chars` characters of `s`, or all of `s` when it is shorter.
+pub fn excerpt(s: &str, max_chars: usize) -> &str {
+    s.char_indices()
+        .nth(max_chars)
+        .map_or(s, |(offset, _)| &s[..offset])
+}
+
+/// Returns the part of `s` around `byte_pos`, with up to `context_chars` characters before it
+/// and up to `context_chars` from it, and the byte offset of `byte_pos` in the part.
+///
+/// `byte_pos` is moved to the start of the character it is in, and to the end of `s` when it is past the end.
+#[cfg(feature = "kv")]
+pub fn excerpt_around(s: &str, byte_pos: usize, context_chars: usize) -> (&str, usize) {
+    let mut pos = byte_pos.min(s.len());
+    while !s.is_char_boundary(pos) {
+        pos -= 1;
+    }
+
+    let start = match context_chars.checked_sub(1) {
+        Some(n) => s[..pos].char_indices().rev().nth(n).map_or(0, |(o, _)| o),
+        None => pos,
+    };
+    let end = pos + excerpt(&s[pos..], context_chars).len();
+    (&s[start..end], pos - start)
+}
+
+#[cfg(all(test, feature = "kv"))]
+mod tests {
+    use super::*;
+
+    mod excerpt {
+        use super::*;
+
+        #[test]
+        fn success_when_cut_on_character() {
+            assert_eq!(excerpt("里々の設定", 2), "里々");
+            assert_eq!(excerpt("ab里々", 3), "ab里");
+            assert_eq!(excerpt("里々", 5), "里々");
+            assert_eq!(excerpt("里々", 0), "");
+        }
+
+        #[test]
+        fn success_when_empty() {
+            assert_eq!(excerpt("", 3), "");
+        }
+    }
+
+    mod excerpt_around {
+        use super::*;
+
+        #[test]
+        fn success_when_position_in_middle() {
+            let s = "一二三四五六七";
+            let (text, caret) = excerpt_around(s, s.find('四').unwrap(), 2);
+            assert_eq!(text, "二三四五");
+            assert_eq!(&text[caret..], "四五");
+        }
+
+        #[test]
+        fn success_when_position_mid_character() {
+            let s = "一二三四五六七";
+            let (text, caret) = excerpt_around(s, s.find('四').unwrap() + 1, 2);
+            assert_eq!(text, "二三四五");
+            assert_eq!(text[..caret].chars().count(), 2);
+        }
+
+        #[test]
+        fn success_when_near_ends() {
+            let s = "一二三四";
+            assert_eq!(excerpt_around(s, 0, 2), ("一二", 0));
+            assert_eq!(excerpt_around(s, s.len(), 2), ("三四", 6));
+            assert_eq!(excerpt_around(s, 100, 2), ("三四", 6));
+            assert_eq!(excerpt_around(s, 3, 10), (s, 3));
+            assert_eq!(excerpt_around(s, 3, 0), ("", 0));
+        }
+
+        #[test]
+        fn success_when_empty() {
+            assert_eq!(excerpt_around("", 0, 3), ("", 0));
+            assert_eq!(excerpt_around("", 5, 3), ("", 0));
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! # `tooltip`
+//!
+//! Tooltips shown on collisions.
+//! `<scope>.tooltip.<collision>,text` in `descript.txt`,
+//! or unscoped `tooltip.<collision>,text` inside a surface block.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::scope::Scope;
+//! use shell_parser_common_rs::tooltip::{merge_tooltips, BlockTooltips, Tooltips};
+//!
+//! let mut descript = Tooltips::default();
+//! assert!(descript.apply(&["sakura", "tooltip", "Head"], "head"));
+//!
+//! let mut block = BlockTooltips::default();
+//! assert!(block.apply(&["tooltip", "Head"], "smiling head"));
+//!
+//! let merged = merge_tooltips(descript.get(Scope::Sakura), &block, &["Head"]);
+//! assert_eq!(merged.tooltips[0].text, "smiling head");
+//! ```
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    bytes::complete::{is_not, tag},
+    combinator::{map, verify},
+    error::context,
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{
+    kv::fields_then_rest,
+    scope::{split_scope, Scope},
+    ShellParseError,
+};
+
+/// Tooltip of a collision.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Tooltip {
+    pub collision: String,
+    pub text: String,
+}
+
+impl Tooltip {
+    /// Builds from the key segments after `tooltip`, joined by `.` as the collision name.
+    pub(crate) fn from_segments(segments: &[&str], value: &str) -> Option<Tooltip> {
+        if segments.is_empty() || segments.iter().any(|s| s.is_empty()) {
+            return None;
+        }
+
+        Some(Tooltip {
+            collision: segments.join("."),
+            text: value.to_string(),
+        })
+    }
+}
+
+/// Tooltips in `descript.txt`, per scope.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Tooltips {
+    tooltips: BTreeMap<Scope, Vec<Tooltip>>,
+}
+
+impl Tooltips {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `false` when the key is not a scoped tooltip key.
+    pub fn apply(&mut self, key_segments: &[&str], value: &str) -> bool {
+        let Some((scope, ["tooltip", rest @ ..])) = split_scope(key_segments) else {
+            return false;
+        };
+        let Some(tooltip) = Tooltip::from_segments(rest, value) else {
+            return false;
+        };
+
+        self.tooltips.entry(scope).or_default().push(tooltip);
+        true
+    }
+
+    /// Returns tooltips of `scope` in definition order.
+    pub fn get(&self, scope: Scope) -> &[Tooltip] {
+        self.tooltips
+            .get(&scope)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// Tooltips inside a surface block.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BlockTooltips {
+    /// `tooltip.*`, in order.
+    pub tooltips: Vec<Tooltip>,
+}
+
+impl BlockTooltips {
+    /// Applies one entry. `key_segments` is the key split by `.`.
+    ///
+    /// Returns `false` when the key is not an unscoped tooltip key.
+    pub fn apply(&mut self, key_segments: &[&str], value: &str) -> bool {
+        let ["tooltip", rest @ ..] = key_segments else {
+            return false;
+        };
+        let Some(tooltip) = Tooltip::from_segments(rest, value) else {
+            return false;
+        };
+
+        self.tooltips.push(tooltip);
+        true
+    }
+
+    /// Returns tooltips naming collisions not in `collisions`, in order.
+    pub fn unmatched(&self, collisions: &[&str]) -> Vec<&Tooltip> {
+        self.tooltips
+            .iter()
+            .filter(|t| !collisions.contains(&t.collision.as_str()))
+            .collect()
+    }
+}
+
+/// [`Tooltip`] with the collision name and the text borrowed from the input.
+///
+/// [`Tooltip`]: crate::tooltip::Tooltip
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TooltipRef<'a> {
+    pub collision: &'a str,
+    pub text: &'a str,
+}
+
+impl TooltipRef<'_> {
+    /// Returns the owned [`Tooltip`], copying the collision name and the text.
+    ///
+    /// [`Tooltip`]: crate::tooltip::Tooltip
+    pub fn to_owned(&self) -> Tooltip {
+        Tooltip {
+            collision: self.collision.to_string(),
+            text: self.text.to_string(),
+        }
+    }
+}
+
+impl From<TooltipRef<'_>> for Tooltip {
+    fn from(value: TooltipRef<'_>) -> Self {
+        value.to_owned()
+    }
+}
+
+/// parser for [`Tooltip`] inside a surface block, `tooltip.<collision>,text`.
+///
+/// The collision name is until the first separator, and the text is the rest of the line,
+/// with its commas and whitespace. The line ending is not consumed.
+///
+/// [`Tooltip`]: crate::tooltip::Tooltip
+pub fn parse_block_tooltip(input: &str) -> IResult<&str, Tooltip, ShellParseError<'_>> {
+    map(parse_block_tooltip_ref, Tooltip::from)(input)
+}
+
+/// Same as [`parse_block_tooltip`], borrowing the collision name and the text from `input`.
+///
+/// [`parse_block_tooltip`]: crate::tooltip::parse_block_tooltip
+pub fn parse_block_tooltip_ref(input: &str) -> IResult<&str, TooltipRef<'_>, ShellParseError<'_>> {
+    let (input, (collision, text)) = context(
+        "tooltip:collision",
+        preceded(
+            tag("tooltip."),
+            fields_then_rest(
+                1,
+                verify(is_not(",\r\n"), |name: &str| {
+                    !name.split('.').any(str::is_empty)
+                }),
+            ),
+        ),
+    )(input)?;
+
+    Ok((
+        input,
+        TooltipRef {
+            collision: collision[0],
+            text,
+        },
+    ))
+}
+
+/// Result of [`merge_tooltips`].
+///
+/// [`merge_tooltips`]: crate::tooltip::merge_tooltips
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct MergedTooltips {
+    /// Tooltips of `descript.txt` in order, each replaced in place by the block tooltip of its collision,
+    /// followed by the other block tooltips in order.
+    pub tooltips: Vec<Tooltip>,
+    /// Collision names of block tooltips not found in the block, in order.
+    pub unmatched: Vec<String>,
+}
+
+/// Layers `block` tooltips over `descript` tooltips.
+///
+/// A block tooltip replaces the descript tooltip of the same collision name.
+/// Block tooltips naming collisions not in `collisions` are reported, but still merged.
+pub fn merge_tooltips(
+    descript: &[Tooltip],
+    block: &BlockTooltips,
+    collisions: &[&str],
+) -> MergedTooltips {
+    let mut merged = MergedTooltips {
+        tooltips: descript.to_vec(),
+        unmatched: Vec::new(),
+    };
+
+    for tooltip in block.tooltips.iter() {
+        if !collisions.contains(&tooltip.collision.as_str()) {
+            merged.unmatched.push(tooltip.collision.clone());
+        }
+
+        match merged
+            .tooltips
+            .iter_mut()
+            .find(|t| t.collision == tooltip.collision)
+        {
+            Some(t) => *t = tooltip.clone(),
+            None => merged.tooltips.push(tooltip.clone()),
+        }
+    }
+
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod tooltips {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_entries() {
+            let mut tooltips = Tooltips::default();
+            assert!(tooltips.apply(&["sakura", "tooltip", "Head"], "head, top"));
+            assert!(tooltips.apply(&["kero", "tooltip", "Face"], "face"));
+
+            assert_eq!(
+                tooltips.get(Scope::Sakura),
+                &[Tooltip {
+                    collision: "Head".to_string(),
+                    text: "head, top".to_string(),
+                }]
+            );
+            assert_eq!(tooltips.get(Scope::Kero)[0].collision, "Face");
+        }
+
+        #[test]
+        fn ignored_when_unscoped_or_nameless() {
+            let mut tooltips = Tooltips::default();
+            assert!(!tooltips.apply(&["tooltip", "Head"], "head"));
+            assert!(!tooltips.apply(&["sakura", "tooltip"], "head"));
+        }
+    }
+
+    mod parse_block_tooltip {
+        use super::*;
+
+        #[test]
+        fn success_when_text_has_commas() {
+            let (remain, result) = parse_block_tooltip("tooltip.Head,head, top ,\r\n").unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(
+                result,
+                Tooltip {
+                    collision: "Head".to_string(),
+                    text: "head, top ,".to_string(),
+                }
+            );
+            let (_, result) = parse_block_tooltip("tooltip.Left.Hand,").unwrap();
+            assert_eq!(result.collision, "Left.Hand");
+        }
+
+        #[test]
+        fn failed_when_collision_missing() {
+            assert!(parse_block_tooltip("tooltip.,head").is_err());
+            assert!(parse_block_tooltip("tooltip.a..b,head").is_err());
+            assert!(parse_block_tooltip("tooltip.Head").is_err());
+            assert!(parse_block_tooltip("sakura.tooltip.Head,head").is_err());
+        }
+    }
+
+    mod block_tooltips {
+        use super::*;
+
+        #[test]
+        fn success_when_unmatched() {
+            let mut block = BlockTooltips::default();
+            block.apply(&["tooltip", "Head"], "head");
+            block.apply(&["tooltip", "Tail"], "tail");
+            let unmatched = block.unmatched(&["Head", "Bust"]);
+            assert_eq!(unmatched.len(), 1);
+            assert_eq!(unmatched[0].collision, "Tail");
+        }
+    }
+
+    mod merge_tooltips {
+        use super::*;
+
+        fn descript() -> Tooltips {
+            let mut tooltips = Tooltips::default();
+            tooltips.apply(&["sakura", "tooltip", "Head"], "head");
+            tooltips.apply(&["sakura", "tooltip", "Bust"], "bust");
+            tooltips
+        }
+
+        #[test]
+        fn block_overrides_descript() {
+            let mut block = BlockTooltips::default();
+            assert!(block.apply(&["tooltip", "Head"], "smiling head"));
+
+            let result = merge_tooltips(descript().get(Scope::Sakura), &block, &["Head", "Bust"]);
+            assert_eq!(result.tooltips.len(), 2);
+            assert_eq!(result.tooltips[0].text, "smiling head");
+            assert_eq!(result.tooltips[1].text, "bust");
+            assert!(result.unmatched.is_empty());
+        }
+
+        #[test]
+        fn unmatched_collision() {
+            let mut block = BlockTooltips::default();
+            block.apply(&["tooltip", "Tail"], "tail");
+
+            let result = merge_tooltips(descript().get(Scope::Sakura), &block, &["Head"]);
+            assert_eq!(result.unmatched, vec!["Tail".to_string()]);
+            assert_eq!(result.tooltips.len(), 3);
+        }
+    }
+}
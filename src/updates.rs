@@ -0,0 +1,217 @@
+//! # `updates`
+//!
+//! Lists of paths in update archives, `delete.txt` and `refreshundeletemask` of `install.txt`,
+//! and records of `updates2.dau` for network updates.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::updates::{is_listed, parse_delete_list};
+//!
+//! let list = parse_delete_list("// old images\r\nimages\\surface9.png\r\nold/\r\n").unwrap();
+//!
+//! assert_eq!(list[0].as_str(), "images/surface9.png");
+//! assert!(is_listed(&list, "IMAGES/Surface9.png"));
+//! assert!(is_listed(&list, "old\\a.png"));
+//! ```
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use nom::{
+    bytes::complete::{is_not, take_while_m_n},
+    character::complete::char,
+    combinator::{all_consuming, opt, rest},
+    error::context,
+    sequence::preceded,
+    IResult,
+};
+
+use crate::{
+    error::{Located, ParseFailure},
+    line::{is_comment, lines},
+    path::RelativePath,
+    ShellParseError,
+};
+
+#[cfg(feature = "encodings")]
+mod url;
+
+#[cfg(feature = "encodings")]
+pub use url::{resolve_url, resolve_urls, HomeUrl, ResolveError};
+
+/// Record of a file in `updates2.dau`, `path\x01md5\x01...`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UpdateRecord {
+    /// Path of the file, not checked to be inside the directory.
+    pub path: RelativePath,
+    /// MD5 digest of the file in hexadecimal.
+    pub md5: String,
+}
+
+/// Parses `delete.txt`, one path per line. Blank and comment lines are ignored.
+///
+/// Paths are trimmed and normalized as [`RelativePath::parse`] does,
+/// failing at the first absolute path or path containing `..`.
+///
+/// [`RelativePath::parse`]: crate::path::RelativePath::parse
+pub fn parse_delete_list(input: &str) -> Result<Vec<RelativePath>, Located<ParseFailure>> {
+    lines(input)
+        .filter(|line| !line.text.trim().is_empty() && !is_comment(line.text))
+        .map(|line| {
+            RelativePath::parse(line.text.trim()).map_err(|e| Located::new(line.number, e.into()))
+        })
+        .collect()
+}
+
+/// Parses the value of `refreshundeletemask`, paths separated by `:`.
+///
+/// Paths are checked as [`parse_delete_list`] does. Empty paths are ignored.
+///
+/// [`parse_delete_list`]: crate::updates::parse_delete_list
+pub fn parse_undelete_mask(value: &str) -> Result<Vec<RelativePath>, ParseFailure> {
+    value
+        .split(':')
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .map(|path| RelativePath::parse(path).map_err(ParseFailure::from))
+        .collect()
+}
+
+/// Parses `updates2.dau`, one record per line. Blank lines are ignored.
+///
+/// Fields after the MD5 digest, such as the size, are skipped.
+/// Paths are read as [`RelativePath::new`] does and checked by [`resolve_url`].
+///
+/// [`RelativePath::new`]: crate::path::RelativePath::new
+/// [`resolve_url`]: crate::updates::resolve_url
+pub fn parse_update_list(input: &str) -> Result<Vec<UpdateRecord>, Located<ParseFailure>> {
+    lines(input)
+        .filter(|line| !line.text.trim().is_empty())
+        .map(|line| {
+            all_consuming(update_record)(line.text)
+                .map(|(_, record)| record)
+                .map_err(|e| Located::new(line.number, e.into()))
+        })
+        .collect()
+}
+
+fn update_record(input: &str) -> IResult<&str, UpdateRecord, ShellParseError<'_>> {
+    let (input, path) = context("updates:path", is_not("\x01\r\n"))(input)?;
+    let (input, md5) = context(
+        "updates:md5",
+        preceded(
+            char('\x01'),
+            take_while_m_n(32, 32, |c: char| c.is_ascii_hexdigit()),
+        ),
+    )(input)?;
+    let (input, _) = opt(preceded(char('\x01'), rest))(input)?;
+
+    Ok((
+        input,
+        UpdateRecord {
+            path: RelativePath::new(path),
+            md5: md5.to_string(),
+        },
+    ))
+}
+
+/// Checks whether `path` is in `list`, or in a directory in it, ignoring ASCII case as Windows does.
+pub fn is_listed(list: &[RelativePath], path: &str) -> bool {
+    let path = RelativePath::new(path);
+    list.iter().any(|p| p.contains_ignore_case(&path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::path::PathError;
+
+    mod parse_delete_list {
+        use super::*;
+
+        #[test]
+        fn success_when_mixed_separators() {
+            let case = "images\\surface9.png\n\r\n// comment\r\n ghost/master\\old.dic \r\n";
+            let list = parse_delete_list(case).unwrap();
+            assert_eq!(
+                list,
+                vec![
+                    RelativePath::new("images/surface9.png"),
+                    RelativePath::new("ghost/master/old.dic")
+                ]
+            );
+            assert!(parse_delete_list("").unwrap().is_empty());
+        }
+
+        #[test]
+        fn failed_when_traversal() {
+            let case = "a.png\r\nimages/../../ghost/master/a.dic\r\n";
+            assert_eq!(
+                parse_delete_list(case).unwrap_err(),
+                Located::new(2, ParseFailure::Path(PathError::Traversal))
+            );
+            assert_eq!(
+                parse_delete_list("\r\n/etc/passwd\r\n").unwrap_err(),
+                Located::new(2, ParseFailure::Path(PathError::Absolute))
+            );
+        }
+    }
+
+    mod parse_undelete_mask {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_value() {
+            let list = parse_undelete_mask("profile\\a.dat:b.txt:").unwrap();
+            assert_eq!(
+                list,
+                vec![
+                    RelativePath::new("profile/a.dat"),
+                    RelativePath::new("b.txt")
+                ]
+            );
+            assert_eq!(
+                parse_undelete_mask("a.txt:../b.txt").unwrap_err(),
+                ParseFailure::Path(PathError::Traversal)
+            );
+        }
+    }
+
+    mod parse_update_list {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_records() {
+            let case = "surface0.png\x010123456789abcdef0123456789ABCDEF\x01size=120\x01\r\n\r\nimages\\a.png\x01ffffffffffffffffffffffffffffffff\r\n";
+            let records = parse_update_list(case).unwrap();
+            assert_eq!(records.len(), 2);
+            assert_eq!(records[0].path.as_str(), "surface0.png");
+            assert_eq!(records[0].md5, "0123456789abcdef0123456789ABCDEF");
+            assert_eq!(records[1].path.as_str(), "images/a.png");
+        }
+
+        #[test]
+        fn failed_when_invalid_md5() {
+            let failure = parse_update_list("a.png\x01abc\r\n").unwrap_err();
+            assert_eq!(failure.line, 1);
+            assert_eq!(failure.value.contexts(), ["updates:md5"]);
+        }
+    }
+
+    mod is_listed {
+        use super::*;
+
+        #[test]
+        fn success_when_case_differs() {
+            let list = parse_delete_list("Images\\Surface9.PNG\r\nold\r\n").unwrap();
+            assert!(is_listed(&list, "images/surface9.png"));
+            assert!(is_listed(&list, "OLD\\surface0.png"));
+            assert!(!is_listed(&list, "images/surface90.png"));
+            assert!(!is_listed(&list, "older/surface0.png"));
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! URLs of files for network updates, `homeurl` joined with records of `updates2.dau`.
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+use crate::{
+    ascii::starts_with_ignore_case,
+    charset::{Charset, CodecError, Decodable},
+    path::{PathError, RelativePath},
+};
+
+use super::UpdateRecord;
+
+/// Base URL of network updates, the value of `homeurl` in `descript.txt`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct HomeUrl(String);
+
+/// Reason a URL is not resolved by [`resolve_url`].
+///
+/// [`resolve_url`]: crate::updates::resolve_url
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ResolveError {
+    /// `homeurl` is not an `http` or `https` URL with a host.
+    InvalidBase(String),
+    /// The path of the record points outside the base URL.
+    Path(PathError),
+    /// A character of the path can not be encoded in the charset of the file list.
+    Unmappable(char),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::InvalidBase(url) => write!(f, "invalid homeurl: {}", url),
+            ResolveError::Path(e) => write!(f, "{}", e),
+            ResolveError::Unmappable(c) => write!(f, "{:?} cannot be encoded", c),
+        }
+    }
+}
+
+impl core::error::Error for ResolveError {}
+
+impl From<PathError> for ResolveError {
+    fn from(value: PathError) -> Self {
+        ResolveError::Path(value)
+    }
+}
+
+impl HomeUrl {
+    /// Reads `url`, trimmed and with `/` appended when it does not end with one.
+    ///
+    /// The scheme is compared ignoring ASCII case. Query and fragment are not allowed.
+    pub fn parse(url: &str) -> Result<HomeUrl, ResolveError> {
+        let url = url.trim();
+        let after_scheme = ["http://", "https://"]
+            .iter()
+            .find(|scheme| starts_with_ignore_case(url, scheme))
+            .map(|scheme| &url[scheme.len()..]);
+        let valid = after_scheme.is_some_and(|rest| {
+            !rest.starts_with('/')
+                && !rest.is_empty()
+                && !rest.contains(['?', '#', '\\', ' ', '\t'])
+        });
+        if !valid {
+            return Err(ResolveError::InvalidBase(String::from(url)));
+        }
+
+        let mut url = String::from(url);
+        if !url.ends_with('/') {
+            url.push('/');
+        }
+        Ok(HomeUrl(url))
+    }
+
+    /// Returns the URL, ending with `/`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Returns the URL of `record` under `base`.
+///
+/// The path is checked as [`RelativePath::parse`] does, rejecting `..` and absolute paths,
+/// and each byte of it encoded in `charset`, the charset of the file list, is percent-encoded
+/// except unreserved characters and `/`.
+///
+/// [`RelativePath::parse`]: crate::path::RelativePath::parse
+pub fn resolve_url(
+    base: &HomeUrl,
+    record: &UpdateRecord,
+    charset: Charset,
+) -> Result<String, ResolveError> {
+    let path = RelativePath::parse(record.path.as_str())?;
+    let bytes = charset.encode_text(path.as_str()).map_err(|e| match e {
+        CodecError::Unmappable(c) => ResolveError::Unmappable(c),
+        CodecError::Malformed { .. } | CodecError::BomMismatch(_) => {
+            unreachable!("encoding does not decode")
+        }
+    })?;
+
+    let mut url = String::from(base.as_str());
+    for byte in bytes.iter() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(byte) {
+            url.push(char::from(*byte));
+        } else {
+            let _ = write!(url, "%{:02X}", byte);
+        }
+    }
+    Ok(url)
+}
+
+/// Same as [`resolve_url`] for all `records`, or all the errors with the index of each record.
+///
+/// [`resolve_url`]: crate::updates::resolve_url
+pub fn resolve_urls(
+    base: &HomeUrl,
+    records: &[UpdateRecord],
+    charset: Charset,
+) -> Result<Vec<String>, Vec<(usize, ResolveError)>> {
+    let mut urls = Vec::new();
+    let mut errors = Vec::new();
+    for (index, record) in records.iter().enumerate() {
+        match resolve_url(base, record, charset.clone()) {
+            Ok(url) => urls.push(url),
+            Err(e) => errors.push((index, e)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(urls)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    fn record(path: &str) -> UpdateRecord {
+        UpdateRecord {
+            path: RelativePath::new(path),
+            md5: String::from("0123456789abcdef0123456789abcdef"),
+        }
+    }
+
+    fn base() -> HomeUrl {
+        HomeUrl::parse("http://example.com/shell/master/").unwrap()
+    }
+
+    mod resolve_url {
+        use super::*;
+
+        #[test]
+        fn success_when_ascii_filename() {
+            assert_eq!(
+                resolve_url(
+                    &base(),
+                    &record("images\\surface0 a.png"),
+                    Charset::ShiftJIS
+                ),
+                Ok(String::from(
+                    "http://example.com/shell/master/images/surface0%20a.png"
+                ))
+            );
+        }
+
+        #[test]
+        fn success_when_shift_jis_filename() {
+            // `表` is `0x95 0x5C` in Shift_JIS, whose second byte is `\` in ASCII.
+            assert_eq!(
+                resolve_url(&base(), &record("表情.png"), Charset::ShiftJIS),
+                Ok(String::from(
+                    "http://example.com/shell/master/%95%5C%8F%EE.png"
+                ))
+            );
+            assert_eq!(
+                resolve_url(&base(), &record("表情.png"), Charset::UTF8),
+                Ok(String::from(
+                    "http://example.com/shell/master/%E8%A1%A8%E6%83%85.png"
+                ))
+            );
+            assert_eq!(
+                resolve_url(&base(), &record("表.png"), Charset::ASCII),
+                Err(ResolveError::Unmappable('表'))
+            );
+        }
+
+        #[test]
+        fn success_when_base_without_trailing_slash() {
+            let base = HomeUrl::parse(" HTTPS://example.com/shell ").unwrap();
+            assert_eq!(base.as_str(), "HTTPS://example.com/shell/");
+            assert_eq!(
+                resolve_url(&base, &record("a.png"), Charset::UTF8),
+                Ok(String::from("HTTPS://example.com/shell/a.png"))
+            );
+            for case in [
+                "example.com/shell",
+                "ftp://example.com/",
+                "http://",
+                "http://a/?x",
+            ] {
+                assert!(HomeUrl::parse(case).is_err(), "{}", case);
+            }
+        }
+
+        #[test]
+        fn failed_when_escaping_base() {
+            assert_eq!(
+                resolve_url(&base(), &record("images/../../a.png"), Charset::UTF8),
+                Err(ResolveError::Path(PathError::Traversal))
+            );
+            assert_eq!(
+                resolve_url(&base(), &record("/a.png"), Charset::UTF8),
+                Err(ResolveError::Path(PathError::Absolute))
+            );
+        }
+    }
+
+    mod resolve_urls {
+        use super::*;
+
+        #[test]
+        fn failed_when_any_record_rejected() {
+            let records = [record("a.png"), record("../b.png"), record("c.png")];
+            assert_eq!(
+                resolve_urls(&base(), &records, Charset::UTF8),
+                Err(vec![(1, ResolveError::Path(PathError::Traversal))])
+            );
+            assert_eq!(
+                resolve_urls(&base(), &records[..1], Charset::UTF8),
+                Ok(vec![String::from("http://example.com/shell/master/a.png")])
+            );
+        }
+    }
+}
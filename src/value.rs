@@ -0,0 +1,856 @@
+//! # `value`
+//!
+//! Parsers for primitive values used in shell settings.
+//! Numbers, coordinates, rects, colors and filenames.
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::value::{parse_rect, parse_rgb, Rect, Rgb};
+//!
+//! let (_remain, rect) = parse_rect("10,20,110,220\r\n").unwrap();
+//! assert_eq!(rect, Rect::new(10, 20, 110, 220));
+//!
+//! let (_remain, rgb) = parse_rgb("255,128,0\r\n").unwrap();
+//! assert_eq!(rgb, Rgb::new(255, 128, 0));
+//! ```
+
+#[cfg(feature = "kv")]
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(feature = "kv")]
+use core::fmt;
+
+#[cfg(feature = "kv")]
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag},
+    character::complete::digit1,
+    combinator::{all_consuming, map, map_res, opt, recognize, value, verify},
+    sequence::{pair, preceded, tuple},
+};
+use nom::{character::complete::char, IResult};
+
+#[cfg(feature = "kv")]
+use crate::span::{in_source, spanned, Spanned};
+use crate::ShellParseError;
+
+#[cfg(all(feature = "std", feature = "kv"))]
+mod filename;
+#[cfg(feature = "kv")]
+mod module;
+mod numeric;
+
+#[cfg(all(feature = "std", feature = "kv"))]
+pub use filename::{filename_matches, resolve_files};
+#[cfg(feature = "kv")]
+pub use module::{parse_module_filename, parse_module_filename_lenient, ModuleFilenameIssue};
+pub use numeric::NumericPolicy;
+#[cfg(feature = "kv")]
+pub(crate) use numeric::NumericReader;
+#[cfg(feature = "kv")]
+pub use numeric::{
+    parse_coordinate_with_policy, parse_decimal_with_policy, parse_i32_with_policy,
+    parse_id_list_with_policy, parse_rect_with_policy, parse_u32_with_policy, Checked, Overflow,
+};
+
+/// Characters which can not be used in filenames.
+#[cfg(feature = "kv")]
+const INVALID_FILENAME_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Point in the image coordinate system.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Coordinate {
+    pub x: i32,
+    pub y: i32,
+}
+
+#[cfg(feature = "kv")]
+impl Coordinate {
+    pub fn new(x: i32, y: i32) -> Coordinate {
+        Coordinate { x, y }
+    }
+}
+
+/// Rectangle written as `left,top,right,bottom`.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+#[cfg(feature = "kv")]
+impl Rect {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Rect {
+        Rect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+}
+
+/// Color written as `r,g,b`.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+#[cfg(feature = "kv")]
+impl Rgb {
+    pub fn new(r: u8, g: u8, b: u8) -> Rgb {
+        Rgb { r, g, b }
+    }
+}
+
+/// Color assembled from per-component keys, such as `font.color.r`.
+///
+/// Each component is `None` until given.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct ColorAccumulator {
+    pub r: Option<u8>,
+    pub g: Option<u8>,
+    pub b: Option<u8>,
+}
+
+#[cfg(feature = "kv")]
+impl ColorAccumulator {
+    /// Applies one entry. `component_segments` is the key after the color name:
+    /// `["r"]`, `["g"]`, `["b"]`, or `[]` for the comma form `r,g,b`.
+    ///
+    /// Returns `Ok(false)` when the key is not a color component.
+    pub fn apply<'a>(
+        &mut self,
+        component_segments: &[&str],
+        value: &'a str,
+    ) -> Result<bool, nom::Err<ShellParseError<'a>>> {
+        let field = match component_segments {
+            [] => {
+                let (_, rgb) = all_consuming(parse_rgb)(value)?;
+                *self = ColorAccumulator::from(rgb);
+                return Ok(true);
+            }
+            ["r"] => &mut self.r,
+            ["g"] => &mut self.g,
+            ["b"] => &mut self.b,
+            _ => return Ok(false),
+        };
+
+        *field = Some(all_consuming(parse_u8)(value)?.1);
+        Ok(true)
+    }
+
+    /// Returns the color when all components are given.
+    pub fn color(&self) -> Option<Rgb> {
+        Some(Rgb::new(self.r?, self.g?, self.b?))
+    }
+
+    /// Checks whether only some of the components are given.
+    pub fn is_partial(&self) -> bool {
+        let given = [self.r, self.g, self.b]
+            .iter()
+            .filter(|c| c.is_some())
+            .count();
+        given != 0 && given != 3
+    }
+}
+
+#[cfg(feature = "kv")]
+impl From<Rgb> for ColorAccumulator {
+    fn from(value: Rgb) -> Self {
+        ColorAccumulator {
+            r: Some(value.r),
+            g: Some(value.g),
+            b: Some(value.b),
+        }
+    }
+}
+
+/// List of IDs written as `1,3,5`.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdList(pub Vec<u32>);
+
+/// Value interpreted without knowing the key.
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Clone)]
+pub enum ShellValue {
+    Int(i64),
+    Decimal(f64),
+    Bool(bool),
+    Color(Rgb),
+    Coordinate(Coordinate),
+    Rect(Rect),
+    IdList(IdList),
+    Text(String),
+}
+
+#[cfg(feature = "kv")]
+impl ShellValue {
+    /// Infers the type of `raw`, trying in this order:
+    ///
+    /// 1. `Int`, so `1` and `0` are numbers, not booleans.
+    /// 2. `Decimal`, such as `-0.5`.
+    /// 3. `Bool`, only `true` and `false`.
+    /// 4. `Coordinate`, two integers, so `0,0` is not an `IdList`.
+    /// 5. `Color`, three integers in `0..=255`.
+    /// 6. `Rect`, four integers.
+    /// 7. `IdList`, other lists of non-negative integers, such as `0,300,2`.
+    /// 8. `Text`, anything else.
+    pub fn infer(raw: &str) -> ShellValue {
+        if let Ok((_, v)) = all_consuming(parse_i64)(raw) {
+            ShellValue::Int(v)
+        } else if let Ok((_, v)) = all_consuming(parse_decimal)(raw) {
+            ShellValue::Decimal(v)
+        } else if let Some(v) = ["false", "true"].iter().position(|w| *w == raw) {
+            ShellValue::Bool(v == 1)
+        } else if let Ok((_, v)) = all_consuming(parse_coordinate)(raw) {
+            ShellValue::Coordinate(v)
+        } else if let Ok((_, v)) = all_consuming(parse_rgb)(raw) {
+            ShellValue::Color(v)
+        } else if let Ok((_, v)) = all_consuming(parse_rect)(raw) {
+            ShellValue::Rect(v)
+        } else if let Ok((_, v)) = all_consuming(parse_id_list)(raw) {
+            ShellValue::IdList(v)
+        } else {
+            ShellValue::Text(raw.to_string())
+        }
+    }
+}
+
+#[cfg(feature = "kv")]
+impl fmt::Display for Coordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+#[cfg(feature = "kv")]
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{}",
+            self.left, self.top, self.right, self.bottom
+        )
+    }
+}
+
+#[cfg(feature = "kv")]
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.r, self.g, self.b)
+    }
+}
+
+/// Written as [`parse_id_list`] reads it.
+///
+/// An empty list is written as the empty string, which is not a list, since lists in shell settings have an ID at least.
+///
+/// [`parse_id_list`]: crate::value::parse_id_list
+#[cfg(feature = "kv")]
+impl fmt::Display for IdList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, id) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Canonical form, which [`ShellValue::infer`] reads back as the same value.
+///
+/// The variant is kept unless an earlier variant in the order of [`ShellValue::infer`] reads the form,
+/// such as `IdList` of two IDs read as `Coordinate` and `Text` of `1` read as `Int`.
+/// Forms of inferred values are read back as the same value, so writing once normalizes them,
+/// dropping leading zeros, the sign of zero and trailing zeros of fractions.
+///
+/// [`ShellValue::infer`]: crate::value::ShellValue::infer
+#[cfg(feature = "kv")]
+impl fmt::Display for ShellValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShellValue::Int(v) => write!(f, "{}", v),
+            // Adding `0.0` drops the sign of `-0.0`.
+            ShellValue::Decimal(v) if v % 1.0 == 0.0 => write!(f, "{:.1}", v + 0.0),
+            ShellValue::Decimal(v) => write!(f, "{}", v),
+            ShellValue::Bool(v) => write!(f, "{}", v),
+            ShellValue::Color(v) => write!(f, "{}", v),
+            ShellValue::Coordinate(v) => write!(f, "{}", v),
+            ShellValue::Rect(v) => write!(f, "{}", v),
+            ShellValue::IdList(v) => write!(f, "{}", v),
+            ShellValue::Text(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+/// parser for the separator between fields.
+pub(crate) fn separator(input: &str) -> IResult<&str, char, ShellParseError<'_>> {
+    char(',')(input)
+}
+
+/// parser for unsigned decimal number.
+#[cfg(feature = "kv")]
+pub fn parse_u32(input: &str) -> IResult<&str, u32, ShellParseError<'_>> {
+    map_res(digit1, |s: &str| s.parse::<u32>())(input)
+}
+
+/// parser for signed decimal number.
+#[cfg(feature = "kv")]
+pub fn parse_i32(input: &str) -> IResult<&str, i32, ShellParseError<'_>> {
+    map_res(recognize(pair(opt(char('-')), digit1)), |s: &str| {
+        s.parse::<i32>()
+    })(input)
+}
+
+/// parser for [`Coordinate`] written as `x,y`.
+///
+/// [`Coordinate`]: crate::value::Coordinate
+#[cfg(feature = "kv")]
+pub fn parse_coordinate(input: &str) -> IResult<&str, Coordinate, ShellParseError<'_>> {
+    map(
+        tuple((parse_i32, preceded(separator, parse_i32))),
+        |(x, y)| Coordinate::new(x, y),
+    )(input)
+}
+
+/// parser for [`Rect`] written as `left,top,right,bottom`.
+///
+/// [`Rect`]: crate::value::Rect
+#[cfg(feature = "kv")]
+pub fn parse_rect(input: &str) -> IResult<&str, Rect, ShellParseError<'_>> {
+    map(
+        tuple((
+            parse_i32,
+            preceded(separator, parse_i32),
+            preceded(separator, parse_i32),
+            preceded(separator, parse_i32),
+        )),
+        |(left, top, right, bottom)| Rect::new(left, top, right, bottom),
+    )(input)
+}
+
+/// [`Coordinate`] with the span of each field.
+///
+/// [`Coordinate`]: crate::value::Coordinate
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CoordinateSpans {
+    pub x: Spanned<i32>,
+    pub y: Spanned<i32>,
+}
+
+#[cfg(feature = "kv")]
+impl CoordinateSpans {
+    pub fn value(&self) -> Coordinate {
+        Coordinate::new(self.x.value, self.y.value)
+    }
+}
+
+/// [`Rect`] with the span of each field.
+///
+/// [`Rect`]: crate::value::Rect
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RectSpans {
+    pub left: Spanned<i32>,
+    pub top: Spanned<i32>,
+    pub right: Spanned<i32>,
+    pub bottom: Spanned<i32>,
+}
+
+#[cfg(feature = "kv")]
+impl RectSpans {
+    pub fn value(&self) -> Rect {
+        Rect::new(
+            self.left.value,
+            self.top.value,
+            self.right.value,
+            self.bottom.value,
+        )
+    }
+}
+
+/// Same as [`parse_coordinate`], with spans in `source`, of which `input` is a subslice.
+///
+/// [`parse_coordinate`]: crate::value::parse_coordinate
+#[cfg(feature = "kv")]
+pub fn parse_coordinate_spanned<'a>(
+    input: &'a str,
+    source: &'a str,
+) -> IResult<&'a str, Spanned<CoordinateSpans>, ShellParseError<'a>> {
+    let (input, _) = in_source(input, source)?;
+    spanned(
+        source,
+        map(
+            tuple((
+                spanned(source, parse_i32),
+                preceded(separator, spanned(source, parse_i32)),
+            )),
+            |(x, y)| CoordinateSpans { x, y },
+        ),
+    )(input)
+}
+
+/// Same as [`parse_rect`], with spans in `source`, of which `input` is a subslice.
+///
+/// [`parse_rect`]: crate::value::parse_rect
+#[cfg(feature = "kv")]
+pub fn parse_rect_spanned<'a>(
+    input: &'a str,
+    source: &'a str,
+) -> IResult<&'a str, Spanned<RectSpans>, ShellParseError<'a>> {
+    let (input, _) = in_source(input, source)?;
+    spanned(
+        source,
+        map(
+            tuple((
+                spanned(source, parse_i32),
+                preceded(separator, spanned(source, parse_i32)),
+                preceded(separator, spanned(source, parse_i32)),
+                preceded(separator, spanned(source, parse_i32)),
+            )),
+            |(left, top, right, bottom)| RectSpans {
+                left,
+                top,
+                right,
+                bottom,
+            },
+        ),
+    )(input)
+}
+
+/// parser for [`Rgb`] written as `r,g,b`.
+///
+/// [`Rgb`]: crate::value::Rgb
+#[cfg(feature = "kv")]
+pub fn parse_rgb(input: &str) -> IResult<&str, Rgb, ShellParseError<'_>> {
+    map(
+        tuple((
+            parse_u8,
+            preceded(separator, parse_u8),
+            preceded(separator, parse_u8),
+        )),
+        |(r, g, b)| Rgb::new(r, g, b),
+    )(input)
+}
+
+/// parser for boolean written as `0`/`1` or `false`/`true`.
+#[cfg(feature = "kv")]
+pub fn parse_bool(input: &str) -> IResult<&str, bool, ShellParseError<'_>> {
+    alt((
+        value(true, tag("1")),
+        value(false, tag("0")),
+        value(true, tag("true")),
+        value(false, tag("false")),
+    ))(input)
+}
+
+/// parser for filename. It reads until the next separator or line ending.
+#[cfg(feature = "kv")]
+pub fn parse_filename(input: &str) -> IResult<&str, &str, ShellParseError<'_>> {
+    verify(is_not(",\r\n"), |s: &str| {
+        !s.contains(INVALID_FILENAME_CHARS)
+    })(input)
+}
+
+/// parser for one of the tags of `table`, giving its value.
+///
+/// The longest matching tag is taken, so a tag is not cut short by another tag it starts with,
+/// and the order of `table` does not matter. Of tags given twice, the first one is taken.
+/// On failure, all tags are kept in [`ShellParseError::expected`], and [`ParseFailure::expected`] returns them.
+///
+/// [`ShellParseError::expected`]: crate::ShellParseError::expected
+/// [`ParseFailure::expected`]: crate::error::ParseFailure::expected
+pub fn tag_table<T: Clone>(
+    table: &'static [(&'static str, T)],
+) -> impl Fn(&str) -> IResult<&str, T, ShellParseError<'_>> {
+    move |input: &str| match longest_tag(input, table.iter().map(|(tag, value)| (*tag, value))) {
+        Some((tag, value)) => Ok((&input[tag.len()..], value.clone())),
+        None => Err(nom::Err::Error(ShellParseError::expecting(
+            input,
+            table.iter().map(|(tag, _)| *tag).collect(),
+        ))),
+    }
+}
+
+/// Returns the longest of `tags` starting `input`, with its value.
+/// Of matching tags of the same length, the first one is taken.
+pub(crate) fn longest_tag<'t, T>(
+    input: &str,
+    tags: impl IntoIterator<Item = (&'t str, T)>,
+) -> Option<(&'t str, T)> {
+    tags.into_iter()
+        .filter(|(tag, _)| input.starts_with(tag))
+        .fold(None, |longest, (tag, value)| match longest {
+            Some((taken, _)) if taken.len() >= tag.len() => longest,
+            _ => Some((tag, value)),
+        })
+}
+
+/// parser for [`IdList`] written as `1,3,5`.
+///
+/// Lists of more IDs than [`Limits::DEFAULT`] allows fail with context `id_list:limit`.
+///
+/// [`IdList`]: crate::value::IdList
+/// [`Limits::DEFAULT`]: crate::options::Limits::DEFAULT
+#[cfg(feature = "kv")]
+pub fn parse_id_list(input: &str) -> IResult<&str, IdList, ShellParseError<'_>> {
+    NumericReader::new(NumericPolicy::DEFAULT).id_list(input)
+}
+
+#[cfg(feature = "kv")]
+fn parse_i64(input: &str) -> IResult<&str, i64, ShellParseError<'_>> {
+    map_res(recognize(pair(opt(char('-')), digit1)), |s: &str| {
+        s.parse::<i64>()
+    })(input)
+}
+
+/// parser for decimal number with fraction, such as `-0.5`. Exponents are not accepted.
+#[cfg(feature = "kv")]
+fn parse_decimal(input: &str) -> IResult<&str, f64, ShellParseError<'_>> {
+    map_res(
+        recognize(tuple((opt(char('-')), digit1, char('.'), digit1))),
+        |s: &str| s.parse::<f64>(),
+    )(input)
+}
+
+#[cfg(feature = "kv")]
+fn parse_u8(input: &str) -> IResult<&str, u8, ShellParseError<'_>> {
+    map_res(digit1, |s: &str| s.parse::<u8>())(input)
+}
+
+/// Maps full-width forms of ASCII characters, such as `１` and `，`, and the ideographic space to half-width.
+///
+/// Returns `Cow::Owned` only when some character is mapped, which tells normalization happened.
+#[cfg(feature = "kv")]
+pub fn normalize_fullwidth(input: &str) -> Cow<'_, str> {
+    if !input.chars().any(|c| halfwidth_of(c).is_some()) {
+        return Cow::Borrowed(input);
+    }
+
+    Cow::Owned(
+        input
+            .chars()
+            .map(|c| halfwidth_of(c).unwrap_or(c))
+            .collect(),
+    )
+}
+
+/// Same as [`normalize_fullwidth`], but only in fields which are numbers after normalization.
+///
+/// A full-width comma is mapped only between two such fields, so names and filenames are kept as they are.
+///
+/// [`normalize_fullwidth`]: crate::value::normalize_fullwidth
+#[cfg(feature = "surfaces")]
+pub(crate) fn normalize_numeric_fields(input: &str) -> Cow<'_, str> {
+    let fields: Vec<_> = input
+        .split([',', '，'])
+        .map(|field| {
+            let normalized = normalize_fullwidth(field);
+            let is_number = !normalized.trim().is_empty()
+                && normalized
+                    .trim()
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '-' || c == '+' || c == '.');
+            (field, normalized, is_number)
+        })
+        .collect();
+    let separators: Vec<_> = input.matches([',', '，']).collect();
+
+    let mut result = String::with_capacity(input.len());
+    for (i, (field, normalized, is_number)) in fields.iter().enumerate() {
+        result.push_str(if *is_number { normalized } else { field });
+        if let Some(separator) = separators.get(i) {
+            let between_numbers = *is_number && fields.get(i + 1).is_some_and(|f| f.2);
+            result.push_str(if between_numbers { "," } else { separator });
+        }
+    }
+
+    if result == input {
+        Cow::Borrowed(input)
+    } else {
+        Cow::Owned(result)
+    }
+}
+
+#[cfg(feature = "kv")]
+fn halfwidth_of(c: char) -> Option<char> {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0),
+        '\u{3000}' => Some(' '),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "kv"))]
+mod tests {
+    use super::*;
+
+    mod parse_i32 {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "-120,";
+            let (remain, result) = parse_i32(case).unwrap();
+            assert_eq!(remain, ",");
+            assert_eq!(result, -120);
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            let case = "-x";
+            assert!(parse_i32(case).is_err());
+        }
+    }
+
+    mod parse_rect {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "0,-10,100,200\r\n";
+            let (remain, result) = parse_rect(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, Rect::new(0, -10, 100, 200));
+        }
+
+        #[test]
+        fn failed_when_too_few_fields() {
+            let case = "0,10,100\r\n";
+            assert!(parse_rect(case).is_err());
+        }
+    }
+
+    mod parse_rect_spanned {
+        use super::*;
+
+        #[test]
+        fn success_when_subslice() {
+            let source = "collision0,0,-10,100,200\r\n";
+            let (remain, result) = parse_rect_spanned(&source[11..], source).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.span, 11..24);
+            assert_eq!(result.value.value(), Rect::new(0, -10, 100, 200));
+            assert_eq!(result.value.left, Spanned::new(0, 11..12));
+            assert_eq!(result.value.top, Spanned::new(-10, 13..16));
+            assert_eq!(result.value.right, Spanned::new(100, 17..20));
+            assert_eq!(result.value.bottom, Spanned::new(200, 21..24));
+            assert!(parse_rect_spanned(&source[11..20], source).is_err());
+        }
+
+        #[test]
+        fn failed_when_not_subslice() {
+            let source = "collision0,0,-10,100,200\r\n";
+            let input = alloc::string::String::from(&source[11..]);
+            let err = parse_rect_spanned(&input, source).unwrap_err();
+            assert_eq!(
+                crate::error::ParseFailure::from(err).contexts(),
+                &["span:source"]
+            );
+
+            let err = parse_rect_spanned(source, &source[11..]).unwrap_err();
+            assert_eq!(
+                crate::error::ParseFailure::from(err).contexts(),
+                &["span:source"]
+            );
+        }
+    }
+
+    mod parse_id_list {
+        use super::*;
+
+        use crate::options::Limits;
+
+        #[test]
+        fn success_when_valid_str() {
+            let (remain, result) = parse_id_list("1,3,5,]").unwrap();
+            assert_eq!(remain, ",]");
+            assert_eq!(result, IdList(alloc::vec![1, 3, 5]));
+        }
+
+        #[test]
+        fn failed_when_over_limit() {
+            let max = Limits::DEFAULT.max_id_set;
+            let case = "0,".repeat(max) + "0";
+            let err = parse_id_list(&case).unwrap_err();
+            assert_eq!(
+                crate::error::ParseFailure::from(err).contexts(),
+                &["id_list:limit"]
+            );
+            assert_eq!(parse_id_list(&case[2..]).unwrap().1 .0.len(), max);
+        }
+    }
+
+    mod parse_rgb {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "0,128,255\r\n";
+            let (remain, result) = parse_rgb(case).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, Rgb::new(0, 128, 255));
+        }
+
+        #[test]
+        fn failed_when_out_of_range() {
+            let case = "0,128,256";
+            assert!(parse_rgb(case).is_err());
+        }
+    }
+
+    mod color_accumulator {
+        use super::*;
+
+        #[test]
+        fn success_when_components() {
+            let mut color = ColorAccumulator::default();
+            assert!(color.apply(&["r"], "10").unwrap());
+            assert!(color.is_partial());
+            assert_eq!(color.color(), None);
+            assert!(color.apply(&["g"], "20").unwrap());
+            assert!(color.apply(&["b"], "30").unwrap());
+            assert!(!color.is_partial());
+            assert_eq!(color.color(), Some(Rgb::new(10, 20, 30)));
+        }
+
+        #[test]
+        fn success_when_comma_form() {
+            let mut color = ColorAccumulator::default();
+            assert!(color.apply(&[], "1,2,3").unwrap());
+            assert_eq!(color.color(), Some(Rgb::new(1, 2, 3)));
+            assert!(!color.apply(&["a"], "1").unwrap());
+            assert!(color.apply(&["r"], "256").is_err());
+        }
+    }
+
+    mod parse_bool {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            assert_eq!(parse_bool("1").unwrap(), ("", true));
+            assert_eq!(parse_bool("0").unwrap(), ("", false));
+            assert_eq!(parse_bool("true").unwrap(), ("", true));
+            assert_eq!(parse_bool("false").unwrap(), ("", false));
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            assert!(parse_bool("yes").is_err());
+        }
+    }
+
+    mod parse_filename {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_str() {
+            let case = "images\\region.png,0";
+            let (remain, result) = parse_filename(case).unwrap();
+            assert_eq!(remain, ",0");
+            assert_eq!(result, "images\\region.png");
+        }
+
+        #[test]
+        fn failed_when_invalid_str() {
+            let case = "region?.png";
+            assert!(parse_filename(case).is_err());
+        }
+    }
+
+    mod shell_value {
+        use super::*;
+
+        #[test]
+        fn infer_in_priority_order() {
+            let cases = [
+                ("1", ShellValue::Int(1)),
+                ("0", ShellValue::Int(0)),
+                ("-20", ShellValue::Int(-20)),
+                ("4294967296", ShellValue::Int(4294967296)),
+                ("0.5", ShellValue::Decimal(0.5)),
+                ("-1.25", ShellValue::Decimal(-1.25)),
+                ("true", ShellValue::Bool(true)),
+                ("false", ShellValue::Bool(false)),
+                ("0,0", ShellValue::Coordinate(Coordinate::new(0, 0))),
+                ("-5,10", ShellValue::Coordinate(Coordinate::new(-5, 10))),
+                ("255,128,0", ShellValue::Color(Rgb::new(255, 128, 0))),
+                ("0,0,640,480", ShellValue::Rect(Rect::new(0, 0, 640, 480))),
+                ("0,300,2", ShellValue::IdList(IdList(vec![0, 300, 2]))),
+                ("1,2,3,4,5", ShellValue::IdList(IdList(vec![1, 2, 3, 4, 5]))),
+                ("-1,2,3", ShellValue::Text("-1,2,3".to_string())),
+                ("1e5", ShellValue::Text("1e5".to_string())),
+                ("TRUE", ShellValue::Text("TRUE".to_string())),
+                ("surface0.png", ShellValue::Text("surface0.png".to_string())),
+                ("", ShellValue::Text(String::new())),
+            ];
+            for (raw, expected) in cases {
+                assert_eq!(ShellValue::infer(raw), expected, "{:?}", raw);
+            }
+        }
+
+        #[test]
+        fn display_is_read_back() {
+            for raw in [
+                "-20", "0.5", "2.0", "true", "0,0", "1,2,3", "0,0,1,1", "0,300,2", "a b",
+            ] {
+                let value = ShellValue::infer(raw);
+                assert_eq!(value.to_string(), raw);
+                assert_eq!(ShellValue::infer(&value.to_string()), value);
+            }
+            assert_eq!(ShellValue::Decimal(3.0).to_string(), "3.0");
+        }
+    }
+    mod normalize_fullwidth {
+        use super::*;
+
+        #[test]
+        fn success_when_fullwidth() {
+            assert_eq!(
+                normalize_fullwidth("１２３，－４　ａ"),
+                Cow::<str>::Owned("123,-4 a".to_string())
+            );
+            assert!(matches!(
+                normalize_fullwidth("123,表面"),
+                Cow::Borrowed("123,表面")
+            ));
+        }
+
+        #[test]
+        #[cfg(feature = "surfaces")]
+        fn success_when_numeric_fields() {
+            assert_eq!(normalize_numeric_fields("０，０，１０，１０"), "0,0,10,10");
+            assert_eq!(
+                normalize_numeric_fields("base,表面１.png,１０,0"),
+                "base,表面１.png,10,0"
+            );
+            assert_eq!(normalize_numeric_fields("Ｈｅａｄ，１,2"), "Ｈｅａｄ，1,2");
+            assert!(matches!(
+                normalize_numeric_fields("0,0,a"),
+                Cow::Borrowed(_)
+            ));
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Matching of filenames in settings against names on disk.
+
+use alloc::vec::Vec;
+use std::ffi::{OsStr, OsString};
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+
+/// Checks whether `parsed`, a filename read from settings, names `on_disk`, a name in a directory listing.
+///
+/// ASCII letters are compared ignoring case, as Windows does, and `\` and `/` are the same separator.
+/// With feature `unicode`, both are compared in Unicode NFC, so that names decomposed by archivers
+/// or file systems match. Names on disk which are not UTF-8 never match.
+pub fn filename_matches(parsed: &str, on_disk: &OsStr) -> bool {
+    let Some(on_disk) = on_disk.to_str() else {
+        return false;
+    };
+    folded(parsed).eq(folded(on_disk))
+}
+
+/// Returns the index in `dir_entries` of the first name matching each of `parsed`,
+/// or `None` for missing files, as [`filename_matches`] does.
+///
+/// [`filename_matches`]: crate::value::filename_matches
+pub fn resolve_files(parsed: &[&str], dir_entries: &[OsString]) -> Vec<Option<usize>> {
+    parsed
+        .iter()
+        .map(|name| {
+            dir_entries
+                .iter()
+                .position(|entry| filename_matches(name, entry))
+        })
+        .collect()
+}
+
+fn folded(name: &str) -> impl Iterator<Item = char> + '_ {
+    #[cfg(feature = "unicode")]
+    let chars = name.nfc();
+    #[cfg(not(feature = "unicode"))]
+    let chars = name.chars();
+    chars.map(|c| match c {
+        '\\' => '/',
+        c => c.to_ascii_lowercase(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod filename_matches {
+        use super::*;
+
+        #[test]
+        fn success_when_case_differs() {
+            assert!(filename_matches("Surface0.PNG", OsStr::new("surface0.png")));
+            assert!(!filename_matches(
+                "surface0.png",
+                OsStr::new("surface1.png")
+            ));
+            // Only ASCII letters are folded.
+            assert!(!filename_matches("Ａ.png", OsStr::new("ａ.png")));
+        }
+
+        #[test]
+        fn success_when_separator_differs() {
+            assert!(filename_matches(
+                "images\\Face.png",
+                OsStr::new("images/face.png")
+            ));
+            assert!(!filename_matches("images.png", OsStr::new("images/.png")));
+        }
+
+        #[cfg(feature = "unicode")]
+        #[test]
+        fn success_when_normalization_differs() {
+            // `が` precomposed, and `か` followed by a combining voiced sound mark.
+            assert!(filename_matches(
+                "\u{304c}.png",
+                OsStr::new("\u{304b}\u{3099}.png")
+            ));
+        }
+    }
+
+    mod resolve_files {
+        use super::*;
+
+        #[test]
+        fn success_when_some_missing() {
+            let entries = [
+                OsString::from("surface0.png"),
+                OsString::from("IMAGES/Face.png"),
+            ];
+            assert_eq!(
+                resolve_files(
+                    &["images\\face.png", "surface1.png", "Surface0.png"],
+                    &entries
+                ),
+                vec![Some(1), None, Some(0)]
+            );
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Filenames of modules, such as `yaya.dll` of `shiori`, stricter than other filenames.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use nom::{
+    bytes::complete::is_not,
+    error::{ContextError, ErrorKind, ParseError},
+    IResult,
+};
+
+use crate::{ascii::eq_ignore_case, ShellParseError};
+
+use super::parse_filename;
+
+/// Rule of module filenames broken by a name, found by [`parse_module_filename_lenient`].
+///
+/// [`parse_module_filename_lenient`]: crate::value::parse_module_filename_lenient
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ModuleFilenameIssue {
+    /// The name has a directory, such as `bin/yaya.dll`. Context `module:path`.
+    Path,
+    /// The name has a character other than ASCII. Context `module:non_ascii`.
+    NonAscii,
+    /// The extension is not one of the allowed ones. Context `module:extension`.
+    Extension,
+}
+
+impl ModuleFilenameIssue {
+    /// Returns the context of errors of [`parse_module_filename`] for the issue.
+    ///
+    /// [`parse_module_filename`]: crate::value::parse_module_filename
+    pub fn context(&self) -> &'static str {
+        match self {
+            ModuleFilenameIssue::Path => "module:path",
+            ModuleFilenameIssue::NonAscii => "module:non_ascii",
+            ModuleFilenameIssue::Extension => "module:extension",
+        }
+    }
+}
+
+impl fmt::Display for ModuleFilenameIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModuleFilenameIssue::Path => write!(f, "module filename with a directory"),
+            ModuleFilenameIssue::NonAscii => write!(f, "module filename with non-ASCII characters"),
+            ModuleFilenameIssue::Extension => write!(f, "module filename with another extension"),
+        }
+    }
+}
+
+/// Returns the issues of `name` in the order of [`ModuleFilenameIssue`].
+///
+/// [`ModuleFilenameIssue`]: crate::value::ModuleFilenameIssue
+fn module_filename_issues(name: &str, allowed_exts: &[&str]) -> Vec<ModuleFilenameIssue> {
+    let mut issues = Vec::new();
+    if name.contains(['/', '\\']) {
+        issues.push(ModuleFilenameIssue::Path);
+    }
+    if !name.is_ascii() {
+        issues.push(ModuleFilenameIssue::NonAscii);
+    }
+    let extension = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+    if !allowed_exts.iter().any(|e| eq_ignore_case(e, extension)) {
+        issues.push(ModuleFilenameIssue::Extension);
+    }
+    issues
+}
+
+/// parser for filename of a module, such as `yaya.dll`. It reads until the next separator or line ending.
+///
+/// The name is a filename of [`parse_filename`], without directories, of ASCII only,
+/// and with one of `allowed_exts`, compared ignoring ASCII case. `""` in `allowed_exts` allows names without extension.
+/// Breaking a rule fails with the context of the first [`ModuleFilenameIssue`].
+///
+/// [`parse_filename`]: crate::value::parse_filename
+/// [`ModuleFilenameIssue`]: crate::value::ModuleFilenameIssue
+pub fn parse_module_filename<'a>(
+    input: &'a str,
+    allowed_exts: &[&str],
+) -> IResult<&'a str, &'a str, ShellParseError<'a>> {
+    let (remain, name) = parse_filename(input)?;
+    match module_filename_issues(name, allowed_exts).first() {
+        Some(issue) => {
+            let error = ShellParseError::from_error_kind(name, ErrorKind::Verify);
+            Err(nom::Err::Error(ShellParseError::add_context(
+                name,
+                issue.context(),
+                error,
+            )))
+        }
+        None => Ok((remain, name)),
+    }
+}
+
+/// Same as [`parse_module_filename`], capturing any name up to the next separator or line ending with its issues,
+/// for viewers showing names as they are written.
+///
+/// [`parse_module_filename`]: crate::value::parse_module_filename
+pub fn parse_module_filename_lenient<'a>(
+    input: &'a str,
+    allowed_exts: &[&str],
+) -> IResult<&'a str, (&'a str, Vec<ModuleFilenameIssue>), ShellParseError<'a>> {
+    let (remain, name) = is_not(",\r\n")(input)?;
+    Ok((remain, (name, module_filename_issues(name, allowed_exts))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::vec;
+
+    use crate::error::ParseFailure;
+
+    fn contexts(input: &str, allowed_exts: &[&str]) -> Vec<&'static str> {
+        ParseFailure::from(parse_module_filename(input, allowed_exts).unwrap_err())
+            .contexts()
+            .to_vec()
+    }
+
+    mod parse_module_filename {
+        use super::*;
+
+        #[test]
+        fn success_when_valid_dll() {
+            let (remain, result) = parse_module_filename("yaya.dll\r\n", &["dll"]).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result, "yaya.dll");
+            assert_eq!(
+                parse_module_filename("MAKOTO.DLL,x", &["dll"]),
+                Ok((",x", "MAKOTO.DLL"))
+            );
+            assert_eq!(
+                parse_module_filename("makoto", &["dll", ""]),
+                Ok(("", "makoto"))
+            );
+        }
+
+        #[test]
+        fn failed_when_subdirectory() {
+            assert_eq!(contexts("bin/yaya.dll", &["dll"]), ["module:path"]);
+            assert_eq!(contexts("..\\yaya.dll", &["dll"]), ["module:path"]);
+        }
+
+        #[test]
+        fn failed_when_wrong_extension() {
+            assert_eq!(contexts("yaya.exe", &["dll"]), ["module:extension"]);
+            assert_eq!(contexts("makoto", &["dll"]), ["module:extension"]);
+            assert!(parse_module_filename("ya|ya.dll", &["dll"]).is_err());
+        }
+
+        #[test]
+        fn failed_when_non_ascii_name() {
+            assert_eq!(contexts("里々.dll", &["dll"]), ["module:non_ascii"]);
+        }
+    }
+
+    mod parse_module_filename_lenient {
+        use super::*;
+
+        #[test]
+        fn success_when_issues_captured() {
+            let (remain, (name, issues)) =
+                parse_module_filename_lenient("bin/里々.exe\r\n", &["dll"]).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(name, "bin/里々.exe");
+            assert_eq!(
+                issues,
+                vec![
+                    ModuleFilenameIssue::Path,
+                    ModuleFilenameIssue::NonAscii,
+                    ModuleFilenameIssue::Extension
+                ]
+            );
+            assert_eq!(
+                parse_module_filename_lenient("yaya.dll", &["dll"]),
+                Ok(("", ("yaya.dll", vec![])))
+            );
+        }
+    }
+}
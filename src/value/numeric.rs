@@ -0,0 +1,485 @@
+//! `NumericPolicy`, checks applied to numbers beyond their syntax.
+
+#[cfg(feature = "kv")]
+use alloc::vec;
+#[cfg(feature = "kv")]
+use core::cell::Cell;
+
+#[cfg(feature = "kv")]
+use nom::{
+    character::complete::{char, digit1},
+    combinator::{map, opt, recognize},
+    error::{ContextError, ErrorKind, ParseError},
+    sequence::{pair, preceded, tuple},
+    IResult,
+};
+
+#[cfg(feature = "surfaces")]
+use crate::options::{Limit, LimitExceeded, ParseOptions};
+#[cfg(feature = "kv")]
+use crate::{options::Limits, ShellParseError};
+
+#[cfg(feature = "kv")]
+use super::{separator, Coordinate, IdList, Rect};
+
+/// Handling of numbers out of the range of their type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Overflow {
+    /// Clamps to the nearest bound, recording it in [`Checked::saturated`].
+    ///
+    /// [`Checked::saturated`]: crate::value::Checked::saturated
+    Saturate,
+    /// Fails to parse.
+    Error,
+}
+
+/// Checks applied to numbers by `*_with_policy` parsers.
+///
+/// The default is [`NumericPolicy::DEFAULT`], which is what parsers without a policy accept.
+///
+/// [`NumericPolicy::DEFAULT`]: crate::value::NumericPolicy::DEFAULT
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[non_exhaustive]
+pub struct NumericPolicy {
+    /// Whether digits can start with `0`, such as `007`. A single `0` is always accepted.
+    pub allow_leading_zeros: bool,
+    pub on_overflow: Overflow,
+    /// Maximum number of digits, not counting the sign, or `None` for no limit.
+    pub max_digits: Option<usize>,
+}
+
+impl NumericPolicy {
+    /// Leading zeros allowed, overflow failing and no limit of digits.
+    pub const DEFAULT: NumericPolicy = NumericPolicy {
+        allow_leading_zeros: true,
+        on_overflow: Overflow::Error,
+        max_digits: None,
+    };
+
+    pub const fn allow_leading_zeros(mut self, enabled: bool) -> NumericPolicy {
+        self.allow_leading_zeros = enabled;
+        self
+    }
+
+    pub const fn on_overflow(mut self, on_overflow: Overflow) -> NumericPolicy {
+        self.on_overflow = on_overflow;
+        self
+    }
+
+    pub const fn max_digits(mut self, max_digits: Option<usize>) -> NumericPolicy {
+        self.max_digits = max_digits;
+        self
+    }
+}
+
+impl Default for NumericPolicy {
+    fn default() -> Self {
+        NumericPolicy::DEFAULT
+    }
+}
+
+/// Value parsed under a [`NumericPolicy`].
+///
+/// [`NumericPolicy`]: crate::value::NumericPolicy
+#[cfg(feature = "kv")]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Checked<T> {
+    pub value: T,
+    /// Whether any number in the value is clamped by [`Overflow::Saturate`].
+    ///
+    /// [`Overflow::Saturate`]: crate::value::Overflow::Saturate
+    pub saturated: bool,
+}
+
+/// Parsers of numbers under a policy, recording saturation over all of them.
+///
+/// Lists of IDs are limited to [`Limits::max_id_set`], of [`Limits::DEFAULT`] unless given.
+///
+/// [`Limits::max_id_set`]: crate::options::Limits::max_id_set
+/// [`Limits::DEFAULT`]: crate::options::Limits::DEFAULT
+#[cfg(feature = "kv")]
+pub(crate) struct NumericReader {
+    policy: NumericPolicy,
+    max_ids: usize,
+    saturated: Cell<bool>,
+    exceeded: Cell<bool>,
+}
+
+#[cfg(feature = "kv")]
+impl NumericReader {
+    pub(crate) fn new(policy: NumericPolicy) -> NumericReader {
+        NumericReader {
+            policy,
+            max_ids: Limits::DEFAULT.max_id_set,
+            saturated: Cell::new(false),
+            exceeded: Cell::new(false),
+        }
+    }
+
+    /// Reader of [`ParseOptions::numeric`], limiting lists of IDs to [`ParseOptions::limits`].
+    ///
+    /// [`ParseOptions::numeric`]: crate::options::ParseOptions::numeric
+    /// [`ParseOptions::limits`]: crate::options::ParseOptions::limits
+    #[cfg(feature = "surfaces")]
+    pub(crate) fn of(options: &ParseOptions) -> NumericReader {
+        NumericReader {
+            max_ids: options.limits.max_id_set,
+            ..NumericReader::new(options.numeric)
+        }
+    }
+
+    /// Runs `parser` with a reader of `policy`, wrapping its output into [`Checked`].
+    pub(crate) fn run<'a, T>(
+        input: &'a str,
+        policy: &NumericPolicy,
+        parser: impl FnOnce(&NumericReader, &'a str) -> IResult<&'a str, T, ShellParseError<'a>>,
+    ) -> IResult<&'a str, Checked<T>, ShellParseError<'a>> {
+        let reader = NumericReader::new(*policy);
+        let (input, value) = parser(&reader, input)?;
+
+        Ok((
+            input,
+            Checked {
+                value,
+                saturated: reader.saturated.get(),
+            },
+        ))
+    }
+
+    /// Checks whether any number read so far is clamped by [`Overflow::Saturate`].
+    ///
+    /// [`Overflow::Saturate`]: crate::value::Overflow::Saturate
+    #[cfg(feature = "surfaces")]
+    pub(crate) fn saturated(&self) -> bool {
+        self.saturated.get()
+    }
+
+    /// Returns the limit when a list of IDs read so far is over it.
+    #[cfg(feature = "surfaces")]
+    pub(crate) fn exceeded(&self) -> Option<LimitExceeded> {
+        self.exceeded.get().then_some(LimitExceeded {
+            limit: Limit::IdSet,
+            max: self.max_ids,
+        })
+    }
+
+    pub(crate) fn u32<'a>(&self, input: &'a str) -> IResult<&'a str, u32, ShellParseError<'a>> {
+        let (rest, digits) = digit1(input)?;
+        let value = self.integer(input, false, digits, 0, u32::MAX.into())?;
+        Ok((rest, value as u32))
+    }
+
+    pub(crate) fn i32<'a>(&self, input: &'a str) -> IResult<&'a str, i32, ShellParseError<'a>> {
+        let (rest, text) = recognize(pair(opt(char('-')), digit1))(input)?;
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text),
+        };
+        let value = self.integer(input, negative, digits, i32::MIN.into(), i32::MAX.into())?;
+        Ok((rest, value as i32))
+    }
+
+    pub(crate) fn decimal<'a>(&self, input: &'a str) -> IResult<&'a str, f64, ShellParseError<'a>> {
+        let (rest, (_, integer, _, fraction)) =
+            tuple((opt(char('-')), digit1, char('.'), digit1))(input)?;
+        self.check_digits(input, integer, integer.len() + fraction.len())?;
+
+        let text = &input[..input.len() - rest.len()];
+        let value = text.parse::<f64>().unwrap_or(f64::INFINITY);
+        if value.is_finite() {
+            return Ok((rest, value));
+        }
+
+        match self.policy.on_overflow {
+            Overflow::Saturate => {
+                self.saturated.set(true);
+                Ok((rest, if value < 0.0 { -f64::MAX } else { f64::MAX }))
+            }
+            Overflow::Error => Err(failure(input, "number:overflow")),
+        }
+    }
+
+    pub(crate) fn coordinate<'a>(
+        &self,
+        input: &'a str,
+    ) -> IResult<&'a str, Coordinate, ShellParseError<'a>> {
+        map(
+            tuple((|i| self.i32(i), preceded(separator, |i| self.i32(i)))),
+            |(x, y)| Coordinate::new(x, y),
+        )(input)
+    }
+
+    pub(crate) fn rect<'a>(&self, input: &'a str) -> IResult<&'a str, Rect, ShellParseError<'a>> {
+        map(
+            tuple((
+                |i| self.i32(i),
+                preceded(separator, |i| self.i32(i)),
+                preceded(separator, |i| self.i32(i)),
+                preceded(separator, |i| self.i32(i)),
+            )),
+            |(left, top, right, bottom)| Rect::new(left, top, right, bottom),
+        )(input)
+    }
+
+    /// Reads IDs separated by commas, failing with context `id_list:limit` over the limit of the reader.
+    pub(crate) fn id_list<'a>(
+        &self,
+        input: &'a str,
+    ) -> IResult<&'a str, IdList, ShellParseError<'a>> {
+        let (mut rest, first) = self.u32(input)?;
+        let mut ids = vec![first];
+        loop {
+            if ids.len() > self.max_ids {
+                self.exceeded.set(true);
+                return Err(failure(input, "id_list:limit"));
+            }
+            match preceded(separator, |i| self.u32(i))(rest) {
+                Ok((next, id)) => {
+                    ids.push(id);
+                    rest = next;
+                }
+                Err(nom::Err::Error(_)) => return Ok((rest, IdList(ids))),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Checks `digits` and reads them as a number between `min` and `max`.
+    fn integer<'a>(
+        &self,
+        input: &'a str,
+        negative: bool,
+        digits: &'a str,
+        min: i64,
+        max: i64,
+    ) -> Result<i64, nom::Err<ShellParseError<'a>>> {
+        self.check_digits(input, digits, digits.len())?;
+
+        // Numbers of more than 18 significant digits are out of the range of any type read here.
+        let significant = digits.trim_start_matches('0');
+        let value = match significant.len() {
+            0 => Some(0),
+            1..=18 => significant
+                .parse::<i64>()
+                .ok()
+                .map(|v| if negative { -v } else { v }),
+            _ => None,
+        };
+
+        match value {
+            Some(v) if min <= v && v <= max => Ok(v),
+            _ => match self.policy.on_overflow {
+                Overflow::Saturate => {
+                    self.saturated.set(true);
+                    Ok(if negative { min } else { max })
+                }
+                Overflow::Error => Err(failure(input, "number:overflow")),
+            },
+        }
+    }
+
+    /// Checks leading zeros of `integer`, the digits before any `.`, and the number of all digits.
+    fn check_digits<'a>(
+        &self,
+        input: &'a str,
+        integer: &str,
+        count: usize,
+    ) -> Result<(), nom::Err<ShellParseError<'a>>> {
+        if !self.policy.allow_leading_zeros && integer.len() > 1 && integer.starts_with('0') {
+            Err(failure(input, "number:leading_zeros"))
+        } else if self.policy.max_digits.is_some_and(|max| count > max) {
+            Err(failure(input, "number:digits"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "kv")]
+fn failure<'a>(input: &'a str, label: &'static str) -> nom::Err<ShellParseError<'a>> {
+    let error = ShellParseError::from_error_kind(input, ErrorKind::Verify);
+    nom::Err::Error(ShellParseError::add_context(input, label, error))
+}
+
+/// parser for unsigned decimal number under `policy`.
+#[cfg(feature = "kv")]
+pub fn parse_u32_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<u32>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| r.u32(i))
+}
+
+/// parser for signed decimal number under `policy`.
+#[cfg(feature = "kv")]
+pub fn parse_i32_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<i32>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| r.i32(i))
+}
+
+/// parser for decimal number with fraction, such as `-0.5`, under `policy`.
+///
+/// Digits of both the integer and the fraction are counted for [`NumericPolicy::max_digits`].
+///
+/// [`NumericPolicy::max_digits`]: crate::value::NumericPolicy::max_digits
+#[cfg(feature = "kv")]
+pub fn parse_decimal_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<f64>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| r.decimal(i))
+}
+
+/// parser for [`Coordinate`] under `policy`.
+///
+/// [`Coordinate`]: crate::value::Coordinate
+#[cfg(feature = "kv")]
+pub fn parse_coordinate_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<Coordinate>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| r.coordinate(i))
+}
+
+/// parser for [`Rect`] under `policy`.
+///
+/// [`Rect`]: crate::value::Rect
+#[cfg(feature = "kv")]
+pub fn parse_rect_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<Rect>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| r.rect(i))
+}
+
+/// parser for [`IdList`] under `policy`.
+///
+/// Lists of more IDs than [`Limits::DEFAULT`] allows fail with context `id_list:limit`.
+///
+/// [`IdList`]: crate::value::IdList
+/// [`Limits::DEFAULT`]: crate::options::Limits::DEFAULT
+#[cfg(feature = "kv")]
+pub fn parse_id_list_with_policy<'a>(
+    input: &'a str,
+    policy: &NumericPolicy,
+) -> IResult<&'a str, Checked<IdList>, ShellParseError<'a>> {
+    NumericReader::run(input, policy, |r, i| r.id_list(i))
+}
+
+#[cfg(all(test, feature = "kv"))]
+mod tests {
+    use super::*;
+
+    mod parse_u32_with_policy {
+        use super::*;
+
+        #[test]
+        fn success_when_overflow_saturated() {
+            let policy = NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate);
+            assert_eq!(
+                parse_u32_with_policy("4294967296,", &policy).unwrap(),
+                (
+                    ",",
+                    Checked {
+                        value: u32::MAX,
+                        saturated: true
+                    }
+                )
+            );
+            assert_eq!(
+                parse_u32_with_policy("4294967295", &policy).unwrap().1,
+                Checked {
+                    value: u32::MAX,
+                    saturated: false
+                }
+            );
+        }
+
+        #[test]
+        fn failed_when_overflow_error() {
+            assert!(parse_u32_with_policy("4294967296", &NumericPolicy::DEFAULT).is_err());
+        }
+
+        #[test]
+        fn success_when_thirty_digits() {
+            let case = "123456789012345678901234567890";
+            assert!(parse_u32_with_policy(case, &NumericPolicy::DEFAULT).is_err());
+
+            let policy = NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate);
+            assert_eq!(
+                parse_u32_with_policy(case, &policy).unwrap().1.value,
+                u32::MAX
+            );
+            assert!(parse_u32_with_policy(case, &policy.max_digits(Some(10))).is_err());
+        }
+
+        #[test]
+        fn success_when_leading_zeros() {
+            let case = "007";
+            assert_eq!(
+                parse_u32_with_policy(case, &NumericPolicy::DEFAULT)
+                    .unwrap()
+                    .1
+                    .value,
+                7
+            );
+
+            let policy = NumericPolicy::DEFAULT.allow_leading_zeros(false);
+            assert!(parse_u32_with_policy(case, &policy).is_err());
+            assert_eq!(parse_u32_with_policy("0", &policy).unwrap().1.value, 0);
+        }
+    }
+
+    mod parse_i32_with_policy {
+        use super::*;
+
+        #[test]
+        fn success_when_negative_overflow_saturated() {
+            let policy = NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate);
+            assert_eq!(
+                parse_i32_with_policy("-99999999999", &policy).unwrap().1,
+                Checked {
+                    value: i32::MIN,
+                    saturated: true
+                }
+            );
+            assert!(parse_i32_with_policy("-99999999999", &NumericPolicy::DEFAULT).is_err());
+            assert!(parse_i32_with_policy("-01", &policy.allow_leading_zeros(false)).is_err());
+        }
+    }
+
+    mod parse_decimal_with_policy {
+        use super::*;
+
+        #[test]
+        fn success_when_leading_zeros() {
+            let policy = NumericPolicy::DEFAULT.allow_leading_zeros(false);
+            assert_eq!(
+                parse_decimal_with_policy("-0.05", &policy).unwrap().1.value,
+                -0.05
+            );
+            assert!(parse_decimal_with_policy("00.5", &policy).is_err());
+            assert!(parse_decimal_with_policy("12.34", &policy.max_digits(Some(3))).is_err());
+        }
+    }
+
+    mod parse_rect_with_policy {
+        use super::*;
+
+        #[test]
+        fn success_when_any_field_saturated() {
+            let policy = NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate);
+            let (remain, result) =
+                parse_rect_with_policy("0,-3000000000,10,10\r\n", &policy).unwrap();
+            assert_eq!(remain, "\r\n");
+            assert_eq!(result.value, Rect::new(0, i32::MIN, 10, 10));
+            assert!(result.saturated);
+
+            assert!(
+                parse_rect_with_policy("0,-3000000000,10,10", &NumericPolicy::DEFAULT).is_err()
+            );
+        }
+    }
+}
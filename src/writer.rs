@@ -0,0 +1,264 @@
+//! # `writer`
+//!
+//! Writers of entries and blocks back into text.
+//! The line ending is given by the caller. [`LineEnding::default`] is `\r\n`, which baseware expects.
+//!
+//! [`LineEnding::default`]: crate::line::LineEnding
+//!
+//! ## Example
+//!
+//! ```
+//! use shell_parser_common_rs::kv::KeyValue;
+//! use shell_parser_common_rs::line::LineEnding;
+//! use shell_parser_common_rs::writer::{write_block, write_key_value};
+//!
+//! let mut out = String::new();
+//! write_key_value(&mut out, "charset", "UTF-8", LineEnding::default()).unwrap();
+//! write_block(
+//!     &mut out,
+//!     "surface0",
+//!     &[KeyValue::new("element0", "base,surface0.png,0,0")],
+//!     LineEnding::default(),
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(
+//!     out,
+//!     "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,surface0.png,0,0\r\n}\r\n"
+//! );
+//! ```
+
+use alloc::string::{String, ToString};
+#[cfg(feature = "seriko")]
+use alloc::{format, vec::Vec};
+use core::fmt;
+
+#[cfg(feature = "seriko")]
+use crate::seriko::Animation;
+use crate::{kv::KeyValue, line::LineEnding};
+
+/// `WriteError` type.
+///
+/// The format has no escape sequences, so text which would be read back differently is rejected.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum WriteError {
+    /// The key is empty or contains `,`.
+    InvalidKey(String),
+    /// The text contains a line ending.
+    LineEnding(String),
+    /// The header is empty or contains a brace.
+    InvalidHeader(String),
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::InvalidKey(key) => write!(f, "invalid key {:?}", key),
+            WriteError::LineEnding(text) => write!(f, "line ending in {:?}", text),
+            WriteError::InvalidHeader(header) => write!(f, "invalid header {:?}", header),
+        }
+    }
+}
+
+impl core::error::Error for WriteError {}
+
+/// Writes `key,value` and `ending` to `out`.
+///
+/// Commas in `value` are kept as is, since the value is the rest of the line.
+pub fn write_key_value(
+    out: &mut String,
+    key: &str,
+    value: &str,
+    ending: LineEnding,
+) -> Result<(), WriteError> {
+    check_key(key)?;
+    check_line_ending(value)?;
+
+    out.push_str(key);
+    out.push(',');
+    out.push_str(value);
+    out.push_str(ending.as_str());
+
+    Ok(())
+}
+
+/// Writes `header`, then `body` enclosed in braces, each on its own line.
+pub fn write_block(
+    out: &mut String,
+    header: &str,
+    body: &[KeyValue<'_>],
+    ending: LineEnding,
+) -> Result<(), WriteError> {
+    if header.trim().is_empty() || header.contains(['{', '}']) {
+        return Err(WriteError::InvalidHeader(header.to_string()));
+    }
+    check_line_ending(header)?;
+
+    let mut block = String::new();
+    block.push_str(header);
+    block.push_str(ending.as_str());
+    block.push('{');
+    block.push_str(ending.as_str());
+    for kv in body.iter() {
+        write_key_value(&mut block, kv.key, kv.value, ending)?;
+    }
+    block.push('}');
+    block.push_str(ending.as_str());
+
+    out.push_str(&block);
+    Ok(())
+}
+
+/// Writes the `animationN.*` entries of `animation` to `out`, each followed by `ending`.
+///
+/// The interval, the patterns in order of their indices and the options joined by `+` are written
+/// in their `Display` forms, which the parsers read back. Collisions of the animation are not written.
+#[cfg(feature = "seriko")]
+pub fn write_animation(
+    out: &mut String,
+    animation: &Animation,
+    ending: LineEnding,
+) -> Result<(), WriteError> {
+    let id = animation.id;
+    let mut lines = String::new();
+    if let Some(intervals) = &animation.intervals {
+        let key = format!("animation{}.interval", id);
+        write_key_value(&mut lines, &key, &intervals.to_string(), ending)?;
+    }
+    for (index, pattern) in animation.patterns.iter() {
+        let key = format!("animation{}.pattern{}", id, index);
+        write_key_value(&mut lines, &key, &pattern.to_string(), ending)?;
+    }
+    if !animation.options.is_empty() {
+        let options: Vec<_> = animation.options.iter().map(|o| o.to_string()).collect();
+        let key = format!("animation{}.option", id);
+        write_key_value(&mut lines, &key, &options.join("+"), ending)?;
+    }
+
+    out.push_str(&lines);
+    Ok(())
+}
+
+/// Checks that `key` is read back as it is.
+pub(crate) fn check_key(key: &str) -> Result<(), WriteError> {
+    if key.is_empty() || key.contains(',') {
+        return Err(WriteError::InvalidKey(key.to_string()));
+    }
+    check_line_ending(key)
+}
+
+fn check_line_ending(text: &str) -> Result<(), WriteError> {
+    if text.contains(['\r', '\n']) {
+        Err(WriteError::LineEnding(text.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use nom::combinator::all_consuming;
+
+    #[cfg(feature = "block")]
+    use crate::block::parse_blocks;
+    use crate::{kv::parse_key_value, line::lines};
+
+    mod write_key_value {
+        use super::*;
+
+        #[test]
+        fn success_when_commas_in_value() {
+            let mut out = String::new();
+            write_key_value(&mut out, "element0", "overlay,a.png,0,0", LineEnding::CrLf).unwrap();
+            let line = lines(&out).next().unwrap();
+            let (_, kv) = all_consuming(parse_key_value)(line.text).unwrap();
+            assert_eq!(kv, KeyValue::new("element0", "overlay,a.png,0,0"));
+        }
+
+        #[test]
+        fn success_when_lf_and_crlf() {
+            let mut out = String::new();
+            write_key_value(&mut out, "a", "1", LineEnding::Lf).unwrap();
+            write_key_value(&mut out, "b", "2", LineEnding::default()).unwrap();
+            assert_eq!(out, "a,1\nb,2\r\n");
+        }
+
+        #[test]
+        fn failed_when_unrepresentable() {
+            let mut out = String::new();
+            assert!(write_key_value(&mut out, "a,b", "1", LineEnding::CrLf).is_err());
+            assert!(write_key_value(&mut out, "a", "1\r\n2", LineEnding::CrLf).is_err());
+            assert!(out.is_empty());
+        }
+    }
+
+    mod write_block {
+        use super::*;
+
+        #[test]
+        #[cfg(feature = "block")]
+        fn success_when_re_parsed() {
+            let body = [
+                KeyValue::new("element0", "base,surface0.png,0,0"),
+                KeyValue::new("collision0", "0,0,10,10,Head"),
+            ];
+            let mut out = String::new();
+            write_block(&mut out, "surface0,surface1", &body, LineEnding::Lf).unwrap();
+
+            let blocks = parse_blocks(&out).unwrap();
+            assert_eq!(blocks[0].header, "surface0,surface1");
+            let parsed: Vec<_> = blocks[0]
+                .body
+                .iter()
+                .map(|l| all_consuming(parse_key_value)(l.text).unwrap().1)
+                .collect();
+            assert_eq!(parsed, body);
+        }
+
+        #[test]
+        fn failed_when_invalid_header() {
+            let mut out = String::new();
+            assert!(write_block(&mut out, "surface0 {", &[], LineEnding::CrLf).is_err());
+            assert!(write_block(&mut out, "", &[], LineEnding::CrLf).is_err());
+            assert!(
+                write_block(&mut out, "s", &[KeyValue::new("a,", "1")], LineEnding::CrLf).is_err()
+            );
+            assert!(out.is_empty());
+        }
+    }
+
+    #[cfg(feature = "surfaces")]
+    mod write_animation {
+        use super::*;
+
+        use crate::surfaces::parse_block_body;
+
+        #[test]
+        fn success_when_re_parsed() {
+            let case = "surface0\r\n{\r\nanimation2.interval,sometimes+talk,3\r\nanimation2.pattern0,overlay,10,50-100\r\nanimation2.pattern1,alternativestart,[3.4]\r\nanimation2.option,exclusive,(1,3),background\r\n}\r\n";
+            let blocks = parse_blocks(case).unwrap();
+            let body = parse_block_body(&blocks[0].body).unwrap();
+            let animation = &body.animations[&2];
+
+            let mut out = String::new();
+            write_animation(&mut out, animation, LineEnding::CrLf).unwrap();
+            assert_eq!(
+                out,
+                "animation2.interval,sometimes+talk,3\r\nanimation2.pattern0,overlay,10,50-100,0,0\r\nanimation2.pattern1,alternativestart,[3.4]\r\nanimation2.option,exclusive,(1,3)+background\r\n"
+            );
+
+            let mut block = String::new();
+            let body_lines: Vec<_> = lines(&out)
+                .map(|l| all_consuming(parse_key_value)(l.text).unwrap().1)
+                .collect();
+            write_block(&mut block, "surface0", &body_lines, LineEnding::CrLf).unwrap();
+            let blocks = parse_blocks(&block).unwrap();
+            assert_eq!(
+                &parse_block_body(&blocks[0].body).unwrap().animations[&2],
+                animation
+            );
+        }
+    }
+}
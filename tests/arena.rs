@@ -0,0 +1,60 @@
+//! Projects of `load_shell_dir_in` compared with those of `load_shell_dir`, on the fixture shell
+//! and on a generated shell of many surfaces files.
+
+use std::path::Path;
+
+use shell_parser_common_rs::{
+    arena::ParseArena,
+    options::ParseOptions,
+    project::{load_shell_dir, load_shell_dir_in},
+};
+
+fn assert_same(dir: &Path) {
+    let project = load_shell_dir(dir, &ParseOptions::STRICT).unwrap();
+    let arena = ParseArena::new();
+    let in_arena = load_shell_dir_in(dir, &ParseOptions::STRICT, &arena).unwrap();
+
+    let files = std::iter::once((in_arena.descript(), project.descript()))
+        .chain(in_arena.surfaces().iter().zip(project.surfaces()));
+    for (file, expected) in files {
+        let path = expected.path().display();
+        assert_eq!(file.path(), expected.path());
+        assert_eq!(file.charset(), expected.charset(), "{}", path);
+        assert_eq!(file.text(), expected.text(), "{}", path);
+        assert_eq!(file.entries(), expected.entries(), "{}", path);
+        assert_eq!(file.blocks(), expected.blocks(), "{}", path);
+    }
+    assert_eq!(in_arena.surfaces().len(), project.surfaces().len());
+    assert_eq!(in_arena.surfaces_descript(), project.surfaces_descript());
+    assert_eq!(in_arena.blocks().count(), project.blocks().len());
+}
+
+#[test]
+fn success_when_fixture_shell_loaded() {
+    assert_same(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/shells/appends"));
+}
+
+#[test]
+fn success_when_many_surfaces_files_loaded() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("descript.txt"),
+        "charset,UTF-8\r\nname,master\r\nsakura.name,Emily\r\n",
+    )
+    .unwrap();
+    for n in 0..8 {
+        let mut text = String::from("charset,UTF-8\r\n");
+        for id in n * 50..(n + 1) * 50 {
+            text.push_str(&format!(
+                "surface{id}\r\n{{\r\n// body\r\nelement0,base,surface{id}.png,0,0\r\ncollision0,0,0,10,10,Head\r\n}}\r\n"
+            ));
+        }
+        let name = match n {
+            0 => String::from("surfaces.txt"),
+            n => format!("surfaces{}.txt", n + 1),
+        };
+        std::fs::write(dir.path().join(name), text).unwrap();
+    }
+
+    assert_same(dir.path());
+}
@@ -0,0 +1,72 @@
+//! Case-insensitive matching of keys and labels without allocation.
+//!
+//! Allocations of every layout are counted by `common::CountingAllocator`.
+
+use nom::combinator::all_consuming;
+
+use shell_parser_common_rs::{
+    ascii::{eq_ignore_case, starts_with_ignore_case},
+    charset::{parse_charset_with, Charset},
+    error::Located,
+    kv::{EntryMap, KeyValue, NormalizedKey},
+    options::ParseOptions,
+};
+
+mod common;
+
+use common::{counted, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn success_when_non_ascii_letters_kept() {
+    // `İ` lowercases into `i` and a combining dot by Unicode, which must not match `i`.
+    assert!(!eq_ignore_case("sakura.İd", "sakura.id"));
+    assert!(!eq_ignore_case("sakura.ıd", "sakura.id"));
+    assert!(!eq_ignore_case("ＮＡＭＥ", "ｎａｍｅ"));
+    assert!(eq_ignore_case("Sakura.İd", "sakura.İd"));
+    assert!(!starts_with_ignore_case("İcon", "icon"));
+
+    let map: EntryMap = [
+        Located::new(1, KeyValue::new("sakura.İd", "a")),
+        Located::new(2, KeyValue::new("ＮＡＭＥ", "b")),
+    ]
+    .into_iter()
+    .collect();
+    assert!(map.get("sakura.id").is_none());
+    assert!(map.get("ｎａｍｅ").is_none());
+    assert_eq!(map.get("SAKURA.İd").unwrap().line, 1);
+    assert_eq!(NormalizedKey::new("Sakura.İd").normalized(), "sakura.İd");
+}
+
+#[test]
+fn success_when_no_allocation() {
+    let map: EntryMap = [
+        Located::new(1, KeyValue::new("Charset", "UTF-8")),
+        Located::new(2, KeyValue::new("sakura.balloon.offsetX", "10")),
+    ]
+    .into_iter()
+    .collect();
+    let options = ParseOptions::COMPATIBLE;
+
+    let (found, counts) = counted(|| {
+        (
+            eq_ignore_case("Sakura.Balloon.OffsetX", "sakura.balloon.offsetx"),
+            starts_with_ignore_case("Element0", "element"),
+            NormalizedKey::new("CHARSET") == NormalizedKey::new("charset"),
+            map.get("SAKURA.BALLOON.OFFSETX").map(|e| e.line),
+            options.key_eq("CharSet", "charset"),
+        )
+    });
+    assert_eq!(found, (true, true, true, Some(2), true));
+    assert_eq!(counts.allocations, 0);
+
+    let (charset, counts) = counted(|| {
+        all_consuming(|input| parse_charset_with(input, &options))("Shift-JIS")
+            .ok()
+            .map(|(_, c)| c)
+    });
+    assert_eq!(charset, Some(Charset::ShiftJIS));
+    assert_eq!(counts.allocations, 0);
+}
@@ -0,0 +1,36 @@
+//! Block tokenizers against the one collecting lines of each block, as `parse_blocks` was before spans,
+//! on generated inputs heavy in braces.
+
+use proptest::prelude::*;
+
+use shell_parser_common_rs::block::{parse_block_spans, parse_blocks};
+
+#[path = "common/reference.rs"]
+mod reference;
+
+const PIECES: &[&str] = &[
+    "{", "}", " { ", "surface0", "a,1", "// c", "\r\n", "\n", "\r", " ", "x {", "あ",
+];
+
+fn any_input() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(PIECES), 0..30).prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn same_as_reference(case in any_input()) {
+        let expected = reference::collecting(&case);
+        prop_assert_eq!(parse_blocks(&case), expected.clone());
+
+        if let Ok(blocks) = expected {
+            let spans = parse_block_spans(&case).unwrap();
+            prop_assert_eq!(spans.len(), blocks.len());
+            for (span, block) in spans.iter().zip(blocks.iter()) {
+                prop_assert_eq!(span.header(&case), block.header);
+                prop_assert!(span.body_lines(&case).eq(block.body.iter().copied()));
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Borrowed parsing of surface bodies without copying strings.
+//!
+//! Allocations of every layout are counted by `common::CountingAllocator`.
+//! Those of a borrowed parse do not grow with the strings of the body, so none of them is a copy of one.
+
+use shell_parser_common_rs::{
+    block::parse_blocks,
+    line::Line,
+    surfaces::{parse_block_body, parse_block_body_ref},
+};
+
+mod common;
+
+use common::{counted, CountingAllocator, Counts};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+const CASE: &str = "surface0\r\n{\r\n// body\r\nelement0,base,body.png,0,0\r\nelement1,overlay,face/smile.png\r\nelement2,add,glow.png,5,5,64\r\ncollision0,10,10,50,50,Head\r\ncollision1,10,60,50,90,Bust, left \r\ntooltip.Head,head, smiling\r\n\r\n}\r\n";
+
+/// `CASE` with longer filenames, names and tooltips.
+fn lengthened() -> String {
+    CASE.replace(".png", "_of_a_longer_name.png")
+        .replace("Bust", "Bust_and_more")
+        .replace("smiling", "smiling_widely")
+}
+
+/// Returns allocations of parsing the bodies of `case` by `parse`, without those of the blocks.
+fn body_allocations(case: &str, parse: impl Fn(&[Line<'_>]) -> usize) -> Counts {
+    let blocks = parse_blocks(case).unwrap();
+    counted(|| blocks.iter().map(|b| parse(&b.body)).sum::<usize>()).1
+}
+
+#[test]
+fn success_when_no_string_allocation() {
+    let long = lengthened();
+    assert_eq!(
+        body_allocations(&long, |body| parse_block_body_ref(body)
+            .unwrap()
+            .elements
+            .len()),
+        body_allocations(CASE, |body| parse_block_body_ref(body)
+            .unwrap()
+            .elements
+            .len())
+    );
+    assert!(
+        body_allocations(&long, |body| parse_block_body(body).unwrap().elements.len()).bytes
+            > body_allocations(CASE, |body| parse_block_body(body).unwrap().elements.len()).bytes
+    );
+
+    let blocks = parse_blocks(CASE).unwrap();
+    let body = parse_block_body_ref(&blocks[0].body).unwrap();
+    let source = CASE.as_bytes().as_ptr_range();
+    assert_eq!(
+        body.elements.iter().map(|e| e.filename).collect::<Vec<_>>(),
+        vec!["body.png", "face/smile.png", "glow.png"]
+    );
+    assert!(body
+        .elements
+        .iter()
+        .all(|e| source.contains(&e.filename.as_ptr())));
+    assert_eq!(body.collisions[1].name, "Bust, left ");
+    assert!(source.contains(&body.collisions[1].name.as_ptr()));
+    assert_eq!(body.tooltips[0].collision, "Head");
+    assert_eq!(body.tooltips[0].text, "head, smiling");
+    assert!(source.contains(&body.tooltips[0].text.as_ptr()));
+}
+
+#[test]
+fn success_when_to_owned_equals_owned_parse() {
+    for block in parse_blocks(CASE).unwrap().iter() {
+        let borrowed = parse_block_body_ref(&block.body).unwrap();
+        let owned = parse_block_body(&block.body).unwrap();
+        assert_eq!(borrowed.to_owned(), owned);
+    }
+
+    let block = &parse_blocks("surface0\r\n{\r\nelement0,base,,0,0\r\n}\r\n").unwrap()[0];
+    assert_eq!(
+        parse_block_body_ref(&block.body).unwrap_err().line,
+        parse_block_body(&block.body).unwrap_err().line
+    );
+}
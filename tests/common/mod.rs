@@ -0,0 +1,68 @@
+//! Helpers shared by the integration tests.
+//!
+//! Tests counting allocations declare [`CountingAllocator`] as their `#[global_allocator]`.
+
+// Each test uses some of the helpers.
+#![allow(dead_code)]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+/// Allocator counting allocations of every layout per thread,
+/// so that tests running in parallel do not disturb each other.
+pub struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static TOTAL: Cell<usize> = const { Cell::new(0) };
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+    static PEAK: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        let _ = TOTAL.try_with(|total| total.set(total.get() + layout.size()));
+        let _ = ALLOCATED.try_with(|allocated| {
+            allocated.set(allocated.get() + layout.size());
+            let _ = PEAK.try_with(|peak| peak.set(peak.get().max(allocated.get())));
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = ALLOCATED
+            .try_with(|allocated| allocated.set(allocated.get().saturating_sub(layout.size())));
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Allocations made in a call of [`counted`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Counts {
+    /// Number of allocations, including those of growing ones.
+    pub allocations: usize,
+    /// Bytes of all allocations.
+    pub bytes: usize,
+    /// Peak of bytes allocated at once over those allocated before.
+    pub peak: usize,
+}
+
+/// Returns the result of `f` and the allocations made in it.
+pub fn counted<T>(f: impl FnOnce() -> T) -> (T, Counts) {
+    let allocations = ALLOCATIONS.with(Cell::get);
+    let total = TOTAL.with(Cell::get);
+    let before = ALLOCATED.with(Cell::get);
+    PEAK.with(|peak| peak.set(before));
+
+    let result = f();
+
+    let counts = Counts {
+        allocations: ALLOCATIONS.with(Cell::get) - allocations,
+        bytes: TOTAL.with(Cell::get) - total,
+        peak: PEAK.with(Cell::get) - before,
+    };
+    (result, counts)
+}
@@ -0,0 +1,68 @@
+//! Tokenizer collecting lines of each block, as `parse_blocks` was before spans,
+//! shared by `tests/blocks.rs` and `benches/blocks.rs`.
+
+use shell_parser_common_rs::{
+    block::{Block, BlockError},
+    error::Located,
+    line::{is_comment, lines, Line},
+};
+
+/// Splits `input` into blocks, pushing lines into a `Vec` of each block.
+pub fn collecting(input: &str) -> Result<Vec<Block<'_>>, Located<BlockError>> {
+    let mut blocks = Vec::new();
+    let mut header: Option<Line<'_>> = None;
+    let mut current: Option<Block<'_>> = None;
+
+    for line in lines(input) {
+        let text = line.text.trim();
+
+        if let Some(mut block) = current.take() {
+            if text == "}" {
+                block.close_line = line.number;
+                blocks.push(block);
+            } else if text.starts_with('{') {
+                return Err(Located::new(line.number, BlockError::NestedOpen));
+            } else {
+                block.body.push(line);
+                current = Some(block);
+            }
+            continue;
+        }
+
+        if text.is_empty() || is_comment(text) {
+            continue;
+        }
+
+        if text == "}" {
+            return Err(Located::new(line.number, BlockError::UnexpectedClose));
+        }
+
+        if let Some(head) = text.strip_suffix('{') {
+            let head = head.trim_end();
+            let (header_text, header_line) = if head.is_empty() {
+                match header.take() {
+                    Some(h) => (h.text.trim(), h.number),
+                    None => return Err(Located::new(line.number, BlockError::MissingHeader)),
+                }
+            } else {
+                (head, line.number)
+            };
+
+            current = Some(Block {
+                header: header_text,
+                header_line,
+                body: Vec::new(),
+                close_line: 0,
+            });
+            header = None;
+        } else {
+            header = Some(line);
+        }
+    }
+
+    if let Some(block) = current {
+        return Err(Located::new(block.header_line, BlockError::Unclosed));
+    }
+
+    Ok(blocks)
+}
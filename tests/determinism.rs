@@ -0,0 +1,67 @@
+//! Reports of the same shell written byte for byte the same.
+//!
+//! The shell is loaded twice, and once more on another thread, where `RandomState` draws other keys.
+//! No collection of the crate is hashed, so the seeds of hashers never change the order.
+
+use std::{collections::hash_map::RandomState, fmt::Write, hash::BuildHasher, path::Path};
+
+use shell_parser_common_rs::{
+    compat::required_level,
+    options::ParseOptions,
+    project::{load_shell_dir, validate, Inventory},
+    surfaces::{build_surfaces, parse_blocks_with},
+};
+
+const DESCRIPT: &str = "charset,UTF-8\r\nsakura.name,Emily\r\nkero.name,Teddy\r\nchar2.name,Lily\r\nsakura.bindgroup3.name,Hat,Ribbon\r\nsakura.bindgroup1.name,Hat,Cap\r\nkero.bindgroup2.name,Hat,Bow\r\nmenu.foreground.bitmap.filename,menu_fg.png\r\nmenu.background.bitmap.filename,menu_bg.png\r\nmenu.background.font.color.r,10\r\ncursor.mouseup0,Head,head.cur\r\n";
+
+const SURFACES: &str = "charset,UTF-8\r\nsurface3\r\n{\r\nelement0,base,c.png,0,0\r\nanimation9.interval,bind\r\nanimation9.pattern0,overlay,300,0,0,0\r\nanimation1.interval,sometimes+talk,3\r\nanimation1.pattern0,overlay,301,50,0,0\r\ncollisionex1,Head,ellipse,0,0,40,40\r\n}\r\nsurface0,surface1\r\n{\r\nelement1,overlay,b.png,0,0\r\nelement0,base,a.png,0,0\r\ncollision1,10,10,50,50,Head\r\ncollision0,10,60,50,90,Bust\r\nanimation3.interval,bind\r\nanimation3.pattern0,overlay,100,0,0,0\r\n}\r\nkero.surface.alias\r\n{\r\nsmile,[11]\r\n}\r\nsakura.surface.alias\r\n{\r\nsmile,[1,0]\r\nangry,[3]\r\n}\r\n";
+
+const SURFACES2: &str = "charset,UTF-8\r\nsurface.append0\r\n{\r\nelement2,add,glow.png,0,0\r\nanimation2.interval,runonce\r\nanimation2.pattern0,overlay,200,0,0,0\r\n}\r\n";
+
+/// Returns a report of all the collections of the shell at `path`.
+fn report(path: &Path) -> String {
+    let project = load_shell_dir(path, &ParseOptions::STRICT).unwrap();
+    let mut report = String::new();
+
+    for (file, warning) in validate(&project) {
+        let name = file.file_name().unwrap().to_str().unwrap();
+        writeln!(report, "{}:{}: {:?}", name, warning.line, warning.value).unwrap();
+    }
+    writeln!(report, "{:#?}", Inventory::from(&project)).unwrap();
+    writeln!(report, "{:#?}", required_level(&project).features).unwrap();
+    for file in project.surfaces() {
+        let parsed = parse_blocks_with(file.text(), &ParseOptions::STRICT).unwrap();
+        writeln!(report, "{:#?}", parsed.aliases).unwrap();
+        writeln!(report, "{:#?}", build_surfaces(&parsed.blocks)).unwrap();
+    }
+    report
+}
+
+#[test]
+fn success_when_report_reproduced() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("descript.txt"), DESCRIPT).unwrap();
+    std::fs::write(dir.path().join("surfaces.txt"), SURFACES).unwrap();
+    std::fs::write(dir.path().join("surfaces2.txt"), SURFACES2).unwrap();
+
+    let first = report(dir.path());
+    assert_eq!(report(dir.path()), first);
+
+    let state = RandomState::new();
+    let path = dir.path().to_path_buf();
+    let (other_state, other) = std::thread::spawn(move || {
+        let state = RandomState::new();
+        (state, report(&path))
+    })
+    .join()
+    .unwrap();
+    assert_ne!(state.hash_one("surface0"), other_state.hash_one("surface0"));
+    assert_eq!(other, first);
+
+    let surface0 = first.find("0: Surface").unwrap();
+    let surface3 = first.find("3: Surface").unwrap();
+    assert!(surface0 < surface3);
+    let angry = first.find("\"angry\"").unwrap();
+    let smile = first.find("\"smile\"").unwrap();
+    assert!(angry < smile);
+}
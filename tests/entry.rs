@@ -0,0 +1,67 @@
+//! Kinds of `classify_entry` agreeing with the conversions of the entries of fixtures.
+//!
+//! Values of `tests/fixtures/files` may fail to convert, though of the kind classified,
+//! as some are broken or full-width on purpose. Those of the shell all convert.
+
+use std::{fs, path::Path};
+
+use shell_parser_common_rs::{
+    charset::Charset,
+    document::parse_bytes,
+    entry::{classify_entry, EntryConverter, EntryError, EntryKind},
+};
+
+const KINDS: &[EntryKind] = &[
+    EntryKind::BalloonOffset,
+    EntryKind::CursorBinding,
+    EntryKind::Tooltip,
+    EntryKind::Alias,
+    EntryKind::Charset,
+    EntryKind::Collision,
+    EntryKind::Element,
+];
+
+#[test]
+fn success_when_classify_agrees_with_conversions() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut found = Vec::new();
+    for (dir, clean) in [("files", false), ("shells/appends", true)] {
+        let mut paths: Vec<_> = fs::read_dir(root.join(dir))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let Ok(document) = parse_bytes(&fs::read(&path).unwrap(), Charset::ShiftJIS) else {
+                continue;
+            };
+            for entry in document.entries() {
+                let kind = classify_entry(&entry.value);
+                for converter in KINDS {
+                    let agreed = match converter.convert(&entry.value) {
+                        Ok(_) => *converter == kind,
+                        Err(EntryError::Value(_)) => !clean && *converter == kind,
+                        Err(_) => *converter != kind,
+                    };
+                    assert!(
+                        agreed,
+                        "{}:{}: {:?} as {:?}",
+                        path.display(),
+                        entry.line,
+                        entry.value,
+                        converter
+                    );
+                }
+                if !found.contains(&kind) {
+                    found.push(kind);
+                }
+            }
+        }
+    }
+
+    for kind in KINDS {
+        assert!(found.contains(kind), "no entry of {:?}", kind);
+    }
+}
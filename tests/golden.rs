@@ -0,0 +1,91 @@
+//! Dumps of the fixtures of `tests/fixtures` compared with the goldens checked in `tests/golden`.
+//!
+//! Files of `tests/fixtures/files` are dumped by `dump_file`, and shells of `tests/fixtures/shells` by `dump_project`,
+//! into `tests/golden/<name>.golden`. A file which can not be parsed is dumped as its error.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the dumps as the new goldens, then review their diff.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use shell_parser_common_rs::{
+    charset::Charset,
+    document::ShellFile,
+    dump::{dump_file, dump_project},
+    options::ParseOptions,
+    project::load_shell_dir,
+};
+
+fn fixtures(kind: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(kind);
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Compares `actual` with the golden of `name`, or writes it with `UPDATE_GOLDEN=1`.
+/// Returns a description of the first difference.
+fn check_golden(name: &str, actual: &str) -> Option<String> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.golden", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some_and(|v| v == "1") {
+        fs::write(&path, actual).unwrap();
+        return None;
+    }
+
+    let Ok(expected) = fs::read_to_string(&path) else {
+        return Some(format!("{}: no golden, run with UPDATE_GOLDEN=1", name));
+    };
+    if expected == actual {
+        return None;
+    }
+    let line = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(e, a)| e != a)
+        .unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+    Some(format!(
+        "{}:{}: expected {:?}, found {:?}",
+        name,
+        line + 1,
+        expected.lines().nth(line).unwrap_or_default(),
+        actual.lines().nth(line).unwrap_or_default()
+    ))
+}
+
+fn assert_goldens(dumps: impl Iterator<Item = (String, String)>) {
+    let mismatches: Vec<_> = dumps
+        .filter_map(|(name, dump)| check_golden(&name, &dump))
+        .collect();
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn success_when_files_match_goldens() {
+    assert_goldens(fixtures("files").iter().map(|path| {
+        let name = path.file_stem().unwrap().to_str().unwrap().to_string();
+        let bytes = fs::read(path).unwrap();
+        let dump = match ShellFile::parse_with(&bytes, Charset::ShiftJIS, &ParseOptions::STRICT) {
+            Ok(file) => dump_file(&file),
+            Err(e) => format!("error: {}\n", e),
+        };
+        (name, dump)
+    }));
+}
+
+#[test]
+fn success_when_shells_match_goldens() {
+    assert_goldens(fixtures("shells").iter().map(|path| {
+        let name = path.file_name().unwrap().to_str().unwrap().to_string();
+        let project = load_shell_dir(path, &ParseOptions::STRICT).unwrap();
+        (format!("shell_{}", name), dump_project(&project))
+    }));
+}
@@ -0,0 +1,146 @@
+//! JSON of parsed data, compared with the snapshot checked in `tests/golden`, and values read back from it.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the JSON as the new snapshot, then review its diff.
+
+use std::{fs, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::json;
+
+use shell_parser_common_rs::{
+    charset::Charset,
+    descript::BalloonAlignment,
+    diagnostic::{parse_with_diagnostics, Severity, WarningKind},
+    document::ShellFile,
+    error::Located,
+    kv::KeyValue,
+    options::ParseOptions,
+    scope::Scope,
+    seriko::{
+        AnimationOption, DrawMethod, IdGroup, IdGroupDelimiter, IdGroupSeparator, Interval,
+        Intervals, Wait,
+    },
+    surfaces::{build_surfaces, parse_blocks_with},
+    value::{Coordinate, IdList, Rect, Rgb},
+};
+
+fn shell_json(dir: &Path) -> serde_json::Value {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    let mut files = Vec::new();
+    let mut blocks = Vec::new();
+    let opened: Vec<_> = paths
+        .iter()
+        .map(|path| ShellFile::open(path, &ParseOptions::STRICT).unwrap())
+        .collect();
+    for (path, file) in paths.iter().zip(opened.iter()) {
+        files.push(json!({
+            "name": path.file_name().unwrap().to_str().unwrap(),
+            "file": file,
+            "diagnostics": parse_with_diagnostics(file.text()),
+        }));
+        // Blocks borrow the texts of `opened`, which outlive the loop.
+        if let Ok(parsed) = parse_blocks_with(file.text(), &ParseOptions::STRICT) {
+            blocks.extend(parsed.blocks);
+        }
+    }
+
+    let (surfaces, warnings) = build_surfaces(&blocks);
+    json!({
+        "files": files,
+        "surfaces": surfaces,
+        "build_warnings": warnings,
+    })
+}
+
+#[test]
+fn success_when_shell_matches_snapshot() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let actual =
+        serde_json::to_string_pretty(&shell_json(&root.join("tests/fixtures/shells/appends")))
+            .unwrap()
+            + "\n";
+    let path = root.join("tests/golden/shell_appends.json");
+    if std::env::var_os("UPDATE_GOLDEN").is_some_and(|v| v == "1") {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).expect("no snapshot, run with UPDATE_GOLDEN=1");
+    assert_eq!(actual, expected);
+}
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug>(
+    value: T,
+    expected: &str,
+) {
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(json, expected);
+    assert_eq!(serde_json::from_str::<T>(&json).unwrap(), value);
+}
+
+#[test]
+fn success_when_values_round_trip() {
+    round_trip(Coordinate::new(-1, 2), r#"{"x":-1,"y":2}"#);
+    round_trip(
+        Rect::new(0, 1, 2, 3),
+        r#"{"left":0,"top":1,"right":2,"bottom":3}"#,
+    );
+    round_trip(Rgb::new(255, 0, 10), r#"{"r":255,"g":0,"b":10}"#);
+    round_trip(IdList(vec![1, 3]), "[1,3]");
+    round_trip(
+        Located::new(4, Coordinate::new(0, 0)),
+        r#"{"line":4,"value":{"x":0,"y":0}}"#,
+    );
+    round_trip(DrawMethod::OverlayFast, r#""overlayfast""#);
+    round_trip(Wait::Range(50, 100), r#""50-100""#);
+    round_trip(Interval::Talk(3), r#""talk,3""#);
+    round_trip(
+        Intervals(vec![Interval::Sometimes, Interval::Bind]),
+        r#""sometimes+bind""#,
+    );
+    round_trip(
+        AnimationOption::Exclusive(Some(vec![1, 3])),
+        r#""exclusive,(1,3)""#,
+    );
+    round_trip(
+        IdGroup {
+            ids: vec![2],
+            delimiter: IdGroupDelimiter::Bracket,
+            separator: IdGroupSeparator::Dot,
+        },
+        r#""[2]""#,
+    );
+    round_trip(Charset::ShiftJIS, r#""Shift_JIS""#);
+    round_trip(Charset::Default, r#""default""#);
+    round_trip(Scope::Char(2), r#""char2""#);
+    round_trip(Severity::Info, r#""info""#);
+    round_trip(BalloonAlignment::Left, r#""left""#);
+    round_trip(
+        WarningKind::TrailingValueWhitespace,
+        r#""trailing_value_whitespace""#,
+    );
+}
+
+#[test]
+fn success_when_borrowed_entry_read() {
+    let json = r#"{"line":2,"value":{"key":"sakura.name","value":"Emily"}}"#;
+    let entry: Located<KeyValue<'_>> = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        entry,
+        Located::new(2, KeyValue::new("sakura.name", "Emily"))
+    );
+    assert_eq!(serde_json::to_string(&entry).unwrap(), json);
+}
+
+#[test]
+fn failed_when_not_in_file_syntax() {
+    assert!(serde_json::from_str::<DrawMethod>(r#""overlays""#).is_err());
+    assert!(serde_json::from_str::<Interval>(r#""talk""#).is_err());
+    assert!(serde_json::from_str::<Charset>(r#""UTF-16""#).is_err());
+    assert!(serde_json::from_str::<AnimationOption>(r#""background+exclusive""#).is_err());
+}
@@ -0,0 +1,42 @@
+//! Parses through the crate without `std`.
+//!
+//! Run with `cargo test --no-default-features --features alloc,block --test no_std`.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use shell_parser_common_rs::{
+    block::parse_blocks,
+    charset::{detect_charset, Charset},
+    kv::parse_key_value,
+    value::ShellValue,
+};
+
+const CASE: &str = "charset,UTF-8\r\nsurface0\r\n{\r\nelement0,base,a.png,0,0\r\n}\r\n";
+
+#[test]
+fn success_when_charset_detected() {
+    assert_eq!(detect_charset(CASE.as_bytes()), Some(Charset::UTF8));
+    assert_eq!(Charset::Default.decode(b"name,a").unwrap(), "name,a");
+}
+
+#[test]
+fn success_when_blocks_parsed() {
+    let blocks = parse_blocks(CASE).unwrap();
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].header, "surface0");
+
+    let (_, kv) = parse_key_value(blocks[0].body[0].text).unwrap();
+    assert_eq!(kv.key, "element0");
+    let values: Vec<_> = kv.value.split(',').collect();
+    assert_eq!(values, ["base", "a.png", "0", "0"]);
+}
+
+#[test]
+fn success_when_value_inferred() {
+    assert_eq!(ShellValue::infer("2.0"), ShellValue::Decimal(2.0));
+    assert_eq!(alloc::format!("{}", ShellValue::infer("2.0")), "2.0");
+}
@@ -0,0 +1,92 @@
+//! Downstream code of `#[non_exhaustive]` enums and options.
+//!
+//! Matchers here have wildcard arms, as downstream crates must, so variants added later keep them compiling.
+//! Words of newer shells are parsed into `Unknown` variants and fall into the wildcard arms.
+
+use shell_parser_common_rs::{
+    descript::SerikoSettings,
+    format::FormatStyle,
+    line::{classify, LineEnding, LineKind},
+    options::{Limits, ParseOptions},
+    seriko::{parse_animation_option, parse_intervals, AnimationOption, Interval, PatternMethod},
+    value::{NumericPolicy, Overflow},
+};
+
+fn describe_option(option: &AnimationOption) -> &'static str {
+    match option {
+        AnimationOption::Exclusive(_) => "exclusive",
+        AnimationOption::Background => "background",
+        _ => "other",
+    }
+}
+
+fn denominator(interval: &Interval) -> Option<u32> {
+    match interval {
+        Interval::Sometimes => Some(2),
+        Interval::Rarely => Some(4),
+        Interval::Random(n) => Some(*n),
+        _ => None,
+    }
+}
+
+fn is_structural(kind: LineKind) -> bool {
+    matches!(
+        kind,
+        LineKind::BlockHeader | LineKind::BraceOpen | LineKind::BraceClose
+    )
+}
+
+fn starts_others(method: PatternMethod) -> bool {
+    matches!(
+        method,
+        PatternMethod::Insert | PatternMethod::Start | PatternMethod::ParallelStart
+    )
+}
+
+#[test]
+fn success_when_future_variant_in_wildcard_arm() {
+    let (_, options) = parse_animation_option("exclusive+future-option+shared-index").unwrap();
+    assert_eq!(
+        options.iter().map(describe_option).collect::<Vec<_>>(),
+        vec!["exclusive", "other", "other"]
+    );
+    assert_eq!(
+        options[1],
+        AnimationOption::Unknown("future-option".to_string())
+    );
+
+    let (_, intervals) = parse_intervals("sometimes+talk,3+random,6").unwrap();
+    assert_eq!(
+        intervals.0.iter().map(denominator).collect::<Vec<_>>(),
+        vec![Some(2), None, Some(6)]
+    );
+
+    assert!(is_structural(classify("}")));
+    assert!(!is_structural(classify("} future")));
+    assert_eq!(classify("} future"), LineKind::Unknown);
+    assert!(starts_others(PatternMethod::Start));
+    assert!(!starts_others(PatternMethod::Stop));
+}
+
+#[test]
+fn success_when_options_built_by_constructors() {
+    let options = ParseOptions::compatible()
+        .case_insensitive_keys(false)
+        .numeric(NumericPolicy::DEFAULT.on_overflow(Overflow::Saturate))
+        .limits(Limits::NONE.max_entries(10));
+    assert!(!options.case_insensitive_keys);
+    assert_eq!(options.numeric.on_overflow, Overflow::Saturate);
+    assert_eq!(options.limits.max_entries, 10);
+    assert_eq!(options.limits.max_line_length, usize::MAX);
+
+    let style = FormatStyle::DEFAULT
+        .line_ending(LineEnding::Lf)
+        .sort_keys(true);
+    assert_eq!(style.line_ending, LineEnding::Lf);
+
+    assert!(
+        SerikoSettings::default()
+            .use_self_alpha(true)
+            .use_self_alpha
+    );
+}
@@ -0,0 +1,170 @@
+//! Round trips of value types through their textual forms.
+//!
+//! `parse(x.to_string()) == x` for generated values, and writing the value of
+//! generated text of valid syntax is stable after one pass.
+
+use nom::{combinator::all_consuming, IResult};
+use proptest::prelude::*;
+
+use shell_parser_common_rs::{
+    seriko::{parse_wait, Wait},
+    value::{
+        parse_coordinate, parse_id_list, parse_rect, parse_rgb, Coordinate, IdList, Rect, Rgb,
+        ShellValue,
+    },
+    ShellParseError,
+};
+
+fn parse<'a, T>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, T, ShellParseError<'a>>,
+    text: &'a str,
+) -> Result<T, TestCaseError> {
+    all_consuming(parser)(text)
+        .map(|(_, value)| value)
+        .map_err(|e| TestCaseError::fail(format!("{:?}: {:?}", text, e)))
+}
+
+fn any_i32() -> impl Strategy<Value = i32> {
+    prop_oneof![
+        Just(i32::MIN),
+        Just(i32::MAX),
+        Just(0),
+        Just(-1),
+        -1000..1000,
+        any::<i32>(),
+    ]
+}
+
+fn any_u32() -> impl Strategy<Value = u32> {
+    prop_oneof![Just(u32::MAX), Just(0), 0u32..1000, any::<u32>()]
+}
+
+fn any_coordinate() -> impl Strategy<Value = Coordinate> {
+    (any_i32(), any_i32()).prop_map(|(x, y)| Coordinate::new(x, y))
+}
+
+fn any_rect() -> impl Strategy<Value = Rect> {
+    (any_i32(), any_i32(), any_i32(), any_i32()).prop_map(|(l, t, r, b)| Rect::new(l, t, r, b))
+}
+
+fn any_rgb() -> impl Strategy<Value = Rgb> {
+    any::<(u8, u8, u8)>().prop_map(|(r, g, b)| Rgb::new(r, g, b))
+}
+
+fn any_id_list() -> impl Strategy<Value = IdList> {
+    prop::collection::vec(any_u32(), 1..8).prop_map(IdList)
+}
+
+fn any_wait() -> impl Strategy<Value = Wait> {
+    prop_oneof![
+        any_u32().prop_map(Wait::Fixed),
+        any_u32().prop_map(|w| Wait::Range(w, w)),
+        (any_u32(), any_u32()).prop_map(|(min, max)| Wait::Range(min, max)),
+    ]
+}
+
+fn any_shell_value() -> impl Strategy<Value = ShellValue> {
+    prop_oneof![
+        any::<i64>().prop_map(ShellValue::Int),
+        any::<bool>().prop_map(ShellValue::Bool),
+        any_rgb().prop_map(ShellValue::Color),
+        any_coordinate().prop_map(ShellValue::Coordinate),
+        any_rect().prop_map(ShellValue::Rect),
+        (any::<i32>(), 0i32..64)
+            .prop_map(|(n, fraction)| ShellValue::Decimal(n as f64 + fraction as f64 / 64.0)),
+    ]
+}
+
+/// Integer as written by hand, with leading zeros or as `-0`.
+fn integer_text() -> impl Strategy<Value = String> {
+    ("-?", "0{0,2}", 0u32..1000).prop_map(|(sign, zeros, n)| format!("{}{}{}", sign, zeros, n))
+}
+
+/// Text of valid syntax for one of the variants of `ShellValue`.
+fn value_text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        integer_text(),
+        (integer_text(), "[0-9]{1,3}").prop_map(|(i, f)| format!("{}.{}", i, f)),
+        Just("true".to_string()),
+        Just("false".to_string()),
+        prop::collection::vec(integer_text(), 2).prop_map(|v| v.join(",")),
+        prop::collection::vec("0{0,2}[0-9]{1,3}", 3).prop_map(|v| v.join(",")),
+        prop::collection::vec(integer_text(), 4).prop_map(|v| v.join(",")),
+        prop::collection::vec("0{0,2}[0-9]{1,9}", 5..9).prop_map(|v| v.join(",")),
+        ("0{0,2}[0-9]{1,3}", 0u32..1000).prop_map(|(min, max)| format!("{}-{}", min, max)),
+        "[a-z0-9 .]{0,12}",
+    ]
+}
+
+proptest! {
+    #[test]
+    fn round_trip_when_coordinate(value in any_coordinate()) {
+        prop_assert_eq!(parse(parse_coordinate, &value.to_string())?, value);
+    }
+
+    #[test]
+    fn round_trip_when_rect(value in any_rect()) {
+        prop_assert_eq!(parse(parse_rect, &value.to_string())?, value);
+    }
+
+    #[test]
+    fn round_trip_when_rgb(value in any_rgb()) {
+        prop_assert_eq!(parse(parse_rgb, &value.to_string())?, value);
+    }
+
+    #[test]
+    fn round_trip_when_id_list(value in any_id_list()) {
+        prop_assert_eq!(parse(parse_id_list, &value.to_string())?, value);
+    }
+
+    #[test]
+    fn round_trip_when_wait(value in any_wait()) {
+        prop_assert_eq!(parse(parse_wait, &value.to_string())?, value);
+    }
+
+    #[test]
+    fn round_trip_when_shell_value(value in any_shell_value()) {
+        prop_assert_eq!(ShellValue::infer(&value.to_string()), value);
+    }
+
+    #[test]
+    fn idempotent_when_written_once(text in value_text()) {
+        let value = ShellValue::infer(&text);
+        let written = value.to_string();
+
+        prop_assert_eq!(ShellValue::infer(&written), value);
+        prop_assert_eq!(ShellValue::infer(&written).to_string(), written);
+    }
+}
+
+#[test]
+fn success_when_edge_values() {
+    let coordinate = Coordinate::new(i32::MIN, i32::MIN);
+    assert_eq!(
+        parse(parse_coordinate, &coordinate.to_string()).unwrap(),
+        coordinate
+    );
+    assert_eq!(Wait::Range(50, 50).to_string(), "50-50");
+    assert_eq!(parse(parse_wait, "50-50").unwrap(), Wait::Range(50, 50));
+}
+
+#[test]
+fn normalized_when_written() {
+    for (text, written) in [
+        ("-0", "0"),
+        ("007", "7"),
+        ("1.50", "1.5"),
+        ("-0.0", "0.0"),
+        ("2.000", "2.0"),
+        ("00,-0", "0,0"),
+    ] {
+        assert_eq!(ShellValue::infer(text).to_string(), written, "{:?}", text);
+    }
+}
+
+#[test]
+fn failed_when_empty_id_list() {
+    // An empty list is written as the empty string, which is not a list.
+    assert_eq!(IdList::default().to_string(), "");
+    assert!(all_consuming(parse_id_list)("").is_err());
+}
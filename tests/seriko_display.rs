@@ -0,0 +1,110 @@
+//! Display of SERIKO values read back by their parsers.
+//!
+//! Every interval, pattern and option line of the fixture is written by `Display` and parsed again.
+//! Lines written differently from the input are only the legacy or abbreviated forms, which normalize.
+
+use nom::{combinator::all_consuming, IResult};
+
+use shell_parser_common_rs::{
+    block::parse_blocks,
+    kv::parse_key_value,
+    seriko::{parse_animation_option, parse_intervals, parse_pattern},
+    ShellParseError,
+};
+
+const FIXTURE: &str = "surface0\r
+{\r
+animation0.interval,sometimes\r
+animation0.pattern0,base,0,100,0,0\r
+animation0.pattern1,overlay,10,50-100,5,-5,128\r
+animation0.pattern2,overlayfast,11,0,0,0\r
+animation0.pattern3,overlaymultiply,12,20,1,1,64\r
+animation0.pattern4,replace,13,20,0,0\r
+animation0.pattern5,interpolate,14,20,0,0,255\r
+animation0.pattern6,asis,15,20,0,0\r
+animation0.pattern7,move,-1,20,10,10\r
+animation0.pattern8,bind,16,20,0,0\r
+animation0.pattern9,add,17,20,0,0,32\r
+animation0.pattern10,reduce,18,20,0,0\r
+animation0.pattern11,overlay,-1,0\r
+animation1.interval,rarely\r
+animation1.pattern0,insert,3\r
+animation1.pattern1,start,4\r
+animation1.pattern2,stop,5\r
+animation1.pattern3,alternativestart,(6,7)\r
+animation1.pattern4,alternativestop,(6)\r
+animation1.pattern5,parallelstart,(8,9,10)\r
+animation1.pattern6,parallelstop,[8.9]\r
+animation1.option,exclusive\r
+animation2.interval,random,6\r
+animation2.option,exclusive,(1,3)+background\r
+animation3.interval,periodic,5\r
+animation3.option,background,shared-index\r
+animation4.interval,always\r
+animation4.option,future-option+bind\r
+animation5.interval,runonce\r
+animation6.interval,never\r
+animation7.interval,yen-e\r
+animation8.interval,talk,2\r
+animation9.interval,bind+sometimes\r
+animation10.interval,bind+runonce+talk,0\r
+animation11.interval,sometimes+yen-e\r
+}\r
+";
+
+/// Returns `value` written by `display` after checking it parses the same as `value`.
+fn round_trip<T, D>(
+    parser: impl for<'a> Fn(&'a str) -> IResult<&'a str, T, ShellParseError<'a>>,
+    display: impl Fn(&T) -> D,
+    value: &str,
+) -> String
+where
+    T: PartialEq + std::fmt::Debug,
+    D: ToString,
+{
+    let (_, parsed) = all_consuming(&parser)(value).unwrap();
+    let written = display(&parsed).to_string();
+    let (_, reparsed) = all_consuming(&parser)(written.as_str()).unwrap();
+    assert_eq!(reparsed, parsed, "{}", value);
+    written
+}
+
+#[test]
+fn success_when_every_line_re_parsed() {
+    let blocks = parse_blocks(FIXTURE).unwrap();
+    let mut lines = 0;
+    let mut normalized = Vec::new();
+    for line in blocks[0].body.iter() {
+        let (_, kv) = all_consuming(parse_key_value)(line.text).unwrap();
+        let written = if kv.key.ends_with(".interval") {
+            round_trip(parse_intervals, |i| i.to_string(), kv.value)
+        } else if kv.key.ends_with(".option") {
+            round_trip(
+                parse_animation_option,
+                |options| {
+                    options
+                        .iter()
+                        .map(|o| o.to_string())
+                        .collect::<Vec<_>>()
+                        .join("+")
+                },
+                kv.value,
+            )
+        } else {
+            round_trip(parse_pattern, |p| p.to_string(), kv.value)
+        };
+        lines += 1;
+        if written != kv.value {
+            normalized.push((line.number, written));
+        }
+    }
+
+    assert_eq!(lines, 35);
+    assert_eq!(
+        normalized,
+        vec![
+            (15, "overlay,-1,0,0,0".to_string()),
+            (28, "background+shared-index".to_string()),
+        ]
+    );
+}
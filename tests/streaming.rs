@@ -0,0 +1,114 @@
+//! Entries streamed by `for_each_entry`, with the peak of memory allocated while streaming,
+//! compared with those of `parse_bytes_with`.
+//!
+//! Allocated bytes are counted by `common::CountingAllocator`.
+
+use std::{fs, ops::ControlFlow, path::Path};
+
+use shell_parser_common_rs::{
+    charset::Charset,
+    document::{for_each_entry, parse_bytes_with, BLOCK_CLOSE, BLOCK_OPEN},
+    options::ParseOptions,
+};
+
+mod common;
+
+use common::{counted, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the result of `f` and the peak of bytes allocated in it over those allocated before.
+fn peak_allocated<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let (result, counts) = counted(f);
+    (result, counts.peak)
+}
+
+/// Bytes allocated at most while streaming, far less than the files streamed.
+const BOUND: usize = 16 * 1024;
+
+fn huge_file() -> Vec<u8> {
+    let mut text = String::from("charset,UTF-8\r\ndescript\r\n{\r\nversion,1\r\n}\r\n");
+    let mut i = 0;
+    while text.len() < 8 << 20 {
+        text.push_str(&format!(
+            "surface{}\r\n{{\r\nelement0,base,surface{}.png,0,0\r\n}}\r\n",
+            i, i
+        ));
+        i += 1;
+    }
+    text.into_bytes()
+}
+
+#[test]
+fn stopped_when_charset_entry_taken() {
+    let case = huge_file();
+    let (keys, peak) = peak_allocated(|| {
+        let mut keys = Vec::new();
+        for_each_entry(&case, Charset::ShiftJIS, &ParseOptions::STRICT, |entry| {
+            keys.push(entry.value.key.len());
+            ControlFlow::Break(())
+        })
+        .unwrap();
+        keys
+    });
+    assert_eq!(keys, vec!["charset".len()]);
+    assert!(peak < BOUND, "{} bytes allocated", peak);
+}
+
+#[test]
+fn success_when_huge_file_streamed_in_bounded_memory() {
+    let case = huge_file();
+    let (count, peak) = peak_allocated(|| {
+        let mut count = 0;
+        for_each_entry(&case, Charset::ShiftJIS, &ParseOptions::STRICT, |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+        count
+    });
+    assert!(count > 100_000);
+    assert!(peak < BOUND, "{} bytes allocated", peak);
+}
+
+#[test]
+fn success_when_same_as_parse_bytes() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/files");
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+
+    for path in paths.iter() {
+        let bytes = fs::read(path).unwrap();
+        let mut entries = Vec::new();
+        let mut headers = Vec::new();
+        let streamed = for_each_entry(&bytes, Charset::ShiftJIS, &ParseOptions::STRICT, |e| {
+            match e.value.key {
+                BLOCK_OPEN => headers.push((e.line, e.value.value.to_string())),
+                BLOCK_CLOSE => {}
+                _ => entries.push((e.line, e.value.key.to_string(), e.value.value.to_string())),
+            }
+            ControlFlow::Continue(())
+        });
+
+        let Ok(document) = parse_bytes_with(&bytes, Charset::ShiftJIS, &ParseOptions::STRICT)
+        else {
+            assert!(streamed.is_err(), "{}", path.display());
+            continue;
+        };
+        assert_eq!(streamed, Ok(()), "{}", path.display());
+        let expected: Vec<_> = document
+            .entries()
+            .map(|e| (e.line, e.value.key.to_string(), e.value.value.to_string()))
+            .collect();
+        assert_eq!(entries, expected, "{}", path.display());
+        let expected: Vec<_> = document
+            .block_headers()
+            .map(|h| (h.line, h.value.to_string()))
+            .collect();
+        assert_eq!(headers, expected, "{}", path.display());
+    }
+}